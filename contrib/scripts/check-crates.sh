@@ -12,6 +12,7 @@ buildargs=(
     "-p nostr --features all-nips --target wasm32-unknown-unknown"
     "-p nostr --features all-nips,rand --target wasm32-unknown-unknown"
     "-p nostr --features all-nips,os-rng --target wasm32-unknown-unknown"
+    "-p nostr --no-default-features --features std"
     "-p nostr --no-default-features --features alloc"
     "-p nostr --no-default-features --features alloc,rand"
     "-p nostr --no-default-features --features alloc,all-nips"
@@ -34,6 +35,9 @@ buildargs=(
     "-p nostr-keyring"
     "-p nostr-keyring --features async"
     "-p nostr-sdk"
+    "-p nostr-sdk --features nip59"
+    "-p nostr-sdk --no-default-features"
+    "-p nostr-sdk --no-default-features --target wasm32-unknown-unknown"
     "-p nostr-sdk --target wasm32-unknown-unknown"
     "-p nostr-relay-builder"
     "-p nostr-connect"
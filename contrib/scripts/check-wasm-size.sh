@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+
+# Build the minimal wasm profile (contrib/wasm-size) and check that its size didn't grow
+# more than MARGIN_PERCENT over the same profile built at the base revision.
+#
+# The threshold is the measured size of the base build plus the margin: no size is hardcoded.
+#
+# Usage: check-wasm-size.sh [base revision] (default: merge base with origin/master)
+
+set -euo pipefail
+
+# Allowed growth over the base build
+MARGIN_PERCENT=5
+
+dir="contrib/wasm-size"
+target="wasm32-unknown-unknown"
+base_rev="${1:-$(git merge-base HEAD origin/master)}"
+
+# Install target
+rustup target list --installed | grep -q "$target" || rustup target add "$target"
+
+# Build the profile of a source tree and print the size of the wasm module
+measure() {
+    local root="$1"
+    RUSTFLAGS='--cfg getrandom_backend="unsupported"' cargo build --quiet --release --target "$target" --manifest-path "$root/$dir/Cargo.toml" >&2
+    wc -c < "$root/$dir/target/$target/release/nostr_wasm_size.wasm"
+}
+
+# Check out the base revision
+base_tree=$(mktemp -d)
+trap 'git worktree remove --force "$base_tree"' EXIT
+git worktree add --detach "$base_tree" "$base_rev" >&2
+
+if [[ ! -d "$base_tree/$dir" ]]; then
+    echo "The base revision $base_rev has no minimal wasm profile: nothing to compare."
+    exit 0
+fi
+
+base_size=$(measure "$base_tree")
+size=$(measure ".")
+threshold=$((base_size + base_size * MARGIN_PERCENT / 100))
+
+echo "Minimal wasm profile size: $size bytes (base $base_rev: $base_size bytes, threshold: $threshold bytes, margin: $MARGIN_PERCENT%)"
+
+if [[ "$size" -gt "$threshold" ]]; then
+    echo "The minimal wasm profile grew more than $MARGIN_PERCENT%: some optional component may have been coupled again."
+    exit 1
+fi
@@ -0,0 +1,60 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Minimal wasm profile of the SDK
+//!
+//! Client, WASM websocket transport, memory database and basic event kinds, with all the optional features disabled.
+//!
+//! The exported function is never executed: it only keeps these components in the binary,
+//! so that `contrib/scripts/check-wasm-size.sh` can track the size of the bundle.
+
+use std::error::Error;
+use std::time::Duration;
+
+use nostr_memory::MemoryDatabase;
+use nostr_sdk::prelude::*;
+
+async fn run() -> Result<(), Box<dyn Error>> {
+    let keys = Keys::generate();
+    let database = MemoryDatabase::unbounded();
+    let client: Client = Client::builder().database(database).build();
+
+    client.add_relay("wss://relay.damus.io").await?;
+    client.connect().await;
+
+    let metadata = Metadata::new().name("wasm-size");
+    let event = EventBuilder::metadata(&metadata).sign(&keys)?;
+    client.send_event(&event).await?;
+
+    let event = EventBuilder::text_note("Hello from wasm").sign(&keys)?;
+    client.send_event(&event).await?;
+
+    let filter = Filter::new().author(keys.public_key()).kinds([
+        Kind::Metadata,
+        Kind::TextNote,
+        Kind::ContactList,
+    ]);
+    client.subscribe(filter.clone()).await?;
+
+    let events = client
+        .fetch_events(filter)
+        .timeout(Duration::from_secs(10))
+        .await?;
+    for event in events {
+        event.verify()?;
+    }
+
+    client.shutdown().await;
+
+    Ok(())
+}
+
+/// Entry point
+#[unsafe(no_mangle)]
+pub extern "C" fn nostr_wasm_size() -> i32 {
+    match futures::executor::block_on(run()) {
+        Ok(()) => 0,
+        Err(..) => 1,
+    }
+}
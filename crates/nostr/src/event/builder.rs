@@ -11,6 +11,7 @@ use core::ops::Range;
 
 use serde_json::{Value, json};
 
+use crate::nips::nip56::{Report, ReportTarget};
 use crate::nips::nip58::Nip58Tag;
 use crate::nips::nip62::VanishTarget;
 use crate::prelude::*;
@@ -801,6 +802,43 @@ impl EventBuilder {
         Self::new(Kind::Reporting, content).tags(tags)
     }
 
+    /// Report a user, a note or a blob
+    ///
+    /// Builds the tags of the [`ReportTarget`], with the [`Report`] type.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/56.md>
+    #[inline]
+    pub fn report_target<S>(target: &ReportTarget, report: Report, comment: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::report(target.to_tags(report), comment)
+    }
+
+    /// Handler information
+    ///
+    /// The metadata, if any, is serialized in the content.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
+    pub fn handler_information(info: &HandlerInformation) -> Self {
+        let content: String = info
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.as_json())
+            .unwrap_or_default();
+        Self::new(Kind::HandlerInformation, content).tags(info.to_tags())
+    }
+
+    /// Handler recommendation
+    ///
+    /// Replaces any previous recommendation of the same kind.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
+    #[inline]
+    pub fn handler_recommendation(recommendation: &HandlerRecommendation) -> Self {
+        Self::new(Kind::HandlerRecommendation, "").tags(recommendation.to_tags())
+    }
+
     /// Create **public** zap request event
     ///
     /// **This event MUST NOT be broadcasted to relays**, instead must be sent to a recipient's LNURL pay callback url.
@@ -1772,6 +1810,7 @@ impl EventBuilder {
     }
 }
 
+#[cfg(feature = "parser")]
 fn has_nostr_event_uri(content: &str, event_id: &EventId) -> bool {
     const OPTS: NostrParserOptions = NostrParserOptions::disable_all().nostr_uris(true);
 
@@ -1788,6 +1827,25 @@ fn has_nostr_event_uri(content: &str, event_id: &EventId) -> bool {
     false
 }
 
+/// Lightweight version of the above, without the content tokenizer
+#[cfg(not(feature = "parser"))]
+fn has_nostr_event_uri(content: &str, event_id: &EventId) -> bool {
+    const SCHEME: &str = "nostr:";
+
+    content.match_indices(SCHEME).any(|(start, ..)| {
+        let rest: &str = &content[start + SCHEME.len()..];
+        let end: usize = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let uri: &str = &content[start..start + SCHEME.len() + end];
+
+        match Nip21::parse(uri) {
+            Ok(nip21) => nip21.event_id().as_ref() == Some(event_id),
+            Err(..) => false,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(all(feature = "std", feature = "os-rng"))]
@@ -178,6 +178,8 @@ kind_variants! {
     WebBookmark => 39701, "Web Bookmark", "<https://github.com/nostr-protocol/nips/blob/master/B0.md>",
     RelayMonitor => 10166, "Relay Monitor", "<https://github.com/nostr-protocol/nips/blob/master/66.md>",
     RelayDiscovery => 30166, "Relay Discovery", "<https://github.com/nostr-protocol/nips/blob/master/66.md>",
+    HandlerRecommendation => 31989, "Handler Recommendation", "<https://github.com/nostr-protocol/nips/blob/master/89.md>",
+    HandlerInformation => 31990, "Handler Information", "<https://github.com/nostr-protocol/nips/blob/master/89.md>",
 }
 
 impl PartialEq for Kind {
@@ -22,6 +22,7 @@ mod error;
 pub mod id;
 pub mod kind;
 pub mod tag;
+pub mod template;
 pub mod unsigned;
 
 pub use self::builder::EventBuilder;
@@ -29,6 +30,7 @@ pub use self::error::Error;
 pub use self::id::EventId;
 pub use self::kind::Kind;
 pub use self::tag::{Tag, Tags};
+pub use self::template::EventTemplate;
 pub use self::unsigned::UnsignedEvent;
 #[cfg(feature = "std")]
 use crate::SECP256K1;
@@ -232,11 +234,13 @@ impl Event {
     /// Returns `true` if the event has an expiration tag that is expired.
     /// If an event has no expiration tag, then it will return `false`.
     ///
+    /// The event is considered expired starting from the expiration second.
+    ///
     /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
     #[inline]
     pub fn is_expired_at(&self, now: Timestamp) -> bool {
         if let Some(timestamp) = self.tags.expiration() {
-            return timestamp < now;
+            return timestamp <= now;
         }
         false
     }
@@ -431,6 +435,22 @@ mod tests {
         assert!(!&event.is_expired());
     }
 
+    #[test]
+    #[cfg(all(feature = "std", feature = "os-rng"))]
+    fn test_event_expired_at_expiration_second() {
+        let expiration = Timestamp::from(1600000000);
+
+        let my_keys = Keys::generate();
+        let event = EventBuilder::text_note("my content")
+            .tags([Tag::expiration(expiration)])
+            .sign(&my_keys)
+            .unwrap();
+
+        assert!(!event.is_expired_at(expiration - 1));
+        assert!(event.is_expired_at(expiration));
+        assert!(event.is_expired_at(expiration + 1));
+    }
+
     #[test]
     fn test_verify_event_id() {
         let event = Event::from_json(r#"{"content":"","created_at":1698412975,"id":"f55c30722f056e330d8a7a6a9ba1522f7522c0f1ced1c93d78ea833c78a3d6ec","kind":3,"pubkey":"f831caf722214748c72db4829986bd0cbb2bb8b3aeade1c959624a52a9629046","sig":"5092a9ffaecdae7d7794706f085ff5852befdf79df424cc3419bb797bf515ae05d4f19404cb8324b8b4380a4bd497763ac7b0f3b1b63ef4d3baa17e5f5901808","tags":[["p","4ddeb9109a8cd29ba279a637f5ec344f2479ee07df1f4043f3fe26d8948cfef9","",""],["p","bb6fd06e156929649a73e6b278af5e648214a69d88943702f1fb627c02179b95","",""],["p","b8b8210f33888fdbf5cedee9edf13c3e9638612698fe6408aff8609059053420","",""],["p","9dcee4fabcd690dc1da9abdba94afebf82e1e7614f4ea92d61d52ef9cd74e083","",""],["p","3eea9e831fefdaa8df35187a204d82edb589a36b170955ac5ca6b88340befaa0","",""],["p","885238ab4568f271b572bf48b9d6f99fa07644731f288259bd395998ee24754e","",""],["p","568a25c71fba591e39bebe309794d5c15d27dbfa7114cacb9f3586ea1314d126","",""]]}"#).unwrap();
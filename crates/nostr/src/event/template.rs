@@ -0,0 +1,739 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Event template
+//!
+//! Pre-compiled event skeleton for publishers that build many structurally identical events.
+//!
+//! The constant parts of the template are validated and serialized once, at compile time:
+//! instantiating it only fills the placeholders and streams the canonical serialization
+//! directly into the hasher, so the event ID is computed without re-serializing the event.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use hashes::sha256::{Hash as Sha256Hash, HashEngine};
+use hashes::{Hash, HashEngine as _};
+
+use crate::{EventId, Kind, PublicKey, Tag, Tags, Timestamp, UnsignedEvent};
+
+const PLACEHOLDER_OPEN: &str = "{{";
+const PLACEHOLDER_CLOSE: &str = "}}";
+
+/// Placeholder position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceholderPosition {
+    /// Tag value
+    Tag {
+        /// Tag index
+        index: usize,
+        /// Value index inside the tag
+        value: usize,
+    },
+    /// Event content
+    Content,
+}
+
+impl fmt::Display for PlaceholderPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag { index, value } => write!(f, "tag {index}, value {value}"),
+            Self::Content => f.write_str("content"),
+        }
+    }
+}
+
+/// Event template error
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Empty tag
+    EmptyTag {
+        /// Tag index
+        index: usize,
+    },
+    /// Placeholder in a tag name
+    ///
+    /// Only the tag values and the content can contain placeholders.
+    PlaceholderInTagName {
+        /// Tag index
+        index: usize,
+    },
+    /// Placeholder opened but never closed
+    UnclosedPlaceholder(PlaceholderPosition),
+    /// Invalid placeholder name
+    ///
+    /// The name must be non-empty and contain only ASCII alphanumeric chars, `_` or `-`.
+    InvalidPlaceholderName {
+        /// Position
+        position: PlaceholderPosition,
+        /// Name
+        name: String,
+    },
+    /// No value provided for a placeholder
+    MissingValue(String),
+    /// Value provided for an unknown placeholder
+    UnknownPlaceholder(String),
+}
+
+impl core::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyTag { index } => write!(f, "Empty tag at index {index}"),
+            Self::PlaceholderInTagName { index } => {
+                write!(f, "Placeholder in the name of the tag at index {index}")
+            }
+            Self::UnclosedPlaceholder(position) => {
+                write!(f, "Unclosed placeholder: {position}")
+            }
+            Self::InvalidPlaceholderName { position, name } => {
+                write!(f, "Invalid placeholder name '{name}': {position}")
+            }
+            Self::MissingValue(name) => write!(f, "Missing value for placeholder '{name}'"),
+            Self::UnknownPlaceholder(name) => write!(f, "Unknown placeholder '{name}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    /// Constant text
+    Literal(String),
+    /// Placeholder index
+    Slot(usize),
+}
+
+/// A string field: a tag value or the content
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Constant(String),
+    Pieces(Vec<Piece>),
+}
+
+impl Field {
+    fn instantiate(&self, values: &[&str]) -> String {
+        match self {
+            Self::Constant(value) => value.clone(),
+            Self::Pieces(pieces) => {
+                let len: usize = pieces
+                    .iter()
+                    .map(|piece| match piece {
+                        Piece::Literal(literal) => literal.len(),
+                        Piece::Slot(slot) => values[*slot].len(),
+                    })
+                    .sum();
+
+                let mut field: String = String::with_capacity(len);
+                for piece in pieces.iter() {
+                    match piece {
+                        Piece::Literal(literal) => field.push_str(literal),
+                        Piece::Slot(slot) => field.push_str(values[*slot]),
+                    }
+                }
+                field
+            }
+        }
+    }
+}
+
+/// Pre-serialized JSON chunk of the tags and content
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum JsonPiece {
+    /// Already escaped JSON
+    Raw(String),
+    /// Placeholder index, to escape at instantiation
+    Slot(usize),
+}
+
+/// Event template
+///
+/// Compile once and instantiate many times.
+///
+/// Placeholders have the `{{name}}` format and are allowed only inside the tag values and the content,
+/// where the substitution can't change the JSON structure of the event.
+/// The same placeholder can be used multiple times.
+///
+/// Differently from [`EventBuilder::build`](crate::EventBuilder::build),
+/// the instantiation never discards or deduplicates tags.
+///
+/// # Example
+/// ```rust
+/// use nostr::prelude::*;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let template = EventTemplate::compile(
+///     Kind::TextNote,
+///     [vec!["t", "{{topic}}"], vec!["r", "https://example.com/{{path}}"]],
+///     "New post about {{topic}}",
+/// )?;
+///
+/// # let public_key = PublicKey::from_hex("79dff8f82963424e0bb02708a22e44b4980893e3a4be0fa3cb60a43b946764e3")?;
+/// let unsigned = template.instantiate(public_key, &[("topic", "nostr"), ("path", "nostr")])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventTemplate {
+    kind: Kind,
+    /// Placeholder names
+    slots: Vec<String>,
+    tags: Vec<Vec<Field>>,
+    content: Field,
+    /// Serialization of `,<kind>,<tags>,<content>]`
+    json: Vec<JsonPiece>,
+}
+
+impl EventTemplate {
+    /// Compile a new event template
+    ///
+    /// The `tags` are the tag skeleton: each tag value (except the tag name) may contain placeholders.
+    pub fn compile<I, T, S, C>(kind: Kind, tags: I, content: C) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        C: AsRef<str>,
+    {
+        let mut slots: Vec<String> = Vec::new();
+        let mut compiled_tags: Vec<Vec<Field>> = Vec::new();
+        let mut json: JsonWriter = JsonWriter::default();
+
+        json.raw(",");
+        json.raw(&kind.as_u16().to_string());
+        json.raw(",[");
+
+        for (index, tag) in tags.into_iter().enumerate() {
+            if index > 0 {
+                json.raw(",");
+            }
+
+            json.raw("[");
+
+            let mut fields: Vec<Field> = Vec::new();
+
+            for (value, field) in tag.into_iter().enumerate() {
+                let field: &str = field.as_ref();
+
+                // The tag name can't contain placeholders
+                if value == 0 && field.contains(PLACEHOLDER_OPEN) {
+                    return Err(Error::PlaceholderInTagName { index });
+                }
+
+                if value > 0 {
+                    json.raw(",");
+                }
+
+                let position = PlaceholderPosition::Tag { index, value };
+                let field: Field = parse_field(field, position, &mut slots)?;
+                json.field(&field);
+                fields.push(field);
+            }
+
+            if fields.is_empty() {
+                return Err(Error::EmptyTag { index });
+            }
+
+            json.raw("]");
+
+            compiled_tags.push(fields);
+        }
+
+        json.raw("],");
+
+        let content: Field =
+            parse_field(content.as_ref(), PlaceholderPosition::Content, &mut slots)?;
+        json.field(&content);
+
+        json.raw("]");
+
+        Ok(Self {
+            kind,
+            slots,
+            tags: compiled_tags,
+            content,
+            json: json.finish(),
+        })
+    }
+
+    /// Get the event kind
+    #[inline]
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Get the placeholder names, in order of first appearance
+    #[inline]
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.slots.iter().map(|s| s.as_str())
+    }
+
+    /// Instantiate the template with the current timestamp
+    ///
+    /// Check [`EventTemplate::instantiate_with_timestamp`] to learn more.
+    #[inline]
+    pub fn instantiate(
+        &self,
+        public_key: PublicKey,
+        values: &[(&str, &str)],
+    ) -> Result<UnsignedEvent, Error> {
+        self.instantiate_with_timestamp(public_key, Timestamp::now(), values)
+    }
+
+    /// Instantiate the template
+    ///
+    /// Every placeholder must have a value. The returned [`UnsignedEvent`] has the ID already set.
+    pub fn instantiate_with_timestamp(
+        &self,
+        public_key: PublicKey,
+        created_at: Timestamp,
+        values: &[(&str, &str)],
+    ) -> Result<UnsignedEvent, Error> {
+        let values: Vec<&str> = self.resolve(values)?;
+
+        // Build tags
+        let tags: Vec<Tag> = self
+            .tags
+            .iter()
+            .map(|fields| Tag::new(fields.iter().map(|f| f.instantiate(&values)).collect()))
+            .collect();
+
+        // Build content
+        let content: String = self.content.instantiate(&values);
+
+        // Compute the ID, streaming the canonical serialization into the hasher
+        let mut hasher = HashWriter(Sha256Hash::engine());
+        hasher.0.input(b"[0,\"");
+        hasher.0.input(&public_key.to_hex_byte_array());
+        hasher.0.input(b"\",");
+        // Can't fail: the hash engine never returns an error
+        let _ = write!(hasher, "{}", created_at.as_secs());
+        for piece in self.json.iter() {
+            match piece {
+                JsonPiece::Raw(raw) => hasher.0.input(raw.as_bytes()),
+                JsonPiece::Slot(slot) => {
+                    let _ = write_escaped(&mut hasher, values[*slot]);
+                }
+            }
+        }
+        let hash: Sha256Hash = Sha256Hash::from_engine(hasher.0);
+
+        Ok(UnsignedEvent {
+            id: Some(EventId::from_byte_array(hash.to_byte_array())),
+            pubkey: public_key,
+            created_at,
+            kind: self.kind,
+            tags: Tags::from_list(tags),
+            content,
+        })
+    }
+
+    /// Map the provided values to the placeholder indexes
+    fn resolve<'a>(&self, values: &[(&str, &'a str)]) -> Result<Vec<&'a str>, Error> {
+        let mut resolved: Vec<Option<&'a str>> = vec![None; self.slots.len()];
+
+        for (name, value) in values.iter() {
+            match self.slots.iter().position(|slot| slot == name) {
+                Some(index) => resolved[index] = Some(value),
+                None => return Err(Error::UnknownPlaceholder(name.to_string())),
+            }
+        }
+
+        resolved
+            .into_iter()
+            .zip(self.slots.iter())
+            .map(|(value, name)| value.ok_or_else(|| Error::MissingValue(name.clone())))
+            .collect()
+    }
+}
+
+fn is_valid_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// Split a string into literals and placeholders
+fn parse_field(
+    field: &str,
+    position: PlaceholderPosition,
+    slots: &mut Vec<String>,
+) -> Result<Field, Error> {
+    if !field.contains(PLACEHOLDER_OPEN) {
+        return Ok(Field::Constant(field.to_string()));
+    }
+
+    let mut pieces: Vec<Piece> = Vec::new();
+    let mut rest: &str = field;
+
+    while let Some(start) = rest.find(PLACEHOLDER_OPEN) {
+        if start > 0 {
+            pieces.push(Piece::Literal(rest[..start].to_string()));
+        }
+
+        let after_open: &str = &rest[start + PLACEHOLDER_OPEN.len()..];
+        let end: usize = after_open
+            .find(PLACEHOLDER_CLOSE)
+            .ok_or(Error::UnclosedPlaceholder(position))?;
+        let name: &str = &after_open[..end];
+
+        if !is_valid_placeholder_name(name) {
+            return Err(Error::InvalidPlaceholderName {
+                position,
+                name: name.to_string(),
+            });
+        }
+
+        let slot: usize = match slots.iter().position(|slot| slot == name) {
+            Some(slot) => slot,
+            None => {
+                slots.push(name.to_string());
+                slots.len() - 1
+            }
+        };
+        pieces.push(Piece::Slot(slot));
+
+        rest = &after_open[end + PLACEHOLDER_CLOSE.len()..];
+    }
+
+    if !rest.is_empty() {
+        pieces.push(Piece::Literal(rest.to_string()));
+    }
+
+    Ok(Field::Pieces(pieces))
+}
+
+/// Build the pre-serialized JSON, merging the adjacent raw chunks
+#[derive(Default)]
+struct JsonWriter {
+    pieces: Vec<JsonPiece>,
+    raw: String,
+}
+
+impl JsonWriter {
+    fn raw(&mut self, raw: &str) {
+        self.raw.push_str(raw);
+    }
+
+    fn field(&mut self, field: &Field) {
+        self.raw.push('"');
+        match field {
+            Field::Constant(value) => {
+                // Can't fail: writing to a string never returns an error
+                let _ = write_escaped(&mut self.raw, value);
+            }
+            Field::Pieces(pieces) => {
+                for piece in pieces.iter() {
+                    match piece {
+                        Piece::Literal(literal) => {
+                            let _ = write_escaped(&mut self.raw, literal);
+                        }
+                        Piece::Slot(slot) => {
+                            self.flush();
+                            self.pieces.push(JsonPiece::Slot(*slot));
+                        }
+                    }
+                }
+            }
+        }
+        self.raw.push('"');
+    }
+
+    fn flush(&mut self) {
+        if !self.raw.is_empty() {
+            self.pieces
+                .push(JsonPiece::Raw(core::mem::take(&mut self.raw)));
+        }
+    }
+
+    fn finish(mut self) -> Vec<JsonPiece> {
+        self.flush();
+        self.pieces
+    }
+}
+
+struct HashWriter(HashEngine);
+
+impl Write for HashWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.input(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Write the content of a JSON string, escaped in the same way as `serde_json`
+fn write_escaped<W>(w: &mut W, value: &str) -> fmt::Result
+where
+    W: Write,
+{
+    const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let bytes: &[u8] = value.as_bytes();
+    let mut start: usize = 0;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        let escape: &str = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\x08' => "\\b",
+            b'\x0c' => "\\f",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x00..=0x1F => "",
+            _ => continue,
+        };
+
+        // Flush the unescaped chars
+        if start < i {
+            w.write_str(&value[start..i])?;
+        }
+
+        if escape.is_empty() {
+            let unicode: [u8; 6] = [
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX_DIGITS[(byte >> 4) as usize],
+                HEX_DIGITS[(byte & 0xF) as usize],
+            ];
+            // SAFETY: the array contains only ASCII chars
+            w.write_str(unsafe { core::str::from_utf8_unchecked(&unicode) })?;
+        } else {
+            w.write_str(escape)?;
+        }
+
+        start = i + 1;
+    }
+
+    if start < bytes.len() {
+        w.write_str(&value[start..])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventBuilder, JsonUtil};
+
+    const PUBLIC_KEY: &str = "79dff8f82963424e0bb02708a22e44b4980893e3a4be0fa3cb60a43b946764e3";
+
+    /// Deterministic pseudo-random generator (xorshift64)
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn string(&mut self) -> String {
+            const CHARS: &[char] = &[
+                'a', 'Z', '0', ' ', '"', '\\', '/', '\n', '\r', '\t', '\x00', '\x08', '\x0c',
+                '\x1f', '\x7f', '{', '}', '[', ']', ',', ':', 'é', '€', '😀', '\u{2028}',
+            ];
+
+            let len: usize = (self.next() % 24) as usize;
+            (0..len)
+                .map(|_| CHARS[(self.next() % CHARS.len() as u64) as usize])
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_escape_like_serde_json() {
+        let mut all: String = (0u8..0x80).map(char::from).collect();
+        all.push_str("é€😀\u{2028}");
+
+        let mut escaped: String = String::from("\"");
+        write_escaped(&mut escaped, &all).unwrap();
+        escaped.push('"');
+
+        assert_eq!(escaped, serde_json::to_string(&all).unwrap());
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        assert_eq!(
+            EventTemplate::compile(Kind::TextNote, [vec!["{{name}}", "value"]], "").unwrap_err(),
+            Error::PlaceholderInTagName { index: 0 }
+        );
+        assert_eq!(
+            EventTemplate::compile(Kind::TextNote, [vec!["t", "a"], vec![]], "").unwrap_err(),
+            Error::EmptyTag { index: 1 }
+        );
+        assert_eq!(
+            EventTemplate::compile(Kind::TextNote, [vec!["t", "a", "{{b"]], "").unwrap_err(),
+            Error::UnclosedPlaceholder(PlaceholderPosition::Tag { index: 0, value: 2 })
+        );
+        assert_eq!(
+            EventTemplate::compile(Kind::TextNote, Vec::<Vec<&str>>::new(), "Hi {{na me}}")
+                .unwrap_err(),
+            Error::InvalidPlaceholderName {
+                position: PlaceholderPosition::Content,
+                name: String::from("na me"),
+            }
+        );
+        assert_eq!(
+            EventTemplate::compile(Kind::TextNote, Vec::<Vec<&str>>::new(), "{{}}").unwrap_err(),
+            Error::InvalidPlaceholderName {
+                position: PlaceholderPosition::Content,
+                name: String::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_instantiate_errors() {
+        let public_key = PublicKey::from_hex(PUBLIC_KEY).unwrap();
+        let template =
+            EventTemplate::compile(Kind::TextNote, [vec!["t", "{{topic}}"]], "{{body}}").unwrap();
+
+        assert_eq!(
+            template.placeholders().collect::<Vec<_>>(),
+            vec!["topic", "body"]
+        );
+        assert_eq!(
+            template
+                .instantiate(public_key, &[("topic", "nostr")])
+                .unwrap_err(),
+            Error::MissingValue(String::from("body"))
+        );
+        assert_eq!(
+            template
+                .instantiate(
+                    public_key,
+                    &[("topic", "nostr"), ("body", "hi"), ("other", "")]
+                )
+                .unwrap_err(),
+            Error::UnknownPlaceholder(String::from("other"))
+        );
+    }
+
+    #[test]
+    fn test_instantiate_like_event_builder() {
+        let public_key = PublicKey::from_hex(PUBLIC_KEY).unwrap();
+        let created_at = Timestamp::from_secs(1_700_000_000);
+        let template = EventTemplate::compile(
+            Kind::Custom(30078),
+            [
+                vec!["d", "{{id}}"],
+                vec!["t", "prefix-{{topic}}-{{topic}}", "\"quoted\"\n"],
+                vec!["alt"],
+            ],
+            "{{body}} (topic: {{topic}})",
+        )
+        .unwrap();
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..500 {
+            let id: String = rng.string();
+            let topic: String = rng.string();
+            let body: String = rng.string();
+
+            let unsigned = template
+                .instantiate_with_timestamp(
+                    public_key,
+                    created_at,
+                    &[("body", &body), ("id", &id), ("topic", &topic)],
+                )
+                .unwrap();
+
+            let expected =
+                EventBuilder::new(Kind::Custom(30078), format!("{body} (topic: {topic})"))
+                    .tags([
+                        Tag::parse(["d", id.as_str()]).unwrap(),
+                        Tag::parse([
+                            String::from("t"),
+                            format!("prefix-{topic}-{topic}"),
+                            String::from("\"quoted\"\n"),
+                        ])
+                        .unwrap(),
+                        Tag::parse(["alt"]).unwrap(),
+                    ])
+                    .custom_created_at(created_at)
+                    .build(public_key);
+
+            // Same ID
+            assert_eq!(unsigned.id, Some(expected.compute_id()));
+
+            // Byte-identical
+            let mut expected = expected;
+            expected.ensure_id();
+            assert_eq!(unsigned.as_json(), expected.as_json());
+            assert_eq!(unsigned, expected);
+        }
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use super::*;
+    use crate::EventBuilder;
+    use crate::test::{Bencher, black_box};
+
+    const PUBLIC_KEY: &str = "79dff8f82963424e0bb02708a22e44b4980893e3a4be0fa3cb60a43b946764e3";
+    const EVENT_ID: &str = "2be17aa3031bdcb006f0fce80c146dea9c1c0268b0af2398bb673365c6444d45";
+
+    #[bench]
+    pub fn instantiate_template_10k(bh: &mut Bencher) {
+        let public_key = PublicKey::from_hex(PUBLIC_KEY).unwrap();
+        let created_at = Timestamp::from_secs(1_700_000_000);
+        let template = EventTemplate::compile(
+            Kind::TextNote,
+            [
+                vec!["e", "{{event}}", "wss://relay.example.com", "root"],
+                vec!["t", "{{topic}}"],
+                vec!["client", "bench"],
+            ],
+            "{{content}}",
+        )
+        .unwrap();
+
+        bh.iter(|| {
+            for _ in 0..10_000 {
+                black_box(template.instantiate_with_timestamp(
+                    public_key,
+                    created_at,
+                    &[
+                        ("event", EVENT_ID),
+                        ("topic", "nostr"),
+                        ("content", "Hello world"),
+                    ],
+                ))
+                .unwrap();
+            }
+        });
+    }
+
+    #[bench]
+    pub fn build_with_event_builder_10k(bh: &mut Bencher) {
+        let public_key = PublicKey::from_hex(PUBLIC_KEY).unwrap();
+        let created_at = Timestamp::from_secs(1_700_000_000);
+
+        bh.iter(|| {
+            for _ in 0..10_000 {
+                let mut unsigned = EventBuilder::new(Kind::TextNote, "Hello world")
+                    .tags([
+                        Tag::parse(["e", EVENT_ID, "wss://relay.example.com", "root"]).unwrap(),
+                        Tag::parse(["t", "nostr"]).unwrap(),
+                        Tag::parse(["client", "bench"]).unwrap(),
+                    ])
+                    .custom_created_at(created_at)
+                    .build(public_key);
+                unsigned.ensure_id();
+                black_box(unsigned);
+            }
+        });
+    }
+}
@@ -46,6 +46,7 @@ pub mod filter;
 pub mod key;
 pub mod message;
 pub mod nips;
+#[cfg(feature = "parser")]
 pub mod parser;
 pub mod prelude;
 pub mod signer;
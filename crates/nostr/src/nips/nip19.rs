@@ -178,6 +178,7 @@ impl Nip19Prefix {
     }
 
     /// Get prefix len
+    #[cfg(feature = "parser")]
     pub fn len(&self) -> usize {
         match self {
             Self::NSec => PREFIX_BECH32_SECRET_KEY.len(),
@@ -6,14 +6,25 @@
 //!
 //! <https://github.com/nostr-protocol/nips/blob/master/56.md>
 
+use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::vec;
+use alloc::vec::Vec;
 use core::fmt;
 use core::str::FromStr;
 
+use hashes::hex::HexToArrayError;
+use hashes::sha256::Hash as Sha256Hash;
+
 use super::util::{take_and_parse_from_str, take_event_id, take_public_key};
 use crate::event::tag::{Tag, TagCodec, TagCodecError, impl_tag_codec_conversions};
-use crate::{EventId, PublicKey, event, key};
+use crate::types::url;
+use crate::{
+    Alphabet, Event, EventId, Filter, Kind, PublicKey, SingleLetterTag, Timestamp, Url, event, key,
+};
+
+const BLOB: &str = "x";
+const SERVER: &str = "server";
 
 /// NIP56 error
 #[derive(Debug, PartialEq)]
@@ -24,6 +35,10 @@ pub enum Error {
     Event(event::Error),
     /// Codec error
     Codec(TagCodecError),
+    /// Hex error
+    Hex(HexToArrayError),
+    /// Url error
+    Url(url::ParseError),
     /// Unknown [`Report`]
     UnknownReportType,
 }
@@ -36,6 +51,8 @@ impl fmt::Display for Error {
             Self::Keys(e) => e.fmt(f),
             Self::Event(e) => e.fmt(f),
             Self::Codec(e) => e.fmt(f),
+            Self::Hex(e) => e.fmt(f),
+            Self::Url(e) => e.fmt(f),
             Self::UnknownReportType => f.write_str("Unknown report type"),
         }
     }
@@ -59,6 +76,18 @@ impl From<TagCodecError> for Error {
     }
 }
 
+impl From<HexToArrayError> for Error {
+    fn from(e: HexToArrayError) -> Self {
+        Self::Hex(e)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(e: url::ParseError) -> Self {
+        Self::Url(e)
+    }
+}
+
 /// Report
 ///
 /// <https://github.com/nostr-protocol/nips/blob/master/56.md>
@@ -137,6 +166,17 @@ pub enum Nip56Tag {
         /// Report
         report: Report,
     },
+    /// `x` tag
+    Blob {
+        /// Blob hash
+        hash: Sha256Hash,
+        /// Report
+        report: Report,
+    },
+    /// `server` tag
+    ///
+    /// Where the reported blob can be found.
+    Server(Url),
 }
 
 impl TagCodec for Nip56Tag {
@@ -163,6 +203,17 @@ impl TagCodec for Nip56Tag {
                 let (public_key, report) = parse_p_tag(iter)?;
                 Ok(Self::PublicKey { public_key, report })
             }
+            BLOB => {
+                let hash: Sha256Hash =
+                    take_and_parse_from_str::<_, _, _, Error>(&mut iter, "blob hash")?;
+                let report: Report =
+                    take_and_parse_from_str::<_, _, _, Error>(&mut iter, "report")?;
+                Ok(Self::Blob { hash, report })
+            }
+            SERVER => {
+                let url: Url = take_and_parse_from_str::<_, _, _, Error>(&mut iter, "server URL")?;
+                Ok(Self::Server(url))
+            }
             _ => Err(TagCodecError::Unknown.into()),
         }
     }
@@ -177,12 +228,227 @@ impl TagCodec for Nip56Tag {
                 public_key.to_hex(),
                 report.to_string(),
             ]),
+            Self::Blob { hash, report } => Tag::new(vec![
+                String::from(BLOB),
+                hash.to_string(),
+                report.to_string(),
+            ]),
+            Self::Server(url) => Tag::new(vec![String::from(SERVER), url.to_string()]),
         }
     }
 }
 
 impl_tag_codec_conversions!(Nip56Tag);
 
+/// Target of a report
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/56.md>
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReportTarget {
+    /// A user
+    PublicKey(PublicKey),
+    /// A note
+    Event {
+        /// Event ID
+        id: EventId,
+        /// Author of the event
+        author: PublicKey,
+    },
+    /// A blob (i.e., an image or a video)
+    Blob {
+        /// Blob hash
+        hash: Sha256Hash,
+        /// ID of the event containing the blob
+        event_id: EventId,
+        /// Server where the blob can be found
+        server: Option<Url>,
+    },
+}
+
+impl ReportTarget {
+    /// Build the tags of a report
+    ///
+    /// - user: `["p", <public-key>, <report>]`
+    /// - note: `["e", <event-id>, <report>]` and `["p", <author>]`
+    /// - blob: `["x", <hash>, <report>]`, `["e", <event-id>, <report>]` and, optionally, `["server", <url>]`
+    pub fn to_tags(&self, report: Report) -> Vec<Tag> {
+        match self {
+            Self::PublicKey(public_key) => vec![
+                Nip56Tag::PublicKey {
+                    public_key: *public_key,
+                    report,
+                }
+                .to_tag(),
+            ],
+            Self::Event { id, author } => vec![
+                Nip56Tag::Event { id: *id, report }.to_tag(),
+                Tag::public_key(*author),
+            ],
+            Self::Blob {
+                hash,
+                event_id,
+                server,
+            } => {
+                let mut tags: Vec<Tag> = vec![
+                    Nip56Tag::Blob {
+                        hash: *hash,
+                        report: report.clone(),
+                    }
+                    .to_tag(),
+                    Nip56Tag::Event {
+                        id: *event_id,
+                        report,
+                    }
+                    .to_tag(),
+                ];
+
+                if let Some(url) = server {
+                    tags.push(Nip56Tag::Server(url.clone()).to_tag());
+                }
+
+                tags
+            }
+        }
+    }
+
+    /// Build the filter to get the reports about this target
+    pub fn filter(&self) -> Filter {
+        let filter: Filter = Filter::new().kind(Kind::Reporting);
+
+        match self {
+            Self::PublicKey(public_key) => filter.pubkey(*public_key),
+            Self::Event { id, .. } => filter.event(*id),
+            Self::Blob { hash, .. } => {
+                filter.custom_tag(SingleLetterTag::lowercase(Alphabet::X), hash.to_string())
+            }
+        }
+    }
+
+    /// Get the report about this target, if the event contains it
+    pub fn report_in(&self, event: &Event) -> Option<Report> {
+        if event.kind != Kind::Reporting {
+            return None;
+        }
+
+        event
+            .tags
+            .iter()
+            .filter_map(|tag| Nip56Tag::try_from(tag).ok())
+            .find_map(|tag| match (self, tag) {
+                (Self::PublicKey(target), Nip56Tag::PublicKey { public_key, report })
+                    if *target == public_key =>
+                {
+                    Some(report)
+                }
+                (Self::Event { id: target, .. }, Nip56Tag::Event { id, report })
+                    if *target == id =>
+                {
+                    Some(report)
+                }
+                (Self::Blob { hash: target, .. }, Nip56Tag::Blob { hash, report })
+                    if *target == hash =>
+                {
+                    Some(report)
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Summary of the reports about a [`ReportTarget`]
+///
+/// Every reporter is counted once, with its most recent report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportSummary {
+    reporters: BTreeMap<PublicKey, (Timestamp, Report)>,
+}
+
+impl ReportSummary {
+    /// New empty summary
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the summary from the report events
+    ///
+    /// The events that don't report the target are ignored.
+    pub fn from_events<'a, I>(target: &ReportTarget, events: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        let mut summary: Self = Self::new();
+        for event in events {
+            summary.add(target, event);
+        }
+        summary
+    }
+
+    /// Add a report event
+    ///
+    /// Returns `true` if the event reports the target and it's the most recent one of its author.
+    pub fn add(&mut self, target: &ReportTarget, event: &Event) -> bool {
+        let Some(report) = target.report_in(event) else {
+            return false;
+        };
+
+        match self.reporters.get(&event.pubkey) {
+            Some((created_at, ..)) if *created_at >= event.created_at => false,
+            _ => {
+                self.reporters
+                    .insert(event.pubkey, (event.created_at, report));
+                true
+            }
+        }
+    }
+
+    /// Number of reporters
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.reporters.len()
+    }
+
+    /// Check if there are no reports
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.reporters.is_empty()
+    }
+
+    /// Get the report of a reporter
+    #[inline]
+    pub fn report(&self, reporter: &PublicKey) -> Option<&Report> {
+        self.reporters.get(reporter).map(|(.., report)| report)
+    }
+
+    /// Iterate the reporters with their report
+    #[inline]
+    pub fn reporters(&self) -> impl Iterator<Item = (&PublicKey, &Report)> {
+        self.reporters
+            .iter()
+            .map(|(public_key, (.., report))| (public_key, report))
+    }
+
+    /// Number of reporters by report type
+    pub fn by_type(&self) -> BTreeMap<Report, usize> {
+        let mut map: BTreeMap<Report, usize> = BTreeMap::new();
+        for (.., report) in self.reporters.values() {
+            *map.entry(report.clone()).or_default() += 1;
+        }
+        map
+    }
+
+    /// Number of reporters in the given set (i.e., the trusted public keys)
+    pub fn count_from<'a, I>(&self, reporters: I) -> usize
+    where
+        I: IntoIterator<Item = &'a PublicKey>,
+    {
+        reporters
+            .into_iter()
+            .filter(|public_key| self.reporters.contains_key(public_key))
+            .count()
+    }
+}
+
 fn parse_e_tag<T, S>(mut iter: T) -> Result<(EventId, Report), Error>
 where
     T: Iterator<Item = S>,
@@ -209,6 +475,117 @@ where
 mod tests {
     use super::*;
 
+    const PUBLIC_KEY: &str = "13adc511de7e1cfcf1c6b7f6365fb5a03442d7bcacf565ea57fa7770912c023d";
+    const EVENT_ID: &str = "378f145897eea948952674269945e88612420db35791784abf0616b4fed56ef7";
+    const BLOB_HASH: &str = "e7f5a0b8c0e0f0b6c3d9a9d1f2e9c8a7b6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f1";
+
+    fn tags_of(target: &ReportTarget, report: Report) -> Vec<Vec<String>> {
+        target
+            .to_tags(report)
+            .into_iter()
+            .map(|t| t.to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_report_public_key_tags() {
+        let target = ReportTarget::PublicKey(PublicKey::from_hex(PUBLIC_KEY).unwrap());
+        assert_eq!(
+            tags_of(&target, Report::Nudity),
+            vec![vec!["p", PUBLIC_KEY, "nudity"]]
+        );
+    }
+
+    #[test]
+    fn test_report_event_tags() {
+        let target = ReportTarget::Event {
+            id: EventId::from_hex(EVENT_ID).unwrap(),
+            author: PublicKey::from_hex(PUBLIC_KEY).unwrap(),
+        };
+        assert_eq!(
+            tags_of(&target, Report::Illegal),
+            vec![vec!["e", EVENT_ID, "illegal"], vec!["p", PUBLIC_KEY]]
+        );
+    }
+
+    #[test]
+    fn test_report_blob_tags() {
+        let target = ReportTarget::Blob {
+            hash: Sha256Hash::from_str(BLOB_HASH).unwrap(),
+            event_id: EventId::from_hex(EVENT_ID).unwrap(),
+            server: Some(
+                Url::parse("https://you-may-find-the-blob-here.com/path-to-url.ext").unwrap(),
+            ),
+        };
+        assert_eq!(
+            tags_of(&target, Report::Malware),
+            vec![
+                vec!["x", BLOB_HASH, "malware"],
+                vec!["e", EVENT_ID, "malware"],
+                vec![
+                    "server",
+                    "https://you-may-find-the-blob-here.com/path-to-url.ext"
+                ],
+            ]
+        );
+
+        // Parse
+        let tags = target.to_tags(Report::Malware);
+        assert_eq!(
+            Nip56Tag::try_from(&tags[0]).unwrap(),
+            Nip56Tag::Blob {
+                hash: Sha256Hash::from_str(BLOB_HASH).unwrap(),
+                report: Report::Malware,
+            }
+        );
+        assert!(matches!(
+            Nip56Tag::try_from(&tags[2]).unwrap(),
+            Nip56Tag::Server(..)
+        ));
+    }
+
+    #[cfg(all(feature = "std", feature = "os-rng"))]
+    #[test]
+    fn test_report_summary() {
+        use crate::{EventBuilder, Keys};
+
+        let author = Keys::generate();
+        let target = ReportTarget::PublicKey(author.public_key());
+        let other = ReportTarget::PublicKey(Keys::generate().public_key());
+
+        let reporter1 = Keys::generate();
+        let reporter2 = Keys::generate();
+
+        let old = EventBuilder::report_target(&target, Report::Spam, "")
+            .custom_created_at(Timestamp::from_secs(1000))
+            .sign(&reporter1)
+            .unwrap();
+        let new = EventBuilder::report_target(&target, Report::Impersonation, "")
+            .custom_created_at(Timestamp::from_secs(2000))
+            .sign(&reporter1)
+            .unwrap();
+        let spam = EventBuilder::report_target(&target, Report::Spam, "")
+            .sign(&reporter2)
+            .unwrap();
+        let unrelated = EventBuilder::report_target(&other, Report::Spam, "")
+            .sign(&reporter2)
+            .unwrap();
+
+        let summary = ReportSummary::from_events(&target, [&new, &old, &spam, &unrelated]);
+
+        // Reporters are deduplicated, keeping the most recent report
+        assert_eq!(summary.len(), 2);
+        assert_eq!(
+            summary.report(&reporter1.public_key()),
+            Some(&Report::Impersonation)
+        );
+        assert_eq!(
+            summary.by_type(),
+            BTreeMap::from([(Report::Spam, 1), (Report::Impersonation, 1)])
+        );
+        assert_eq!(summary.count_from(&[reporter2.public_key()]), 1);
+    }
+
     #[test]
     fn test_report_e_tag() {
         let tag = vec![
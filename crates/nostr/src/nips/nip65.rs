@@ -6,6 +6,7 @@
 //!
 //! <https://github.com/nostr-protocol/nips/blob/master/65.md>
 
+use alloc::collections::btree_map::{self, BTreeMap};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::fmt;
@@ -185,6 +186,101 @@ pub fn extract_relay_list(
         })
 }
 
+/// Relay list
+///
+/// The relays of a NIP-65 relay list, with their optional read/write marker.
+///
+/// A relay without marker is used both for reading and writing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayList {
+    relays: BTreeMap<RelayUrl, Option<RelayMetadata>>,
+}
+
+impl RelayList {
+    /// New empty relay list
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Extract the relay list from a NIP-65 event
+    #[inline]
+    pub fn from_event(event: &Event) -> Self {
+        extract_relay_list(event).collect()
+    }
+
+    /// Insert a relay
+    ///
+    /// If the relay is already in the list with a different marker, it's used both for reading and writing.
+    pub fn insert(&mut self, relay_url: RelayUrl, metadata: Option<RelayMetadata>) {
+        match self.relays.entry(relay_url) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(metadata);
+            }
+            btree_map::Entry::Occupied(mut entry) => {
+                if *entry.get() != metadata {
+                    entry.insert(None);
+                }
+            }
+        }
+    }
+
+    /// Get the marker of a relay
+    ///
+    /// Returns `None` if the relay isn't in the list.
+    #[inline]
+    pub fn get(&self, relay_url: &RelayUrl) -> Option<Option<RelayMetadata>> {
+        self.relays.get(relay_url).copied()
+    }
+
+    /// Check if the relay is in the list
+    #[inline]
+    pub fn contains(&self, relay_url: &RelayUrl) -> bool {
+        self.relays.contains_key(relay_url)
+    }
+
+    /// Number of relays
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.relays.len()
+    }
+
+    /// Check if the list is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.relays.is_empty()
+    }
+
+    /// Iterate the relays
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&RelayUrl, Option<RelayMetadata>)> {
+        self.relays.iter().map(|(url, metadata)| (url, *metadata))
+    }
+}
+
+impl FromIterator<(RelayUrl, Option<RelayMetadata>)> for RelayList {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (RelayUrl, Option<RelayMetadata>)>,
+    {
+        let mut list: Self = Self::new();
+        for (relay_url, metadata) in iter {
+            list.insert(relay_url, metadata);
+        }
+        list
+    }
+}
+
+impl IntoIterator for RelayList {
+    type Item = (RelayUrl, Option<RelayMetadata>);
+    type IntoIter = btree_map::IntoIter<RelayUrl, Option<RelayMetadata>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.relays.into_iter()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,4 +316,23 @@ mod tests {
         );
         assert_eq!(parsed.to_tag(), Tag::parse(tag).unwrap());
     }
+
+    #[test]
+    fn test_relay_list_merge_markers() {
+        let damus = RelayUrl::parse("wss://relay.damus.io").unwrap();
+        let nos = RelayUrl::parse("wss://nos.lol").unwrap();
+
+        let list: RelayList = [
+            (damus.clone(), Some(RelayMetadata::Read)),
+            (nos.clone(), Some(RelayMetadata::Write)),
+            (damus.clone(), Some(RelayMetadata::Write)),
+            (nos.clone(), Some(RelayMetadata::Write)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(&damus), Some(None));
+        assert_eq!(list.get(&nos), Some(Some(RelayMetadata::Write)));
+    }
 }
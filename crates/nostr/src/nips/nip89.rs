@@ -6,23 +6,62 @@
 //!
 //! <https://github.com/nostr-protocol/nips/blob/master/89.md>
 
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
+use core::cmp::Reverse;
 use core::fmt;
+use core::str::FromStr;
 
-use super::nip01::Coordinate;
+use super::nip01::{Coordinate, Metadata};
+use super::nip19::{self, Nip19, Nip19Coordinate, Nip19Event, Nip19Profile, ToBech32};
 use super::util::take_string;
 use crate::event::tag::{Tag, TagCodec, TagCodecError, impl_tag_codec_conversions};
 use crate::types::url::RelayUrl;
+use crate::util::JsonUtil;
+use crate::{Event, EventId, Kind, PublicKey, Timestamp};
 
 const CLIENT: &str = "client";
+const KIND: &str = "k";
+const ADDRESS: &str = "a";
+const WEB: &str = "web";
+const IOS: &str = "ios";
+const ANDROID: &str = "android";
+const NPUB: &str = "npub";
+const NPROFILE: &str = "nprofile";
+const NOTE: &str = "note";
+const NEVENT: &str = "nevent";
+const NADDR: &str = "naddr";
+
+/// Placeholder of the URL templates, replaced by the bech32-encoded entity
+pub const BECH32_PLACEHOLDER: &str = "<bech32>";
 
 /// NIP-89 error
 #[derive(Debug, PartialEq)]
 pub enum Error {
     /// Codec error
     Codec(TagCodecError),
+    /// NIP-19 error
+    NIP19(nip19::Error),
+    /// Unexpected event kind
+    UnexpectedKind(Kind),
+    /// Missing `d` tag identifier
+    MissingIdentifier,
+    /// Invalid kind
+    InvalidKind(String),
+    /// Unknown entity type
+    UnknownEntity(String),
+    /// The URL template doesn't contain the [`BECH32_PLACEHOLDER`]
+    MissingPlaceholder,
+    /// The URL template has an invalid scheme
+    InvalidScheme,
+    /// The URL template has the [`BECH32_PLACEHOLDER`] in the scheme or in the host
+    PlaceholderInAuthority,
+    /// The handler doesn't support the kind
+    UnsupportedKind(Kind),
+    /// The event can't be encoded as the entity type of the URL template
+    UnsupportedEntity(HandlerEntity),
 }
 
 impl core::error::Error for Error {}
@@ -31,6 +70,20 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Codec(e) => e.fmt(f),
+            Self::NIP19(e) => e.fmt(f),
+            Self::UnexpectedKind(kind) => write!(f, "unexpected kind: {kind}"),
+            Self::MissingIdentifier => f.write_str("missing identifier"),
+            Self::InvalidKind(kind) => write!(f, "invalid kind: {kind}"),
+            Self::UnknownEntity(entity) => write!(f, "unknown entity type: {entity}"),
+            Self::MissingPlaceholder => write!(f, "missing {BECH32_PLACEHOLDER} placeholder"),
+            Self::InvalidScheme => f.write_str("invalid URL scheme"),
+            Self::PlaceholderInAuthority => {
+                write!(f, "{BECH32_PLACEHOLDER} placeholder in the scheme or host")
+            }
+            Self::UnsupportedKind(kind) => write!(f, "kind {kind} not supported"),
+            Self::UnsupportedEntity(entity) => {
+                write!(f, "the event can't be encoded as {entity}")
+            }
         }
     }
 }
@@ -41,6 +94,12 @@ impl From<TagCodecError> for Error {
     }
 }
 
+impl From<nip19::Error> for Error {
+    fn from(e: nip19::Error) -> Self {
+        Self::NIP19(e)
+    }
+}
+
 /// Standardized NIP-89 tags
 ///
 /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
@@ -139,9 +198,618 @@ where
     Ok((name, address))
 }
 
+/// Platform of a handler URL template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandlerPlatform {
+    /// Web
+    Web,
+    /// iOS
+    Ios,
+    /// Android
+    Android,
+}
+
+impl HandlerPlatform {
+    /// Get as `&str`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Web => WEB,
+            Self::Ios => IOS,
+            Self::Android => ANDROID,
+        }
+    }
+}
+
+impl fmt::Display for HandlerPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HandlerPlatform {
+    type Err = Error;
+
+    fn from_str(platform: &str) -> Result<Self, Self::Err> {
+        match platform {
+            WEB => Ok(Self::Web),
+            IOS => Ok(Self::Ios),
+            ANDROID => Ok(Self::Android),
+            _ => Err(TagCodecError::Unknown.into()),
+        }
+    }
+}
+
+/// Entity type of a handler URL template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandlerEntity {
+    /// `npub` of the event author
+    Npub,
+    /// `nprofile` of the event author
+    Nprofile,
+    /// `note` of the event ID
+    Note,
+    /// `nevent` of the event
+    Nevent,
+    /// `naddr` of the event coordinate (addressable events only)
+    Naddr,
+}
+
+impl HandlerEntity {
+    /// Get as `&str`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Npub => NPUB,
+            Self::Nprofile => NPROFILE,
+            Self::Note => NOTE,
+            Self::Nevent => NEVENT,
+            Self::Naddr => NADDR,
+        }
+    }
+
+    fn encode(&self, event: &Event) -> Result<String, Error> {
+        let entity: Nip19 = match self {
+            Self::Npub => Nip19::Pubkey(event.pubkey),
+            Self::Nprofile => Nip19::Profile(Nip19Profile::new(event.pubkey, [])),
+            Self::Note => Nip19::EventId(event.id),
+            Self::Nevent => Nip19::Event(
+                Nip19Event::new(event.id)
+                    .author(event.pubkey)
+                    .kind(event.kind),
+            ),
+            Self::Naddr => {
+                let coordinate: Coordinate =
+                    event.coordinate().ok_or(Error::UnsupportedEntity(*self))?;
+                Nip19::Coordinate(Nip19Coordinate::new(coordinate, []))
+            }
+        };
+        Ok(entity.to_bech32()?)
+    }
+}
+
+impl fmt::Display for HandlerEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HandlerEntity {
+    type Err = Error;
+
+    fn from_str(entity: &str) -> Result<Self, Self::Err> {
+        match entity {
+            NPUB => Ok(Self::Npub),
+            NPROFILE => Ok(Self::Nprofile),
+            NOTE => Ok(Self::Note),
+            NEVENT => Ok(Self::Nevent),
+            NADDR => Ok(Self::Naddr),
+            _ => Err(Error::UnknownEntity(entity.to_string())),
+        }
+    }
+}
+
+/// Platform-specific URL template of a handler
+///
+/// I.e., `["web", "https://example.com/a/<bech32>", "nevent"]` or `["ios", "example:<bech32>"]`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandlerUrl {
+    platform: HandlerPlatform,
+    template: String,
+    entity: Option<HandlerEntity>,
+}
+
+impl HandlerUrl {
+    /// New URL template
+    ///
+    /// The template must contain the [`BECH32_PLACEHOLDER`], after the scheme and the host.
+    /// If no entity type is set, the template handles all of them.
+    pub fn new<S>(
+        platform: HandlerPlatform,
+        template: S,
+        entity: Option<HandlerEntity>,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let template: String = template.into();
+        check_template(&template)?;
+        Ok(Self {
+            platform,
+            template,
+            entity,
+        })
+    }
+
+    /// Get the platform
+    #[inline]
+    pub fn platform(&self) -> HandlerPlatform {
+        self.platform
+    }
+
+    /// Get the template
+    #[inline]
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Get the entity type
+    #[inline]
+    pub fn entity(&self) -> Option<HandlerEntity> {
+        self.entity
+    }
+
+    /// Build the URL to open the event with the handler
+    ///
+    /// The event is bech32-encoded as the entity type of the template.
+    /// Without an entity type, addressable events are encoded as `naddr` and the others as `nevent`.
+    /// The encoded entity is percent-encoded before the substitution.
+    pub fn url_for(&self, event: &Event) -> Result<String, Error> {
+        let entity: HandlerEntity = match self.entity {
+            Some(entity) => entity,
+            None if event.kind.is_addressable() => HandlerEntity::Naddr,
+            None => HandlerEntity::Nevent,
+        };
+        let value: String = percent_encode(&entity.encode(event)?);
+        Ok(self.template.replace(BECH32_PLACEHOLDER, &value))
+    }
+
+    fn parse<S>(platform: HandlerPlatform, mut iter: impl Iterator<Item = S>) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let template: String = take_string(&mut iter, "URL template")?;
+        let entity: Option<HandlerEntity> = match iter.next() {
+            Some(entity) if !entity.as_ref().is_empty() => Some(entity.as_ref().parse()?),
+            _ => None,
+        };
+        Self::new(platform, template, entity)
+    }
+
+    fn to_tag(&self) -> Tag {
+        let mut tag: Vec<String> = vec![self.platform.to_string(), self.template.clone()];
+        if let Some(entity) = self.entity {
+            tag.push(entity.to_string());
+        }
+        Tag::new(tag)
+    }
+}
+
+/// Check that the placeholder can't end up in the scheme or in the host
+fn check_template(template: &str) -> Result<(), Error> {
+    let placeholder: usize = template
+        .find(BECH32_PLACEHOLDER)
+        .ok_or(Error::MissingPlaceholder)?;
+
+    // Scheme (RFC 3986): ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+    let colon: usize = template.find(':').ok_or(Error::InvalidScheme)?;
+    let mut scheme = template[..colon].chars();
+    if !scheme.next().is_some_and(|c| c.is_ascii_alphabetic())
+        || !scheme.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return Err(Error::InvalidScheme);
+    }
+
+    // Authority, if any: `//host[:port]`, until the path, the query or the fragment
+    let rest: &str = &template[colon + 1..];
+    let authority_end: usize = match rest.strip_prefix("//") {
+        Some(authority) => {
+            let len: usize = authority.find(['/', '?', '#']).unwrap_or(authority.len());
+            colon + 3 + len
+        }
+        None => colon + 1,
+    };
+
+    if placeholder < authority_end {
+        return Err(Error::PlaceholderInAuthority);
+    }
+
+    Ok(())
+}
+
+/// Percent-encode everything except the unreserved characters (RFC 3986)
+fn percent_encode(value: &str) -> String {
+    let mut encoded: String = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&alloc::format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Handler information (kind `31990`)
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/89.md#handler-information>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerInformation {
+    /// `d` tag identifier
+    pub identifier: String,
+    /// Supported kinds
+    pub kinds: Vec<Kind>,
+    /// URL templates
+    pub urls: Vec<HandlerUrl>,
+    /// Optional metadata, if the handler doesn't use the one of its author
+    pub metadata: Option<Metadata>,
+}
+
+impl HandlerInformation {
+    /// New handler information
+    pub fn new<S>(identifier: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            identifier: identifier.into(),
+            kinds: Vec::new(),
+            urls: Vec::new(),
+            metadata: None,
+        }
+    }
+
+    /// Add a supported kind
+    #[inline]
+    pub fn kind(mut self, kind: Kind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Add a URL template
+    #[inline]
+    pub fn url(mut self, url: HandlerUrl) -> Self {
+        self.urls.push(url);
+        self
+    }
+
+    /// Set the metadata
+    #[inline]
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Parse the handler information event
+    ///
+    /// Unknown tags are ignored. Invalid metadata is ignored too, since it's optional.
+    pub fn from_event(event: &Event) -> Result<Self, Error> {
+        if event.kind != Kind::HandlerInformation {
+            return Err(Error::UnexpectedKind(event.kind));
+        }
+
+        let identifier: String = event.tags.identifier().ok_or(Error::MissingIdentifier)?;
+        let mut info: Self = Self::new(identifier);
+
+        for tag in event.tags.iter() {
+            let mut iter = tag.as_slice().iter();
+            let Some(kind) = iter.next() else {
+                continue;
+            };
+
+            if kind == KIND {
+                let value: String = take_string(&mut iter, "kind")?;
+                let kind: u16 = value.parse().map_err(|_| Error::InvalidKind(value))?;
+                info.kinds.push(Kind::from_u16(kind));
+            } else if let Ok(platform) = HandlerPlatform::from_str(kind) {
+                info.urls.push(HandlerUrl::parse(platform, iter)?);
+            }
+        }
+
+        if !event.content.is_empty() {
+            info.metadata = Metadata::from_json(&event.content).ok();
+        }
+
+        Ok(info)
+    }
+
+    /// Check if the kind is supported
+    #[inline]
+    pub fn supports(&self, kind: &Kind) -> bool {
+        self.kinds.contains(kind)
+    }
+
+    /// Get the URL templates of a platform
+    pub fn urls_for(&self, platform: HandlerPlatform) -> impl Iterator<Item = &HandlerUrl> {
+        self.urls.iter().filter(move |url| url.platform == platform)
+    }
+
+    /// Build the tags: `d`, `k` and platform tags
+    pub fn to_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::with_capacity(1 + self.kinds.len() + self.urls.len());
+        tags.push(Tag::identifier(self.identifier.clone()));
+        tags.extend(
+            self.kinds
+                .iter()
+                .map(|kind| Tag::new(vec![KIND.to_string(), kind.to_string()])),
+        );
+        tags.extend(self.urls.iter().map(HandlerUrl::to_tag));
+        tags
+    }
+}
+
+/// Recommended handler of a [`HandlerRecommendation`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecommendedHandler {
+    /// Coordinate of the handler information event
+    pub coordinate: Coordinate,
+    /// Relay hint
+    pub relay_hint: Option<RelayUrl>,
+    /// Platform
+    pub platform: Option<HandlerPlatform>,
+}
+
+impl RecommendedHandler {
+    /// New recommended handler
+    #[inline]
+    pub fn new(coordinate: Coordinate) -> Self {
+        Self {
+            coordinate,
+            relay_hint: None,
+            platform: None,
+        }
+    }
+
+    /// Set the relay hint
+    #[inline]
+    pub fn relay_hint(mut self, relay_hint: RelayUrl) -> Self {
+        self.relay_hint = Some(relay_hint);
+        self
+    }
+
+    /// Set the platform
+    #[inline]
+    pub fn platform(mut self, platform: HandlerPlatform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Build the `a` tag
+    ///
+    /// `["a", <coordinate>, <relay-hint>, <platform>]`
+    pub fn to_tag(&self) -> Tag {
+        let mut tag: Vec<String> = vec![ADDRESS.to_string(), self.coordinate.to_string()];
+
+        match (&self.relay_hint, self.platform) {
+            (hint, Some(platform)) => {
+                tag.reserve_exact(2);
+                tag.push(hint.as_ref().map(|h| h.to_string()).unwrap_or_default());
+                tag.push(platform.to_string());
+            }
+            (Some(hint), None) => tag.push(hint.to_string()),
+            (None, None) => {}
+        }
+
+        Tag::new(tag)
+    }
+}
+
+/// Handler recommendation (kind `31989`)
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/89.md#recommendation-event>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerRecommendation {
+    /// Recommended kind (the `d` tag)
+    pub kind: Kind,
+    /// Recommended handlers
+    pub handlers: Vec<RecommendedHandler>,
+}
+
+impl HandlerRecommendation {
+    /// New recommendation
+    pub fn new<I>(kind: Kind, handlers: I) -> Self
+    where
+        I: IntoIterator<Item = RecommendedHandler>,
+    {
+        Self {
+            kind,
+            handlers: handlers.into_iter().collect(),
+        }
+    }
+
+    /// Parse the recommendation event
+    ///
+    /// The `a` tags that don't point to a handler information event are ignored.
+    pub fn from_event(event: &Event) -> Result<Self, Error> {
+        if event.kind != Kind::HandlerRecommendation {
+            return Err(Error::UnexpectedKind(event.kind));
+        }
+
+        let identifier: String = event.tags.identifier().ok_or(Error::MissingIdentifier)?;
+        let kind: u16 = identifier
+            .parse()
+            .map_err(|_| Error::InvalidKind(identifier))?;
+
+        let handlers = event.tags.iter().filter_map(|tag| {
+            let mut iter = tag.as_slice().iter();
+
+            if iter.next()? != ADDRESS {
+                return None;
+            }
+
+            let coordinate: Coordinate = Coordinate::parse(iter.next()?).ok()?;
+            if coordinate.kind != Kind::HandlerInformation {
+                return None;
+            }
+
+            Some(RecommendedHandler {
+                coordinate,
+                relay_hint: iter.next().and_then(|url| RelayUrl::parse(url).ok()),
+                platform: iter.next().and_then(|p| HandlerPlatform::from_str(p).ok()),
+            })
+        });
+
+        Ok(Self::new(Kind::from_u16(kind), handlers))
+    }
+
+    /// Build the tags: `d` (the recommended kind) and `a` tags
+    pub fn to_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::with_capacity(1 + self.handlers.len());
+        tags.push(Tag::identifier(self.kind.to_string()));
+        tags.extend(self.handlers.iter().map(RecommendedHandler::to_tag));
+        tags
+    }
+}
+
+/// Handler candidate of a [`HandlerDiscovery`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerCandidate {
+    /// Coordinate of the handler information event
+    pub coordinate: Coordinate,
+    /// Handler information
+    pub information: HandlerInformation,
+    /// Follows that recommend the handler
+    pub recommended_by: BTreeSet<PublicKey>,
+}
+
+impl HandlerCandidate {
+    /// Score: number of follows that recommend the handler
+    #[inline]
+    pub fn score(&self) -> usize {
+        self.recommended_by.len()
+    }
+}
+
+/// Handler information event skipped by a [`HandlerDiscovery`]
+#[derive(Debug, PartialEq)]
+pub struct SkippedHandler {
+    /// Event ID
+    pub event_id: EventId,
+    /// Author
+    pub author: PublicKey,
+    /// Why the event has been skipped
+    pub reason: Error,
+}
+
+/// Handlers of a kind, ranked by the recommendations of the follows
+#[derive(Debug, Default, PartialEq)]
+pub struct HandlerDiscovery {
+    /// Candidates, sorted by score (descending)
+    pub candidates: Vec<HandlerCandidate>,
+    /// Malformed or unsupported handler information events
+    pub skipped: Vec<SkippedHandler>,
+}
+
+impl HandlerDiscovery {
+    /// Rank the handlers of a kind
+    ///
+    /// The events can be a mix of handler information (kind `31990`) and recommendations (kind `31989`);
+    /// the other kinds are ignored.
+    /// Only the most recent version of every addressable event is considered,
+    /// and only the recommendations of the `follows` are counted.
+    pub fn from_events<'a, I>(kind: Kind, follows: &BTreeSet<PublicKey>, events: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        // Latest version of every addressable event
+        let mut latest: BTreeMap<Coordinate, &Event> = BTreeMap::new();
+        for event in events {
+            let is_handler: bool = matches!(
+                event.kind,
+                Kind::HandlerInformation | Kind::HandlerRecommendation
+            );
+            let Some(coordinate) = event.coordinate().filter(|_| is_handler) else {
+                continue;
+            };
+
+            match latest.get(&coordinate) {
+                Some(current) if current.created_at >= event.created_at => {}
+                _ => {
+                    latest.insert(coordinate, event);
+                }
+            }
+        }
+
+        let mut recommended_by: BTreeMap<Coordinate, BTreeSet<PublicKey>> = BTreeMap::new();
+        let mut informations: Vec<(Coordinate, Timestamp, HandlerInformation)> = Vec::new();
+        let mut skipped: Vec<SkippedHandler> = Vec::new();
+
+        for (coordinate, event) in latest.into_iter() {
+            if event.kind == Kind::HandlerRecommendation {
+                if !follows.contains(&event.pubkey) {
+                    continue;
+                }
+
+                match HandlerRecommendation::from_event(event) {
+                    Ok(recommendation) if recommendation.kind == kind => {
+                        for handler in recommendation.handlers.into_iter() {
+                            recommended_by
+                                .entry(handler.coordinate)
+                                .or_default()
+                                .insert(event.pubkey);
+                        }
+                    }
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            match HandlerInformation::from_event(event) {
+                Ok(info) if info.supports(&kind) => {
+                    informations.push((coordinate, event.created_at, info))
+                }
+                Ok(..) => skipped.push(SkippedHandler {
+                    event_id: event.id,
+                    author: event.pubkey,
+                    reason: Error::UnsupportedKind(kind),
+                }),
+                Err(reason) => skipped.push(SkippedHandler {
+                    event_id: event.id,
+                    author: event.pubkey,
+                    reason,
+                }),
+            }
+        }
+
+        let mut candidates: Vec<(Timestamp, HandlerCandidate)> = informations
+            .into_iter()
+            .map(|(coordinate, created_at, information)| {
+                let candidate = HandlerCandidate {
+                    recommended_by: recommended_by.remove(&coordinate).unwrap_or_default(),
+                    coordinate,
+                    information,
+                };
+                (created_at, candidate)
+            })
+            .collect();
+
+        // Most recommended first, then the most recently updated
+        candidates.sort_by_key(|(created_at, c)| (Reverse(c.score()), Reverse(*created_at)));
+
+        Self {
+            candidates: candidates.into_iter().map(|(.., c)| c).collect(),
+            skipped,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nips::nip19::FromBech32;
+
+    const NOTE_JSON: &str = r#"{"content":"Hello","created_at":1731850618,"id":"3e9e9c2fbf263590860a9c60a7de6b0d166230a5a15aa8dcdb70f537cec9807a","kind":1,"pubkey":"a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919","sig":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","tags":[]}"#;
+    const HANDLER_JSON: &str = r#"{"content":"{\"name\":\"Example\"}","created_at":1731850618,"id":"1e9e9c2fbf263590860a9c60a7de6b0d166230a5a15aa8dcdb70f537cec9807a","kind":31990,"pubkey":"a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919","sig":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","tags":[["d","1731850618505"],["k","1"],["k","30023"],["web","https://example.com/e/<bech32>","nevent"],["web","https://example.com/a/<bech32>","naddr"],["ios","example:<bech32>"],["alt","Handler information"]]}"#;
+    const RECOMMENDATION_JSON: &str = r#"{"content":"","created_at":1731850618,"id":"2e9e9c2fbf263590860a9c60a7de6b0d166230a5a15aa8dcdb70f537cec9807a","kind":31989,"pubkey":"a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919","sig":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","tags":[["d","30023"],["a","31990:a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919:1731850618505","wss://relay.damus.io","web"],["a","30023:a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919:article"],["a","invalid"]]}"#;
 
     #[test]
     fn test_client_tag() {
@@ -201,4 +869,218 @@ mod tests {
             Tag::parse(tag[..=2].iter().copied()).unwrap()
         ); // The empty relay-hint is not serialized
     }
+
+    #[test]
+    fn test_handler_url_templates() {
+        let note = Event::from_json(NOTE_JSON).unwrap();
+
+        // Web
+        let web = HandlerUrl::new(
+            HandlerPlatform::Web,
+            "https://example.com/e/<bech32>?ref=nostr",
+            Some(HandlerEntity::Note),
+        )
+        .unwrap();
+        assert_eq!(
+            web.url_for(&note).unwrap(),
+            format!(
+                "https://example.com/e/{}?ref=nostr",
+                note.id.to_bech32().unwrap()
+            )
+        );
+
+        // Native, without entity type: `nevent` for non-addressable events
+        let ios = HandlerUrl::new(HandlerPlatform::Ios, "example:<bech32>", None).unwrap();
+        let url = ios.url_for(&note).unwrap();
+        let nevent = Nip19Event::from_bech32(url.strip_prefix("example:").unwrap()).unwrap();
+        assert_eq!(nevent.event_id, note.id);
+        assert_eq!(nevent.author, Some(note.pubkey));
+
+        let android = HandlerUrl::new(
+            HandlerPlatform::Android,
+            "example://open/<bech32>",
+            Some(HandlerEntity::Npub),
+        )
+        .unwrap();
+        assert_eq!(
+            android.url_for(&note).unwrap(),
+            format!("example://open/{}", note.pubkey.to_bech32().unwrap())
+        );
+
+        // `naddr` requires an addressable event
+        let naddr = HandlerUrl::new(
+            HandlerPlatform::Web,
+            "https://example.com/a/<bech32>",
+            Some(HandlerEntity::Naddr),
+        )
+        .unwrap();
+        assert_eq!(
+            naddr.url_for(&note).unwrap_err(),
+            Error::UnsupportedEntity(HandlerEntity::Naddr)
+        );
+    }
+
+    #[test]
+    fn test_handler_url_template_injection() {
+        let cases: [(&str, Error); 6] = [
+            ("https://example.com/", Error::MissingPlaceholder),
+            ("<bech32>://example.com/", Error::InvalidScheme),
+            ("java script:<bech32>", Error::InvalidScheme),
+            (
+                "https://<bech32>.example.com/",
+                Error::PlaceholderInAuthority,
+            ),
+            (
+                "https://example.com:<bech32>/",
+                Error::PlaceholderInAuthority,
+            ),
+            (
+                "https://user:<bech32>@example.com/",
+                Error::PlaceholderInAuthority,
+            ),
+        ];
+
+        for (template, error) in cases {
+            assert_eq!(
+                HandlerUrl::new(HandlerPlatform::Web, template, None).unwrap_err(),
+                error,
+                "{template}"
+            );
+        }
+
+        assert_eq!(percent_encode("nevent1abc"), "nevent1abc");
+        assert_eq!(percent_encode("a/b?c=d#e f"), "a%2Fb%3Fc%3Dd%23e%20f");
+    }
+
+    #[test]
+    fn test_handler_information_from_event() {
+        let event = Event::from_json(HANDLER_JSON).unwrap();
+        let info = HandlerInformation::from_event(&event).unwrap();
+
+        assert_eq!(info.identifier, "1731850618505");
+        assert_eq!(info.kinds, vec![Kind::TextNote, Kind::LongFormTextNote]);
+        assert!(info.supports(&Kind::LongFormTextNote));
+        assert_eq!(info.urls.len(), 3);
+        assert_eq!(info.urls_for(HandlerPlatform::Web).count(), 2);
+        assert_eq!(
+            info.metadata.as_ref().and_then(|m| m.name.as_deref()),
+            Some("Example")
+        );
+
+        // The tags roundtrip, except for the unknown ones
+        let tags: Vec<&Tag> = event.tags.iter().filter(|t| t.kind() != "alt").collect();
+        assert_eq!(info.to_tags().iter().collect::<Vec<_>>(), tags);
+    }
+
+    #[cfg(all(feature = "std", feature = "os-rng"))]
+    #[test]
+    fn test_handler_discovery() {
+        use crate::{EventBuilder, Keys};
+
+        let follow1 = Keys::generate();
+        let follow2 = Keys::generate();
+        let stranger = Keys::generate();
+        let app1 = Keys::generate();
+        let app2 = Keys::generate();
+
+        let follows: BTreeSet<PublicKey> =
+            BTreeSet::from([follow1.public_key(), follow2.public_key()]);
+
+        let web =
+            HandlerUrl::new(HandlerPlatform::Web, "https://app.example/<bech32>", None).unwrap();
+        let info = HandlerInformation::new("app")
+            .kind(Kind::LongFormTextNote)
+            .url(web);
+
+        let handler1 = EventBuilder::handler_information(&info)
+            .sign(&app1)
+            .unwrap();
+        let handler2 = EventBuilder::handler_information(&info)
+            .sign(&app2)
+            .unwrap();
+        // Malformed: missing placeholder
+        let malformed = EventBuilder::new(Kind::HandlerInformation, "")
+            .tags([
+                Tag::identifier("bad"),
+                Tag::parse(["k", "30023"]).unwrap(),
+                Tag::parse(["web", "https://bad.example/"]).unwrap(),
+            ])
+            .sign(&stranger)
+            .unwrap();
+        // Doesn't support the kind
+        let unsupported = EventBuilder::handler_information(
+            &HandlerInformation::new("other").kind(Kind::TextNote),
+        )
+        .sign(&stranger)
+        .unwrap();
+
+        let recommend = |keys: &Keys, handlers: &[&Event]| {
+            let recommendation = HandlerRecommendation::new(
+                Kind::LongFormTextNote,
+                handlers.iter().map(|handler| {
+                    RecommendedHandler::new(handler.coordinate().unwrap())
+                        .platform(HandlerPlatform::Web)
+                }),
+            );
+            EventBuilder::handler_recommendation(&recommendation)
+                .sign(keys)
+                .unwrap()
+        };
+
+        let events = [
+            handler1.clone(),
+            handler2.clone(),
+            malformed.clone(),
+            unsupported.clone(),
+            recommend(&follow1, &[&handler1, &handler2]),
+            recommend(&follow2, &[&handler2]),
+            // Not a follow: ignored
+            recommend(&stranger, &[&handler1]),
+        ];
+
+        let discovery =
+            HandlerDiscovery::from_events(Kind::LongFormTextNote, &follows, events.iter());
+
+        assert_eq!(discovery.candidates.len(), 2);
+        assert_eq!(
+            discovery.candidates[0].coordinate,
+            handler2.coordinate().unwrap()
+        );
+        assert_eq!(discovery.candidates[0].score(), 2);
+        assert_eq!(
+            discovery.candidates[1].coordinate,
+            handler1.coordinate().unwrap()
+        );
+        assert_eq!(discovery.candidates[1].score(), 1);
+
+        assert_eq!(discovery.skipped.len(), 2);
+        assert!(discovery.skipped.contains(&SkippedHandler {
+            event_id: malformed.id,
+            author: stranger.public_key(),
+            reason: Error::MissingPlaceholder,
+        }));
+        assert!(discovery.skipped.contains(&SkippedHandler {
+            event_id: unsupported.id,
+            author: stranger.public_key(),
+            reason: Error::UnsupportedKind(Kind::LongFormTextNote),
+        }));
+    }
+
+    #[test]
+    fn test_handler_recommendation_from_event() {
+        let recommendation =
+            HandlerRecommendation::from_event(&Event::from_json(RECOMMENDATION_JSON).unwrap())
+                .unwrap();
+
+        assert_eq!(recommendation.kind, Kind::LongFormTextNote);
+        assert_eq!(
+            recommendation.handlers,
+            vec![RecommendedHandler::new(
+                Coordinate::parse("31990:a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919:1731850618505").unwrap()
+            )
+            .relay_hint(RelayUrl::parse("wss://relay.damus.io").unwrap())
+            .platform(HandlerPlatform::Web)]
+        );
+        assert_eq!(recommendation.handlers[0].to_tag().as_slice().len(), 4);
+    }
 }
@@ -23,6 +23,7 @@ pub use crate::event::builder::{self, *};
 pub use crate::event::id::{self, *};
 pub use crate::event::kind::{self, *};
 pub use crate::event::tag::{self, *};
+pub use crate::event::template::{self, *};
 pub use crate::event::unsigned::{self, *};
 pub use crate::event::{self, *};
 pub use crate::filter::{self, *};
@@ -88,6 +89,7 @@ pub use crate::nips::nip98::{self, *};
 pub use crate::nips::nipb0::{self, *};
 pub use crate::nips::nipb7::{self, *};
 pub use crate::nips::nipc0::{self, *};
+#[cfg(feature = "parser")]
 pub use crate::parser::{self, *};
 pub use crate::signer::{self, *};
 pub use crate::types::*;
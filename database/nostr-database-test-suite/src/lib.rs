@@ -1,5 +1,6 @@
 //! Database test suite
 
+pub extern crate futures;
 pub extern crate tokio;
 
 /// Macro to generate common database store tests.
@@ -13,6 +14,7 @@ macro_rules! database_unit_tests {
         use nostr::prelude::*;
         use nostr_database::prelude::*;
 
+        use $crate::futures::StreamExt;
         use $crate::tokio::{self, time};
 
         const EVENTS: [&str; 14] = [
@@ -340,6 +342,77 @@ macro_rules! database_unit_tests {
             assert_eq!(result_event.content, "Content 2");
         }
 
+        #[tokio::test]
+        async fn test_replaceable_events_out_of_order() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+
+            for kind in [Kind::Metadata, Kind::ContactList, Kind::RelayList] {
+                let newer = EventBuilder::new(kind, "newer")
+                    .custom_created_at(Timestamp::from_secs(2000))
+                    .sign(&keys)
+                    .expect("Failed to sign");
+                let older = EventBuilder::new(kind, "older")
+                    .custom_created_at(Timestamp::from_secs(1000))
+                    .sign(&keys)
+                    .expect("Failed to sign");
+
+                // Save the newer event first
+                let status = store.save_event(&newer).await.expect("Failed to save event");
+                assert_eq!(status, SaveEventStatus::Success);
+
+                // The older event arrives later: must be rejected
+                let status = store.save_event(&older).await.expect("Failed to save event");
+                assert_eq!(status, SaveEventStatus::Rejected(RejectedReason::Replaced));
+
+                let filter = Filter::new().author(keys.public_key()).kind(kind);
+                let results = store.query(filter).await.expect("Failed to query");
+                assert_eq!(results.to_vec(), vec![newer]);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_addressable_events_out_of_order() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+            let kind = Kind::from(32121);
+
+            let newer = EventBuilder::new(kind, "newer")
+                .tag(Tag::identifier("a"))
+                .custom_created_at(Timestamp::from_secs(2000))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let older = EventBuilder::new(kind, "older")
+                .tag(Tag::identifier("a"))
+                .custom_created_at(Timestamp::from_secs(1000))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let other = EventBuilder::new(kind, "other identifier")
+                .tag(Tag::identifier("b"))
+                .custom_created_at(Timestamp::from_secs(1000))
+                .sign(&keys)
+                .expect("Failed to sign");
+
+            // Save the newer event first
+            let status = store.save_event(&newer).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Success);
+
+            // The older event with the same identifier arrives later: must be rejected
+            let status = store.save_event(&older).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Rejected(RejectedReason::Replaced));
+
+            // An older event with another identifier is not replaced
+            let status = store.save_event(&other).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Success);
+
+            let filter = Filter::new().author(keys.public_key()).kind(kind);
+            let results = store.query(filter).await.expect("Failed to query");
+            assert_eq!(results.len(), 2);
+            assert!(results.contains(&newer));
+            assert!(results.contains(&other));
+            assert!(!results.contains(&older));
+        }
+
         #[tokio::test]
         async fn test_event_deletion_by_id() {
             let store: $store_type = $setup_fn().await;
@@ -422,6 +495,167 @@ macro_rules! database_unit_tests {
             assert_eq!(status, SaveEventStatus::Rejected(RejectedReason::Deleted));
         }
 
+        #[tokio::test]
+        async fn test_event_deletion_before_event() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+            let other = Keys::generate();
+
+            // Events not saved yet
+            let event1 = EventBuilder::text_note("To be deleted")
+                .sign(&keys)
+                .expect("Failed to sign");
+            let event2 = EventBuilder::text_note("Not owned")
+                .sign(&keys)
+                .expect("Failed to sign");
+
+            // Deletion requests received before the events
+            let deletion = EventBuilder::delete(EventDeletionRequest::new().id(event1.id))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let status = store.save_event(&deletion).await.expect("Failed to save deletion");
+            assert_eq!(status, SaveEventStatus::Success);
+
+            let deletion = EventBuilder::delete(EventDeletionRequest::new().id(event2.id))
+                .sign(&other)
+                .expect("Failed to sign");
+            let status = store.save_event(&deletion).await.expect("Failed to save deletion");
+            assert_eq!(status, SaveEventStatus::Success);
+
+            // Sleep to ensure deletion is processed in the ingester
+            time::sleep(Duration::from_millis(50)).await;
+
+            // The author requested the deletion: rejected
+            let status = store.save_event(&event1).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Rejected(RejectedReason::Deleted));
+
+            // Requested by another public key: saved
+            let status = store.save_event(&event2).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Success);
+        }
+
+        #[tokio::test]
+        async fn test_event_deletion_by_coordinate_keeps_newest() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+
+            let event = EventBuilder::new(Kind::Custom(11_111), "")
+                .custom_created_at(Timestamp::from_secs(1_000))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let coordinate: Coordinate = event.coordinate().unwrap();
+
+            // Newest deletion first, then an older one
+            for created_at in [2_000, 1_500] {
+                let deletion =
+                    EventBuilder::delete(EventDeletionRequest::new().coordinate(coordinate.clone()))
+                        .custom_created_at(Timestamp::from_secs(created_at))
+                        .sign(&keys)
+                        .expect("Failed to sign");
+                store.save_event(&deletion).await.expect("Failed to save deletion");
+            }
+
+            // Sleep to ensure deletion is processed in the ingester
+            time::sleep(Duration::from_millis(50)).await;
+
+            // Older than the newest deletion: rejected
+            let older = EventBuilder::new(Kind::Custom(11_111), "older")
+                .custom_created_at(Timestamp::from_secs(1_800))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let status = store.save_event(&older).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Rejected(RejectedReason::Deleted));
+
+            // Newer than the deletion: saved
+            let newer = EventBuilder::new(Kind::Custom(11_111), "newer")
+                .custom_created_at(Timestamp::from_secs(2_500))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let status = store.save_event(&newer).await.expect("Failed to save event");
+            assert_eq!(status, SaveEventStatus::Success);
+        }
+
+        #[tokio::test]
+        async fn test_save_events() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+            let other = Keys::generate();
+
+            let first = EventBuilder::text_note("First")
+                .sign(&keys)
+                .expect("Failed to sign");
+            let ephemeral = EventBuilder::new(Kind::Custom(20_000), "Ephemeral")
+                .sign(&keys)
+                .expect("Failed to sign");
+            // Deletion of a non-owned event
+            let invalid_delete = EventBuilder::delete(EventDeletionRequest::new().id(first.id))
+                .sign(&other)
+                .expect("Failed to sign");
+            let second = EventBuilder::text_note("Second")
+                .sign(&keys)
+                .expect("Failed to sign");
+
+            let statuses = store
+                .save_events(vec![
+                    first.clone(),
+                    ephemeral,
+                    first.clone(),
+                    invalid_delete,
+                    second.clone(),
+                ])
+                .await
+                .expect("Failed to save events");
+
+            // The rejected events don't abort the batch
+            assert_eq!(
+                statuses,
+                vec![
+                    SaveEventStatus::Success,
+                    SaveEventStatus::Rejected(RejectedReason::Ephemeral),
+                    SaveEventStatus::Rejected(RejectedReason::Duplicate),
+                    SaveEventStatus::Rejected(RejectedReason::InvalidDelete),
+                    SaveEventStatus::Success,
+                ]
+            );
+
+            assert_eq!(count_all(&store).await, 2);
+            assert!(get_event_by_id(&store, &first.id).await.is_some());
+            assert!(get_event_by_id(&store, &second.id).await.is_some());
+        }
+
+        #[tokio::test]
+        async fn test_wipe_expired() {
+            let store: $store_type = $setup_fn().await;
+            let keys = Keys::generate();
+            let now = Timestamp::now();
+
+            let expiring = EventBuilder::text_note("Expiring")
+                .tag(Tag::expiration(now + 1))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let not_expiring = EventBuilder::text_note("Not expiring")
+                .tag(Tag::expiration(now + 3600))
+                .sign(&keys)
+                .expect("Failed to sign");
+            let without_expiration = EventBuilder::text_note("Without expiration")
+                .sign(&keys)
+                .expect("Failed to sign");
+
+            for event in [&expiring, &not_expiring, &without_expiration] {
+                let status = store.save_event(event).await.expect("Failed to save event");
+                assert_eq!(status, SaveEventStatus::Success);
+            }
+
+            // Wait for the expiration
+            time::sleep(Duration::from_secs(2)).await;
+
+            store.wipe_expired().await.expect("Failed to wipe expired events");
+
+            assert!(get_event_by_id(&store, &expiring.id).await.is_none());
+            assert!(get_event_by_id(&store, &not_expiring.id).await.is_some());
+            assert!(get_event_by_id(&store, &without_expiration.id).await.is_some());
+        }
+
         #[tokio::test]
         async fn test_wipe_database() {
             let store: $store_type = $setup_fn().await;
@@ -479,6 +713,140 @@ macro_rules! database_unit_tests {
             }
         }
 
+        #[tokio::test]
+        async fn test_count_and_contains() {
+            let store: $store_type = $setup_fn().await;
+            let events = decode_events();
+
+            // Save all events
+            for event in &events {
+                store.save_event(event).await.expect("Failed to save event");
+            }
+
+            let author_a = PublicKey::from_hex("aa4fc8665f5696e33db7e1a572e3b0f5b3d615837b0f362dcb1c8068b098c7b4").unwrap();
+            let author_b = PublicKey::from_hex("79dff8f82963424e0bb02708a22e44b4980893e3a4be0fa3cb60a43b946764e3").unwrap();
+
+            let filters = [
+                Filter::new(),
+                Filter::new().limit(2),
+                Filter::new().since(Timestamp::from_secs(1704644600)),
+                Filter::new().kind(Kind::Custom(32122)),
+                Filter::new().kinds([Kind::Custom(32122), Kind::EventDeletion]),
+                Filter::new().kind(Kind::Custom(32122)).until(Timestamp::from_secs(1704644601)),
+                Filter::new().author(author_a),
+                Filter::new().authors([author_a, author_b]).limit(3),
+                Filter::new().author(author_b).kind(Kind::EventDeletion),
+                Filter::new().identifier("id-1"),
+                Filter::new().identifiers(["id-1", "id-2", "id-3"]),
+                Filter::new().kind(Kind::Custom(32122)).identifiers(["id-1", "id-3"]),
+                Filter::new().author(author_b).identifier("id-1"),
+                Filter::new().pubkey(author_a).since(Timestamp::from_secs(1705241093)),
+                Filter::new().author(author_a).kind(Kind::Custom(32122)).identifier("id-1"),
+                Filter::new().since(Timestamp::from_secs(10)).until(Timestamp::from_secs(5)),
+            ];
+
+            for filter in filters {
+                let expected: usize = store.query(filter.clone()).await.unwrap().len();
+                let count: usize = store.count(filter.clone()).await.unwrap();
+                assert_eq!(count, expected, "filter: {filter:?}");
+            }
+
+            // Contains
+            for event in store.query(Filter::new()).await.unwrap() {
+                assert!(store.contains(&event.id).await.unwrap());
+            }
+
+            // Deleted event
+            let deleted = EventId::from_hex("90a761aec9b5b60b399a76826141f529db17466deac85696a17e4a243aa271f9").unwrap();
+            assert!(!store.contains(&deleted).await.unwrap());
+
+            // Not existent
+            assert!(!store.contains(&EventId::all_zeros()).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_stream_query() {
+            let store: $store_type = $setup_fn().await;
+            let events = decode_events();
+
+            // Save all events
+            for event in &events {
+                store.save_event(event).await.expect("Failed to save event");
+            }
+
+            for filter in [
+                Filter::new(),
+                Filter::new().limit(3),
+                Filter::new().kind(Kind::Custom(32122)),
+                Filter::new().author(PublicKey::from_hex("aa4fc8665f5696e33db7e1a572e3b0f5b3d615837b0f362dcb1c8068b098c7b4").unwrap()).limit(2),
+            ] {
+                let expected: Vec<Event> = store.query(filter.clone()).await.unwrap().to_vec();
+
+                // Same events, same order
+                let streamed: Vec<Event> = store
+                    .stream_query(filter)
+                    .map(|res| res.expect("Failed to stream event"))
+                    .collect()
+                    .await;
+                assert_eq!(streamed, expected);
+            }
+
+            // Drop the stream early
+            let mut stream = store.stream_query(Filter::new());
+            assert!(stream.next().await.is_some());
+            drop(stream);
+
+            // The database must still be writable
+            let (_keys, event) = add_event(&store, EventBuilder::text_note("After stream")).await;
+            let event = get_existent_event_by_id(&store, &event.id).await;
+            assert_eq!(event.content, "After stream");
+        }
+
+        #[tokio::test]
+        async fn test_jsonl_export_import() {
+            let store: $store_type = $setup_fn().await;
+            let events = decode_events();
+
+            // Save all events
+            for event in &events {
+                store.save_event(event).await.expect("Failed to save event");
+            }
+
+            let expected: Vec<Event> = store.query(Filter::new()).await.unwrap().to_vec();
+
+            // Export
+            let mut buf: Vec<u8> = Vec::new();
+            let exported: usize = store
+                .export_jsonl(Filter::new(), $crate::futures::io::Cursor::new(&mut buf))
+                .await
+                .unwrap();
+            assert_eq!(exported, expected.len());
+
+            let jsonl: String = String::from_utf8(buf).unwrap();
+            assert_eq!(jsonl.lines().count(), expected.len());
+
+            // Append an invalid line, an event with an invalid signature and a duplicate
+            let mut tampered = expected[0].clone();
+            tampered.content = String::from("tampered");
+            let jsonl: String = format!(
+                "{jsonl}\nnot json\n{}\n{}\n",
+                tampered.as_json(),
+                expected[0].as_json()
+            );
+
+            // Import in a new store
+            let other: $store_type = $setup_fn().await;
+            let report: JsonlImportReport = other.import_jsonl(jsonl.as_bytes()).await.unwrap();
+            assert_eq!(report.saved, expected.len());
+            assert_eq!(report.rejected, 1);
+            assert_eq!(report.errors.len(), 2);
+            assert_eq!(report.errors[0].line, expected.len() + 2);
+            assert_eq!(report.errors[1].line, expected.len() + 3);
+
+            let imported: Vec<Event> = other.query(Filter::new()).await.unwrap().to_vec();
+            assert_eq!(imported, expected);
+        }
+
         #[tokio::test]
         async fn test_event_by_id() {
             let store: $store_type = $setup_fn().await;
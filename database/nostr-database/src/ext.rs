@@ -6,9 +6,14 @@
 
 use std::collections::{BTreeSet, HashMap, HashSet};
 
+use futures_util::StreamExt;
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
 use nostr::prelude::*;
 
-use crate::{DatabaseError, Events, NostrDatabase, Profile, RelaysMap};
+use crate::{
+    DatabaseError, Events, JsonlImportReport, JsonlLineError, NostrDatabase, Profile, RelaysMap,
+    SaveEventStatus,
+};
 
 /// Nostr Event Store Extension
 pub trait NostrDatabaseExt: NostrDatabase {
@@ -135,6 +140,107 @@ pub trait NostrDatabaseExt: NostrDatabase {
             Ok(map)
         })
     }
+
+    /// Export the events matching the filter as JSON lines
+    ///
+    /// The events are streamed from the database (see [`NostrDatabase::stream_query`])
+    /// and written one per line, without collecting them all in memory.
+    /// The database is read only as fast as the writer accepts the events.
+    ///
+    /// Returns the number of exported events.
+    fn export_jsonl<'a, W>(
+        &'a self,
+        filter: Filter,
+        mut writer: W,
+    ) -> BoxedFuture<'a, Result<usize, DatabaseError>>
+    where
+        W: AsyncWrite + Unpin + Send + 'a,
+    {
+        Box::pin(async move {
+            let mut stream = self.stream_query(filter);
+            let mut count: usize = 0;
+
+            while let Some(event) = stream.next().await {
+                let mut line: String = event?.as_json();
+                line.push('\n');
+
+                writer
+                    .write_all(line.as_bytes())
+                    .await
+                    .map_err(DatabaseError::backend)?;
+
+                count += 1;
+            }
+
+            writer.flush().await.map_err(DatabaseError::backend)?;
+
+            Ok(count)
+        })
+    }
+
+    /// Import the events from JSON lines
+    ///
+    /// Every line is parsed, verified and saved, one by one.
+    /// The empty lines are skipped and the invalid ones are collected in the report, without aborting the import.
+    fn import_jsonl<'a, R>(
+        &'a self,
+        mut reader: R,
+    ) -> BoxedFuture<'a, Result<JsonlImportReport, DatabaseError>>
+    where
+        R: AsyncBufRead + Unpin + Send + 'a,
+    {
+        Box::pin(async move {
+            let mut report: JsonlImportReport = JsonlImportReport::default();
+            let mut buf: String = String::new();
+            let mut line: usize = 0;
+
+            loop {
+                buf.clear();
+
+                if reader
+                    .read_line(&mut buf)
+                    .await
+                    .map_err(DatabaseError::backend)?
+                    == 0
+                {
+                    break;
+                }
+
+                line += 1;
+
+                let json: &str = buf.trim();
+                if json.is_empty() {
+                    continue;
+                }
+
+                let event: Event = match Event::from_json(json) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        report.errors.push(JsonlLineError {
+                            line,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                };
+
+                if let Err(e) = event.verify() {
+                    report.errors.push(JsonlLineError {
+                        line,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+
+                match self.save_event(&event).await? {
+                    SaveEventStatus::Success => report.saved += 1,
+                    SaveEventStatus::Rejected(..) => report.rejected += 1,
+                }
+            }
+
+            Ok(report)
+        })
+    }
 }
 
 impl<T: NostrDatabase + ?Sized> NostrDatabaseExt for T {}
@@ -0,0 +1,307 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! FlatBuffers batch API
+
+use flatbuffers::{FlatBufferBuilder, VerifierOptions};
+use nostr::Event;
+
+use super::{Error, FlatBufferDecode, FlatBufferEncode, create_event, decode_event, event_fbs};
+
+/// Encoded FlatBuffers
+///
+/// Contiguous buffer holding the FlatBuffers produced by [`FlatBufferEncodeBatch::encode_all`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlatBufferBatch {
+    data: Vec<u8>,
+    /// End offset of every buffer
+    ends: Vec<usize>,
+}
+
+impl FlatBufferBatch {
+    fn with_capacity(len: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            ends: Vec::with_capacity(len),
+        }
+    }
+
+    fn push(&mut self, buf: &[u8]) {
+        self.data.extend_from_slice(buf);
+        self.ends.push(self.data.len());
+    }
+
+    /// Number of buffers
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Check if there are no buffers
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Get buffer by index
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let end: usize = *self.ends.get(index)?;
+        let start: usize = match index {
+            0 => 0,
+            i => self.ends[i - 1],
+        };
+        Some(&self.data[start..end])
+    }
+
+    /// Iterate the buffers
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.len()).filter_map(|i| self.get(i))
+    }
+
+    /// Collect the buffers
+    #[inline]
+    pub fn to_vec(&self) -> Vec<&[u8]> {
+        self.iter().collect()
+    }
+}
+
+/// FlatBuffer batch encode trait
+pub trait FlatBufferEncodeBatch: Sized {
+    /// FlatBuffer encode all the items, reusing the same builder
+    fn encode_all(items: &[Self], fbb: &mut FlatBufferBuilder) -> FlatBufferBatch;
+}
+
+impl<T> FlatBufferEncodeBatch for T
+where
+    T: FlatBufferEncode,
+{
+    fn encode_all(items: &[Self], fbb: &mut FlatBufferBuilder) -> FlatBufferBatch {
+        let mut batch: FlatBufferBatch = FlatBufferBatch::with_capacity(items.len());
+
+        for item in items.iter() {
+            // The builder is reset by the encoder, keeping its allocation
+            batch.push(item.encode(fbb));
+        }
+
+        batch
+    }
+}
+
+/// FlatBuffer batch decode trait
+pub trait FlatBufferDecodeBatch: Sized {
+    /// FlatBuffer decode all the buffers
+    ///
+    /// With the `parallel` feature, the batches of at least 256 buffers are decoded on the rayon thread pool.
+    fn decode_all(bufs: &[&[u8]]) -> Result<Vec<Self>, Error>;
+}
+
+impl<T> FlatBufferDecodeBatch for T
+where
+    T: FlatBufferDecode + Send,
+{
+    fn decode_all(bufs: &[&[u8]]) -> Result<Vec<Self>, Error> {
+        #[cfg(feature = "parallel")]
+        if bufs.len() >= PARALLEL_MIN_BATCH_SIZE {
+            return parallel_decode_all(bufs);
+        }
+
+        bufs.iter().map(|buf| T::decode(buf)).collect()
+    }
+}
+
+/// FlatBuffer encode the events in a single buffer
+///
+/// Unlike [`FlatBufferEncodeBatch::encode_all`], the events are packed in one FlatBuffers vector,
+/// so the whole batch can be stored or sent as a single blob.
+pub fn encode_events<'a>(events: &[Event], fbb: &'a mut FlatBufferBuilder) -> &'a [u8] {
+    fbb.reset();
+
+    let offsets = events
+        .iter()
+        .map(|event| create_event(event, fbb))
+        .collect::<Vec<_>>();
+    let args = event_fbs::EventBatchArgs {
+        events: Some(fbb.create_vector(&offsets)),
+    };
+    let offset = event_fbs::EventBatch::create(fbb, &args);
+
+    fbb.finish(offset, None);
+
+    fbb.finished_data()
+}
+
+/// FlatBuffer decode the events encoded with [`encode_events`], preserving their order
+pub fn decode_events(buf: &[u8]) -> Result<Vec<Event>, Error> {
+    // Every event and tag is a table: don't cap their number
+    let opts = VerifierOptions {
+        max_tables: usize::MAX,
+        ..Default::default()
+    };
+    let batch = flatbuffers::root_with_opts::<event_fbs::EventBatch>(&opts, buf)?;
+
+    match batch.events() {
+        Some(events) => events.iter().map(decode_event).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Below this size, the batch is decoded on the current thread
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_BATCH_SIZE: usize = 256;
+
+/// Decode on the rayon global thread pool, shared by all the calls
+#[cfg(feature = "parallel")]
+fn parallel_decode_all<T>(bufs: &[&[u8]]) -> Result<Vec<T>, Error>
+where
+    T: FlatBufferDecode + Send,
+{
+    use rayon::prelude::*;
+
+    // The order of the items is preserved
+    bufs.par_iter().map(|buf| T::decode(buf)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use nostr::prelude::*;
+    use nostr::secp256k1::schnorr::Signature;
+
+    use super::*;
+
+    pub(super) fn events(n: u64) -> Vec<Event> {
+        let public_key =
+            PublicKey::from_hex("3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3")
+                .unwrap();
+        let sig = Signature::from_str("3f2ba6d713e4851500b81de2d2ef44b72f1eff061898bf8488e74f7e4ed141b0dadab4c3a9c6b237f3a6db83171bd41eafd7ab973f6fb067a4305e95abeadeee").unwrap();
+        (0..n)
+            .map(|i| {
+                let created_at = Timestamp::from_secs(1_700_000_000 + i);
+                let tags = Tags::from_list(vec![Tag::hashtag("nostr")]);
+                let content = format!("note {i}");
+                let id = EventId::new(&public_key, &created_at, &Kind::TextNote, &tags, &content);
+                Event::new(
+                    id,
+                    public_key,
+                    created_at,
+                    Kind::TextNote,
+                    tags,
+                    content,
+                    sig,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let events: Vec<Event> = events(10);
+
+        let mut fbb = FlatBufferBuilder::new();
+        let batch: FlatBufferBatch = Event::encode_all(&events, &mut fbb);
+        assert_eq!(batch.len(), events.len());
+
+        // Every buffer is the same as the single encoding
+        for (buf, event) in batch.iter().zip(events.iter()) {
+            assert_eq!(buf, event.encode(&mut fbb));
+        }
+
+        let decoded: Vec<Event> = Event::decode_all(&batch.to_vec()).unwrap();
+        assert_eq!(decoded, events);
+    }
+
+    #[test]
+    fn test_batch_empty() {
+        let mut fbb = FlatBufferBuilder::new();
+        let batch: FlatBufferBatch = Event::encode_all(&[], &mut fbb);
+        assert!(batch.is_empty());
+        assert!(batch.get(0).is_none());
+        assert!(Event::decode_all(&batch.to_vec()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encode_decode_events() {
+        let events: Vec<Event> = events(1_000);
+
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: Vec<u8> = encode_events(&events, &mut fbb).to_vec();
+
+        // Order is preserved
+        assert_eq!(decode_events(&buf).unwrap(), events);
+
+        // Empty
+        let buf: &[u8] = encode_events(&[], &mut fbb);
+        assert!(decode_events(buf).unwrap().is_empty());
+
+        // Invalid
+        assert!(decode_events(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_batch_decode_error() {
+        let events: Vec<Event> = events(1_000);
+
+        let mut fbb = FlatBufferBuilder::new();
+        let batch: FlatBufferBatch = Event::encode_all(&events, &mut fbb);
+
+        let decoded: Vec<Event> = Event::decode_all(&batch.to_vec()).unwrap();
+        assert_eq!(decoded, events);
+
+        // Corrupted buffer
+        let mut bufs: Vec<&[u8]> = batch.to_vec();
+        bufs[700] = &[0, 1, 2];
+        assert!(Event::decode_all(&bufs).is_err());
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use super::tests::events;
+    use super::*;
+    use crate::test::{Bencher, black_box};
+
+    #[bench]
+    pub fn bench_encode_events_one_by_one(bh: &mut Bencher) {
+        let events: Vec<Event> = events(1_000);
+        let mut fbb = FlatBufferBuilder::new();
+
+        bh.iter(|| {
+            black_box(Event::encode_all(&events, &mut fbb));
+        });
+    }
+
+    #[bench]
+    pub fn bench_encode_events_batch(bh: &mut Bencher) {
+        let events: Vec<Event> = events(1_000);
+        let mut fbb = FlatBufferBuilder::new();
+
+        bh.iter(|| {
+            black_box(encode_events(&events, &mut fbb));
+        });
+    }
+
+    #[bench]
+    pub fn bench_decode_events_one_by_one(bh: &mut Bencher) {
+        let events: Vec<Event> = events(1_000);
+        let mut fbb = FlatBufferBuilder::new();
+        let batch: FlatBufferBatch = Event::encode_all(&events, &mut fbb);
+        let bufs: Vec<&[u8]> = batch.to_vec();
+
+        bh.iter(|| {
+            black_box(Event::decode_all(&bufs)).unwrap();
+        });
+    }
+
+    #[bench]
+    pub fn bench_decode_events_batch(bh: &mut Bencher) {
+        let events: Vec<Event> = events(1_000);
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: Vec<u8> = encode_events(&events, &mut fbb).to_vec();
+
+        bh.iter(|| {
+            black_box(decode_events(&buf)).unwrap();
+        });
+    }
+}
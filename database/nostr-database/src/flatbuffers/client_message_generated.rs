@@ -0,0 +1,597 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+use self::flatbuffers::{EndianScalar, Follow};
+
+#[allow(unused_imports, dead_code)]
+pub mod client_message_fbs {
+
+    use core::cmp::Ordering;
+    use core::mem;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MIN_CLIENT_MESSAGE_TYPE: u8 = 0;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MAX_CLIENT_MESSAGE_TYPE: u8 = 7;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    #[allow(non_camel_case_types)]
+    pub const ENUM_VALUES_CLIENT_MESSAGE_TYPE: [ClientMessageType; 8] = [
+        ClientMessageType::Event,
+        ClientMessageType::Req,
+        ClientMessageType::Count,
+        ClientMessageType::Close,
+        ClientMessageType::Auth,
+        ClientMessageType::NegOpen,
+        ClientMessageType::NegMsg,
+        ClientMessageType::NegClose,
+    ];
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    #[repr(transparent)]
+    pub struct ClientMessageType(pub u8);
+    #[allow(non_upper_case_globals)]
+    impl ClientMessageType {
+        pub const Event: Self = Self(0);
+        pub const Req: Self = Self(1);
+        pub const Count: Self = Self(2);
+        pub const Close: Self = Self(3);
+        pub const Auth: Self = Self(4);
+        pub const NegOpen: Self = Self(5);
+        pub const NegMsg: Self = Self(6);
+        pub const NegClose: Self = Self(7);
+
+        pub const ENUM_MIN: u8 = 0;
+        pub const ENUM_MAX: u8 = 7;
+        pub const ENUM_VALUES: &'static [Self] = &[
+            Self::Event,
+            Self::Req,
+            Self::Count,
+            Self::Close,
+            Self::Auth,
+            Self::NegOpen,
+            Self::NegMsg,
+            Self::NegClose,
+        ];
+        /// Returns the variant's name or "" if unknown.
+        pub fn variant_name(self) -> Option<&'static str> {
+            match self {
+                Self::Event => Some("Event"),
+                Self::Req => Some("Req"),
+                Self::Count => Some("Count"),
+                Self::Close => Some("Close"),
+                Self::Auth => Some("Auth"),
+                Self::NegOpen => Some("NegOpen"),
+                Self::NegMsg => Some("NegMsg"),
+                Self::NegClose => Some("NegClose"),
+                _ => None,
+            }
+        }
+    }
+    impl core::fmt::Debug for ClientMessageType {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            if let Some(name) = self.variant_name() {
+                f.write_str(name)
+            } else {
+                f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+            }
+        }
+    }
+    impl<'a> flatbuffers::Follow<'a> for ClientMessageType {
+        type Inner = Self;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            let b = flatbuffers::read_scalar_at::<u8>(buf, loc);
+            Self(b)
+        }
+    }
+
+    impl flatbuffers::Push for ClientMessageType {
+        type Output = ClientMessageType;
+        #[inline]
+        unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+            flatbuffers::emplace_scalar::<u8>(dst, self.0);
+        }
+    }
+
+    impl flatbuffers::EndianScalar for ClientMessageType {
+        type Scalar = u8;
+        #[inline]
+        fn to_little_endian(self) -> u8 {
+            self.0.to_le()
+        }
+        #[inline]
+        #[allow(clippy::wrong_self_convention)]
+        fn from_little_endian(v: u8) -> Self {
+            let b = u8::from_le(v);
+            Self(b)
+        }
+    }
+
+    impl<'a> flatbuffers::Verifiable for ClientMessageType {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            u8::run_verifier(v, pos)
+        }
+    }
+
+    impl flatbuffers::SimpleToVerifyInSlice for ClientMessageType {}
+
+    pub enum FilterBufferOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct FilterBuffer<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for FilterBuffer<'a> {
+        type Inner = FilterBuffer<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> FilterBuffer<'a> {
+        pub const VT_DATA: flatbuffers::VOffsetT = 4;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            FilterBuffer { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args FilterBufferArgs<'args>,
+        ) -> flatbuffers::WIPOffset<FilterBuffer<'bldr>> {
+            let mut builder = FilterBufferBuilder::new(_fbb);
+            if let Some(x) = args.data {
+                builder.add_data(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn data(&self) -> Option<flatbuffers::Vector<'a, u8>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(
+                        FilterBuffer::VT_DATA,
+                        None,
+                    )
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for FilterBuffer<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u8>>>(
+                    "data",
+                    Self::VT_DATA,
+                    false,
+                )?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct FilterBufferArgs<'a> {
+        pub data: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+    }
+    impl<'a> Default for FilterBufferArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            FilterBufferArgs { data: None }
+        }
+    }
+
+    pub struct FilterBufferBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> FilterBufferBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_data(&mut self, data: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u8>>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(FilterBuffer::VT_DATA, data);
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> FilterBufferBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            FilterBufferBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<FilterBuffer<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for FilterBuffer<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("FilterBuffer");
+            ds.field("data", &self.data());
+            ds.finish()
+        }
+    }
+
+    pub enum ClientMessageOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct ClientMessage<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for ClientMessage<'a> {
+        type Inner = ClientMessage<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> ClientMessage<'a> {
+        pub const VT_MESSAGE_TYPE: flatbuffers::VOffsetT = 4;
+        pub const VT_SUBSCRIPTION_ID: flatbuffers::VOffsetT = 6;
+        pub const VT_EVENT: flatbuffers::VOffsetT = 8;
+        pub const VT_FILTERS: flatbuffers::VOffsetT = 10;
+        pub const VT_ID_SIZE: flatbuffers::VOffsetT = 12;
+        pub const VT_MESSAGE: flatbuffers::VOffsetT = 14;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            ClientMessage { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args ClientMessageArgs<'args>,
+        ) -> flatbuffers::WIPOffset<ClientMessage<'bldr>> {
+            let mut builder = ClientMessageBuilder::new(_fbb);
+            if let Some(x) = args.message {
+                builder.add_message(x);
+            }
+            if let Some(x) = args.filters {
+                builder.add_filters(x);
+            }
+            if let Some(x) = args.event {
+                builder.add_event(x);
+            }
+            if let Some(x) = args.subscription_id {
+                builder.add_subscription_id(x);
+            }
+            if let Some(x) = args.id_size {
+                builder.add_id_size(x);
+            }
+            builder.add_message_type(args.message_type);
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn message_type(&self) -> ClientMessageType {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<ClientMessageType>(
+                        ClientMessage::VT_MESSAGE_TYPE,
+                        Some(ClientMessageType::Event),
+                    )
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn subscription_id(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(
+                    ClientMessage::VT_SUBSCRIPTION_ID,
+                    None,
+                )
+            }
+        }
+        #[inline]
+        pub fn event(&self) -> Option<flatbuffers::Vector<'a, u8>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(
+                        ClientMessage::VT_EVENT,
+                        None,
+                    )
+            }
+        }
+        #[inline]
+        pub fn filters(
+            &self,
+        ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<FilterBuffer<'a>>>>
+        {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<FilterBuffer>>,
+                >>(ClientMessage::VT_FILTERS, None)
+            }
+        }
+        #[inline]
+        pub fn id_size(&self) -> Option<u8> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u8>(ClientMessage::VT_ID_SIZE, None) }
+        }
+        #[inline]
+        pub fn message(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(ClientMessage::VT_MESSAGE, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for ClientMessage<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<ClientMessageType>("message_type", Self::VT_MESSAGE_TYPE, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "subscription_id",
+                    Self::VT_SUBSCRIPTION_ID,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u8>>>(
+                    "event",
+                    Self::VT_EVENT,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<FilterBuffer>>,
+                >>("filters", Self::VT_FILTERS, false)?
+                .visit_field::<u8>("id_size", Self::VT_ID_SIZE, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "message",
+                    Self::VT_MESSAGE,
+                    false,
+                )?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct ClientMessageArgs<'a> {
+        pub message_type: ClientMessageType,
+        pub subscription_id: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub event: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+        pub filters: Option<
+            flatbuffers::WIPOffset<
+                flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<FilterBuffer<'a>>>,
+            >,
+        >,
+        pub id_size: Option<u8>,
+        pub message: Option<flatbuffers::WIPOffset<&'a str>>,
+    }
+    impl<'a> Default for ClientMessageArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            ClientMessageArgs {
+                message_type: ClientMessageType::Event,
+                subscription_id: None,
+                event: None,
+                filters: None,
+                id_size: None,
+                message: None,
+            }
+        }
+    }
+
+    pub struct ClientMessageBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> ClientMessageBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_message_type(&mut self, message_type: ClientMessageType) {
+            self.fbb_.push_slot::<ClientMessageType>(
+                ClientMessage::VT_MESSAGE_TYPE,
+                message_type,
+                ClientMessageType::Event,
+            );
+        }
+        #[inline]
+        pub fn add_subscription_id(&mut self, subscription_id: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                ClientMessage::VT_SUBSCRIPTION_ID,
+                subscription_id,
+            );
+        }
+        #[inline]
+        pub fn add_event(&mut self, event: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u8>>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(ClientMessage::VT_EVENT, event);
+        }
+        #[inline]
+        pub fn add_filters(
+            &mut self,
+            filters: flatbuffers::WIPOffset<
+                flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<FilterBuffer<'b>>>,
+            >,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(ClientMessage::VT_FILTERS, filters);
+        }
+        #[inline]
+        pub fn add_id_size(&mut self, id_size: u8) {
+            self.fbb_
+                .push_slot_always::<u8>(ClientMessage::VT_ID_SIZE, id_size);
+        }
+        #[inline]
+        pub fn add_message(&mut self, message: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(ClientMessage::VT_MESSAGE, message);
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> ClientMessageBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            ClientMessageBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<ClientMessage<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for ClientMessage<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("ClientMessage");
+            ds.field("message_type", &self.message_type());
+            ds.field("subscription_id", &self.subscription_id());
+            ds.field("event", &self.event());
+            ds.field("filters", &self.filters());
+            ds.field("id_size", &self.id_size());
+            ds.field("message", &self.message());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `ClientMessage`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_client_message_unchecked`.
+    pub fn root_as_client_message(
+        buf: &[u8],
+    ) -> Result<ClientMessage<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<ClientMessage>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `ClientMessage` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_client_message_unchecked`.
+    pub fn size_prefixed_root_as_client_message(
+        buf: &[u8],
+    ) -> Result<ClientMessage<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<ClientMessage>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `ClientMessage` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_client_message_unchecked`.
+    pub fn root_as_client_message_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<ClientMessage<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<ClientMessage<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `ClientMessage` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_client_message_unchecked`.
+    pub fn size_prefixed_root_as_client_message_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<ClientMessage<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<ClientMessage<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a ClientMessage and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `ClientMessage`.
+    pub unsafe fn root_as_client_message_unchecked(buf: &[u8]) -> ClientMessage<'_> {
+        flatbuffers::root_unchecked::<ClientMessage>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed ClientMessage and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `ClientMessage`.
+    pub unsafe fn size_prefixed_root_as_client_message_unchecked(buf: &[u8]) -> ClientMessage<'_> {
+        flatbuffers::size_prefixed_root_unchecked::<ClientMessage>(buf)
+    }
+    #[inline]
+    pub fn finish_client_message_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<ClientMessage<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_client_message_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<ClientMessage<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod ClientMessageFbs
@@ -547,6 +547,130 @@ pub mod event_fbs {
             ds.finish()
         }
     }
+    pub enum EventBatchOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct EventBatch<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for EventBatch<'a> {
+        type Inner = EventBatch<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> EventBatch<'a> {
+        pub const VT_EVENTS: flatbuffers::VOffsetT = 4;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            EventBatch { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args EventBatchArgs<'args>,
+        ) -> flatbuffers::WIPOffset<EventBatch<'bldr>> {
+            let mut builder = EventBatchBuilder::new(_fbb);
+            if let Some(x) = args.events {
+                builder.add_events(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn events(
+            &self,
+        ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Event<'a>>>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Event>>,
+                >>(EventBatch::VT_EVENTS, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for EventBatch<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Event>>,
+                >>("events", Self::VT_EVENTS, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct EventBatchArgs<'a> {
+        pub events: Option<
+            flatbuffers::WIPOffset<
+                flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Event<'a>>>,
+            >,
+        >,
+    }
+    impl<'a> Default for EventBatchArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            EventBatchArgs { events: None }
+        }
+    }
+
+    pub struct EventBatchBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> EventBatchBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_events(
+            &mut self,
+            events: flatbuffers::WIPOffset<
+                flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<Event<'b>>>,
+            >,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(EventBatch::VT_EVENTS, events);
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> EventBatchBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            EventBatchBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<EventBatch<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for EventBatch<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("EventBatch");
+            ds.field("events", &self.events());
+            ds.finish()
+        }
+    }
     #[inline]
     /// Verifies that a buffer of bytes contains a `Event`
     /// and returns it.
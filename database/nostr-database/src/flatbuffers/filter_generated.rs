@@ -0,0 +1,633 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+use self::flatbuffers::{EndianScalar, Follow};
+
+#[allow(unused_imports, dead_code)]
+pub mod filter_fbs {
+
+    use core::cmp::Ordering;
+    use core::mem;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    // struct Fixed32Bytes, aligned to 1
+    #[repr(transparent)]
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Fixed32Bytes(pub [u8; 32]);
+    impl Default for Fixed32Bytes {
+        fn default() -> Self {
+            Self([0; 32])
+        }
+    }
+    impl core::fmt::Debug for Fixed32Bytes {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.debug_struct("Fixed32Bytes")
+                .field("val", &self.val())
+                .finish()
+        }
+    }
+
+    impl flatbuffers::SimpleToVerifyInSlice for Fixed32Bytes {}
+    impl<'a> flatbuffers::Follow<'a> for Fixed32Bytes {
+        type Inner = &'a Fixed32Bytes;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            <&'a Fixed32Bytes>::follow(buf, loc)
+        }
+    }
+    impl<'a> flatbuffers::Follow<'a> for &'a Fixed32Bytes {
+        type Inner = &'a Fixed32Bytes;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            flatbuffers::follow_cast_ref::<Fixed32Bytes>(buf, loc)
+        }
+    }
+    impl<'b> flatbuffers::Push for Fixed32Bytes {
+        type Output = Fixed32Bytes;
+        #[inline]
+        unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+            let src = ::core::slice::from_raw_parts(
+                self as *const Fixed32Bytes as *const u8,
+                <Self as flatbuffers::Push>::size(),
+            );
+            dst.copy_from_slice(src);
+        }
+        #[inline]
+        fn alignment() -> flatbuffers::PushAlignment {
+            flatbuffers::PushAlignment::new(1)
+        }
+    }
+
+    impl<'a> flatbuffers::Verifiable for Fixed32Bytes {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.in_buffer::<Self>(pos)
+        }
+    }
+
+    impl<'a> Fixed32Bytes {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(val: &[u8; 32]) -> Self {
+            let mut s = Self([0; 32]);
+            s.set_val(val);
+            s
+        }
+
+        pub fn val(&'a self) -> flatbuffers::Array<'a, u8, 32> {
+            // Safety:
+            // Created from a valid Table for this object
+            // Which contains a valid array in this slot
+            unsafe { flatbuffers::Array::follow(&self.0, 0) }
+        }
+
+        pub fn set_val(&mut self, items: &[u8; 32]) {
+            // Safety:
+            // Created from a valid Table for this object
+            // Which contains a valid array in this slot
+            unsafe { flatbuffers::emplace_scalar_array(&mut self.0, 0, items) };
+        }
+    }
+
+    pub enum GenericTagOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct GenericTag<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for GenericTag<'a> {
+        type Inner = GenericTag<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> GenericTag<'a> {
+        pub const VT_KEY: flatbuffers::VOffsetT = 4;
+        pub const VT_VALUES: flatbuffers::VOffsetT = 6;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            GenericTag { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args GenericTagArgs<'args>,
+        ) -> flatbuffers::WIPOffset<GenericTag<'bldr>> {
+            let mut builder = GenericTagBuilder::new(_fbb);
+            if let Some(x) = args.values {
+                builder.add_values(x);
+            }
+            if let Some(x) = args.key {
+                builder.add_key(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn key(&self) -> &'a str {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(GenericTag::VT_KEY, None)
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn values(
+            &self,
+        ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>,
+                >>(GenericTag::VT_VALUES, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for GenericTag<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>("key", Self::VT_KEY, true)?
+                .visit_field::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<&'_ str>>,
+                >>("values", Self::VT_VALUES, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct GenericTagArgs<'a> {
+        pub key: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub values: Option<
+            flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>,
+        >,
+    }
+    impl<'a> Default for GenericTagArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            GenericTagArgs {
+                key: None, // required field
+                values: None,
+            }
+        }
+    }
+
+    pub struct GenericTagBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> GenericTagBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_key(&mut self, key: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(GenericTag::VT_KEY, key);
+        }
+        #[inline]
+        pub fn add_values(
+            &mut self,
+            values: flatbuffers::WIPOffset<
+                flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<&'b str>>,
+            >,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(GenericTag::VT_VALUES, values);
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> GenericTagBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            GenericTagBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<GenericTag<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            self.fbb_.required(o, GenericTag::VT_KEY, "key");
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for GenericTag<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("GenericTag");
+            ds.field("key", &self.key());
+            ds.field("values", &self.values());
+            ds.finish()
+        }
+    }
+    pub enum FilterOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct Filter<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for Filter<'a> {
+        type Inner = Filter<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> Filter<'a> {
+        pub const VT_IDS: flatbuffers::VOffsetT = 4;
+        pub const VT_AUTHORS: flatbuffers::VOffsetT = 6;
+        pub const VT_KINDS: flatbuffers::VOffsetT = 8;
+        pub const VT_SEARCH: flatbuffers::VOffsetT = 10;
+        pub const VT_SINCE: flatbuffers::VOffsetT = 12;
+        pub const VT_UNTIL: flatbuffers::VOffsetT = 14;
+        pub const VT_LIMIT: flatbuffers::VOffsetT = 16;
+        pub const VT_GENERIC_TAGS: flatbuffers::VOffsetT = 18;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            Filter { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args FilterArgs<'args>,
+        ) -> flatbuffers::WIPOffset<Filter<'bldr>> {
+            let mut builder = FilterBuilder::new(_fbb);
+            if let Some(x) = args.limit {
+                builder.add_limit(x);
+            }
+            if let Some(x) = args.until {
+                builder.add_until(x);
+            }
+            if let Some(x) = args.since {
+                builder.add_since(x);
+            }
+            if let Some(x) = args.generic_tags {
+                builder.add_generic_tags(x);
+            }
+            if let Some(x) = args.search {
+                builder.add_search(x);
+            }
+            if let Some(x) = args.kinds {
+                builder.add_kinds(x);
+            }
+            if let Some(x) = args.authors {
+                builder.add_authors(x);
+            }
+            if let Some(x) = args.ids {
+                builder.add_ids(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn ids(&self) -> Option<flatbuffers::Vector<'a, Fixed32Bytes>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, Fixed32Bytes>>>(
+                        Filter::VT_IDS,
+                        None,
+                    )
+            }
+        }
+        #[inline]
+        pub fn authors(&self) -> Option<flatbuffers::Vector<'a, Fixed32Bytes>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, Fixed32Bytes>>>(
+                        Filter::VT_AUTHORS,
+                        None,
+                    )
+            }
+        }
+        #[inline]
+        pub fn kinds(&self) -> Option<flatbuffers::Vector<'a, u16>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u16>>>(
+                        Filter::VT_KINDS,
+                        None,
+                    )
+            }
+        }
+        #[inline]
+        pub fn search(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(Filter::VT_SEARCH, None)
+            }
+        }
+        #[inline]
+        pub fn since(&self) -> Option<u64> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Filter::VT_SINCE, None) }
+        }
+        #[inline]
+        pub fn until(&self) -> Option<u64> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Filter::VT_UNTIL, None) }
+        }
+        #[inline]
+        pub fn limit(&self) -> Option<u64> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Filter::VT_LIMIT, None) }
+        }
+        #[inline]
+        pub fn generic_tags(
+            &self,
+        ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<GenericTag<'a>>>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<GenericTag>>,
+                >>(Filter::VT_GENERIC_TAGS, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for Filter<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, Fixed32Bytes>>>(
+                    "ids",
+                    Self::VT_IDS,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, Fixed32Bytes>>>(
+                    "authors",
+                    Self::VT_AUTHORS,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u16>>>(
+                    "kinds",
+                    Self::VT_KINDS,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "search",
+                    Self::VT_SEARCH,
+                    false,
+                )?
+                .visit_field::<u64>("since", Self::VT_SINCE, false)?
+                .visit_field::<u64>("until", Self::VT_UNTIL, false)?
+                .visit_field::<u64>("limit", Self::VT_LIMIT, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<GenericTag>>,
+                >>("generic_tags", Self::VT_GENERIC_TAGS, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct FilterArgs<'a> {
+        pub ids: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, Fixed32Bytes>>>,
+        pub authors: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, Fixed32Bytes>>>,
+        pub kinds: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u16>>>,
+        pub search: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub since: Option<u64>,
+        pub until: Option<u64>,
+        pub limit: Option<u64>,
+        pub generic_tags: Option<
+            flatbuffers::WIPOffset<
+                flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<GenericTag<'a>>>,
+            >,
+        >,
+    }
+    impl<'a> Default for FilterArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            FilterArgs {
+                ids: None,
+                authors: None,
+                kinds: None,
+                search: None,
+                since: None,
+                until: None,
+                limit: None,
+                generic_tags: None,
+            }
+        }
+    }
+
+    pub struct FilterBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> FilterBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_ids(
+            &mut self,
+            ids: flatbuffers::WIPOffset<flatbuffers::Vector<'b, Fixed32Bytes>>,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Filter::VT_IDS, ids);
+        }
+        #[inline]
+        pub fn add_authors(
+            &mut self,
+            authors: flatbuffers::WIPOffset<flatbuffers::Vector<'b, Fixed32Bytes>>,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Filter::VT_AUTHORS, authors);
+        }
+        #[inline]
+        pub fn add_kinds(&mut self, kinds: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u16>>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Filter::VT_KINDS, kinds);
+        }
+        #[inline]
+        pub fn add_search(&mut self, search: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Filter::VT_SEARCH, search);
+        }
+        #[inline]
+        pub fn add_since(&mut self, since: u64) {
+            self.fbb_.push_slot_always::<u64>(Filter::VT_SINCE, since);
+        }
+        #[inline]
+        pub fn add_until(&mut self, until: u64) {
+            self.fbb_.push_slot_always::<u64>(Filter::VT_UNTIL, until);
+        }
+        #[inline]
+        pub fn add_limit(&mut self, limit: u64) {
+            self.fbb_.push_slot_always::<u64>(Filter::VT_LIMIT, limit);
+        }
+        #[inline]
+        pub fn add_generic_tags(
+            &mut self,
+            generic_tags: flatbuffers::WIPOffset<
+                flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<GenericTag<'b>>>,
+            >,
+        ) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                Filter::VT_GENERIC_TAGS,
+                generic_tags,
+            );
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> FilterBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            FilterBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<Filter<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for Filter<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("Filter");
+            ds.field("ids", &self.ids());
+            ds.field("authors", &self.authors());
+            ds.field("kinds", &self.kinds());
+            ds.field("search", &self.search());
+            ds.field("since", &self.since());
+            ds.field("until", &self.until());
+            ds.field("limit", &self.limit());
+            ds.field("generic_tags", &self.generic_tags());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `Filter`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_filter_unchecked`.
+    pub fn root_as_filter(buf: &[u8]) -> Result<Filter<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<Filter>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `Filter` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_filter_unchecked`.
+    pub fn size_prefixed_root_as_filter(
+        buf: &[u8],
+    ) -> Result<Filter<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<Filter>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `Filter` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_filter_unchecked`.
+    pub fn root_as_filter_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Filter<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<Filter<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `Filter` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_filter_unchecked`.
+    pub fn size_prefixed_root_as_filter_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Filter<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<Filter<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a Filter and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `Filter`.
+    pub unsafe fn root_as_filter_unchecked(buf: &[u8]) -> Filter<'_> {
+        flatbuffers::root_unchecked::<Filter>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed Filter and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `Filter`.
+    pub unsafe fn size_prefixed_root_as_filter_unchecked(buf: &[u8]) -> Filter<'_> {
+        flatbuffers::size_prefixed_root_unchecked::<Filter>(buf)
+    }
+    #[inline]
+    pub fn finish_filter_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<Filter<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_filter_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<Filter<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod FilterFbs
@@ -0,0 +1,228 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! FlatBuffers filter matching
+
+use std::str::FromStr;
+
+use nostr::{Filter, Kind, SingleLetterTag, Timestamp};
+
+use super::{Error, MissingField, decode_kind, event_fbs};
+
+/// Check if a FlatBuffers-encoded event matches the [`Filter`], without decoding it
+///
+/// Equivalent to [`Filter::match_event`] with the default options,
+/// but works directly on the borrowed buffer: no tags are allocated and the signature isn't parsed.
+pub fn match_filter(buf: &[u8], filter: &Filter) -> Result<bool, Error> {
+    let ev = event_fbs::root_as_event(buf)?;
+
+    // Cheapest checks first
+    let kind: Kind = Kind::from_u16(decode_kind(&ev)?);
+    if let Some(kinds) = &filter.kinds {
+        if !kinds.is_empty() && !kinds.contains(&kind) {
+            return Ok(false);
+        }
+    }
+
+    let created_at: Timestamp = Timestamp::from_secs(ev.created_at());
+    if filter.since.is_some_and(|since| created_at < since)
+        || filter.until.is_some_and(|until| created_at > until)
+    {
+        return Ok(false);
+    }
+
+    if let Some(ids) = &filter.ids {
+        if !ids.is_empty() {
+            let id = ev.id().ok_or(Error::FieldNotFound(MissingField::Id))?;
+            if !ids.iter().any(|i| i.as_bytes() == &id.0) {
+                return Ok(false);
+            }
+        }
+    }
+
+    if let Some(authors) = &filter.authors {
+        if !authors.is_empty() {
+            let pubkey = ev
+                .pubkey()
+                .ok_or(Error::FieldNotFound(MissingField::Pubkey))?;
+            if !authors.iter().any(|pk| pk.as_bytes() == &pubkey.0) {
+                return Ok(false);
+            }
+        }
+    }
+
+    if !filter.generic_tags.is_empty() {
+        let tags = ev.tags().ok_or(Error::FieldNotFound(MissingField::Tags))?;
+
+        let matched: bool = filter.generic_tags.iter().all(|(tag_name, values)| {
+            tags.iter().filter_map(|t| t.data()).any(|data| {
+                // Same as the tag indexes: single-letter tags with a value
+                if data.len() < 2 {
+                    return false;
+                }
+
+                SingleLetterTag::from_str(data.get(0)).is_ok_and(|t| &t == tag_name)
+                    && values.contains(data.get(1))
+            })
+        });
+
+        if !matched {
+            return Ok(false);
+        }
+    }
+
+    if let Some(query) = &filter.search {
+        let content: &str = ev
+            .content()
+            .ok_or(Error::FieldNotFound(MissingField::Content))?;
+        let found: bool = content
+            .as_bytes()
+            .windows(query.len())
+            .any(|window| window.eq_ignore_ascii_case(query.as_bytes()));
+
+        if !found {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::prelude::*;
+    use nostr::secp256k1::schnorr::Signature;
+
+    use super::*;
+    use crate::flatbuffers::{FlatBufferBuilder, FlatBufferEncode};
+
+    /// Small deterministic PRNG (xorshift64), to make the failures reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn chance(&mut self) -> bool {
+            self.next() % 2 == 0
+        }
+
+        fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+            &items[self.below(items.len())]
+        }
+    }
+
+    const WORDS: [&str; 4] = ["nostr", "Rust", "relay", "flatbuffers"];
+    const LETTERS: [char; 4] = ['e', 'p', 't', 'K'];
+    const VALUES: [&str; 3] = ["a", "b", "c"];
+    const KINDS: [u16; 4] = [0, 1, 7, 30023];
+
+    fn random_event(rng: &mut Rng, authors: &[PublicKey]) -> Event {
+        let mut tags: Vec<Tag> = Vec::new();
+        for _ in 0..rng.below(4) {
+            let mut tag: Vec<String> = vec![rng.pick(&LETTERS).to_string()];
+            // Sometimes a tag without value, or a multi-letter tag
+            if rng.below(5) == 0 {
+                tag[0].push('x');
+            }
+            for _ in 0..rng.below(3) {
+                tag.push(rng.pick(&VALUES).to_string());
+            }
+            tags.push(Tag::parse(tag).unwrap());
+        }
+
+        let mut id: [u8; 32] = [0; 32];
+        id[..8].copy_from_slice(&rng.next().to_be_bytes());
+
+        // The signature isn't checked by the matching
+        Event::new(
+            EventId::from_byte_array(id),
+            *rng.pick(authors),
+            Timestamp::from_secs(1_700_000_000 + rng.below(10) as u64),
+            Kind::from_u16(*rng.pick(&KINDS)),
+            tags,
+            *rng.pick(&WORDS),
+            Signature::from_slice(&[0; 64]).unwrap(),
+        )
+    }
+
+    fn random_filter(rng: &mut Rng, authors: &[PublicKey], events: &[Event]) -> Filter {
+        let mut filter = Filter::new();
+
+        if rng.chance() {
+            filter = filter.kinds((0..rng.below(3)).map(|_| Kind::from_u16(*rng.pick(&KINDS))));
+        }
+        if rng.chance() {
+            filter = filter.since(Timestamp::from_secs(1_700_000_000 + rng.below(10) as u64));
+        }
+        if rng.chance() {
+            filter = filter.until(Timestamp::from_secs(1_700_000_000 + rng.below(10) as u64));
+        }
+        if rng.below(4) == 0 {
+            filter = filter.ids((0..rng.below(3)).map(|_| rng.pick(events).id));
+        }
+        if rng.chance() {
+            filter = filter.authors((0..rng.below(3)).map(|_| *rng.pick(authors)));
+        }
+        for _ in 0..rng.below(3) {
+            let tag = SingleLetterTag::from_char(*rng.pick(&LETTERS)).unwrap();
+            filter = filter.custom_tags(tag, (0..=rng.below(2)).map(|_| *rng.pick(&VALUES)));
+        }
+        if rng.below(4) == 0 {
+            filter = filter.search(rng.pick(&WORDS).to_lowercase());
+        }
+
+        filter
+    }
+
+    #[test]
+    fn test_match_filter_against_decoded_event() {
+        let mut rng = Rng(0x5eed_cafe_f00d_beef);
+        let authors: Vec<PublicKey> = [
+            "3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3",
+            "379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe",
+            "b2d670de53b27691c0c3400225b65c35a26d06093bcc41f48ffc71e0907f9d4a",
+        ]
+        .into_iter()
+        .map(|pk| PublicKey::from_hex(pk).unwrap())
+        .collect();
+        let events: Vec<Event> = (0..64).map(|_| random_event(&mut rng, &authors)).collect();
+
+        let mut fbb = FlatBufferBuilder::new();
+        let mut matches: usize = 0;
+
+        for _ in 0..2_000 {
+            let event: &Event = rng.pick(&events);
+            let filter: Filter = random_filter(&mut rng, &authors, &events);
+
+            let expected: bool = filter.match_event(event, MatchEventOptions::new());
+            let buf: &[u8] = event.encode(&mut fbb);
+            assert_eq!(
+                match_filter(buf, &filter).unwrap(),
+                expected,
+                "filter: {}, event: {}",
+                filter.as_json(),
+                event.as_json()
+            );
+
+            matches += usize::from(expected);
+        }
+
+        // Make sure both outcomes have been exercised
+        assert!(matches > 0 && matches < 2_000);
+    }
+
+    #[test]
+    fn test_match_filter_invalid_buffer() {
+        assert!(match_filter(&[0, 1, 2], &Filter::new()).is_err());
+    }
+}
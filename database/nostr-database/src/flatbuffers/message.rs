@@ -0,0 +1,498 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! FlatBuffers relay and client messages
+//!
+//! The events and the filters are embedded as nested FlatBuffers,
+//! so they can be read with the event and filter decoders (i.e., [`EventBorrow`]) without copying them.
+//!
+//! The decoded messages borrow their text fields (messages, challenges and negentropy payloads) from the buffer.
+//! The subscription IDs, the events and the filters are owned:
+//! use [`peek_relay_message_event`] to read an event without copying it.
+
+use std::borrow::Cow;
+
+use flatbuffers::{FlatBufferBuilder, Vector, WIPOffset};
+use nostr::prelude::*;
+
+use super::client_message_generated::client_message_fbs;
+use super::relay_message_generated::relay_message_fbs;
+use super::{Error, FlatBufferDecode, FlatBufferDecodeBorrowed, FlatBufferEncode, MissingField};
+
+/// Encode the item in its own buffer and embed it as a vector of bytes
+fn create_nested<'a, T>(item: &T, fbb: &mut FlatBufferBuilder<'a>) -> WIPOffset<Vector<'a, u8>>
+where
+    T: FlatBufferEncode,
+{
+    let mut nested: FlatBufferBuilder = FlatBufferBuilder::new();
+    let buf: &[u8] = item.encode(&mut nested);
+    fbb.create_vector(buf)
+}
+
+impl FlatBufferEncode for RelayMessage<'_> {
+    fn encode<'a>(&self, fbb: &'a mut FlatBufferBuilder) -> &'a [u8] {
+        use relay_message_fbs::RelayMessageType;
+
+        fbb.reset();
+
+        let mut args = relay_message_fbs::RelayMessageArgs::default();
+        let event_id: relay_message_fbs::Fixed32Bytes;
+
+        match self {
+            Self::Event {
+                subscription_id,
+                event,
+            } => {
+                args.message_type = RelayMessageType::Event;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.event = Some(create_nested(event.as_ref(), fbb));
+            }
+            Self::Ok {
+                event_id: id,
+                status,
+                message,
+            } => {
+                event_id = relay_message_fbs::Fixed32Bytes::new(id.as_bytes());
+                args.message_type = RelayMessageType::Ok;
+                args.event_id = Some(&event_id);
+                args.status = *status;
+                args.message = Some(fbb.create_string(message));
+            }
+            Self::EndOfStoredEvents(subscription_id) => {
+                args.message_type = RelayMessageType::EndOfStoredEvents;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+            }
+            Self::Notice(message) => {
+                args.message_type = RelayMessageType::Notice;
+                args.message = Some(fbb.create_string(message));
+            }
+            Self::Closed {
+                subscription_id,
+                message,
+            } => {
+                args.message_type = RelayMessageType::Closed;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.message = Some(fbb.create_string(message));
+            }
+            Self::Auth { challenge } => {
+                args.message_type = RelayMessageType::Auth;
+                args.message = Some(fbb.create_string(challenge));
+            }
+            Self::Count {
+                subscription_id,
+                count,
+            } => {
+                args.message_type = RelayMessageType::Count;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.count = *count as u64;
+            }
+            Self::NegMsg {
+                subscription_id,
+                message,
+            } => {
+                args.message_type = RelayMessageType::NegMsg;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.message = Some(fbb.create_string(message));
+            }
+            Self::NegErr {
+                subscription_id,
+                message,
+            } => {
+                args.message_type = RelayMessageType::NegErr;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.message = Some(fbb.create_string(message));
+            }
+        }
+
+        let offset = relay_message_fbs::RelayMessage::create(fbb, &args);
+
+        relay_message_fbs::finish_relay_message_buffer(fbb, offset);
+
+        fbb.finished_data()
+    }
+}
+
+impl<'a> FlatBufferDecodeBorrowed<'a> for RelayMessage<'a> {
+    fn decode(buf: &'a [u8]) -> Result<Self, Error> {
+        use relay_message_fbs::RelayMessageType;
+
+        let msg = relay_message_fbs::root_as_relay_message(buf)?;
+
+        let subscription_id = || -> Result<Cow<'a, SubscriptionId>, Error> {
+            let id: &str = msg
+                .subscription_id()
+                .ok_or(Error::FieldNotFound(MissingField::SubscriptionId))?;
+            Ok(Cow::Owned(SubscriptionId::new(id)))
+        };
+        let message = || -> Result<Cow<'a, str>, Error> {
+            let message: &'a str = msg
+                .message()
+                .ok_or(Error::FieldNotFound(MissingField::Message))?;
+            Ok(Cow::Borrowed(message))
+        };
+
+        match msg.message_type() {
+            RelayMessageType::Event => {
+                let event: Vector<u8> = msg
+                    .event()
+                    .ok_or(Error::FieldNotFound(MissingField::Event))?;
+                Ok(Self::Event {
+                    subscription_id: subscription_id()?,
+                    event: Cow::Owned(Event::decode(event.bytes())?),
+                })
+            }
+            RelayMessageType::Ok => Ok(Self::Ok {
+                event_id: EventId::from_byte_array(
+                    msg.event_id()
+                        .ok_or(Error::FieldNotFound(MissingField::EventId))?
+                        .0,
+                ),
+                status: msg.status(),
+                message: message()?,
+            }),
+            RelayMessageType::EndOfStoredEvents => Ok(Self::EndOfStoredEvents(subscription_id()?)),
+            RelayMessageType::Notice => Ok(Self::Notice(message()?)),
+            RelayMessageType::Closed => Ok(Self::Closed {
+                subscription_id: subscription_id()?,
+                message: message()?,
+            }),
+            RelayMessageType::Auth => Ok(Self::Auth {
+                challenge: message()?,
+            }),
+            RelayMessageType::Count => Ok(Self::Count {
+                subscription_id: subscription_id()?,
+                count: usize::try_from(msg.count()).unwrap_or(usize::MAX),
+            }),
+            RelayMessageType::NegMsg => Ok(Self::NegMsg {
+                subscription_id: subscription_id()?,
+                message: message()?,
+            }),
+            RelayMessageType::NegErr => Ok(Self::NegErr {
+                subscription_id: subscription_id()?,
+                message: message()?,
+            }),
+            unknown => Err(Error::UnknownMessageType(unknown.0)),
+        }
+    }
+}
+
+impl FlatBufferEncode for ClientMessage<'_> {
+    fn encode<'a>(&self, fbb: &'a mut FlatBufferBuilder) -> &'a [u8] {
+        use client_message_fbs::ClientMessageType;
+
+        fbb.reset();
+
+        let mut args = client_message_fbs::ClientMessageArgs::default();
+
+        match self {
+            Self::Event(event) => {
+                args.message_type = ClientMessageType::Event;
+                args.event = Some(create_nested(event.as_ref(), fbb));
+            }
+            Self::Req {
+                subscription_id,
+                filters,
+            } => {
+                args.message_type = ClientMessageType::Req;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.filters = Some(create_filters(filters.iter().map(|f| f.as_ref()), fbb));
+            }
+            Self::Count {
+                subscription_id,
+                filter,
+            } => {
+                args.message_type = ClientMessageType::Count;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.filters = Some(create_filters([filter.as_ref()], fbb));
+            }
+            Self::Close(subscription_id) => {
+                args.message_type = ClientMessageType::Close;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+            }
+            Self::Auth(event) => {
+                args.message_type = ClientMessageType::Auth;
+                args.event = Some(create_nested(event.as_ref(), fbb));
+            }
+            Self::NegOpen {
+                subscription_id,
+                filter,
+                id_size,
+                initial_message,
+            } => {
+                args.message_type = ClientMessageType::NegOpen;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.filters = Some(create_filters([filter.as_ref()], fbb));
+                args.id_size = *id_size;
+                args.message = Some(fbb.create_string(initial_message));
+            }
+            Self::NegMsg {
+                subscription_id,
+                message,
+            } => {
+                args.message_type = ClientMessageType::NegMsg;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+                args.message = Some(fbb.create_string(message));
+            }
+            Self::NegClose { subscription_id } => {
+                args.message_type = ClientMessageType::NegClose;
+                args.subscription_id = Some(fbb.create_string(subscription_id.as_str()));
+            }
+        }
+
+        let offset = client_message_fbs::ClientMessage::create(fbb, &args);
+
+        client_message_fbs::finish_client_message_buffer(fbb, offset);
+
+        fbb.finished_data()
+    }
+}
+
+fn create_filters<'a, 'f, I>(
+    filters: I,
+    fbb: &mut FlatBufferBuilder<'a>,
+) -> WIPOffset<Vector<'a, flatbuffers::ForwardsUOffset<client_message_fbs::FilterBuffer<'a>>>>
+where
+    I: IntoIterator<Item = &'f Filter>,
+{
+    let filters = filters
+        .into_iter()
+        .map(|filter| {
+            let args = client_message_fbs::FilterBufferArgs {
+                data: Some(create_nested(filter, fbb)),
+            };
+            client_message_fbs::FilterBuffer::create(fbb, &args)
+        })
+        .collect::<Vec<_>>();
+    fbb.create_vector(&filters)
+}
+
+impl<'a> FlatBufferDecodeBorrowed<'a> for ClientMessage<'a> {
+    fn decode(buf: &'a [u8]) -> Result<Self, Error> {
+        use client_message_fbs::ClientMessageType;
+
+        let msg = client_message_fbs::root_as_client_message(buf)?;
+
+        let subscription_id = || -> Result<Cow<'a, SubscriptionId>, Error> {
+            let id: &str = msg
+                .subscription_id()
+                .ok_or(Error::FieldNotFound(MissingField::SubscriptionId))?;
+            Ok(Cow::Owned(SubscriptionId::new(id)))
+        };
+        let message = || -> Result<Cow<'a, str>, Error> {
+            let message: &'a str = msg
+                .message()
+                .ok_or(Error::FieldNotFound(MissingField::Message))?;
+            Ok(Cow::Borrowed(message))
+        };
+        let event = || -> Result<Cow<'a, Event>, Error> {
+            let event: Vector<u8> = msg
+                .event()
+                .ok_or(Error::FieldNotFound(MissingField::Event))?;
+            Ok(Cow::Owned(Event::decode(event.bytes())?))
+        };
+        let filters = || -> Result<Vec<Cow<'a, Filter>>, Error> {
+            msg.filters()
+                .ok_or(Error::FieldNotFound(MissingField::Filters))?
+                .iter()
+                .map(|filter| {
+                    let data: Vector<u8> = filter
+                        .data()
+                        .ok_or(Error::FieldNotFound(MissingField::Filters))?;
+                    Ok(Cow::Owned(Filter::decode(data.bytes())?))
+                })
+                .collect()
+        };
+        // COUNT and NEG-OPEN carry exactly one filter
+        let filter = || -> Result<Cow<'a, Filter>, Error> {
+            filters()?
+                .into_iter()
+                .next()
+                .ok_or(Error::FieldNotFound(MissingField::Filters))
+        };
+
+        match msg.message_type() {
+            ClientMessageType::Event => Ok(Self::Event(event()?)),
+            ClientMessageType::Req => Ok(Self::Req {
+                subscription_id: subscription_id()?,
+                filters: filters()?,
+            }),
+            ClientMessageType::Count => Ok(Self::Count {
+                subscription_id: subscription_id()?,
+                filter: filter()?,
+            }),
+            ClientMessageType::Close => Ok(Self::Close(subscription_id()?)),
+            ClientMessageType::Auth => Ok(Self::Auth(event()?)),
+            ClientMessageType::NegOpen => Ok(Self::NegOpen {
+                subscription_id: subscription_id()?,
+                filter: filter()?,
+                id_size: msg.id_size(),
+                initial_message: message()?,
+            }),
+            ClientMessageType::NegMsg => Ok(Self::NegMsg {
+                subscription_id: subscription_id()?,
+                message: message()?,
+            }),
+            ClientMessageType::NegClose => Ok(Self::NegClose {
+                subscription_id: subscription_id()?,
+            }),
+            unknown => Err(Error::UnknownMessageType(unknown.0)),
+        }
+    }
+}
+
+/// Get the FlatBuffers-encoded event of a relay `EVENT` message
+///
+/// Returns `None` for the other messages.
+/// The returned buffer can be decoded with [`EventBorrow`] without copying it.
+pub fn peek_relay_message_event(buf: &[u8]) -> Result<Option<&[u8]>, Error> {
+    let msg = relay_message_fbs::root_as_relay_message(buf)?;
+    match msg.message_type() {
+        relay_message_fbs::RelayMessageType::Event => {
+            let event: Vector<u8> = msg
+                .event()
+                .ok_or(Error::FieldNotFound(MissingField::Event))?;
+            Ok(Some(event.bytes()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::secp256k1::schnorr::Signature;
+
+    use super::*;
+
+    fn event() -> Event {
+        Event::new(
+            EventId::all_zeros(),
+            PublicKey::from_hex("3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3")
+                .unwrap(),
+            Timestamp::from_secs(1_700_000_000),
+            Kind::TextNote,
+            [Tag::hashtag("nostr")],
+            "Hello",
+            Signature::from_slice(&[0; 64]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_relay_message_roundtrip() {
+        let event = event();
+        let id = SubscriptionId::new("sub");
+        let messages = vec![
+            RelayMessage::event(id.clone(), event.clone()),
+            RelayMessage::ok(event.id, true, ""),
+            RelayMessage::ok(event.id, false, "blocked: spam"),
+            RelayMessage::eose(id.clone()),
+            RelayMessage::notice("hello"),
+            RelayMessage::closed(id.clone(), "auth-required: please authenticate"),
+            RelayMessage::auth("challenge"),
+            RelayMessage::count(id.clone(), 42),
+            RelayMessage::NegMsg {
+                subscription_id: Cow::Owned(id.clone()),
+                message: Cow::Borrowed("6100"),
+            },
+            RelayMessage::NegErr {
+                subscription_id: Cow::Owned(id),
+                message: Cow::Borrowed("closed"),
+            },
+        ];
+
+        let mut fbb = FlatBufferBuilder::new();
+        for msg in messages {
+            let bytes = msg.encode(&mut fbb);
+            assert_eq!(RelayMessage::decode(bytes).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn test_relay_message_borrows_text() {
+        let mut fbb = FlatBufferBuilder::new();
+        let bytes = RelayMessage::notice("hello").encode(&mut fbb);
+
+        match RelayMessage::decode(bytes).unwrap() {
+            RelayMessage::Notice(Cow::Borrowed(message)) => {
+                assert_eq!(message, "hello");
+                assert!(bytes.as_ptr_range().contains(&message.as_ptr()));
+            }
+            msg => panic!("Unexpected message: {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn test_client_message_roundtrip() {
+        let event = event();
+        let id = SubscriptionId::new("sub");
+        let filter = Filter::new()
+            .kind(Kind::TextNote)
+            .author(event.pubkey)
+            .hashtag("nostr")
+            .limit(10);
+        let messages = vec![
+            ClientMessage::event(event.clone()),
+            ClientMessage::req(id.clone(), vec![filter.clone(), Filter::new().id(event.id)]),
+            ClientMessage::req(id.clone(), Vec::new()),
+            ClientMessage::count(id.clone(), filter.clone()),
+            ClientMessage::close(id.clone()),
+            ClientMessage::auth(event),
+            ClientMessage::NegOpen {
+                subscription_id: Cow::Owned(id.clone()),
+                filter: Cow::Owned(filter.clone()),
+                id_size: Some(16),
+                initial_message: Cow::Borrowed("6100"),
+            },
+            ClientMessage::NegOpen {
+                subscription_id: Cow::Owned(id.clone()),
+                filter: Cow::Owned(filter),
+                id_size: None,
+                initial_message: Cow::Borrowed("6100"),
+            },
+            ClientMessage::NegMsg {
+                subscription_id: Cow::Owned(id.clone()),
+                message: Cow::Borrowed("6100"),
+            },
+            ClientMessage::NegClose {
+                subscription_id: Cow::Owned(id),
+            },
+        ];
+
+        let mut fbb = FlatBufferBuilder::new();
+        for msg in messages {
+            let bytes = msg.encode(&mut fbb);
+            assert_eq!(ClientMessage::decode(bytes).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn test_peek_relay_message_event() {
+        let event = event();
+
+        let mut fbb = FlatBufferBuilder::new();
+
+        let bytes = RelayMessage::event(SubscriptionId::new("sub"), event.clone()).encode(&mut fbb);
+        let nested = peek_relay_message_event(bytes).unwrap().unwrap();
+        let borrowed = EventBorrow::decode(nested).unwrap();
+        assert_eq!(borrowed.into_owned(), event);
+
+        let bytes = RelayMessage::notice("hello").encode(&mut fbb);
+        assert!(peek_relay_message_event(bytes).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unknown_message_type() {
+        let mut fbb = FlatBufferBuilder::new();
+        let args = relay_message_fbs::RelayMessageArgs {
+            message_type: relay_message_fbs::RelayMessageType(200),
+            ..Default::default()
+        };
+        let offset = relay_message_fbs::RelayMessage::create(&mut fbb, &args);
+        relay_message_fbs::finish_relay_message_buffer(&mut fbb, offset);
+
+        assert!(matches!(
+            RelayMessage::decode(fbb.finished_data()),
+            Err(Error::UnknownMessageType(200))
+        ));
+    }
+}
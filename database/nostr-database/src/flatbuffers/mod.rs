@@ -5,14 +5,26 @@
 //! Nostr Database Flatbuffers
 
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::str::FromStr;
 
-use flatbuffers::InvalidFlatbuffer;
 pub use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector};
+use flatbuffers::{InvalidFlatbuffer, WIPOffset};
 use nostr::prelude::*;
 use nostr::secp256k1;
 use nostr::secp256k1::schnorr::Signature;
 
+mod batch;
+#[allow(
+    unused_imports,
+    dead_code,
+    clippy::all,
+    unsafe_code,
+    missing_docs,
+    unsafe_op_in_unsafe_fn
+)]
+mod client_message_generated;
 #[allow(
     unused_imports,
     dead_code,
@@ -22,8 +34,38 @@ use nostr::secp256k1::schnorr::Signature;
     unsafe_op_in_unsafe_fn
 )]
 mod event_generated;
+#[allow(
+    unused_imports,
+    dead_code,
+    clippy::all,
+    unsafe_code,
+    missing_docs,
+    unsafe_op_in_unsafe_fn
+)]
+mod filter_generated;
+mod matching;
+mod message;
+mod peek;
+#[allow(
+    unused_imports,
+    dead_code,
+    clippy::all,
+    unsafe_code,
+    missing_docs,
+    unsafe_op_in_unsafe_fn
+)]
+mod relay_message_generated;
 
+pub use self::batch::{
+    FlatBufferBatch, FlatBufferDecodeBatch, FlatBufferEncodeBatch, decode_events, encode_events,
+};
+pub use self::client_message_generated::client_message_fbs;
 pub use self::event_generated::event_fbs;
+pub use self::filter_generated::filter_fbs;
+pub use self::matching::match_filter;
+pub use self::message::peek_relay_message_event;
+pub use self::peek::{EventMetadata, peek_content_len, peek_created_at, peek_id, peek_kind};
+pub use self::relay_message_generated::relay_message_fbs;
 
 /// Missing field
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -38,6 +80,16 @@ pub enum MissingField {
     Content,
     /// Signature
     Sig,
+    /// Subscription ID
+    SubscriptionId,
+    /// Event
+    Event,
+    /// Event ID
+    EventId,
+    /// Message
+    Message,
+    /// Filters
+    Filters,
 }
 
 impl fmt::Display for MissingField {
@@ -48,6 +100,11 @@ impl fmt::Display for MissingField {
             Self::Tags => write!(f, "tags"),
             Self::Content => write!(f, "content"),
             Self::Sig => write!(f, "sig"),
+            Self::SubscriptionId => write!(f, "subscription_id"),
+            Self::Event => write!(f, "event"),
+            Self::EventId => write!(f, "event_id"),
+            Self::Message => write!(f, "message"),
+            Self::Filters => write!(f, "filters"),
         }
     }
 }
@@ -61,8 +118,14 @@ pub enum Error {
     Tag(tag::Error),
     /// Secp256k1 error
     Secp256k1(secp256k1::Error),
+    /// Single letter tag error
+    SingleLetterTag(SingleLetterTagError),
     /// Field not found
     FieldNotFound(MissingField),
+    /// The kind doesn't fit in 16 bits
+    KindOutOfRange(u64),
+    /// Unknown relay or client message type
+    UnknownMessageType(u8),
 }
 
 impl std::error::Error for Error {}
@@ -73,7 +136,10 @@ impl fmt::Display for Error {
             Self::FlatBuffer(e) => write!(f, "{e}"),
             Self::Tag(e) => write!(f, "{e}"),
             Self::Secp256k1(e) => write!(f, "{e}"),
+            Self::SingleLetterTag(e) => write!(f, "{e}"),
             Self::FieldNotFound(field) => write!(f, "'{field}' field not found"),
+            Self::KindOutOfRange(kind) => write!(f, "kind out of range: {kind}"),
+            Self::UnknownMessageType(t) => write!(f, "unknown message type: {t}"),
         }
     }
 }
@@ -96,6 +162,12 @@ impl From<secp256k1::Error> for Error {
     }
 }
 
+impl From<SingleLetterTagError> for Error {
+    fn from(e: SingleLetterTagError) -> Self {
+        Self::SingleLetterTag(e)
+    }
+}
+
 /// FlatBuffer Encode trait
 pub trait FlatBufferEncode {
     /// FlatBuffer encode
@@ -114,39 +186,17 @@ pub trait FlatBufferDecodeBorrowed<'a>: Sized {
     fn decode(buf: &'a [u8]) -> Result<Self, Error>;
 }
 
+/// FlatBuffer Decode metadata trait
+pub trait FlatBufferDecodeMetadata {
+    /// FlatBuffer decode the metadata only, without allocating the tags and the content
+    fn decode_metadata(buf: &[u8]) -> Result<EventMetadata, Error>;
+}
+
 impl FlatBufferEncode for Event {
     fn encode<'a>(&self, fbb: &'a mut FlatBufferBuilder) -> &'a [u8] {
         fbb.reset();
 
-        let id = event_fbs::Fixed32Bytes::new(self.id.as_bytes());
-        let pubkey = event_fbs::Fixed32Bytes::new(self.pubkey.as_bytes());
-        let sig = event_fbs::Fixed64Bytes::new(self.sig.as_ref());
-        let tags = self
-            .tags
-            .iter()
-            .map(|t| {
-                let tags = t
-                    .as_slice()
-                    .iter()
-                    .map(|t| fbb.create_string(t))
-                    .collect::<Vec<_>>();
-                let args = event_fbs::StringVectorArgs {
-                    data: Some(fbb.create_vector(&tags)),
-                };
-                event_fbs::StringVector::create(fbb, &args)
-            })
-            .collect::<Vec<_>>();
-        let args = event_fbs::EventArgs {
-            id: Some(&id),
-            pubkey: Some(&pubkey),
-            created_at: self.created_at.as_secs(),
-            kind: self.kind.as_u16() as u64,
-            tags: Some(fbb.create_vector(&tags)),
-            content: Some(fbb.create_string(&self.content)),
-            sig: Some(&sig),
-        };
-
-        let offset = event_fbs::Event::create(fbb, &args);
+        let offset = create_event(self, fbb);
 
         event_fbs::finish_event_buffer(fbb, offset);
 
@@ -154,34 +204,81 @@ impl FlatBufferEncode for Event {
     }
 }
 
+fn create_event<'a>(
+    event: &Event,
+    fbb: &mut FlatBufferBuilder<'a>,
+) -> WIPOffset<event_fbs::Event<'a>> {
+    let id = event_fbs::Fixed32Bytes::new(event.id.as_bytes());
+    let pubkey = event_fbs::Fixed32Bytes::new(event.pubkey.as_bytes());
+    let sig = event_fbs::Fixed64Bytes::new(event.sig.as_ref());
+    let tags = event
+        .tags
+        .iter()
+        .map(|t| {
+            let tags = t
+                .as_slice()
+                .iter()
+                .map(|t| fbb.create_string(t))
+                .collect::<Vec<_>>();
+            let args = event_fbs::StringVectorArgs {
+                data: Some(fbb.create_vector(&tags)),
+            };
+            event_fbs::StringVector::create(fbb, &args)
+        })
+        .collect::<Vec<_>>();
+    let args = event_fbs::EventArgs {
+        id: Some(&id),
+        pubkey: Some(&pubkey),
+        created_at: event.created_at.as_secs(),
+        kind: event.kind.as_u16() as u64,
+        tags: Some(fbb.create_vector(&tags)),
+        content: Some(fbb.create_string(&event.content)),
+        sig: Some(&sig),
+    };
+
+    event_fbs::Event::create(fbb, &args)
+}
+
 impl FlatBufferDecode for Event {
+    #[inline]
     fn decode(buf: &[u8]) -> Result<Self, Error> {
         let ev = event_fbs::root_as_event(buf)?;
-        let tags = ev
-            .tags()
-            .ok_or(Error::FieldNotFound(MissingField::Tags))?
-            .into_iter()
-            .filter_map(|tag| tag.data().map(Tag::parse))
-            .collect::<Result<Vec<Tag>, _>>()?;
-
-        Ok(Self::new(
-            EventId::from_byte_array(ev.id().ok_or(Error::FieldNotFound(MissingField::Id))?.0),
-            PublicKey::from_byte_array(
-                ev.pubkey()
-                    .ok_or(Error::FieldNotFound(MissingField::Pubkey))?
-                    .0,
-            ),
-            Timestamp::from(ev.created_at()),
-            Kind::from(ev.kind() as u16),
-            tags,
-            ev.content()
-                .ok_or(Error::FieldNotFound(MissingField::Content))?
-                .to_owned(),
-            Signature::from_slice(&ev.sig().ok_or(Error::FieldNotFound(MissingField::Sig))?.0)?,
-        ))
+        decode_event(ev)
     }
 }
 
+#[inline]
+fn decode_kind(ev: &event_fbs::Event<'_>) -> Result<u16, Error> {
+    let kind: u64 = ev.kind();
+    u16::try_from(kind).map_err(|_| Error::KindOutOfRange(kind))
+}
+
+fn decode_event(ev: event_fbs::Event<'_>) -> Result<Event, Error> {
+    let tags = ev
+        .tags()
+        .ok_or(Error::FieldNotFound(MissingField::Tags))?
+        .into_iter()
+        // A tag without data is parsed as empty, instead of being silently dropped
+        .map(|tag| Tag::parse(tag.data().into_iter().flatten()))
+        .collect::<Result<Vec<Tag>, _>>()?;
+
+    Ok(Event::new(
+        EventId::from_byte_array(ev.id().ok_or(Error::FieldNotFound(MissingField::Id))?.0),
+        PublicKey::from_byte_array(
+            ev.pubkey()
+                .ok_or(Error::FieldNotFound(MissingField::Pubkey))?
+                .0,
+        ),
+        Timestamp::from(ev.created_at()),
+        Kind::from(decode_kind(&ev)?),
+        tags,
+        ev.content()
+            .ok_or(Error::FieldNotFound(MissingField::Content))?
+            .to_owned(),
+        Signature::from_slice(&ev.sig().ok_or(Error::FieldNotFound(MissingField::Sig))?.0)?,
+    ))
+}
+
 impl<'a> FlatBufferDecodeBorrowed<'a> for EventBorrow<'a> {
     fn decode(buf: &'a [u8]) -> Result<Self, Error> {
         let ev = event_fbs::root_as_event(buf)?;
@@ -189,8 +286,15 @@ impl<'a> FlatBufferDecodeBorrowed<'a> for EventBorrow<'a> {
         let fb_tags = ev.tags().ok_or(Error::FieldNotFound(MissingField::Tags))?;
         let mut tags = Vec::with_capacity(fb_tags.len());
 
-        for tag in fb_tags.iter().filter_map(|t| t.data()) {
-            tags.push(CowTag::parse(tag.into_iter().map(Cow::Borrowed).collect())?);
+        for tag in fb_tags.iter() {
+            // A tag without data is parsed as empty, instead of being silently dropped
+            let tag = tag
+                .data()
+                .into_iter()
+                .flatten()
+                .map(Cow::Borrowed)
+                .collect();
+            tags.push(CowTag::parse(tag)?);
         }
 
         Ok(Self {
@@ -200,7 +304,7 @@ impl<'a> FlatBufferDecodeBorrowed<'a> for EventBorrow<'a> {
                 .ok_or(Error::FieldNotFound(MissingField::Pubkey))?
                 .0,
             created_at: Timestamp::from_secs(ev.created_at()),
-            kind: ev.kind() as u16, // TODO: should use try_into
+            kind: decode_kind(&ev)?,
             tags,
             content: ev
                 .content()
@@ -210,6 +314,273 @@ impl<'a> FlatBufferDecodeBorrowed<'a> for EventBorrow<'a> {
     }
 }
 
+impl FlatBufferEncode for Filter {
+    fn encode<'a>(&self, fbb: &'a mut FlatBufferBuilder) -> &'a [u8] {
+        fbb.reset();
+
+        let ids = self.ids.as_ref().map(|ids| {
+            let ids = ids
+                .iter()
+                .map(|id| filter_fbs::Fixed32Bytes::new(id.as_bytes()))
+                .collect::<Vec<_>>();
+            fbb.create_vector(&ids)
+        });
+        let authors = self.authors.as_ref().map(|authors| {
+            let authors = authors
+                .iter()
+                .map(|pk| filter_fbs::Fixed32Bytes::new(pk.as_bytes()))
+                .collect::<Vec<_>>();
+            fbb.create_vector(&authors)
+        });
+        let kinds = self.kinds.as_ref().map(|kinds| {
+            let kinds = kinds.iter().map(|k| k.as_u16()).collect::<Vec<_>>();
+            fbb.create_vector(&kinds)
+        });
+        let search = self.search.as_ref().map(|s| fbb.create_string(s));
+        let generic_tags = self
+            .generic_tags
+            .iter()
+            .map(|(key, values)| {
+                let values = values
+                    .iter()
+                    .map(|v| fbb.create_string(v))
+                    .collect::<Vec<_>>();
+                let args = filter_fbs::GenericTagArgs {
+                    key: Some(fbb.create_string(&key.to_string())),
+                    values: Some(fbb.create_vector(&values)),
+                };
+                filter_fbs::GenericTag::create(fbb, &args)
+            })
+            .collect::<Vec<_>>();
+        let args = filter_fbs::FilterArgs {
+            ids,
+            authors,
+            kinds,
+            search,
+            since: self.since.map(|t| t.as_secs()),
+            until: self.until.map(|t| t.as_secs()),
+            limit: self.limit.map(|l| l as u64),
+            generic_tags: Some(fbb.create_vector(&generic_tags)),
+        };
+
+        let offset = filter_fbs::Filter::create(fbb, &args);
+
+        filter_fbs::finish_filter_buffer(fbb, offset);
+
+        fbb.finished_data()
+    }
+}
+
+impl FlatBufferDecode for Filter {
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let f = filter_fbs::root_as_filter(buf)?;
+
+        let mut generic_tags: BTreeMap<SingleLetterTag, BTreeSet<String>> = BTreeMap::new();
+        for tag in f.generic_tags().into_iter().flatten() {
+            let key: SingleLetterTag = SingleLetterTag::from_str(tag.key())?;
+            let values: BTreeSet<String> = tag
+                .values()
+                .into_iter()
+                .flatten()
+                .map(|v| v.to_owned())
+                .collect();
+            generic_tags.insert(key, values);
+        }
+
+        Ok(Self {
+            ids: f.ids().map(|ids| {
+                ids.iter()
+                    .map(|id| EventId::from_byte_array(id.0))
+                    .collect()
+            }),
+            authors: f.authors().map(|authors| {
+                authors
+                    .iter()
+                    .map(|pk| PublicKey::from_byte_array(pk.0))
+                    .collect()
+            }),
+            kinds: f
+                .kinds()
+                .map(|kinds| kinds.iter().map(Kind::from_u16).collect()),
+            search: f.search().map(|s| s.to_owned()),
+            since: f.since().map(Timestamp::from_secs),
+            until: f.until().map(Timestamp::from_secs),
+            limit: f.limit().map(|l| l as usize),
+            generic_tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_with_tags(tags: Vec<Tag>) -> Event {
+        Event::new(
+            EventId::all_zeros(),
+            PublicKey::from_hex("3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3")
+                .unwrap(),
+            Timestamp::from_secs(1_700_000_000),
+            Kind::TextNote,
+            tags,
+            "test",
+            Signature::from_slice(&[0; 64]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_event_roundtrip_keeps_all_tags() {
+        let event = event_with_tags(vec![
+            Tag::hashtag("nostr"),
+            Tag::parse(["alt"]).unwrap(),
+            Tag::parse(["client", ""]).unwrap(),
+            Tag::identifier(""),
+            Tag::hashtag("rust"),
+        ]);
+
+        let mut fbb = FlatBufferBuilder::new();
+        let bytes = event.encode(&mut fbb);
+        assert_eq!(Event::decode(bytes).unwrap(), event);
+
+        let borrow = EventBorrow::decode(bytes).unwrap();
+        assert_eq!(borrow.into_owned(), event);
+    }
+
+    #[test]
+    fn test_event_decode_tag_without_data() {
+        let event = event_with_tags(vec![Tag::hashtag("nostr"), Tag::hashtag("rust")]);
+
+        // Encode the event with a tag without data in the middle
+        let mut fbb = FlatBufferBuilder::new();
+        let id = event_fbs::Fixed32Bytes::new(event.id.as_bytes());
+        let pubkey = event_fbs::Fixed32Bytes::new(event.pubkey.as_bytes());
+        let sig = event_fbs::Fixed64Bytes::new(event.sig.as_ref());
+        let mut tags = Vec::new();
+        for (i, tag) in event.tags.iter().enumerate() {
+            if i == 1 {
+                let args = event_fbs::StringVectorArgs { data: None };
+                tags.push(event_fbs::StringVector::create(&mut fbb, &args));
+            }
+
+            let data = tag
+                .as_slice()
+                .iter()
+                .map(|t| fbb.create_string(t))
+                .collect::<Vec<_>>();
+            let args = event_fbs::StringVectorArgs {
+                data: Some(fbb.create_vector(&data)),
+            };
+            tags.push(event_fbs::StringVector::create(&mut fbb, &args));
+        }
+        let args = event_fbs::EventArgs {
+            id: Some(&id),
+            pubkey: Some(&pubkey),
+            created_at: event.created_at.as_secs(),
+            kind: event.kind.as_u16() as u64,
+            tags: Some(fbb.create_vector(&tags)),
+            content: Some(fbb.create_string(&event.content)),
+            sig: Some(&sig),
+        };
+        let offset = event_fbs::Event::create(&mut fbb, &args);
+        event_fbs::finish_event_buffer(&mut fbb, offset);
+        let bytes = fbb.finished_data();
+
+        // Never decoded without the tag
+        assert!(matches!(
+            Event::decode(bytes),
+            Err(Error::Tag(tag::Error::EmptyTag))
+        ));
+        assert!(matches!(
+            EventBorrow::decode(bytes),
+            Err(Error::Tag(tag::Error::EmptyTag))
+        ));
+    }
+
+    #[test]
+    fn test_event_decode_kind_out_of_range() {
+        let event = event_with_tags(Vec::new());
+
+        let mut fbb = FlatBufferBuilder::new();
+        let id = event_fbs::Fixed32Bytes::new(event.id.as_bytes());
+        let pubkey = event_fbs::Fixed32Bytes::new(event.pubkey.as_bytes());
+        let sig = event_fbs::Fixed64Bytes::new(event.sig.as_ref());
+        let args = event_fbs::EventArgs {
+            id: Some(&id),
+            pubkey: Some(&pubkey),
+            created_at: event.created_at.as_secs(),
+            kind: u16::MAX as u64 + 1,
+            tags: Some(fbb.create_vector::<ForwardsUOffset<event_fbs::StringVector>>(&[])),
+            content: Some(fbb.create_string(&event.content)),
+            sig: Some(&sig),
+        };
+        let offset = event_fbs::Event::create(&mut fbb, &args);
+        event_fbs::finish_event_buffer(&mut fbb, offset);
+        let bytes = fbb.finished_data();
+
+        // Not truncated to kind 0
+        assert!(matches!(
+            Event::decode(bytes),
+            Err(Error::KindOutOfRange(65536))
+        ));
+        assert!(matches!(
+            EventBorrow::decode(bytes),
+            Err(Error::KindOutOfRange(65536))
+        ));
+        assert!(matches!(
+            match_filter(bytes, &Filter::new().kind(Kind::Metadata)),
+            Err(Error::KindOutOfRange(65536))
+        ));
+    }
+
+    #[test]
+    fn test_filter_roundtrip() {
+        let public_key =
+            PublicKey::from_hex("3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3")
+                .unwrap();
+        let coordinate = Coordinate::new(Kind::LongFormTextNote, public_key).identifier("id");
+
+        let filter = Filter::new()
+            .id(EventId::all_zeros())
+            .author(public_key)
+            .kinds([Kind::TextNote, Kind::Custom(65535)])
+            .search("nostr")
+            .since(Timestamp::from_secs(1_700_000_000))
+            .until(Timestamp::from_secs(1_800_000_000))
+            .limit(42)
+            .event(EventId::all_zeros())
+            .pubkey(public_key)
+            .coordinate(&coordinate)
+            .hashtags(["rust", "nostr"])
+            .custom_tag(SingleLetterTag::uppercase(Alphabet::K), "1");
+
+        let mut fbb = FlatBufferBuilder::new();
+        let bytes = filter.encode(&mut fbb);
+        assert_eq!(Filter::decode(bytes).unwrap(), filter);
+    }
+
+    #[test]
+    fn test_filter_roundtrip_empty_fields() {
+        let mut fbb = FlatBufferBuilder::new();
+
+        // Default filter
+        let filter = Filter::new();
+        let bytes = filter.encode(&mut fbb);
+        assert_eq!(Filter::decode(bytes).unwrap(), filter);
+
+        // Empty, but not missing, fields
+        let filter = Filter {
+            ids: Some(BTreeSet::new()),
+            authors: Some(BTreeSet::new()),
+            kinds: Some(BTreeSet::new()),
+            search: Some(String::new()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        let bytes = filter.encode(&mut fbb);
+        assert_eq!(Filter::decode(bytes).unwrap(), filter);
+    }
+}
+
 #[cfg(bench)]
 mod benches {
     use super::*;
@@ -0,0 +1,156 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! FlatBuffers single-field accessors
+//!
+//! Read single fields or the metadata of a FlatBuffers-encoded event, without decoding the tags, the content or the signature.
+
+use nostr::{Event, Kind, Timestamp};
+
+use super::{Error, FlatBufferDecodeMetadata, MissingField, decode_kind, event_fbs};
+
+/// Event metadata
+///
+/// The fields needed to pre-filter an event, before committing to the full decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventMetadata {
+    /// Event ID
+    pub id: [u8; 32],
+    /// Author
+    pub pubkey: [u8; 32],
+    /// Creation timestamp
+    pub created_at: Timestamp,
+    /// Kind
+    pub kind: u16,
+}
+
+impl FlatBufferDecodeMetadata for Event {
+    fn decode_metadata(buf: &[u8]) -> Result<EventMetadata, Error> {
+        let ev = event_fbs::root_as_event(buf)?;
+        Ok(EventMetadata {
+            id: ev.id().ok_or(Error::FieldNotFound(MissingField::Id))?.0,
+            pubkey: ev
+                .pubkey()
+                .ok_or(Error::FieldNotFound(MissingField::Pubkey))?
+                .0,
+            created_at: Timestamp::from_secs(ev.created_at()),
+            kind: decode_kind(&ev)?,
+        })
+    }
+}
+
+/// Get the event ID
+pub fn peek_id(buf: &[u8]) -> Result<&[u8; 32], Error> {
+    let ev = event_fbs::root_as_event(buf)?;
+    let id = ev.id().ok_or(Error::FieldNotFound(MissingField::Id))?;
+    Ok(&id.0)
+}
+
+/// Get the event creation timestamp
+pub fn peek_created_at(buf: &[u8]) -> Result<Timestamp, Error> {
+    let ev = event_fbs::root_as_event(buf)?;
+    Ok(Timestamp::from_secs(ev.created_at()))
+}
+
+/// Get the event kind
+pub fn peek_kind(buf: &[u8]) -> Result<Kind, Error> {
+    let ev = event_fbs::root_as_event(buf)?;
+    Ok(Kind::from_u16(decode_kind(&ev)?))
+}
+
+/// Get the length of the event content, in bytes
+pub fn peek_content_len(buf: &[u8]) -> Result<usize, Error> {
+    let ev = event_fbs::root_as_event(buf)?;
+    let content: &str = ev
+        .content()
+        .ok_or(Error::FieldNotFound(MissingField::Content))?;
+    Ok(content.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::prelude::*;
+
+    use super::*;
+    use crate::flatbuffers::{FlatBufferBuilder, FlatBufferEncode};
+
+    pub(super) const JSON: &str = r#"{"content":"Hello, world!","created_at":1716508454,"id":"3e9e9c2fbf263590860a9c60a7de6b0d166230a5a15aa8dcdb70f537cec9807a","kind":7,"pubkey":"3bbddb5c7233ad993b41cb639e63122120f391b8580a9b83aae33c648230e0a3","sig":"3f2ba6d713e4851500b81de2d2ef44b72f1eff061898bf8488e74f7e4ed141b0dadab4c3a9c6b237f3a6db83171bd41eafd7ab973f6fb067a4305e95abeadeee","tags":[["p","04c915daefee38317fa734444acee390a8269fe5810b2241e5e6dd343dfbecc9"]]}"#;
+
+    #[test]
+    fn test_peek() {
+        let event = Event::from_json(JSON).unwrap();
+
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: &[u8] = event.encode(&mut fbb);
+
+        assert_eq!(peek_id(buf).unwrap(), event.id.as_bytes());
+        assert_eq!(peek_created_at(buf).unwrap(), event.created_at);
+        assert_eq!(peek_kind(buf).unwrap(), event.kind);
+        assert_eq!(peek_content_len(buf).unwrap(), event.content.len());
+
+        assert!(peek_id(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_decode_metadata() {
+        let event = Event::from_json(JSON).unwrap();
+
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: &[u8] = event.encode(&mut fbb);
+
+        let metadata: EventMetadata = Event::decode_metadata(buf).unwrap();
+        assert_eq!(&metadata.id, event.id.as_bytes());
+        assert_eq!(&metadata.pubkey, event.pubkey.as_bytes());
+        assert_eq!(metadata.created_at, event.created_at);
+        assert_eq!(metadata.kind, event.kind.as_u16());
+
+        assert!(Event::decode_metadata(&[0, 1, 2]).is_err());
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use nostr::prelude::*;
+
+    use super::tests::JSON;
+    use super::*;
+    use crate::flatbuffers::{FlatBufferBuilder, FlatBufferDecode, FlatBufferEncode};
+    use crate::test::{Bencher, black_box};
+
+    #[bench]
+    pub fn bench_peek_id_created_at_kind(bh: &mut Bencher) {
+        let event = Event::from_json(JSON).unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: &[u8] = event.encode(&mut fbb);
+
+        bh.iter(|| {
+            black_box(peek_id(buf)).unwrap();
+            black_box(peek_created_at(buf)).unwrap();
+            black_box(peek_kind(buf)).unwrap();
+        });
+    }
+
+    #[bench]
+    pub fn bench_decode_metadata(bh: &mut Bencher) {
+        let event = Event::from_json(JSON).unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: &[u8] = event.encode(&mut fbb);
+
+        bh.iter(|| {
+            black_box(Event::decode_metadata(buf)).unwrap();
+        });
+    }
+
+    #[bench]
+    pub fn bench_decode_id_created_at_kind(bh: &mut Bencher) {
+        let event = Event::from_json(JSON).unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        let buf: &[u8] = event.encode(&mut fbb);
+
+        bh.iter(|| {
+            let event = Event::decode(buf).unwrap();
+            black_box((event.id, event.created_at, event.kind));
+        });
+    }
+}
@@ -0,0 +1,572 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+use self::flatbuffers::{EndianScalar, Follow};
+
+#[allow(unused_imports, dead_code)]
+pub mod relay_message_fbs {
+
+    use core::cmp::Ordering;
+    use core::mem;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MIN_RELAY_MESSAGE_TYPE: u8 = 0;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MAX_RELAY_MESSAGE_TYPE: u8 = 8;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    #[allow(non_camel_case_types)]
+    pub const ENUM_VALUES_RELAY_MESSAGE_TYPE: [RelayMessageType; 9] = [
+        RelayMessageType::Event,
+        RelayMessageType::Ok,
+        RelayMessageType::EndOfStoredEvents,
+        RelayMessageType::Notice,
+        RelayMessageType::Closed,
+        RelayMessageType::Auth,
+        RelayMessageType::Count,
+        RelayMessageType::NegMsg,
+        RelayMessageType::NegErr,
+    ];
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    #[repr(transparent)]
+    pub struct RelayMessageType(pub u8);
+    #[allow(non_upper_case_globals)]
+    impl RelayMessageType {
+        pub const Event: Self = Self(0);
+        pub const Ok: Self = Self(1);
+        pub const EndOfStoredEvents: Self = Self(2);
+        pub const Notice: Self = Self(3);
+        pub const Closed: Self = Self(4);
+        pub const Auth: Self = Self(5);
+        pub const Count: Self = Self(6);
+        pub const NegMsg: Self = Self(7);
+        pub const NegErr: Self = Self(8);
+
+        pub const ENUM_MIN: u8 = 0;
+        pub const ENUM_MAX: u8 = 8;
+        pub const ENUM_VALUES: &'static [Self] = &[
+            Self::Event,
+            Self::Ok,
+            Self::EndOfStoredEvents,
+            Self::Notice,
+            Self::Closed,
+            Self::Auth,
+            Self::Count,
+            Self::NegMsg,
+            Self::NegErr,
+        ];
+        /// Returns the variant's name or "" if unknown.
+        pub fn variant_name(self) -> Option<&'static str> {
+            match self {
+                Self::Event => Some("Event"),
+                Self::Ok => Some("Ok"),
+                Self::EndOfStoredEvents => Some("EndOfStoredEvents"),
+                Self::Notice => Some("Notice"),
+                Self::Closed => Some("Closed"),
+                Self::Auth => Some("Auth"),
+                Self::Count => Some("Count"),
+                Self::NegMsg => Some("NegMsg"),
+                Self::NegErr => Some("NegErr"),
+                _ => None,
+            }
+        }
+    }
+    impl core::fmt::Debug for RelayMessageType {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            if let Some(name) = self.variant_name() {
+                f.write_str(name)
+            } else {
+                f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+            }
+        }
+    }
+    impl<'a> flatbuffers::Follow<'a> for RelayMessageType {
+        type Inner = Self;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            let b = flatbuffers::read_scalar_at::<u8>(buf, loc);
+            Self(b)
+        }
+    }
+
+    impl flatbuffers::Push for RelayMessageType {
+        type Output = RelayMessageType;
+        #[inline]
+        unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+            flatbuffers::emplace_scalar::<u8>(dst, self.0);
+        }
+    }
+
+    impl flatbuffers::EndianScalar for RelayMessageType {
+        type Scalar = u8;
+        #[inline]
+        fn to_little_endian(self) -> u8 {
+            self.0.to_le()
+        }
+        #[inline]
+        #[allow(clippy::wrong_self_convention)]
+        fn from_little_endian(v: u8) -> Self {
+            let b = u8::from_le(v);
+            Self(b)
+        }
+    }
+
+    impl<'a> flatbuffers::Verifiable for RelayMessageType {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            u8::run_verifier(v, pos)
+        }
+    }
+
+    impl flatbuffers::SimpleToVerifyInSlice for RelayMessageType {}
+    // struct Fixed32Bytes, aligned to 1
+    #[repr(transparent)]
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Fixed32Bytes(pub [u8; 32]);
+    impl Default for Fixed32Bytes {
+        fn default() -> Self {
+            Self([0; 32])
+        }
+    }
+    impl core::fmt::Debug for Fixed32Bytes {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.debug_struct("Fixed32Bytes")
+                .field("val", &self.val())
+                .finish()
+        }
+    }
+
+    impl flatbuffers::SimpleToVerifyInSlice for Fixed32Bytes {}
+    impl<'a> flatbuffers::Follow<'a> for Fixed32Bytes {
+        type Inner = &'a Fixed32Bytes;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            <&'a Fixed32Bytes>::follow(buf, loc)
+        }
+    }
+    impl<'a> flatbuffers::Follow<'a> for &'a Fixed32Bytes {
+        type Inner = &'a Fixed32Bytes;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            flatbuffers::follow_cast_ref::<Fixed32Bytes>(buf, loc)
+        }
+    }
+    impl<'b> flatbuffers::Push for Fixed32Bytes {
+        type Output = Fixed32Bytes;
+        #[inline]
+        unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+            let src = ::core::slice::from_raw_parts(
+                self as *const Fixed32Bytes as *const u8,
+                <Self as flatbuffers::Push>::size(),
+            );
+            dst.copy_from_slice(src);
+        }
+        #[inline]
+        fn alignment() -> flatbuffers::PushAlignment {
+            flatbuffers::PushAlignment::new(1)
+        }
+    }
+
+    impl<'a> flatbuffers::Verifiable for Fixed32Bytes {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.in_buffer::<Self>(pos)
+        }
+    }
+
+    impl<'a> Fixed32Bytes {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(val: &[u8; 32]) -> Self {
+            let mut s = Self([0; 32]);
+            s.set_val(val);
+            s
+        }
+
+        pub fn val(&'a self) -> flatbuffers::Array<'a, u8, 32> {
+            // Safety:
+            // Created from a valid Table for this object
+            // Which contains a valid array in this slot
+            unsafe { flatbuffers::Array::follow(&self.0, 0) }
+        }
+
+        pub fn set_val(&mut self, items: &[u8; 32]) {
+            // Safety:
+            // Created from a valid Table for this object
+            // Which contains a valid array in this slot
+            unsafe { flatbuffers::emplace_scalar_array(&mut self.0, 0, items) };
+        }
+    }
+
+    pub enum RelayMessageOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct RelayMessage<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for RelayMessage<'a> {
+        type Inner = RelayMessage<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> RelayMessage<'a> {
+        pub const VT_MESSAGE_TYPE: flatbuffers::VOffsetT = 4;
+        pub const VT_SUBSCRIPTION_ID: flatbuffers::VOffsetT = 6;
+        pub const VT_EVENT: flatbuffers::VOffsetT = 8;
+        pub const VT_EVENT_ID: flatbuffers::VOffsetT = 10;
+        pub const VT_STATUS: flatbuffers::VOffsetT = 12;
+        pub const VT_MESSAGE: flatbuffers::VOffsetT = 14;
+        pub const VT_COUNT: flatbuffers::VOffsetT = 16;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            RelayMessage { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<
+            'bldr: 'args,
+            'args: 'mut_bldr,
+            'mut_bldr,
+            A: flatbuffers::Allocator + 'bldr,
+        >(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+            args: &'args RelayMessageArgs<'args>,
+        ) -> flatbuffers::WIPOffset<RelayMessage<'bldr>> {
+            let mut builder = RelayMessageBuilder::new(_fbb);
+            builder.add_count(args.count);
+            if let Some(x) = args.message {
+                builder.add_message(x);
+            }
+            if let Some(x) = args.event_id {
+                builder.add_event_id(x);
+            }
+            if let Some(x) = args.event {
+                builder.add_event(x);
+            }
+            if let Some(x) = args.subscription_id {
+                builder.add_subscription_id(x);
+            }
+            builder.add_status(args.status);
+            builder.add_message_type(args.message_type);
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn message_type(&self) -> RelayMessageType {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<RelayMessageType>(
+                        RelayMessage::VT_MESSAGE_TYPE,
+                        Some(RelayMessageType::Event),
+                    )
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn subscription_id(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(
+                    RelayMessage::VT_SUBSCRIPTION_ID,
+                    None,
+                )
+            }
+        }
+        #[inline]
+        pub fn event(&self) -> Option<flatbuffers::Vector<'a, u8>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(
+                        RelayMessage::VT_EVENT,
+                        None,
+                    )
+            }
+        }
+        #[inline]
+        pub fn event_id(&self) -> Option<&'a Fixed32Bytes> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<Fixed32Bytes>(RelayMessage::VT_EVENT_ID, None)
+            }
+        }
+        #[inline]
+        pub fn status(&self) -> bool {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<bool>(RelayMessage::VT_STATUS, Some(false))
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn message(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(RelayMessage::VT_MESSAGE, None)
+            }
+        }
+        #[inline]
+        pub fn count(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<u64>(RelayMessage::VT_COUNT, Some(0))
+                    .unwrap()
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for RelayMessage<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<RelayMessageType>("message_type", Self::VT_MESSAGE_TYPE, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "subscription_id",
+                    Self::VT_SUBSCRIPTION_ID,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u8>>>(
+                    "event",
+                    Self::VT_EVENT,
+                    false,
+                )?
+                .visit_field::<Fixed32Bytes>("event_id", Self::VT_EVENT_ID, false)?
+                .visit_field::<bool>("status", Self::VT_STATUS, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "message",
+                    Self::VT_MESSAGE,
+                    false,
+                )?
+                .visit_field::<u64>("count", Self::VT_COUNT, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct RelayMessageArgs<'a> {
+        pub message_type: RelayMessageType,
+        pub subscription_id: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub event: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+        pub event_id: Option<&'a Fixed32Bytes>,
+        pub status: bool,
+        pub message: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub count: u64,
+    }
+    impl<'a> Default for RelayMessageArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            RelayMessageArgs {
+                message_type: RelayMessageType::Event,
+                subscription_id: None,
+                event: None,
+                event_id: None,
+                status: false,
+                message: None,
+                count: 0,
+            }
+        }
+    }
+
+    pub struct RelayMessageBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> RelayMessageBuilder<'a, 'b, A> {
+        #[inline]
+        pub fn add_message_type(&mut self, message_type: RelayMessageType) {
+            self.fbb_.push_slot::<RelayMessageType>(
+                RelayMessage::VT_MESSAGE_TYPE,
+                message_type,
+                RelayMessageType::Event,
+            );
+        }
+        #[inline]
+        pub fn add_subscription_id(&mut self, subscription_id: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                RelayMessage::VT_SUBSCRIPTION_ID,
+                subscription_id,
+            );
+        }
+        #[inline]
+        pub fn add_event(&mut self, event: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u8>>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(RelayMessage::VT_EVENT, event);
+        }
+        #[inline]
+        pub fn add_event_id(&mut self, event_id: &Fixed32Bytes) {
+            self.fbb_
+                .push_slot_always::<&Fixed32Bytes>(RelayMessage::VT_EVENT_ID, event_id);
+        }
+        #[inline]
+        pub fn add_status(&mut self, status: bool) {
+            self.fbb_
+                .push_slot::<bool>(RelayMessage::VT_STATUS, status, false);
+        }
+        #[inline]
+        pub fn add_message(&mut self, message: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(RelayMessage::VT_MESSAGE, message);
+        }
+        #[inline]
+        pub fn add_count(&mut self, count: u64) {
+            self.fbb_.push_slot::<u64>(RelayMessage::VT_COUNT, count, 0);
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        ) -> RelayMessageBuilder<'a, 'b, A> {
+            let start = _fbb.start_table();
+            RelayMessageBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<RelayMessage<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for RelayMessage<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("RelayMessage");
+            ds.field("message_type", &self.message_type());
+            ds.field("subscription_id", &self.subscription_id());
+            ds.field("event", &self.event());
+            ds.field("event_id", &self.event_id());
+            ds.field("status", &self.status());
+            ds.field("message", &self.message());
+            ds.field("count", &self.count());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `RelayMessage`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_relay_message_unchecked`.
+    pub fn root_as_relay_message(
+        buf: &[u8],
+    ) -> Result<RelayMessage<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<RelayMessage>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `RelayMessage` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_relay_message_unchecked`.
+    pub fn size_prefixed_root_as_relay_message(
+        buf: &[u8],
+    ) -> Result<RelayMessage<'_>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<RelayMessage>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `RelayMessage` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_relay_message_unchecked`.
+    pub fn root_as_relay_message_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<RelayMessage<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<RelayMessage<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `RelayMessage` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_relay_message_unchecked`.
+    pub fn size_prefixed_root_as_relay_message_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<RelayMessage<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<RelayMessage<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a RelayMessage and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `RelayMessage`.
+    pub unsafe fn root_as_relay_message_unchecked(buf: &[u8]) -> RelayMessage<'_> {
+        flatbuffers::root_unchecked::<RelayMessage>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed RelayMessage and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `RelayMessage`.
+    pub unsafe fn size_prefixed_root_as_relay_message_unchecked(buf: &[u8]) -> RelayMessage<'_> {
+        flatbuffers::size_prefixed_root_unchecked::<RelayMessage>(buf)
+    }
+    #[inline]
+    pub fn finish_relay_message_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<RelayMessage<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_relay_message_buffer<'a, 'b, A: flatbuffers::Allocator + 'a>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+        root: flatbuffers::WIPOffset<RelayMessage<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod RelayMessageFbs
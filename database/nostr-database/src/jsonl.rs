@@ -0,0 +1,41 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! JSON-lines import/export
+
+use std::fmt;
+
+/// Invalid line of a JSON-lines import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlLineError {
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Error message
+    pub error: String,
+}
+
+impl fmt::Display for JsonlLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+/// JSON-lines import report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonlImportReport {
+    /// Number of saved events
+    pub saved: usize,
+    /// Number of events rejected by the database (i.e., duplicates, deleted or replaced)
+    pub rejected: usize,
+    /// Lines that can't be parsed or contain an invalid event
+    pub errors: Vec<JsonlLineError>,
+}
+
+impl JsonlImportReport {
+    /// Check if all the lines have been parsed
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
@@ -26,14 +26,20 @@ mod error;
 pub mod ext;
 #[cfg(feature = "flatbuf")]
 pub mod flatbuffers;
+mod jsonl;
 pub mod prelude;
 pub mod profile;
+mod stream;
+mod verify;
 
 pub use self::collections::events::Events;
 pub use self::error::DatabaseError;
 #[cfg(feature = "flatbuf")]
 pub use self::flatbuffers::{FlatBufferBuilder, FlatBufferDecode, FlatBufferEncode};
+pub use self::jsonl::{JsonlImportReport, JsonlLineError};
 pub use self::profile::Profile;
+pub use self::stream::BoxedStream;
+pub use self::verify::{VerifyAction, VerifyOptions, VerifyReport};
 
 /// NIP65 relays map
 pub type RelaysMap = HashMap<RelayUrl, Option<RelayMetadata>>;
@@ -192,6 +198,30 @@ pub trait NostrDatabase: Any + Debug + Send + Sync {
         event: &'a Event,
     ) -> BoxedFuture<'a, Result<SaveEventStatus, DatabaseError>>;
 
+    /// Save a batch of [`Event`]s into store
+    ///
+    /// Returns the status of each event, in the same order.
+    /// The rejected events (i.e., duplicates, also within the batch) don't abort the batch.
+    ///
+    /// By default, calls [`NostrDatabase::save_event`] for each event:
+    /// backends that can write the whole batch at once should override it.
+    ///
+    /// **This method assumes that the [`Event`]s were already verified**
+    fn save_events(
+        &self,
+        events: Vec<Event>,
+    ) -> BoxedFuture<'_, Result<Vec<SaveEventStatus>, DatabaseError>> {
+        Box::pin(async move {
+            let mut statuses: Vec<SaveEventStatus> = Vec::with_capacity(events.len());
+
+            for event in events.iter() {
+                statuses.push(self.save_event(event).await?);
+            }
+
+            Ok(statuses)
+        })
+    }
+
     /// Check event status by ID
     ///
     /// Check if the event is saved, deleted or not existent.
@@ -200,6 +230,19 @@ pub trait NostrDatabase: Any + Debug + Send + Sync {
         event_id: &'a EventId,
     ) -> BoxedFuture<'a, Result<DatabaseEventStatus, DatabaseError>>;
 
+    /// Check if the event is saved
+    ///
+    /// By default, uses [`NostrDatabase::check_id`].
+    fn contains<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<bool, DatabaseError>> {
+        Box::pin(async move {
+            let status: DatabaseEventStatus = self.check_id(event_id).await?;
+            Ok(status == DatabaseEventStatus::Saved)
+        })
+    }
+
     /// Get [`Event`] by [`EventId`]
     fn event_by_id<'a>(
         &'a self,
@@ -214,6 +257,16 @@ pub trait NostrDatabase: Any + Debug + Send + Sync {
     /// Query stored events.
     fn query(&self, filter: Filter) -> BoxedFuture<'_, Result<Events, DatabaseError>>;
 
+    /// Stream stored events.
+    ///
+    /// Same order (descending `created_at`) and `limit` semantics of [`NostrDatabase::query`],
+    /// but the events are yielded incrementally, without collecting them all in memory.
+    ///
+    /// By default, wraps [`NostrDatabase::query`].
+    fn stream_query(&self, filter: Filter) -> BoxedStream<'_, Result<Event, DatabaseError>> {
+        Box::pin(stream::QueryStream::new(self.query(filter)))
+    }
+
     /// Get `negentropy` items
     fn negentropy_items(
         &self,
@@ -230,4 +283,48 @@ pub trait NostrDatabase: Any + Debug + Send + Sync {
 
     /// Wipe all data
     fn wipe(&self) -> BoxedFuture<'_, Result<(), DatabaseError>>;
+
+    /// Delete the expired events (NIP-40)
+    ///
+    /// Events are considered expired starting from the second of their `expiration` tag.
+    /// Meant to be called periodically, to physically remove the expired events.
+    ///
+    /// By default, queries all the events and deletes the expired ones:
+    /// backends that exclude the expired events from the queries must override it.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
+    fn wipe_expired(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            let now: Timestamp = Timestamp::now();
+            let events: Events = self.query(Filter::new()).await?;
+
+            let ids: Vec<EventId> = events
+                .into_iter()
+                .filter(|e| e.is_expired_at(now))
+                .map(|e| e.id)
+                .collect();
+
+            if ids.is_empty() {
+                return Ok(());
+            }
+
+            self.delete(Filter::new().ids(ids)).await
+        })
+    }
+
+    /// Verify the stored events
+    ///
+    /// Walk the stored events in batches, recompute the IDs from the canonical serialization
+    /// and, optionally, re-check the signatures.
+    /// The invalid events are reported and, according to [`VerifyOptions::action`],
+    /// quarantined or deleted.
+    ///
+    /// By default, returns [`DatabaseError::NotSupported`].
+    fn verify_events(
+        &self,
+        opts: VerifyOptions,
+    ) -> BoxedFuture<'_, Result<VerifyReport, DatabaseError>> {
+        let _ = opts;
+        Box::pin(async move { Err(DatabaseError::NotSupported) })
+    }
 }
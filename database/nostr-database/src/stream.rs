@@ -0,0 +1,58 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Streams
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use nostr::prelude::*;
+
+use crate::{DatabaseError, Events};
+
+/// Boxed stream
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxedStream<'a, T> = Pin<Box<dyn Stream<Item = T> + Send + 'a>>;
+
+/// Boxed stream
+#[cfg(target_arch = "wasm32")]
+pub type BoxedStream<'a, T> = Pin<Box<dyn Stream<Item = T> + 'a>>;
+
+/// Stream the output of [`NostrDatabase::query`](crate::NostrDatabase::query)
+pub(crate) enum QueryStream<'a> {
+    Querying(BoxedFuture<'a, Result<Events, DatabaseError>>),
+    Streaming(<Events as IntoIterator>::IntoIter),
+    Terminated,
+}
+
+impl<'a> QueryStream<'a> {
+    #[inline]
+    pub(crate) fn new(fut: BoxedFuture<'a, Result<Events, DatabaseError>>) -> Self {
+        Self::Querying(fut)
+    }
+}
+
+impl Stream for QueryStream<'_> {
+    type Item = Result<Event, DatabaseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match this {
+                Self::Querying(fut) => match fut.as_mut().poll(cx) {
+                    Poll::Ready(Ok(events)) => *this = Self::Streaming(events.into_iter()),
+                    Poll::Ready(Err(e)) => {
+                        *this = Self::Terminated;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                Self::Streaming(iter) => return Poll::Ready(iter.next().map(Ok)),
+                Self::Terminated => return Poll::Ready(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Stored events verification
+
+use std::time::Duration;
+
+use nostr::EventId;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const DEFAULT_PAUSE: Duration = Duration::from_millis(50);
+
+/// What to do with the events that failed the verification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VerifyAction {
+    /// Only report the failures, without touching the stored events
+    #[default]
+    Report,
+    /// Move the invalid events to a separate keyspace, excluding them from queries
+    Quarantine,
+    /// Permanently delete the invalid events
+    Delete,
+}
+
+/// Stored events verification options
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VerifyOptions {
+    /// Number of events to verify in a single batch (default: 500)
+    pub batch_size: usize,
+    /// Re-check the event signatures (default: false)
+    ///
+    /// The event ID is always recomputed from the canonical serialization.
+    pub signatures: bool,
+    /// Action to take for the invalid events (default: [`VerifyAction::Report`])
+    pub action: VerifyAction,
+    /// Pause between batches (default: 50 ms)
+    ///
+    /// Used to avoid starving the foreground queries.
+    pub pause: Option<Duration>,
+    /// Max number of events to verify in this run (default: None)
+    ///
+    /// If the limit is reached before the end of the database,
+    /// the sweep can be resumed later (see [`VerifyOptions::resume`]).
+    pub limit: Option<usize>,
+    /// Resume the sweep from the stored cursor (default: true)
+    ///
+    /// If `false`, the sweep restarts from the beginning.
+    pub resume: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            signatures: false,
+            action: VerifyAction::default(),
+            pause: Some(DEFAULT_PAUSE),
+            limit: None,
+            resume: true,
+        }
+    }
+}
+
+impl VerifyOptions {
+    /// New default options
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set batch size
+    #[inline]
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size;
+        self
+    }
+
+    /// Re-check the event signatures
+    #[inline]
+    pub fn signatures(mut self, enable: bool) -> Self {
+        self.signatures = enable;
+        self
+    }
+
+    /// Set the action to take for the invalid events
+    #[inline]
+    pub fn action(mut self, action: VerifyAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Set the pause between batches
+    #[inline]
+    pub fn pause(mut self, pause: Option<Duration>) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Set the max number of events to verify in this run
+    #[inline]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Resume the sweep from the stored cursor
+    #[inline]
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+}
+
+/// Stored events verification report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of verified events
+    pub checked: usize,
+    /// Events whose content doesn't hash to the stored ID
+    pub id_mismatches: Vec<EventId>,
+    /// Events with an invalid signature
+    pub invalid_signatures: Vec<EventId>,
+    /// Stored values that can't be decoded
+    ///
+    /// The IDs are the keys under which the values are stored.
+    pub undecodable: Vec<EventId>,
+    /// Whether the sweep reached the end of the database
+    pub completed: bool,
+}
+
+impl VerifyReport {
+    /// Check if no invalid events have been found
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.id_mismatches.is_empty()
+            && self.invalid_signatures.is_empty()
+            && self.undecodable.is_empty()
+    }
+
+    /// Merge another report into this one
+    pub fn merge(&mut self, other: Self) {
+        self.checked += other.checked;
+        self.id_mismatches.extend(other.id_mismatches);
+        self.invalid_signatures.extend(other.invalid_signatures);
+        self.undecodable.extend(other.undecodable);
+        self.completed = other.completed;
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Database layout
+
+use std::fmt;
+use std::num::NonZeroU16;
+
+/// Granularity of the time buckets of the partitioned layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PartitionGranularity {
+    /// A bucket per day (UTC)
+    Day,
+    /// A bucket per ISO week (UTC)
+    Week,
+    /// A bucket per number of days, aligned to the ISO weeks
+    Days(NonZeroU16),
+}
+
+impl PartitionGranularity {
+    /// Width of the buckets, in days
+    #[inline]
+    pub fn days(&self) -> u64 {
+        match self {
+            Self::Day => 1,
+            Self::Week => 7,
+            Self::Days(days) => days.get() as u64,
+        }
+    }
+}
+
+/// Layout of the events on disk
+///
+/// The layout is chosen when the database is created and can't be changed later:
+/// opening an existing database with another layout fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LmdbLayout {
+    /// All the events in a single set of indexes
+    #[default]
+    Flat,
+    /// The events split by creation time in buckets, each with its own set of indexes
+    ///
+    /// The queries visit only the buckets overlapping their time range
+    /// and the events older than a cutoff can be dropped a whole bucket at a time
+    /// (see [`NostrLmdb::prune`](crate::NostrLmdb::prune)).
+    Partitioned(PartitionGranularity),
+}
+
+impl LmdbLayout {
+    /// Width of the time buckets, in days (`0` for the flat layout)
+    #[inline]
+    pub fn days(&self) -> u64 {
+        match self {
+            Self::Flat => 0,
+            Self::Partitioned(granularity) => granularity.days(),
+        }
+    }
+
+    pub(crate) fn from_days(days: u64) -> Self {
+        match days {
+            0 => Self::Flat,
+            1 => Self::Partitioned(PartitionGranularity::Day),
+            7 => Self::Partitioned(PartitionGranularity::Week),
+            days => match u16::try_from(days).ok().and_then(NonZeroU16::new) {
+                Some(days) => Self::Partitioned(PartitionGranularity::Days(days)),
+                // Not written by this crate
+                None => Self::Flat,
+            },
+        }
+    }
+}
+
+impl fmt::Display for LmdbLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flat => write!(f, "flat"),
+            Self::Partitioned(granularity) => {
+                write!(f, "partitioned by {} days", granularity.days())
+            }
+        }
+    }
+}
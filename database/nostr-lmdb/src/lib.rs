@@ -9,14 +9,20 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::bare_urls)]
 #![allow(clippy::mutable_key_type)]
+#![cfg_attr(bench, feature(test))]
+
+#[cfg(bench)]
+extern crate test;
 
 use std::path::{Path, PathBuf};
 
 use nostr_database::prelude::*;
 
+mod layout;
 pub mod prelude;
 mod store;
 
+pub use self::layout::{LmdbLayout, PartitionGranularity};
 use self::store::Store;
 
 // 64-bit
@@ -58,8 +64,24 @@ pub struct NostrLmdbBuilder {
     ///
     /// Defaults to `true`
     pub process_nip09: bool,
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    ///
+    /// Defaults to `true`
+    pub process_nip40: bool,
     /// Relay URL for relay-specific request to vanish (NIP-62)
     pub relay_url: Option<RelayUrl>,
+    /// Layout of the events on disk
+    ///
+    /// Used only when the database is created.
+    ///
+    /// Defaults to [`LmdbLayout::Flat`]
+    pub layout: LmdbLayout,
+    /// Maximum number of time buckets of the partitioned layout
+    ///
+    /// Every bucket takes 9 databases.
+    ///
+    /// Defaults to 1024
+    pub max_buckets: u32,
 }
 
 impl NostrLmdbBuilder {
@@ -75,7 +97,10 @@ impl NostrLmdbBuilder {
             additional_dbs: 0,
             process_nip62: true,
             process_nip09: true,
+            process_nip40: true,
             relay_url: None,
+            layout: LmdbLayout::Flat,
+            max_buckets: 1024,
         }
     }
 
@@ -121,6 +146,17 @@ impl NostrLmdbBuilder {
         self
     }
 
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    ///
+    /// Disable it to keep serving the expired events (i.e., for archival use).
+    /// The expired events can be physically removed with [`NostrDatabase::wipe_expired`].
+    ///
+    /// Defaults to `true`
+    pub fn process_nip40(mut self, process_nip40: bool) -> Self {
+        self.process_nip40 = process_nip40;
+        self
+    }
+
     /// Set the relay URL to handle relay-specific request to vanish
     #[inline]
     pub fn relay_url(mut self, relay_url: RelayUrl) -> Self {
@@ -128,6 +164,27 @@ impl NostrLmdbBuilder {
         self
     }
 
+    /// Layout of the events on disk
+    ///
+    /// Used only when the database is created:
+    /// opening an existing database with another layout fails.
+    ///
+    /// Defaults to [`LmdbLayout::Flat`]
+    pub fn layout(mut self, layout: LmdbLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Maximum number of time buckets of the partitioned layout
+    ///
+    /// The pruned buckets are emptied but still count, since their databases can't be deleted.
+    ///
+    /// Defaults to 1024
+    pub fn max_buckets(mut self, max_buckets: u32) -> Self {
+        self.max_buckets = max_buckets;
+        self
+    }
+
     /// Build
     pub async fn build(self) -> Result<NostrLmdb, DatabaseError> {
         let db: Store = Store::from_builder(self)
@@ -167,6 +224,18 @@ impl NostrLmdb {
     pub async fn reindex(&self) -> Result<(), DatabaseError> {
         self.db.reindex().await.map_err(DatabaseError::backend)
     }
+
+    /// Remove the events created before `older_than`
+    ///
+    /// With the [`LmdbLayout::Partitioned`] layout, only the whole buckets ending before `older_than` are dropped,
+    /// so the events of the bucket including `older_than` are kept.
+    #[inline]
+    pub async fn prune(&self, older_than: Timestamp) -> Result<(), DatabaseError> {
+        self.db
+            .prune(older_than)
+            .await
+            .map_err(DatabaseError::backend)
+    }
 }
 
 impl NostrDatabase for NostrLmdb {
@@ -178,7 +247,7 @@ impl NostrDatabase for NostrLmdb {
     fn features(&self) -> Features {
         Features {
             persistent: true,
-            event_expiration: false,
+            event_expiration: true,
             full_text_search: true,
             request_to_vanish: true,
         }
@@ -196,6 +265,18 @@ impl NostrDatabase for NostrLmdb {
         })
     }
 
+    fn save_events(
+        &self,
+        events: Vec<Event>,
+    ) -> BoxedFuture<'_, Result<Vec<SaveEventStatus>, DatabaseError>> {
+        Box::pin(async move {
+            self.db
+                .save_events(events)
+                .await
+                .map_err(DatabaseError::backend)
+        })
+    }
+
     fn check_id<'a>(
         &'a self,
         event_id: &'a EventId,
@@ -208,6 +289,18 @@ impl NostrDatabase for NostrLmdb {
         })
     }
 
+    fn contains<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<bool, DatabaseError>> {
+        Box::pin(async move {
+            self.db
+                .contains(*event_id)
+                .await
+                .map_err(DatabaseError::backend)
+        })
+    }
+
     fn event_by_id<'a>(
         &'a self,
         event_id: &'a EventId,
@@ -228,6 +321,10 @@ impl NostrDatabase for NostrLmdb {
         Box::pin(async move { self.db.query(filter).await.map_err(DatabaseError::backend) })
     }
 
+    fn stream_query(&self, filter: Filter) -> BoxedStream<'_, Result<Event, DatabaseError>> {
+        self.db.stream_query(filter)
+    }
+
     fn negentropy_items(
         &self,
         filter: Filter,
@@ -248,6 +345,24 @@ impl NostrDatabase for NostrLmdb {
     fn wipe(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
         Box::pin(async move { self.db.wipe().await.map_err(DatabaseError::backend) })
     }
+
+    #[inline]
+    fn wipe_expired(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move { self.db.wipe_expired().await.map_err(DatabaseError::backend) })
+    }
+
+    #[inline]
+    fn verify_events(
+        &self,
+        opts: VerifyOptions,
+    ) -> BoxedFuture<'_, Result<VerifyReport, DatabaseError>> {
+        Box::pin(async move {
+            self.db
+                .verify_events(opts)
+                .await
+                .map_err(DatabaseError::backend)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -298,4 +413,367 @@ mod tests {
         TempDatabase::new,
         TempDatabase::new_with_relay_url
     );
+
+    /// The conformance suite, with the events split in daily buckets
+    mod partitioned {
+        use nostr_database_test_suite::database_unit_tests;
+        use tempfile::TempDir;
+
+        use crate::{LmdbLayout, NostrLmdb, PartitionGranularity};
+
+        const LAYOUT: LmdbLayout = LmdbLayout::Partitioned(PartitionGranularity::Day);
+
+        struct TempDatabase {
+            db: NostrLmdb,
+            // Needed to avoid the drop and deletion of temp folder
+            _temp: TempDir,
+        }
+
+        impl Deref for TempDatabase {
+            type Target = NostrLmdb;
+
+            fn deref(&self) -> &Self::Target {
+                &self.db
+            }
+        }
+
+        impl TempDatabase {
+            async fn new() -> Self {
+                let path = tempfile::tempdir().unwrap();
+                Self {
+                    db: NostrLmdb::builder(&path)
+                        .layout(LAYOUT)
+                        .build()
+                        .await
+                        .unwrap(),
+                    _temp: path,
+                }
+            }
+
+            async fn new_with_relay_url(url: RelayUrl) -> Self {
+                let path = tempfile::tempdir().unwrap();
+                Self {
+                    db: NostrLmdb::builder(&path)
+                        .layout(LAYOUT)
+                        .relay_url(url)
+                        .build()
+                        .await
+                        .unwrap(),
+                    _temp: path,
+                }
+            }
+        }
+
+        database_unit_tests!(
+            TempDatabase,
+            TempDatabase::new,
+            TempDatabase::new_with_relay_url
+        );
+    }
+
+    const DAY: u64 = 86_400;
+    const MONDAY: u64 = 1_699_833_600; // 2023-11-13T00:00:00Z
+
+    async fn partitioned(path: &TempDir) -> NostrLmdb {
+        NostrLmdb::builder(path.path())
+            .layout(LmdbLayout::Partitioned(PartitionGranularity::Day))
+            .build()
+            .await
+            .unwrap()
+    }
+
+    fn text_note_at(keys: &Keys, created_at: u64) -> Event {
+        EventBuilder::text_note(format!("Created at {created_at}"))
+            .custom_created_at(Timestamp::from_secs(created_at))
+            .sign(keys)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_query_across_buckets() {
+        let path = tempfile::tempdir().unwrap();
+        let db = partitioned(&path).await;
+        let keys = Keys::generate();
+
+        let events: Vec<Event> = (0..10)
+            .map(|i| text_note_at(&keys, MONDAY + i * DAY / 2))
+            .collect();
+        db.save_events(events.clone()).await.unwrap();
+
+        // Newest first, over the buckets
+        let filter = Filter::new().author(keys.public_key());
+        let res: Vec<Event> = db.query(filter.clone()).await.unwrap().to_vec();
+        let expected: Vec<Event> = events.iter().rev().cloned().collect();
+        assert_eq!(res, expected);
+
+        // The limit is applied over the buckets
+        let res = db.query(filter.clone().limit(3)).await.unwrap();
+        assert_eq!(res.to_vec(), expected[..3]);
+
+        // Only the buckets in the time range
+        let filter = Filter::new()
+            .since(Timestamp::from_secs(MONDAY + DAY))
+            .until(Timestamp::from_secs(MONDAY + 2 * DAY));
+        let res: Vec<Event> = db.query(filter.clone()).await.unwrap().to_vec();
+        assert_eq!(res, expected[5..8]);
+        assert_eq!(db.count(filter).await.unwrap(), 3);
+
+        // Lookup by ID
+        assert_eq!(
+            db.event_by_id(&events[0].id).await.unwrap(),
+            Some(events[0].clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_replaceable_across_buckets() {
+        let path = tempfile::tempdir().unwrap();
+        let db = partitioned(&path).await;
+        let keys = Keys::generate();
+
+        let metadata_at = |created_at: u64, name: &str| {
+            EventBuilder::metadata(&Metadata::new().name(name))
+                .custom_created_at(Timestamp::from_secs(created_at))
+                .sign(&keys)
+                .unwrap()
+        };
+        let first = metadata_at(MONDAY, "first");
+        let second = metadata_at(MONDAY + 2 * DAY, "second");
+        let older = metadata_at(MONDAY + DAY, "older");
+
+        assert!(db.save_event(&first).await.unwrap().is_success());
+        assert!(db.save_event(&second).await.unwrap().is_success());
+        assert_eq!(
+            db.save_event(&older).await.unwrap(),
+            SaveEventStatus::Rejected(RejectedReason::Replaced)
+        );
+
+        let filter = Filter::new().author(keys.public_key()).kind(Kind::Metadata);
+        assert_eq!(db.query(filter).await.unwrap().to_vec(), vec![second]);
+        assert!(db.event_by_id(&first.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_prune() {
+        let path = tempfile::tempdir().unwrap();
+        let db = partitioned(&path).await;
+        let keys = Keys::generate();
+
+        let events: Vec<Event> = (0..4)
+            .map(|i| text_note_at(&keys, MONDAY + i * DAY))
+            .collect();
+        db.save_events(events.clone()).await.unwrap();
+
+        // The bucket including the cutoff is kept
+        db.prune(Timestamp::from_secs(MONDAY + 2 * DAY + 1))
+            .await
+            .unwrap();
+
+        let res: Vec<Event> = db.query(Filter::new()).await.unwrap().to_vec();
+        assert_eq!(res, vec![events[3].clone(), events[2].clone()]);
+        assert!(db.event_by_id(&events[0].id).await.unwrap().is_none());
+        assert_eq!(
+            db.check_id(&events[1].id).await.unwrap(),
+            DatabaseEventStatus::NotExistent
+        );
+
+        // The pruned buckets can be used again
+        assert!(db.save_event(&events[0]).await.unwrap().is_success());
+        assert_eq!(db.count(Filter::new()).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_flat_prune() {
+        let db = TempDatabase::new().await;
+        let keys = Keys::generate();
+
+        let events: Vec<Event> = (0..4)
+            .map(|i| text_note_at(&keys, MONDAY + i * DAY))
+            .collect();
+        db.save_events(events.clone()).await.unwrap();
+
+        db.prune(Timestamp::from_secs(MONDAY + 2 * DAY))
+            .await
+            .unwrap();
+
+        let res: Vec<Event> = db.query(Filter::new()).await.unwrap().to_vec();
+        assert_eq!(res, vec![events[3].clone(), events[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_layout_is_chosen_at_creation() {
+        let path = tempfile::tempdir().unwrap();
+        let db = partitioned(&path).await;
+        db.save_event(&text_note_at(&Keys::generate(), MONDAY))
+            .await
+            .unwrap();
+        drop(db);
+
+        // Another layout
+        assert!(NostrLmdb::open(path.path()).await.is_err());
+
+        // Same layout
+        let db = partitioned(&path).await;
+        assert_eq!(db.count(Filter::new()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_too_many_buckets() {
+        let path = tempfile::tempdir().unwrap();
+        let db = NostrLmdb::builder(path.path())
+            .layout(LmdbLayout::Partitioned(PartitionGranularity::Day))
+            .max_buckets(2)
+            .build()
+            .await
+            .unwrap();
+        let keys = Keys::generate();
+
+        db.save_event(&text_note_at(&keys, MONDAY)).await.unwrap();
+        db.save_event(&text_note_at(&keys, MONDAY + DAY))
+            .await
+            .unwrap();
+        assert!(
+            db.save_event(&text_note_at(&keys, MONDAY + 2 * DAY))
+                .await
+                .is_err()
+        );
+
+        // The existing buckets are still usable
+        db.save_event(&text_note_at(&keys, MONDAY + 1))
+            .await
+            .unwrap();
+        assert_eq!(db.count(Filter::new()).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_query_dropped_early() {
+        use futures::StreamExt;
+
+        let db = TempDatabase::new().await;
+        let keys = Keys::generate();
+
+        // More events than the stream buffer
+        for i in 0..(store::STREAM_QUERY_BUFFER_SIZE * 2) {
+            let event = EventBuilder::text_note(format!("event {i}"))
+                .tag(Tag::hashtag("stream"))
+                .sign(&keys)
+                .unwrap();
+            db.save_event(&event).await.unwrap();
+        }
+
+        let filter = Filter::new().hashtag("stream");
+        let mut stream = db.stream_query(filter.clone());
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+
+        // The write isn't blocked by the stream task
+        let event = EventBuilder::text_note("after")
+            .tag(Tag::hashtag("stream"))
+            .sign(&keys)
+            .unwrap();
+        let status = tokio::time::timeout(Duration::from_secs(5), db.save_event(&event))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(status.is_success());
+
+        let count: usize = db.count(filter).await.unwrap();
+        assert_eq!(count, store::STREAM_QUERY_BUFFER_SIZE * 2 + 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_skips_expired_events() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("Expiring")
+            .tag(Tag::hashtag("expiring"))
+            .tag(Tag::expiration(Timestamp::now() + 1))
+            .sign(&keys)
+            .unwrap();
+
+        let database = TempDatabase::new().await;
+        let path = tempfile::tempdir().unwrap();
+        let archive = NostrLmdb::builder(&path)
+            .process_nip40(false)
+            .build()
+            .await
+            .unwrap();
+
+        for db in [&database.db, &archive] {
+            let status = db.save_event(&event).await.unwrap();
+            assert_eq!(status, SaveEventStatus::Success);
+            assert_eq!(db.count(Filter::new()).await.unwrap(), 1);
+        }
+
+        // Wait for the expiration
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // Not served anymore, but still stored
+        let filter = Filter::new().hashtag("expiring");
+        assert_eq!(database.count(Filter::new()).await.unwrap(), 0);
+        assert_eq!(database.count(filter.clone()).await.unwrap(), 0);
+        assert!(database.query(filter.clone()).await.unwrap().is_empty());
+        assert!(database.stream_query(filter.clone()).next().await.is_none());
+        assert!(database.event_by_id(&event.id).await.unwrap().is_some());
+
+        // Served by the archive
+        assert_eq!(archive.count(filter.clone()).await.unwrap(), 1);
+        assert_eq!(archive.query(filter).await.unwrap().len(), 1);
+
+        // Physically removed
+        database.wipe_expired().await.unwrap();
+        assert!(database.event_by_id(&event.id).await.unwrap().is_none());
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::test::Bencher;
+
+    const EVENTS: usize = 1_000;
+
+    fn events() -> Vec<Event> {
+        let keys = Keys::generate();
+        (0..EVENTS)
+            .map(|i| {
+                EventBuilder::text_note(format!("Event {i}"))
+                    .sign(&keys)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[bench]
+    pub fn bench_import_save_event(bh: &mut Bencher) {
+        let rt = Runtime::new().unwrap();
+        let events = events();
+
+        bh.iter(|| {
+            rt.block_on(async {
+                let temp = tempfile::tempdir().unwrap();
+                let db = NostrLmdb::open(temp.path()).await.unwrap();
+
+                for event in events.iter() {
+                    db.save_event(event).await.unwrap();
+                }
+            })
+        });
+    }
+
+    #[bench]
+    pub fn bench_import_save_events(bh: &mut Bencher) {
+        let rt = Runtime::new().unwrap();
+        let events = events();
+
+        bh.iter(|| {
+            rt.block_on(async {
+                let temp = tempfile::tempdir().unwrap();
+                let db = NostrLmdb::open(temp.path()).await.unwrap();
+
+                db.save_events(events.clone()).await.unwrap();
+            })
+        });
+    }
 }
@@ -10,6 +10,8 @@ use nostr::{key, secp256k1};
 use nostr_database::flatbuffers;
 use tokio::sync::oneshot;
 
+use crate::LmdbLayout;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum MigrationError {
     /// Database version is newer than supported one
@@ -59,6 +61,10 @@ pub enum Error {
     NotFound,
     /// Batched transaction failed - sent to operations that didn't cause the error
     BatchTransactionFailed,
+    /// The maximum number of time buckets has been reached
+    TooManyBuckets,
+    /// The database was created with another layout
+    LayoutMismatch(LmdbLayout),
 }
 
 impl std::error::Error for Error {}
@@ -78,6 +84,10 @@ impl fmt::Display for Error {
             Self::NotFound => write!(f, "Not found"),
             Self::WrongEventKind => write!(f, "Wrong event kind"),
             Self::BatchTransactionFailed => write!(f, "Batched transaction failed"),
+            Self::TooManyBuckets => write!(f, "Too many time buckets"),
+            Self::LayoutMismatch(layout) => {
+                write!(f, "Database layout mismatch: created as {layout}")
+            }
         }
     }
 }
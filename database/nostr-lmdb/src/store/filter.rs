@@ -21,6 +21,8 @@ pub(crate) struct DatabaseFilter {
     pub(crate) since: Option<Timestamp>,
     pub(crate) until: Option<Timestamp>,
     pub(crate) generic_tags: BTreeMap<SingleLetterTag, BTreeSet<String>>,
+    /// Exclude the events expired at this time (NIP-40)
+    pub(crate) expired_at: Option<Timestamp>,
 }
 
 impl DatabaseFilter {
@@ -103,9 +105,18 @@ impl DatabaseFilter {
             && self.until.is_none_or(|t| event.created_at <= t)
             && self.tag_match(event)
             && self.search_match(event)
+            && self
+                .expired_at
+                .is_none_or(|now| expiration(event).is_none_or(|expiration| expiration > now))
     }
 }
 
+/// Get the expiration (NIP-40) of the event
+pub(crate) fn expiration(event: &EventBorrow) -> Option<Timestamp> {
+    let tag = event.tags.iter().find(|t| t.kind() == "expiration")?;
+    tag.content()?.parse().ok()
+}
+
 #[inline]
 fn match_content(query: &[u8], content: &[u8]) -> bool {
     // Early exit if query is empty
@@ -164,6 +175,7 @@ impl From<Filter> for DatabaseFilter {
             since: filter.since,
             until: filter.until,
             generic_tags: filter.generic_tags,
+            expired_at: None,
         }
     }
 }
@@ -14,8 +14,8 @@ use std::{iter, thread};
 
 use flume::{Receiver, Sender};
 use heed::RwTxn;
-use nostr::{Event, Filter};
-use nostr_database::{FlatBufferBuilder, SaveEventStatus};
+use nostr::{Event, EventId, Filter, Timestamp};
+use nostr_database::{FlatBufferBuilder, SaveEventStatus, VerifyAction};
 use tokio::sync::oneshot;
 
 use super::error::Error;
@@ -36,6 +36,10 @@ enum OperationResult {
         result: Result<SaveEventStatus, Error>,
         tx: Option<oneshot::Sender<Result<SaveEventStatus, Error>>>,
     },
+    SaveMany {
+        result: Result<Vec<SaveEventStatus>, Error>,
+        tx: Option<oneshot::Sender<Result<Vec<SaveEventStatus>, Error>>>,
+    },
     Delete {
         result: Result<(), Error>,
         tx: Option<oneshot::Sender<Result<(), Error>>>,
@@ -44,6 +48,10 @@ enum OperationResult {
         result: Result<(), Error>,
         tx: Option<oneshot::Sender<Result<(), Error>>>,
     },
+    Remediate {
+        result: Result<(), Error>,
+        tx: Option<oneshot::Sender<Result<(), Error>>>,
+    },
 }
 
 impl OperationResult {
@@ -68,6 +76,15 @@ impl OperationResult {
                     tracing::error!(error = %e, "Event save failed in batch");
                 }
             }
+            Self::SaveMany { result, tx } => {
+                if let Some(tx) = tx {
+                    if tx.send(result).is_err() {
+                        tracing::debug!("Failed to send save events result: receiver dropped");
+                    }
+                } else if let Err(e) = result {
+                    tracing::error!(error = %e, "Events save failed in batch");
+                }
+            }
             Self::Delete { result, tx } => {
                 if let Some(tx) = tx {
                     if tx.send(result).is_err() {
@@ -86,6 +103,15 @@ impl OperationResult {
                     tracing::error!(error = %e, "Wipe operation failed in batch");
                 }
             }
+            Self::Remediate { result, tx } => {
+                if let Some(tx) = tx {
+                    if tx.send(result).is_err() {
+                        tracing::debug!("Failed to send remediation result: receiver dropped");
+                    }
+                } else if let Err(e) = result {
+                    tracing::error!(error = %e, "Remediation failed in batch");
+                }
+            }
         }
     }
 }
@@ -98,6 +124,10 @@ enum IngesterOperation {
         event: Event,
         tx: Option<oneshot::Sender<Result<SaveEventStatus, Error>>>,
     },
+    SaveEvents {
+        events: Vec<Event>,
+        tx: Option<oneshot::Sender<Result<Vec<SaveEventStatus>, Error>>>,
+    },
     Delete {
         filter: Filter,
         tx: Option<oneshot::Sender<Result<(), Error>>>,
@@ -105,6 +135,20 @@ enum IngesterOperation {
     Wipe {
         tx: Option<oneshot::Sender<Result<(), Error>>>,
     },
+    WipeExpired {
+        now: Timestamp,
+        tx: Option<oneshot::Sender<Result<(), Error>>>,
+    },
+    Prune {
+        older_than: Timestamp,
+        tx: Option<oneshot::Sender<Result<(), Error>>>,
+    },
+    Remediate {
+        ids: Vec<EventId>,
+        action: VerifyAction,
+        cursor: Option<EventId>,
+        tx: Option<oneshot::Sender<Result<(), Error>>>,
+    },
 }
 
 impl IngesterOperation {
@@ -119,14 +163,24 @@ impl IngesterOperation {
                 result: Err(error),
                 tx,
             },
-            Self::Delete { tx, .. } => OperationResult::Delete {
+            Self::SaveEvents { tx, .. } => OperationResult::SaveMany {
                 result: Err(error),
                 tx,
             },
+            Self::Delete { tx, .. } | Self::WipeExpired { tx, .. } | Self::Prune { tx, .. } => {
+                OperationResult::Delete {
+                    result: Err(error),
+                    tx,
+                }
+            }
             Self::Wipe { tx } => OperationResult::Wipe {
                 result: Err(error),
                 tx,
             },
+            Self::Remediate { tx, .. } => OperationResult::Remediate {
+                result: Err(error),
+                tx,
+            },
         }
     }
 }
@@ -159,6 +213,20 @@ impl IngesterItem {
         (item, rx)
     }
 
+    #[must_use]
+    pub(super) fn save_events_with_feedback(
+        events: Vec<Event>,
+    ) -> (Self, oneshot::Receiver<Result<Vec<SaveEventStatus>, Error>>) {
+        let (tx, rx) = oneshot::channel();
+        let item: Self = Self {
+            operation: IngesterOperation::SaveEvents {
+                events,
+                tx: Some(tx),
+            },
+        };
+        (item, rx)
+    }
+
     #[must_use]
     pub(super) fn delete_with_feedback(
         filter: Filter,
@@ -181,6 +249,49 @@ impl IngesterItem {
         };
         (item, rx)
     }
+
+    #[must_use]
+    pub(super) fn wipe_expired_with_feedback(
+        now: Timestamp,
+    ) -> (Self, oneshot::Receiver<Result<(), Error>>) {
+        let (tx, rx) = oneshot::channel();
+        let item: Self = Self {
+            operation: IngesterOperation::WipeExpired { now, tx: Some(tx) },
+        };
+        (item, rx)
+    }
+
+    #[must_use]
+    pub(super) fn prune_with_feedback(
+        older_than: Timestamp,
+    ) -> (Self, oneshot::Receiver<Result<(), Error>>) {
+        let (tx, rx) = oneshot::channel();
+        let item: Self = Self {
+            operation: IngesterOperation::Prune {
+                older_than,
+                tx: Some(tx),
+            },
+        };
+        (item, rx)
+    }
+
+    #[must_use]
+    pub(super) fn remediate_with_feedback(
+        ids: Vec<EventId>,
+        action: VerifyAction,
+        cursor: Option<EventId>,
+    ) -> (Self, oneshot::Receiver<Result<(), Error>>) {
+        let (tx, rx) = oneshot::channel();
+        let item: Self = Self {
+            operation: IngesterOperation::Remediate {
+                ids,
+                action,
+                cursor,
+                tx: Some(tx),
+            },
+        };
+        (item, rx)
+    }
 }
 
 #[derive(Debug)]
@@ -249,6 +360,21 @@ impl Ingester {
     ) where
         I: Iterator<Item = IngesterItem>,
     {
+        let batch: Vec<IngesterItem> = batch.collect();
+
+        // The time buckets can't be created in the batch transaction, since it may be aborted.
+        // If the creation fails, the saves of the missing buckets fail and abort the batch.
+        let timestamps = batch.iter().flat_map(|item| match &item.operation {
+            IngesterOperation::SaveEvent { event, .. } => vec![event.created_at],
+            IngesterOperation::SaveEvents { events, .. } => {
+                events.iter().map(|event| event.created_at).collect()
+            }
+            _ => Vec::new(),
+        });
+        if let Err(e) = self.db.create_partitions(timestamps) {
+            tracing::error!(error = %e, "Failed to create the time buckets");
+        }
+
         // Note: We're only using a write transaction here since LMDB doesn't require
         // a separate read transaction for queries within a write transaction
         let mut write_txn = match self.db.write_txn() {
@@ -268,7 +394,7 @@ impl Ingester {
             }
         };
 
-        let mut batch_iter = batch.peekable();
+        let mut batch_iter = batch.into_iter().peekable();
 
         // Process all operations in the batch
         while let Some(item) = batch_iter.next() {
@@ -280,6 +406,10 @@ impl Ingester {
                     tracing::error!(error = %e, "Failed to save event, aborting batch");
                     true
                 }
+                OperationResult::SaveMany { result: Err(e), .. } => {
+                    tracing::error!(error = %e, "Failed to save events, aborting batch");
+                    true
+                }
                 OperationResult::Delete { result: Err(e), .. } => {
                     tracing::error!(error = %e, "Failed to delete event, aborting batch");
                     true
@@ -336,14 +466,39 @@ impl Ingester {
                 let result = self.db.save_event_with_txn(txn, fbb, &event);
                 OperationResult::Save { result, tx }
             }
+            IngesterOperation::SaveEvents { events, tx } => {
+                // The rejections (i.e., duplicates) are reported per event, without aborting
+                let result = events
+                    .iter()
+                    .map(|event| self.db.save_event_with_txn(txn, fbb, event))
+                    .collect();
+                OperationResult::SaveMany { result, tx }
+            }
             IngesterOperation::Delete { filter, tx } => {
                 let result = self.db.delete(txn, filter);
                 OperationResult::Delete { result, tx }
             }
+            IngesterOperation::WipeExpired { now, tx } => {
+                let result = self.db.wipe_expired(txn, now);
+                OperationResult::Delete { result, tx }
+            }
+            IngesterOperation::Prune { older_than, tx } => {
+                let result = self.db.prune(txn, older_than);
+                OperationResult::Delete { result, tx }
+            }
             IngesterOperation::Wipe { tx } => {
                 let result = self.db.wipe(txn);
                 OperationResult::Wipe { result, tx }
             }
+            IngesterOperation::Remediate {
+                ids,
+                action,
+                cursor,
+                tx,
+            } => {
+                let result = self.db.remediate(txn, &ids, action, cursor.as_ref());
+                OperationResult::Remediate { result, tx }
+            }
         }
     }
 }
@@ -357,10 +512,16 @@ fn mark_all_as_failed(results: &mut [OperationResult]) {
                 *res = Err(Error::BatchTransactionFailed)
             }
             OperationResult::Save { result: res, .. } => *res = Err(Error::BatchTransactionFailed),
+            OperationResult::SaveMany { result: res, .. } => {
+                *res = Err(Error::BatchTransactionFailed)
+            }
             OperationResult::Delete { result: res, .. } => {
                 *res = Err(Error::BatchTransactionFailed)
             }
             OperationResult::Wipe { result: res, .. } => *res = Err(Error::BatchTransactionFailed),
+            OperationResult::Remediate { result: res, .. } => {
+                *res = Err(Error::BatchTransactionFailed)
+            }
         }
     }
 }
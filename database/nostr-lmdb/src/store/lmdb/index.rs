@@ -9,9 +9,15 @@ use nostr::event::borrow::EventBorrow;
 use nostr::nips::nip01::Coordinate;
 use nostr::{EventId, PublicKey, SingleLetterTag, Timestamp};
 
+use crate::store::filter;
+
 const CREATED_AT_BE: usize = 8;
 const KIND_BE: usize = 2;
 const TAG_VALUE_PAD_LEN: usize = 182;
+/// Length of the `reverse_created_at(8)` + `event_id(32)` suffix of the time-ordered index keys
+pub(super) const CREATED_AT_ID_LEN: usize = CREATED_AT_BE + EventId::LEN;
+/// Length of the expiration index keys
+pub(super) const EXPIRATION_ID_LEN: usize = CREATED_AT_BE + EventId::LEN;
 
 // TODO: use fixed-size arrays instead of vectors
 pub(super) struct TagIndexKeySet {
@@ -23,11 +29,13 @@ pub(super) struct TagIndexKeySet {
 // TODO: use fixed-size arrays instead of vectors
 pub(super) struct EventIndexKeys {
     pub(super) id: [u8; EventId::LEN],
+    pub(super) created_at: Timestamp,
     pub(super) ci_index: Vec<u8>,
     pub(super) akc_index: Vec<u8>,
     pub(super) ac_index: Vec<u8>,
     pub(super) kc_index: Vec<u8>,
     pub(super) tags: Vec<TagIndexKeySet>,
+    pub(super) expiration: Option<[u8; EXPIRATION_ID_LEN]>,
 }
 
 impl EventIndexKeys {
@@ -80,17 +88,31 @@ impl EventIndexKeys {
             })
             .collect();
 
+        // Index by expiration (with id)
+        let expiration: Option<[u8; EXPIRATION_ID_LEN]> =
+            filter::expiration(&event).map(|expiration| make_expiration_key(expiration, event.id));
+
         Self {
             id: *event.id,
+            created_at: event.created_at,
             ci_index,
             akc_index,
             ac_index,
             kc_index,
             tags,
+            expiration,
         }
     }
 }
 
+/// Check if the tag value is unambiguously stored in the index keys
+///
+/// A value filling the whole padding is stored like the longer values with the same prefix.
+#[inline]
+pub(super) fn is_tag_value_covered(tag_value: &str) -> bool {
+    tag_value.len() < TAG_VALUE_PAD_LEN
+}
+
 /// Reverse created_at and convert `u64` to big-endian byte order
 #[inline]
 fn reverse_and_conv_to_be64(created_at: Timestamp) -> [u8; 8] {
@@ -124,6 +146,28 @@ pub fn make_ci_index_key(created_at: Timestamp, event_id: &[u8; EventId::LEN]) -
     key
 }
 
+/// Make Expiration + ID index key
+///
+/// ## Structure
+///
+/// `expiration(8)` + `event_id(32)`
+pub fn make_expiration_key(
+    expiration: Timestamp,
+    event_id: &[u8; EventId::LEN],
+) -> [u8; EXPIRATION_ID_LEN] {
+    let mut key: [u8; EXPIRATION_ID_LEN] = [0; EXPIRATION_ID_LEN];
+    key[..CREATED_AT_BE].copy_from_slice(&expiration.as_secs().to_be_bytes());
+    key[CREATED_AT_BE..].copy_from_slice(event_id);
+    key
+}
+
+/// Get the expiration from the Expiration + ID index key
+#[inline]
+pub(super) fn expiration_from_key(key: &[u8]) -> Option<Timestamp> {
+    let expiration: [u8; CREATED_AT_BE] = key.get(..CREATED_AT_BE)?.try_into().ok()?;
+    Some(Timestamp::from_secs(u64::from_be_bytes(expiration)))
+}
+
 /// Make Tag + CreatedAt + ID index key (for looking up event by `tag`)
 ///
 /// ## Structure
@@ -276,3 +320,15 @@ pub fn make_coordinate_index_key(coordinate: &Coordinate) -> Vec<u8> {
 
     key
 }
+
+/// Make deletion request key
+///
+/// ## Structure
+///
+/// `event_id(32)` + `author(32)`
+pub fn make_deletion_request_key(event_id: &EventId, author: &PublicKey) -> [u8; 64] {
+    let mut key: [u8; 64] = [0u8; 64];
+    key[..EventId::LEN].copy_from_slice(event_id.as_bytes());
+    key[EventId::LEN..].copy_from_slice(author.as_bytes());
+    key
+}
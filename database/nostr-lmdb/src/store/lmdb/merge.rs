@@ -0,0 +1,81 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use heed::RoRange;
+use heed::types::Bytes;
+
+use super::index::CREATED_AT_ID_LEN;
+use crate::store::error::Error;
+
+/// Next key suffix, ID and range index
+type Head<'a> = (&'a [u8], &'a [u8], usize);
+
+/// Lazy merge of time-ordered index ranges
+///
+/// All the time-ordered index keys end with `reverse_created_at(8)` + `event_id(32)`,
+/// so comparing the key suffixes yields the IDs in the same order of the events (newest first).
+///
+/// The ranges are read only while iterating.
+pub(super) struct MergedIndexRanges<'a> {
+    ranges: Vec<RoRange<'a, Bytes, Bytes>>,
+    /// Head of every non-exhausted range
+    heads: BinaryHeap<Reverse<Head<'a>>>,
+    /// Last yielded ID
+    ///
+    /// The same event can be indexed in more ranges (i.e., multiple tag values):
+    /// having the same key suffix, the duplicates are always adjacent.
+    last: Option<&'a [u8]>,
+}
+
+impl<'a> MergedIndexRanges<'a> {
+    pub(super) fn new(ranges: Vec<RoRange<'a, Bytes, Bytes>>) -> Result<Self, Error> {
+        let mut merged: Self = Self {
+            heads: BinaryHeap::with_capacity(ranges.len()),
+            ranges,
+            last: None,
+        };
+
+        for index in 0..merged.ranges.len() {
+            merged.advance(index)?;
+        }
+
+        Ok(merged)
+    }
+
+    /// Read the next key of the range
+    fn advance(&mut self, index: usize) -> Result<(), Error> {
+        if let Some(res) = self.ranges[index].next() {
+            let (key, id) = res?;
+            let suffix: &[u8] = &key[key.len().saturating_sub(CREATED_AT_ID_LEN)..];
+            self.heads.push(Reverse((suffix, id, index)));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for MergedIndexRanges<'a> {
+    type Item = Result<&'a [u8], Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Reverse((_, id, index)) = self.heads.pop()?;
+
+            if let Err(e) = self.advance(index) {
+                return Some(Err(e));
+            }
+
+            // Skip the duplicates
+            if self.last == Some(id) {
+                continue;
+            }
+
+            self.last = Some(id);
+            return Some(Ok(id));
+        }
+    }
+}
@@ -4,7 +4,7 @@
 // Distributed under the MIT software license
 
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::iter;
 use std::ops::Bound;
 
@@ -13,22 +13,36 @@ use heed::types::{Bytes, U64, Unit};
 use heed::{Database, Env, EnvFlags, EnvOpenOptions, RoRange, RoTxn, RwTxn};
 use nostr::prelude::*;
 use nostr_database::flatbuffers::FlatBufferDecodeBorrowed;
-use nostr_database::{FlatBufferBuilder, FlatBufferEncode, RejectedReason, SaveEventStatus};
+use nostr_database::{
+    FlatBufferBuilder, RejectedReason, SaveEventStatus, VerifyAction, VerifyReport,
+};
 
 mod index;
+mod merge;
+mod partition;
 
 use self::index::EventIndexKeys;
+use self::merge::MergedIndexRanges;
+use self::partition::{BUCKETS_DBS, Buckets, PARTITION_DBS, Partition};
 use super::error::{Error, MigrationError};
-use super::filter::DatabaseFilter;
-use crate::NostrLmdbBuilder;
+use super::filter::{self, DatabaseFilter};
+use crate::{LmdbLayout, NostrLmdbBuilder};
 
 const EVENT_ID_ALL_ZEROS: [u8; 32] = [0; 32];
 const EVENT_ID_ALL_255: [u8; 32] = [255; 32];
 
 /// Current database schema version
-const DB_VERSION: u64 = 2;
+const DB_VERSION: u64 = 3;
 const DB_VERSION_KEY: &[u8] = b"db_version";
 
+/// Width of the time buckets in days, `0` for the flat layout
+const LAYOUT_KEY: &[u8] = b"layout";
+
+const VERIFY_CURSOR_KEY: &[u8] = b"verify_cursor";
+
+/// Stored event: ID and raw value
+type RawEntry<'a> = (&'a [u8], &'a [u8]);
+
 #[derive(Debug)]
 enum QueryFilterPattern {
     Ids,
@@ -76,49 +90,58 @@ pub(crate) struct LmdbOptions {
     pub(crate) process_nip62: bool,
     /// Whether to process event deletion request (NIP-09) events
     pub(crate) process_nip09: bool,
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    pub(crate) process_nip40: bool,
     /// Relay URL for relay-specific request to vanish (NIP-62).
     pub(crate) relay_url: Option<RelayUrl>,
 }
 
+/// Partitions of the events, depending on the layout
+#[derive(Debug, Clone)]
+enum Partitions {
+    Flat(Partition),
+    Buckets(Buckets),
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Lmdb {
     /// Database options
     options: LmdbOptions,
     /// LMDB env
     env: Env,
-    /// Events
-    events: Database<Bytes, Bytes>, // Event ID, Event
-    /// CreatedAt + ID index
-    ci_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Tag + CreatedAt + ID index
-    tc_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Author + CreatedAt + ID index
-    ac_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Author + Kind + CreatedAt + ID index
-    akc_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Author + Tag + CreatedAt + ID index
-    atc_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Kind + CreatedAt + ID index
-    kc_index: Database<Bytes, Bytes>, // <Index>, Event ID
-    /// Kind + Tag + CreatedAt + ID index
-    ktc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Events and indexes
+    partitions: Partitions,
     /// Deleted IDs
     deleted_ids: Database<Bytes, Unit>, // Event ID
     /// Deleted coordinates
     deleted_coordinates: Database<Bytes, U64<NativeEndian>>, // Coordinate, UNIX timestamp
+    /// Deletion requests for events not yet received
+    deletion_requests: Database<Bytes, Unit>, // Event ID + Public key of the deletion author
     /// Vanished public keys
     vanished_public_keys: Database<Bytes, Unit>, // Public key
     /// Database metadata (version, etc)
     metadata: Database<Bytes, U64<NativeEndian>>, // Key, Value
+    /// Quarantined events (failed verification)
+    quarantine: Database<Bytes, Bytes>, // Event ID, Raw value
+    /// Cursors of the resumable jobs
+    cursors: Database<Bytes, Bytes>, // Key, Event ID
 }
 
 impl Lmdb {
     pub(super) fn from_builder(builder: NostrLmdbBuilder) -> Result<Self, Error> {
+        // Each bucket of the partitioned layout has its own databases
+        let max_dbs: u32 = match builder.layout {
+            LmdbLayout::Flat => 16,
+            LmdbLayout::Partitioned(..) => {
+                16 + BUCKETS_DBS + builder.max_buckets.saturating_mul(PARTITION_DBS)
+            }
+        };
+
         // Construct LMDB env
         let env: Env = unsafe {
             EnvOpenOptions::new()
                 .flags(EnvFlags::NO_TLS)
-                .max_dbs(12 + builder.additional_dbs)
+                .max_dbs(max_dbs.saturating_add(builder.additional_dbs))
                 .max_readers(builder.max_readers)
                 .map_size(builder.map_size)
                 .open(builder.path)?
@@ -128,45 +151,38 @@ impl Lmdb {
         let mut txn = env.write_txn()?;
 
         // Open/Create maps
-        let events = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .create(&mut txn)?;
-        let ci_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("ci")
-            .create(&mut txn)?;
-        let tc_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("tci")
-            .create(&mut txn)?;
-        let ac_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("aci")
-            .create(&mut txn)?;
-        let akc_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("akci")
-            .create(&mut txn)?;
-        let atc_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("atci")
-            .create(&mut txn)?;
-        let kc_index = env
-            .database_options()
-            .types::<Bytes, Bytes>()
-            .name("kci")
-            .create(&mut txn)?;
-        let ktc_index = env
+        let metadata = env
             .database_options()
-            .types::<Bytes, Bytes>()
-            .name("ktci")
+            .types::<Bytes, U64<NativeEndian>>()
+            .name("metadata")
             .create(&mut txn)?;
+
+        // The layout is chosen when the database is created
+        let layout: LmdbLayout = match metadata.get(&txn, LAYOUT_KEY)? {
+            Some(days) => LmdbLayout::from_days(days),
+            // Created before the layouts were introduced
+            None if is_existing_flat_database(&env, &txn)? => LmdbLayout::Flat,
+            None => {
+                metadata.put(&mut txn, LAYOUT_KEY, &builder.layout.days())?;
+                metadata.put(&mut txn, DB_VERSION_KEY, &DB_VERSION)?;
+                builder.layout
+            }
+        };
+
+        if layout.days() != builder.layout.days() {
+            return Err(Error::LayoutMismatch(layout));
+        }
+
+        let partitions: Partitions = match layout {
+            LmdbLayout::Flat => Partitions::Flat(Partition::flat(&env, &mut txn)?),
+            LmdbLayout::Partitioned(..) => Partitions::Buckets(Buckets::open(
+                &env,
+                &mut txn,
+                layout.days() * 86_400,
+                builder.max_buckets as usize,
+            )?),
+        };
+
         let deleted_ids = env
             .database_options()
             .types::<Bytes, Unit>()
@@ -177,15 +193,25 @@ impl Lmdb {
             .types::<Bytes, U64<NativeEndian>>()
             .name("deleted-coordinates")
             .create(&mut txn)?;
+        let deletion_requests = env
+            .database_options()
+            .types::<Bytes, Unit>()
+            .name("deletion-requests")
+            .create(&mut txn)?;
         let vanished_public_keys = env
             .database_options()
             .types::<Bytes, Unit>()
             .name("vanished-public-keys")
             .create(&mut txn)?;
-        let metadata = env
+        let quarantine = env
             .database_options()
-            .types::<Bytes, U64<NativeEndian>>()
-            .name("metadata")
+            .types::<Bytes, Bytes>()
+            .name("quarantine")
+            .create(&mut txn)?;
+        let cursors = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name("cursors")
             .create(&mut txn)?;
 
         // Commit changes
@@ -194,24 +220,21 @@ impl Lmdb {
         let options = LmdbOptions {
             process_nip62: builder.process_nip62,
             process_nip09: builder.process_nip09,
+            process_nip40: builder.process_nip40,
             relay_url: builder.relay_url,
         };
 
         let lmdb = Self {
             options,
             env,
-            events,
-            ci_index,
-            tc_index,
-            ac_index,
-            akc_index,
-            atc_index,
-            kc_index,
-            ktc_index,
+            partitions,
             deleted_ids,
             deleted_coordinates,
+            deletion_requests,
             vanished_public_keys,
             metadata,
+            quarantine,
+            cursors,
         };
 
         // Check and run migrations if needed
@@ -235,9 +258,16 @@ impl Lmdb {
                     DB_VERSION
                 );
 
-                // Run migrations sequentially
-                if current_version < 2 {
-                    self.migrate_v1_to_v2(&mut txn)?;
+                // Run migrations sequentially.
+                // The partitioned layout has always been created at the current version.
+                if let Partitions::Flat(partition) = &self.partitions {
+                    if current_version < 2 {
+                        self.migrate_v1_to_v2(&mut txn, partition)?;
+                    }
+
+                    if current_version < 3 {
+                        self.migrate_v2_to_v3(&mut txn, partition)?;
+                    }
                 }
 
                 // Update version
@@ -263,16 +293,16 @@ impl Lmdb {
     }
 
     /// Migrate from version 1 to version 2: Build kc_index
-    fn migrate_v1_to_v2(&self, txn: &mut RwTxn) -> Result<(), Error> {
+    fn migrate_v1_to_v2(&self, txn: &mut RwTxn, partition: &Partition) -> Result<(), Error> {
         tracing::info!("Building kc_index for existing events...");
 
-        let event_count = self.events.len(txn)?;
+        let event_count = partition.events.len(txn)?;
         tracing::info!("Processing {} events", event_count);
 
         // Collect all kc_index keys first to avoid borrow conflicts
         let kc_indexes: Vec<(Vec<u8>, [u8; 32])> = {
             let mut indexes = Vec::with_capacity(event_count as usize);
-            for result in self.events.iter(txn)? {
+            for result in partition.events.iter(txn)? {
                 let (_id, event_bytes) = result?;
 
                 // Decode event
@@ -288,13 +318,41 @@ impl Lmdb {
 
         // Now insert all the indexes
         for (kc_index_key, event_id) in kc_indexes {
-            self.kc_index.put(txn, &kc_index_key, &event_id)?;
+            partition.kc_index.put(txn, &kc_index_key, &event_id)?;
         }
 
         tracing::info!("kc_index built successfully");
         Ok(())
     }
 
+    /// Migrate from version 2 to version 3: Build expirations index
+    fn migrate_v2_to_v3(&self, txn: &mut RwTxn, partition: &Partition) -> Result<(), Error> {
+        tracing::info!("Building expirations index for existing events...");
+
+        // Collect all the keys first to avoid borrow conflicts
+        let keys: Vec<[u8; index::EXPIRATION_ID_LEN]> = {
+            let mut keys = Vec::new();
+            for result in partition.events.iter(txn)? {
+                let (_id, event_bytes) = result?;
+
+                // Decode event
+                if let Ok(event) = EventBorrow::decode(event_bytes) {
+                    if let Some(expiration) = filter::expiration(&event) {
+                        keys.push(index::make_expiration_key(expiration, event.id));
+                    }
+                }
+            }
+            keys
+        };
+
+        for key in keys {
+            partition.expirations.put(txn, &key, &())?;
+        }
+
+        tracing::info!("expirations index built successfully");
+        Ok(())
+    }
+
     /// Get a read transaction
     ///
     /// This should never block the current thread
@@ -311,6 +369,54 @@ impl Lmdb {
         Ok(self.env.write_txn()?)
     }
 
+    /// Get the partitions overlapping the time range, from the newest
+    fn partitions(&self, since: Timestamp, until: Timestamp) -> Vec<Partition> {
+        match &self.partitions {
+            Partitions::Flat(partition) => vec![*partition],
+            Partitions::Buckets(buckets) => buckets.overlapping(since, until),
+        }
+    }
+
+    /// Get all the partitions
+    fn all_partitions(&self) -> Vec<Partition> {
+        match &self.partitions {
+            Partitions::Flat(partition) => vec![*partition],
+            Partitions::Buckets(buckets) => buckets.all(),
+        }
+    }
+
+    /// Get the partition of a stored event
+    fn partition_of(&self, txn: &RoTxn, event_id: &[u8]) -> Result<Option<Partition>, Error> {
+        match &self.partitions {
+            Partitions::Flat(partition) => Ok(Some(*partition)),
+            Partitions::Buckets(buckets) => buckets.locate(txn, event_id),
+        }
+    }
+
+    /// Get the partition of the events created at `created_at`
+    fn partition_at(&self, created_at: Timestamp) -> Result<Partition, Error> {
+        match &self.partitions {
+            Partitions::Flat(partition) => Ok(*partition),
+            // Not created before the transaction: the maximum number of buckets has been reached
+            Partitions::Buckets(buckets) => buckets
+                .get(buckets.bucket(created_at))
+                .ok_or(Error::TooManyBuckets),
+        }
+    }
+
+    /// Create the partitions to store the events created at the `timestamps`
+    ///
+    /// Must be called before acquiring the write transaction storing the events.
+    pub(crate) fn create_partitions<I>(&self, timestamps: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Timestamp>,
+    {
+        match &self.partitions {
+            Partitions::Flat(..) => Ok(()),
+            Partitions::Buckets(buckets) => buckets.create(&self.env, timestamps),
+        }
+    }
+
     /// Store and index the event
     pub(crate) fn store(
         &self,
@@ -318,26 +424,11 @@ impl Lmdb {
         fbb: &mut FlatBufferBuilder,
         event: &Event,
     ) -> Result<(), Error> {
-        // Store event
-        self.events
-            .put(txn, event.id.as_bytes(), event.encode(fbb))?;
+        let partition: Partition = self.partition_at(event.created_at)?;
+        partition.store(txn, fbb, event)?;
 
-        // Index event
-        let event: EventBorrow = EventBorrow::from(event);
-        let index: EventIndexKeys = EventIndexKeys::new(event);
-        self.index_event(txn, index)
-    }
-
-    fn index_event(&self, txn: &mut RwTxn, index: EventIndexKeys) -> Result<(), Error> {
-        self.ci_index.put(txn, &index.ci_index, &index.id)?;
-        self.akc_index.put(txn, &index.akc_index, &index.id)?;
-        self.ac_index.put(txn, &index.ac_index, &index.id)?;
-        self.kc_index.put(txn, &index.kc_index, &index.id)?;
-
-        for tag in index.tags.into_iter() {
-            self.atc_index.put(txn, &tag.atc_index, &index.id)?;
-            self.ktc_index.put(txn, &tag.ktc_index, &index.id)?;
-            self.tc_index.put(txn, &tag.tc_index, &index.id)?;
+        if let Partitions::Buckets(buckets) = &self.partitions {
+            buckets.track(txn, event)?;
         }
 
         Ok(())
@@ -361,76 +452,219 @@ impl Lmdb {
     ///
     /// It only performs the mechanical deletion from all indexes.
     fn remove(&self, txn: &mut RwTxn, index: &EventIndexKeys) -> Result<(), Error> {
-        self.events.delete(txn, &index.id)?;
-        self.ci_index.delete(txn, &index.ci_index)?;
-        self.akc_index.delete(txn, &index.akc_index)?;
-        self.ac_index.delete(txn, &index.ac_index)?;
-        self.kc_index.delete(txn, &index.kc_index)?;
+        let partition: Partition = self.partition_at(index.created_at)?;
+        partition.remove(txn, index)?;
 
-        // Delete tag indexes
-        for tag in &index.tags {
-            self.atc_index.delete(txn, &tag.atc_index)?;
-            self.ktc_index.delete(txn, &tag.ktc_index)?;
-            self.tc_index.delete(txn, &tag.tc_index)?;
+        if let Partitions::Buckets(buckets) = &self.partitions {
+            buckets.untrack(txn, &index.id)?;
         }
 
         Ok(())
     }
 
     pub(crate) fn wipe(&self, txn: &mut RwTxn) -> Result<(), Error> {
-        // Wipe events
-        self.events.clear(txn)?;
-
-        // Wipe indexes
-        self.wipe_indexes(txn)?;
+        // Wipe events and indexes
+        for partition in self.all_partitions().into_iter() {
+            partition.clear(txn)?;
+        }
 
-        Ok(())
-    }
+        if let Partitions::Buckets(buckets) = &self.partitions {
+            buckets.clear(txn)?;
+        }
 
-    fn wipe_indexes(&self, txn: &mut RwTxn) -> Result<(), Error> {
-        self.ci_index.clear(txn)?;
-        self.tc_index.clear(txn)?;
-        self.ac_index.clear(txn)?;
-        self.akc_index.clear(txn)?;
-        self.kc_index.clear(txn)?;
-        self.atc_index.clear(txn)?;
-        self.ktc_index.clear(txn)?;
+        // Wipe tombstones
         self.deleted_ids.clear(txn)?;
         self.deleted_coordinates.clear(txn)?;
+        self.deletion_requests.clear(txn)?;
         self.vanished_public_keys.clear(txn)?;
+
+        // Wipe quarantined events and cursors
+        self.quarantine.clear(txn)?;
+        self.cursors.clear(txn)?;
+
         Ok(())
     }
 
     pub(super) fn reindex(&self, txn: &mut RwTxn) -> Result<(), Error> {
-        // First, wipe all indexes
-        self.wipe_indexes(txn)?;
+        for partition in self.all_partitions().into_iter() {
+            partition.reindex(txn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the events created before `older_than`
+    ///
+    /// With the partitioned layout, only the buckets ending before `older_than` are dropped, at once.
+    pub(crate) fn prune(&self, txn: &mut RwTxn, older_than: Timestamp) -> Result<(), Error> {
+        match &self.partitions {
+            Partitions::Flat(..) => match older_than.as_secs().checked_sub(1) {
+                Some(until) => self.delete(txn, Filter::new().until(Timestamp::from_secs(until))),
+                None => Ok(()),
+            },
+            Partitions::Buckets(buckets) => buckets.prune(txn, older_than),
+        }
+    }
+
+    /// Verify a batch of stored events, starting after the `cursor`.
+    ///
+    /// Returns the report of the batch and the last visited key.
+    pub(crate) fn verify_batch(
+        &self,
+        txn: &RoTxn,
+        cursor: Option<&EventId>,
+        size: usize,
+        signatures: bool,
+    ) -> Result<(VerifyReport, Option<EventId>), Error> {
+        // Visit the events in the order of the IDs
+        let entries: Box<dyn Iterator<Item = Result<RawEntry, Error>>> = match &self.partitions {
+            Partitions::Flat(partition) => {
+                let range = match cursor {
+                    Some(cursor) => (
+                        Bound::Excluded(cursor.as_bytes().as_slice()),
+                        Bound::Unbounded,
+                    ),
+                    None => (Bound::Unbounded, Bound::Unbounded),
+                };
+                Box::new(
+                    partition
+                        .events
+                        .range(txn, &range)?
+                        .map(|res| res.map_err(Error::from)),
+                )
+            }
+            Partitions::Buckets(buckets) => {
+                Box::new(buckets.ids(txn, cursor)?.filter_map(move |res| {
+                    let lookup = || -> Result<Option<RawEntry>, Error> {
+                        let (id, partition) = res?;
+                        match partition {
+                            Some(partition) => Ok(partition.events.get(txn, id)?.map(|v| (id, v))),
+                            // Bucket not opened
+                            None => Ok(None),
+                        }
+                    };
+                    lookup().transpose()
+                }))
+            }
+        };
+
+        let mut report: VerifyReport = VerifyReport::default();
+        let mut last: Option<EventId> = None;
+
+        for result in entries {
+            let (key, value) = result?;
+
+            // The unnamed database also holds the names of the other databases: skip them.
+            let id: EventId = match EventId::from_slice(key) {
+                Ok(id) => id,
+                Err(..) => continue,
+            };
+
+            if report.checked >= size {
+                break;
+            }
+
+            last = Some(id);
+            report.checked += 1;
+
+            let event: Event = match EventBorrow::decode(value) {
+                Ok(event) => event.into_owned(),
+                Err(..) => {
+                    report.undecodable.push(id);
+                    continue;
+                }
+            };
+
+            // The ID must match both the key and the content
+            if event.id != id || !event.verify_id() {
+                report.id_mismatches.push(id);
+                continue;
+            }
+
+            if signatures && !event.verify_signature() {
+                report.invalid_signatures.push(id);
+            }
+        }
+
+        // Less events than requested: reached the end
+        report.completed = report.checked < size;
+
+        Ok((report, last))
+    }
 
-        // Collect indexes
-        // TODO: avoid this allocation
-        let size: u64 = self.events.len(txn)?;
-        let mut indexes: Vec<EventIndexKeys> = Vec::with_capacity(size as usize);
+    /// Quarantine or delete the events that failed the verification
+    /// and update the verification cursor.
+    pub(crate) fn remediate(
+        &self,
+        txn: &mut RwTxn,
+        ids: &[EventId],
+        action: VerifyAction,
+        cursor: Option<&EventId>,
+    ) -> Result<(), Error> {
+        if action != VerifyAction::Report {
+            for id in ids.iter() {
+                let partition: Partition = match self.partition_of(txn, id.as_bytes())? {
+                    Some(partition) => partition,
+                    None => continue,
+                };
+
+                // Copy the raw value, since it may not be decodable
+                let raw: Vec<u8> = match partition.events.get(txn, id.as_bytes())? {
+                    Some(raw) => raw.to_vec(),
+                    None => continue,
+                };
+
+                if action == VerifyAction::Quarantine {
+                    self.quarantine.put(txn, id.as_bytes(), &raw)?;
+                }
 
-        for result in self.events.iter(txn)? {
-            let (_id, event) = result?;
+                match EventBorrow::decode(&raw) {
+                    Ok(event) => {
+                        let index: EventIndexKeys = EventIndexKeys::new(event);
+                        self.remove(txn, &index)?;
+                    }
+                    Err(..) => {
+                        // Index keys can't be rebuilt, so scan the indexes
+                        partition.events.delete(txn, id.as_bytes())?;
+                        partition.remove_dangling_index_entries(txn, id)?;
 
-            // Decode event
-            if let Ok(event) = EventBorrow::decode(event) {
-                // Build indexes
-                let index: EventIndexKeys = EventIndexKeys::new(event);
-                indexes.push(index);
+                        if let Partitions::Buckets(buckets) = &self.partitions {
+                            buckets.untrack(txn, id.as_bytes())?;
+                        }
+                    }
+                }
             }
         }
 
-        for index in indexes.into_iter() {
-            self.index_event(txn, index)?;
+        match cursor {
+            Some(cursor) => self
+                .cursors
+                .put(txn, VERIFY_CURSOR_KEY, cursor.as_bytes())?,
+            None => {
+                self.cursors.delete(txn, VERIFY_CURSOR_KEY)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Get the stored verification cursor
+    ///
+    /// A malformed cursor is ignored, restarting the sweep from the beginning.
+    pub(crate) fn verify_cursor(&self, txn: &RoTxn) -> Result<Option<EventId>, Error> {
+        Ok(self
+            .cursors
+            .get(txn, VERIFY_CURSOR_KEY)?
+            .and_then(|cursor| EventId::from_slice(cursor).ok()))
+    }
+
     #[inline]
     pub(crate) fn has_event(&self, txn: &RoTxn, event_id: &EventId) -> Result<bool, Error> {
-        Ok(self.get_event_by_id(txn, event_id.as_bytes())?.is_some())
+        // Don't decode the event
+        match self.partition_of(txn, event_id.as_bytes())? {
+            Some(partition) => Ok(partition.events.get(txn, event_id.as_bytes())?.is_some()),
+            None => Ok(false),
+        }
     }
 
     /// Save event with transaction support - uses single transaction for batch consistency
@@ -454,6 +688,16 @@ impl Lmdb {
             return Ok(SaveEventStatus::Rejected(RejectedReason::Deleted));
         }
 
+        // Reject event if already expired
+        if self.options.process_nip40 && event.is_expired() {
+            return Ok(SaveEventStatus::Rejected(RejectedReason::Expired));
+        }
+
+        // Reject event if the author requested its deletion before
+        if self.is_deletion_requested(txn, &event.id, &event.pubkey)? {
+            return Ok(SaveEventStatus::Rejected(RejectedReason::Deleted));
+        }
+
         // Reject event if the public key was vanished
         if self.is_pubkey_vanished(txn, &event.pubkey)? {
             return Ok(SaveEventStatus::Rejected(RejectedReason::Vanished));
@@ -526,8 +770,8 @@ impl Lmdb {
         txn: &'a RoTxn,
         event_id: &[u8],
     ) -> Result<Option<EventBorrow<'a>>, Error> {
-        match self.events.get(txn, event_id)? {
-            Some(bytes) => Ok(Some(EventBorrow::decode(bytes)?)),
+        match self.partition_of(txn, event_id)? {
+            Some(partition) => partition.get_event_by_id(txn, event_id),
             None => Ok(None),
         }
     }
@@ -536,7 +780,8 @@ impl Lmdb {
     pub fn delete(&self, txn: &mut RwTxn, filter: Filter) -> Result<(), Error> {
         // First, collect all deletion info while we have immutable borrows
         let indexes: Vec<EventIndexKeys> = {
-            let events = self.query(txn, filter)?;
+            // The expired events are deleted too
+            let events = self.query_with_expiration(txn, filter, None)?;
             events
                 .into_iter()
                 .map(|event| EventIndexKeys::new(event))
@@ -551,7 +796,101 @@ impl Lmdb {
         Ok(())
     }
 
+    /// Delete the events expired at `now`
+    pub fn wipe_expired(&self, txn: &mut RwTxn, now: Timestamp) -> Result<(), Error> {
+        let upper: [u8; index::EXPIRATION_ID_LEN] =
+            index::make_expiration_key(now, &EVENT_ID_ALL_255);
+
+        for partition in self.all_partitions().into_iter() {
+            // First, collect the keys of the expired events
+            let keys: Vec<[u8; index::EXPIRATION_ID_LEN]> = {
+                let range = (Bound::Unbounded, Bound::Included(upper.as_slice()));
+                let mut keys = Vec::new();
+                for result in partition.expirations.range(txn, &range)? {
+                    let (key, ()) = result?;
+                    if let Ok(key) = key.try_into() {
+                        keys.push(key);
+                    }
+                }
+                keys
+            };
+
+            for key in keys {
+                let id: &[u8] = &key[index::EXPIRATION_ID_LEN - EventId::LEN..];
+                let index: Option<EventIndexKeys> =
+                    partition.get_event_by_id(txn, id)?.map(EventIndexKeys::new);
+
+                match index {
+                    Some(index) => self.remove(txn, &index)?,
+                    // Dangling index entry
+                    None => {
+                        partition.expirations.delete(txn, &key)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the time to use to exclude the expired events from the queries
+    ///
+    /// Returns `None` if there aren't expired events or NIP-40 isn't processed,
+    /// so the queries don't need to check the expiration of every event.
+    fn expired_at(&self, txn: &RoTxn) -> Result<Option<Timestamp>, Error> {
+        if !self.options.process_nip40 {
+            return Ok(None);
+        }
+
+        let now: Timestamp = Timestamp::now();
+
+        for partition in self.all_partitions().into_iter() {
+            if let Some((key, ())) = partition.expirations.first(txn)? {
+                if index::expiration_from_key(key).is_some_and(|expiration| expiration <= now) {
+                    return Ok(Some(now));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     pub fn count(&self, txn: &RoTxn, filter: Filter) -> Result<usize, Error> {
+        // The indexes include the expired events not wiped yet: need to check every event
+        if self.expired_at(txn)?.is_some() {
+            return Ok(self.query(txn, filter)?.count());
+        }
+
+        let since: Timestamp = filter.since.unwrap_or_else(Timestamp::min);
+        let until: Timestamp = filter.until.unwrap_or_else(Timestamp::max);
+        let limit: usize = filter.limit.unwrap_or(usize::MAX);
+
+        let mut count: usize = 0;
+
+        for partition in self.partitions(since, until).into_iter() {
+            match self.count_in(&partition, txn, &filter)? {
+                Some(partial) => count += partial,
+                // Complex filter - need to iterate
+                None => return Ok(self.query(txn, filter)?.count()),
+            }
+
+            if count >= limit {
+                break;
+            }
+        }
+
+        Ok(count.min(limit))
+    }
+
+    /// Count the events of a partition, without decoding them
+    ///
+    /// Returns `None` if the filter isn't covered by the indexes.
+    fn count_in(
+        &self,
+        partition: &Partition,
+        txn: &RoTxn,
+        filter: &Filter,
+    ) -> Result<Option<usize>, Error> {
         // Check if we can use fast counting
         let can_fast_count: bool = filter.ids.is_none()
             && filter.authors.is_none()
@@ -560,8 +899,8 @@ impl Lmdb {
             && filter.generic_tags.is_empty();
 
         if !can_fast_count {
-            // Complex filter - need to iterate
-            return Ok(self.query(txn, filter)?.count());
+            // Filter covered by an index: count the index entries
+            return self.count_by_index(partition, txn, filter);
         }
 
         // Empty filter with maybe a limit
@@ -571,12 +910,12 @@ impl Lmdb {
         };
 
         // Empty filter with no time constraints = O(1) using index length
-        if filter == empty_with_maybe_limit {
-            let total: usize = self.ci_index.len(txn)? as usize;
-            return Ok(match filter.limit {
+        if *filter == empty_with_maybe_limit {
+            let total: usize = partition.ci_index.len(txn)? as usize;
+            return Ok(Some(match filter.limit {
                 Some(limit) => total.min(limit), // Return min of limit and total
                 None => total,
-            });
+            }));
         }
 
         // Fast counting for time-based filters only
@@ -586,18 +925,163 @@ impl Lmdb {
 
         // Time-based filter: iterate ci_index (already sorted by time)
         let count = match limit {
-            Some(limit) => self.ci_iter(txn, since, until)?.take(limit).count(),
-            None => self.ci_iter(txn, since, until)?.count(),
+            Some(limit) => partition.ci_iter(txn, since, until)?.take(limit).count(),
+            None => partition.ci_iter(txn, since, until)?.count(),
+        };
+
+        Ok(Some(count))
+    }
+
+    /// Count the events using only the indexes
+    ///
+    /// Returns `None` if the filter isn't fully covered by a single index.
+    fn count_by_index(
+        &self,
+        partition: &Partition,
+        txn: &RoTxn,
+        filter: &Filter,
+    ) -> Result<Option<usize>, Error> {
+        if filter.ids.is_some() || filter.search.is_some() {
+            return Ok(None);
+        }
+
+        // Empty sets are handled by the query
+        let authors: Option<&BTreeSet<PublicKey>> = filter.authors.as_ref();
+        let kinds: Option<&BTreeSet<Kind>> = filter.kinds.as_ref();
+        if authors.is_some_and(|a| a.is_empty()) || kinds.is_some_and(|k| k.is_empty()) {
+            return Ok(None);
+        }
+
+        // At most one tag name is covered by the indexes.
+        // The tag values are truncated in the index keys, so only the short ones are covered.
+        let tag: Option<(&SingleLetterTag, &BTreeSet<String>)> = match filter.generic_tags.len() {
+            0 => None,
+            1 => {
+                let (name, values) = filter.generic_tags.iter().next().expect("one tag");
+                if values.is_empty() || values.iter().any(|v| !index::is_tag_value_covered(v)) {
+                    return Ok(None);
+                }
+                Some((name, values))
+            }
+            _ => return Ok(None),
         };
 
-        Ok(count)
+        if let (Some(since), Some(until)) = (filter.since, filter.until) {
+            if since > until {
+                return Ok(Some(0));
+            }
+        }
+
+        let since: Timestamp = filter.since.unwrap_or_else(Timestamp::min);
+        let until: Timestamp = filter.until.unwrap_or_else(Timestamp::max);
+
+        let mut ranges: Vec<RoRange<Bytes, Bytes>> = Vec::new();
+
+        match (authors, kinds, tag) {
+            (None, None, Some((name, values))) => {
+                for value in values.iter() {
+                    ranges.push(partition.tc_iter(txn, name, value, since, until)?);
+                }
+            }
+            (None, Some(kinds), None) => {
+                for kind in kinds.iter() {
+                    ranges.push(partition.kc_iter(txn, kind.as_u16(), since, until)?);
+                }
+            }
+            (None, Some(kinds), Some((name, values))) => {
+                for kind in kinds.iter() {
+                    for value in values.iter() {
+                        ranges.push(partition.ktc_iter(
+                            txn,
+                            kind.as_u16(),
+                            name,
+                            value,
+                            since,
+                            until,
+                        )?);
+                    }
+                }
+            }
+            (Some(authors), None, None) => {
+                for author in authors.iter() {
+                    ranges.push(partition.ac_iter(txn, author.as_bytes(), since, until)?);
+                }
+            }
+            (Some(authors), Some(kinds), None) => {
+                for author in authors.iter() {
+                    for kind in kinds.iter() {
+                        ranges.push(partition.akc_iter(
+                            txn,
+                            author.as_bytes(),
+                            kind.as_u16(),
+                            since,
+                            until,
+                        )?);
+                    }
+                }
+            }
+            (Some(authors), None, Some((name, values))) => {
+                for author in authors.iter() {
+                    for value in values.iter() {
+                        ranges.push(partition.atc_iter(
+                            txn,
+                            author.as_bytes(),
+                            name,
+                            value,
+                            since,
+                            until,
+                        )?);
+                    }
+                }
+            }
+            _ => return Ok(None),
+        }
+
+        let limit: usize = filter.limit.unwrap_or(usize::MAX);
+
+        // An event may be indexed under many tag values: deduplicate by ID
+        let dedup: bool = tag.is_some_and(|(_, values)| values.len() > 1);
+        let mut ids: HashSet<&[u8]> = HashSet::new();
+        let mut count: usize = 0;
+
+        for range in ranges.into_iter() {
+            for res in range {
+                let (_key, id) = res?;
+
+                if dedup && !ids.insert(id) {
+                    continue;
+                }
+
+                count += 1;
+
+                if count >= limit {
+                    return Ok(Some(count));
+                }
+            }
+        }
+
+        Ok(Some(count))
     }
 
     /// Find all events that match the filter
+    ///
+    /// The expired events are excluded, unless NIP-40 isn't processed.
+    #[inline]
     pub fn query<'a>(
         &'a self,
         txn: &'a RoTxn,
         filter: Filter,
+    ) -> Result<Box<dyn Iterator<Item = EventBorrow<'a>> + 'a>, Error> {
+        let expired_at: Option<Timestamp> = self.expired_at(txn)?;
+        self.query_with_expiration(txn, filter, expired_at)
+    }
+
+    /// Find all events that match the filter, excluding the ones expired at `expired_at`
+    fn query_with_expiration<'a>(
+        &'a self,
+        txn: &'a RoTxn,
+        filter: Filter,
+        expired_at: Option<Timestamp>,
     ) -> Result<Box<dyn Iterator<Item = EventBorrow<'a>> + 'a>, Error> {
         if let (Some(since), Some(until)) = (filter.since, filter.until) {
             if since > until {
@@ -612,47 +1096,149 @@ impl Lmdb {
         let since = filter.since.unwrap_or_else(Timestamp::min);
         let until = filter.until.unwrap_or_else(Timestamp::max);
 
-        let filter: DatabaseFilter = filter.into();
+        let mut filter: DatabaseFilter = filter.into();
+        filter.expired_at = expired_at;
 
         // Identify pattern
         let pattern: QueryFilterPattern = QueryFilterPattern::from_filter(&filter);
 
         tracing::debug!("Querying by pattern: {pattern:?}");
 
-        // Query by pattern
         match pattern {
             QueryFilterPattern::Ids => self.query_by_ids(txn, filter, limit, &mut output)?,
-            QueryFilterPattern::AuthorsAndKinds => {
-                self.query_by_authors_and_kinds(txn, filter, since, until, limit, &mut output)?
+            QueryFilterPattern::Scraping => {
+                return self.query_by_scraping(txn, filter, since, until, limit);
             }
-            QueryFilterPattern::AuthorsAndTags => {
-                self.query_by_authors_and_tags(txn, filter, since, until, limit, &mut output)?
+            pattern => {
+                // The partitions are visited from the newest:
+                // the older ones can't have events newer than the collected ones.
+                for partition in self.partitions(since, until).into_iter() {
+                    self.query_partition(
+                        &partition,
+                        txn,
+                        &pattern,
+                        &filter,
+                        since,
+                        until,
+                        limit,
+                        &mut output,
+                    )?;
+
+                    if limit.is_some_and(|limit| output.len() >= limit) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Optionally apply limit
+        Ok(match limit {
+            Some(limit) => Box::new(output.into_iter().take(limit)),
+            None => Box::new(output.into_iter()),
+        })
+    }
+
+    /// Query a partition by pattern
+    #[allow(clippy::too_many_arguments)]
+    fn query_partition<'a>(
+        &self,
+        partition: &Partition,
+        txn: &'a RoTxn,
+        pattern: &QueryFilterPattern,
+        filter: &DatabaseFilter,
+        since: Timestamp,
+        until: Timestamp,
+        limit: Option<usize>,
+        output: &mut BTreeSet<EventBorrow<'a>>,
+    ) -> Result<(), Error> {
+        match pattern {
+            QueryFilterPattern::AuthorsAndKinds => {
+                self.query_by_authors_and_kinds(partition, txn, filter, since, until, limit, output)
             }
-            QueryFilterPattern::AuthorKindsAndTags => {
-                self.query_by_authors_kinds_and_tags(txn, filter, since, until, limit, &mut output)?
+            QueryFilterPattern::AuthorsAndTags => {
+                self.query_by_authors_and_tags(partition, txn, filter, since, until, limit, output)
             }
+            QueryFilterPattern::AuthorKindsAndTags => self.query_by_authors_kinds_and_tags(
+                partition, txn, filter, since, until, limit, output,
+            ),
             QueryFilterPattern::KindsAndTags => {
-                self.query_by_kinds_and_tags(txn, filter, since, until, limit, &mut output)?
+                self.query_by_kinds_and_tags(partition, txn, filter, since, until, limit, output)
             }
             QueryFilterPattern::Tags => {
-                self.query_by_tags(txn, filter, since, until, limit, &mut output)?
+                self.query_by_tags(partition, txn, filter, since, until, limit, output)
             }
             QueryFilterPattern::Authors => {
-                self.query_by_authors(txn, filter, since, until, limit, &mut output)?
+                self.query_by_authors(partition, txn, filter, since, until, limit, output)
             }
             QueryFilterPattern::Kinds => {
-                self.query_by_kinds(txn, filter, since, until, limit, &mut output)?
+                self.query_by_kinds(partition, txn, filter, since, until, limit, output)
             }
-            QueryFilterPattern::Scraping => {
-                return self.query_by_scraping(txn, filter, since, until, limit);
+            // Handled by the caller
+            QueryFilterPattern::Ids | QueryFilterPattern::Scraping => Ok(()),
+        }
+    }
+
+    /// Find the events that match the filter, lazily
+    ///
+    /// Unlike [`Lmdb::query`], the time-ordered indexes are read only while iterating,
+    /// so the events are yielded in the same order but without collecting them first.
+    /// The iteration stops at the limit.
+    pub fn query_iter<'a>(
+        &'a self,
+        txn: &'a RoTxn,
+        filter: Filter,
+    ) -> Result<Box<dyn Iterator<Item = Result<EventBorrow<'a>, Error>> + 'a>, Error> {
+        if let (Some(since), Some(until)) = (filter.since, filter.until) {
+            if since > until {
+                return Ok(Box::new(iter::empty()));
             }
         }
 
-        // Optionally apply limit
-        Ok(match limit {
-            Some(limit) => Box::new(output.into_iter().take(limit)),
-            None => Box::new(output.into_iter()),
-        })
+        let limit: usize = filter.limit.unwrap_or(usize::MAX);
+        let since = filter.since.unwrap_or_else(Timestamp::min);
+        let until = filter.until.unwrap_or_else(Timestamp::max);
+
+        let mut filter: DatabaseFilter = filter.into();
+        filter.expired_at = self.expired_at(txn)?;
+
+        // Identify pattern
+        let pattern: QueryFilterPattern = QueryFilterPattern::from_filter(&filter);
+
+        tracing::debug!("Streaming by pattern: {pattern:?}");
+
+        // There isn't a time-ordered index for the IDs, but their number is bounded by the filter
+        if let QueryFilterPattern::Ids = pattern {
+            let mut output: BTreeSet<EventBorrow<'a>> = BTreeSet::new();
+            self.query_by_ids(txn, filter, Some(limit), &mut output)?;
+            return Ok(Box::new(output.into_iter().take(limit).map(Ok)));
+        }
+
+        // Merge the ranges of every partition, visiting the partitions from the newest
+        let mut partitions: Vec<(Partition, MergedIndexRanges<'a>)> = Vec::new();
+        for partition in self.partitions(since, until).into_iter() {
+            let ranges = index_ranges(&partition, txn, &pattern, &filter, since, until)?;
+            partitions.push((partition, MergedIndexRanges::new(ranges)?));
+        }
+
+        Ok(Box::new(
+            partitions
+                .into_iter()
+                .flat_map(|(partition, ids)| ids.map(move |res| (partition, res)))
+                .filter_map(move |(partition, res)| {
+                    let lookup = || -> Result<Option<EventBorrow<'a>>, Error> {
+                        // Skip the dangling index entries, like the eager scraping
+                        let event = match partition.get_event_by_id(txn, res?)? {
+                            Some(event) => event,
+                            None => return Ok(None),
+                        };
+
+                        // Check against the rest of the filter
+                        Ok(filter.match_event(&event).then_some(event))
+                    };
+                    lookup().transpose()
+                })
+                .take(limit),
+        ))
     }
 
     fn query_by_ids<'a>(
@@ -684,8 +1270,9 @@ impl Lmdb {
 
     fn query_by_authors_and_kinds<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -697,7 +1284,7 @@ impl Lmdb {
 
         for author in filter.authors.iter() {
             for kind in filter.kinds.iter() {
-                let iter = self.akc_iter(txn, author, *kind, since, until)?;
+                let iter = partition.akc_iter(txn, author, *kind, since, until)?;
 
                 // Count how many we have found of this author-kind pair, so we
                 // can possibly update `since`
@@ -705,7 +1292,9 @@ impl Lmdb {
 
                 'per_event: for result in iter {
                     let (_key, value) = result?;
-                    let event = self.get_event_by_id(txn, value)?.ok_or(Error::NotFound)?;
+                    let event = partition
+                        .get_event_by_id(txn, value)?
+                        .ok_or(Error::NotFound)?;
 
                     // If we have gone beyond since, we can stop early
                     // (We have to check because `since` might change in this loop)
@@ -751,8 +1340,9 @@ impl Lmdb {
 
     fn query_by_authors_and_tags<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -765,13 +1355,15 @@ impl Lmdb {
         for author in filter.authors.iter() {
             for (tagname, set) in filter.generic_tags.iter() {
                 for tag_value in set.iter() {
-                    let iter = self
+                    let iter = partition
                         .atc_iter(txn, author, tagname, tag_value, since, until)?
                         .filter_map(|res| {
                             let (_k, v) = res.ok()?;
                             Some(v)
                         });
-                    self.iterate_filter_until_limit(txn, &filter, iter, &mut since, limit, output)?;
+                    self.iterate_filter_until_limit(
+                        partition, txn, filter, iter, &mut since, limit, output,
+                    )?;
                 }
             }
         }
@@ -781,8 +1373,9 @@ impl Lmdb {
 
     fn query_by_authors_kinds_and_tags<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -795,7 +1388,7 @@ impl Lmdb {
         for author in filter.authors.iter() {
             for kind in filter.kinds.iter() {
                 // Author + Kind index
-                let akc_iter = self.akc_iter(txn, author, *kind, since, until)?;
+                let akc_iter = partition.akc_iter(txn, author, *kind, since, until)?;
 
                 // Collect Author + Kind BTree set
                 let akc_set: BTreeSet<&[u8]> = akc_iter
@@ -809,7 +1402,7 @@ impl Lmdb {
                     for tag_value in set.iter() {
                         // Author + Tag index
                         let atc_iter =
-                            self.atc_iter(txn, author, tagname, tag_value, since, until)?;
+                            partition.atc_iter(txn, author, tagname, tag_value, since, until)?;
 
                         // Collect Author + Tag BTree set
                         let atc_set: BTreeSet<&[u8]> = atc_iter
@@ -823,7 +1416,7 @@ impl Lmdb {
                         let iter = atc_set.intersection(&akc_set).copied();
 
                         self.iterate_filter_until_limit(
-                            txn, &filter, iter, &mut since, limit, output,
+                            partition, txn, filter, iter, &mut since, limit, output,
                         )?;
                     }
                 }
@@ -835,8 +1428,9 @@ impl Lmdb {
 
     fn query_by_kinds_and_tags<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -849,13 +1443,15 @@ impl Lmdb {
         for kind in filter.kinds.iter() {
             for (tag_name, set) in filter.generic_tags.iter() {
                 for tag_value in set.iter() {
-                    let iter = self
+                    let iter = partition
                         .ktc_iter(txn, *kind, tag_name, tag_value, since, until)?
                         .filter_map(|res| {
                             let (_k, v) = res.ok()?;
                             Some(v)
                         });
-                    self.iterate_filter_until_limit(txn, &filter, iter, &mut since, limit, output)?;
+                    self.iterate_filter_until_limit(
+                        partition, txn, filter, iter, &mut since, limit, output,
+                    )?;
                 }
             }
         }
@@ -865,8 +1461,9 @@ impl Lmdb {
 
     fn query_by_tags<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -878,13 +1475,15 @@ impl Lmdb {
 
         for (tag_name, set) in filter.generic_tags.iter() {
             for tag_value in set.iter() {
-                let iter = self
+                let iter = partition
                     .tc_iter(txn, tag_name, tag_value, since, until)?
                     .filter_map(|res| {
                         let (_k, v) = res.ok()?;
                         Some(v)
                     });
-                self.iterate_filter_until_limit(txn, &filter, iter, &mut since, limit, output)?;
+                self.iterate_filter_until_limit(
+                    partition, txn, filter, iter, &mut since, limit, output,
+                )?;
             }
         }
 
@@ -893,8 +1492,9 @@ impl Lmdb {
 
     fn query_by_authors<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -905,11 +1505,15 @@ impl Lmdb {
         let mut since: Timestamp = since;
 
         for author in filter.authors.iter() {
-            let iter = self.ac_iter(txn, author, since, until)?.filter_map(|res| {
-                let (_k, v) = res.ok()?;
-                Some(v)
-            });
-            self.iterate_filter_until_limit(txn, &filter, iter, &mut since, limit, output)?;
+            let iter = partition
+                .ac_iter(txn, author, since, until)?
+                .filter_map(|res| {
+                    let (_k, v) = res.ok()?;
+                    Some(v)
+                });
+            self.iterate_filter_until_limit(
+                partition, txn, filter, iter, &mut since, limit, output,
+            )?;
         }
 
         Ok(())
@@ -917,8 +1521,9 @@ impl Lmdb {
 
     fn query_by_kinds<'a>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
-        filter: DatabaseFilter,
+        filter: &DatabaseFilter,
         since: Timestamp,
         until: Timestamp,
         limit: Option<usize>,
@@ -929,11 +1534,15 @@ impl Lmdb {
         let mut since: Timestamp = since;
 
         for kind in filter.kinds.iter() {
-            let iter = self.kc_iter(txn, *kind, since, until)?.filter_map(|res| {
-                let (_k, v) = res.ok()?;
-                Some(v)
-            });
-            self.iterate_filter_until_limit(txn, &filter, iter, &mut since, limit, output)?;
+            let iter = partition
+                .kc_iter(txn, *kind, since, until)?
+                .filter_map(|res| {
+                    let (_k, v) = res.ok()?;
+                    Some(v)
+                });
+            self.iterate_filter_until_limit(
+                partition, txn, filter, iter, &mut since, limit, output,
+            )?;
         }
 
         Ok(())
@@ -950,12 +1559,19 @@ impl Lmdb {
         until: Timestamp,
         limit: Option<usize>,
     ) -> Result<Box<dyn Iterator<Item = EventBorrow<'a>> + 'a>, Error> {
-        // Iterate over created _at index, so events are already sorted
+        let mut ranges: Vec<(Partition, RoRange<'a, Bytes, Bytes>)> = Vec::new();
+        for partition in self.partitions(since, until).into_iter() {
+            ranges.push((partition, partition.ci_iter(txn, since, until)?));
+        }
+
+        // Iterate over created _at index of the partitions, from the newest, so events are already sorted
         Ok(Box::new(
-            self.ci_iter(txn, since, until)?
-                .filter_map(move |res| {
+            ranges
+                .into_iter()
+                .flat_map(|(partition, range)| range.map(move |res| (partition, res)))
+                .filter_map(move |(partition, res)| {
                     let (_key, value) = res.ok()?;
-                    let event: EventBorrow = self.get_event_by_id(txn, value).ok()??;
+                    let event: EventBorrow = partition.get_event_by_id(txn, value).ok()??;
 
                     if filter.match_event(&event) {
                         Some(event)
@@ -969,6 +1585,7 @@ impl Lmdb {
 
     fn iterate_filter_until_limit<'a, 'i, I>(
         &self,
+        partition: &Partition,
         txn: &'a RoTxn,
         filter: &DatabaseFilter,
         iter: I,
@@ -983,7 +1600,7 @@ impl Lmdb {
 
         for id in iter {
             // Get event by ID
-            let event = self.get_event_by_id(txn, id)?.ok_or(Error::NotFound)?;
+            let event = partition.get_event_by_id(txn, id)?.ok_or(Error::NotFound)?;
 
             if event.created_at < *since {
                 break;
@@ -1023,7 +1640,14 @@ impl Lmdb {
             return Err(Error::WrongEventKind);
         }
 
-        let mut iter = self.akc_iter(
+        let partition: &Partition = match &self.partitions {
+            Partitions::Flat(partition) => partition,
+            Partitions::Buckets(buckets) => {
+                return self.find_latest(txn, buckets, &Coordinate::new(kind, *author));
+            }
+        };
+
+        let mut iter = partition.akc_iter(
             txn,
             author.as_bytes(),
             kind.as_u16(),
@@ -1033,7 +1657,7 @@ impl Lmdb {
 
         if let Some(result) = iter.next() {
             let (_key, id) = result?;
-            return self.get_event_by_id(txn, id);
+            return partition.get_event_by_id(txn, id);
         }
 
         Ok(None)
@@ -1048,7 +1672,12 @@ impl Lmdb {
             return Err(Error::WrongEventKind);
         }
 
-        let iter = self.atc_iter(
+        let partition: &Partition = match &self.partitions {
+            Partitions::Flat(partition) => partition,
+            Partitions::Buckets(buckets) => return self.find_latest(txn, buckets, addr),
+        };
+
+        let iter = partition.atc_iter(
             txn,
             addr.public_key.as_bytes(),
             &SingleLetterTag::lowercase(Alphabet::D),
@@ -1059,7 +1688,7 @@ impl Lmdb {
 
         for result in iter {
             let (_key, id) = result?;
-            let event = self.get_event_by_id(txn, id)?.ok_or(Error::NotFound)?;
+            let event = partition.get_event_by_id(txn, id)?.ok_or(Error::NotFound)?;
 
             // the atc index doesn't have kind, so we have to compare the kinds
             if event.kind != addr.kind.as_u16() {
@@ -1072,6 +1701,22 @@ impl Lmdb {
         Ok(None)
     }
 
+    /// Find the stored version of a coordinate, in the partitioned layout
+    ///
+    /// Only one version of a coordinate is stored, so the global pointer is enough.
+    fn find_latest<'a>(
+        &self,
+        txn: &'a RoTxn,
+        buckets: &Buckets,
+        coordinate: &Coordinate,
+    ) -> Result<Option<EventBorrow<'a>>, Error> {
+        match buckets.latest(txn, coordinate)? {
+            // The pointer is left dangling when the event is removed
+            Some(id) => self.get_event_by_id(txn, id),
+            None => Ok(None),
+        }
+    }
+
     /// Remove all replaceable events with the matching author-kind
     /// Kind must be a replaceable (not parameterized replaceable) event kind
     pub fn remove_replaceable(
@@ -1084,7 +1729,14 @@ impl Lmdb {
             return Err(Error::WrongEventKind);
         }
 
-        let iter = self.akc_iter(
+        let partition: &Partition = match &self.partitions {
+            Partitions::Flat(partition) => partition,
+            Partitions::Buckets(buckets) => {
+                return self.remove_latest(txn, buckets, coordinate, until);
+            }
+        };
+
+        let iter = partition.akc_iter(
             txn,
             coordinate.public_key.as_bytes(),
             coordinate.kind.as_u16(),
@@ -1097,7 +1749,7 @@ impl Lmdb {
 
         for result in iter {
             let (_key, id) = result?;
-            if let Some(event) = self.get_event_by_id(txn, id)? {
+            if let Some(event) = partition.get_event_by_id(txn, id)? {
                 indexes.push(EventIndexKeys::new(event));
             }
         }
@@ -1122,7 +1774,14 @@ impl Lmdb {
             return Err(Error::WrongEventKind);
         }
 
-        let iter = self.atc_iter(
+        let partition: &Partition = match &self.partitions {
+            Partitions::Flat(partition) => partition,
+            Partitions::Buckets(buckets) => {
+                return self.remove_latest(txn, buckets, coordinate, until);
+            }
+        };
+
+        let iter = partition.atc_iter(
             txn,
             coordinate.public_key.as_bytes(),
             &SingleLetterTag::lowercase(Alphabet::D),
@@ -1136,7 +1795,7 @@ impl Lmdb {
 
         for result in iter {
             let (_key, id) = result?;
-            if let Some(event) = self.get_event_by_id(txn, id)? {
+            if let Some(event) = partition.get_event_by_id(txn, id)? {
                 // Our index doesn't have Kind embedded, so we have to check it
                 if event.kind == coordinate.kind.as_u16() {
                     indexes.push(EventIndexKeys::new(event));
@@ -1152,11 +1811,38 @@ impl Lmdb {
         Ok(())
     }
 
+    /// Remove the stored version of a coordinate, in the partitioned layout, if not newer than `until`
+    fn remove_latest(
+        &self,
+        txn: &mut RwTxn,
+        buckets: &Buckets,
+        coordinate: &Coordinate,
+        until: Timestamp,
+    ) -> Result<(), Error> {
+        let index: EventIndexKeys = match self.find_latest(txn, buckets, coordinate)? {
+            Some(event) if event.created_at <= until => EventIndexKeys::new(event),
+            _ => return Ok(()),
+        };
+
+        self.remove(txn, &index)
+    }
+
     #[inline]
     pub(crate) fn is_deleted(&self, txn: &RoTxn, event_id: &EventId) -> Result<bool, Error> {
         Ok(self.deleted_ids.get(txn, event_id.as_bytes())?.is_some())
     }
 
+    /// Check if the author of the event requested its deletion, before the event was received
+    pub(crate) fn is_deletion_requested(
+        &self,
+        txn: &RoTxn,
+        event_id: &EventId,
+        author: &PublicKey,
+    ) -> Result<bool, Error> {
+        let key: [u8; 64] = index::make_deletion_request_key(event_id, author);
+        Ok(self.deletion_requests.get(txn, &key)?.is_some())
+    }
+
     pub(crate) fn mark_deleted(&self, txn: &mut RwTxn, event_id: &EventId) -> Result<(), Error> {
         self.deleted_ids.put(txn, event_id.as_bytes(), &())?;
         Ok(())
@@ -1169,6 +1855,14 @@ impl Lmdb {
         when: Timestamp,
     ) -> Result<(), Error> {
         let key: Vec<u8> = index::make_coordinate_index_key(coordinate);
+
+        // Keep the most recent deletion
+        if let Some(current) = self.deleted_coordinates.get(txn, &key)? {
+            if current >= when.as_secs() {
+                return Ok(());
+            }
+        }
+
         self.deleted_coordinates.put(txn, &key, &when.as_secs())?;
         Ok(())
     }
@@ -1215,129 +1909,22 @@ impl Lmdb {
         Ok(())
     }
 
-    pub(crate) fn ci_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_ci_index_key(until, &EVENT_ID_ALL_ZEROS);
-        let end_prefix = index::make_ci_index_key(since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.ci_index.range(txn, &range)?)
-    }
-
-    pub(crate) fn tc_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        tag_name: &SingleLetterTag,
-        tag_value: &str,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_tc_index_key(
-            tag_name,
-            tag_value,
-            until, // scan goes backwards in time
-            &EVENT_ID_ALL_ZEROS,
-        );
-        let end_prefix = index::make_tc_index_key(tag_name, tag_value, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.tc_index.range(txn, &range)?)
-    }
-
-    pub(crate) fn ac_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        author: &[u8; 32],
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_ac_index_key(author, until, &EVENT_ID_ALL_ZEROS);
-        let end_prefix = index::make_ac_index_key(author, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.ac_index.range(txn, &range)?)
-    }
-
-    pub(crate) fn akc_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        author: &[u8; 32],
-        kind: u16,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_akc_index_key(author, kind, until, &EVENT_ID_ALL_ZEROS);
-        let end_prefix = index::make_akc_index_key(author, kind, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.akc_index.range(txn, &range)?)
-    }
-
-    pub(crate) fn kc_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        kind: u16,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_kc_index_key(kind, until, &EVENT_ID_ALL_ZEROS);
-        let end_prefix = index::make_kc_index_key(kind, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.kc_index.range(txn, &range)?)
-    }
-
-    pub(crate) fn atc_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        author: &[u8; 32],
-        tag_name: &SingleLetterTag,
-        tag_value: &str,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix: Vec<u8> = index::make_atc_index_key(
-            author,
-            tag_name,
-            tag_value,
-            until, // scan goes backwards in time
-            &EVENT_ID_ALL_ZEROS,
-        );
-        let end_prefix: Vec<u8> =
-            index::make_atc_index_key(author, tag_name, tag_value, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.atc_index.range(txn, &range)?)
-    }
-
     fn handle_deletion_event(&self, txn: &mut RwTxn, event: &Event) -> Result<bool, Error> {
         // Collect DeletionInfo and EventIds for all valid targets first
         let mut deletions_to_process = Vec::new();
+        let mut not_received = Vec::new();
 
         for id in event.tags.event_ids() {
-            if let Some(target) = self.get_event_by_id(txn, id.as_bytes())? {
-                // Author must match
-                if target.pubkey != event.pubkey.as_bytes() {
-                    return Ok(true);
-                }
+            match self.get_event_by_id(txn, id.as_bytes())? {
+                Some(target) => {
+                    // Author must match
+                    if target.pubkey != event.pubkey.as_bytes() {
+                        return Ok(true);
+                    }
 
-                deletions_to_process.push((id, EventIndexKeys::new(target)));
+                    deletions_to_process.push((id, EventIndexKeys::new(target)));
+                }
+                None => not_received.push(id),
             }
         }
 
@@ -1350,6 +1937,13 @@ impl Lmdb {
             self.remove(txn, &info)?;
         }
 
+        // Remember the deletion requests of the events not received yet.
+        // The author can't be verified now, so is checked when the event is received.
+        for id in not_received {
+            let key: [u8; 64] = index::make_deletion_request_key(&id, &event.pubkey);
+            self.deletion_requests.put(txn, &key, &())?;
+        }
+
         for coordinate in event.tags.coordinates() {
             // Author must match
             if coordinate.public_key != event.pubkey {
@@ -1369,31 +1963,83 @@ impl Lmdb {
 
         Ok(false)
     }
+}
 
-    pub(crate) fn ktc_iter<'a>(
-        &self,
-        txn: &'a RoTxn,
-        kind: u16,
-        tag_name: &SingleLetterTag,
-        tag_value: &str,
-        since: Timestamp,
-        until: Timestamp,
-    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
-        let start_prefix = index::make_ktc_index_key(
-            kind,
-            tag_name,
-            tag_value,
-            until, // scan goes backwards in time
-            &EVENT_ID_ALL_ZEROS,
-        );
-        let end_prefix =
-            index::make_ktc_index_key(kind, tag_name, tag_value, since, &EVENT_ID_ALL_255);
-        let range = (
-            Bound::Included(start_prefix.as_slice()),
-            Bound::Excluded(end_prefix.as_slice()),
-        );
-        Ok(self.ktc_index.range(txn, &range)?)
+/// Check if the database was created before the layouts were introduced
+///
+/// The indexes of the flat layout were always created with the database.
+fn is_existing_flat_database(env: &Env, txn: &RoTxn) -> Result<bool, Error> {
+    let ci_index: Option<Database<Bytes, Bytes>> = env.open_database(txn, Some("ci"))?;
+    Ok(ci_index.is_some())
+}
+
+/// Get the ranges of the partition indexes covering the pattern
+fn index_ranges<'a>(
+    partition: &Partition,
+    txn: &'a RoTxn,
+    pattern: &QueryFilterPattern,
+    filter: &DatabaseFilter,
+    since: Timestamp,
+    until: Timestamp,
+) -> Result<Vec<RoRange<'a, Bytes, Bytes>>, Error> {
+    let mut ranges: Vec<RoRange<'a, Bytes, Bytes>> = Vec::new();
+
+    match pattern {
+        // Not time-ordered
+        QueryFilterPattern::Ids => {}
+        QueryFilterPattern::AuthorsAndKinds => {
+            for author in filter.authors.iter() {
+                for kind in filter.kinds.iter() {
+                    ranges.push(partition.akc_iter(txn, author, *kind, since, until)?);
+                }
+            }
+        }
+        // The kinds are checked by the filter
+        QueryFilterPattern::AuthorsAndTags | QueryFilterPattern::AuthorKindsAndTags => {
+            for author in filter.authors.iter() {
+                for (tag_name, set) in filter.generic_tags.iter() {
+                    for tag_value in set.iter() {
+                        ranges.push(
+                            partition.atc_iter(txn, author, tag_name, tag_value, since, until)?,
+                        );
+                    }
+                }
+            }
+        }
+        QueryFilterPattern::KindsAndTags => {
+            for kind in filter.kinds.iter() {
+                for (tag_name, set) in filter.generic_tags.iter() {
+                    for tag_value in set.iter() {
+                        ranges.push(
+                            partition.ktc_iter(txn, *kind, tag_name, tag_value, since, until)?,
+                        );
+                    }
+                }
+            }
+        }
+        QueryFilterPattern::Tags => {
+            for (tag_name, set) in filter.generic_tags.iter() {
+                for tag_value in set.iter() {
+                    ranges.push(partition.tc_iter(txn, tag_name, tag_value, since, until)?);
+                }
+            }
+        }
+        QueryFilterPattern::Authors => {
+            for author in filter.authors.iter() {
+                ranges.push(partition.ac_iter(txn, author, since, until)?);
+            }
+        }
+        QueryFilterPattern::Kinds => {
+            for kind in filter.kinds.iter() {
+                ranges.push(partition.kc_iter(txn, *kind, since, until)?);
+            }
+        }
+        QueryFilterPattern::Scraping => {
+            ranges.push(partition.ci_iter(txn, since, until)?);
+        }
     }
+
+    Ok(ranges)
 }
 
 /// Check if the new event should replace the stored one.
@@ -1415,6 +2061,16 @@ mod tests {
 
     use super::*;
 
+    impl Lmdb {
+        /// Get the partition of the flat layout
+        fn flat(&self) -> Partition {
+            match &self.partitions {
+                Partitions::Flat(partition) => *partition,
+                Partitions::Buckets(..) => panic!("Not the flat layout"),
+            }
+        }
+    }
+
     fn create_test_event(kind: u16, created_at: u64) -> Event {
         let keys = Keys::generate();
         EventBuilder::new(Kind::from(kind), "test content")
@@ -1423,6 +2079,286 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn test_verify_and_quarantine() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 100)
+            .max_readers(126);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        let valid = create_test_event(1, 1000);
+
+        // Same content, but under a forged ID
+        let other = create_test_event(1, 1001);
+        let forged = Event::new(
+            other.id,
+            valid.pubkey,
+            valid.created_at,
+            valid.kind,
+            valid.tags.clone(),
+            "forged content",
+            valid.sig,
+        );
+
+        // Garbage value
+        let garbage_id = EventId::from_byte_array([7; 32]);
+
+        {
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+            lmdb.store(&mut txn, &mut fbb, &valid).unwrap();
+            lmdb.store(&mut txn, &mut fbb, &forged).unwrap();
+            lmdb.flat()
+                .events
+                .put(&mut txn, garbage_id.as_bytes(), b"garbage")
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        // Verify
+        let txn = lmdb.read_txn().unwrap();
+        let (report, last) = lmdb.verify_batch(&txn, None, 10, true).unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.id_mismatches, vec![forged.id]);
+        assert!(report.invalid_signatures.is_empty());
+        assert_eq!(report.undecodable, vec![garbage_id]);
+        assert!(report.completed);
+        assert!(last.is_some());
+
+        // Quarantine
+        let mut txn = lmdb.write_txn().unwrap();
+        lmdb.remediate(
+            &mut txn,
+            &[forged.id, garbage_id],
+            VerifyAction::Quarantine,
+            None,
+        )
+        .unwrap();
+        txn.commit().unwrap();
+
+        let txn = lmdb.read_txn().unwrap();
+        assert!(lmdb.has_event(&txn, &valid.id).unwrap());
+        assert!(!lmdb.has_event(&txn, &forged.id).unwrap());
+        assert!(
+            lmdb.flat()
+                .events
+                .get(&txn, garbage_id.as_bytes())
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(lmdb.quarantine.len(&txn).unwrap(), 2);
+
+        // Quarantined events are excluded from queries
+        let results: Vec<EventBorrow> = lmdb.query(&txn, Filter::new()).unwrap().collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, valid.id.as_bytes());
+    }
+
+    #[test]
+    fn test_query_iter() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 100)
+            .max_readers(126);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+
+        {
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+            for i in 0..30u64 {
+                let keys = if i % 2 == 0 { &alice } else { &bob };
+                let kind = [Kind::TextNote, Kind::Repost, Kind::Reaction][i as usize % 3];
+                // Some events share the timestamp
+                let event = EventBuilder::new(kind, format!("event {i}"))
+                    .tag(Tag::hashtag(format!("t{}", i % 4)))
+                    .tag(Tag::hashtag(format!("t{}", i % 5)))
+                    .custom_created_at(Timestamp::from_secs(1000 + i / 2))
+                    .sign(keys)
+                    .unwrap();
+                lmdb.store(&mut txn, &mut fbb, &event).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        let filters = vec![
+            Filter::new(),
+            Filter::new().limit(7),
+            Filter::new()
+                .since(Timestamp::from_secs(1003))
+                .until(Timestamp::from_secs(1010)),
+            Filter::new().author(alice.public_key()),
+            Filter::new()
+                .authors([alice.public_key(), bob.public_key()])
+                .limit(9),
+            Filter::new()
+                .kinds([Kind::TextNote, Kind::Reaction])
+                .limit(5),
+            Filter::new()
+                .author(bob.public_key())
+                .kinds([Kind::Repost, Kind::Reaction]),
+            // The events tagged with both the values are yielded once
+            Filter::new().hashtags(["t0", "t1", "t2"]),
+            Filter::new().hashtags(["t0", "t1"]).limit(4),
+            Filter::new()
+                .author(alice.public_key())
+                .hashtags(["t1", "t3"]),
+            Filter::new().kind(Kind::Repost).hashtags(["t0", "t4"]),
+            Filter::new()
+                .author(bob.public_key())
+                .kind(Kind::TextNote)
+                .hashtag("t3"),
+            Filter::new()
+                .since(Timestamp::from_secs(10))
+                .until(Timestamp::from_secs(5)),
+        ];
+
+        let txn = lmdb.read_txn().unwrap();
+        for filter in filters {
+            let expected: Vec<EventId> = lmdb
+                .query(&txn, filter.clone())
+                .unwrap()
+                .map(|e| EventId::from_byte_array(*e.id))
+                .collect();
+            let lazy: Vec<EventId> = lmdb
+                .query_iter(&txn, filter.clone())
+                .unwrap()
+                .map(|res| EventId::from_byte_array(*res.unwrap().id))
+                .collect();
+            assert_eq!(lazy, expected, "filter: {filter:?}");
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_query_iter_skips_dangling_index_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 100)
+            .max_readers(126);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        let events: Vec<Event> = (0..5u64).map(|i| create_test_event(1, 1000 + i)).collect();
+
+        {
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+            for event in events.iter() {
+                lmdb.store(&mut txn, &mut fbb, event).unwrap();
+            }
+
+            // Remove an event, but keep its index entries
+            lmdb.flat()
+                .events
+                .delete(&mut txn, events[2].id.as_bytes())
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        let txn = lmdb.read_txn().unwrap();
+        let expected: Vec<EventId> = lmdb
+            .query(&txn, Filter::new())
+            .unwrap()
+            .map(|e| EventId::from_byte_array(*e.id))
+            .collect();
+        let lazy: Vec<EventId> = lmdb
+            .query_iter(&txn, Filter::new())
+            .unwrap()
+            .map(|res| EventId::from_byte_array(*res.unwrap().id))
+            .collect();
+        assert_eq!(lazy.len(), 4);
+        assert!(!lazy.contains(&events[2].id));
+        assert_eq!(lazy, expected);
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_count_long_tag_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 100)
+            .max_readers(126);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        // Same prefix, filling the whole padding
+        let value: String = "a".repeat(182);
+        let longer: String = "a".repeat(183);
+
+        let keys = Keys::generate();
+        {
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+            for v in [&value, &longer] {
+                let event = EventBuilder::text_note("long tag")
+                    .tag(Tag::hashtag(v))
+                    .sign(&keys)
+                    .unwrap();
+                lmdb.store(&mut txn, &mut fbb, &event).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        let txn = lmdb.read_txn().unwrap();
+        for filter in [
+            Filter::new().hashtag(&value),
+            Filter::new().kind(Kind::TextNote).hashtag(&value),
+            Filter::new().author(keys.public_key()).hashtag(&value),
+        ] {
+            let count: usize = lmdb.count(&txn, filter.clone()).unwrap();
+            let queried: usize = lmdb.query(&txn, filter.clone()).unwrap().count();
+            assert_eq!(queried, 1, "filter: {filter:?}");
+            assert_eq!(count, queried, "filter: {filter:?}");
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_verify_cursor() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 100)
+            .max_readers(126);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        {
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+            for i in 0..5 {
+                let event = create_test_event(1, 1000 + i);
+                lmdb.store(&mut txn, &mut fbb, &event).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        // First batch
+        let txn = lmdb.read_txn().unwrap();
+        let (report, last) = lmdb.verify_batch(&txn, None, 3, false).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(report.checked, 3);
+        assert!(!report.completed);
+
+        // Store the cursor
+        let mut txn = lmdb.write_txn().unwrap();
+        lmdb.remediate(&mut txn, &[], VerifyAction::Report, last.as_ref())
+            .unwrap();
+        txn.commit().unwrap();
+
+        // Resume from the stored cursor
+        let txn = lmdb.read_txn().unwrap();
+        let cursor = lmdb.verify_cursor(&txn).unwrap();
+        assert_eq!(cursor, last);
+        let (report, _) = lmdb.verify_batch(&txn, cursor.as_ref(), 3, false).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(report.checked, 2);
+        assert!(report.completed);
+        assert!(report.is_clean());
+    }
+
     #[test]
     fn test_migration_v1_to_v2() {
         // Create a temporary directory for the test database
@@ -1450,7 +2386,7 @@ mod tests {
             lmdb.store(&mut txn, &mut fbb, &event4).unwrap();
 
             // Manually clear kc_index and set version to 1 to simulate v1 database
-            lmdb.kc_index.clear(&mut txn).unwrap();
+            lmdb.flat().kc_index.clear(&mut txn).unwrap();
             lmdb.metadata.put(&mut txn, DB_VERSION_KEY, &1u64).unwrap();
 
             txn.commit().unwrap();
@@ -1479,11 +2415,57 @@ mod tests {
             assert_eq!(results.len(), 1, "Should find 1 event of kind 5");
 
             // Verify kc_index has entries
-            let kc_count = lmdb.kc_index.len(&txn).unwrap();
+            let kc_count = lmdb.flat().kc_index.len(&txn).unwrap();
             assert_eq!(kc_count, 4, "kc_index should have 4 entries");
         }
     }
 
+    #[test]
+    fn test_migration_v2_to_v3() {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path()).map_size(1024 * 1024 * 100);
+
+        let keys = Keys::generate();
+        let expired = EventBuilder::text_note("Expired")
+            .tag(Tag::expiration(Timestamp::from_secs(1000)))
+            .sign(&keys)
+            .unwrap();
+        let not_expiring = create_test_event(1, 1001);
+
+        // Create a v2 database (without expirations index)
+        {
+            let lmdb = Lmdb::from_builder(lmdb_builder.clone()).unwrap();
+            let mut txn = lmdb.write_txn().unwrap();
+            let mut fbb = FlatBufferBuilder::new();
+
+            lmdb.store(&mut txn, &mut fbb, &expired).unwrap();
+            lmdb.store(&mut txn, &mut fbb, &not_expiring).unwrap();
+
+            lmdb.flat().expirations.clear(&mut txn).unwrap();
+            lmdb.metadata.put(&mut txn, DB_VERSION_KEY, &2u64).unwrap();
+
+            txn.commit().unwrap();
+        }
+
+        // Reopen the database: the migration indexes the expired event
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+        {
+            let txn = lmdb.read_txn().unwrap();
+            assert_eq!(lmdb.flat().expirations.len(&txn).unwrap(), 1);
+            assert_eq!(lmdb.query(&txn, Filter::new()).unwrap().count(), 1);
+            assert_eq!(lmdb.count(&txn, Filter::new()).unwrap(), 1);
+        }
+
+        let mut txn = lmdb.write_txn().unwrap();
+        lmdb.wipe_expired(&mut txn, Timestamp::now()).unwrap();
+        txn.commit().unwrap();
+
+        let txn = lmdb.read_txn().unwrap();
+        assert!(!lmdb.has_event(&txn, &expired.id).unwrap());
+        assert!(lmdb.has_event(&txn, &not_expiring.id).unwrap());
+        assert!(lmdb.flat().expirations.is_empty(&txn).unwrap());
+    }
+
     #[test]
     fn test_migration_new_database() {
         // Create a new database from scratch
@@ -1528,3 +2510,140 @@ mod tests {
         ));
     }
 }
+
+#[cfg(bench)]
+mod benches {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::PartitionGranularity;
+    use crate::test::{Bencher, black_box};
+
+    /// Store 10k reactions, half of them referencing the same event
+    fn setup() -> (TempDir, Lmdb, Filter) {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path()).map_size(1024 * 1024 * 1024);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        let keys = Keys::generate();
+        let target = EventId::all_zeros();
+        let other = EventId::from_byte_array([1; 32]);
+
+        let mut txn = lmdb.write_txn().unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        for i in 0..10_000u64 {
+            let id = if i % 2 == 0 { target } else { other };
+            let event = EventBuilder::new(Kind::Reaction, "+")
+                .tag(Tag::event(id))
+                .custom_created_at(Timestamp::from_secs(1_700_000_000 + i))
+                .sign(&keys)
+                .unwrap();
+            lmdb.store(&mut txn, &mut fbb, &event).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let filter = Filter::new().kind(Kind::Reaction).event(target);
+
+        (temp_dir, lmdb, filter)
+    }
+
+    #[bench]
+    pub fn bench_count_by_index(bh: &mut Bencher) {
+        let (_temp_dir, lmdb, filter) = setup();
+        let txn = lmdb.read_txn().unwrap();
+
+        bh.iter(|| {
+            black_box(lmdb.count(&txn, filter.clone())).unwrap();
+        });
+    }
+
+    #[bench]
+    pub fn bench_count_by_query(bh: &mut Bencher) {
+        let (_temp_dir, lmdb, filter) = setup();
+        let txn = lmdb.read_txn().unwrap();
+
+        bh.iter(|| {
+            black_box(lmdb.query(&txn, filter.clone()).unwrap().count());
+        });
+    }
+
+    const DAY: u64 = 86_400;
+    const START: u64 = 1_699_833_600; // Monday
+
+    /// Store 10k text notes, spread over 100 days
+    fn setup_layout(layout: LmdbLayout) -> (TempDir, Lmdb) {
+        let temp_dir = TempDir::new().unwrap();
+        let lmdb_builder = NostrLmdbBuilder::new(temp_dir.path())
+            .map_size(1024 * 1024 * 1024)
+            .layout(layout);
+        let lmdb = Lmdb::from_builder(lmdb_builder).unwrap();
+
+        let keys = Keys::generate();
+        let events: Vec<Event> = (0..10_000u64)
+            .map(|i| {
+                EventBuilder::text_note(format!("Event {i}"))
+                    .custom_created_at(Timestamp::from_secs(START + i * 100 * DAY / 10_000))
+                    .sign(&keys)
+                    .unwrap()
+            })
+            .collect();
+
+        lmdb.create_partitions(events.iter().map(|event| event.created_at))
+            .unwrap();
+
+        let mut txn = lmdb.write_txn().unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        for event in events.iter() {
+            lmdb.store(&mut txn, &mut fbb, event).unwrap();
+        }
+        txn.commit().unwrap();
+
+        (temp_dir, lmdb)
+    }
+
+    /// Prune the first half of the events, aborting the transaction to keep them
+    fn bench_prune(bh: &mut Bencher, layout: LmdbLayout) {
+        let (_temp_dir, lmdb) = setup_layout(layout);
+        let older_than = Timestamp::from_secs(START + 50 * DAY);
+
+        bh.iter(|| {
+            let mut txn = lmdb.write_txn().unwrap();
+            black_box(lmdb.prune(&mut txn, older_than)).unwrap();
+            txn.abort();
+        });
+    }
+
+    #[bench]
+    pub fn bench_prune_flat(bh: &mut Bencher) {
+        bench_prune(bh, LmdbLayout::Flat);
+    }
+
+    #[bench]
+    pub fn bench_prune_partitioned(bh: &mut Bencher) {
+        bench_prune(bh, LmdbLayout::Partitioned(PartitionGranularity::Week));
+    }
+
+    /// Query the events of 30 days
+    fn bench_query_broad_range(bh: &mut Bencher, layout: LmdbLayout) {
+        let (_temp_dir, lmdb) = setup_layout(layout);
+        let txn = lmdb.read_txn().unwrap();
+        let filter = Filter::new()
+            .kind(Kind::TextNote)
+            .since(Timestamp::from_secs(START + 40 * DAY))
+            .until(Timestamp::from_secs(START + 70 * DAY));
+
+        bh.iter(|| {
+            black_box(lmdb.query(&txn, filter.clone()).unwrap().count());
+        });
+    }
+
+    #[bench]
+    pub fn bench_query_broad_range_flat(bh: &mut Bencher) {
+        bench_query_broad_range(bh, LmdbLayout::Flat);
+    }
+
+    #[bench]
+    pub fn bench_query_broad_range_partitioned(bh: &mut Bencher) {
+        bench_query_broad_range(bh, LmdbLayout::Partitioned(PartitionGranularity::Week));
+    }
+}
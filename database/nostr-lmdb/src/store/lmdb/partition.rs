@@ -0,0 +1,682 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Partitions of the events and of their indexes
+//!
+//! The flat layout has a single partition.
+//! The partitioned layout has a partition for every time bucket,
+//! so the old buckets can be dropped at once, without deleting the events one by one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+use std::sync::{Arc, RwLock};
+
+use heed::byteorder::BigEndian;
+use heed::types::{Bytes, U64, Unit};
+use heed::{Database, Env, RoRange, RoTxn, RwTxn};
+use nostr::prelude::*;
+use nostr_database::flatbuffers::FlatBufferDecodeBorrowed;
+use nostr_database::{FlatBufferBuilder, FlatBufferEncode};
+
+use super::index::{self, EventIndexKeys};
+use super::{EVENT_ID_ALL_255, EVENT_ID_ALL_ZEROS};
+use crate::store::error::Error;
+
+/// Number of databases of a partition
+pub(super) const PARTITION_DBS: u32 = 9;
+
+/// Number of databases of the partitioned layout, beyond the ones of the buckets
+pub(super) const BUCKETS_DBS: u32 = 3;
+
+/// Offset aligning the buckets to the midnight of Monday (UTC)
+///
+/// The UNIX epoch was a Thursday: the first Monday was 4 days later.
+const BUCKET_OFFSET: u64 = 3 * 86_400;
+
+/// Stored event ID and its bucket, if not dropped
+type BucketEntry<'a> = (&'a [u8], Option<Partition>);
+
+/// The events and their indexes
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Partition {
+    /// Events
+    pub(super) events: Database<Bytes, Bytes>, // Event ID, Event
+    /// CreatedAt + ID index
+    pub(super) ci_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Tag + CreatedAt + ID index
+    pub(super) tc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Author + CreatedAt + ID index
+    pub(super) ac_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Author + Kind + CreatedAt + ID index
+    pub(super) akc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Author + Tag + CreatedAt + ID index
+    pub(super) atc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Kind + CreatedAt + ID index
+    pub(super) kc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Kind + Tag + CreatedAt + ID index
+    pub(super) ktc_index: Database<Bytes, Bytes>, // <Index>, Event ID
+    /// Expiration + ID index
+    pub(super) expirations: Database<Bytes, Unit>, // <Index>
+}
+
+impl Partition {
+    /// Open or create the databases of the flat layout
+    pub(super) fn flat(env: &Env, txn: &mut RwTxn) -> Result<Self, Error> {
+        Self::create(env, txn, None)
+    }
+
+    /// Open or create the databases of a time bucket
+    fn bucket(env: &Env, txn: &mut RwTxn, bucket: u64) -> Result<Self, Error> {
+        Self::create(env, txn, Some(&format!("b{bucket}")))
+    }
+
+    fn create(env: &Env, txn: &mut RwTxn, prefix: Option<&str>) -> Result<Self, Error> {
+        let name = |name: &str| -> String {
+            match prefix {
+                Some(prefix) => format!("{prefix}-{name}"),
+                None => name.to_string(),
+            }
+        };
+
+        // The flat layout keeps the events in the unnamed database
+        let events = match prefix {
+            Some(..) => env
+                .database_options()
+                .types::<Bytes, Bytes>()
+                .name(&name("events"))
+                .create(txn)?,
+            None => env.database_options().types::<Bytes, Bytes>().create(txn)?,
+        };
+        let ci_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("ci"))
+            .create(txn)?;
+        let tc_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("tci"))
+            .create(txn)?;
+        let ac_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("aci"))
+            .create(txn)?;
+        let akc_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("akci"))
+            .create(txn)?;
+        let atc_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("atci"))
+            .create(txn)?;
+        let kc_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("kci"))
+            .create(txn)?;
+        let ktc_index = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&name("ktci"))
+            .create(txn)?;
+        let expirations = env
+            .database_options()
+            .types::<Bytes, Unit>()
+            .name(&name("expirations"))
+            .create(txn)?;
+
+        Ok(Self {
+            events,
+            ci_index,
+            tc_index,
+            ac_index,
+            akc_index,
+            atc_index,
+            kc_index,
+            ktc_index,
+            expirations,
+        })
+    }
+
+    /// Store and index the event
+    pub(super) fn store(
+        &self,
+        txn: &mut RwTxn,
+        fbb: &mut FlatBufferBuilder,
+        event: &Event,
+    ) -> Result<(), Error> {
+        // Store event
+        self.events
+            .put(txn, event.id.as_bytes(), event.encode(fbb))?;
+
+        // Index event
+        let event: EventBorrow = EventBorrow::from(event);
+        let index: EventIndexKeys = EventIndexKeys::new(event);
+        self.index_event(txn, index)
+    }
+
+    fn index_event(&self, txn: &mut RwTxn, index: EventIndexKeys) -> Result<(), Error> {
+        self.ci_index.put(txn, &index.ci_index, &index.id)?;
+        self.akc_index.put(txn, &index.akc_index, &index.id)?;
+        self.ac_index.put(txn, &index.ac_index, &index.id)?;
+        self.kc_index.put(txn, &index.kc_index, &index.id)?;
+
+        for tag in index.tags.into_iter() {
+            self.atc_index.put(txn, &tag.atc_index, &index.id)?;
+            self.ktc_index.put(txn, &tag.ktc_index, &index.id)?;
+            self.tc_index.put(txn, &tag.tc_index, &index.id)?;
+        }
+
+        if let Some(expiration) = &index.expiration {
+            self.expirations.put(txn, expiration, &())?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete the event and all its index entries
+    pub(super) fn remove(&self, txn: &mut RwTxn, index: &EventIndexKeys) -> Result<(), Error> {
+        self.events.delete(txn, &index.id)?;
+        self.ci_index.delete(txn, &index.ci_index)?;
+        self.akc_index.delete(txn, &index.akc_index)?;
+        self.ac_index.delete(txn, &index.ac_index)?;
+        self.kc_index.delete(txn, &index.kc_index)?;
+
+        // Delete tag indexes
+        for tag in &index.tags {
+            self.atc_index.delete(txn, &tag.atc_index)?;
+            self.ktc_index.delete(txn, &tag.ktc_index)?;
+            self.tc_index.delete(txn, &tag.tc_index)?;
+        }
+
+        if let Some(expiration) = &index.expiration {
+            self.expirations.delete(txn, expiration)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drop the events and all the indexes
+    ///
+    /// The pages of the databases are released at once, without deleting the entries one by one.
+    pub(super) fn clear(&self, txn: &mut RwTxn) -> Result<(), Error> {
+        self.events.clear(txn)?;
+        self.clear_indexes(txn)
+    }
+
+    pub(super) fn clear_indexes(&self, txn: &mut RwTxn) -> Result<(), Error> {
+        self.ci_index.clear(txn)?;
+        self.tc_index.clear(txn)?;
+        self.ac_index.clear(txn)?;
+        self.akc_index.clear(txn)?;
+        self.kc_index.clear(txn)?;
+        self.atc_index.clear(txn)?;
+        self.ktc_index.clear(txn)?;
+        self.expirations.clear(txn)?;
+        Ok(())
+    }
+
+    pub(super) fn reindex(&self, txn: &mut RwTxn) -> Result<(), Error> {
+        // First, wipe all indexes
+        self.clear_indexes(txn)?;
+
+        // Collect indexes
+        // TODO: avoid this allocation
+        let size: u64 = self.events.len(txn)?;
+        let mut indexes: Vec<EventIndexKeys> = Vec::with_capacity(size as usize);
+
+        for result in self.events.iter(txn)? {
+            let (_id, event) = result?;
+
+            // Decode event
+            if let Ok(event) = EventBorrow::decode(event) {
+                // Build indexes
+                let index: EventIndexKeys = EventIndexKeys::new(event);
+                indexes.push(index);
+            }
+        }
+
+        for index in indexes.into_iter() {
+            self.index_event(txn, index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all index entries pointing to the event ID
+    ///
+    /// This is INEFFICIENT as it scans through all the indexes
+    pub(super) fn remove_dangling_index_entries(
+        &self,
+        txn: &mut RwTxn,
+        id: &EventId,
+    ) -> Result<(), Error> {
+        let indexes = [
+            self.ci_index,
+            self.tc_index,
+            self.ac_index,
+            self.akc_index,
+            self.atc_index,
+            self.kc_index,
+            self.ktc_index,
+        ];
+
+        for index in indexes.into_iter() {
+            let mut keys: Vec<Vec<u8>> = Vec::new();
+
+            for result in index.iter(txn)? {
+                let (key, value) = result?;
+                if value == id.as_bytes() {
+                    keys.push(key.to_vec());
+                }
+            }
+
+            for key in keys.into_iter() {
+                index.delete(txn, &key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub(super) fn get_event_by_id<'a>(
+        &self,
+        txn: &'a RoTxn,
+        event_id: &[u8],
+    ) -> Result<Option<EventBorrow<'a>>, Error> {
+        match self.events.get(txn, event_id)? {
+            Some(bytes) => Ok(Some(EventBorrow::decode(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(super) fn ci_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_ci_index_key(until, &EVENT_ID_ALL_ZEROS);
+        let end_prefix = index::make_ci_index_key(since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.ci_index.range(txn, &range)?)
+    }
+
+    pub(super) fn tc_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        tag_name: &SingleLetterTag,
+        tag_value: &str,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_tc_index_key(
+            tag_name,
+            tag_value,
+            until, // scan goes backwards in time
+            &EVENT_ID_ALL_ZEROS,
+        );
+        let end_prefix = index::make_tc_index_key(tag_name, tag_value, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.tc_index.range(txn, &range)?)
+    }
+
+    pub(super) fn ac_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        author: &[u8; 32],
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_ac_index_key(author, until, &EVENT_ID_ALL_ZEROS);
+        let end_prefix = index::make_ac_index_key(author, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.ac_index.range(txn, &range)?)
+    }
+
+    pub(super) fn akc_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        author: &[u8; 32],
+        kind: u16,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_akc_index_key(author, kind, until, &EVENT_ID_ALL_ZEROS);
+        let end_prefix = index::make_akc_index_key(author, kind, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.akc_index.range(txn, &range)?)
+    }
+
+    pub(super) fn kc_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        kind: u16,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_kc_index_key(kind, until, &EVENT_ID_ALL_ZEROS);
+        let end_prefix = index::make_kc_index_key(kind, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.kc_index.range(txn, &range)?)
+    }
+
+    pub(super) fn atc_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        author: &[u8; 32],
+        tag_name: &SingleLetterTag,
+        tag_value: &str,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix: Vec<u8> = index::make_atc_index_key(
+            author,
+            tag_name,
+            tag_value,
+            until, // scan goes backwards in time
+            &EVENT_ID_ALL_ZEROS,
+        );
+        let end_prefix: Vec<u8> =
+            index::make_atc_index_key(author, tag_name, tag_value, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.atc_index.range(txn, &range)?)
+    }
+
+    pub(super) fn ktc_iter<'a>(
+        &self,
+        txn: &'a RoTxn,
+        kind: u16,
+        tag_name: &SingleLetterTag,
+        tag_value: &str,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> Result<RoRange<'a, Bytes, Bytes>, Error> {
+        let start_prefix = index::make_ktc_index_key(
+            kind,
+            tag_name,
+            tag_value,
+            until, // scan goes backwards in time
+            &EVENT_ID_ALL_ZEROS,
+        );
+        let end_prefix =
+            index::make_ktc_index_key(kind, tag_name, tag_value, since, &EVENT_ID_ALL_255);
+        let range = (
+            Bound::Included(start_prefix.as_slice()),
+            Bound::Excluded(end_prefix.as_slice()),
+        );
+        Ok(self.ktc_index.range(txn, &range)?)
+    }
+}
+
+/// Time buckets of the partitioned layout
+///
+/// The buckets are aligned to the midnight of Monday (UTC), so the weekly ones are the ISO weeks.
+/// The event IDs and the coordinates of the replaceable and addressable events are indexed globally,
+/// so the point lookups don't need to visit every bucket.
+#[derive(Debug, Clone)]
+pub(super) struct Buckets {
+    /// Width of the buckets, in seconds
+    width: u64,
+    /// Maximum number of buckets
+    max: usize,
+    /// Event ID + bucket index
+    ids: Database<Bytes, U64<BigEndian>>, // Event ID, Bucket
+    /// Coordinate + event ID index of the stored replaceable and addressable events
+    ///
+    /// Only the latest version of a coordinate is stored, but it may live in another bucket
+    /// than the previous ones.
+    latest: Database<Bytes, Bytes>, // Coordinate, Event ID
+    /// Created buckets
+    list: Database<U64<BigEndian>, Unit>, // Bucket
+    /// Opened buckets
+    opened: Arc<RwLock<BTreeMap<u64, Partition>>>,
+}
+
+impl Buckets {
+    /// Open or create the global databases and open all the buckets
+    pub(super) fn open(env: &Env, txn: &mut RwTxn, width: u64, max: usize) -> Result<Self, Error> {
+        let ids = env
+            .database_options()
+            .types::<Bytes, U64<BigEndian>>()
+            .name("bucket-ids")
+            .create(txn)?;
+        let latest = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name("bucket-latest")
+            .create(txn)?;
+        let list = env
+            .database_options()
+            .types::<U64<BigEndian>, Unit>()
+            .name("buckets")
+            .create(txn)?;
+
+        let buckets: Vec<u64> = list
+            .iter(txn)?
+            .map(|res| res.map(|(bucket, ())| bucket))
+            .collect::<Result<_, _>>()?;
+
+        let mut opened: BTreeMap<u64, Partition> = BTreeMap::new();
+        for bucket in buckets.into_iter() {
+            opened.insert(bucket, Partition::bucket(env, txn, bucket)?);
+        }
+
+        Ok(Self {
+            width,
+            max,
+            ids,
+            latest,
+            list,
+            opened: Arc::new(RwLock::new(opened)),
+        })
+    }
+
+    /// Get the bucket of a timestamp
+    #[inline]
+    pub(super) fn bucket(&self, timestamp: Timestamp) -> u64 {
+        timestamp.as_secs().saturating_add(BUCKET_OFFSET) / self.width
+    }
+
+    /// Get the last second of the bucket
+    #[inline]
+    fn end(&self, bucket: u64) -> Timestamp {
+        let next: u64 = bucket.saturating_add(1).saturating_mul(self.width);
+        Timestamp::from_secs(next.saturating_sub(BUCKET_OFFSET + 1))
+    }
+
+    #[inline]
+    pub(super) fn get(&self, bucket: u64) -> Option<Partition> {
+        let opened = self.opened.read().unwrap_or_else(|e| e.into_inner());
+        opened.get(&bucket).copied()
+    }
+
+    /// Get all the buckets
+    pub(super) fn all(&self) -> Vec<Partition> {
+        let opened = self.opened.read().unwrap_or_else(|e| e.into_inner());
+        opened.values().copied().collect()
+    }
+
+    /// Get the buckets overlapping the time range, from the newest
+    pub(super) fn overlapping(&self, since: Timestamp, until: Timestamp) -> Vec<Partition> {
+        if since > until {
+            return Vec::new();
+        }
+
+        let opened = self.opened.read().unwrap_or_else(|e| e.into_inner());
+        opened
+            .range(self.bucket(since)..=self.bucket(until))
+            .rev()
+            .map(|(_, partition)| *partition)
+            .collect()
+    }
+
+    /// Get the buckets ending before `timestamp`
+    pub(super) fn ended_before(&self, timestamp: Timestamp) -> Vec<Partition> {
+        let opened = self.opened.read().unwrap_or_else(|e| e.into_inner());
+        opened
+            .iter()
+            .filter(|(bucket, _)| self.end(**bucket) < timestamp)
+            .map(|(_, partition)| *partition)
+            .collect()
+    }
+
+    /// Create the missing buckets of the timestamps, in their own transaction
+    ///
+    /// The databases created in a transaction can't be used if the transaction is aborted,
+    /// so the buckets are created before storing the events.
+    pub(super) fn create<I>(&self, env: &Env, timestamps: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Timestamp>,
+    {
+        let missing: BTreeSet<u64> = {
+            let opened = self.opened.read().unwrap_or_else(|e| e.into_inner());
+            timestamps
+                .into_iter()
+                .map(|timestamp| self.bucket(timestamp))
+                .filter(|bucket| !opened.contains_key(bucket))
+                .collect()
+        };
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let mut opened = self.opened.write().unwrap_or_else(|e| e.into_inner());
+
+        if opened.len() + missing.len() > self.max {
+            return Err(Error::TooManyBuckets);
+        }
+
+        let mut txn = env.write_txn()?;
+        let mut created: Vec<(u64, Partition)> = Vec::with_capacity(missing.len());
+
+        for bucket in missing.into_iter() {
+            created.push((bucket, Partition::bucket(env, &mut txn, bucket)?));
+            self.list.put(&mut txn, &bucket, &())?;
+        }
+
+        txn.commit()?;
+
+        // Usable only after the commit
+        opened.extend(created);
+
+        Ok(())
+    }
+
+    /// Get the bucket of a stored event
+    pub(super) fn locate(&self, txn: &RoTxn, event_id: &[u8]) -> Result<Option<Partition>, Error> {
+        Ok(self
+            .ids
+            .get(txn, event_id)?
+            .and_then(|bucket| self.get(bucket)))
+    }
+
+    /// Index the event ID and, for the replaceable and addressable events, the coordinate
+    pub(super) fn track(&self, txn: &mut RwTxn, event: &Event) -> Result<(), Error> {
+        let bucket: u64 = self.bucket(event.created_at);
+        self.ids.put(txn, event.id.as_bytes(), &bucket)?;
+
+        if let Some(coordinate) = event.coordinate() {
+            let key: Vec<u8> = index::make_coordinate_index_key(&coordinate);
+            self.latest.put(txn, &key, event.id.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the event ID from the global index
+    ///
+    /// The coordinate entry is left in place: it's ignored once its event is gone,
+    /// and replaced by the next version.
+    #[inline]
+    pub(super) fn untrack(&self, txn: &mut RwTxn, event_id: &[u8]) -> Result<(), Error> {
+        self.ids.delete(txn, event_id)?;
+        Ok(())
+    }
+
+    /// Get the ID of the latest stored event of the coordinate
+    pub(super) fn latest<'a>(
+        &self,
+        txn: &'a RoTxn,
+        coordinate: &Coordinate,
+    ) -> Result<Option<&'a [u8]>, Error> {
+        let key: Vec<u8> = index::make_coordinate_index_key(coordinate);
+        Ok(self.latest.get(txn, &key)?)
+    }
+
+    /// Iterate the stored event IDs and their buckets, in the order of the IDs
+    pub(super) fn ids<'a>(
+        &self,
+        txn: &'a RoTxn,
+        after: Option<&EventId>,
+    ) -> Result<impl Iterator<Item = Result<BucketEntry<'a>, Error>> + 'a, Error> {
+        let range = match after {
+            Some(after) => (
+                Bound::Excluded(after.as_bytes().as_slice()),
+                Bound::Unbounded,
+            ),
+            None => (Bound::Unbounded, Bound::Unbounded),
+        };
+
+        let buckets: Self = self.clone();
+        Ok(self.ids.range(txn, &range)?.map(move |res| {
+            let (id, bucket) = res?;
+            Ok((id, buckets.get(bucket)))
+        }))
+    }
+
+    /// Drop the buckets ending before `timestamp`
+    ///
+    /// Only the entries of the global ID index are removed one by one:
+    /// the events and their indexes are dropped with their databases.
+    pub(super) fn prune(&self, txn: &mut RwTxn, timestamp: Timestamp) -> Result<(), Error> {
+        for partition in self.ended_before(timestamp).into_iter() {
+            let ids: Vec<Vec<u8>> = partition
+                .events
+                .iter(txn)?
+                .map(|res| res.map(|(id, _)| id.to_vec()))
+                .collect::<Result<_, _>>()?;
+
+            for id in ids.into_iter() {
+                self.ids.delete(txn, &id)?;
+            }
+
+            partition.clear(txn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear the global indexes
+    ///
+    /// The buckets are kept, since their databases can't be deleted.
+    pub(super) fn clear(&self, txn: &mut RwTxn) -> Result<(), Error> {
+        self.ids.clear(txn)?;
+        self.latest.clear(txn)?;
+        Ok(())
+    }
+}
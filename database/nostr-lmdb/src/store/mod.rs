@@ -5,7 +5,7 @@
 
 use std::fs;
 
-use async_utility::task;
+use async_utility::{task, time};
 use flume::Sender;
 use heed::RoTxn;
 use nostr_database::prelude::*;
@@ -20,6 +20,9 @@ use self::ingester::{Ingester, IngesterItem};
 use self::lmdb::Lmdb;
 use crate::NostrLmdbBuilder;
 
+/// Number of events buffered by [`Store::stream_query`]
+pub(crate) const STREAM_QUERY_BUFFER_SIZE: usize = 128;
+
 #[derive(Debug)]
 pub(super) struct Store {
     db: Lmdb,
@@ -68,6 +71,15 @@ impl Store {
         rx.await?
     }
 
+    pub(super) async fn save_events(
+        &self,
+        events: Vec<Event>,
+    ) -> Result<Vec<SaveEventStatus>, Error> {
+        let (item, rx) = IngesterItem::save_events_with_feedback(events);
+        self.ingester.send(item).map_err(|_| Error::FlumeSend)?;
+        rx.await?
+    }
+
     pub(super) async fn get_event_by_id(&self, id: EventId) -> Result<Option<Event>, Error> {
         self.interact(move |db| {
             let txn = db.read_txn()?;
@@ -99,6 +111,16 @@ impl Store {
         .await?
     }
 
+    pub(super) async fn contains(&self, id: EventId) -> Result<bool, Error> {
+        self.interact(move |db| {
+            let txn = db.read_txn()?;
+            let contains: bool = db.has_event(&txn, &id)?;
+            txn.commit()?;
+            Ok(contains)
+        })
+        .await?
+    }
+
     pub(super) async fn count(&self, filter: Filter) -> Result<usize, Error> {
         self.interact(move |db| {
             let txn = db.read_txn()?;
@@ -124,6 +146,51 @@ impl Store {
         .await?
     }
 
+    /// Stream the events matching the filter
+    ///
+    /// The events are lazily read from the indexes in a blocking task, holding a read transaction,
+    /// and sent through a bounded channel.
+    /// When the stream is dropped, the task stops and the read transaction is released.
+    pub(super) fn stream_query(
+        &self,
+        filter: Filter,
+    ) -> BoxedStream<'static, Result<Event, DatabaseError>> {
+        let (tx, rx) = flume::bounded(STREAM_QUERY_BUFFER_SIZE);
+        let db: Lmdb = self.db.clone();
+
+        task::spawn_blocking(move || {
+            let txn: RoTxn = match db.read_txn() {
+                Ok(txn) => txn,
+                Err(e) => {
+                    let _ = tx.send(Err(DatabaseError::backend(e)));
+                    return;
+                }
+            };
+
+            match db.query_iter(&txn, filter) {
+                Ok(events) => {
+                    for res in events {
+                        let res = res
+                            .map(|event| event.into_owned())
+                            .map_err(DatabaseError::backend);
+
+                        // Stop if the stream has been dropped
+                        if tx.send(res).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(DatabaseError::backend(e)));
+                }
+            }
+
+            // The read transaction is released here
+        });
+
+        Box::pin(rx.into_stream())
+    }
+
     pub(super) async fn negentropy_items(
         &self,
         filter: Filter,
@@ -144,6 +211,90 @@ impl Store {
         rx.await?
     }
 
+    pub(super) async fn verify_events(&self, opts: VerifyOptions) -> Result<VerifyReport, Error> {
+        let mut report: VerifyReport = VerifyReport::default();
+
+        // Get the cursor to resume from
+        let mut cursor: Option<EventId> = if opts.resume {
+            self.interact(|db| {
+                let txn = db.read_txn()?;
+                let cursor: Option<EventId> = db.verify_cursor(&txn)?;
+                txn.commit()?;
+                Ok::<_, Error>(cursor)
+            })
+            .await??
+        } else {
+            None
+        };
+
+        let batch_size: usize = opts.batch_size.max(1);
+
+        loop {
+            // Calculate the batch size, respecting the limit
+            let size: usize = match opts.limit {
+                Some(limit) => batch_size.min(limit.saturating_sub(report.checked)),
+                None => batch_size,
+            };
+
+            if size == 0 {
+                break;
+            }
+
+            let signatures: bool = opts.signatures;
+            let (batch, last) = self
+                .interact(move |db| {
+                    let txn = db.read_txn()?;
+                    let output = db.verify_batch(&txn, cursor.as_ref(), size, signatures)?;
+                    txn.commit()?;
+                    Ok::<_, Error>(output)
+                })
+                .await??;
+
+            // Quarantine or delete the invalid events and store the new cursor
+            let ids: Vec<EventId> = batch
+                .id_mismatches
+                .iter()
+                .chain(batch.invalid_signatures.iter())
+                .chain(batch.undecodable.iter())
+                .copied()
+                .collect();
+            cursor = if batch.completed {
+                None
+            } else {
+                last.or(cursor)
+            };
+            let (item, rx) = IngesterItem::remediate_with_feedback(ids, opts.action, cursor);
+            self.ingester.send(item).map_err(|_| Error::FlumeSend)?;
+            rx.await??;
+
+            let completed: bool = batch.completed;
+            report.merge(batch);
+
+            if completed {
+                break;
+            }
+
+            // Give room to the other queries
+            if let Some(pause) = opts.pause {
+                time::sleep(pause).await;
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub(super) async fn wipe_expired(&self) -> Result<(), Error> {
+        let (item, rx) = IngesterItem::wipe_expired_with_feedback(Timestamp::now());
+        self.ingester.send(item).map_err(|_| Error::FlumeSend)?;
+        rx.await?
+    }
+
+    pub(super) async fn prune(&self, older_than: Timestamp) -> Result<(), Error> {
+        let (item, rx) = IngesterItem::prune_with_feedback(older_than);
+        self.ingester.send(item).map_err(|_| Error::FlumeSend)?;
+        rx.await?
+    }
+
     pub(super) async fn wipe(&self) -> Result<(), Error> {
         let (item, rx) = IngesterItem::wipe_with_feedback();
         self.ingester.send(item).map_err(|_| Error::FlumeSend)?;
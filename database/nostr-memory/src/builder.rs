@@ -15,6 +15,10 @@ pub struct MemoryDatabaseBuilder {
     ///
     /// Defaults to `true`
     pub(crate) process_nip09: bool,
+    /// Whether to exclude the expired (NIP-40) events from the queries.
+    ///
+    /// Defaults to `true`
+    pub(crate) process_nip40: bool,
     /// Whether to process request to vanish (NIP-62) events.
     pub(crate) process_nip62: bool,
     /// Relay URL for relay-specific request to vanish (NIP-62).
@@ -26,6 +30,7 @@ impl Default for MemoryDatabaseBuilder {
         Self {
             max_events: None,
             process_nip09: true,
+            process_nip40: true,
             process_nip62: true,
             relay_url: None,
         }
@@ -51,6 +56,18 @@ impl MemoryDatabaseBuilder {
         self
     }
 
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    ///
+    /// Disable it to keep serving the expired events (i.e., for archival use).
+    /// The expired events can be physically removed with [`NostrDatabase::wipe_expired`](nostr_database::NostrDatabase::wipe_expired).
+    ///
+    /// Defaults to `true`
+    #[inline]
+    pub fn process_nip40(mut self, process_nip40: bool) -> Self {
+        self.process_nip40 = process_nip40;
+        self
+    }
+
     /// Whether to process request to vanish (NIP-62) events
     ///
     /// Defaults to `true`
@@ -24,6 +24,7 @@ use self::store::{MemoryOptions, MemoryStore};
 #[derive(Debug)]
 pub struct MemoryDatabase {
     store: RwLock<MemoryStore>,
+    process_nip40: bool,
 }
 
 impl MemoryDatabase {
@@ -49,12 +50,14 @@ impl MemoryDatabase {
     fn from_builder(builder: MemoryDatabaseBuilder) -> Self {
         let options = MemoryOptions {
             process_nip09: builder.process_nip09,
+            process_nip40: builder.process_nip40,
             process_nip62: builder.process_nip62,
             relay_url: builder.relay_url,
         };
 
         Self {
             store: RwLock::new(MemoryStore::new(builder.max_events, options)),
+            process_nip40: builder.process_nip40,
         }
     }
 }
@@ -67,7 +70,7 @@ impl NostrDatabase for MemoryDatabase {
     fn features(&self) -> Features {
         Features {
             persistent: false,
-            event_expiration: false,
+            event_expiration: self.process_nip40,
             full_text_search: true,
             request_to_vanish: true,
         }
@@ -100,6 +103,16 @@ impl NostrDatabase for MemoryDatabase {
         })
     }
 
+    fn contains<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<bool, DatabaseError>> {
+        Box::pin(async move {
+            let store = self.store.read().await;
+            Ok(store.has_event(event_id))
+        })
+    }
+
     fn event_by_id<'a>(
         &'a self,
         event_id: &'a EventId,
@@ -152,6 +165,14 @@ impl NostrDatabase for MemoryDatabase {
             Ok(())
         })
     }
+
+    fn wipe_expired(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            let mut store = self.store.write().await;
+            store.wipe_expired(Timestamp::now());
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
@@ -191,4 +212,79 @@ mod tests {
         TestDatabase::new,
         TestDatabase::new_with_relay_url
     );
+
+    #[tokio::test]
+    async fn test_query_skips_expired_events() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("Expiring")
+            .tag(Tag::expiration(Timestamp::now() + 1))
+            .sign(&keys)
+            .unwrap();
+
+        let database = MemoryDatabase::unbounded();
+        let archive = MemoryDatabase::builder().process_nip40(false).build();
+
+        for db in [&database, &archive] {
+            let status = db.save_event(&event).await.unwrap();
+            assert_eq!(status, SaveEventStatus::Success);
+            assert_eq!(db.count(Filter::new()).await.unwrap(), 1);
+        }
+
+        // Wait for the expiration
+        time::sleep(Duration::from_secs(2)).await;
+
+        // Not served anymore, but still stored
+        assert_eq!(database.count(Filter::new()).await.unwrap(), 0);
+        assert!(
+            database
+                .query(Filter::new().author(keys.public_key))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(database.contains(&event.id).await.unwrap());
+
+        // Served by the archive
+        assert_eq!(archive.count(Filter::new()).await.unwrap(), 1);
+        assert_eq!(
+            archive
+                .query(Filter::new().author(keys.public_key))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_deletion_is_not_applied() {
+        let keys = Keys::generate();
+        let other = Keys::generate();
+
+        let database = MemoryDatabase::unbounded();
+
+        let event = EventBuilder::text_note("Stored").sign(&keys).unwrap();
+        database.save_event(&event).await.unwrap();
+
+        // Not received yet
+        let later = EventBuilder::text_note("Later").sign(&keys).unwrap();
+
+        // The coordinate of another author invalidates the whole request
+        let request = EventDeletionRequest::new()
+            .id(event.id)
+            .id(later.id)
+            .coordinate(Coordinate::new(Kind::Metadata, other.public_key));
+        let deletion = EventBuilder::delete(request).sign(&keys).unwrap();
+
+        let status = database.save_event(&deletion).await.unwrap();
+        assert_eq!(
+            status,
+            SaveEventStatus::Rejected(RejectedReason::InvalidDelete)
+        );
+
+        // Nothing has been deleted
+        assert!(database.event_by_id(&event.id).await.unwrap().is_some());
+        let status = database.save_event(&later).await.unwrap();
+        assert_eq!(status, SaveEventStatus::Success);
+    }
 }
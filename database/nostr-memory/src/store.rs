@@ -63,6 +63,7 @@ impl QueryByParamReplaceable {
 #[derive(Debug, Clone, Default)]
 pub(crate) struct MemoryOptions {
     pub(crate) process_nip09: bool,
+    pub(crate) process_nip40: bool,
     pub(crate) process_nip62: bool,
     pub(crate) relay_url: Option<RelayUrl>,
 }
@@ -148,8 +149,12 @@ pub(crate) struct MemoryStore {
     author_index: HashMap<PublicKey, BTreeSet<DatabaseEvent>>,
     kind_author_index: HashMap<(Kind, PublicKey), BTreeSet<DatabaseEvent>>,
     param_replaceable_index: HashMap<(Kind, PublicKey, String), DatabaseEvent>,
+    /// Events with an expiration (NIP-40), sorted by expiration
+    expirations: BTreeSet<(Timestamp, EventId)>,
     deleted_ids: HashSet<EventId>,
     deleted_coordinates: HashMap<Coordinate, Timestamp>,
+    /// Deletion requests for events not yet received, with the public key of the deletion author
+    deletion_requests: HashSet<(EventId, PublicKey)>,
     vanished_public_keys: HashSet<PublicKey>,
 }
 
@@ -186,6 +191,11 @@ impl MemoryStore {
             return SaveEventStatus::Rejected(RejectedReason::Deleted);
         }
 
+        // Check if the author requested the deletion before
+        if self.deletion_requests.contains(&(event.id, event.pubkey)) {
+            return SaveEventStatus::Rejected(RejectedReason::Deleted);
+        }
+
         if self.is_pubkey_vanished(&event.pubkey) {
             return SaveEventStatus::Rejected(RejectedReason::Vanished);
         }
@@ -246,56 +256,67 @@ impl MemoryStore {
                 None => status = SaveEventStatus::Rejected(RejectedReason::Other),
             }
         } else if self.options.process_nip09 && kind == Kind::EventDeletion {
-            // Check `e` tags
-            for id in event.tags.event_ids() {
-                if let Some(ev) = self.ids.get(&id) {
-                    if ev.pubkey != author {
-                        status = SaveEventStatus::Rejected(RejectedReason::InvalidDelete);
-                        break;
-                    }
-
-                    if ev.created_at <= created_at {
-                        to_discard.insert(ev.id);
-                        self.deleted_ids.insert(ev.id);
+            // Validate all the targets before applying the deletion
+            let invalid: bool = event
+                .tags
+                .event_ids()
+                .any(|id| self.ids.get(&id).is_some_and(|ev| ev.pubkey != author))
+                || event
+                    .tags
+                    .coordinates()
+                    .any(|coordinate| coordinate.public_key != author);
+
+            if invalid {
+                status = SaveEventStatus::Rejected(RejectedReason::InvalidDelete);
+            } else {
+                // Check `e` tags
+                for id in event.tags.event_ids() {
+                    match self.ids.get(&id) {
+                        Some(ev) => {
+                            if ev.created_at <= created_at {
+                                to_discard.insert(ev.id);
+                                self.deleted_ids.insert(ev.id);
+                            }
+                        }
+                        // Not received yet: the author is checked when the event is received
+                        None => {
+                            self.deletion_requests.insert((id, author));
+                        }
                     }
                 }
-            }
-
-            // Check `a` tags
-            for coordinate in event.tags.coordinates() {
-                if coordinate.public_key != author {
-                    status = SaveEventStatus::Rejected(RejectedReason::InvalidDelete);
-                    break;
-                }
 
-                // Save deleted coordinate at certain timestamp
-                self.deleted_coordinates
-                    .entry(coordinate.clone())
-                    .and_modify(|t| {
-                        // Update only if newer
-                        if created_at > *t {
-                            *t = created_at
+                // Check `a` tags
+                for coordinate in event.tags.coordinates() {
+                    // Save deleted coordinate at certain timestamp
+                    self.deleted_coordinates
+                        .entry(coordinate.clone())
+                        .and_modify(|t| {
+                            // Update only if newer
+                            if created_at > *t {
+                                *t = created_at
+                            }
+                        })
+                        .or_insert(created_at);
+
+                    // Not check if ev.pubkey match the author because assume that query
+                    // returned only the events owned by author
+                    if !coordinate.identifier.is_empty() {
+                        let mut params: QueryByParamReplaceable = QueryByParamReplaceable::new(
+                            coordinate.kind,
+                            coordinate.public_key,
+                            coordinate.identifier.clone(),
+                        );
+                        params.until = Some(created_at);
+                        if let Some(ev) = self.internal_query_param_replaceable(params) {
+                            to_discard.insert(ev.id);
                         }
-                    })
-                    .or_insert(created_at);
-
-                // Not check if ev.pubkey match the author because assume that query
-                // returned only the events owned by author
-                if !coordinate.identifier.is_empty() {
-                    let mut params: QueryByParamReplaceable = QueryByParamReplaceable::new(
-                        coordinate.kind,
-                        coordinate.public_key,
-                        coordinate.identifier.clone(),
-                    );
-                    params.until = Some(created_at);
-                    if let Some(ev) = self.internal_query_param_replaceable(params) {
-                        to_discard.insert(ev.id);
+                    } else {
+                        let mut params: QueryByKindAndAuthorParams =
+                            QueryByKindAndAuthorParams::new(coordinate.kind, coordinate.public_key);
+                        params.until = Some(created_at);
+                        to_discard
+                            .extend(self.internal_query_by_kind_and_author(params).map(|e| e.id));
                     }
-                } else {
-                    let mut params: QueryByKindAndAuthorParams =
-                        QueryByKindAndAuthorParams::new(coordinate.kind, coordinate.public_key);
-                    params.until = Some(created_at);
-                    to_discard.extend(self.internal_query_by_kind_and_author(params).map(|e| e.id));
                 }
             }
         } else if self.options.process_nip62
@@ -319,6 +340,10 @@ impl MemoryStore {
 
             if inserted {
                 self.ids.insert(e.id, e.clone());
+
+                if let Some(expiration) = e.tags.expiration() {
+                    self.expirations.insert((expiration, e.id));
+                }
                 self.author_index
                     .entry(author)
                     .or_default()
@@ -368,6 +393,10 @@ impl MemoryStore {
             if let Some(ev) = self.ids.remove(id) {
                 self.events.remove(&ev);
 
+                if let Some(expiration) = ev.tags.expiration() {
+                    self.expirations.remove(&(expiration, ev.id));
+                }
+
                 if let Some(set) = self.author_index.get_mut(&ev.pubkey) {
                     set.remove(&ev);
                 }
@@ -392,6 +421,10 @@ impl MemoryStore {
     fn discard_event(&mut self, ev: DatabaseEvent) {
         self.ids.remove(&ev.id);
 
+        if let Some(expiration) = ev.tags.expiration() {
+            self.expirations.remove(&(expiration, ev.id));
+        }
+
         if let Some(set) = self.author_index.get_mut(&ev.pubkey) {
             set.remove(&ev);
         }
@@ -538,8 +571,21 @@ impl MemoryStore {
         })
     }
 
+    /// Check if there are expired events to exclude from the queries
+    #[inline]
+    fn has_expired_events(&self, now: Timestamp) -> bool {
+        self.options.process_nip40
+            && self
+                .expirations
+                .first()
+                .is_some_and(|(expiration, ..)| *expiration <= now)
+    }
+
     fn internal_query(&self, filter: Filter) -> InternalQueryResult<'_> {
-        if filter.is_empty() {
+        let now: Timestamp = Timestamp::now();
+        let skip_expired: bool = self.has_expired_events(now);
+
+        if filter.is_empty() && !skip_expired {
             return InternalQueryResult::All;
         }
 
@@ -564,6 +610,13 @@ impl MemoryStore {
             QueryPattern::Generic(filter) => Box::new(self.internal_generic_query(*filter)),
         };
 
+        // Exclude the expired events
+        let evs: Box<dyn Iterator<Item = &DatabaseEvent>> = if skip_expired {
+            Box::new(evs.filter(move |ev| !ev.is_expired_at(now)))
+        } else {
+            evs
+        };
+
         if let Some(limit) = limit {
             matching_ids.extend(evs.take(limit))
         } else {
@@ -642,6 +695,17 @@ impl MemoryStore {
         }
     }
 
+    /// Delete the events expired at `now`
+    pub fn wipe_expired(&mut self, now: Timestamp) {
+        let ids: HashSet<EventId> = self
+            .expirations
+            .iter()
+            .take_while(|(expiration, ..)| *expiration <= now)
+            .map(|(.., id)| *id)
+            .collect();
+        self.discard_events(ids);
+    }
+
     pub fn clear(&mut self) {
         // Get current capacity
         let capacity: Capacity = self.events.capacity();
@@ -0,0 +1,7 @@
+PRAGMA user_version = 3; -- Schema version
+
+CREATE TABLE deletion_requests (
+    event_id BLOB NOT NULL CHECK(length(event_id) = 32), -- The requested event ID, not received yet
+    pubkey BLOB NOT NULL CHECK(length(pubkey) = 32),     -- The author of the deletion request (NIP-09)
+    PRIMARY KEY (event_id, pubkey)
+) WITHOUT ROWID;
@@ -0,0 +1,20 @@
+PRAGMA user_version = 4; -- Schema version
+
+CREATE TABLE event_expirations (
+    event_id BLOB PRIMARY KEY NOT NULL CHECK(length(event_id) = 32),
+    expiration INTEGER NOT NULL, -- The NIP-40 expiration timestamp
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+) WITHOUT ROWID;
+
+CREATE INDEX idx_event_expirations_expiration ON event_expirations(expiration);
+
+-- Index the expiration of the already stored events (the first valid expiration tag)
+INSERT OR IGNORE INTO event_expirations(event_id, expiration)
+SELECT e.id, CAST(json_extract(jt.value, '$[1]') AS INTEGER)
+FROM events e, json_each(e.tags) AS jt
+WHERE json_type(jt.value) = 'array'
+AND json_extract(jt.value, '$[0]') = 'expiration'
+AND json_type(jt.value, '$[1]') = 'text'
+AND json_extract(jt.value, '$[1]') <> ''
+AND json_extract(jt.value, '$[1]') NOT GLOB '*[^0-9]*'
+ORDER BY jt.key;
@@ -27,6 +27,8 @@ pub struct NostrSqliteBuilder {
     pub(crate) process_nip62: bool,
     /// Whether to process event deletion request (NIP-09) events
     pub(crate) process_nip09: bool,
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    pub(crate) process_nip40: bool,
     /// Relay URL for relay-specific request to vanish (NIP-62)
     pub(crate) relay_url: Option<RelayUrl>,
 }
@@ -88,6 +90,18 @@ impl NostrSqliteBuilder {
         self
     }
 
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    ///
+    /// Disable it to keep serving the expired events (i.e., for archival use).
+    /// The expired events can be physically removed with [`NostrDatabase::wipe_expired`](nostr_database::NostrDatabase::wipe_expired).
+    ///
+    /// Defaults to `true`
+    #[inline]
+    pub fn process_nip40(mut self, process_nip40: bool) -> Self {
+        self.process_nip40 = process_nip40;
+        self
+    }
+
     /// Set the relay URL to handle relay-specific request to vanish
     #[inline]
     pub fn relay_url(mut self, relay_url: RelayUrl) -> Self {
@@ -106,12 +120,13 @@ impl Default for NostrSqliteBuilder {
     /// Creates a new builder with default settings.
     ///
     /// This builder uses an in-memory database connection and enables
-    /// processing for NIP-62 and NIP-09 features by default.
+    /// processing for NIP-62, NIP-09 and NIP-40 features by default.
     fn default() -> Self {
         Self {
             db_type: Default::default(),
             process_nip62: true,
             process_nip09: true,
+            process_nip40: true,
             relay_url: None,
         }
     }
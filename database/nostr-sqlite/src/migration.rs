@@ -4,7 +4,7 @@ use rusqlite::Transaction;
 
 use crate::error::{Error, MigrationError};
 
-const DB_VERSION: i64 = 2;
+const DB_VERSION: i64 = 4;
 
 pub(super) fn run(tx: &Transaction<'_>) -> Result<(), Error> {
     // Get the current version
@@ -20,6 +20,14 @@ pub(super) fn run(tx: &Transaction<'_>) -> Result<(), Error> {
                 curr_version = mig_1_to_2(tx)?;
             }
 
+            if curr_version == 2 {
+                curr_version = mig_2_to_3(tx)?;
+            }
+
+            if curr_version == 3 {
+                curr_version = mig_3_to_4(tx)?;
+            }
+
             let _ = curr_version;
         }
         Ordering::Equal => {}
@@ -56,3 +64,15 @@ fn mig_1_to_2(tx: &Transaction<'_>) -> Result<i64, Error> {
     set_db_version(tx, 2)?;
     Ok(2)
 }
+
+fn mig_2_to_3(tx: &Transaction<'_>) -> Result<i64, Error> {
+    tx.execute_batch(include_str!("../migrations/003_deletion_requests.sql"))?;
+    set_db_version(tx, 3)?;
+    Ok(3)
+}
+
+fn mig_3_to_4(tx: &Transaction<'_>) -> Result<i64, Error> {
+    tx.execute_batch(include_str!("../migrations/004_event_expirations.sql"))?;
+    set_db_version(tx, 4)?;
+    Ok(4)
+}
@@ -31,6 +31,8 @@ pub(crate) struct NostrSqliteOptions {
     pub(crate) process_nip62: bool,
     /// Whether to process event deletion request (NIP-09) events
     pub(crate) process_nip09: bool,
+    /// Whether to exclude the expired (NIP-40) events from the queries
+    pub(crate) process_nip40: bool,
     /// Relay URL for relay-specific request to vanish (NIP-62).
     pub(crate) relay_url: Option<RelayUrl>,
 }
@@ -100,6 +102,7 @@ impl NostrSqlite {
     pub(crate) async fn from_builder(builder: NostrSqliteBuilder) -> Result<Self, Error> {
         let options = NostrSqliteOptions {
             process_nip09: builder.process_nip09,
+            process_nip40: builder.process_nip40,
             process_nip62: builder.process_nip62,
             relay_url: builder.relay_url,
         };
@@ -142,17 +145,21 @@ impl NostrSqlite {
 
     fn handle_deletion_event(tx: &Transaction<'_>, event: &Event) -> Result<bool, Error> {
         for id in event.tags.event_ids() {
-            if let Some(pubkey) = Self::get_pubkey_of_event_by_id(tx, &id)? {
-                // Author must match
-                if pubkey != event.pubkey {
-                    return Ok(true);
-                }
+            match Self::get_pubkey_of_event_by_id(tx, &id)? {
+                Some(pubkey) => {
+                    // Author must match
+                    if pubkey != event.pubkey {
+                        return Ok(true);
+                    }
 
-                // Mark the event ID as deleted (for NIP-09 deletion events)
-                Self::mark_event_as_deleted(tx, &id)?;
+                    // Mark the event ID as deleted (for NIP-09 deletion events)
+                    Self::mark_event_as_deleted(tx, &id)?;
 
-                // Remove event from store
-                Self::remove_event(tx, &id)?;
+                    // Remove event from store
+                    Self::remove_event(tx, &id)?;
+                }
+                // Not received yet: the author is checked when the event is received
+                None => Self::add_deletion_request(tx, &id, &event.pubkey)?,
             }
         }
 
@@ -197,6 +204,16 @@ impl NostrSqlite {
             return Ok(SaveEventStatus::Rejected(RejectedReason::Deleted));
         }
 
+        // Reject event if already expired
+        if options.process_nip40 && event.is_expired() {
+            return Ok(SaveEventStatus::Rejected(RejectedReason::Expired));
+        }
+
+        // Reject event if the author requested its deletion before
+        if Self::deletion_is_requested(&tx, &event.id, &event.pubkey)? {
+            return Ok(SaveEventStatus::Rejected(RejectedReason::Deleted));
+        }
+
         // Reject event if the public key was vanished
         if Self::pubkey_is_vanished(&tx, &event.pubkey)? {
             return Ok(SaveEventStatus::Rejected(RejectedReason::Vanished));
@@ -302,6 +319,14 @@ impl NostrSqlite {
                 )?;
             }
 
+            // Index the expiration
+            if let Some(expiration) = event.tags.expiration() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO event_expirations(event_id, expiration) VALUES (?1, ?2)",
+                    params![event.id.as_bytes().as_slice(), expiration.as_secs() as i64],
+                )?;
+            }
+
             // Commit transaction
             tx.commit()?;
 
@@ -327,7 +352,8 @@ impl NostrSqlite {
         deleted_at: Timestamp,
     ) -> Result<(), Error> {
         tx.execute(
-            "INSERT OR IGNORE INTO deleted_coordinates(pubkey, kind, identifier, deleted_at) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO deleted_coordinates(pubkey, kind, identifier, deleted_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(pubkey, kind, identifier) DO UPDATE SET deleted_at = MAX(deleted_at, excluded.deleted_at)",
             params![
                 coordinate.public_key.as_bytes().as_slice(),
                 coordinate.kind.as_u16() as i64,
@@ -338,6 +364,31 @@ impl NostrSqlite {
         Ok(())
     }
 
+    fn add_deletion_request(
+        tx: &Transaction<'_>,
+        id: &EventId,
+        author: &PublicKey,
+    ) -> Result<(), Error> {
+        tx.execute(
+            "INSERT OR IGNORE INTO deletion_requests(event_id, pubkey) VALUES (?1, ?2)",
+            params![id.as_bytes().as_slice(), author.as_bytes().as_slice()],
+        )?;
+        Ok(())
+    }
+
+    fn deletion_is_requested(
+        tx: &Transaction<'_>,
+        id: &EventId,
+        author: &PublicKey,
+    ) -> Result<bool, Error> {
+        let is_requested: i64 = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM deletion_requests WHERE event_id = ?1 AND pubkey = ?2)",
+            params![id.as_bytes().as_slice(), author.as_bytes().as_slice()],
+            |row| row.get(0),
+        )?;
+        Ok(is_requested != 0)
+    }
+
     fn event_is_deleted(tx: &Transaction<'_>, id: &EventId) -> Result<bool, Error> {
         let is_deleted: i64 = tx.query_row(
             "SELECT EXISTS(SELECT 1 FROM deleted_ids WHERE event_id = ?1)",
@@ -509,7 +560,7 @@ impl NostrDatabase for NostrSqlite {
     fn features(&self) -> Features {
         Features {
             persistent: true,
-            event_expiration: false,
+            event_expiration: true,
             full_text_search: true,
             request_to_vanish: true,
         }
@@ -568,12 +619,19 @@ impl NostrDatabase for NostrSqlite {
         Box::pin(async move {
             let filter = with_limit(filter, EVENTS_QUERY_LIMIT);
             self.pool
-                .interact(move |conn| {
-                    let query = build_filter(&filter, SqlSelectClause::Count);
+                .interact_options(move |conn, options| {
+                    let expired_at: Option<Timestamp> = options.process_nip40.then(Timestamp::now);
+                    let query = build_filter(&filter, SqlSelectClause::Count, expired_at);
                     let mut stmt = conn.prepare(&query.sql)?;
                     let count: i64 =
                         stmt.query_row(params_from_iter(query.params), |row| row.get(0))?;
-                    Ok(count as usize)
+                    let count: usize = count as usize;
+
+                    // The `LIMIT` clause doesn't apply to the counted rows
+                    Ok(match filter.limit {
+                        Some(limit) => count.min(limit),
+                        None => count,
+                    })
                 })
                 .await
                 .map_err(DatabaseError::backend)
@@ -584,9 +642,10 @@ impl NostrDatabase for NostrSqlite {
         Box::pin(async move {
             let filter = with_limit(filter, EVENTS_QUERY_LIMIT);
             self.pool
-                .interact(move |conn| {
+                .interact_options(move |conn, options| {
                     let mut events = Events::new(&filter);
-                    let query = build_filter(&filter, SqlSelectClause::Select);
+                    let expired_at: Option<Timestamp> = options.process_nip40.then(Timestamp::now);
+                    let query = build_filter(&filter, SqlSelectClause::Select, expired_at);
                     let mut stmt = conn.prepare(&query.sql)?;
                     let rows = stmt.query_map(params_from_iter(query.params), EventDb::from_row)?;
 
@@ -609,7 +668,7 @@ impl NostrDatabase for NostrSqlite {
         Box::pin(async move {
             self.pool
                 .interact(move |conn| {
-                    let query = build_filter(&filter, SqlSelectClause::Delete);
+                    let query = build_filter(&filter, SqlSelectClause::Delete, None);
                     conn.execute(&query.sql, params_from_iter(query.params))?;
                     Ok(())
                 })
@@ -626,6 +685,7 @@ impl NostrDatabase for NostrSqlite {
                     conn.execute("DELETE FROM events", [])?;
                     conn.execute("DELETE FROM deleted_ids", [])?;
                     conn.execute("DELETE FROM deleted_coordinates", [])?;
+                    conn.execute("DELETE FROM deletion_requests", [])?;
 
                     // Vacuum to reclaim space
                     conn.execute("VACUUM", [])?;
@@ -636,6 +696,23 @@ impl NostrDatabase for NostrSqlite {
                 .map_err(DatabaseError::backend)
         })
     }
+
+    fn wipe_expired(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            let now: Timestamp = Timestamp::now();
+            self.pool
+                .interact(move |conn| {
+                    // CASCADE will handle event_tags and event_expirations
+                    conn.execute(
+                        "DELETE FROM events WHERE id IN (SELECT event_id FROM event_expirations WHERE expiration <= ?1)",
+                        params![now.as_secs() as i64],
+                    )?;
+                    Ok(())
+                })
+                .await
+                .map_err(DatabaseError::backend)
+        })
+    }
 }
 
 fn has_event_been_replaced(stored: &EventDb, event: &Event) -> bool {
@@ -656,30 +733,39 @@ struct FilterQuery {
     params: Vec<Value>,
 }
 
-fn build_filter(filter: &Filter, select_clause: SqlSelectClause) -> FilterQuery {
+/// Build the query of the filter
+///
+/// If `expired_at` is set, the events expired at that time (NIP-40) are excluded.
+fn build_filter(
+    filter: &Filter,
+    select_clause: SqlSelectClause,
+    expired_at: Option<Timestamp>,
+) -> FilterQuery {
     // If no filters, simple query without JOIN
     if filter.is_empty() {
-        let mut sql = String::from(match select_clause {
-            SqlSelectClause::Select => "SELECT * FROM events",
-            SqlSelectClause::Count => "SELECT COUNT(*) FROM events",
-            SqlSelectClause::Delete => "DELETE FROM events",
-        });
+        let mut query = FilterQuery {
+            sql: String::from(match select_clause {
+                SqlSelectClause::Select => "SELECT * FROM events e",
+                SqlSelectClause::Count => "SELECT COUNT(*) FROM events e",
+                SqlSelectClause::Delete => "DELETE FROM events",
+            }),
+            params: Vec::new(),
+        };
 
         if let SqlSelectClause::Select | SqlSelectClause::Count = select_clause {
-            sql.push_str(" ORDER BY created_at DESC");
+            if let Some(expired_at) = expired_at {
+                query.sql.push_str(" WHERE 1=1");
+                add_expiration_condition(expired_at, &mut query);
+            }
+
+            query.sql.push_str(" ORDER BY e.created_at DESC");
             if let Some(limit) = filter.limit {
-                sql.push_str(" LIMIT ?");
-                return FilterQuery {
-                    sql,
-                    params: vec![Value::Integer(limit as i64)],
-                };
+                query.sql.push_str(" LIMIT ?");
+                query.params.push(Value::Integer(limit as i64));
             }
         }
 
-        return FilterQuery {
-            sql,
-            params: Vec::new(),
-        };
+        return query;
     }
 
     let mut sql = match select_clause {
@@ -726,6 +812,10 @@ fn build_filter(filter: &Filter, select_clause: SqlSelectClause) -> FilterQuery
     // Add all the filter conditions
     add_filter_conditions(filter, &mut query);
 
+    if let Some(expired_at) = expired_at {
+        add_expiration_condition(expired_at, &mut query);
+    }
+
     // Only add ORDER BY and LIMIT for SELECT queries
     query.sql.push_str(" ORDER BY e.created_at DESC");
 
@@ -836,6 +926,16 @@ fn add_filter_conditions(filter: &Filter, query: &mut FilterQuery) {
     }
 }
 
+/// Exclude the events expired at the given time (NIP-40)
+fn add_expiration_condition(expired_at: Timestamp, query: &mut FilterQuery) {
+    query.sql.push_str(
+        " AND NOT EXISTS (SELECT 1 FROM event_expirations ex WHERE ex.event_id = e.id AND ex.expiration <= ?)",
+    );
+    query
+        .params
+        .push(Value::Integer(expired_at.as_secs() as i64));
+}
+
 /// sets the given default limit on a Nostr filter if not set
 fn with_limit(filter: Filter, default_limit: usize) -> Filter {
     match filter.limit {
@@ -924,4 +1024,88 @@ mod tests {
         let events = db.query(Filter::new().search("")).await.unwrap();
         assert_eq!(events.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_query_skips_expired_events() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("Expiring")
+            .tag(Tag::expiration(Timestamp::now() + 1))
+            .sign(&keys)
+            .unwrap();
+
+        let database = NostrSqliteBuilder::default().build().await.unwrap();
+        let archive = NostrSqliteBuilder::default()
+            .process_nip40(false)
+            .build()
+            .await
+            .unwrap();
+
+        for db in [&database, &archive] {
+            let status = db.save_event(&event).await.unwrap();
+            assert_eq!(status, SaveEventStatus::Success);
+            assert_eq!(db.count(Filter::new()).await.unwrap(), 1);
+        }
+
+        // Wait for the expiration
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // Not served anymore, but still stored
+        assert_eq!(database.count(Filter::new()).await.unwrap(), 0);
+        assert!(
+            database
+                .query(Filter::new().author(keys.public_key))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(database.event_by_id(&event.id).await.unwrap().is_some());
+
+        // Served by the archive
+        assert_eq!(archive.count(Filter::new()).await.unwrap(), 1);
+        assert_eq!(
+            archive
+                .query(Filter::new().author(keys.public_key))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Physically removed
+        database.wipe_expired().await.unwrap();
+        assert!(database.event_by_id(&event.id).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migration_indexes_expirations() {
+        let keys = Keys::generate();
+        let expiring = EventBuilder::text_note("Expiring")
+            .tag(Tag::expiration(Timestamp::from_secs(1000)))
+            .sign(&keys)
+            .unwrap();
+        let not_expiring = EventBuilder::text_note("Not expiring").sign(&keys).unwrap();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.execute_batch(include_str!("../migrations/001_init.sql"))
+            .unwrap();
+        tx.execute_batch(include_str!("../migrations/002_vanished_public_keys.sql"))
+            .unwrap();
+        tx.execute_batch(include_str!("../migrations/003_deletion_requests.sql"))
+            .unwrap();
+        for event in [&expiring, &not_expiring] {
+            NostrSqlite::insert_event_tx(&tx, event).unwrap();
+        }
+
+        migration::run(&tx).unwrap();
+
+        let expirations: Vec<(Vec<u8>, i64)> = tx
+            .prepare("SELECT event_id, expiration FROM event_expirations")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(expirations, vec![(expiring.id.as_bytes().to_vec(), 1000)]);
+    }
 }
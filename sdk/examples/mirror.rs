@@ -0,0 +1,186 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Mirror the events of some source relays to a destination relay.
+//!
+//! - the local database is the progress cursor: on restart, the subscriptions resume from the newest stored event;
+//! - the events already on the destination are never re-sent (negentropy, or ID checks as fallback);
+//! - the publishing is paced by the destination rate limits.
+//!
+//! Set the `MIRROR_SECRET_KEY` env var to authenticate to the destination (NIP-42).
+
+use std::time::Duration;
+
+use nostr_lmdb::NostrLmdb;
+use nostr_sdk::prelude::*;
+
+const SOURCES: [&str; 3] = ["wss://relay.damus.io", "wss://nos.lol", "wss://nostr.wine"];
+const DESTINATION: &str = "ws://127.0.0.1:7777";
+const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+/// Max number of local events checked against the destination, when negentropy isn't supported
+const MAX_ID_CHECKS: usize = 500;
+
+fn filters() -> Vec<Filter> {
+    vec![
+        Filter::new().kinds([Kind::Metadata, Kind::RelayList]),
+        Filter::new().kind(Kind::LongFormTextNote),
+    ]
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let database = NostrLmdb::open("./db/mirror").await?;
+    let mut builder = Client::builder().database(database);
+
+    if let Ok(secret_key) = std::env::var("MIRROR_SECRET_KEY") {
+        let keys = Keys::parse(&secret_key)?;
+        builder = builder.authenticator(SignerAuthenticator::new(keys));
+    }
+
+    let client: Client = builder.build();
+
+    // Sources are read-only, the destination write-only:
+    // the events from the destination never get mirrored back.
+    for url in SOURCES.into_iter() {
+        client
+            .add_relay(url)
+            .capabilities(RelayCapabilities::READ)
+            .await?;
+    }
+    client
+        .add_relay(DESTINATION)
+        .capabilities(RelayCapabilities::WRITE)
+        .await?;
+
+    client.connect().and_wait(Duration::from_secs(10)).await;
+
+    let destination = RelayUrl::parse(DESTINATION)?;
+
+    // Catch up: send the stored events that the destination is missing (i.e., after a crash)
+    for filter in filters().into_iter() {
+        catch_up(&client, &destination, filter).await?;
+    }
+
+    // Resume the subscriptions from the newest stored event.
+    // `since` is inclusive: the events of that second are received again, and deduplicated by the database.
+    let mut targets = Vec::new();
+    for filter in filters().into_iter() {
+        let filter = match newest_stored(&client, filter.clone()).await? {
+            Some(created_at) => filter.since(created_at),
+            None => filter.since(Timestamp::now()),
+        };
+
+        for url in SOURCES.into_iter() {
+            targets.push((url, filter.clone()));
+        }
+    }
+    client.subscribe(ReqTarget::manual(targets)).await?;
+
+    let mut notifications = client.notifications();
+    let mut status = tokio::time::interval(STATUS_INTERVAL);
+    let mut mirrored: usize = 0;
+
+    loop {
+        tokio::select! {
+            notification = notifications.next() => match notification {
+                // Sent only the first time an event is seen (and saved)
+                Some(ClientNotification::Event { event, .. }) => {
+                    if mirror(&client, &destination, &event).await {
+                        mirrored += 1;
+                    }
+                }
+                Some(ClientNotification::Shutdown) | None => break,
+                Some(..) => {}
+            },
+            _ = status.tick() => log_status(&client, mirrored).await,
+        }
+    }
+
+    Ok(())
+}
+
+async fn newest_stored(client: &Client, filter: Filter) -> Result<Option<Timestamp>> {
+    let events = client.database().query(filter.limit(1)).await?;
+    Ok(events.first().map(|e| e.created_at))
+}
+
+async fn catch_up(client: &Client, destination: &RelayUrl, filter: Filter) -> Result<()> {
+    let opts = SyncOptions::new().direction(SyncDirection::Up);
+    let output = client
+        .sync(filter.clone())
+        .with([destination])
+        .opts(opts)
+        .await?;
+
+    if output.success.contains(destination) {
+        println!("Catch-up: sent {} events", output.sent.len());
+        return Ok(());
+    }
+
+    // Negentropy not supported: check the IDs of the newest stored events
+    let events = client.database().query(filter.limit(MAX_ID_CHECKS)).await?;
+    let available = client
+        .fetch_events(ReqTarget::single(
+            destination,
+            [Filter::new().ids(events.iter().map(|e| e.id))],
+        ))
+        .timeout(Duration::from_secs(30))
+        .await?;
+
+    for event in events.iter() {
+        if !available.contains(event) {
+            mirror(client, destination, event).await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn mirror(client: &Client, destination: &RelayUrl, event: &Event) -> bool {
+    loop {
+        match client.send_event(event).to([destination]).await {
+            Ok(output) if output.success.contains(destination) => return true,
+            Ok(output) => {
+                let relay = match client.relay(destination).await {
+                    Ok(Some(relay)) => relay,
+                    _ => return false,
+                };
+
+                // Wait for the rate limit to expire, then retry
+                match relay.stats().rate_limited_until(RateLimitClass::Publish) {
+                    Some(until) => {
+                        let wait = until.as_secs().saturating_sub(Timestamp::now().as_secs());
+                        tokio::time::sleep(Duration::from_secs(wait)).await;
+                    }
+                    None => {
+                        eprintln!("Can't mirror {}: {:?}", event.id, output.failed);
+                        return false;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Can't mirror {}: {e}", event.id);
+                return false;
+            }
+        }
+    }
+}
+
+async fn log_status(client: &Client, mirrored: usize) {
+    println!("Mirrored events: {mirrored}");
+
+    for (url, relay) in client.relays().await.into_iter() {
+        let stats = relay.stats();
+        println!(
+            "{url}: status={}, received={} bytes, sent={} bytes, latency={:?}, publish rate-limited until {:?}",
+            relay.status(),
+            stats.bytes_received(),
+            stats.bytes_sent(),
+            stats.latency(),
+            stats.rate_limited_until(RateLimitClass::Publish),
+        );
+    }
+}
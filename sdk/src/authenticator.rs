@@ -4,7 +4,7 @@ use std::any::Any;
 use std::fmt::Debug;
 
 use nostr::signer::{AsyncGetPublicKey, AsyncSignEvent};
-use nostr::{Event, EventBuilder, RelayUrl};
+use nostr::{Event, EventBuilder, RelayUrl, Timestamp};
 
 use crate::future::BoxedFuture;
 
@@ -21,6 +21,22 @@ pub trait Authenticator: Any + Debug + Send + Sync {
         relay_url: &'a RelayUrl,
         challenge: &'a str,
     ) -> BoxedFuture<'a, Result<Event, AuthenticationError>>;
+
+    /// Makes a NIP-42 event for authentication, with a specific `created_at`
+    ///
+    /// Used when the local clock is found skewed compared to the relay one
+    /// (see [`RelayOptions::auth_relay_clock`](crate::relay::RelayOptions::auth_relay_clock)).
+    ///
+    /// By default, the `created_at` is ignored and [`Authenticator::make_auth_event`] is called.
+    fn make_auth_event_at<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+        challenge: &'a str,
+        created_at: Timestamp,
+    ) -> BoxedFuture<'a, Result<Event, AuthenticationError>> {
+        let _ = created_at;
+        self.make_auth_event(relay_url, challenge)
+    }
 }
 
 /// An authenticator that uses a signer that implements [`AsyncGetPublicKey`] and [`AsyncSignEvent`] for creating NIP-42 events.
@@ -69,4 +85,18 @@ where
                 .await?)
         })
     }
+
+    fn make_auth_event_at<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+        challenge: &'a str,
+        created_at: Timestamp,
+    ) -> BoxedFuture<'a, Result<Event, AuthenticationError>> {
+        Box::pin(async move {
+            Ok(EventBuilder::auth(challenge, relay_url.clone())
+                .custom_created_at(created_at)
+                .sign_async(&self.signer)
+                .await?)
+        })
+    }
 }
@@ -40,6 +40,24 @@ impl<'client, 'url> AddRelay<'client, 'url> {
         self
     }
 
+    /// Enable or disable the [`RelayCapabilities::READ`] capability
+    ///
+    /// See [`RelayBuilder::read`](crate::relay::RelayBuilder::read) for details.
+    #[inline]
+    pub fn read(mut self, enable: bool) -> Self {
+        self.capabilities.set(RelayCapabilities::READ, enable);
+        self
+    }
+
+    /// Enable or disable the [`RelayCapabilities::WRITE`] capability
+    ///
+    /// See [`RelayBuilder::write`](crate::relay::RelayBuilder::write) for details.
+    #[inline]
+    pub fn write(mut self, enable: bool) -> Self {
+        self.capabilities.set(RelayCapabilities::WRITE, enable);
+        self
+    }
+
     /// Connection timeout (default: 15 sec)
     ///
     /// This is the default timeout use when attempting to establish a connection with the relay
@@ -256,6 +274,23 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_add_write_only_relay() {
+        let client = Client::default();
+
+        let res = client
+            .add_relay("wss://relay.damus.io")
+            .read(false)
+            .await
+            .unwrap();
+        assert!(res);
+
+        let relay = client.relay("wss://relay.damus.io").await.unwrap().unwrap();
+        assert_eq!(relay.capabilities().load(), RelayCapabilities::WRITE);
+        assert!(!relay.capabilities().can_read());
+        assert!(relay.capabilities().can_write());
+    }
+
     #[tokio::test]
     async fn test_add_relay_rejected_by_policy() {
         let rejected = RelayUrl::parse("wss://relay.damus.io").unwrap();
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use nostr::RelayUrl;
+
+/// Summary of the relay list applied with [`Client::apply_relay_list`](crate::client::Client::apply_relay_list)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayListSummary {
+    /// Relays added and connected
+    pub added: HashSet<RelayUrl>,
+    /// Relays removed and disconnected
+    ///
+    /// Relays with [`RelayCapabilities::GOSSIP`](crate::relay::RelayCapabilities::GOSSIP) are kept in the pool,
+    /// without the [`RelayCapabilities::READ`](crate::relay::RelayCapabilities::READ) and
+    /// [`RelayCapabilities::WRITE`](crate::relay::RelayCapabilities::WRITE) capabilities.
+    pub removed: HashSet<RelayUrl>,
+    /// Relays with updated read/write capabilities
+    pub updated: HashSet<RelayUrl>,
+    /// Relays rejected by the admission policy
+    pub rejected: HashSet<RelayUrl>,
+}
+
+impl RelayListSummary {
+    /// Check if nothing changed
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nostr::nips::nip65::{RelayList, RelayMetadata};
+    use nostr_relay_builder::MockRelay;
+
+    use super::*;
+    use crate::client::Client;
+    use crate::relay::{Relay, RelayCapabilities, RelayStatus};
+
+    #[tokio::test]
+    async fn test_apply_relay_list() {
+        let unchanged = MockRelay::run().await.unwrap();
+        let unchanged_url = unchanged.url().await;
+        let changed = MockRelay::run().await.unwrap();
+        let changed_url = changed.url().await;
+        let removed = MockRelay::run().await.unwrap();
+        let removed_url = removed.url().await;
+        let added = MockRelay::run().await.unwrap();
+        let added_url = added.url().await;
+
+        let client = Client::default();
+        client.add_relay(&unchanged_url).await.unwrap();
+        client.add_relay(&changed_url).await.unwrap();
+        client.add_relay(&removed_url).await.unwrap();
+        client.connect().and_wait(Duration::from_secs(2)).await;
+
+        let unchanged_relay: Relay = client.relay(&unchanged_url).await.unwrap().unwrap();
+        let changed_relay: Relay = client.relay(&changed_url).await.unwrap().unwrap();
+        let removed_relay: Relay = client.relay(&removed_url).await.unwrap().unwrap();
+        assert_eq!(unchanged_relay.status(), RelayStatus::Connected);
+
+        let list: RelayList = [
+            (unchanged_url.clone(), None),
+            (changed_url.clone(), Some(RelayMetadata::Read)),
+            (added_url.clone(), Some(RelayMetadata::Write)),
+        ]
+        .into_iter()
+        .collect();
+
+        let summary = client.apply_relay_list(list.clone()).await.unwrap();
+        assert_eq!(summary.added, HashSet::from([added_url.clone()]));
+        assert_eq!(summary.removed, HashSet::from([removed_url.clone()]));
+        assert_eq!(summary.updated, HashSet::from([changed_url.clone()]));
+        assert!(summary.rejected.is_empty());
+
+        // The unchanged and updated relays must not be reconnected
+        assert_eq!(unchanged_relay.status(), RelayStatus::Connected);
+        assert_eq!(unchanged_relay.stats().attempts(), 1);
+        assert_eq!(changed_relay.status(), RelayStatus::Connected);
+        assert_eq!(changed_relay.stats().attempts(), 1);
+        assert_eq!(changed_relay.capabilities().load(), RelayCapabilities::READ);
+
+        // The removed relay is disconnected
+        assert!(client.relay(&removed_url).await.unwrap().is_none());
+        assert!(removed_relay.status().is_disconnected());
+
+        // The added relay is connected
+        let added_relay: Relay = client.relay(&added_url).await.unwrap().unwrap();
+        assert_eq!(added_relay.capabilities().load(), RelayCapabilities::WRITE);
+        added_relay
+            .try_connect()
+            .timeout(Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        // Apply the same list again: nothing changes
+        let summary = client.apply_relay_list(list).await.unwrap();
+        assert!(summary.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_relay_list_keeps_gossip_relays() {
+        let client = Client::default();
+
+        client
+            .add_relay("ws://127.0.0.1:6666")
+            .capabilities(RelayCapabilities::default() | RelayCapabilities::GOSSIP)
+            .await
+            .unwrap();
+        client
+            .add_relay("ws://127.0.0.1:7777")
+            .capabilities(RelayCapabilities::GOSSIP)
+            .await
+            .unwrap();
+
+        let url = RelayUrl::parse("ws://127.0.0.1:7777").unwrap();
+        let list: RelayList = [(url.clone(), None)].into_iter().collect();
+
+        let summary = client.apply_relay_list(list).await.unwrap();
+        assert_eq!(summary.added, HashSet::from([url.clone()]));
+        assert_eq!(summary.removed.len(), 1);
+
+        // The gossip relay that is not in the list is kept, without the READ and WRITE capabilities
+        let relay: Relay = client.relay("ws://127.0.0.1:6666").await.unwrap().unwrap();
+        assert_eq!(relay.capabilities().load(), RelayCapabilities::GOSSIP);
+
+        // The gossip relay in the list gets the READ and WRITE capabilities
+        let relay: Relay = client.relay(&url).await.unwrap().unwrap();
+        assert_eq!(
+            relay.capabilities().load(),
+            RelayCapabilities::default() | RelayCapabilities::GOSSIP
+        );
+    }
+}
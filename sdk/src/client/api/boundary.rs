@@ -0,0 +1,73 @@
+//! Time boundaries of the reads
+//!
+//! The `since` and `until` fields of a [`Filter`] are both inclusive (NIP-01),
+//! so two reads sharing a boundary second would both include the events created in that second.
+//!
+//! To split a timeline in consecutive reads, without overlaps or gaps, the SDK uses half-open intervals:
+//! a read bounded by a cutoff includes the events with `created_at < cutoff`,
+//! and the next read starts with `since = cutoff`.
+//! An event created exactly at the cutoff second belongs to the **next** read.
+
+use nostr::{Filter, Timestamp};
+
+/// Bound the filter to the events created **before** the cutoff.
+///
+/// If the filter already has a stricter `until`, it's kept.
+pub(super) fn bound_before(mut filter: Filter, cutoff: Timestamp) -> Filter {
+    // `until` is inclusive
+    let until: Timestamp = cutoff - 1;
+
+    filter.until = Some(match filter.until {
+        Some(current) => current.min(until),
+        None => until,
+    });
+
+    filter
+}
+
+/// Check if an event, created at `created_at`, is **before** the cutoff.
+#[inline]
+pub(super) fn is_before(created_at: Timestamp, cutoff: Timestamp) -> bool {
+    created_at < cutoff
+}
+
+/// Resume the filter from the cutoff, including the events created **at or after** it.
+#[inline]
+pub(super) fn resume_from(filter: Filter, cutoff: Timestamp) -> Filter {
+    filter.since(cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::Kind;
+
+    use super::*;
+
+    #[test]
+    fn test_event_at_cutoff_belongs_to_next_read() {
+        let cutoff = Timestamp::from_secs(1_700_000_000);
+        let filter = Filter::new().kind(Kind::ZapReceipt);
+
+        // The bounded read excludes the cutoff second
+        let bounded: Filter = bound_before(filter.clone(), cutoff);
+        assert_eq!(bounded.until, Some(cutoff - 1));
+        assert!(is_before(cutoff - 1, cutoff));
+        assert!(!is_before(cutoff, cutoff));
+        assert!(!is_before(cutoff + 1, cutoff));
+
+        // The next read includes it
+        let resumed: Filter = resume_from(filter, cutoff);
+        assert_eq!(resumed.since, Some(cutoff));
+    }
+
+    #[test]
+    fn test_bound_before_keeps_stricter_until() {
+        let cutoff = Timestamp::from_secs(1_700_000_000);
+
+        let filter = Filter::new().until(cutoff - 100);
+        assert_eq!(bound_before(filter, cutoff).until, Some(cutoff - 100));
+
+        let filter = Filter::new().until(cutoff + 100);
+        assert_eq!(bound_before(filter, cutoff).until, Some(cutoff - 1));
+    }
+}
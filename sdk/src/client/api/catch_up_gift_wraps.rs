@@ -0,0 +1,536 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::future::IntoFuture;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use futures::{StreamExt, future, stream};
+use nostr::nips::nip44::AsyncNip44;
+use nostr::nips::nip59::{self, RANGE_RANDOM_TIMESTAMP_TWEAK, UnwrappedGift};
+use nostr::signer::AsyncGetPublicKey;
+use nostr::{Event, EventId, Filter, Kind, PublicKey, RelayUrl, Timestamp};
+use nostr_database::{BoxedStream, Events, NostrDatabase};
+
+use crate::client::{Client, Error};
+use crate::future::BoxedFuture;
+use crate::relay::{Relay, RelayCapabilities};
+
+const DEFAULT_PAGE_SIZE: NonZeroUsize = NonZeroUsize::new(500).unwrap();
+const DEFAULT_CONCURRENCY: NonZeroUsize = NonZeroUsize::new(8).unwrap();
+const DEFAULT_COUNT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Gift wrap catch-up notification
+#[derive(Debug)]
+pub enum GiftWrapCatchUpNotification {
+    /// A gift wrap has been unwrapped and stored into the database
+    Unwrapped {
+        /// The gift wrap
+        gift_wrap: Box<Event>,
+        /// The unwrapped gift
+        unwrapped: Box<UnwrappedGift>,
+    },
+    /// A gift wrap can't be unwrapped
+    ///
+    /// The gift wrap is discarded, without stopping the catch-up.
+    Failed {
+        /// Gift wrap ID
+        gift_wrap_id: EventId,
+        /// Error
+        error: nip59::Error,
+    },
+    /// A page of gift wraps has been processed
+    Progress {
+        /// Number of gift wraps processed so far, including the ones already in the database
+        processed: usize,
+        /// Estimated number of gift wraps to process (NIP-45), if any relay supports it
+        total: Option<usize>,
+        /// The history of all the conversations is complete from this timestamp on
+        complete_since: Timestamp,
+    },
+    /// The history of a conversation is complete from a timestamp on
+    ///
+    /// Sent after each page for the conversations that had messages in it.
+    ConversationComplete {
+        /// The participants of the conversation (the sender and the receivers of the rumors)
+        participants: BTreeSet<PublicKey>,
+        /// The history of the conversation is complete from this timestamp on
+        since: Timestamp,
+    },
+    /// The catch-up is completed
+    ///
+    /// The cursor must be persisted by the caller and passed to [`CatchUpGiftWraps::since`] at the next catch-up.
+    /// It's sent only after all the gift wraps have been processed:
+    /// if the catch-up is interrupted, the next one restarts from the previous cursor.
+    ///
+    /// As the gift wraps have a random timestamp up to 2 days in the past,
+    /// the next catch-up processes again the ones of the 2 days before the cursor:
+    /// skip the IDs already handled.
+    Finished {
+        /// Cursor
+        cursor: Timestamp,
+    },
+}
+
+/// Catch up the gift wraps received while offline
+#[must_use = "Does nothing unless you await!"]
+pub struct CatchUpGiftWraps<'client, 'signer, T> {
+    // --------------------------------------------------
+    // WHEN ADDING NEW OPTIONS HERE,
+    // REMEMBER TO UPDATE THE "Configuration" SECTION in
+    // Client::catch_up_gift_wraps DOC.
+    // --------------------------------------------------
+    client: &'client Client,
+    signer: &'signer T,
+    since: Option<Timestamp>,
+    page_size: NonZeroUsize,
+    concurrency: NonZeroUsize,
+    timeout: Option<Duration>,
+}
+
+impl<'client, 'signer, T> CatchUpGiftWraps<'client, 'signer, T>
+where
+    T: AsyncGetPublicKey + AsyncNip44,
+{
+    #[inline]
+    pub(crate) fn new(client: &'client Client, signer: &'signer T) -> Self {
+        Self {
+            client,
+            signer,
+            since: None,
+            page_size: DEFAULT_PAGE_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+            timeout: None,
+        }
+    }
+
+    /// Catch up from the cursor of a previous catch-up
+    ///
+    /// By default, all the gift wraps are fetched.
+    #[inline]
+    pub fn since(mut self, cursor: Timestamp) -> Self {
+        self.since = Some(cursor);
+        self
+    }
+
+    /// Max number of gift wraps fetched and kept in memory at a time (default: 500)
+    #[inline]
+    pub fn page_size(mut self, page_size: NonZeroUsize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Max number of gift wraps unwrapped concurrently (default: 8)
+    #[inline]
+    pub fn concurrency(mut self, concurrency: NonZeroUsize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Set a timeout for fetching each page
+    ///
+    /// By default, no timeout is configured.
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+struct CatchUp<'client, 'signer, T> {
+    client: &'client Client,
+    signer: &'signer T,
+    /// The filter of all the gift wraps to catch up
+    filter: Filter,
+    cursor: Timestamp,
+    page_size: NonZeroUsize,
+    concurrency: NonZeroUsize,
+    timeout: Option<Duration>,
+    /// Upper bound of the next page
+    until: Timestamp,
+    /// The gift wraps of the previous page older than `until`, returned again by the next page
+    seen: HashSet<EventId>,
+    processed: usize,
+    total: Option<usize>,
+    pending: VecDeque<Result<GiftWrapCatchUpNotification, Error>>,
+    done: bool,
+}
+
+impl<T> CatchUp<'_, '_, T>
+where
+    T: AsyncNip44,
+{
+    /// Fetch and process the next page, queueing its notifications
+    async fn next_page(&mut self) {
+        match self.process_page().await {
+            Ok(true) => {}
+            Ok(false) => {
+                self.pending
+                    .push_back(Ok(GiftWrapCatchUpNotification::Finished {
+                        cursor: self.cursor,
+                    }));
+                self.done = true;
+            }
+            Err(e) => {
+                self.pending.push_back(Err(e));
+                self.done = true;
+            }
+        }
+    }
+
+    /// Returns `false` when there are no more gift wraps
+    async fn process_page(&mut self) -> Result<bool, Error> {
+        let filter: Filter = self
+            .filter
+            .clone()
+            .until(self.until)
+            .limit(self.page_size.get());
+
+        let mut stream = self.client.stream_events(filter);
+
+        if let Some(timeout) = self.timeout {
+            stream = stream.timeout(timeout);
+        }
+
+        let mut stream = stream.await?;
+
+        // Deduplicate across the relays, before paying the decryption.
+        // Keep all the gift wraps received, not only the newest of the page:
+        // the relays already stored them into the database, so they must be processed now.
+        let mut events: Events = Events::default();
+        let mut per_relay: HashMap<RelayUrl, (usize, Timestamp)> = HashMap::new();
+
+        while let Some((url, result)) = stream.next().await {
+            let event: Event = match result {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!(url = %url, error = %e, "Failed to handle streamed event");
+                    continue;
+                }
+            };
+
+            let (count, oldest) = per_relay.entry(url).or_insert((0, event.created_at));
+            *count += 1;
+            *oldest = (*oldest).min(event.created_at);
+
+            events.insert(event);
+        }
+
+        // The relays with a full page may have older gift wraps:
+        // the next page starts from the newest of their oldest ones, so none of them is skipped.
+        let next_until: Option<Timestamp> = per_relay
+            .into_values()
+            .filter(|(count, ..)| *count >= self.page_size.get())
+            .map(|(.., oldest)| oldest)
+            .max();
+
+        // Skip the gift wraps already processed by the previous page
+        let (seen, page): (Vec<Event>, Vec<Event>) = events
+            .into_iter()
+            .partition(|event| self.seen.contains(&event.id));
+
+        let oldest: Timestamp = match (next_until, page.last()) {
+            // A full page of gift wraps with the same timestamp: can't go further
+            (Some(next_until), _) if next_until == self.until && page.is_empty() => {
+                return Ok(false);
+            }
+            (Some(next_until), _) => next_until,
+            (None, Some(event)) => event.created_at,
+            (None, None) => return Ok(false),
+        };
+
+        // The gift wraps that the next page returns again
+        self.seen = seen
+            .iter()
+            .chain(page.iter())
+            .filter(|event| event.created_at <= oldest)
+            .map(|event| event.id)
+            .collect();
+        self.until = oldest;
+        self.processed += page.len();
+
+        // Unwrap concurrently, keeping the order
+        let database: &dyn NostrDatabase = self.client.database().as_ref();
+        let signer: &T = self.signer;
+        let mut unwrapped = stream::iter(page)
+            .map(|gift_wrap| async move {
+                let res = UnwrappedGift::from_gift_wrap_async(signer, &gift_wrap).await;
+                (gift_wrap, res)
+            })
+            .buffered(self.concurrency.get());
+
+        // Any message older than the oldest gift wrap of the page may have been sent up to the max timestamp tweak later
+        let complete_since: Timestamp = oldest + RANGE_RANDOM_TIMESTAMP_TWEAK.end;
+        let mut conversations: BTreeSet<BTreeSet<PublicKey>> = BTreeSet::new();
+
+        while let Some((gift_wrap, res)) = unwrapped.next().await {
+            match res {
+                Ok(unwrapped) => {
+                    // Store before notifying, so a gift wrap is never lost if the catch-up is interrupted
+                    database.save_event(&gift_wrap).await?;
+
+                    let mut participants: BTreeSet<PublicKey> =
+                        unwrapped.rumor.tags.public_keys().collect();
+                    participants.insert(unwrapped.rumor.pubkey);
+                    conversations.insert(participants);
+
+                    self.pending
+                        .push_back(Ok(GiftWrapCatchUpNotification::Unwrapped {
+                            gift_wrap: Box::new(gift_wrap),
+                            unwrapped: Box::new(unwrapped),
+                        }));
+                }
+                Err(error) => {
+                    tracing::debug!(id = %gift_wrap.id, error = %error, "Failed to unwrap gift wrap");
+
+                    self.pending
+                        .push_back(Ok(GiftWrapCatchUpNotification::Failed {
+                            gift_wrap_id: gift_wrap.id,
+                            error,
+                        }));
+                }
+            }
+        }
+
+        self.pending
+            .push_back(Ok(GiftWrapCatchUpNotification::Progress {
+                processed: self.processed,
+                total: self.total,
+                complete_since,
+            }));
+
+        for participants in conversations.into_iter() {
+            self.pending
+                .push_back(Ok(GiftWrapCatchUpNotification::ConversationComplete {
+                    participants,
+                    since: complete_since,
+                }));
+        }
+
+        // No relay had a full page: no more gift wraps
+        Ok(next_until.is_some())
+    }
+}
+
+/// Estimate the number of gift wraps with NIP-45, using the highest count of the relays
+async fn estimate_total(
+    relays: &HashMap<RelayUrl, Relay>,
+    filter: &Filter,
+    timeout: Duration,
+) -> Option<usize> {
+    let counts = future::join_all(
+        relays
+            .values()
+            .map(|relay| relay.count_events(filter.clone(), timeout)),
+    )
+    .await;
+
+    // Not all the relays support NIP-45: ignore the errors
+    counts.into_iter().filter_map(Result::ok).max()
+}
+
+impl<'client, 'signer, T> IntoFuture for CatchUpGiftWraps<'client, 'signer, T>
+where
+    'signer: 'client,
+    T: AsyncGetPublicKey + AsyncNip44,
+{
+    type Output = Result<BoxedStream<'client, Result<GiftWrapCatchUpNotification, Error>>, Error>;
+    type IntoFuture = BoxedFuture<'client, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let public_key: PublicKey = self.signer.get_public_key().await?;
+
+            // The cursor of the next catch-up
+            let cursor: Timestamp = Timestamp::now();
+
+            // The gift wraps have a random timestamp in the past:
+            // the ones received after the previous cursor may be older than it.
+            let mut filter: Filter = Filter::new()
+                .kind(Kind::GiftWrap)
+                .pubkey(public_key)
+                .until(cursor);
+
+            if let Some(since) = self.since {
+                filter = filter.since(since - RANGE_RANDOM_TIMESTAMP_TWEAK.end);
+            }
+
+            let relays: HashMap<RelayUrl, Relay> = self
+                .client
+                .pool()
+                .relays_with_any_cap(RelayCapabilities::READ)
+                .await;
+
+            let total: Option<usize> = estimate_total(
+                &relays,
+                &filter,
+                self.timeout.unwrap_or(DEFAULT_COUNT_TIMEOUT),
+            )
+            .await;
+
+            let state = CatchUp {
+                client: self.client,
+                signer: self.signer,
+                filter,
+                cursor,
+                page_size: self.page_size,
+                concurrency: self.concurrency,
+                timeout: self.timeout,
+                until: cursor,
+                seen: HashSet::new(),
+                processed: 0,
+                total,
+                pending: VecDeque::new(),
+                done: false,
+            };
+
+            let stream = stream::unfold(state, |mut state| async move {
+                loop {
+                    if let Some(notification) = state.pending.pop_front() {
+                        return Some((notification, state));
+                    }
+
+                    if state.done {
+                        return None;
+                    }
+
+                    state.next_page().await;
+                }
+            });
+
+            Ok(Box::pin(stream) as BoxedStream<'client, _>)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::{EventBuilder, Keys, Tag};
+    use nostr_memory::MemoryDatabase;
+    use nostr_relay_builder::MockRelay;
+
+    use super::*;
+
+    const WRAPS: usize = 2000;
+
+    #[tokio::test]
+    async fn test_catch_up_gift_wraps() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let receiver = Keys::generate();
+        let senders = [Keys::generate(), Keys::generate(), Keys::generate()];
+
+        let mut ids: HashSet<EventId> = HashSet::with_capacity(WRAPS + 1);
+
+        for i in 0..WRAPS {
+            let sender: &Keys = &senders[i % senders.len()];
+            let rumor = EventBuilder::private_msg_rumor(receiver.public_key, format!("msg {i}"))
+                .build(sender.public_key);
+            let gift_wrap: Event =
+                EventBuilder::gift_wrap_async(sender, &receiver.public_key, rumor, [])
+                    .await
+                    .unwrap();
+            ids.insert(gift_wrap.id);
+            mock.add_event(gift_wrap).await.unwrap();
+        }
+
+        // A gift wrap that can't be unwrapped
+        let invalid: Event = EventBuilder::new(Kind::GiftWrap, "invalid")
+            .tag(Tag::public_key(receiver.public_key))
+            .sign(&Keys::generate())
+            .unwrap();
+        mock.add_event(invalid.clone()).await.unwrap();
+
+        let client = Client::builder()
+            .database(MemoryDatabase::unbounded())
+            .build();
+        client.add_relay(&url).and_connect().await.unwrap();
+
+        let page_size: NonZeroUsize = NonZeroUsize::new(300).unwrap();
+
+        let mut stream = client
+            .catch_up_gift_wraps(&receiver)
+            .page_size(page_size)
+            .await
+            .unwrap();
+
+        let mut unwrapped: HashSet<EventId> = HashSet::with_capacity(WRAPS);
+        let mut failed: Vec<EventId> = Vec::new();
+        let mut last_created_at: Option<Timestamp> = None;
+        let mut last_processed: usize = 0;
+        let mut pages: usize = 0;
+        let mut conversations: HashSet<BTreeSet<PublicKey>> = HashSet::new();
+        let mut cursor: Option<Timestamp> = None;
+
+        while let Some(notification) = stream.next().await {
+            match notification.unwrap() {
+                GiftWrapCatchUpNotification::Unwrapped { gift_wrap, .. } => {
+                    // From the newest
+                    if let Some(last) = last_created_at {
+                        assert!(gift_wrap.created_at <= last);
+                    }
+                    last_created_at = Some(gift_wrap.created_at);
+
+                    // No duplicates
+                    assert!(unwrapped.insert(gift_wrap.id));
+                }
+                GiftWrapCatchUpNotification::Failed { gift_wrap_id, .. } => {
+                    failed.push(gift_wrap_id);
+                }
+                GiftWrapCatchUpNotification::Progress {
+                    processed, total, ..
+                } => {
+                    // Never more than a page at a time
+                    assert!(processed - last_processed <= page_size.get());
+                    assert_eq!(total, Some(WRAPS + 1));
+                    last_processed = processed;
+                    pages += 1;
+                }
+                GiftWrapCatchUpNotification::ConversationComplete { participants, .. } => {
+                    conversations.insert(participants);
+                }
+                GiftWrapCatchUpNotification::Finished { cursor: c } => {
+                    cursor = Some(c);
+                }
+            }
+        }
+
+        // Complete
+        assert_eq!(unwrapped, ids);
+        assert_eq!(failed, vec![invalid.id]);
+        assert_eq!(last_processed, WRAPS + 1);
+        assert!(pages >= WRAPS.div_ceil(page_size.get()));
+        assert_eq!(conversations.len(), senders.len());
+        let cursor: Timestamp = cursor.unwrap();
+
+        // Stored
+        for id in ids.iter() {
+            assert!(client.database().contains(id).await.unwrap());
+        }
+
+        // Catch up again: the gift wraps of the timestamp tweak window are processed again, only once
+        let mut unwrapped_again: HashSet<EventId> = HashSet::with_capacity(WRAPS);
+        let mut stream = client
+            .catch_up_gift_wraps(&receiver)
+            .since(cursor)
+            .page_size(page_size)
+            .await
+            .unwrap();
+        let mut finished: bool = false;
+
+        while let Some(notification) = stream.next().await {
+            match notification.unwrap() {
+                GiftWrapCatchUpNotification::Unwrapped { gift_wrap, .. } => {
+                    assert!(unwrapped_again.insert(gift_wrap.id));
+                }
+                GiftWrapCatchUpNotification::Finished { cursor: c } => {
+                    assert!(c >= cursor);
+                    finished = true;
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(unwrapped_again, ids);
+        assert!(finished);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use futures::StreamExt;
+use nostr::{EventId, Filter};
+use nostr_database::{DatabaseEventStatus, Events, NostrDatabase};
+
+use crate::client::{Client, Error};
+use crate::future::BoxedFuture;
+
+/// Fetch and store output
+#[derive(Debug, Clone, Default)]
+pub struct FetchAndStoreOutput {
+    /// Events from the relays, merged with the ones already in the database
+    pub events: Events,
+    /// Events that failed to be saved into the database, with related errors
+    pub failed: HashMap<EventId, String>,
+}
+
+/// Fetch events and store them into the database
+#[must_use = "Does nothing unless you await!"]
+pub struct FetchEventsAndStore<'client> {
+    // --------------------------------------------------
+    // WHEN ADDING NEW OPTIONS HERE,
+    // REMEMBER TO UPDATE THE "Configuration" SECTION in
+    // Client::fetch_events_and_store DOC.
+    // --------------------------------------------------
+    client: &'client Client,
+    filter: Filter,
+    timeout: Option<Duration>,
+    include_database: bool,
+}
+
+impl<'client> FetchEventsAndStore<'client> {
+    pub(crate) fn new(client: &'client Client, filter: Filter) -> Self {
+        Self {
+            client,
+            filter,
+            timeout: None,
+            include_database: true,
+        }
+    }
+
+    /// Set a timeout
+    ///
+    /// By default, no timeout is configured.
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Include the events that are only in the database (default: true)
+    ///
+    /// If disabled, only the events received from the relays are returned.
+    #[inline]
+    pub fn include_database(mut self, include: bool) -> Self {
+        self.include_database = include;
+        self
+    }
+}
+
+impl<'client> IntoFuture for FetchEventsAndStore<'client> {
+    type Output = Result<FetchAndStoreOutput, Error>;
+    type IntoFuture = BoxedFuture<'client, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let database: &dyn NostrDatabase = self.client.pool().database().as_ref();
+
+            // The limit of the filter applies to the merged events
+            let mut output: FetchAndStoreOutput = FetchAndStoreOutput {
+                events: Events::new(&self.filter),
+                failed: HashMap::new(),
+            };
+
+            // Stream from the READ relays
+            let mut stream = self.client.stream_events(self.filter.clone());
+
+            // Set timeout
+            if let Some(timeout) = self.timeout {
+                stream = stream.timeout(timeout);
+            }
+
+            // Execute stream
+            let mut stream = stream.await?;
+
+            while let Some((url, result)) = stream.next().await {
+                // NOTE: not propagate the error here! A single error by any of the relays would stop the entire fetching process.
+                let event = match result {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::error!(url = %url, error = %e, "Failed to handle streamed event");
+                        continue;
+                    }
+                };
+
+                // The streamed events are already verified, and are usually saved by the relay.
+                // Save the missing ones, collecting the errors instead of aborting the fetch.
+                match database.check_id(&event.id).await {
+                    Ok(DatabaseEventStatus::Saved) => {}
+                    Ok(DatabaseEventStatus::Deleted) => continue,
+                    Ok(DatabaseEventStatus::NotExistent) => {
+                        if let Err(e) = database.save_event(&event).await {
+                            output.failed.insert(event.id, e.to_string());
+                        }
+                    }
+                    Err(e) => {
+                        output.failed.insert(event.id, e.to_string());
+                    }
+                }
+
+                output.events.insert(event);
+            }
+
+            // Merge with the events in the database
+            if self.include_database {
+                let stored: Events = database.query(self.filter).await?;
+                output.events.extend(stored);
+            }
+
+            Ok(output)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::{EventBuilder, Keys, Kind};
+    use nostr_memory::MemoryDatabase;
+    use nostr_relay_builder::MockRelay;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_events_and_store() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let keys = Keys::generate();
+        let on_relay = EventBuilder::text_note("On relay").sign(&keys).unwrap();
+        let only_local = EventBuilder::text_note("Only local").sign(&keys).unwrap();
+
+        // Publish an event with another client
+        let publisher = Client::default();
+        publisher.add_relay(&url).and_connect().await.unwrap();
+        publisher.send_event(&on_relay).await.unwrap();
+
+        let client = Client::builder()
+            .database(MemoryDatabase::unbounded())
+            .build();
+        client.add_relay(&url).and_connect().await.unwrap();
+        client.database().save_event(&only_local).await.unwrap();
+
+        let filter = Filter::new().kind(Kind::TextNote);
+
+        // Network events only
+        let output = client
+            .fetch_events_and_store(filter.clone())
+            .include_database(false)
+            .await
+            .unwrap();
+        assert_eq!(output.events.len(), 1);
+        assert!(output.events.contains(&on_relay));
+        assert!(output.failed.is_empty());
+
+        // The fetched event has been stored
+        assert!(client.database().contains(&on_relay.id).await.unwrap());
+
+        // Database and network events
+        let output = client.fetch_events_and_store(filter).await.unwrap();
+        assert_eq!(output.events.len(), 2);
+        assert!(output.events.contains(&only_local));
+    }
+}
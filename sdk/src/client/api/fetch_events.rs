@@ -2,8 +2,10 @@ use std::future::IntoFuture;
 use std::time::Duration;
 
 use futures::StreamExt;
+use nostr::{Filter, Timestamp};
 use nostr_database::Events;
 
+use super::boundary;
 use super::req_target::ReqTarget;
 use super::stream_events::StreamEvents;
 use crate::client::{Client, Error};
@@ -49,6 +51,66 @@ impl<'client, 'url> FetchEvents<'client, 'url> {
         self.policy = policy;
         self
     }
+
+    /// Fetch a consistent snapshot, bounded by a cutoff timestamp
+    ///
+    /// The cutoff is recorded when the fetch starts (or set with [`FetchSnapshot::cutoff`]).
+    /// Every filter sent to the relays is bounded to the events created **before** the cutoff,
+    /// and the events created at or after it are discarded, even if a relay sends them anyway.
+    ///
+    /// The snapshot includes the events with `created_at < cutoff`:
+    /// to continue reading from where the snapshot stopped, use [`Snapshot::next_filter`],
+    /// which includes the events created exactly at the cutoff second.
+    #[inline]
+    pub fn snapshot(self) -> FetchSnapshot<'client, 'url> {
+        FetchSnapshot {
+            fetch: self,
+            cutoff: None,
+        }
+    }
+
+    async fn collect(self, cutoff: Option<Timestamp>) -> Result<Events, Error>
+    where
+        'url: 'client,
+    {
+        // Stream events
+        let mut stream: StreamEvents<'client, 'url> =
+            self.client.stream_events(self.target).policy(self.policy);
+
+        // Set timeout
+        if let Some(timeout) = self.timeout {
+            stream = stream.timeout(timeout);
+        }
+
+        // Execute stream
+        let mut stream = stream.await?;
+
+        let mut events: Events = Events::default();
+
+        // Collect events
+        while let Some((url, result)) = stream.next().await {
+            // NOTE: not propagate the error here! A single error by any of the relays would stop the entire fetching process.
+            match result {
+                Ok(event) => {
+                    // Discard the events that the relay sent despite the cutoff
+                    if let Some(cutoff) = cutoff {
+                        if !boundary::is_before(event.created_at, cutoff) {
+                            tracing::debug!(url = %url, id = %event.id, "Discarded event created after the snapshot cutoff");
+                            continue;
+                        }
+                    }
+
+                    // To find out more about why the `force_insert` was used, search for EVENTS_FORCE_INSERT in the code.
+                    events.force_insert(event);
+                }
+                Err(e) => {
+                    tracing::error!(url = %url, error = %e, "Failed to handle streamed event");
+                }
+            }
+        }
+
+        Ok(events)
+    }
 }
 
 impl<'client, 'url> IntoFuture for FetchEvents<'client, 'url>
@@ -59,36 +121,251 @@ where
     type IntoFuture = BoxedFuture<'client, Self::Output>;
 
     fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self.collect(None).await })
+    }
+}
+
+/// Fetch a consistent snapshot of events
+///
+/// Check [`FetchEvents::snapshot`] to learn more.
+#[must_use = "Does nothing unless you await!"]
+pub struct FetchSnapshot<'client, 'url> {
+    fetch: FetchEvents<'client, 'url>,
+    cutoff: Option<Timestamp>,
+}
+
+impl FetchSnapshot<'_, '_> {
+    /// Set a timeout
+    ///
+    /// By default, no timeout is configured.
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.fetch.timeout = Some(timeout);
+        self
+    }
+
+    /// Set request exit policy (default: [`ReqExitPolicy::ExitOnEOSE`]).
+    #[inline]
+    pub fn policy(mut self, policy: ReqExitPolicy) -> Self {
+        self.fetch.policy = policy;
+        self
+    }
+
+    /// Set the cutoff
+    ///
+    /// By default, the cutoff is the current timestamp, taken when the fetch starts.
+    #[inline]
+    pub fn cutoff(mut self, cutoff: Timestamp) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+}
+
+impl<'client, 'url> IntoFuture for FetchSnapshot<'client, 'url>
+where
+    'url: 'client,
+{
+    type Output = Result<Snapshot, Error>;
+    type IntoFuture = BoxedFuture<'client, Self::Output>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
         Box::pin(async move {
-            // Stream events
-            let mut stream: StreamEvents<'client, 'url> =
-                self.client.stream_events(self.target).policy(self.policy);
+            let cutoff: Timestamp = self.cutoff.unwrap_or_else(Timestamp::now);
 
-            // Set timeout
-            if let Some(timeout) = self.timeout {
-                stream = stream.timeout(timeout);
-            }
+            // Bound every filter to the cutoff
+            self.fetch.target = self
+                .fetch
+                .target
+                .map_filters(|filter| boundary::bound_before(filter, cutoff));
 
-            // Execute stream
-            let mut stream = stream.await?;
+            let events: Events = self.fetch.collect(Some(cutoff)).await?;
 
-            let mut events: Events = Events::default();
+            Ok(Snapshot { events, cutoff })
+        })
+    }
+}
 
-            // Collect events
-            while let Some((url, result)) = stream.next().await {
-                // NOTE: not propagate the error here! A single error by any of the relays would stop the entire fetching process.
-                match result {
-                    Ok(event) => {
-                        // To find out more about why the `force_insert` was used, search for EVENTS_FORCE_INSERT in the code.
-                        events.force_insert(event);
-                    }
-                    Err(e) => {
-                        tracing::error!(url = %url, error = %e, "Failed to handle streamed event");
-                    }
+/// Snapshot of events
+///
+/// Includes only the events created **before** the [`Snapshot::cutoff`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Events
+    pub events: Events,
+    /// Cutoff (exclusive)
+    pub cutoff: Timestamp,
+}
+
+impl Snapshot {
+    /// Build the filter to continue reading from where the snapshot stopped
+    ///
+    /// Sets `since` to the [`Snapshot::cutoff`],
+    /// so the events created exactly at the cutoff second are included in the next read.
+    #[inline]
+    pub fn next_filter(&self, filter: Filter) -> Filter {
+        boundary::resume_from(filter, self.cutoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "gossip")]
+    use std::num::NonZeroUsize;
+
+    use nostr::{ClientMessage, EventBuilder, JsonUtil, Keys, Kind, RelayMessage};
+    #[cfg(feature = "gossip")]
+    use nostr_gossip::GossipAllowedRelays;
+    #[cfg(feature = "gossip")]
+    use nostr_gossip_memory::store::NostrGossipMemory;
+    #[cfg(feature = "gossip")]
+    use nostr_relay_builder::MockRelay;
+
+    use super::*;
+    #[cfg(feature = "gossip")]
+    use crate::client::{GossipConfig, GossipRelayLimits};
+    #[cfg(feature = "gossip")]
+    use crate::relay::RelayCapabilities;
+    use crate::transport::mock::MockWebSocketTransport;
+    use crate::transport::websocket::Message;
+
+    #[tokio::test]
+    async fn test_snapshot_excludes_events_at_cutoff() {
+        let transport = MockWebSocketTransport::new();
+        let client = Client::builder()
+            .websocket_transport(transport.clone())
+            .build();
+        client.add_relay("ws://mock").and_connect().await.unwrap();
+
+        let cutoff = Timestamp::from_secs(1_700_000_000);
+        let filter = Filter::new().kind(Kind::ZapReceipt);
+
+        let c = client.clone();
+        let f = filter.clone();
+        let handle = tokio::spawn(async move {
+            c.fetch_events(f)
+                .snapshot()
+                .cutoff(cutoff)
+                .timeout(Duration::from_secs(5))
+                .await
+        });
+
+        // The REQ must be bounded to the cutoff
+        let id = match transport.recv().await.unwrap() {
+            Message::Text(json) => match ClientMessage::from_json(json).unwrap() {
+                ClientMessage::Req {
+                    subscription_id,
+                    filters,
+                } => {
+                    assert_eq!(filters.len(), 1);
+                    assert_eq!(filters[0].until, Some(cutoff - 1));
+                    subscription_id.into_owned()
                 }
-            }
+                msg => panic!("unexpected message: {msg:?}"),
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        };
 
-            Ok(events)
-        })
+        // Send an event before the cutoff and one exactly at the cutoff
+        let keys = Keys::generate();
+        let before = EventBuilder::new(Kind::ZapReceipt, "before")
+            .custom_created_at(cutoff - 1)
+            .sign(&keys)
+            .unwrap();
+        let at = EventBuilder::new(Kind::ZapReceipt, "at")
+            .custom_created_at(cutoff)
+            .sign(&keys)
+            .unwrap();
+
+        for msg in [
+            RelayMessage::event(id.clone(), before.clone()),
+            RelayMessage::event(id.clone(), at.clone()),
+            RelayMessage::eose(id),
+        ] {
+            transport.push(Message::Text(msg.as_json()));
+        }
+
+        let snapshot: Snapshot = handle.await.unwrap().unwrap();
+        assert_eq!(snapshot.cutoff, cutoff);
+        assert_eq!(snapshot.events.len(), 1);
+        assert!(snapshot.events.contains(&before));
+        assert!(!snapshot.events.contains(&at));
+
+        // The event at the cutoff belongs to the next read
+        let next: Filter = snapshot.next_filter(filter);
+        assert!(next.match_event(&at, Default::default()));
+        assert!(!next.match_event(&before, Default::default()));
+    }
+
+    #[cfg(feature = "gossip")]
+    async fn fetch_from_write_relay(enabled: bool) -> Events {
+        // The event exists only on the write relay of the author
+        let write_mock = MockRelay::run().await.unwrap();
+        let write_url = write_mock.url().await;
+
+        // NIP-65 lists
+        let discovery_mock = MockRelay::run().await.unwrap();
+        let discovery_url = discovery_mock.url().await;
+
+        // The only READ relay of the client
+        let public_mock = MockRelay::run().await.unwrap();
+        let public_url = public_mock.url().await;
+
+        let author = Keys::generate();
+        let relay_list = EventBuilder::relay_list([(write_url.clone(), None)])
+            .sign(&author)
+            .unwrap();
+        discovery_mock.add_event(relay_list).await.unwrap();
+
+        let event = EventBuilder::text_note("Outbox").sign(&author).unwrap();
+        write_mock.add_event(event).await.unwrap();
+
+        let config = GossipConfig::default()
+            .enabled(enabled)
+            .limits(GossipRelayLimits {
+                most_used_relays_per_user: 0,
+                ..Default::default()
+            })
+            .allowed(GossipAllowedRelays {
+                onion: true,
+                local: true,
+                without_tls: true,
+            })
+            .max_new_relays(NonZeroUsize::new(1).unwrap());
+        let client = Client::builder()
+            .gossip(NostrGossipMemory::unbounded())
+            .gossip_config(config)
+            .build();
+
+        client
+            .add_relay(&discovery_url)
+            .capabilities(RelayCapabilities::DISCOVERY)
+            .await
+            .unwrap();
+        client.add_relay(&public_url).await.unwrap();
+        client.connect().await;
+
+        let filter = Filter::new()
+            .author(author.public_key())
+            .kind(Kind::TextNote);
+        let events = client
+            .fetch_events(filter)
+            .timeout(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        // The write relay has been added only by gossip
+        assert_eq!(client.relay(&write_url).await.unwrap().is_some(), enabled);
+
+        events
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "gossip")]
+    async fn test_fetch_events_from_author_write_relays() {
+        let events = fetch_from_write_relay(true).await;
+        assert_eq!(events.len(), 1);
+
+        let events = fetch_from_write_relay(false).await;
+        assert!(events.is_empty());
     }
 }
@@ -1,5 +1,10 @@
 mod add;
+mod apply_relay_list;
+mod boundary;
+#[cfg(feature = "nip59")]
+mod catch_up_gift_wraps;
 mod connect;
+mod fetch_and_store;
 mod fetch_events;
 mod output;
 mod relays;
@@ -10,6 +15,7 @@ mod send_event;
 mod send_msg;
 mod stream_events;
 mod subscribe;
+#[cfg(feature = "negentropy")]
 mod sync;
 mod try_connect;
 mod unsubscribe;
@@ -17,7 +23,11 @@ mod unsubscribe_all;
 mod util;
 
 pub use self::add::*;
+pub use self::apply_relay_list::*;
+#[cfg(feature = "nip59")]
+pub use self::catch_up_gift_wraps::*;
 pub use self::connect::*;
+pub use self::fetch_and_store::*;
 pub use self::fetch_events::*;
 pub use self::output::*;
 pub use self::relays::*;
@@ -28,6 +38,7 @@ pub use self::send_event::*;
 pub use self::send_msg::*;
 pub use self::stream_events::*;
 pub use self::subscribe::*;
+#[cfg(feature = "negentropy")]
 pub use self::sync::*;
 pub use self::try_connect::*;
 pub use self::unsubscribe::*;
@@ -3,11 +3,14 @@
 // Distributed under the MIT software license
 
 use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::ops::{Deref, DerefMut};
 
+use nostr::message::MachineReadablePrefix;
 use nostr::{EventId, RelayUrl, SubscriptionId};
 
+use crate::relay;
+
 /// Output
 ///
 /// Send or negentropy reconciliation output
@@ -81,3 +84,88 @@ impl Output<SubscriptionId> {
         self.deref()
     }
 }
+
+/// Why a relay didn't accept a published event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishError {
+    /// The relay replied with `OK` false
+    Rejected {
+        /// Machine-readable prefix (i.e., `duplicate`, `pow`, `rate-limited`, `auth-required`, `blocked`)
+        prefix: Option<MachineReadablePrefix>,
+        /// Message, including the prefix
+        message: String,
+    },
+    /// Timeout waiting for the `OK`
+    Timeout,
+    /// Any other error (i.e., transport error, relay not connected)
+    Error(String),
+}
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected { message, .. } => f.write_str(message),
+            Self::Timeout => f.write_str("timeout"),
+            Self::Error(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<relay::Error> for PublishError {
+    fn from(e: relay::Error) -> Self {
+        match e {
+            relay::Error::RelayMessage(message) => Self::Rejected {
+                prefix: MachineReadablePrefix::parse(&message),
+                message,
+            },
+            relay::Error::Timeout => Self::Timeout,
+            e => Self::Error(e.to_string()),
+        }
+    }
+}
+
+/// Publish output
+///
+/// Result of sending an event to the relays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishOutput {
+    /// Event ID
+    pub val: EventId,
+    /// Set of relays that accepted the event
+    pub success: HashSet<RelayUrl>,
+    /// Map of relays that didn't accept the event, with the reasons.
+    pub failed: HashMap<RelayUrl, PublishError>,
+}
+
+impl Deref for PublishOutput {
+    type Target = EventId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.val
+    }
+}
+
+impl PublishOutput {
+    /// Create a new output
+    #[must_use]
+    pub fn new(id: EventId) -> Self {
+        Self {
+            val: id,
+            success: HashSet::new(),
+            failed: HashMap::new(),
+        }
+    }
+
+    /// Get event ID
+    #[inline]
+    pub fn id(&self) -> &EventId {
+        &self.val
+    }
+
+    /// Get inner value
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> EventId {
+        self.val
+    }
+}
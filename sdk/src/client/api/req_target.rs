@@ -57,6 +57,24 @@ impl<'url> ReqTarget<'url> {
     pub(super) fn into_inner(self) -> InnerReqTarget<'url> {
         self.0
     }
+
+    /// Rewrite every filter of the target
+    pub(super) fn map_filters<F>(self, f: F) -> Self
+    where
+        F: Fn(Filter) -> Filter,
+    {
+        Self(match self.0 {
+            InnerReqTarget::Auto(filters) => {
+                InnerReqTarget::Auto(filters.into_iter().map(&f).collect())
+            }
+            InnerReqTarget::Manual(targets) => InnerReqTarget::Manual(
+                targets
+                    .into_iter()
+                    .map(|(url, filters)| (url, filters.into_iter().map(&f).collect()))
+                    .collect(),
+            ),
+        })
+    }
 }
 
 impl From<Filter> for ReqTarget<'_> {
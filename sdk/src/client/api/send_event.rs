@@ -1,16 +1,22 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::HashSet;
+#[cfg(feature = "gossip")]
+use std::collections::{BTreeSet, HashMap};
 use std::future::IntoFuture;
+#[cfg(feature = "gossip")]
 use std::iter;
 use std::time::Duration;
 
-use nostr::{Event, EventId, Kind, PublicKey, RelayUrl, RelayUrlArg};
+use nostr::{Event, RelayUrl, RelayUrlArg};
+#[cfg(feature = "gossip")]
+use nostr::{Kind, PublicKey};
+#[cfg(feature = "gossip")]
 use nostr_gossip::{BestRelaySelection, GossipListKind};
 
-use super::output::Output;
+use super::output::PublishOutput;
+#[cfg(feature = "gossip")]
 use crate::client::gossip::Gossip;
 use crate::client::{Client, Error};
 use crate::future::BoxedFuture;
-use crate::relay::RelayCapabilities;
 
 enum OverwritePolicy<'url> {
     // All WRITE relays
@@ -124,6 +130,7 @@ pub struct SendEvent<'client, 'event, 'url> {
     save_into_database: bool,
     wait_for_ok_timeout: Duration,
     wait_for_authentication_timeout: Duration,
+    require_success_on: usize,
 }
 
 impl<'client, 'event, 'url> SendEvent<'client, 'event, 'url> {
@@ -136,10 +143,11 @@ impl<'client, 'event, 'url> SendEvent<'client, 'event, 'url> {
             save_into_database: true,
             wait_for_ok_timeout: Duration::from_secs(10),
             wait_for_authentication_timeout: Duration::from_secs(10),
+            require_success_on: 1,
         }
     }
 
-    /// Send event to all relays with [`RelayCapabilities::WRITE`] capability.
+    /// Send event to all relays with [`RelayCapabilities::WRITE`](crate::relay::RelayCapabilities::WRITE) capability.
     ///
     /// This overwrites the following methods:
     /// - [`SendEvent::to`]
@@ -231,8 +239,19 @@ impl<'client, 'event, 'url> SendEvent<'client, 'event, 'url> {
         self.wait_for_authentication_timeout = timeout;
         self
     }
+
+    /// Minimum number of relays that must accept the event (default: 1)
+    ///
+    /// If less relays accept it, [`Error::PublishFailed`] is returned, with the reasons of the failures.
+    /// Set to `0` to always get the [`PublishOutput`].
+    #[inline]
+    pub fn require_success_on(mut self, relays: usize) -> Self {
+        self.require_success_on = relays;
+        self
+    }
 }
 
+#[cfg(feature = "gossip")]
 async fn gossip_prepare_urls(
     client: &Client,
     gossip: &Gossip,
@@ -276,10 +295,11 @@ async fn gossip_prepare_urls(
     if is_nip17 && is_gift_wrap {
         // Get NIP17 relays
         // Get only for relays for p tags since gift wraps are signed with random key (random author)
-        let relays = gossip
+        let p_tags: Vec<PublicKey> = event.tags.public_keys().collect();
+        let relays: HashMap<RelayUrl, BTreeSet<PublicKey>> = gossip
             .resolver()
-            .get_relays(
-                event.tags.public_keys(),
+            .map_relays(
+                &p_tags,
                 BestRelaySelection::PrivateMessage {
                     limit: client.config().gossip_config.limits.nip17_relays,
                 },
@@ -296,18 +316,10 @@ async fn gossip_prepare_urls(
         }
 
         // Add outbox and inbox relays
-        for url in relays.iter().cloned() {
-            client
-                .add_relay(url)
-                .capabilities(RelayCapabilities::GOSSIP)
-                .and_connect()
-                .await?;
-        }
-
-        Ok(relays)
+        client.add_gossip_relays(relays).await
     } else {
         // Get OUTBOX, HINTS and MOST_RECEIVED relays for the author
-        let mut relays: HashSet<RelayUrl> = gossip
+        let outbox: HashSet<RelayUrl> = gossip
             .store()
             .get_best_relays(
                 &event.pubkey,
@@ -325,12 +337,19 @@ async fn gossip_prepare_urls(
             )
             .await?;
 
+        // The relays of the author cover only the author
+        let mut relays: HashMap<RelayUrl, BTreeSet<PublicKey>> = outbox
+            .into_iter()
+            .map(|url| (url, BTreeSet::from([event.pubkey])))
+            .collect();
+
         // Extend with INBOX, HINTS and MOST_RECEIVED relays for the tags
         if !is_contact_list {
-            let inbox_hints_most_recv: HashSet<RelayUrl> = gossip
+            let p_tags: Vec<PublicKey> = event.tags.public_keys().collect();
+            let inbox_hints_most_recv: HashMap<RelayUrl, BTreeSet<PublicKey>> = gossip
                 .resolver()
-                .get_relays(
-                    event.tags.public_keys(),
+                .map_relays(
+                    &p_tags,
                     BestRelaySelection::All {
                         read: client.config().gossip_config.limits.read_relays_per_user,
                         write: 0, // No write relays
@@ -345,17 +364,13 @@ async fn gossip_prepare_urls(
                 )
                 .await?;
 
-            relays.extend(inbox_hints_most_recv);
+            for (url, public_keys) in inbox_hints_most_recv.into_iter() {
+                relays.entry(url).or_default().extend(public_keys);
+            }
         }
 
         // Add OUTBOX and INBOX relays
-        for url in relays.iter().cloned() {
-            client
-                .add_relay(url)
-                .capabilities(RelayCapabilities::GOSSIP)
-                .and_connect()
-                .await?;
-        }
+        let mut relays: HashSet<RelayUrl> = client.add_gossip_relays(relays).await?;
 
         // Get WRITE relays
         let write_relays: HashSet<RelayUrl> = client.pool().write_relay_urls().await;
@@ -368,41 +383,61 @@ async fn gossip_prepare_urls(
     }
 }
 
+/// Get the gossip relays for the event, or `None` if gossip is not configured.
+#[cfg_attr(not(feature = "gossip"), allow(unused_variables))]
+async fn gossip_urls(
+    client: &Client,
+    event: &Event,
+    is_nip17: bool,
+) -> Result<Option<HashSet<RelayUrl>>, Error> {
+    #[cfg(feature = "gossip")]
+    if let Some(gossip) = client.gossip() {
+        return gossip_prepare_urls(client, gossip, event, is_nip17)
+            .await
+            .map(Some);
+    }
+
+    Ok(None)
+}
+
 impl<'client, 'event, 'url> IntoFuture for SendEvent<'client, 'event, 'url>
 where
     'event: 'client,
     'url: 'client,
 {
-    type Output = Result<Output<EventId>, Error>;
+    type Output = Result<PublishOutput, Error>;
     type IntoFuture = BoxedFuture<'client, Self::Output>;
 
     fn into_future(self) -> Self::IntoFuture {
         Box::pin(async move {
             // Save event into database
             if self.save_into_database {
-                self.client.database().save_event(self.event).await?;
+                self.client.pool().database().save_event(self.event).await?;
             }
 
             // Process event for gossip, independently of the policy
+            #[cfg(feature = "gossip")]
             if let Some(gossip) = self.client.gossip() {
                 gossip.store().process(self.event, None).await?;
             }
 
-            let urls: HashSet<RelayUrl> = match (self.policy, self.client.gossip()) {
-                // No overwrite policy or send to NIP-65 and gossip available: send to NIP-65 relays
-                (None | Some(OverwritePolicy::ToNip65), Some(gossip)) => {
-                    gossip_prepare_urls(self.client, gossip, self.event, false).await?
-                }
-                // Send to NIP-17 and gossip available: send to NIP-17 relays
-                (Some(OverwritePolicy::ToNip17), Some(gossip)) => {
-                    gossip_prepare_urls(self.client, gossip, self.event, true).await?
-                }
-                // Send to gossip, but gossip is not available: error
-                (Some(OverwritePolicy::ToNip17 | OverwritePolicy::ToNip65), None) => {
-                    return Err(Error::GossipNotConfigured);
-                }
+            let urls: HashSet<RelayUrl> = match self.policy {
+                // No overwrite policy: send to NIP-65 relays if gossip is available,
+                // otherwise send to all WRITE relays
+                None => match gossip_urls(self.client, self.event, false).await? {
+                    Some(urls) => urls,
+                    None => self.client.pool().write_relay_urls().await,
+                },
+                // Send to NIP-65 relays: gossip is required
+                Some(OverwritePolicy::ToNip65) => gossip_urls(self.client, self.event, false)
+                    .await?
+                    .ok_or(Error::GossipNotConfigured)?,
+                // Send to NIP-17 relays: gossip is required
+                Some(OverwritePolicy::ToNip17) => gossip_urls(self.client, self.event, true)
+                    .await?
+                    .ok_or(Error::GossipNotConfigured)?,
                 // Send to specific relays
-                (Some(OverwritePolicy::To(list)), _) => {
+                Some(OverwritePolicy::To(list)) => {
                     let mut urls: HashSet<RelayUrl> = HashSet::with_capacity(list.len());
 
                     for url in list {
@@ -411,15 +446,11 @@ where
 
                     urls
                 }
-                // - Broadcast policy,
-                // - Or, no overwrite policy and no gossip available
-                // -> Send to all WRITE relays
-                (Some(OverwritePolicy::Broadcast), _) | (None, None) => {
-                    self.client.pool().write_relay_urls().await
-                }
+                // Broadcast policy: send to all WRITE relays
+                Some(OverwritePolicy::Broadcast) => self.client.pool().write_relay_urls().await,
             };
 
-            Ok(self
+            let output: PublishOutput = self
                 .client
                 .pool()
                 .send_event(
@@ -429,7 +460,17 @@ where
                     self.wait_for_ok_timeout,
                     self.wait_for_authentication_timeout,
                 )
-                .await?)
+                .await?;
+
+            // Check if enough relays accepted the event
+            if output.success.len() < self.require_success_on {
+                return Err(Error::PublishFailed {
+                    required: self.require_success_on,
+                    output: Box::new(output),
+                });
+            }
+
+            Ok(output)
         })
     }
 }
@@ -437,12 +478,17 @@ where
 #[cfg(test)]
 mod tests {
     use nostr::prelude::*;
+    #[cfg(feature = "gossip")]
     use nostr_gossip::GossipAllowedRelays;
+    #[cfg(feature = "gossip")]
     use nostr_gossip_memory::store::NostrGossipMemory;
     use nostr_relay_builder::MockRelay;
 
     use super::*;
-    use crate::client::{Error, GossipConfig, GossipRelayLimits};
+    use crate::client::Error;
+    #[cfg(feature = "gossip")]
+    use crate::client::{GossipConfig, GossipRelayLimits};
+    use crate::relay::RelayCapabilities;
 
     #[tokio::test]
     async fn test_send_event() {
@@ -512,6 +558,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "gossip")]
     async fn test_send_event_broadcast() {
         let mock1 = MockRelay::run().await.unwrap();
         let url1 = mock1.url().await;
@@ -554,6 +601,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "gossip")]
     async fn test_send_event_with_auto_gossip() {
         // Setup Outbox Relay (where the user wants to receive/send events)
         let outbox_mock = MockRelay::run().await.unwrap();
@@ -641,6 +689,64 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_send_event_failure_reasons() {
+        use nostr_relay_builder::prelude::{
+            LocalRelay, LocalRelayBuilderNip42, LocalRelayBuilderNip42Mode,
+        };
+
+        use crate::client::PublishError;
+
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        // Relay that requires the authentication to write
+        let auth_relay = LocalRelay::builder()
+            .nip42(LocalRelayBuilderNip42 {
+                mode: LocalRelayBuilderNip42Mode::Write,
+            })
+            .build();
+        auth_relay.run().await.unwrap();
+        let auth_url = auth_relay.url().await;
+
+        let client: Client = Client::default();
+        client.add_relay(&url).await.unwrap();
+        client.add_relay(&auth_url).await.unwrap();
+        client.connect().await;
+
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("Reasons test").sign(&keys).unwrap();
+
+        // Accepted by one relay: success
+        let output = client.send_event(&event).await.unwrap();
+        assert_eq!(output.success, HashSet::from([url.clone()]));
+        match output.failed.get(&auth_url).unwrap() {
+            PublishError::Rejected { prefix, .. } => {
+                assert_eq!(prefix, &Some(MachineReadablePrefix::AuthRequired))
+            }
+            e => panic!("Unexpected error: {e}"),
+        }
+
+        // Required by both relays: error, with the output
+        match client
+            .send_event(&event)
+            .require_success_on(2)
+            .await
+            .unwrap_err()
+        {
+            Error::PublishFailed { required, output } => {
+                assert_eq!(required, 2);
+                assert_eq!(output.success.len(), 1);
+                assert!(output.failed.contains_key(&auth_url));
+            }
+            e => panic!("Unexpected error: {e}"),
+        }
+
+        // No relay accepted the event
+        let err = client.send_event(&event).to([&auth_url]).await.unwrap_err();
+        assert!(matches!(err, Error::PublishFailed { required: 1, .. }));
+    }
+
     #[tokio::test]
     async fn test_send_event_to_nip65_without_gossip() {
         let mock = MockRelay::run().await.unwrap();
@@ -661,6 +767,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "gossip")]
     async fn test_send_event_to_nip17() {
         let inbox_mock = MockRelay::run().await.unwrap();
         let inbox_url = inbox_mock.url().await;
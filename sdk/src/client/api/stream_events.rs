@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::IntoFuture;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::time::Duration;
 
-use futures::Stream;
+use futures::{Stream, StreamExt, stream};
+use nostr::filter::MatchEventOptions;
 use nostr::types::url::RelayUrl;
 use nostr::{Event, Filter, SubscriptionId};
 
@@ -11,9 +13,31 @@ use super::req_target::ReqTarget;
 use super::util::build_targets;
 use crate::client::{Client, Error};
 use crate::future::BoxedFuture;
+use crate::pool::{DEFAULT_STREAM_BUFFER, DEFAULT_STREAM_DEDUP_CAPACITY, StreamOptions};
 use crate::relay::{self, ReqExitPolicy};
 
 type EventStream = Pin<Box<dyn Stream<Item = (RelayUrl, Result<Event, relay::Error>)> + Send>>;
+type SourcedEventStream =
+    Pin<Box<dyn Stream<Item = (HashSet<RelayUrl>, Result<Event, relay::Error>)> + Send>>;
+
+/// What to do when the stream buffer is full
+///
+/// The buffer fills up when the consumer of the stream is slower than the relays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StreamOverflowPolicy {
+    /// Wait for the consumer (back-pressure)
+    ///
+    /// No events are lost, but the subscriptions stop reading the relay notifications while waiting:
+    /// if the consumer falls behind more than the notification channel capacity
+    /// (see [`ClientBuilder::notification_channel_size`](crate::client::ClientBuilder::notification_channel_size)),
+    /// the relay stream terminates early.
+    #[default]
+    Block,
+    /// Discard the oldest buffered event
+    ///
+    /// The subscriptions never wait for the consumer, but the events may be lost.
+    DropOldest,
+}
 
 /// Stream events
 #[must_use = "Does nothing unless you await!"]
@@ -28,6 +52,11 @@ pub struct StreamEvents<'client, 'url> {
     id: Option<SubscriptionId>,
     timeout: Option<Duration>,
     policy: ReqExitPolicy,
+    deduplicate: bool,
+    dedup_capacity: NonZeroUsize,
+    ordered: bool,
+    buffer: NonZeroUsize,
+    overflow: StreamOverflowPolicy,
 }
 
 impl<'client, 'url> StreamEvents<'client, 'url> {
@@ -38,6 +67,11 @@ impl<'client, 'url> StreamEvents<'client, 'url> {
             id: None,
             timeout: None,
             policy: ReqExitPolicy::ExitOnEOSE,
+            deduplicate: true,
+            dedup_capacity: DEFAULT_STREAM_DEDUP_CAPACITY,
+            ordered: false,
+            buffer: DEFAULT_STREAM_BUFFER,
+            overflow: StreamOverflowPolicy::Block,
         }
     }
 
@@ -63,6 +97,68 @@ impl<'client, 'url> StreamEvents<'client, 'url> {
         self.policy = policy;
         self
     }
+
+    /// Deduplicate the events across relays (default: true)
+    ///
+    /// The event is yielded once, with the URL of the first relay that sent it.
+    #[inline]
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Max number of event IDs remembered for deduplication (default: 100_000)
+    ///
+    /// When exceeded, the least recently seen IDs are forgotten,
+    /// so the memory stays bounded for never-ending streams.
+    #[inline]
+    pub fn dedup_capacity(mut self, capacity: NonZeroUsize) -> Self {
+        self.dedup_capacity = capacity;
+        self
+    }
+
+    /// Capacity of the stream buffer (default: 1024)
+    ///
+    /// When the buffer is full, the [`StreamEvents::overflow`] policy applies and a warning is logged.
+    #[inline]
+    pub fn buffer(mut self, capacity: NonZeroUsize) -> Self {
+        self.buffer = capacity;
+        self
+    }
+
+    /// What to do when the stream buffer is full (default: [`StreamOverflowPolicy::Block`])
+    #[inline]
+    pub fn overflow(mut self, policy: StreamOverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Yield the events sorted by `created_at` descending, ID ascending (default: false)
+    ///
+    /// With [`ReqExitPolicy::ExitOnEOSE`], the events are collected until all the relays sent the EOSE
+    /// (or the timeout expired), then merged, sorted and yielded.
+    /// The `limit` of the filters is applied to the merged events, instead of per relay.
+    /// Errors are yielded before the events.
+    ///
+    /// With the other policies, the stream includes live events, so it isn't ordered:
+    /// the events are yielded as they arrive.
+    #[inline]
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Yield every event once, with all the relays that sent it
+    ///
+    /// The events are held until all the relay streams terminated (i.e., EOSE with [`ReqExitPolicy::ExitOnEOSE`]).
+    /// To keep the memory bounded, up to [`StreamEvents::dedup_capacity`] events are held:
+    /// when exceeded, the oldest event is yielded early, with the relays that sent it so far.
+    ///
+    /// Errors are yielded immediately, with the relay that caused them.
+    #[inline]
+    pub fn with_sources(self) -> StreamEventsWithSources<'client, 'url> {
+        StreamEventsWithSources { stream: self }
+    }
 }
 
 impl<'client, 'url> IntoFuture for StreamEvents<'client, 'url>
@@ -78,11 +174,135 @@ where
             let targets: HashMap<RelayUrl, Vec<Filter>> =
                 build_targets(self.client, self.target).await?;
 
+            // Only the stored events can be ordered
+            let ordered: bool = self.ordered && matches!(self.policy, ReqExitPolicy::ExitOnEOSE);
+
+            // Distinct filters, to apply the limits after merging
+            let filters: Vec<Filter> = if ordered {
+                let filters: HashSet<&Filter> = targets.values().flatten().collect();
+                filters.into_iter().cloned().collect()
+            } else {
+                Vec::new()
+            };
+
+            // Stream
+            let stream: EventStream = self
+                .client
+                .pool()
+                .stream_events(
+                    targets,
+                    self.id,
+                    self.timeout,
+                    self.policy,
+                    StreamOptions {
+                        dedup: self.deduplicate.then_some(self.dedup_capacity),
+                        buffer: self.buffer,
+                        overflow: self.overflow,
+                    },
+                )
+                .await?;
+
+            if ordered {
+                Ok(ordered_stream(stream, filters))
+            } else {
+                Ok(stream)
+            }
+        })
+    }
+}
+
+/// Collect the stream, then yield the errors and the sorted events
+fn ordered_stream(stream: EventStream, filters: Vec<Filter>) -> EventStream {
+    let items = stream::once(async move {
+        let (events, errors): (Vec<_>, Vec<_>) = stream
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .partition(|(.., res)| res.is_ok());
+
+        let mut events: Vec<(RelayUrl, Event)> = events
+            .into_iter()
+            .filter_map(|(url, res)| res.ok().map(|event| (url, event)))
+            .collect();
+
+        // Lookup ID: EVENT_ORD_IMPL
+        events.sort_by(|(.., a), (.., b)| a.cmp(b));
+
+        let events = apply_limits(events, &filters)
+            .into_iter()
+            .map(|(url, event)| (url, Ok(event)));
+
+        stream::iter(errors.into_iter().chain(events))
+    });
+
+    Box::pin(items.flatten())
+}
+
+/// Apply the `limit` of the filters to the sorted events
+///
+/// An event is kept if at least one of the matching filters hasn't reached its limit yet.
+/// The events that don't match any filter are kept.
+fn apply_limits(events: Vec<(RelayUrl, Event)>, filters: &[Filter]) -> Vec<(RelayUrl, Event)> {
+    let mut counts: Vec<usize> = vec![0; filters.len()];
+
+    events
+        .into_iter()
+        .filter(|(.., event)| {
+            let mut matched: bool = false;
+            let mut keep: bool = false;
+
+            for (filter, count) in filters.iter().zip(counts.iter_mut()) {
+                if !filter.match_event(event, MatchEventOptions::new()) {
+                    continue;
+                }
+
+                matched = true;
+
+                if filter.limit.is_none_or(|limit| *count < limit) {
+                    *count += 1;
+                    keep = true;
+                }
+            }
+
+            keep || !matched
+        })
+        .collect()
+}
+
+/// Stream events with their sources
+///
+/// Check [`StreamEvents::with_sources`] to learn more.
+#[must_use = "Does nothing unless you await!"]
+pub struct StreamEventsWithSources<'client, 'url> {
+    stream: StreamEvents<'client, 'url>,
+}
+
+impl<'client, 'url> IntoFuture for StreamEventsWithSources<'client, 'url>
+where
+    'url: 'client,
+{
+    type Output = Result<SourcedEventStream, Error>;
+    type IntoFuture = BoxedFuture<'client, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let stream: StreamEvents<'client, 'url> = self.stream;
+
+            // Build targets
+            let targets: HashMap<RelayUrl, Vec<Filter>> =
+                build_targets(stream.client, stream.target).await?;
+
             // Stream
-            Ok(self
+            Ok(stream
                 .client
                 .pool()
-                .stream_events(targets, self.id, self.timeout, self.policy)
+                .stream_events_with_sources(
+                    targets,
+                    stream.id,
+                    stream.timeout,
+                    stream.policy,
+                    stream.dedup_capacity,
+                )
                 .await?)
         })
     }
@@ -92,10 +312,16 @@ where
 mod tests {
     use std::time::Duration;
 
-    use nostr::{Filter, Kind, SubscriptionId};
+    use futures::StreamExt;
+    use nostr::{
+        ClientMessage, EventBuilder, EventId, Filter, JsonUtil, Keys, Kind, SubscriptionId,
+        Timestamp,
+    };
     use nostr_relay_builder::MockRelay;
 
     use super::*;
+    use crate::transport::mock::MockWebSocketTransport;
+    use crate::transport::websocket::Message;
 
     #[tokio::test]
     async fn test_stream_terminates_on_drop() {
@@ -133,4 +359,255 @@ mod tests {
         let exists: bool = relay.subscription(&id).await.is_some();
         assert!(!exists);
     }
+
+    /// Two relays with the same event
+    async fn client_with_duplicated_event()
+    -> (Client, Event, RelayUrl, RelayUrl, MockRelay, MockRelay) {
+        let mock1 = MockRelay::run().await.unwrap();
+        let url1 = mock1.url().await;
+        let mock2 = MockRelay::run().await.unwrap();
+        let url2 = mock2.url().await;
+
+        let client = Client::default();
+        client.add_relay(&url1).await.unwrap();
+        client.add_relay(&url2).await.unwrap();
+        client.connect().await;
+
+        let event = EventBuilder::text_note("Duplicated")
+            .sign(&Keys::generate())
+            .unwrap();
+        let output = client.send_event(&event).await.unwrap();
+        assert_eq!(output.success.len(), 2);
+
+        (client, event, url1, url2, mock1, mock2)
+    }
+
+    #[tokio::test]
+    async fn test_stream_deduplicate() {
+        let (client, event, url1, url2, _mock1, _mock2) = client_with_duplicated_event().await;
+        let filter = Filter::new().kind(Kind::TextNote);
+
+        // Deduplicated by default
+        let stream = client.stream_events(filter.clone()).await.unwrap();
+        let items: Vec<_> = stream.collect().await;
+        assert_eq!(items.len(), 1);
+        let (url, result) = &items[0];
+        assert!(url == &url1 || url == &url2);
+        assert_eq!(result.as_ref().unwrap().id, event.id);
+
+        // Deduplication disabled
+        let stream = client
+            .stream_events(filter)
+            .deduplicate(false)
+            .await
+            .unwrap();
+        let urls: HashSet<RelayUrl> = stream.map(|(url, ..)| url).collect().await;
+        assert_eq!(urls, HashSet::from([url1, url2]));
+    }
+
+    #[tokio::test]
+    async fn test_stream_with_sources() {
+        let (client, event, url1, url2, _mock1, _mock2) = client_with_duplicated_event().await;
+
+        let stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote))
+            .with_sources()
+            .await
+            .unwrap();
+        let items: Vec<_> = stream.collect().await;
+        assert_eq!(items.len(), 1);
+
+        let (sources, result) = &items[0];
+        assert_eq!(sources, &HashSet::from([url1, url2]));
+        assert_eq!(result.as_ref().unwrap().id, event.id);
+    }
+
+    #[tokio::test]
+    async fn test_stream_dedup_capacity_bounds_memory() {
+        let (client, .., _mock1, _mock2) = client_with_duplicated_event().await;
+        let event = EventBuilder::text_note("Second")
+            .sign(&Keys::generate())
+            .unwrap();
+        client.send_event(&event).await.unwrap();
+
+        // Only one event held: the first event is yielded before the second one is aggregated
+        let stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote))
+            .dedup_capacity(NonZeroUsize::new(1).unwrap())
+            .with_sources()
+            .await
+            .unwrap();
+        let items: Vec<_> = stream.collect().await;
+        assert!(items.iter().all(|(.., result)| result.is_ok()));
+
+        // Every event is yielded, at most once per relay
+        let ids: HashSet<EventId> = items
+            .iter()
+            .map(|(.., result)| result.as_ref().unwrap().id)
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(items.len() <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ordered() {
+        let mock1 = MockRelay::run().await.unwrap();
+        let url1 = mock1.url().await;
+        let mock2 = MockRelay::run().await.unwrap();
+        let url2 = mock2.url().await;
+
+        let client = Client::default();
+        client.add_relay(&url1).await.unwrap();
+        client.add_relay(&url2).await.unwrap();
+        client.connect().await;
+
+        // Interleave the timestamps across the relays
+        let keys = Keys::generate();
+        for (i, url) in [&url1, &url2, &url1, &url2, &url1].into_iter().enumerate() {
+            let event = EventBuilder::text_note(i.to_string())
+                .custom_created_at(Timestamp::from(1_000 + i as u64))
+                .sign(&keys)
+                .unwrap();
+            client.send_event(&event).to([url]).await.unwrap();
+        }
+
+        let stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote).limit(3))
+            .ordered(true)
+            .await
+            .unwrap();
+        let timestamps: Vec<u64> = stream
+            .map(|(.., res)| res.unwrap().created_at.as_secs())
+            .collect()
+            .await;
+
+        // Sorted, and the limit applied to the merged events
+        assert_eq!(timestamps, vec![1_004, 1_003, 1_002]);
+    }
+
+    /// Relay with the given number of events
+    async fn client_with_events(num: usize) -> (Client, MockRelay) {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let client = Client::default();
+        client.add_relay(&url).and_connect().await.unwrap();
+
+        let keys = Keys::generate();
+        for i in 0..num {
+            let event = EventBuilder::text_note(i.to_string()).sign(&keys).unwrap();
+            client.send_event(&event).await.unwrap();
+        }
+
+        (client, mock)
+    }
+
+    #[tokio::test]
+    async fn test_stream_overflow_block() {
+        let (client, _mock) = client_with_events(5).await;
+
+        let mut stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote))
+            .buffer(NonZeroUsize::new(1).unwrap())
+            .overflow(StreamOverflowPolicy::Block)
+            .await
+            .unwrap();
+
+        // Slow consumer: no events are lost
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let mut count: usize = 0;
+        while let Some((.., res)) = stream.next().await {
+            res.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_stream_overflow_drop_oldest() {
+        let (client, _mock) = client_with_events(5).await;
+
+        let stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote))
+            .buffer(NonZeroUsize::new(2).unwrap())
+            .overflow(StreamOverflowPolicy::DropOldest)
+            .await
+            .unwrap();
+
+        // Slow consumer: the buffered events, plus the one already handed over
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let items: Vec<_> = stream.collect().await;
+        assert_eq!(items.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_limits() {
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let keys = Keys::generate();
+        let event = |kind: Kind, created_at: u64| {
+            let event = EventBuilder::new(kind, "")
+                .custom_created_at(Timestamp::from(created_at))
+                .sign(&keys)
+                .unwrap();
+            (url.clone(), event)
+        };
+
+        let mut events = vec![
+            event(Kind::TextNote, 5),
+            event(Kind::Metadata, 4),
+            event(Kind::TextNote, 3),
+            event(Kind::Metadata, 2),
+            event(Kind::Reaction, 1),
+        ];
+        events.sort_by(|(.., a), (.., b)| a.cmp(b));
+
+        let filters = vec![
+            Filter::new().kind(Kind::TextNote).limit(1),
+            Filter::new().kind(Kind::Metadata),
+        ];
+        let kept: Vec<u64> = apply_limits(events, &filters)
+            .into_iter()
+            .map(|(.., event)| event.created_at.as_secs())
+            .collect();
+
+        // The limit is per filter, and the events not matching any filter are kept
+        assert_eq!(kept, vec![5, 4, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_sends_close_on_drop() {
+        let transport = MockWebSocketTransport::new();
+        let client = Client::builder()
+            .websocket_transport(transport.clone())
+            .build();
+
+        client.add_relay("ws://mock").and_connect().await.unwrap();
+
+        let id = SubscriptionId::generate();
+        let stream = client
+            .stream_events(Filter::new().kind(Kind::TextNote))
+            .with_id(id.clone())
+            .await
+            .unwrap();
+
+        // Wait for the REQ
+        transport.recv().await.unwrap();
+
+        // Drop the stream without unsubscribing
+        drop(stream);
+
+        let msg = tokio::time::timeout(Duration::from_secs(1), transport.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            Message::Text(json) => match ClientMessage::from_json(json).unwrap() {
+                ClientMessage::Close(subscription_id) => {
+                    assert_eq!(subscription_id.as_ref(), &id)
+                }
+                msg => panic!("unexpected message: {msg:?}"),
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
 }
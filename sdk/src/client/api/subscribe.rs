@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 use std::future::IntoFuture;
 
+use async_utility::task;
 use nostr::{Filter, RelayUrl, SubscriptionId};
 
 use super::output::Output;
 use super::req_target::ReqTarget;
 use super::util::build_targets;
-use crate::client::{Client, Error};
+use crate::client::{Client, Error, WeakClient};
 use crate::future::BoxedFuture;
 use crate::relay::SubscribeAutoCloseOptions;
 
@@ -43,6 +44,15 @@ impl<'client, 'url> Subscribe<'client, 'url> {
         self.auto_close = Some(opts);
         self
     }
+
+    /// Return a [`SubscriptionGuard`] instead of the subscription ID
+    ///
+    /// The subscription is closed on all relays when the guard is dropped,
+    /// so it can't be leaked by forgetting to call [`Client::unsubscribe`].
+    #[inline]
+    pub fn guard(self) -> SubscribeGuarded<'client, 'url> {
+        SubscribeGuarded { subscribe: self }
+    }
 }
 
 impl<'client, 'url> IntoFuture for Subscribe<'client, 'url>
@@ -66,3 +76,216 @@ where
         })
     }
 }
+
+/// Subscribe to events, closing the subscription on drop
+///
+/// Check [`Subscribe::guard`] to learn more.
+#[must_use = "Does nothing unless you await!"]
+pub struct SubscribeGuarded<'client, 'url> {
+    subscribe: Subscribe<'client, 'url>,
+}
+
+impl<'client, 'url> IntoFuture for SubscribeGuarded<'client, 'url>
+where
+    'url: 'client,
+{
+    type Output = Result<SubscriptionGuard, Error>;
+    type IntoFuture = BoxedFuture<'client, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let client: WeakClient = self.subscribe.client.weak_clone();
+            let output: Output<SubscriptionId> = self.subscribe.await?;
+
+            Ok(SubscriptionGuard {
+                client,
+                output: Some(output),
+            })
+        })
+    }
+}
+
+/// Subscription guard
+///
+/// Send the `CLOSE` message to all relays when dropped.
+/// Use [`SubscriptionGuard::forget`] to keep the subscription alive.
+#[derive(Debug)]
+pub struct SubscriptionGuard {
+    // Not keep the client alive: if all the clients are dropped, the pool is already shut down.
+    client: WeakClient,
+    // Always `Some`, taken by `forget`
+    output: Option<Output<SubscriptionId>>,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        let Some(output) = self.output.take() else {
+            return;
+        };
+
+        let Some(client) = self.client.upgrade() else {
+            return;
+        };
+
+        let fut = async move {
+            let id: SubscriptionId = output.val;
+            let output: Output<()> = client.pool().unsubscribe(&id).await;
+
+            if !output.failed.is_empty() {
+                tracing::warn!(%id, failed = ?output.failed, "Failed to close guarded subscription.");
+            }
+        };
+
+        // Outside the runtime (i.e., dropped in a sync thread), close the subscription in place:
+        // it only removes the subscription and enqueues the CLOSE message, without waiting for the relays.
+        #[cfg(not(target_arch = "wasm32"))]
+        if tokio::runtime::Handle::try_current().is_err() {
+            futures::executor::block_on(fut);
+            return;
+        }
+
+        task::spawn(fut);
+    }
+}
+
+impl SubscriptionGuard {
+    /// Subscription ID
+    #[inline]
+    pub fn id(&self) -> &SubscriptionId {
+        &self.output().val
+    }
+
+    /// Subscribe output
+    #[inline]
+    pub fn output(&self) -> &Output<SubscriptionId> {
+        // The output is taken only by `forget` and `drop`, that consume the guard
+        self.output.as_ref().expect("output must be set")
+    }
+
+    /// Disarm the guard, keeping the subscription alive
+    ///
+    /// Returns the subscribe output.
+    pub fn forget(mut self) -> Output<SubscriptionId> {
+        self.output.take().expect("output must be set")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nostr::{ClientMessage, JsonUtil, Kind};
+
+    use super::*;
+    use crate::transport::mock::MockWebSocketTransport;
+    use crate::transport::websocket::Message;
+
+    fn into_client_message(msg: Message) -> ClientMessage<'static> {
+        match msg {
+            Message::Text(json) => ClientMessage::from_json(json).unwrap(),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    async fn connected_client(transport: &MockWebSocketTransport) -> Client {
+        let client = Client::builder()
+            .websocket_transport(transport.clone())
+            .build();
+        client.add_relay("ws://mock").and_connect().await.unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_guard_closes_subscription_on_drop() {
+        let transport = MockWebSocketTransport::new();
+        let client = connected_client(&transport).await;
+
+        let guard = client
+            .subscribe(Filter::new().kind(Kind::TextNote))
+            .guard()
+            .await
+            .unwrap();
+        let id: SubscriptionId = guard.id().clone();
+
+        match into_client_message(transport.recv().await.unwrap()) {
+            ClientMessage::Req {
+                subscription_id, ..
+            } => assert_eq!(subscription_id.as_ref(), &id),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+
+        // Drop the guard: the CLOSE must be sent
+        drop(guard);
+
+        let msg = tokio::time::timeout(Duration::from_secs(1), transport.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match into_client_message(msg) {
+            ClientMessage::Close(subscription_id) => assert_eq!(subscription_id.as_ref(), &id),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+
+        let relay = client.relay("ws://mock").await.unwrap().unwrap();
+        assert!(relay.subscription(&id).await.is_none());
+    }
+
+    #[test]
+    fn test_guard_drop_outside_runtime() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let transport = MockWebSocketTransport::new();
+        let (_client, guard) = rt.block_on(async {
+            let client = connected_client(&transport).await;
+            let guard = client
+                .subscribe(Filter::new().kind(Kind::TextNote))
+                .guard()
+                .await
+                .unwrap();
+
+            // Wait for the REQ
+            transport.recv().await.unwrap();
+
+            (client, guard)
+        });
+        let id: SubscriptionId = guard.id().clone();
+
+        // Drop the guard outside the runtime: the CLOSE must be sent anyway
+        drop(guard);
+
+        let msg = rt.block_on(async {
+            tokio::time::timeout(Duration::from_secs(1), transport.recv())
+                .await
+                .unwrap()
+                .unwrap()
+        });
+        match into_client_message(msg) {
+            ClientMessage::Close(subscription_id) => assert_eq!(subscription_id.as_ref(), &id),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_guard_forget() {
+        let transport = MockWebSocketTransport::new();
+        let client = connected_client(&transport).await;
+
+        let guard = client
+            .subscribe(Filter::new().kind(Kind::TextNote))
+            .guard()
+            .await
+            .unwrap();
+
+        // Wait for the REQ
+        transport.recv().await.unwrap();
+
+        // Disarm the guard
+        let output: Output<SubscriptionId> = guard.forget();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(transport.try_recv().is_none());
+
+        let relay = client.relay("ws://mock").await.unwrap().unwrap();
+        assert!(relay.subscription(&output.val).await.is_some());
+    }
+}
@@ -138,6 +138,25 @@ async fn make_sync_targets(
     Ok(f)
 }
 
+async fn make_auto_filters(
+    client: &Client,
+    filter: Filter,
+) -> Result<HashMap<RelayUrl, Filter>, Error> {
+    // Gossip is available: break down filter
+    #[cfg(feature = "gossip")]
+    if let Some(gossip) = client.gossip() {
+        return client.gossip_break_down_filter(gossip, filter).await;
+    }
+
+    // Gossip is not available: get all READ and WRITE relays from pool
+    let urls: HashSet<RelayUrl> = client
+        .pool()
+        .relay_urls_with_any_cap(RelayCapabilities::READ | RelayCapabilities::WRITE)
+        .await;
+
+    construct_filters(urls, filter)
+}
+
 impl<'client, 'url> IntoFuture for SyncEvents<'client, 'url>
 where
     'url: 'client,
@@ -147,48 +166,17 @@ where
 
     fn into_future(self) -> Self::IntoFuture {
         Box::pin(async move {
-            // Build targets
+            // Build filters
+            let filters: HashMap<RelayUrl, Filter> = match self.with {
+                // There are specified relays: use them as targets
+                Some(with) => construct_filters(with, self.filter)?,
+                // There are no specified relays: use gossip, if available, or all relays
+                None => make_auto_filters(self.client, self.filter).await?,
+            };
+
+            // Make targets
             let targets: HashMap<RelayUrl, (Filter, Vec<(EventId, Timestamp)>)> =
-                match (self.client.gossip(), self.with) {
-                    // Gossip is available, and there are no specified relays: use gossip
-                    (Some(gossip), None) => {
-                        // Break down filter
-                        let filters: HashMap<RelayUrl, Filter> = self
-                            .client
-                            .gossip_break_down_filter(gossip, self.filter)
-                            .await?;
-
-                        // Make targets
-                        make_sync_targets(self.client, filters).await?
-                    }
-                    // There are specified relays: use them as targets
-                    (_, Some(with)) => {
-                        // Construct filters
-                        let filters: HashMap<RelayUrl, Filter> =
-                            construct_filters(with, self.filter)?;
-
-                        // Make targets
-                        make_sync_targets(self.client, filters).await?
-                    }
-                    // Gossip is not available, and there are no specified targets: use all relays as targets
-                    (None, None) => {
-                        // Get all READ and WRITE relays from pool
-                        let urls: HashSet<RelayUrl> = self
-                            .client
-                            .pool()
-                            .relay_urls_with_any_cap(
-                                RelayCapabilities::READ | RelayCapabilities::WRITE,
-                            )
-                            .await;
-
-                        // Construct filters
-                        let filters: HashMap<RelayUrl, Filter> =
-                            construct_filters(urls, self.filter)?;
-
-                        // Make targets
-                        make_sync_targets(self.client, filters).await?
-                    }
-                };
+                make_sync_targets(self.client, filters).await?;
 
             Ok(self.client.pool().sync(targets, self.opts).await?)
         })
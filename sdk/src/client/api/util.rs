@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use nostr::types::url;
 use nostr::{Filter, RelayUrl, RelayUrlArg};
@@ -12,27 +12,31 @@ pub(super) async fn build_targets(
     client: &Client,
     target: ReqTarget<'_>,
 ) -> Result<HashMap<RelayUrl, Vec<Filter>>, Error> {
-    // Build targets
-    match client.gossip() {
-        Some(gossip) => match target.into_inner() {
-            // Gossip is configured and we need to break down filters before subscribing
+    // Gossip is configured and we need to break down filters before subscribing
+    #[cfg(feature = "gossip")]
+    if let Some(gossip) = client.gossip() {
+        return match target.into_inner() {
             InnerReqTarget::Auto(filters) => {
                 client.gossip_break_down_filters(gossip, filters).await
             }
             // The request is already manual, skip gossip
             InnerReqTarget::Manual(target) => Ok(convert_filters_arg_vec_to_map(target)?),
-        },
-        // No gossip configured: directly use the target
-        None => Ok(convert_filters_arg_to_targets(client.pool(), target).await?),
+        };
     }
+
+    // No gossip configured: directly use the target
+    Ok(convert_filters_arg_to_targets(client.pool(), target).await?)
 }
 
 async fn make_targets_from_filter_list(
     pool: &RelayPool,
     filters: Vec<Filter>,
 ) -> HashMap<RelayUrl, Vec<Filter>> {
-    let urls: HashSet<RelayUrl> = pool.read_relay_urls().await;
-    urls.into_iter().map(|u| (u, filters.clone())).collect()
+    pool.read_relay_urls()
+        .await
+        .into_iter()
+        .map(|u| (u, filters.clone()))
+        .collect()
 }
 
 async fn convert_filters_arg_to_targets(
@@ -55,3 +59,69 @@ pub(super) fn convert_filters_arg_vec_to_map(
     }
     Ok(map)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nostr::Kind;
+    use nostr_relay_builder::prelude::*;
+
+    use super::*;
+    use crate::relay::RateLimitClass;
+
+    #[tokio::test]
+    async fn test_skip_req_rate_limited_relays() {
+        let open = LocalRelay::builder().build();
+        open.run().await.unwrap();
+        let open_url = open.url().await;
+
+        // Rate-limits every REQ
+        let limited = LocalRelay::builder()
+            .rate_limit(RateLimit {
+                max_reqs: 0,
+                notes_per_minute: 60,
+            })
+            .build();
+        limited.run().await.unwrap();
+        let limited_url = limited.url().await;
+
+        let client = Client::default();
+        client.add_relay(&open_url).await.unwrap();
+        client.add_relay(&limited_url).await.unwrap();
+        client.connect().and_wait(Duration::from_secs(2)).await;
+
+        let filter = Filter::new().kind(Kind::TextNote);
+
+        let targets = build_targets(&client, ReqTarget::from(filter.clone()))
+            .await
+            .unwrap();
+        assert_eq!(targets.len(), 2);
+
+        // The limited relay closes the REQ
+        client.subscribe(filter.clone()).await.unwrap();
+        let relay = client.relay(&limited_url).await.unwrap().unwrap();
+        for _ in 0..20 {
+            if relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Req)
+                .is_some()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(
+            relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Req)
+                .is_some()
+        );
+
+        // Skipped until the limit expires
+        let targets = build_targets(&client, ReqTarget::from(filter))
+            .await
+            .unwrap();
+        assert_eq!(targets.keys().collect::<Vec<_>>(), vec![&open_url]);
+    }
+}
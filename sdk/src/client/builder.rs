@@ -9,22 +9,27 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use nostr_database::{IntoNostrDatabase, NostrDatabase};
+#[cfg(feature = "gossip")]
 use nostr_gossip::{GossipAllowedRelays, IntoNostrGossip, NostrGossip};
 
 use crate::authenticator::Authenticator;
 use crate::client::Client;
 use crate::events_tracker::MemoryEventsTracker;
+#[cfg(not(feature = "gossip"))]
+use crate::feature::RequiresGossipFeature;
 use crate::monitor::Monitor;
 use crate::policy::AdmitPolicy;
-use crate::prelude::RelayLimits;
+use crate::prelude::{AuthPolicy, RelayLimits};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::proxy::Proxy;
+use crate::transport::headers::UpgradeHeadersResolver;
 use crate::transport::websocket::{
     DefaultWebsocketTransport, IntoWebSocketTransport, WebSocketTransport,
 };
 
 const DEFAULT_NOTIFICATION_CHANNEL_SIZE: NonZeroUsize = NonZeroUsize::new(4096).unwrap();
 
+#[cfg(feature = "gossip")]
 /// Max number of relays to use for gossip
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GossipRelayLimits {
@@ -40,6 +45,7 @@ pub struct GossipRelayLimits {
     pub nip17_relays: u8,
 }
 
+#[cfg(feature = "gossip")]
 impl Default for GossipRelayLimits {
     fn default() -> Self {
         Self {
@@ -52,6 +58,7 @@ impl Default for GossipRelayLimits {
     }
 }
 
+#[cfg(feature = "gossip")]
 /// Background gossip refresh configuration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GossipBackgroundRefresh {
@@ -61,6 +68,7 @@ pub struct GossipBackgroundRefresh {
     pub max_public_keys_per_round: NonZeroUsize,
 }
 
+#[cfg(feature = "gossip")]
 impl Default for GossipBackgroundRefresh {
     fn default() -> Self {
         Self {
@@ -70,6 +78,7 @@ impl Default for GossipBackgroundRefresh {
     }
 }
 
+#[cfg(feature = "gossip")]
 impl GossipBackgroundRefresh {
     /// Set refresh interval. (default: 5 min)
     pub fn interval(mut self, interval: Duration) -> Self {
@@ -84,16 +93,36 @@ impl GossipBackgroundRefresh {
     }
 }
 
+#[cfg(feature = "gossip")]
 /// Gossip config
 #[derive(Debug, Clone)]
 pub struct GossipConfig {
+    /// Use gossip to select the relays (default: true)
+    ///
+    /// If disabled, the client behaves as if no gossip database has been set.
+    pub enabled: bool,
     /// Max number of gossip relays to use
     pub limits: GossipRelayLimits,
+    /// Max number of relays, not already in the pool, added for a single operation (default: unlimited)
+    ///
+    /// Applied when publishing an event or breaking down the filters of a query.
+    /// The relays are ranked by the number of public keys they cover,
+    /// and the limit is exceeded when needed to keep at least one relay for every public key.
+    pub max_new_relays: Option<NonZeroUsize>,
+    /// Put the relays added by gossip to sleep when idle (default: enabled, 5 min)
+    ///
+    /// Prevents the connections to the gossip relays from growing without bound.
+    /// Doesn't apply to the relays already in the pool.
+    pub sleep_when_idle: SleepWhenIdle,
     /// Allowed relays during gossip selection
     pub allowed: GossipAllowedRelays,
     /// Timeout for checking if negentropy is supported, when updating gossip data
+    ///
+    /// Unused without the `negentropy` feature.
     pub sync_initial_timeout: Duration,
     /// Idle timeout when syncing gossip data
+    ///
+    /// Unused without the `negentropy` feature.
     pub sync_idle_timeout: Duration,
     /// Fetch timeout when updating gossip data (fallback of the sync)
     pub fetch_timeout: Duration,
@@ -103,10 +132,16 @@ pub struct GossipConfig {
     pub background_refresh: Option<GossipBackgroundRefresh>,
 }
 
+#[cfg(feature = "gossip")]
 impl Default for GossipConfig {
     fn default() -> Self {
         Self {
+            enabled: true,
             limits: GossipRelayLimits::default(),
+            max_new_relays: None,
+            sleep_when_idle: SleepWhenIdle::Enabled {
+                timeout: Duration::from_secs(5 * 60),
+            },
             allowed: GossipAllowedRelays::default(),
             sync_initial_timeout: Duration::from_secs(10),
             sync_idle_timeout: Duration::from_secs(10),
@@ -117,13 +152,32 @@ impl Default for GossipConfig {
     }
 }
 
+#[cfg(feature = "gossip")]
 impl GossipConfig {
+    /// Enable or disable gossip (default: true)
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     /// Max number of gossip relays to use
     pub fn limits(mut self, limits: GossipRelayLimits) -> Self {
         self.limits = limits;
         self
     }
 
+    /// Max number of relays, not already in the pool, added for a single operation (default: unlimited)
+    pub fn max_new_relays(mut self, max: NonZeroUsize) -> Self {
+        self.max_new_relays = Some(max);
+        self
+    }
+
+    /// Put the relays added by gossip to sleep when idle (default: enabled, 5 min)
+    pub fn sleep_when_idle(mut self, config: SleepWhenIdle) -> Self {
+        self.sleep_when_idle = config;
+        self
+    }
+
     /// Allowed relays during gossip selection
     pub fn allowed(mut self, allowed: GossipAllowedRelays) -> Self {
         self.allowed = allowed;
@@ -181,11 +235,15 @@ pub struct ClientBuilder {
     pub admit_policy: Option<Arc<dyn AdmitPolicy>>,
     /// Authenticator
     pub authenticator: Option<Arc<dyn Authenticator>>,
+    /// WebSocket upgrade headers resolver
+    pub upgrade_headers: Option<Arc<dyn UpgradeHeadersResolver>>,
     /// Database
     pub database: Arc<dyn NostrDatabase>,
     /// Gossip
+    #[cfg(feature = "gossip")]
     pub gossip: Option<Arc<dyn NostrGossip>>,
     /// Gossip config
+    #[cfg(feature = "gossip")]
     pub gossip_config: GossipConfig,
     /// Relay monitor
     pub monitor: Option<Monitor>,
@@ -210,16 +268,29 @@ pub struct ClientBuilder {
     pub verify_subscriptions: bool,
     /// Ban relay on mismatch
     pub ban_relay_on_mismatch: bool,
+    /// NIP-42 AUTH policy
+    pub auth_policy: AuthPolicy,
+    /// Sticky failover of the read subscriptions
+    pub failover: bool,
+    /// Preserve the original JSON of the received events
+    pub preserve_raw_events: bool,
+    /// Max number of messages sent per second, to all the relays combined
+    pub max_messages_per_sec: Option<u32>,
+    /// Max number of bytes sent per second, to all the relays combined
+    pub max_bytes_per_sec: Option<u64>,
 }
 
 impl Default for ClientBuilder {
     fn default() -> Self {
         Self {
-            websocket_transport: Arc::new(DefaultWebsocketTransport),
+            websocket_transport: Arc::new(DefaultWebsocketTransport::default()),
             admit_policy: None,
             authenticator: None,
+            upgrade_headers: None,
             database: Arc::new(MemoryEventsTracker::default()),
+            #[cfg(feature = "gossip")]
             gossip: None,
+            #[cfg(feature = "gossip")]
             gossip_config: GossipConfig::default(),
             monitor: None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -231,6 +302,11 @@ impl Default for ClientBuilder {
             sleep_when_idle: SleepWhenIdle::default(),
             verify_subscriptions: false,
             ban_relay_on_mismatch: false,
+            auth_policy: AuthPolicy::default(),
+            failover: false,
+            preserve_raw_events: false,
+            max_messages_per_sec: None,
+            max_bytes_per_sec: None,
             notification_channel_size: DEFAULT_NOTIFICATION_CHANNEL_SIZE,
         }
     }
@@ -295,6 +371,42 @@ impl ClientBuilder {
         self
     }
 
+    /// Set a WebSocket upgrade headers resolver
+    ///
+    /// The resolver is called at every connection attempt and the returned headers
+    /// (i.e., a bearer token or a cookie) are attached to the WebSocket handshake of that relay.
+    /// Useful to access relays behind reverse proxies that check the HTTP headers.
+    ///
+    /// The headers are not supported in the browser and by the custom transports
+    /// that don't implement [`WebSocketTransport::connect_with_headers`]:
+    /// in that case, the connection to the relays with custom headers fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use nostr_sdk::prelude::*;
+    /// use nostr_sdk::transport::headers::UpgradeHeaders;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let url = RelayUrl::parse("wss://private.example.com")?;
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert(url, UpgradeHeaders::new().bearer("token"));
+    ///
+    /// let client: Client = Client::builder().upgrade_headers(headers).build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn upgrade_headers<T>(mut self, resolver: T) -> Self
+    where
+        T: UpgradeHeadersResolver + 'static,
+    {
+        self.upgrade_headers = Some(Arc::new(resolver));
+        self
+    }
+
     /// Set database
     #[inline]
     pub fn database<D>(mut self, database: D) -> Self
@@ -307,6 +419,7 @@ impl ClientBuilder {
 
     /// Set a gossip database
     #[inline]
+    #[cfg(feature = "gossip")]
     pub fn gossip<T>(mut self, gossip: T) -> Self
     where
         T: IntoNostrGossip,
@@ -317,11 +430,32 @@ impl ClientBuilder {
 
     /// Set gossip config
     #[inline]
+    #[cfg(feature = "gossip")]
     pub fn gossip_config(mut self, config: GossipConfig) -> Self {
         self.gossip_config = config;
         self
     }
 
+    /// Placeholder of [`ClientBuilder::gossip`], that requires the `gossip` feature
+    #[doc(hidden)]
+    #[cfg(not(feature = "gossip"))]
+    pub fn gossip<T>(self, _gossip: T) -> T::Disabled
+    where
+        T: RequiresGossipFeature,
+    {
+        unreachable!("the `gossip` feature is disabled")
+    }
+
+    /// Placeholder of [`ClientBuilder::gossip_config`], that requires the `gossip` feature
+    #[doc(hidden)]
+    #[cfg(not(feature = "gossip"))]
+    pub fn gossip_config<T>(self, _config: T) -> T::Disabled
+    where
+        T: RequiresGossipFeature,
+    {
+        unreachable!("the `gossip` feature is disabled")
+    }
+
     /// Set monitor
     #[inline]
     pub fn monitor(mut self, monitor: Monitor) -> Self {
@@ -390,6 +524,59 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the NIP-42 AUTH policy of the relays (default: [`AuthPolicy::Automatic`])
+    ///
+    /// Check [`RelayOptions::auth_policy`](crate::relay::RelayOptions::auth_policy) to learn more.
+    #[inline]
+    pub fn auth_policy(mut self, policy: AuthPolicy) -> Self {
+        self.auth_policy = policy;
+        self
+    }
+
+    /// Sticky failover of the read subscriptions (default: false)
+    ///
+    /// When a relay disconnects, its subscriptions are moved to another connected read relay,
+    /// resuming from the last seen event (`since`).
+    /// The subscriptions stay on the replacement relay, also after the reconnection of the original one.
+    ///
+    /// The failover relies on the relay [`Monitor`]: if not set, a default one is created.
+    pub fn failover(mut self, enable: bool) -> Self {
+        self.failover = enable;
+        self
+    }
+
+    /// Preserve the original JSON of the received events (default: false)
+    ///
+    /// When enabled, the original JSON of the events received from the relays is kept in memory
+    /// and sent byte-identical when the events are re-broadcasted,
+    /// instead of re-serializing them.
+    /// The locally-constructed events are always serialized.
+    ///
+    /// Only the most recent events are kept, to bound the memory usage.
+    pub fn preserve_raw_events(mut self, enable: bool) -> Self {
+        self.preserve_raw_events = enable;
+        self
+    }
+
+    /// Max number of messages sent per second, to all the relays combined (default: None)
+    ///
+    /// Applied in addition to the per-relay limits (see [`RelayOptions::max_messages_per_sec`](crate::relay::RelayOptions::max_messages_per_sec)).
+    /// When the limit is reached, the messages are queued and sent as soon as allowed, instead of failing.
+    #[inline]
+    pub fn max_messages_per_sec(mut self, max: u32) -> Self {
+        self.max_messages_per_sec = Some(max);
+        self
+    }
+
+    /// Max number of bytes sent per second, to all the relays combined (default: None)
+    ///
+    /// Applied in addition to the per-relay limits (see [`RelayOptions::max_bytes_per_sec`](crate::relay::RelayOptions::max_bytes_per_sec)).
+    #[inline]
+    pub fn max_bytes_per_sec(mut self, max: u64) -> Self {
+        self.max_bytes_per_sec = Some(max);
+        self
+    }
+
     /// Notification channel size (default: 4096)
     #[inline]
     pub fn notification_channel_size(mut self, size: NonZeroUsize) -> Self {
@@ -7,8 +7,10 @@ use std::fmt;
 use nostr::prelude::*;
 use nostr::serde_json;
 use nostr_database::prelude::*;
+#[cfg(feature = "gossip")]
 use nostr_gossip::error::GossipError;
 
+use crate::client::PublishOutput;
 use crate::{pool, relay};
 
 /// Client error
@@ -25,6 +27,7 @@ pub enum Error {
     /// Signer error
     Signer(SignerError),
     /// Gossip error
+    #[cfg(feature = "gossip")]
     Gossip(GossipError),
     /// [`EventBuilder`] error
     EventBuilder(event::builder::Error),
@@ -38,6 +41,13 @@ pub enum Error {
     GossipFiltersEmpty,
     /// Private message (NIP17) relays not found
     PrivateMsgRelaysNotFound,
+    /// The event has been accepted by less relays than required
+    PublishFailed {
+        /// Required number of relays
+        required: usize,
+        /// Publish output, with the reasons of the failures
+        output: Box<PublishOutput>,
+    },
 }
 
 impl std::error::Error for Error {}
@@ -50,6 +60,7 @@ impl fmt::Display for Error {
             Self::RelayUrl(e) => e.fmt(f),
             Self::Database(e) => e.fmt(f),
             Self::Signer(e) => e.fmt(f),
+            #[cfg(feature = "gossip")]
             Self::Gossip(e) => e.fmt(f),
             Self::EventBuilder(e) => e.fmt(f),
             Self::Json(e) => e.fmt(f),
@@ -59,6 +70,11 @@ impl fmt::Display for Error {
                 f.write_str("gossip broken down filters are empty")
             }
             Self::PrivateMsgRelaysNotFound => f.write_str("Private message relays not found. The user is not ready to receive private messages."),
+            Self::PublishFailed { required, output } => write!(
+                f,
+                "event accepted by {} relays, {required} required",
+                output.success.len()
+            ),
         }
     }
 }
@@ -93,6 +109,7 @@ impl From<SignerError> for Error {
     }
 }
 
+#[cfg(feature = "gossip")]
 impl From<GossipError> for Error {
     fn from(e: GossipError) -> Self {
         Self::Gossip(e)
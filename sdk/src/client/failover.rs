@@ -0,0 +1,207 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Sticky failover of the read subscriptions
+
+use std::collections::HashMap;
+
+use async_utility::task;
+use nostr::{Filter, RelayMessage, RelayUrl, SubscriptionId, Timestamp};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{Client, ClientNotification, WeakClient};
+use crate::monitor::MonitorNotification;
+use crate::relay::{Relay, RelayStatus};
+
+/// Last seen event timestamp, per relay and subscription
+type LastSeen = HashMap<RelayUrl, HashMap<SubscriptionId, Timestamp>>;
+
+impl Client {
+    pub(super) fn spawn_failover_handler(&self) {
+        if !self.config().failover {
+            return;
+        }
+
+        // The monitor is always set when the failover is enabled
+        let Some(monitor) = self.monitor() else {
+            return;
+        };
+
+        let mut statuses = monitor.subscribe();
+        let mut notifications = self.pool().notifications();
+
+        // Make a weak reference to the client.
+        let weak: WeakClient = self.weak_clone();
+
+        task::spawn(async move {
+            tracing::debug!("Failover handler started.");
+
+            let mut last_seen: LastSeen = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    notification = notifications.recv() => match notification {
+                        Ok(ClientNotification::Message { relay_url, message }) => {
+                            if let RelayMessage::Event { subscription_id, event } = *message {
+                                let timestamp: &mut Timestamp = last_seen
+                                    .entry(relay_url)
+                                    .or_default()
+                                    .entry(subscription_id.into_owned())
+                                    .or_insert(event.created_at);
+
+                                if event.created_at > *timestamp {
+                                    *timestamp = event.created_at;
+                                }
+                            }
+                        }
+                        Ok(ClientNotification::Shutdown) => break,
+                        Ok(..) => {}
+                        Err(RecvError::Lagged(..)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                    notification = statuses.recv() => match notification {
+                        Ok(MonitorNotification::StatusChanged {
+                            relay_url,
+                            status: RelayStatus::Disconnected,
+                        }) => {
+                            // Check if we can upgrade the client.
+                            let Some(client) = weak.upgrade() else {
+                                break;
+                            };
+
+                            let last_seen: HashMap<SubscriptionId, Timestamp> =
+                                last_seen.remove(&relay_url).unwrap_or_default();
+
+                            client.failover(&relay_url, last_seen).await;
+                        }
+                        Ok(..) => {}
+                        Err(RecvError::Lagged(..)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+
+            tracing::debug!("Failover handler exited.");
+        });
+    }
+
+    /// Move the subscriptions of a disconnected relay to another connected read relay.
+    async fn failover(&self, url: &RelayUrl, last_seen: HashMap<SubscriptionId, Timestamp>) {
+        let relays: HashMap<RelayUrl, Relay> = self.pool().all_relays().await;
+
+        let Some(disconnected) = relays.get(url) else {
+            return;
+        };
+
+        for (id, filters) in disconnected.subscriptions().await.into_iter() {
+            let Some(replacement) = find_replacement(&relays, url, &id).await else {
+                tracing::warn!(url = %url, id = %id, "No relay available for failover.");
+                continue;
+            };
+
+            // Resume from the last seen event
+            let since: Option<Timestamp> = last_seen.get(&id).copied();
+            let filters: Vec<Filter> = filters
+                .into_iter()
+                .map(|filter| match since {
+                    Some(since) => filter.since(since),
+                    None => filter,
+                })
+                .collect();
+
+            match replacement.subscribe(filters).with_id(id.clone()).await {
+                Ok(..) => tracing::info!(
+                    from = %url,
+                    to = %replacement.url(),
+                    id = %id,
+                    "Subscription moved to failover relay."
+                ),
+                Err(e) => tracing::error!(
+                    from = %url,
+                    to = %replacement.url(),
+                    id = %id,
+                    error = %e,
+                    "Failed to move subscription to failover relay."
+                ),
+            }
+        }
+    }
+}
+
+/// Find a connected read relay that isn't already serving the subscription
+async fn find_replacement<'a>(
+    relays: &'a HashMap<RelayUrl, Relay>,
+    url: &RelayUrl,
+    id: &SubscriptionId,
+) -> Option<&'a Relay> {
+    for relay in relays.values() {
+        if relay.url() == url
+            || relay.status() != RelayStatus::Connected
+            || !relay.capabilities().load().can_read()
+        {
+            continue;
+        }
+
+        if relay.subscription(id).await.is_none() {
+            return Some(relay);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nostr::prelude::*;
+    use nostr_relay_builder::MockRelay;
+
+    use super::*;
+    use crate::client::ReqTarget;
+
+    #[tokio::test]
+    async fn test_failover_moves_subscription() {
+        let mock1 = MockRelay::run().await.unwrap();
+        let url1 = mock1.url().await;
+        let mock2 = MockRelay::run().await.unwrap();
+        let url2 = mock2.url().await;
+
+        // Store an event in the first relay
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("test")
+            .custom_created_at(Timestamp::from_secs(1_700_000_000))
+            .sign(&keys)
+            .unwrap();
+        mock1.add_event(event.clone()).await.unwrap();
+
+        let client = Client::builder().failover(true).build();
+        client.add_relay(&url1).and_connect().await.unwrap();
+        client.add_relay(&url2).and_connect().await.unwrap();
+        client.connect().and_wait(Duration::from_secs(2)).await;
+
+        // Subscribe only to the first relay
+        let filter = Filter::new().kind(Kind::TextNote);
+        let output = client
+            .subscribe(ReqTarget::single(&url1, [filter.clone()]))
+            .await
+            .unwrap();
+        let id: SubscriptionId = output.val;
+
+        let relay2 = client.relay(&url2).await.unwrap().unwrap();
+        assert!(relay2.subscription(&id).await.is_none());
+
+        // Wait for the event
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        // Shutdown the first relay
+        mock1.shutdown();
+
+        // Wait for the failover
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let filters = relay2.subscription(&id).await.unwrap();
+        assert_eq!(filters, vec![filter.since(event.created_at)]);
+    }
+}
@@ -60,7 +60,7 @@ impl GossipRelayResolver {
         Ok(urls)
     }
 
-    async fn map_relays<'a, I>(
+    pub(in crate::client) async fn map_relays<'a, I>(
         &self,
         public_keys: I,
         selection: BestRelaySelection,
@@ -1,4 +1,5 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,10 +9,24 @@ use nostr_database::prelude::*;
 use nostr_gossip::prelude::*;
 
 use super::{
-    BrokenDownFilters, Gossip, GossipFilterPattern, GossipSemaphorePermit, find_filter_pattern,
+    BrokenDownFilters, Gossip, GossipFilterPattern, GossipSemaphorePermit, P_TAG,
+    find_filter_pattern,
 };
-use crate::client::{Client, Error, Output, SyncSummary};
-use crate::relay::{RelayCapabilities, ReqExitPolicy, SyncDirection, SyncOptions};
+use crate::client::{Client, Error, GossipConfig, SleepWhenIdle};
+#[cfg(feature = "negentropy")]
+use crate::client::{Output, SyncSummary};
+use crate::pool::StreamOptions;
+use crate::relay::{RelayCapabilities, ReqExitPolicy};
+#[cfg(feature = "negentropy")]
+use crate::relay::{SyncDirection, SyncOptions};
+
+/// Outcome of the first step of the gossip sync
+struct GossipSyncOutcome {
+    /// Relays that must be queried with a REQ, with the failure reason
+    failed: HashMap<RelayUrl, String>,
+    /// Events that are already up to date after the first step
+    skip_ids: HashSet<EventId>,
+}
 
 impl Client {
     async fn compute_gossip_update_candidates(
@@ -99,7 +114,8 @@ impl Client {
             return Ok(());
         }
 
-        let (output, stored_events) = self
+        #[cfg(feature = "negentropy")]
+        let (outcome, stored_events) = self
             .sync_gossip_public_keys_with_negentropy(
                 sync_id,
                 gossip.store(),
@@ -108,16 +124,26 @@ impl Client {
             )
             .await?;
 
+        #[cfg(not(feature = "negentropy"))]
+        let (outcome, stored_events) = self
+            .load_stored_gossip_lists(
+                sync_id,
+                gossip.store(),
+                gossip_kinds,
+                outdated_public_keys.clone(),
+            )
+            .await?;
+
         let mut missing_public_keys: BTreeSet<PublicKey> = outdated_public_keys;
 
         for event in stored_events.iter() {
             missing_public_keys.remove(&event.pubkey);
         }
 
-        if !output.failed.is_empty() {
+        if !outcome.failed.is_empty() {
             tracing::debug!(
                 sync_id,
-                relays = ?output.failed,
+                relays = ?outcome.failed,
                 "Gossip sync failed for some relays."
             );
 
@@ -125,7 +151,7 @@ impl Client {
                 sync_id,
                 gossip.store(),
                 gossip_kinds,
-                &output,
+                &outcome,
                 &stored_events,
                 &mut missing_public_keys,
             )
@@ -136,7 +162,7 @@ impl Client {
                     sync_id,
                     gossip.store(),
                     gossip_kinds,
-                    &output,
+                    &outcome,
                     missing_public_keys,
                 )
                 .await?;
@@ -151,13 +177,14 @@ impl Client {
         Ok(())
     }
 
+    #[cfg(feature = "negentropy")]
     async fn sync_gossip_public_keys_with_negentropy(
         &self,
         sync_id: u64,
         gossip: &Arc<dyn NostrGossip>,
         gossip_kinds: &[GossipListKind],
         outdated_public_keys: BTreeSet<PublicKey>,
-    ) -> Result<(Output<SyncSummary>, Events), Error> {
+    ) -> Result<(GossipSyncOutcome, Events), Error> {
         let mut kinds: Vec<Kind> = Vec::with_capacity(gossip_kinds.len());
 
         for gossip_kind in gossip_kinds {
@@ -199,7 +226,68 @@ impl Client {
             gossip.process(event, None).await?;
         }
 
-        Ok((output, stored_events))
+        let received: HashSet<EventId> = output.received.keys().copied().collect();
+        let skip_ids: HashSet<EventId> = output.local.union(&received).copied().collect();
+
+        let outcome: GossipSyncOutcome = GossipSyncOutcome {
+            failed: output.failed,
+            skip_ids,
+        };
+
+        Ok((outcome, stored_events))
+    }
+
+    /// Load the stored gossip lists, without reconciling them with the relays.
+    ///
+    /// Without negentropy, all the relays are reported as failed, so the lists are fetched with REQs.
+    #[cfg(not(feature = "negentropy"))]
+    async fn load_stored_gossip_lists(
+        &self,
+        sync_id: u64,
+        gossip: &Arc<dyn NostrGossip>,
+        gossip_kinds: &[GossipListKind],
+        outdated_public_keys: BTreeSet<PublicKey>,
+    ) -> Result<(GossipSyncOutcome, Events), Error> {
+        let mut kinds: Vec<Kind> = Vec::with_capacity(gossip_kinds.len());
+
+        for gossip_kind in gossip_kinds {
+            kinds.push(gossip_kind.to_event_kind());
+        }
+
+        tracing::debug!(
+            sync_id,
+            public_keys = outdated_public_keys.len(),
+            "Loading stored gossip data."
+        );
+
+        let filter: Filter = Filter::default().authors(outdated_public_keys).kinds(kinds);
+
+        let urls: HashSet<RelayUrl> = self
+            .pool()
+            .relay_urls_with_any_cap(RelayCapabilities::DISCOVERY | RelayCapabilities::READ)
+            .await;
+
+        let stored_events: Events = self.database().query(filter).await?;
+
+        for event in stored_events.iter() {
+            for gossip_kind in gossip_kinds {
+                gossip
+                    .update_fetch_attempt(&event.pubkey, *gossip_kind)
+                    .await?;
+            }
+
+            gossip.process(event, None).await?;
+        }
+
+        let outcome: GossipSyncOutcome = GossipSyncOutcome {
+            failed: urls
+                .into_iter()
+                .map(|url| (url, String::from("negentropy feature disabled")))
+                .collect(),
+            skip_ids: HashSet::new(),
+        };
+
+        Ok((outcome, stored_events))
     }
 
     async fn fetch_newer_gossip_lists_from_failed_relays(
@@ -207,19 +295,16 @@ impl Client {
         sync_id: u64,
         gossip: &Arc<dyn NostrGossip>,
         gossip_kinds: &[GossipListKind],
-        output: &Output<SyncSummary>,
+        outcome: &GossipSyncOutcome,
         stored_events: &Events,
         missing_public_keys: &mut BTreeSet<PublicKey>,
     ) -> Result<(), Error> {
         let mut filters: Vec<Filter> = Vec::new();
 
-        let received: HashSet<EventId> = output.received.keys().copied().collect();
-        let skip_ids: HashSet<EventId> = output.local.union(&received).copied().collect();
-
         for event in stored_events.iter() {
             missing_public_keys.remove(&event.pubkey);
 
-            if skip_ids.contains(&event.id) {
+            if outcome.skip_ids.contains(&event.id) {
                 continue;
             }
 
@@ -247,9 +332,9 @@ impl Client {
         );
 
         for chunk in filters.chunks(self.config().gossip_config.fetch_chunks) {
-            let mut targets = HashMap::with_capacity(output.failed.len());
+            let mut targets = HashMap::with_capacity(outcome.failed.len());
 
-            for url in output.failed.keys() {
+            for url in outcome.failed.keys() {
                 targets.insert(url.clone(), chunk.to_vec());
             }
 
@@ -260,6 +345,7 @@ impl Client {
                     None,
                     Some(self.config().gossip_config.fetch_timeout),
                     ReqExitPolicy::ExitOnEOSE,
+                    StreamOptions::default(),
                 )
                 .await?;
 
@@ -287,7 +373,7 @@ impl Client {
         sync_id: u64,
         gossip: &Arc<dyn NostrGossip>,
         gossip_kinds: &[GossipListKind],
-        output: &Output<SyncSummary>,
+        outcome: &GossipSyncOutcome,
         missing_public_keys: BTreeSet<PublicKey>,
     ) -> Result<(), Error> {
         let mut kinds: Vec<Kind> = Vec::with_capacity(gossip_kinds.len());
@@ -306,9 +392,9 @@ impl Client {
             .authors(missing_public_keys.clone())
             .kinds(kinds);
 
-        let mut targets = HashMap::with_capacity(output.failed.len());
+        let mut targets = HashMap::with_capacity(outcome.failed.len());
 
-        for url in output.failed.keys() {
+        for url in outcome.failed.keys() {
             targets.insert(url.clone(), vec![missing_filter.clone()]);
         }
 
@@ -319,6 +405,7 @@ impl Client {
                 None,
                 Some(self.config().gossip_config.fetch_timeout),
                 ReqExitPolicy::ExitOnEOSE,
+                StreamOptions::default(),
             )
             .await?;
 
@@ -381,8 +468,62 @@ impl Client {
             .await
     }
 
-    /// Break down a filter for gossip and discovery relays.
-    pub(in crate::client) async fn gossip_break_down_filter(
+    /// Add the gossip relays to the pool and connect them
+    ///
+    /// Takes the relays with the public keys they cover.
+    /// The relays already in the pool are always kept.
+    /// The new relays are ranked by coverage: the ones needed to keep at least one relay for every public key
+    /// are always added, while the others are added until [`GossipConfig::max_new_relays`] is reached.
+    ///
+    /// Returns the kept relays.
+    pub(in crate::client) async fn add_gossip_relays<I>(
+        &self,
+        relays: I,
+    ) -> Result<HashSet<RelayUrl>, Error>
+    where
+        I: IntoIterator<Item = (RelayUrl, BTreeSet<PublicKey>)>,
+    {
+        let config: &GossipConfig = &self.config().gossip_config;
+
+        let mut existing: HashSet<RelayUrl> = HashSet::new();
+        let mut covered: BTreeSet<PublicKey> = BTreeSet::new();
+        let mut candidates: Vec<(RelayUrl, BTreeSet<PublicKey>)> = Vec::new();
+
+        for (url, public_keys) in relays.into_iter() {
+            if self.pool().relay(&url).await.is_some() {
+                covered.extend(public_keys);
+                existing.insert(url);
+            } else {
+                candidates.push((url, public_keys));
+            }
+        }
+
+        let new_relays: Vec<RelayUrl> =
+            select_new_gossip_relays(candidates, covered, config.max_new_relays);
+
+        let mut kept: HashSet<RelayUrl> = HashSet::with_capacity(existing.len() + new_relays.len());
+
+        for url in existing.into_iter().chain(new_relays) {
+            let mut add = self
+                .add_relay(&url)
+                .capabilities(RelayCapabilities::GOSSIP)
+                .and_connect();
+
+            // Options are applied only to the new relays
+            if let SleepWhenIdle::Enabled { timeout } = config.sleep_when_idle {
+                add = add.sleep_when_idle(true).idle_timeout(timeout);
+            }
+
+            add.await?;
+
+            kept.insert(url);
+        }
+
+        Ok(kept)
+    }
+
+    /// Resolve the relays of a filter, without adding them to the pool.
+    async fn gossip_resolve_filter(
         &self,
         gossip: &Gossip,
         filter: Filter,
@@ -427,13 +568,6 @@ impl Client {
             }
         };
 
-        for url in filters.keys() {
-            self.add_relay(url)
-                .capabilities(RelayCapabilities::GOSSIP)
-                .and_connect()
-                .await?;
-        }
-
         if filters.is_empty() {
             return Err(Error::GossipFiltersEmpty);
         }
@@ -441,6 +575,25 @@ impl Client {
         Ok(filters)
     }
 
+    /// Break down a filter for gossip and discovery relays.
+    pub(in crate::client) async fn gossip_break_down_filter(
+        &self,
+        gossip: &Gossip,
+        filter: Filter,
+    ) -> Result<HashMap<RelayUrl, Filter>, Error> {
+        let mut filters: HashMap<RelayUrl, Filter> =
+            self.gossip_resolve_filter(gossip, filter).await?;
+
+        let relays: Vec<(RelayUrl, BTreeSet<PublicKey>)> = filters
+            .iter()
+            .map(|(url, filter)| (url.clone(), covered_public_keys(filter)))
+            .collect();
+        let added: HashSet<RelayUrl> = self.add_gossip_relays(relays).await?;
+        filters.retain(|url, _| added.contains(url));
+
+        Ok(filters)
+    }
+
     /// Break down multiple filters for gossip and discovery relays.
     pub(in crate::client) async fn gossip_break_down_filters<F>(
         &self,
@@ -455,13 +608,25 @@ impl Client {
         let mut output: HashMap<RelayUrl, HashSet<Filter>> = HashMap::new();
 
         for filter in filters {
-            let f = self.gossip_break_down_filter(gossip, filter).await?;
+            let f = self.gossip_resolve_filter(gossip, filter).await?;
 
             for (url, filter) in f {
                 output.entry(url).or_default().insert(filter);
             }
         }
 
+        // Add the relays once, so the limit applies to the whole operation
+        let relays: Vec<(RelayUrl, BTreeSet<PublicKey>)> = output
+            .iter()
+            .map(|(url, filters)| {
+                let public_keys: BTreeSet<PublicKey> =
+                    filters.iter().flat_map(covered_public_keys).collect();
+                (url.clone(), public_keys)
+            })
+            .collect();
+        let added: HashSet<RelayUrl> = self.add_gossip_relays(relays).await?;
+        output.retain(|url, _| added.contains(url));
+
         Ok(output
             .into_iter()
             .map(|(k, v)| (k, v.into_iter().collect()))
@@ -469,8 +634,74 @@ impl Client {
     }
 }
 
+/// Public keys (authors and `p` tags) covered by a broken down filter
+fn covered_public_keys(filter: &Filter) -> BTreeSet<PublicKey> {
+    let mut public_keys: BTreeSet<PublicKey> = filter.authors.clone().unwrap_or_default();
+
+    if let Some(p_tags) = filter.generic_tags.get(&P_TAG) {
+        public_keys.extend(p_tags.iter().filter_map(|p| PublicKey::from_hex(p).ok()));
+    }
+
+    public_keys
+}
+
+/// Select the new gossip relays to add, ranked by coverage
+///
+/// The relays covering the public keys not in `covered` are selected first (greedy set cover),
+/// without any limit; the remaining ones are added until `max` new relays are selected.
+/// Ties are broken by URL, to keep the same relays across the operations.
+fn select_new_gossip_relays(
+    mut candidates: Vec<(RelayUrl, BTreeSet<PublicKey>)>,
+    mut covered: BTreeSet<PublicKey>,
+    max: Option<NonZeroUsize>,
+) -> Vec<RelayUrl> {
+    // Rank by coverage
+    candidates.sort_by(|(a_url, a_pks), (b_url, b_pks)| {
+        b_pks.len().cmp(&a_pks.len()).then_with(|| a_url.cmp(b_url))
+    });
+
+    let mut selected: Vec<RelayUrl> = Vec::with_capacity(candidates.len());
+
+    // Keep at least one relay for every public key
+    loop {
+        let best = candidates
+            .iter()
+            .enumerate()
+            .map(|(index, (_, pks))| (index, pks.difference(&covered).count()))
+            .filter(|(_, uncovered)| *uncovered > 0)
+            .max_by(|(a_index, a), (b_index, b)| a.cmp(b).then_with(|| b_index.cmp(a_index)));
+
+        match best {
+            Some((index, _)) => {
+                let (url, pks) = candidates.remove(index);
+                covered.extend(pks);
+                selected.push(url);
+            }
+            None => break,
+        }
+    }
+
+    // Fill the remaining slots
+    let remaining: usize = match max {
+        Some(max) => max.get().saturating_sub(selected.len()),
+        None => candidates.len(),
+    };
+
+    for (index, (url, _)) in candidates.into_iter().enumerate() {
+        if index < remaining {
+            selected.push(url);
+        } else {
+            tracing::debug!(url = %url, "Skipping gossip relay: too many new relays.");
+        }
+    }
+
+    selected
+}
+
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
+
     use nostr_gossip_memory::prelude::NostrGossipMemory;
 
     use super::*;
@@ -502,4 +733,92 @@ mod tests {
             .unwrap();
         assert!(matches!(status, GossipPublicKeyStatus::Updated));
     }
+
+    #[tokio::test]
+    async fn test_add_gossip_relays_max_new_relays() {
+        let config = GossipConfig::default().max_new_relays(NonZeroUsize::new(2).unwrap());
+        let client = Client::builder()
+            .gossip(NostrGossipMemory::unbounded())
+            .gossip_config(config)
+            .build();
+
+        let existing = RelayUrl::parse("ws://127.0.0.1:1").unwrap();
+        client.add_relay(&existing).await.unwrap();
+
+        let urls: Vec<RelayUrl> = (2..6)
+            .map(|port| RelayUrl::parse(&format!("ws://127.0.0.1:{port}")).unwrap())
+            .collect();
+
+        // Every relay covers the same public key
+        let public_key = Keys::generate().public_key();
+        let relays = urls
+            .iter()
+            .cloned()
+            .chain([existing.clone()])
+            .map(|url| (url, BTreeSet::from([public_key])));
+
+        let kept = client.add_gossip_relays(relays).await.unwrap();
+
+        // The relay already in the pool is always kept
+        assert_eq!(kept.len(), 3);
+        assert!(kept.contains(&existing));
+        assert!(kept.contains(&urls[0]));
+        assert!(kept.contains(&urls[1]));
+
+        // The other relays are not added to the pool
+        assert!(client.relay(&urls[2]).await.unwrap().is_none());
+        assert!(client.relay(&urls[3]).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_select_new_gossip_relays_by_coverage() {
+        let a = Keys::generate().public_key();
+        let b = Keys::generate().public_key();
+        let c = Keys::generate().public_key();
+
+        let url = |port: u16| RelayUrl::parse(&format!("ws://127.0.0.1:{port}")).unwrap();
+        let candidates = vec![
+            (url(1), BTreeSet::from([a])),
+            (url(2), BTreeSet::from([a, b])),
+            (url(3), BTreeSet::from([c])),
+            (url(4), BTreeSet::from([a, b, c])),
+            (url(5), BTreeSet::from([b])),
+        ];
+
+        // The relay with the widest coverage is preferred over the lower URLs
+        let selected =
+            select_new_gossip_relays(candidates.clone(), BTreeSet::new(), NonZeroUsize::new(2));
+        assert_eq!(selected, vec![url(4), url(2)]);
+
+        // The public keys already covered by the pool don't need a new relay
+        let selected = select_new_gossip_relays(
+            candidates.clone(),
+            BTreeSet::from([a, b, c]),
+            NonZeroUsize::new(1),
+        );
+        assert_eq!(selected, vec![url(4)]);
+
+        // Without a limit, all the relays are added
+        let selected = select_new_gossip_relays(candidates, BTreeSet::new(), None);
+        assert_eq!(selected.len(), 5);
+    }
+
+    #[test]
+    fn test_select_new_gossip_relays_keeps_a_relay_per_public_key() {
+        let a = Keys::generate().public_key();
+        let b = Keys::generate().public_key();
+        let c = Keys::generate().public_key();
+
+        let url = |port: u16| RelayUrl::parse(&format!("ws://127.0.0.1:{port}")).unwrap();
+        let candidates = vec![
+            (url(1), BTreeSet::from([a])),
+            (url(2), BTreeSet::from([a, b])),
+            (url(3), BTreeSet::from([c])),
+            (url(4), BTreeSet::from([a])),
+        ];
+
+        // The cap is exceeded to keep at least one relay for every public key
+        let selected = select_new_gossip_relays(candidates, BTreeSet::new(), NonZeroUsize::new(1));
+        assert_eq!(selected, vec![url(2), url(3)]);
+    }
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use nostr::{Event, RelayUrl, SubscriptionId};
+#[cfg(feature = "gossip")]
 use nostr_gossip::NostrGossip;
 
 use crate::future::BoxedFuture;
@@ -8,6 +9,7 @@ use crate::policy::{AdmitPolicy, AdmitStatus, PolicyError};
 
 #[derive(Debug)]
 pub(crate) struct AdmissionPolicyMiddleware {
+    #[cfg(feature = "gossip")]
     pub(crate) gossip: Option<Arc<dyn NostrGossip>>,
     pub(crate) external_policy: Option<Arc<dyn AdmitPolicy>>,
 }
@@ -57,6 +59,7 @@ impl AdmitPolicy for AdmissionPolicyMiddleware {
     ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
         Box::pin(async move {
             // Process event in gossip
+            #[cfg(feature = "gossip")]
             if let Some(gossip) = &self.gossip {
                 gossip
                     .process(event, Some(relay_url))
@@ -5,7 +5,7 @@
 //! Client
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
@@ -13,32 +13,48 @@ use std::time::Duration;
 use futures::{Stream, StreamExt};
 use nostr::prelude::*;
 use nostr_database::prelude::*;
+#[cfg(feature = "gossip")]
+use nostr_gossip::NostrGossip;
 use tokio::sync::oneshot;
 
 mod api;
 mod builder;
 mod error;
+mod failover;
+#[cfg(feature = "gossip")]
 mod gossip;
 mod middleware;
 mod notification;
+mod standing;
 
 pub use self::api::*;
 pub use self::builder::*;
 pub use self::error::Error;
+#[cfg(feature = "gossip")]
 use self::gossip::*;
 use self::middleware::AdmissionPolicyMiddleware;
 pub use self::notification::*;
+use self::standing::WatchedDatabase;
+pub use self::standing::{QueryCondition, StandingQuery, StandingQueryUpdate};
+#[cfg(not(feature = "negentropy"))]
+use crate::feature::RequiresNegentropyFeature;
 use crate::monitor::Monitor;
 use crate::pool::{RelayPool, RelayPoolBuilder};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::proxy::Proxy;
-use crate::relay::{Relay, RelayCapabilities, RelayLimits, RelayOptions, SyncOptions};
+#[cfg(feature = "negentropy")]
+use crate::relay::SyncOptions;
+use crate::relay::{
+    AuthPolicy, Relay, RelayCapabilities, RelayLimits, RelayOptions, RelayShutdownSummary,
+};
 use crate::stream::NotificationStream;
+use crate::throttle::SendLimiter;
 
 #[derive(Debug)]
 struct ClientConfig {
     #[cfg(not(target_arch = "wasm32"))]
     proxy: Option<Proxy>,
+    #[cfg(feature = "gossip")]
     gossip_config: GossipConfig,
     connect_timeout: Duration,
     relay_limits: RelayLimits,
@@ -46,11 +62,15 @@ struct ClientConfig {
     sleep_when_idle: SleepWhenIdle,
     verify_subscriptions: bool,
     ban_relay_on_mismatch: bool,
+    auth_policy: AuthPolicy,
+    failover: bool,
 }
 
 #[derive(Debug)]
 struct InnerClient {
     pool: RelayPool,
+    database: Arc<WatchedDatabase>,
+    #[cfg(feature = "gossip")]
     gossip: Option<Gossip>,
     config: ClientConfig,
 }
@@ -106,30 +126,55 @@ impl Client {
     }
 
     fn from_builder(builder: ClientBuilder) -> Self {
+        // Ignore the gossip database if gossip is disabled
+        #[cfg(feature = "gossip")]
+        let gossip: Option<Arc<dyn NostrGossip>> =
+            builder.gossip.filter(|_| builder.gossip_config.enabled);
+
         // Construct admission policy middleware
         let admit_policy_wrapper = AdmissionPolicyMiddleware {
-            gossip: builder.gossip.clone(),
+            #[cfg(feature = "gossip")]
+            gossip: gossip.clone(),
             external_policy: builder.admit_policy,
         };
 
+        // The failover requires a monitor
+        let monitor: Option<Monitor> = match builder.monitor {
+            Some(monitor) => Some(monitor),
+            None if builder.failover => Some(Monitor::new(builder.notification_channel_size.get())),
+            None => None,
+        };
+
+        // Wrap the database to track the changes
+        let database: Arc<WatchedDatabase> = Arc::new(WatchedDatabase::new(
+            builder.database,
+            builder.notification_channel_size.get(),
+        ));
+
         // Construct relay pool builder
         let pool_builder: RelayPoolBuilder = RelayPoolBuilder {
             websocket_transport: builder.websocket_transport,
             admit_policy: Some(Arc::new(admit_policy_wrapper)),
             authenticator: builder.authenticator,
-            monitor: builder.monitor,
-            database: builder.database,
+            upgrade_headers: builder.upgrade_headers,
+            monitor,
+            database: database.clone(),
             max_relays: builder.max_relays,
             notification_channel_size: builder.notification_channel_size,
+            preserve_raw_events: builder.preserve_raw_events,
+            send_limiter: SendLimiter::new(builder.max_messages_per_sec, builder.max_bytes_per_sec),
         };
 
         // Construct the inner client
         let inner = InnerClient {
             pool: pool_builder.build(),
-            gossip: builder.gossip.map(Gossip::new),
+            database,
+            #[cfg(feature = "gossip")]
+            gossip: gossip.map(Gossip::new),
             config: ClientConfig {
                 #[cfg(not(target_arch = "wasm32"))]
                 proxy: builder.proxy,
+                #[cfg(feature = "gossip")]
                 gossip_config: builder.gossip_config,
                 connect_timeout: builder.connect_timeout,
                 relay_limits: builder.relay_limits,
@@ -137,13 +182,17 @@ impl Client {
                 sleep_when_idle: builder.sleep_when_idle,
                 verify_subscriptions: builder.verify_subscriptions,
                 ban_relay_on_mismatch: builder.ban_relay_on_mismatch,
+                auth_policy: builder.auth_policy,
+                failover: builder.failover,
             },
         };
 
         // Construct the client
         let client = Self(Arc::new(inner));
 
+        #[cfg(feature = "gossip")]
         client.spawn_gossip_background_refresher();
+        client.spawn_failover_handler();
 
         client
     }
@@ -159,6 +208,7 @@ impl Client {
     }
 
     #[inline]
+    #[cfg(feature = "gossip")]
     fn gossip(&self) -> Option<&Gossip> {
         self.0.gossip.as_ref()
     }
@@ -169,9 +219,11 @@ impl Client {
     }
 
     /// Get database
+    ///
+    /// It's the database passed to the [`ClientBuilder`], so it can be downcast to the concrete backend.
     #[inline]
     pub fn database(&self) -> &Arc<dyn NostrDatabase> {
-        self.pool().database()
+        self.0.database.inner()
     }
 
     /// Get the relay monitor
@@ -194,6 +246,20 @@ impl Client {
         self.pool().shutdown().await
     }
 
+    /// Gracefully shutdown the client
+    ///
+    /// Flush the messages queued for each relay and wait for the pending `OK`s, up to the `timeout`,
+    /// before shutting down the client and all its relays.
+    ///
+    /// Returns the shutdown summary of each relay.
+    #[inline]
+    pub async fn graceful_shutdown(
+        &self,
+        timeout: Duration,
+    ) -> HashMap<RelayUrl, RelayShutdownSummary> {
+        self.pool().graceful_shutdown(timeout).await
+    }
+
     /// Get a new notification stream
     ///
     /// The stream terminates when the client shutdowns.
@@ -307,6 +373,7 @@ impl Client {
             .max_avg_latency(self.config().max_avg_latency)
             .verify_subscriptions(self.config().verify_subscriptions)
             .ban_relay_on_mismatch(self.config().ban_relay_on_mismatch)
+            .auth_policy(self.config().auth_policy)
     }
 
     /// Add relay
@@ -432,6 +499,36 @@ impl Client {
         let _ = self.remove_all_relays().force().await;
     }
 
+    /// Apply a NIP-65 relay list
+    ///
+    /// Diff the list against the current relays with [`RelayCapabilities::READ`] or [`RelayCapabilities::WRITE`]:
+    ///
+    /// - the relays not in the list are removed and disconnected (see [`Client::remove_relay`]),
+    /// - the new relays are added and connected,
+    /// - the relays with a changed read/write marker get their capabilities updated, without reconnecting them.
+    ///
+    /// The relays that are in both lists, with the same marker, are left untouched.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/65.md>
+    pub async fn apply_relay_list(&self, list: RelayList) -> Result<RelayListSummary, Error> {
+        let list: HashMap<RelayUrl, RelayCapabilities> = list
+            .into_iter()
+            .map(|(url, metadata)| {
+                let capabilities: RelayCapabilities = match metadata {
+                    Some(RelayMetadata::Read) => RelayCapabilities::READ,
+                    Some(RelayMetadata::Write) => RelayCapabilities::WRITE,
+                    None => RelayCapabilities::READ | RelayCapabilities::WRITE,
+                };
+                (url, capabilities)
+            })
+            .collect();
+
+        Ok(self
+            .pool()
+            .apply_relay_list(list, RelayOptions::default())
+            .await?)
+    }
+
     /// Connect to a previously added relay
     #[inline]
     pub async fn connect_relay<'a, U>(&self, url: U) -> Result<(), Error>
@@ -605,6 +702,7 @@ impl Client {
     ///
     /// - It is explicitly closed,
     /// - Auto-close conditions are met (if configured),
+    /// - The [`SubscriptionGuard`] is dropped (if requested with [`Subscribe::guard`]),
     /// - Or the relay closes it remotely.
     ///
     /// # Errors
@@ -774,6 +872,12 @@ impl Client {
     /// - [`StreamEvents::with_id`]: use a specific subscription ID
     /// - [`StreamEvents::timeout`]: set a maximum duration for the stream
     /// - [`StreamEvents::policy`]: control when the stream terminates
+    /// - [`StreamEvents::deduplicate`]: enable or disable the deduplication across relays
+    /// - [`StreamEvents::dedup_capacity`]: max number of event IDs remembered for deduplication
+    /// - [`StreamEvents::ordered`]: yield the stored events sorted by `created_at`
+    /// - [`StreamEvents::buffer`]: capacity of the stream buffer
+    /// - [`StreamEvents::overflow`]: what to do when the stream buffer is full
+    /// - [`StreamEvents::with_sources`]: yield every event with all the relays that sent it
     ///
     /// # Target Resolution
     ///
@@ -787,7 +891,8 @@ impl Client {
     ///
     /// # Event Semantics
     ///
-    /// - Events are **deduplicated** across relays by event ID.
+    /// - Events are **deduplicated** across relays by event ID (unless disabled),
+    ///   remembering up to [`StreamEvents::dedup_capacity`] IDs.
     /// - Event signatures are **validated**.
     /// - Events are **verified against the requested filters** if
     ///   [`ClientBuilder::verify_subscriptions`] is enabled.
@@ -898,6 +1003,7 @@ impl Client {
     ///
     /// - [`FetchEvents::timeout`]: set a maximum duration for the stream
     /// - [`FetchEvents::policy`]: control when the stream terminates
+    /// - [`FetchEvents::snapshot`]: fetch a consistent snapshot, bounded by a cutoff timestamp
     ///
     /// # Target Resolution, Event Semantics and Termination
     ///
@@ -977,6 +1083,137 @@ impl Client {
         FetchEvents::new(self, target.into())
     }
 
+    /// Fetch events from relays and store them into the database.
+    ///
+    /// # Overview
+    ///
+    /// Fetches the events from the relays with [`RelayCapabilities::READ`],
+    /// saves the ones missing from the [`NostrDatabase`] and returns them,
+    /// merged with the events already in the database that match the filter.
+    ///
+    /// The events that fail the signature verification are discarded by the relays,
+    /// so are neither stored nor returned.
+    /// The `limit` of the filter applies to the merged events.
+    ///
+    /// # Configuration
+    ///
+    /// By default:
+    ///
+    /// - No timeout is set
+    /// - The events only in the database are included
+    ///
+    /// To customize this behavior, the returned [`FetchEventsAndStore`] can be
+    /// configured before awaiting it:
+    ///
+    /// - [`FetchEventsAndStore::timeout`]: set a maximum duration for the fetch
+    /// - [`FetchEventsAndStore::include_database`]: include the events only in the database
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no READ relays or if the database query fails.
+    ///
+    /// The errors saving single events don't abort the fetch:
+    /// they are reported in [`FetchAndStoreOutput::failed`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use nostr_sdk::prelude::*;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   let client = Client::default();
+    /// let filter = Filter::new().kind(Kind::TextNote).limit(10);
+    ///
+    /// let output = client
+    ///     .fetch_events_and_store(filter)
+    ///     .timeout(Duration::from_secs(10))
+    ///     .await?;
+    ///
+    /// for event in output.events {
+    ///     println!("{}", event.as_json());
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[inline]
+    pub fn fetch_events_and_store(&self, filter: Filter) -> FetchEventsAndStore<'_> {
+        FetchEventsAndStore::new(self, filter)
+    }
+
+    /// Catch up the gift wraps (NIP-59) received while offline.
+    ///
+    /// # Overview
+    ///
+    /// Fetches the gift wraps addressed to the public key of the `signer`, from the newest,
+    /// in pages, so the recent conversations are available first and the memory stays bounded.
+    /// The gift wraps are deduplicated across the relays before being unwrapped,
+    /// then unwrapped concurrently and stored into the [`NostrDatabase`].
+    ///
+    /// The gift wraps that can't be unwrapped are reported and discarded, without stopping the catch-up.
+    ///
+    /// Returns a stream of [`GiftWrapCatchUpNotification`]:
+    /// the progress, the conversations whose history is complete from a timestamp on
+    /// and, at the end, the cursor to pass to the next catch-up.
+    ///
+    /// # Configuration
+    ///
+    /// By default:
+    ///
+    /// - All the gift wraps are fetched
+    /// - The pages have 500 gift wraps
+    /// - 8 gift wraps are unwrapped concurrently
+    /// - No timeout is set
+    ///
+    /// To customize this behavior, the returned [`CatchUpGiftWraps`] can be
+    /// configured before awaiting it:
+    ///
+    /// - [`CatchUpGiftWraps::since`]: catch up from the cursor of a previous catch-up
+    /// - [`CatchUpGiftWraps::page_size`]: set the max number of gift wraps in memory at a time
+    /// - [`CatchUpGiftWraps::concurrency`]: set the max number of gift wraps unwrapped concurrently
+    /// - [`CatchUpGiftWraps::timeout`]: set a maximum duration for fetching each page
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the public key of the signer can't be got.
+    /// The stream yields an error, and ends without the cursor, if a page can't be fetched
+    /// or the database fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use nostr_sdk::prelude::*;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   let client = Client::default();
+    /// #   let keys = Keys::generate();
+    /// #   let cursor = Timestamp::now();
+    /// let mut stream = client.catch_up_gift_wraps(&keys).since(cursor).await?;
+    ///
+    /// while let Some(notification) = stream.next().await {
+    ///     match notification? {
+    ///         GiftWrapCatchUpNotification::Unwrapped { unwrapped, .. } => {
+    ///             println!("{}", unwrapped.rumor.content);
+    ///         }
+    ///         GiftWrapCatchUpNotification::Finished { cursor } => {
+    ///             println!("Next cursor: {cursor}");
+    ///         }
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[inline]
+    #[cfg(feature = "nip59")]
+    pub fn catch_up_gift_wraps<'signer, T>(
+        &self,
+        signer: &'signer T,
+    ) -> CatchUpGiftWraps<'_, 'signer, T>
+    where
+        T: AsyncGetPublicKey + AsyncNip44,
+    {
+        CatchUpGiftWraps::new(self, signer)
+    }
+
     /// Synchronize events with relays using negentropy.
     ///
     /// # Overview
@@ -1027,6 +1264,7 @@ impl Client {
     /// Relay-specific failures during event transfer are reported in the
     /// returned [`SyncSummary`].
     #[inline]
+    #[cfg(feature = "negentropy")]
     pub fn sync<'url>(&self, filter: Filter) -> SyncEvents<'_, 'url> {
         SyncEvents::new(self, filter)
     }
@@ -1038,6 +1276,7 @@ impl Client {
         since = "0.45.0",
         note = "use `client.sync(filter).with(urls).await` instead"
     )]
+    #[cfg(feature = "negentropy")]
     pub async fn sync_with<'a, I, U>(
         &self,
         urls: I,
@@ -1051,6 +1290,16 @@ impl Client {
         self.sync(filter).with(urls).opts(opts.clone()).await
     }
 
+    /// Placeholder of [`Client::sync`], that requires the `negentropy` feature
+    #[doc(hidden)]
+    #[cfg(not(feature = "negentropy"))]
+    pub fn sync<T>(&self, _filter: T) -> T::Disabled
+    where
+        T: RequiresNegentropyFeature,
+    {
+        unreachable!("the `negentropy` feature is disabled")
+    }
+
     /// Send a client message to relays.
     ///
     /// # Overview
@@ -1128,6 +1377,7 @@ impl Client {
     /// - [`SendEvent::ack_policy`]: control relay `OK` acknowledgement behavior
     /// - [`SendEvent::ok_timeout`]: set a timeout for waiting for `OK` responses
     /// - [`SendEvent::authentication_timeout`]: set a timeout for relay authentication
+    /// - [`SendEvent::require_success_on`]: set the minimum number of relays that must accept the event
     ///
     /// # Target Resolution
     ///
@@ -1167,9 +1417,12 @@ impl Client {
     /// - A specified relay URL is invalid,
     /// - A specified relay does not exist in the pool,
     /// - The event cannot be saved to the database,
-    /// - Or sending cannot be initiated.
+    /// - Sending cannot be initiated,
+    /// - Or less relays than required accepted the event (see [`SendEvent::require_success_on`]).
     ///
-    /// Relay-specific delivery failures are reported in the returned [`Output`].
+    /// Relay-specific delivery failures are reported in the returned [`PublishOutput`],
+    /// with the reason of every failure: rejected by the relay (with the machine-readable prefix, if any),
+    /// timeout waiting for the `OK` or another error.
     #[inline]
     pub fn send_event<'event, 'url>(&self, event: &'event Event) -> SendEvent<'_, 'event, 'url> {
         SendEvent::new(self, event)
@@ -1189,17 +1442,153 @@ impl Client {
         &self,
         urls: I,
         event: &Event,
-    ) -> Result<Output<EventId>, Error>
+    ) -> Result<PublishOutput, Error>
     where
         I: IntoIterator<Item = U>,
         U: Into<RelayUrlArg<'a>>,
     {
         self.send_event(event).to(urls).await
     }
+
+    /// Report a user, a note or a blob (NIP-56)
+    ///
+    /// Builds the report event with the tags of the [`ReportTarget`], signs it and sends it.
+    ///
+    /// The report is routed like any other event (see [`Client::send_event`]):
+    /// with gossip, it's sent to the WRITE relays of the reporter and
+    /// to the READ relays of the reported author (when the target includes its public key).
+    /// To send it to other relays (i.e., the relay where the reported event has been seen),
+    /// build it with [`EventBuilder::report_target`] and use [`SendEvent::to`].
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/56.md>
+    pub async fn report<T>(
+        &self,
+        signer: &T,
+        target: ReportTarget,
+        report: Report,
+        comment: &str,
+    ) -> Result<PublishOutput, Error>
+    where
+        T: AsyncGetPublicKey + AsyncSignEvent,
+    {
+        let event: Event = EventBuilder::report_target(&target, report, comment)
+            .sign_async(signer)
+            .await?;
+        self.send_event(&event).await
+    }
+
+    /// Get the summary of the reports about a target (NIP-56)
+    ///
+    /// Fetches the reports from the relays, merging them with the ones already in the database.
+    /// Every reporter is counted once, with its most recent report.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/56.md>
+    pub async fn reports_about(
+        &self,
+        target: &ReportTarget,
+        timeout: Duration,
+    ) -> Result<ReportSummary, Error> {
+        let filter: Filter = target.filter();
+
+        let fetched: Events = self.fetch_events(filter.clone()).timeout(timeout).await?;
+        let stored: Events = self.database().query(filter).await?;
+
+        Ok(ReportSummary::from_events(
+            target,
+            fetched.iter().chain(stored.iter()),
+        ))
+    }
+
+    /// Find the application handlers of a kind (NIP-89)
+    ///
+    /// The contact list of the `user` is taken from the database or, if missing, fetched from the relays.
+    /// The handler information events and the recommendations of the follows are fetched from the relays,
+    /// merging them with the ones already in the database.
+    ///
+    /// The candidates are sorted by the number of follows that recommend them.
+    /// The malformed handler information events are returned as skipped, with the reason.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
+    pub async fn find_handlers(
+        &self,
+        kind: Kind,
+        user: PublicKey,
+        timeout: Duration,
+    ) -> Result<HandlerDiscovery, Error> {
+        let filter: Filter = Filter::new().kind(Kind::ContactList).author(user).limit(1);
+        let mut contacts: Events = self.database().query(filter.clone()).await?;
+        if contacts.is_empty() {
+            contacts = self.fetch_events(filter).timeout(timeout).await?;
+        }
+
+        let follows: BTreeSet<PublicKey> = contacts
+            .first()
+            .map(|event| event.tags.public_keys().collect())
+            .unwrap_or_default();
+
+        let mut filters: Vec<Filter> = vec![
+            Filter::new()
+                .kind(Kind::HandlerInformation)
+                .custom_tag(SingleLetterTag::lowercase(Alphabet::K), kind.to_string()),
+        ];
+        if !follows.is_empty() {
+            filters.push(
+                Filter::new()
+                    .kind(Kind::HandlerRecommendation)
+                    .authors(follows.iter().copied())
+                    .identifier(kind.to_string()),
+            );
+        }
+
+        let mut events: Vec<Event> = Vec::new();
+        for filter in filters.iter() {
+            events.extend(self.database().query(filter.clone()).await?);
+        }
+        events.extend(self.fetch_events(filters).timeout(timeout).await?);
+
+        Ok(HandlerDiscovery::from_events(kind, &follows, events.iter()))
+    }
+
+    /// Recommend application handlers for a kind (NIP-89)
+    ///
+    /// Replaces the previous recommendation of the same kind.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
+    pub async fn recommend_handler<T>(
+        &self,
+        signer: &T,
+        recommendation: &HandlerRecommendation,
+    ) -> Result<PublishOutput, Error>
+    where
+        T: AsyncGetPublicKey + AsyncSignEvent,
+    {
+        let event: Event = EventBuilder::handler_recommendation(recommendation)
+            .sign_async(signer)
+            .await?;
+        self.send_event(&event).await
+    }
+
+    /// Publish the handler information of an application (NIP-89)
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/89.md>
+    pub async fn publish_handler_info<T>(
+        &self,
+        signer: &T,
+        info: &HandlerInformation,
+    ) -> Result<PublishOutput, Error>
+    where
+        T: AsyncGetPublicKey + AsyncSignEvent,
+    {
+        let event: Event = EventBuilder::handler_information(info)
+            .sign_async(signer)
+            .await?;
+        self.send_event(&event).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "gossip")]
     use nostr_gossip_memory::prelude::*;
     use nostr_relay_builder::MockRelay;
 
@@ -1236,6 +1625,40 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let client = Client::builder().max_messages_per_sec(5).build();
+        client.add_relay(&url).and_connect().await.unwrap();
+
+        let keys = Keys::generate();
+        for i in 0..8 {
+            let event = EventBuilder::text_note(format!("flush-{i}"))
+                .sign(&keys)
+                .unwrap();
+            client
+                .send_event(&event)
+                .ack_policy(AckPolicy::none())
+                .await
+                .unwrap();
+        }
+
+        let summaries = client.graceful_shutdown(Duration::from_secs(5)).await;
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[&url].is_clean(), "{summaries:?}");
+        assert!(summaries[&url].sent > 0);
+
+        assert!(client.is_shutdown());
+
+        let event = EventBuilder::text_note("late").sign(&keys).unwrap();
+        assert!(matches!(
+            client.send_event(&event).await.unwrap_err(),
+            Error::RelayPool(pool::Error::Shutdown)
+        ));
+    }
+
     #[tokio::test]
     async fn test_shutdown_on_drop() {
         let mock = MockRelay::run().await.unwrap();
@@ -1286,6 +1709,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg(feature = "gossip")]
     async fn test_shutdown_on_drop_with_gossip_background_refresher_enabled() {
         let weak: WeakClient = {
             let gossip = NostrGossipMemory::unbounded();
@@ -1372,4 +1796,110 @@ mod tests {
         // Must return None, as it's empty
         assert!(res.is_none());
     }
+
+    #[tokio::test]
+    async fn test_report_and_reports_about() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let client = Client::default();
+        client.add_relay(&url).and_connect().await.unwrap();
+
+        let reporter = Keys::generate();
+        let spammer = Keys::generate();
+        let target = ReportTarget::PublicKey(spammer.public_key());
+
+        let output = client
+            .report(&reporter, target.clone(), Report::Spam, "spam bot")
+            .await
+            .unwrap();
+        assert!(output.success.contains(&url));
+
+        let reader = Client::default();
+        reader.add_relay(&url).and_connect().await.unwrap();
+
+        let summary = reader
+            .reports_about(&target, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary.report(&reporter.public_key()), Some(&Report::Spam));
+    }
+
+    #[tokio::test]
+    async fn test_find_handlers() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let client = Client::default();
+        client.add_relay(&url).and_connect().await.unwrap();
+
+        let app = Keys::generate();
+        let follow = Keys::generate();
+        let user = Keys::generate();
+
+        // The application publishes its handler information
+        let web = HandlerUrl::new(
+            HandlerPlatform::Web,
+            "https://app.example/a/<bech32>",
+            Some(HandlerEntity::Naddr),
+        )
+        .unwrap();
+        let info = HandlerInformation::new("reader")
+            .kind(Kind::LongFormTextNote)
+            .url(web);
+        client.publish_handler_info(&app, &info).await.unwrap();
+
+        // A malformed handler information
+        let malformed = EventBuilder::new(Kind::HandlerInformation, "")
+            .tags([
+                Tag::identifier("broken"),
+                Tag::parse(["k", "30023"]).unwrap(),
+                Tag::parse(["web", "https://<bech32>.evil.example/"]).unwrap(),
+            ])
+            .sign(&Keys::generate())
+            .unwrap();
+        client.send_event(&malformed).await.unwrap();
+
+        // A follow of the user recommends it
+        let coordinate =
+            Coordinate::new(Kind::HandlerInformation, app.public_key()).identifier("reader");
+        let recommendation = HandlerRecommendation::new(
+            Kind::LongFormTextNote,
+            [RecommendedHandler::new(coordinate.clone()).relay_hint(url.clone())],
+        );
+        client
+            .recommend_handler(&follow, &recommendation)
+            .await
+            .unwrap();
+
+        let contacts = EventBuilder::contact_list([Contact::new(follow.public_key())])
+            .sign(&user)
+            .unwrap();
+        client.send_event(&contacts).await.unwrap();
+
+        // Discover them from another client
+        let reader = Client::default();
+        reader.add_relay(&url).and_connect().await.unwrap();
+
+        let discovery = reader
+            .find_handlers(
+                Kind::LongFormTextNote,
+                user.public_key(),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(discovery.candidates.len(), 1);
+        assert_eq!(discovery.candidates[0].coordinate, coordinate);
+        assert_eq!(discovery.candidates[0].score(), 1);
+
+        assert_eq!(discovery.skipped.len(), 1);
+        assert_eq!(discovery.skipped[0].event_id, malformed.id);
+        assert_eq!(
+            discovery.skipped[0].reason,
+            nip89::Error::PlaceholderInAuthority
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use nostr::{Event, RelayMessage, RelayUrl, SubscriptionId};
 
 /// Nostr client notification
@@ -32,6 +34,25 @@ pub enum ClientNotification {
         /// The received relay message.
         message: Box<RelayMessage<'static>>,
     },
+    /// Measured the round-trip time of a relay
+    ///
+    /// Sent after every ping/pong exchange, i.e., to sort the relays by responsiveness.
+    Rtt {
+        /// Relay URL
+        relay_url: RelayUrl,
+        /// Round-trip time
+        rtt: Duration,
+    },
+    /// Received a NIP-42 AUTH challenge that must be answered manually
+    ///
+    /// Sent only for the relays with [`AuthPolicy::Manual`](crate::relay::AuthPolicy::Manual).
+    /// Answer it with [`Relay::auth`](crate::relay::Relay::auth).
+    AuthChallenge {
+        /// Relay URL
+        relay_url: RelayUrl,
+        /// Challenge
+        challenge: String,
+    },
     /// Shutdown
     ///
     /// This notification variant is sent after [`Client::shutdown`](super::Client::shutdown) method is called and all connections have been closed.
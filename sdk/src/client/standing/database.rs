@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use nostr::prelude::*;
+use nostr_database::prelude::*;
+use tokio::sync::broadcast;
+
+/// Database change
+#[derive(Debug, Clone)]
+pub(super) enum DatabaseChange {
+    /// New event saved
+    Saved(Box<Event>),
+    /// One or more events may have been removed
+    Removed,
+}
+
+/// Database wrapper that notifies the changes
+#[derive(Debug)]
+pub(crate) struct WatchedDatabase {
+    inner: Arc<dyn NostrDatabase>,
+    changes: broadcast::Sender<DatabaseChange>,
+}
+
+impl WatchedDatabase {
+    pub(crate) fn new(inner: Arc<dyn NostrDatabase>, channel_size: usize) -> Self {
+        let (changes, ..) = broadcast::channel(channel_size);
+        Self { inner, changes }
+    }
+
+    /// Wrapped database
+    #[inline]
+    pub(crate) fn inner(&self) -> &Arc<dyn NostrDatabase> {
+        &self.inner
+    }
+
+    #[inline]
+    pub(super) fn subscribe(&self) -> broadcast::Receiver<DatabaseChange> {
+        self.changes.subscribe()
+    }
+
+    #[inline]
+    fn notify(&self, change: DatabaseChange) {
+        // Skip if no one is listening
+        if self.changes.receiver_count() > 0 {
+            let _ = self.changes.send(change);
+        }
+    }
+}
+
+impl NostrDatabase for WatchedDatabase {
+    #[inline]
+    fn backend(&self) -> Backend {
+        self.inner.backend()
+    }
+
+    #[inline]
+    fn features(&self) -> Features {
+        self.inner.features()
+    }
+
+    fn save_event<'a>(
+        &'a self,
+        event: &'a Event,
+    ) -> BoxedFuture<'a, Result<SaveEventStatus, DatabaseError>> {
+        Box::pin(async move {
+            let status: SaveEventStatus = self.inner.save_event(event).await?;
+
+            if status.is_success() {
+                self.notify(DatabaseChange::Saved(Box::new(event.clone())));
+            }
+
+            Ok(status)
+        })
+    }
+
+    fn save_events(
+        &self,
+        events: Vec<Event>,
+    ) -> BoxedFuture<'_, Result<Vec<SaveEventStatus>, DatabaseError>> {
+        Box::pin(async move {
+            // Avoid cloning the batch if no one is listening
+            if self.changes.receiver_count() == 0 {
+                return self.inner.save_events(events).await;
+            }
+
+            let statuses: Vec<SaveEventStatus> = self.inner.save_events(events.clone()).await?;
+
+            for (event, status) in events.into_iter().zip(statuses.iter()) {
+                if status.is_success() {
+                    self.notify(DatabaseChange::Saved(Box::new(event)));
+                }
+            }
+
+            Ok(statuses)
+        })
+    }
+
+    #[inline]
+    fn check_id<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<DatabaseEventStatus, DatabaseError>> {
+        self.inner.check_id(event_id)
+    }
+
+    #[inline]
+    fn contains<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<bool, DatabaseError>> {
+        self.inner.contains(event_id)
+    }
+
+    #[inline]
+    fn event_by_id<'a>(
+        &'a self,
+        event_id: &'a EventId,
+    ) -> BoxedFuture<'a, Result<Option<Event>, DatabaseError>> {
+        self.inner.event_by_id(event_id)
+    }
+
+    #[inline]
+    fn count(&self, filter: Filter) -> BoxedFuture<'_, Result<usize, DatabaseError>> {
+        self.inner.count(filter)
+    }
+
+    #[inline]
+    fn query(&self, filter: Filter) -> BoxedFuture<'_, Result<Events, DatabaseError>> {
+        self.inner.query(filter)
+    }
+
+    #[inline]
+    fn stream_query(&self, filter: Filter) -> BoxedStream<'_, Result<Event, DatabaseError>> {
+        self.inner.stream_query(filter)
+    }
+
+    #[inline]
+    fn negentropy_items(
+        &self,
+        filter: Filter,
+    ) -> BoxedFuture<'_, Result<Vec<(EventId, Timestamp)>, DatabaseError>> {
+        self.inner.negentropy_items(filter)
+    }
+
+    fn delete(&self, filter: Filter) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            self.inner.delete(filter).await?;
+            self.notify(DatabaseChange::Removed);
+            Ok(())
+        })
+    }
+
+    fn wipe(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            self.inner.wipe().await?;
+            self.notify(DatabaseChange::Removed);
+            Ok(())
+        })
+    }
+
+    fn wipe_expired(&self) -> BoxedFuture<'_, Result<(), DatabaseError>> {
+        Box::pin(async move {
+            self.inner.wipe_expired().await?;
+            self.notify(DatabaseChange::Removed);
+            Ok(())
+        })
+    }
+
+    fn verify_events(
+        &self,
+        opts: VerifyOptions,
+    ) -> BoxedFuture<'_, Result<VerifyReport, DatabaseError>> {
+        Box::pin(async move {
+            let report: VerifyReport = self.inner.verify_events(opts).await?;
+
+            // The invalid events may have been quarantined or deleted
+            if !report.is_clean() {
+                self.notify(DatabaseChange::Removed);
+            }
+
+            Ok(report)
+        })
+    }
+}
@@ -0,0 +1,451 @@
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+
+use nostr::prelude::*;
+use nostr_database::prelude::*;
+
+use super::QueryCondition;
+
+/// Max number of events tracked per filter
+///
+/// When exceeded, the untracked events are recovered by reloading from the database.
+pub(super) const MAX_TRACKED_EVENTS: usize = 10_000;
+
+/// Reference to an event
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    Id(EventId),
+    Coordinate(Coordinate),
+}
+
+/// Tracked event
+#[derive(Debug)]
+struct TrackedEvent {
+    author: PublicKey,
+    /// Coordinate of the replaceable and addressable events
+    coordinate: Option<Coordinate>,
+    /// References used by the joins
+    references: Vec<Reference>,
+}
+
+impl TrackedEvent {
+    fn new(event: &Event, references: Vec<Reference>) -> Self {
+        Self {
+            author: event.pubkey,
+            coordinate: event.coordinate(),
+            references,
+        }
+    }
+}
+
+/// Evaluation transition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Transition {
+    Satisfied(Vec<EventId>),
+    Unsatisfied,
+}
+
+/// Incremental evaluation state of a standing query
+#[derive(Debug)]
+pub(super) struct QueryState {
+    filter: Filter,
+    condition: QueryCondition,
+    /// Events matching the filter, with the references that point to them
+    matched: BTreeMap<EventId, TrackedEvent>,
+    /// Events matching the related filter, with the references they contain
+    related: BTreeMap<EventId, TrackedEvent>,
+    /// Some events haven't been tracked due to the memory bound
+    overflow: bool,
+    satisfied: bool,
+}
+
+impl QueryState {
+    pub(super) fn new(filter: Filter, condition: QueryCondition) -> Self {
+        Self {
+            filter,
+            condition,
+            matched: BTreeMap::new(),
+            related: BTreeMap::new(),
+            overflow: false,
+            satisfied: false,
+        }
+    }
+
+    #[inline]
+    fn max_tracked(&self) -> usize {
+        match &self.condition {
+            QueryCondition::AtLeast(n) => cmp::max(*n, MAX_TRACKED_EVENTS),
+            QueryCondition::Referenced(..) => MAX_TRACKED_EVENTS,
+        }
+    }
+
+    /// Load the events from the database, replacing the current state
+    pub(super) async fn load(&mut self, database: &dyn NostrDatabase) -> Result<(), DatabaseError> {
+        self.matched.clear();
+        self.related.clear();
+        self.overflow = false;
+
+        // Query one more event than the limit, to detect the overflow
+        let limit: usize = self.max_tracked() + 1;
+
+        let events: Events = database.query(self.filter.clone().limit(limit)).await?;
+        for event in events.into_iter() {
+            self.insert_matched(&event);
+        }
+
+        if let QueryCondition::Referenced(related) = &self.condition {
+            let events: Events = database.query(related.clone().limit(limit)).await?;
+            for event in events.into_iter() {
+                self.insert_related(&event);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process a new stored event
+    pub(super) fn insert(&mut self, event: &Event) {
+        if self.filter.match_event(event, MatchEventOptions::new()) {
+            self.insert_matched(event);
+        }
+
+        if let QueryCondition::Referenced(related) = &self.condition {
+            if related.match_event(event, MatchEventOptions::new()) {
+                self.insert_related(event);
+            }
+        }
+    }
+
+    fn insert_matched(&mut self, event: &Event) {
+        if self.matched.contains_key(&event.id) {
+            return;
+        }
+
+        if self.matched.len() >= self.max_tracked() {
+            self.overflow = true;
+            return;
+        }
+
+        // The references are needed only for the joins
+        let references: Vec<Reference> = match &self.condition {
+            QueryCondition::AtLeast(..) => Vec::new(),
+            QueryCondition::Referenced(..) => {
+                let mut references: Vec<Reference> = vec![Reference::Id(event.id)];
+                if let Some(coordinate) = event.coordinate() {
+                    references.push(Reference::Coordinate(coordinate));
+                }
+                references
+            }
+        };
+
+        self.matched
+            .insert(event.id, TrackedEvent::new(event, references));
+    }
+
+    fn insert_related(&mut self, event: &Event) {
+        if self.related.contains_key(&event.id) {
+            return;
+        }
+
+        if self.related.len() >= self.max_tracked() {
+            self.overflow = true;
+            return;
+        }
+
+        let references: Vec<Reference> = event
+            .tags
+            .event_ids()
+            .map(Reference::Id)
+            .chain(event.tags.coordinates().map(Reference::Coordinate))
+            .collect();
+
+        self.related
+            .insert(event.id, TrackedEvent::new(event, references));
+    }
+
+    /// Find the tracked events that may have been removed by saving the event
+    ///
+    /// I.e., the targets of a deletion or vanish request and the older versions of a (addressable) replaceable event.
+    fn removal_candidates(&self, event: &Event) -> BTreeSet<EventId> {
+        let tracked = self.matched.iter().chain(self.related.iter());
+
+        if event.kind == Kind::EventDeletion {
+            let coordinates: Vec<Coordinate> = event.tags.coordinates().collect();
+
+            let mut candidates: BTreeSet<EventId> = event
+                .tags
+                .event_ids()
+                .filter(|id| self.matched.contains_key(id) || self.related.contains_key(id))
+                .collect();
+
+            if !coordinates.is_empty() {
+                candidates.extend(tracked.filter_map(|(id, t)| {
+                    let coordinate = t.coordinate.as_ref()?;
+                    coordinates.contains(coordinate).then_some(*id)
+                }));
+            }
+
+            candidates
+        } else if event.kind == Kind::RequestToVanish {
+            tracked
+                .filter(|(_, t)| t.author == event.pubkey)
+                .map(|(id, _)| *id)
+                .collect()
+        } else if let Some(coordinate) = event.coordinate() {
+            tracked
+                .filter(|(id, t)| **id != event.id && t.coordinate.as_ref() == Some(&coordinate))
+                .map(|(id, _)| *id)
+                .collect()
+        } else {
+            BTreeSet::new()
+        }
+    }
+
+    /// Drop the tracked events removed by saving the event
+    pub(super) async fn remove_stale(
+        &mut self,
+        event: &Event,
+        database: &dyn NostrDatabase,
+    ) -> Result<(), DatabaseError> {
+        let candidates: BTreeSet<EventId> = self.removal_candidates(event);
+        self.remove_gone(candidates, database).await
+    }
+
+    /// Drop the tracked events that are no longer stored
+    pub(super) async fn prune(
+        &mut self,
+        database: &dyn NostrDatabase,
+    ) -> Result<(), DatabaseError> {
+        let ids: BTreeSet<EventId> = self
+            .matched
+            .keys()
+            .chain(self.related.keys())
+            .copied()
+            .collect();
+        self.remove_gone(ids, database).await
+    }
+
+    async fn remove_gone<I>(
+        &mut self,
+        ids: I,
+        database: &dyn NostrDatabase,
+    ) -> Result<(), DatabaseError>
+    where
+        I: IntoIterator<Item = EventId>,
+    {
+        let mut removed: bool = false;
+
+        for id in ids.into_iter() {
+            if !matches!(database.check_id(&id).await?, DatabaseEventStatus::Saved) {
+                removed |= self.matched.remove(&id).is_some();
+                removed |= self.related.remove(&id).is_some();
+            }
+        }
+
+        // Some untracked events may now fit: reload them from the database
+        if removed && self.overflow {
+            self.load(database).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the witnesses of the condition, if satisfied
+    ///
+    /// The witnesses are selected in a deterministic way, independently of the order in which the events have been processed.
+    fn witnesses(&self) -> Option<Vec<EventId>> {
+        match &self.condition {
+            QueryCondition::AtLeast(n) => {
+                if self.matched.len() >= *n {
+                    Some(self.matched.keys().take(*n).copied().collect())
+                } else {
+                    None
+                }
+            }
+            QueryCondition::Referenced(..) => {
+                // Index the references of the matched events.
+                // The map is iterated in order, so the first (smallest) ID is kept.
+                let mut index: BTreeMap<&Reference, EventId> = BTreeMap::new();
+                for (id, tracked) in self.matched.iter() {
+                    for reference in tracked.references.iter() {
+                        index.entry(reference).or_insert(*id);
+                    }
+                }
+
+                // Find the smallest (matched, related) pair
+                let mut pairs: BTreeSet<(EventId, EventId)> = BTreeSet::new();
+                for (related_id, tracked) in self.related.iter() {
+                    for reference in tracked.references.iter() {
+                        if let Some(matched_id) = index.get(reference) {
+                            pairs.insert((*matched_id, *related_id));
+                        }
+                    }
+                }
+
+                pairs
+                    .first()
+                    .map(|(matched_id, related_id)| vec![*matched_id, *related_id])
+            }
+        }
+    }
+
+    /// Evaluate the condition
+    ///
+    /// Returns a [`Transition`] only if the satisfaction changed since the last evaluation.
+    pub(super) fn evaluate(&mut self) -> Option<Transition> {
+        match (self.satisfied, self.witnesses()) {
+            (false, Some(witnesses)) => {
+                self.satisfied = true;
+                Some(Transition::Satisfied(witnesses))
+            }
+            (true, None) => {
+                self.satisfied = false;
+                Some(Transition::Unsatisfied)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_memory::MemoryDatabase;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_at_least_incremental() {
+        let keys = Keys::generate();
+        let target = EventId::all_zeros();
+        let filter = Filter::new().event(target);
+
+        let mut state = QueryState::new(filter, QueryCondition::AtLeast(2));
+        assert_eq!(state.evaluate(), None);
+
+        let e1 = EventBuilder::text_note("1")
+            .tag(Tag::event(target))
+            .sign(&keys)
+            .unwrap();
+        let e2 = EventBuilder::text_note("2")
+            .tag(Tag::event(target))
+            .sign(&keys)
+            .unwrap();
+        let unrelated = EventBuilder::text_note("3").sign(&keys).unwrap();
+
+        state.insert(&e1);
+        state.insert(&unrelated);
+        assert_eq!(state.evaluate(), None);
+
+        // Duplicates aren't counted twice
+        state.insert(&e1);
+        assert_eq!(state.evaluate(), None);
+
+        state.insert(&e2);
+        let mut witnesses = vec![e1.id, e2.id];
+        witnesses.sort();
+        assert_eq!(state.evaluate(), Some(Transition::Satisfied(witnesses)));
+
+        // No transition
+        assert_eq!(state.evaluate(), None);
+    }
+
+    #[tokio::test]
+    async fn test_remove_replaced() {
+        let database = MemoryDatabase::unbounded();
+        let keys = Keys::generate();
+
+        let old = EventBuilder::metadata(&Metadata::new().name("old"))
+            .custom_created_at(Timestamp::from_secs(1000))
+            .sign(&keys)
+            .unwrap();
+        let new = EventBuilder::metadata(&Metadata::new().name("new"))
+            .custom_created_at(Timestamp::from_secs(2000))
+            .sign(&keys)
+            .unwrap();
+        let unrelated = EventBuilder::text_note("unrelated").sign(&keys).unwrap();
+
+        let mut state = QueryState::new(
+            Filter::new().author(keys.public_key()),
+            QueryCondition::AtLeast(3),
+        );
+
+        for event in [&old, &unrelated, &new] {
+            database.save_event(event).await.unwrap();
+            state.insert(event);
+        }
+
+        // The replaced event is the only candidate
+        assert_eq!(state.removal_candidates(&new), BTreeSet::from([old.id]));
+        assert!(state.removal_candidates(&unrelated).is_empty());
+
+        state.remove_stale(&new, &database).await.unwrap();
+        assert_eq!(state.evaluate(), None);
+        assert!(!state.matched.contains_key(&old.id));
+    }
+
+    #[tokio::test]
+    async fn test_removal_candidates_of_deletion() {
+        let keys = Keys::generate();
+
+        let note = EventBuilder::text_note("note").sign(&keys).unwrap();
+        let other = EventBuilder::text_note("other").sign(&keys).unwrap();
+        let article = EventBuilder::new(Kind::LongFormTextNote, "article")
+            .tag(Tag::identifier("article"))
+            .sign(&keys)
+            .unwrap();
+
+        let mut state = QueryState::new(
+            Filter::new().author(keys.public_key()),
+            QueryCondition::AtLeast(1),
+        );
+        for event in [&note, &other, &article] {
+            state.insert(event);
+        }
+
+        let deletion = EventBuilder::delete(
+            EventDeletionRequest::new()
+                .id(note.id)
+                .coordinate(article.coordinate().unwrap()),
+        )
+        .sign(&keys)
+        .unwrap();
+        assert_eq!(
+            state.removal_candidates(&deletion),
+            BTreeSet::from([note.id, article.id])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_referenced_prune() {
+        let database = MemoryDatabase::unbounded();
+        let keys = Keys::generate();
+
+        let definition = EventBuilder::new(Kind::BadgeDefinition, "")
+            .tag(Tag::identifier("badge"))
+            .sign(&keys)
+            .unwrap();
+        let coordinate = definition.coordinate().unwrap();
+        let award = EventBuilder::new(Kind::BadgeAward, "")
+            .tag(Tag::coordinate(coordinate, None))
+            .sign(&keys)
+            .unwrap();
+
+        database.save_event(&definition).await.unwrap();
+        database.save_event(&award).await.unwrap();
+
+        let mut state = QueryState::new(
+            Filter::new().kind(Kind::BadgeDefinition),
+            QueryCondition::Referenced(Filter::new().kind(Kind::BadgeAward)),
+        );
+        state.load(&database).await.unwrap();
+        assert_eq!(
+            state.evaluate(),
+            Some(Transition::Satisfied(vec![definition.id, award.id]))
+        );
+
+        // Remove the award
+        database.delete(Filter::new().id(award.id)).await.unwrap();
+        state.prune(&database).await.unwrap();
+        assert_eq!(state.evaluate(), Some(Transition::Unsatisfied));
+    }
+}
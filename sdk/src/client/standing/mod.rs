@@ -0,0 +1,366 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Standing queries over the local database
+
+use std::sync::Arc;
+
+use async_utility::task;
+use nostr::{EventId, Filter};
+use nostr_database::{BoxedStream, NostrDatabase};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+mod database;
+mod engine;
+
+use self::database::DatabaseChange;
+pub(crate) use self::database::WatchedDatabase;
+use self::engine::{QueryState, Transition};
+use super::Client;
+use crate::stream::ReceiverStream;
+
+const UPDATES_CHANNEL_SIZE: usize = 64;
+
+/// Standing query condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryCondition {
+    /// At least `n` stored events match the filter
+    ///
+    /// I.e., "at least 3 events reference this ID".
+    AtLeast(usize),
+    /// At least one stored event matching the filter is referenced by a stored event matching the related filter
+    ///
+    /// An event is referenced by an `e` tag with its ID or,
+    /// for the replaceable and addressable events, by an `a` tag with its coordinate.
+    ///
+    /// I.e., "a badge definition and an award of it".
+    Referenced(Filter),
+}
+
+/// Standing query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandingQuery {
+    /// Filter
+    pub filter: Filter,
+    /// Condition to satisfy
+    pub condition: QueryCondition,
+    /// Name, used to identify the updates
+    pub name: String,
+}
+
+impl StandingQuery {
+    /// New standing query
+    pub fn new<S>(name: S, filter: Filter, condition: QueryCondition) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            filter,
+            condition,
+            name: name.into(),
+        }
+    }
+}
+
+/// Standing query update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StandingQueryUpdate {
+    /// The condition has been satisfied
+    Satisfied {
+        /// Query name
+        name: String,
+        /// IDs of the events that satisfy the condition
+        ///
+        /// For [`QueryCondition::AtLeast`], the smallest `n` matching IDs.
+        /// For [`QueryCondition::Referenced`], the referenced and the referencing event IDs.
+        witnesses: Vec<EventId>,
+    },
+    /// The condition is no longer satisfied (i.e., some events have been deleted)
+    Unsatisfied {
+        /// Query name
+        name: String,
+    },
+}
+
+impl StandingQueryUpdate {
+    fn new(name: String, transition: Transition) -> Self {
+        match transition {
+            Transition::Satisfied(witnesses) => Self::Satisfied { name, witnesses },
+            Transition::Unsatisfied => Self::Unsatisfied { name },
+        }
+    }
+}
+
+impl Client {
+    /// Register a standing query over the local database
+    ///
+    /// Unlike the relay subscriptions, the standing query is evaluated against the database:
+    /// it fires when the events stored by the client (received from the relays, published, fetched and stored)
+    /// start satisfying the [`QueryCondition`].
+    ///
+    /// The changes made directly through [`Client::database`] aren't observed.
+    ///
+    /// The condition is evaluated immediately against the existing data
+    /// and then incrementally, at every database change.
+    ///
+    /// The returned stream yields the transitions:
+    /// [`StandingQueryUpdate::Satisfied`] when the condition becomes satisfied
+    /// and [`StandingQueryUpdate::Unsatisfied`] if it's no longer satisfied (i.e., after a deletion).
+    ///
+    /// The query is dropped when the stream is dropped.
+    pub fn standing_query(
+        &self,
+        query: StandingQuery,
+    ) -> BoxedStream<'static, StandingQueryUpdate> {
+        let database: Arc<dyn NostrDatabase> = self.0.database.inner().clone();
+
+        // Subscribe before the initial evaluation, to not lose the changes
+        let mut changes = self.0.database.subscribe();
+
+        let (tx, rx) = mpsc::channel(UPDATES_CHANNEL_SIZE);
+
+        task::spawn(async move {
+            let StandingQuery {
+                filter,
+                condition,
+                name,
+            } = query;
+
+            let mut state: QueryState = QueryState::new(filter, condition);
+
+            // Initial evaluation
+            if let Err(e) = state.load(database.as_ref()).await {
+                tracing::error!(name = %name, error = %e, "Failed to load standing query.");
+            }
+
+            loop {
+                if let Some(transition) = state.evaluate() {
+                    let update = StandingQueryUpdate::new(name.clone(), transition);
+
+                    if tx.send(update).await.is_err() {
+                        break;
+                    }
+                }
+
+                let change: Option<DatabaseChange> = tokio::select! {
+                    change = changes.recv() => match change {
+                        Ok(change) => Some(change),
+                        // Some changes have been lost
+                        Err(RecvError::Lagged(..)) => None,
+                        Err(RecvError::Closed) => break,
+                    },
+                    // The stream has been dropped
+                    _ = tx.closed() => break,
+                };
+
+                let res = match change {
+                    Some(DatabaseChange::Saved(event)) => {
+                        state.insert(&event);
+                        state.remove_stale(&event, database.as_ref()).await
+                    }
+                    Some(DatabaseChange::Removed) => state.prune(database.as_ref()).await,
+                    // Both saved and removed events may have been lost: reload the state
+                    None => state.load(database.as_ref()).await,
+                };
+
+                if let Err(e) = res {
+                    tracing::error!(name = %name, error = %e, "Failed to update standing query.");
+                }
+            }
+
+            tracing::debug!(name = %name, "Standing query exited.");
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use nostr::prelude::*;
+    use nostr_memory::MemoryDatabase;
+
+    use super::*;
+
+    async fn next(
+        stream: &mut BoxedStream<'static, StandingQueryUpdate>,
+    ) -> Option<StandingQueryUpdate> {
+        tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    fn references(keys: &Keys, target: EventId, n: usize) -> Vec<Event> {
+        (0..n)
+            .map(|i| {
+                EventBuilder::text_note(format!("reply {i}"))
+                    .tag(Tag::event(target))
+                    .sign(keys)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_registered_before_and_after() {
+        let client = Client::builder()
+            .database(MemoryDatabase::unbounded())
+            .build();
+        let keys = Keys::generate();
+        let target = EventId::all_zeros();
+        let query = StandingQuery::new(
+            "replies",
+            Filter::new().event(target),
+            QueryCondition::AtLeast(3),
+        );
+
+        // Register before the data arrives
+        let mut before = client.standing_query(query.clone());
+
+        let events = references(&keys, target, 3);
+        for event in events.iter() {
+            client.pool().database().save_event(event).await.unwrap();
+        }
+
+        // Register after the data arrived
+        let mut after = client.standing_query(query);
+
+        let mut witnesses: Vec<EventId> = events.iter().map(|e| e.id).collect();
+        witnesses.sort();
+        let expected = StandingQueryUpdate::Satisfied {
+            name: String::from("replies"),
+            witnesses,
+        };
+
+        assert_eq!(next(&mut before).await, Some(expected.clone()));
+        assert_eq!(next(&mut after).await, Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_reload_on_lag() {
+        let client = Client::builder()
+            .database(MemoryDatabase::unbounded())
+            .notification_channel_size(NonZeroUsize::new(1).unwrap())
+            .build();
+        let keys = Keys::generate();
+        let target = EventId::all_zeros();
+
+        let mut stream = client.standing_query(StandingQuery::new(
+            "replies",
+            Filter::new().event(target),
+            QueryCondition::AtLeast(5),
+        ));
+
+        // Wait for the initial evaluation
+        assert_eq!(next(&mut stream).await, None);
+
+        // Most of the changes are lost
+        let events = references(&keys, target, 5);
+        client
+            .pool()
+            .database()
+            .save_events(events.clone())
+            .await
+            .unwrap();
+
+        let mut witnesses: Vec<EventId> = events.iter().map(|e| e.id).collect();
+        witnesses.sort();
+        assert_eq!(
+            next(&mut stream).await,
+            Some(StandingQueryUpdate::Satisfied {
+                name: String::from("replies"),
+                witnesses,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_database_not_wrapped() {
+        let database: Arc<dyn NostrDatabase> = Arc::new(MemoryDatabase::unbounded());
+        let client = Client::builder().database(database.clone()).build();
+
+        // Same instance, so it can be downcast to the backend
+        assert_eq!(
+            Arc::as_ptr(client.database()) as *const (),
+            Arc::as_ptr(&database) as *const ()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deletion_unsatisfies() {
+        let database = MemoryDatabase::builder().process_nip09(true).build();
+        let client = Client::builder().database(database).build();
+        let keys = Keys::generate();
+
+        let definition = EventBuilder::new(Kind::BadgeDefinition, "")
+            .tag(Tag::identifier("badge"))
+            .sign(&keys)
+            .unwrap();
+        let award = EventBuilder::new(Kind::BadgeAward, "")
+            .tag(Tag::coordinate(definition.coordinate().unwrap(), None))
+            .sign(&keys)
+            .unwrap();
+
+        let mut stream = client.standing_query(StandingQuery::new(
+            "badge",
+            Filter::new().kind(Kind::BadgeDefinition),
+            QueryCondition::Referenced(Filter::new().kind(Kind::BadgeAward)),
+        ));
+
+        client
+            .pool()
+            .database()
+            .save_event(&definition)
+            .await
+            .unwrap();
+        assert_eq!(next(&mut stream).await, None);
+
+        client.pool().database().save_event(&award).await.unwrap();
+        assert_eq!(
+            next(&mut stream).await,
+            Some(StandingQueryUpdate::Satisfied {
+                name: String::from("badge"),
+                witnesses: vec![definition.id, award.id],
+            })
+        );
+
+        // Delete the award with a NIP-09 request
+        let deletion = EventBuilder::delete(EventDeletionRequest::new().id(award.id))
+            .sign(&keys)
+            .unwrap();
+        client
+            .pool()
+            .database()
+            .save_event(&deletion)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            next(&mut stream).await,
+            Some(StandingQueryUpdate::Unsatisfied {
+                name: String::from("badge"),
+            })
+        );
+
+        // Award again
+        let award = EventBuilder::new(Kind::BadgeAward, "again")
+            .tag(Tag::coordinate(definition.coordinate().unwrap(), None))
+            .sign(&keys)
+            .unwrap();
+        client.pool().database().save_event(&award).await.unwrap();
+        assert_eq!(
+            next(&mut stream).await,
+            Some(StandingQueryUpdate::Satisfied {
+                name: String::from("badge"),
+                witnesses: vec![definition.id, award.id],
+            })
+        );
+    }
+}
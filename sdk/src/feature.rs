@@ -0,0 +1,37 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Feature markers
+//!
+//! When a feature is disabled, the main entry points of its APIs are kept as placeholders bounded by these traits,
+//! so using them fails with an error that names the missing feature, instead of a "method not found" one.
+//!
+//! The placeholders return the [`RequiresGossipFeature::Disabled`] (or [`RequiresNegentropyFeature::Disabled`]) type:
+//! as the bound can't be satisfied, the compiler reports only the missing feature, without follow-up errors.
+
+/// Marker for the APIs that require the `gossip` feature
+///
+/// Intentionally not implemented for any type.
+#[diagnostic::on_unimplemented(
+    message = "this API requires the `gossip` feature of `nostr-sdk`",
+    label = "the `gossip` feature is disabled",
+    note = "enable it in your `Cargo.toml`: `nostr-sdk = {{ version = \"...\", features = [\"gossip\"] }}`"
+)]
+pub trait RequiresGossipFeature {
+    /// Return type of the placeholders
+    type Disabled;
+}
+
+/// Marker for the APIs that require the `negentropy` feature
+///
+/// Intentionally not implemented for any type.
+#[diagnostic::on_unimplemented(
+    message = "this API requires the `negentropy` feature of `nostr-sdk`",
+    label = "the `negentropy` feature is disabled",
+    note = "enable it in your `Cargo.toml`: `nostr-sdk = {{ version = \"...\", features = [\"negentropy\"] }}`"
+)]
+pub trait RequiresNegentropyFeature {
+    /// Return type of the placeholders
+    type Disabled;
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Interval
+
+use std::time::Duration;
+
+use async_utility::time;
+use universal_time::Instant;
+
+/// Periodic ticker, firing on a fixed schedule
+///
+/// The ticks are scheduled at `start + n * period`, so, unlike sleeping in a loop,
+/// the time spent between the ticks doesn't make the schedule drift.
+///
+/// # Missed ticks
+///
+/// If the caller is late (i.e., it was busy for more than a period), the missed ticks are skipped:
+/// the late tick fires immediately and the next one at the following slot of the original schedule.
+/// The missed ticks are never fired in a burst to catch up.
+#[derive(Debug)]
+pub(crate) struct Interval {
+    period: Duration,
+    next: Instant,
+}
+
+impl Interval {
+    /// New interval
+    ///
+    /// The first tick fires immediately.
+    pub(crate) fn new(period: Duration) -> Self {
+        assert!(!period.is_zero(), "the interval period must be non-zero");
+
+        Self {
+            period,
+            next: Instant::now(),
+        }
+    }
+
+    /// Wait for the next tick
+    pub(crate) async fn tick(&mut self) {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            time::sleep(wait).await;
+        }
+
+        // Schedule the next tick, skipping the missed ones
+        let late: Duration = Instant::now()
+            .checked_duration_since(self.next)
+            .unwrap_or_default();
+        let missed: u32 = (late.as_nanos() / self.period.as_nanos())
+            .try_into()
+            .unwrap_or(u32::MAX);
+        self.next = self.next + self.period.saturating_mul(missed.saturating_add(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interval_fixed_schedule() {
+        let period = Duration::from_millis(50);
+        let start = Instant::now();
+        let mut interval = Interval::new(period);
+
+        for _ in 0..5 {
+            interval.tick().await;
+            // Work between the ticks doesn't shift the schedule
+            time::sleep(Duration::from_millis(20)).await;
+        }
+
+        // First tick immediate, then 4 periods
+        let elapsed = start.elapsed();
+        assert!(elapsed >= period * 4, "{elapsed:?}");
+        assert!(
+            elapsed < period * 4 + Duration::from_millis(45),
+            "{elapsed:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interval_skips_missed_ticks() {
+        let period = Duration::from_millis(50);
+        let start = Instant::now();
+        let mut interval = Interval::new(period);
+        interval.tick().await;
+
+        // Miss 3 ticks
+        time::sleep(Duration::from_millis(170)).await;
+
+        // The late tick fires immediately, without a burst of the missed ones
+        interval.tick().await;
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        // The next one is on the original schedule (at 200 ms)
+        interval.tick().await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(240), "{elapsed:?}");
+    }
+}
@@ -9,7 +9,10 @@
 pub mod authenticator;
 pub mod client;
 mod events_tracker;
+#[doc(hidden)]
+pub mod feature;
 mod future;
+mod interval;
 pub mod monitor;
 pub mod policy;
 mod pool;
@@ -19,4 +22,5 @@ pub mod proxy;
 pub mod relay;
 mod shared;
 mod stream;
+mod throttle;
 pub mod transport;
@@ -4,12 +4,23 @@
 
 //! Policies
 
+use std::collections::HashSet;
 use std::fmt;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use nostr::{Event, RelayUrl, SubscriptionId};
+use lru::LruCache;
+use nostr::nips::nip56::{ReportSummary, ReportTarget};
+use nostr::{Event, EventId, PublicKey, RelayUrl, SubscriptionId};
+use nostr_database::{Events, IntoNostrDatabase, NostrDatabase};
+use universal_time::Instant;
 
 use crate::future::BoxedFuture;
 
+const DEFAULT_MAX_RATE_LIMITED_AUTHORS: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+const MAX_RATE_LIMITED_EVENTS: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
 /// Policy Error
 #[derive(Debug)]
 pub enum PolicyError {
@@ -116,10 +127,475 @@ pub trait AdmitPolicy: fmt::Debug + Send + Sync {
         let _ = (relay_url, subscription_id, event);
         Box::pin(async move { Ok(AdmitStatus::Success) })
     }
+
+    /// Admit only if both `self` and `other` admit
+    ///
+    /// `other` isn't checked if `self` rejects.
+    #[inline]
+    fn and<P>(self, other: P) -> AndPolicy<Self, P>
+    where
+        Self: Sized,
+        P: AdmitPolicy,
+    {
+        AndPolicy {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Admit if either `self` or `other` admits
+    ///
+    /// `other` isn't checked if `self` admits.
+    #[inline]
+    fn or<P>(self, other: P) -> OrPolicy<Self, P>
+    where
+        Self: Sized,
+        P: AdmitPolicy,
+    {
+        OrPolicy {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Invert the event decisions of `self`
+    ///
+    /// Only [`AdmitPolicy::admit_event`] is inverted: the relays, connections and authentications
+    /// keep the decisions of `self`, unless inverted with [`NotPolicy::relays`], [`NotPolicy::connections`] and [`NotPolicy::auth`].
+    #[inline]
+    fn not(self) -> NotPolicy<Self>
+    where
+        Self: Sized,
+    {
+        NotPolicy {
+            inner: self,
+            relays: false,
+            connections: false,
+            auth: false,
+        }
+    }
+}
+
+impl<T> AdmitPolicy for Arc<T>
+where
+    T: AdmitPolicy + ?Sized,
+{
+    fn admit_relay<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        self.as_ref().admit_relay(relay_url)
+    }
+
+    fn admit_connection<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        self.as_ref().admit_connection(relay_url)
+    }
+
+    fn admit_auth<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        self.as_ref().admit_auth(relay_url)
+    }
+
+    fn admit_event<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+        subscription_id: &'a SubscriptionId,
+        event: &'a Event,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        self.as_ref().admit_event(relay_url, subscription_id, event)
+    }
+}
+
+/// Admission request, to share the combinators logic across the [`AdmitPolicy`] methods
+#[derive(Clone, Copy)]
+enum Admission<'a> {
+    Relay(&'a RelayUrl),
+    Connection(&'a RelayUrl),
+    Auth(&'a RelayUrl),
+    Event(&'a RelayUrl, &'a SubscriptionId, &'a Event),
+}
+
+impl<'a> Admission<'a> {
+    fn check<P>(self, policy: &'a P) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>>
+    where
+        P: AdmitPolicy + ?Sized,
+    {
+        match self {
+            Self::Relay(relay_url) => policy.admit_relay(relay_url),
+            Self::Connection(relay_url) => policy.admit_connection(relay_url),
+            Self::Auth(relay_url) => policy.admit_auth(relay_url),
+            Self::Event(relay_url, subscription_id, event) => {
+                policy.admit_event(relay_url, subscription_id, event)
+            }
+        }
+    }
+}
+
+/// Implement the [`AdmitPolicy`] methods by dispatching an [`Admission`] to `$fn`
+macro_rules! impl_admit_policy_combinator {
+    ($fn:ident) => {
+        fn admit_relay<'a>(
+            &'a self,
+            relay_url: &'a RelayUrl,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(self.$fn(Admission::Relay(relay_url)))
+        }
+
+        fn admit_connection<'a>(
+            &'a self,
+            relay_url: &'a RelayUrl,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(self.$fn(Admission::Connection(relay_url)))
+        }
+
+        fn admit_auth<'a>(
+            &'a self,
+            relay_url: &'a RelayUrl,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(self.$fn(Admission::Auth(relay_url)))
+        }
+
+        fn admit_event<'a>(
+            &'a self,
+            relay_url: &'a RelayUrl,
+            subscription_id: &'a SubscriptionId,
+            event: &'a Event,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(self.$fn(Admission::Event(relay_url, subscription_id, event)))
+        }
+    };
+}
+
+/// Admission policy that admits only if both the policies admit
+///
+/// Check [`AdmitPolicy::and`].
+#[derive(Debug, Clone)]
+pub struct AndPolicy<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AndPolicy<A, B>
+where
+    A: AdmitPolicy,
+    B: AdmitPolicy,
+{
+    async fn check(&self, admission: Admission<'_>) -> Result<AdmitStatus, PolicyError> {
+        match admission.check(&self.first).await? {
+            AdmitStatus::Success => admission.check(&self.second).await,
+            // Stop at the first rejection
+            rejected => Ok(rejected),
+        }
+    }
+}
+
+impl<A, B> AdmitPolicy for AndPolicy<A, B>
+where
+    A: AdmitPolicy,
+    B: AdmitPolicy,
+{
+    impl_admit_policy_combinator!(check);
+}
+
+/// Admission policy that admits if either of the policies admits
+///
+/// Check [`AdmitPolicy::or`].
+#[derive(Debug, Clone)]
+pub struct OrPolicy<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> OrPolicy<A, B>
+where
+    A: AdmitPolicy,
+    B: AdmitPolicy,
+{
+    async fn check(&self, admission: Admission<'_>) -> Result<AdmitStatus, PolicyError> {
+        match admission.check(&self.first).await? {
+            // Stop at the first admission
+            AdmitStatus::Success => Ok(AdmitStatus::Success),
+            // Both rejected: return the last rejection
+            AdmitStatus::Rejected { .. } => admission.check(&self.second).await,
+        }
+    }
+}
+
+impl<A, B> AdmitPolicy for OrPolicy<A, B>
+where
+    A: AdmitPolicy,
+    B: AdmitPolicy,
+{
+    impl_admit_policy_combinator!(check);
+}
+
+/// Admission policy that inverts the decisions of the inner policy
+///
+/// Check [`AdmitPolicy::not`].
+#[derive(Debug, Clone)]
+pub struct NotPolicy<P> {
+    inner: P,
+    relays: bool,
+    connections: bool,
+    auth: bool,
+}
+
+impl<P> NotPolicy<P>
+where
+    P: AdmitPolicy,
+{
+    /// Invert also [`AdmitPolicy::admit_relay`]
+    ///
+    /// Defaults to `false`
+    #[inline]
+    pub fn relays(mut self, invert: bool) -> Self {
+        self.relays = invert;
+        self
+    }
+
+    /// Invert also [`AdmitPolicy::admit_connection`]
+    ///
+    /// Defaults to `false`
+    #[inline]
+    pub fn connections(mut self, invert: bool) -> Self {
+        self.connections = invert;
+        self
+    }
+
+    /// Invert also [`AdmitPolicy::admit_auth`]
+    ///
+    /// Defaults to `false`
+    #[inline]
+    pub fn auth(mut self, invert: bool) -> Self {
+        self.auth = invert;
+        self
+    }
+
+    async fn check(&self, admission: Admission<'_>) -> Result<AdmitStatus, PolicyError> {
+        let invert: bool = match admission {
+            Admission::Relay(..) => self.relays,
+            Admission::Connection(..) => self.connections,
+            Admission::Auth(..) => self.auth,
+            Admission::Event(..) => true,
+        };
+
+        let status: AdmitStatus = admission.check(&self.inner).await?;
+
+        if !invert {
+            return Ok(status);
+        }
+
+        match status {
+            AdmitStatus::Success => Ok(AdmitStatus::Rejected { reason: None }),
+            AdmitStatus::Rejected { .. } => Ok(AdmitStatus::Success),
+        }
+    }
+}
+
+impl<P> AdmitPolicy for NotPolicy<P>
+where
+    P: AdmitPolicy,
+{
+    impl_admit_policy_combinator!(check);
+}
+
+/// Admission policy that rejects the events reported (NIP-56) by trusted public keys
+///
+/// An event is rejected when the number of trusted public keys that reported it, or its author,
+/// reaches the threshold.
+/// The reports are looked up in the database: to keep them updated,
+/// use the same database of the client and fetch the reports (i.e., with [`Client::reports_about`](crate::client::Client::reports_about)).
+#[derive(Debug)]
+pub struct ReportsAdmitPolicy {
+    database: Arc<dyn NostrDatabase>,
+    trusted: HashSet<PublicKey>,
+    threshold: NonZeroUsize,
+}
+
+impl ReportsAdmitPolicy {
+    /// New policy
+    pub fn new<D, I>(database: D, trusted: I, threshold: NonZeroUsize) -> Self
+    where
+        D: IntoNostrDatabase,
+        I: IntoIterator<Item = PublicKey>,
+    {
+        Self {
+            database: database.into_nostr_database(),
+            trusted: trusted.into_iter().collect(),
+            threshold,
+        }
+    }
+
+    async fn trusted_reporters(&self, target: &ReportTarget) -> Result<usize, PolicyError> {
+        let filter = target.filter().authors(self.trusted.iter().copied());
+        let events: Events = self
+            .database
+            .query(filter)
+            .await
+            .map_err(PolicyError::backend)?;
+        let summary: ReportSummary = ReportSummary::from_events(target, events.iter());
+        Ok(summary.count_from(&self.trusted))
+    }
+}
+
+impl AdmitPolicy for ReportsAdmitPolicy {
+    fn admit_event<'a>(
+        &'a self,
+        _relay_url: &'a RelayUrl,
+        _subscription_id: &'a SubscriptionId,
+        event: &'a Event,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        Box::pin(async move {
+            if self.trusted.is_empty() {
+                return Ok(AdmitStatus::Success);
+            }
+
+            let targets: [ReportTarget; 2] = [
+                ReportTarget::Event {
+                    id: event.id,
+                    author: event.pubkey,
+                },
+                ReportTarget::PublicKey(event.pubkey),
+            ];
+
+            for target in targets.iter() {
+                let reporters: usize = self.trusted_reporters(target).await?;
+
+                if reporters >= self.threshold.get() {
+                    return Ok(AdmitStatus::rejected(format!(
+                        "reported by {reporters} trusted public keys"
+                    )));
+                }
+            }
+
+            Ok(AdmitStatus::Success)
+        })
+    }
+}
+
+/// Admission policy that limits the events received from each author
+///
+/// Every author has a token bucket, refilled at `events / period`, holding up to `burst` tokens:
+/// an event is rejected when the bucket of its author is empty.
+///
+/// The buckets are kept in an LRU cache, so a flood of unique public keys can't exhaust the memory:
+/// when the cache is full, the least recently seen author is evicted (and starts again with a full bucket).
+///
+/// An event is charged only once, also if received from many relays:
+/// the IDs of the last 10_000 admitted events are remembered.
+#[derive(Debug)]
+pub struct RateLimitPolicy {
+    /// Tokens per second
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<LruCache<PublicKey, AuthorBucket>>,
+    /// Already charged events
+    charged: Mutex<LruCache<EventId, ()>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AuthorBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimitPolicy {
+    /// New policy, admitting up to `events` events per `period` and author, with bursts of up to `burst` events
+    ///
+    /// Up to 10_000 authors are tracked: use [`RateLimitPolicy::max_authors`] to change it.
+    pub fn new(events: NonZeroU32, period: Duration, burst: NonZeroU32) -> Self {
+        // Avoid a division by zero
+        let period: f64 = period.as_secs_f64().max(f64::EPSILON);
+
+        Self {
+            rate: events.get() as f64 / period,
+            burst: burst.get() as f64,
+            buckets: Mutex::new(LruCache::new(DEFAULT_MAX_RATE_LIMITED_AUTHORS)),
+            charged: Mutex::new(LruCache::new(MAX_RATE_LIMITED_EVENTS)),
+        }
+    }
+
+    /// Max number of tracked authors (default: 10_000)
+    pub fn max_authors(self, max: NonZeroUsize) -> Self {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .resize(max);
+        self
+    }
+
+    /// Admit the event, charging its author only the first time
+    fn try_admit(&self, event: &Event, now: Instant) -> bool {
+        // Keep the lock while charging, so the concurrent copies of the event are charged once
+        let mut charged = self.charged.lock().unwrap_or_else(|e| e.into_inner());
+
+        if charged.get(&event.id).is_some() {
+            return true;
+        }
+
+        if self.try_take(event.pubkey, now) {
+            charged.put(event.id, ());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try to take a token from the bucket of the author
+    fn try_take(&self, author: PublicKey, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let bucket: &mut AuthorBucket = buckets.get_or_insert_mut(author, || AuthorBucket {
+            tokens: self.burst,
+            last_refill: now,
+        });
+
+        // Refill
+        let elapsed: f64 = now
+            .checked_duration_since(bucket.last_refill)
+            .unwrap_or_default()
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl AdmitPolicy for RateLimitPolicy {
+    fn admit_event<'a>(
+        &'a self,
+        _relay_url: &'a RelayUrl,
+        _subscription_id: &'a SubscriptionId,
+        event: &'a Event,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        Box::pin(async move {
+            if self.try_admit(event, Instant::now()) {
+                Ok(AdmitStatus::Success)
+            } else {
+                Ok(AdmitStatus::rejected(format!(
+                    "rate-limited: too many events from {}",
+                    event.pubkey
+                )))
+            }
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use nostr::{EventBuilder, Keys};
+
     use super::*;
 
     #[test]
@@ -138,4 +614,298 @@ mod tests {
             }
         );
     }
+
+    #[tokio::test]
+    async fn test_reports_admit_policy() {
+        use nostr::nips::nip56::Report;
+        use nostr_memory::MemoryDatabase;
+
+        let database = Arc::new(MemoryDatabase::unbounded());
+        let trusted = Keys::generate();
+        let untrusted = Keys::generate();
+        let spammer = Keys::generate();
+
+        let policy = ReportsAdmitPolicy::new(
+            database.clone(),
+            [trusted.public_key()],
+            NonZeroUsize::new(1).unwrap(),
+        );
+
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let id = SubscriptionId::generate();
+        let event = EventBuilder::text_note("Spam").sign(&spammer).unwrap();
+
+        // Reported by an untrusted public key
+        let target = ReportTarget::PublicKey(spammer.public_key());
+        let report = EventBuilder::report_target(&target, Report::Spam, "")
+            .sign(&untrusted)
+            .unwrap();
+        database.save_event(&report).await.unwrap();
+
+        let status = policy.admit_event(&url, &id, &event).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        // Reported by a trusted public key
+        let target = ReportTarget::Event {
+            id: event.id,
+            author: spammer.public_key(),
+        };
+        let report = EventBuilder::report_target(&target, Report::Spam, "")
+            .sign(&trusted)
+            .unwrap();
+        database.save_event(&report).await.unwrap();
+
+        let status = policy.admit_event(&url, &id, &event).await.unwrap();
+        assert!(matches!(status, AdmitStatus::Rejected { .. }));
+    }
+
+    #[test]
+    fn test_rate_limit_policy_bucket() {
+        let policy = RateLimitPolicy::new(
+            NonZeroU32::new(60).unwrap(),
+            Duration::from_secs(60),
+            NonZeroU32::new(2).unwrap(),
+        );
+        let author = Keys::generate().public_key();
+        let other = Keys::generate().public_key();
+        let now = Instant::now();
+
+        // Burst
+        assert!(policy.try_take(author, now));
+        assert!(policy.try_take(author, now));
+        assert!(!policy.try_take(author, now));
+
+        // The other authors have their own bucket
+        assert!(policy.try_take(other, now));
+
+        // Refilled at 1 event per second
+        assert!(!policy.try_take(author, now + Duration::from_millis(500)));
+        assert!(policy.try_take(author, now + Duration::from_secs(1)));
+        assert!(!policy.try_take(author, now + Duration::from_secs(1)));
+
+        // Never over the burst
+        let later = now + Duration::from_secs(60);
+        assert!(policy.try_take(author, later));
+        assert!(policy.try_take(author, later));
+        assert!(!policy.try_take(author, later));
+    }
+
+    #[test]
+    fn test_rate_limit_policy_eviction() {
+        let policy = RateLimitPolicy::new(
+            NonZeroU32::new(1).unwrap(),
+            Duration::from_secs(60),
+            NonZeroU32::new(1).unwrap(),
+        )
+        .max_authors(NonZeroUsize::new(2).unwrap());
+        let now = Instant::now();
+
+        let authors: Vec<PublicKey> = (0..10).map(|_| Keys::generate().public_key()).collect();
+        for author in authors.iter() {
+            assert!(policy.try_take(*author, now));
+        }
+
+        assert_eq!(policy.buckets.lock().unwrap().len(), 2);
+
+        // The least recently seen author was evicted, so its bucket is full again
+        assert!(policy.try_take(authors[0], now));
+        // The most recently seen author is still limited
+        assert!(!policy.try_take(authors[9], now));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_policy_same_event_from_many_relays() {
+        let policy = RateLimitPolicy::new(
+            NonZeroU32::new(1).unwrap(),
+            Duration::from_secs(60),
+            NonZeroU32::new(2).unwrap(),
+        );
+        let keys = Keys::generate();
+        let relay1 = RelayUrl::parse("wss://relay1.example.com").unwrap();
+        let relay2 = RelayUrl::parse("wss://relay2.example.com").unwrap();
+        let id = SubscriptionId::generate();
+        let first = EventBuilder::text_note("First").sign(&keys).unwrap();
+        let second = EventBuilder::text_note("Second").sign(&keys).unwrap();
+        let third = EventBuilder::text_note("Third").sign(&keys).unwrap();
+
+        // The same event, received from two relays, costs a single token
+        for relay in [&relay1, &relay2] {
+            let status = policy.admit_event(relay, &id, &first).await.unwrap();
+            assert_eq!(status, AdmitStatus::Success);
+        }
+
+        let status = policy.admit_event(&relay1, &id, &second).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        // Bucket empty
+        let status = policy.admit_event(&relay2, &id, &third).await.unwrap();
+        assert!(matches!(status, AdmitStatus::Rejected { .. }));
+
+        // The already admitted events are still admitted
+        let status = policy.admit_event(&relay2, &id, &second).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_policy_rejection() {
+        let policy = RateLimitPolicy::new(
+            NonZeroU32::new(1).unwrap(),
+            Duration::from_secs(60),
+            NonZeroU32::new(1).unwrap(),
+        );
+        let keys = Keys::generate();
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let id = SubscriptionId::generate();
+        let event = EventBuilder::text_note("Hello").sign(&keys).unwrap();
+        let other = EventBuilder::text_note("World").sign(&keys).unwrap();
+
+        let status = policy.admit_event(&url, &id, &event).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        let status = policy.admit_event(&url, &id, &other).await.unwrap();
+        assert!(
+            matches!(status, AdmitStatus::Rejected { reason: Some(reason) } if reason.starts_with("rate-limited:"))
+        );
+    }
+
+    /// Admit the relays and the events with the given outcome, counting the calls
+    #[derive(Debug)]
+    struct Fixed {
+        admit: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Fixed {
+        fn new(admit: bool) -> Arc<Self> {
+            Arc::new(Self {
+                admit,
+                calls: Default::default(),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+
+        fn status(&self) -> AdmitStatus {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+            if self.admit {
+                AdmitStatus::Success
+            } else {
+                AdmitStatus::rejected("fixed")
+            }
+        }
+    }
+
+    impl AdmitPolicy for Fixed {
+        fn admit_relay<'a>(
+            &'a self,
+            _relay_url: &'a RelayUrl,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(async move { Ok(self.status()) })
+        }
+
+        fn admit_event<'a>(
+            &'a self,
+            _relay_url: &'a RelayUrl,
+            _subscription_id: &'a SubscriptionId,
+            _event: &'a Event,
+        ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+            Box::pin(async move { Ok(self.status()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_and_policy() {
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+
+        let (a, b) = (Fixed::new(true), Fixed::new(true));
+        let status = a.clone().and(b.clone()).admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+        assert_eq!((a.calls(), b.calls()), (1, 1));
+
+        // Stop at the first rejection
+        let (a, b) = (Fixed::new(false), Fixed::new(true));
+        let status = a.clone().and(b.clone()).admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::rejected("fixed"));
+        assert_eq!((a.calls(), b.calls()), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_or_policy() {
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+
+        // Stop at the first admission
+        let (a, b) = (Fixed::new(true), Fixed::new(false));
+        let status = a.clone().or(b.clone()).admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+        assert_eq!((a.calls(), b.calls()), (1, 0));
+
+        let (a, b) = (Fixed::new(false), Fixed::new(true));
+        let status = a.clone().or(b.clone()).admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+        assert_eq!((a.calls(), b.calls()), (1, 1));
+
+        let (a, b) = (Fixed::new(false), Fixed::new(false));
+        let status = a.clone().or(b.clone()).admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::rejected("fixed"));
+    }
+
+    #[tokio::test]
+    async fn test_not_policy() {
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let id = SubscriptionId::generate();
+        let event = EventBuilder::text_note("Hello")
+            .sign(&Keys::generate())
+            .unwrap();
+
+        let status = Fixed::new(false)
+            .not()
+            .admit_event(&url, &id, &event)
+            .await
+            .unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        let status = Fixed::new(true)
+            .not()
+            .admit_event(&url, &id, &event)
+            .await
+            .unwrap();
+        assert_eq!(status, AdmitStatus::Rejected { reason: None });
+
+        // The relays, connections and authentications aren't inverted by default
+        let policy = Fixed::new(true).not();
+        let status = policy.admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+        let status = policy.admit_connection(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+        let status = policy.admit_auth(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        // Unless requested
+        let status = Fixed::new(true)
+            .not()
+            .relays(true)
+            .admit_relay(&url)
+            .await
+            .unwrap();
+        assert_eq!(status, AdmitStatus::Rejected { reason: None });
+    }
+
+    #[tokio::test]
+    async fn test_combined_policy_as_trait_object() {
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+
+        // Boxed policies can be combined too
+        let boxed: Arc<dyn AdmitPolicy> = Fixed::new(false);
+        let policy: Arc<dyn AdmitPolicy> = Arc::new(boxed.or(Fixed::new(false).not().relays(true)));
+
+        let status = policy.admit_relay(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+
+        // Not implemented by the first policy, so admitted by default
+        let status = policy.admit_connection(&url).await.unwrap();
+        assert_eq!(status, AdmitStatus::Success);
+    }
 }
@@ -7,16 +7,21 @@ use super::RelayPool;
 use crate::authenticator::Authenticator;
 use crate::monitor::Monitor;
 use crate::policy::AdmitPolicy;
+use crate::throttle::SendLimiter;
+use crate::transport::headers::UpgradeHeadersResolver;
 use crate::transport::websocket::WebSocketTransport;
 
 pub(crate) struct RelayPoolBuilder {
     pub(crate) websocket_transport: Arc<dyn WebSocketTransport>,
     pub(crate) admit_policy: Option<Arc<dyn AdmitPolicy>>,
     pub(crate) authenticator: Option<Arc<dyn Authenticator>>,
+    pub(crate) upgrade_headers: Option<Arc<dyn UpgradeHeadersResolver>>,
     pub(crate) monitor: Option<Monitor>,
     pub(crate) database: Arc<dyn NostrDatabase>,
     pub(crate) max_relays: Option<NonZeroUsize>,
     pub(crate) notification_channel_size: NonZeroUsize,
+    pub(crate) preserve_raw_events: bool,
+    pub(crate) send_limiter: Option<SendLimiter>,
 }
 
 impl RelayPoolBuilder {
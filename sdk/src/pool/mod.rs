@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::{Future, IntoFuture};
 use std::iter::Zip;
 use std::mem;
@@ -12,7 +12,8 @@ use std::vec::IntoIter;
 
 use async_utility::task;
 use futures::stream::FuturesUnordered;
-use futures::{Stream, StreamExt, future};
+use futures::{Stream, StreamExt, future, stream};
+use lru::LruCache;
 use nostr_database::prelude::*;
 use tokio::sync::{Mutex, RwLock, broadcast, mpsc};
 
@@ -21,18 +22,59 @@ mod error;
 
 pub(crate) use self::builder::RelayPoolBuilder;
 pub(crate) use self::error::Error;
-use crate::client::{ClientNotification, InnerAckPolicy, Output, SyncSummary};
+#[cfg(feature = "negentropy")]
+use crate::client::SyncSummary;
+use crate::client::{
+    ClientNotification, InnerAckPolicy, Output, PublishOutput, RelayListSummary,
+    StreamOverflowPolicy,
+};
 use crate::monitor::Monitor;
 use crate::policy::AdmitStatus;
+#[cfg(feature = "negentropy")]
+use crate::relay::SyncOptions;
 use crate::relay::{
-    self, AtomicRelayCapabilities, Relay, RelayCapabilities, RelayOptions, ReqExitPolicy,
-    SubscribeAutoCloseOptions, SyncOptions,
+    self, AtomicRelayCapabilities, RateLimitClass, Relay, RelayCapabilities, RelayOptions,
+    RelayShutdownSummary, ReqExitPolicy, SubscribeAutoCloseOptions,
 };
 use crate::shared::SharedState;
 use crate::stream::ReceiverStream;
 
 pub(super) type Relays = HashMap<RelayUrl, Relay>;
+
+/// Default number of event IDs remembered to deduplicate a stream
+pub(crate) const DEFAULT_STREAM_DEDUP_CAPACITY: NonZeroUsize = NonZeroUsize::new(100_000).unwrap();
+/// Default capacity of the buffer of a stream
+// NOTE: the events are deduplicated and the send method awaits, so a huge capacity isn't necessary.
+pub(crate) const DEFAULT_STREAM_BUFFER: NonZeroUsize = NonZeroUsize::new(1024).unwrap();
+
+const READ_WRITE: RelayCapabilities =
+    RelayCapabilities::from_bits(RelayCapabilities::READ.bits() | RelayCapabilities::WRITE.bits());
 type EventStream = Pin<Box<dyn Stream<Item = (RelayUrl, Result<Event, relay::Error>)> + Send>>;
+type SourcedEventStream =
+    Pin<Box<dyn Stream<Item = (HashSet<RelayUrl>, Result<Event, relay::Error>)> + Send>>;
+type RelayEventStream = Pin<Box<dyn Stream<Item = Result<Event, relay::Error>> + Send>>;
+type RelayStreams = Zip<IntoIter<RelayUrl>, IntoIter<Result<RelayEventStream, relay::Error>>>;
+
+/// Stream options
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StreamOptions {
+    /// Max number of event IDs remembered to deduplicate the events, or `None` to not deduplicate them
+    pub(crate) dedup: Option<NonZeroUsize>,
+    /// Capacity of the buffer
+    pub(crate) buffer: NonZeroUsize,
+    /// What to do when the buffer is full
+    pub(crate) overflow: StreamOverflowPolicy,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            dedup: Some(DEFAULT_STREAM_DEDUP_CAPACITY),
+            buffer: DEFAULT_STREAM_BUFFER,
+            overflow: StreamOverflowPolicy::Block,
+        }
+    }
+}
 
 // IMPORTANT: we rely on the Drop trait for shutting down the pool,
 // so it's important that the RelayPool can't be cloned, otherwise may cause a non-expected shutdown.
@@ -70,8 +112,11 @@ impl RelayPool {
                 builder.websocket_transport,
                 builder.admit_policy,
                 builder.authenticator,
+                builder.upgrade_headers,
                 builder.monitor,
-            ),
+                builder.preserve_raw_events,
+            )
+            .with_send_limiter(builder.send_limiter),
             relays: RwLock::new(HashMap::new()),
             notification_sender,
             shutdown: AtomicBool::new(false),
@@ -93,6 +138,31 @@ impl RelayPool {
         shutdown(&self.shutdown, &mut relays, &self.notification_sender)
     }
 
+    pub(crate) async fn graceful_shutdown(
+        &self,
+        timeout: Duration,
+    ) -> HashMap<RelayUrl, RelayShutdownSummary> {
+        let summaries: HashMap<RelayUrl, RelayShutdownSummary> = {
+            // Lock with read shared access
+            let relays = self.relays.read().await;
+
+            // Flush all the relays concurrently
+            let list = future::join_all(
+                relays
+                    .values()
+                    .map(|relay| relay.graceful_shutdown(timeout)),
+            )
+            .await;
+
+            relays.keys().cloned().zip(list).collect()
+        };
+
+        // Shutdown
+        self.shutdown().await;
+
+        summaries
+    }
+
     #[inline]
     pub(crate) fn notifications(&self) -> broadcast::Receiver<ClientNotification> {
         self.notification_sender.subscribe()
@@ -119,9 +189,32 @@ impl RelayPool {
             .collect()
     }
 
-    #[inline]
+    /// Get the READ relays to use for the subscriptions
+    ///
+    /// The relays that rate-limited the subscriptions are temporarily skipped, unless all of them did.
     pub(crate) async fn read_relay_urls(&self) -> HashSet<RelayUrl> {
-        self.relay_urls_with_any_cap(RelayCapabilities::READ).await
+        let relays = self.relays.read().await;
+
+        let mut all: HashSet<RelayUrl> = HashSet::new();
+        let mut available: HashSet<RelayUrl> = HashSet::new();
+
+        for (url, relay) in filter_relays_with_any_cap(&relays, RelayCapabilities::READ) {
+            if relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Req)
+                .is_none()
+            {
+                available.insert(url.clone());
+            }
+
+            all.insert(url.clone());
+        }
+
+        if available.is_empty() {
+            return all;
+        }
+
+        available
     }
 
     #[inline]
@@ -226,6 +319,133 @@ impl RelayPool {
         Ok(true)
     }
 
+    /// Apply a relay list to the READ and WRITE relays of the pool
+    ///
+    /// Relays not in the list are removed, new ones are added and connected and
+    /// the capabilities of the others are updated, without reconnecting them.
+    pub(crate) async fn apply_relay_list(
+        &self,
+        list: HashMap<RelayUrl, RelayCapabilities>,
+        opts: RelayOptions,
+    ) -> Result<RelayListSummary, Error> {
+        // Check if the pool has been shutdown
+        if self.is_shutdown() {
+            return Err(Error::Shutdown);
+        }
+
+        let mut summary: RelayListSummary = RelayListSummary::default();
+
+        // Check the new relays against the admission policy, before acquiring the lock
+        let mut list: HashMap<RelayUrl, RelayCapabilities> = list;
+        if let Some(policy) = &self.state.admit_policy {
+            let candidates: Vec<RelayUrl> = {
+                let relays = self.relays.read().await;
+                list.keys()
+                    .filter(|url| !relays.contains_key(*url))
+                    .cloned()
+                    .collect()
+            };
+
+            for url in candidates {
+                if let AdmitStatus::Rejected { .. } = policy.admit_relay(&url).await? {
+                    list.remove(&url);
+                    summary.rejected.insert(url);
+                }
+            }
+        }
+
+        // Acquire write lock
+        let mut relays = self.relays.write().await;
+
+        // Check the limit, considering the relays that will be removed
+        if let Some(max) = self.max_relays.map(|m| m.get()) {
+            let to_add: usize = list.keys().filter(|url| !relays.contains_key(*url)).count();
+            let to_remove: usize = relays
+                .iter()
+                .filter(|(url, relay)| {
+                    !list.contains_key(*url)
+                        && relay.capabilities().has_any(READ_WRITE)
+                        && !relay.capabilities().has_gossip()
+                })
+                .count();
+
+            if relays.len() - to_remove + to_add > max {
+                return Err(Error::TooManyRelays { limit: max });
+            }
+        }
+
+        // Remove the READ and WRITE relays that are not in the list
+        let to_remove: Vec<RelayUrl> = relays
+            .iter()
+            .filter(|(url, relay)| {
+                !list.contains_key(*url) && relay.capabilities().has_any(READ_WRITE)
+            })
+            .map(|(url, ..)| url.clone())
+            .collect();
+
+        for url in to_remove {
+            if let Some(relay) = relays.remove(&url) {
+                if can_remove_relay(&relay) {
+                    relay.disconnect();
+                } else {
+                    relays.insert(url.clone(), relay);
+                }
+
+                summary.removed.insert(url);
+            }
+        }
+
+        // Add the new relays and update the existing ones
+        for (url, capabilities) in list.into_iter() {
+            match relays.get(&url) {
+                Some(relay) => {
+                    let current: RelayCapabilities = relay.capabilities().load();
+
+                    // Unchanged
+                    if current.bits() & READ_WRITE.bits() == capabilities.bits() {
+                        continue;
+                    }
+
+                    // Replace READ and WRITE capabilities, keeping the others
+                    let mut new: RelayCapabilities = current;
+                    new.remove(READ_WRITE);
+                    new.add(capabilities);
+                    relay.capabilities().store(new);
+
+                    // The relay wasn't a READ or WRITE one (i.e., a gossip relay): it's new for the list
+                    if current.has_any(READ_WRITE) {
+                        summary.updated.insert(url);
+                    } else {
+                        relay.connect();
+                        summary.added.insert(url);
+                    }
+                }
+                None => {
+                    // Compose new relay
+                    let mut relay: Relay = Relay::new_shared(
+                        url.clone(),
+                        self.state.clone(),
+                        capabilities,
+                        opts.clone(),
+                    );
+
+                    // Set notification sender
+                    relay.set_notification_sender(self.notification_sender.clone());
+
+                    // Connect
+                    relay.connect();
+
+                    // Insert relay into map
+                    relays.insert(url.clone(), relay);
+
+                    summary.added.insert(url);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
     pub(crate) async fn remove_relay(
         &self,
         url: Cow<'_, RelayUrl>,
@@ -434,6 +654,10 @@ impl RelayPool {
         msg: ClientMessage<'_>,
         wait_until_sent: Option<Duration>,
     ) -> Result<Output<()>, Error> {
+        if self.is_shutdown() {
+            return Err(Error::Shutdown);
+        }
+
         // Check if urls set is empty
         if set.is_empty() {
             return Err(Error::NoRelaysSpecified);
@@ -486,10 +710,14 @@ impl RelayPool {
         wait_policy: InnerAckPolicy,
         wait_for_ok_timeout: Duration,
         wait_for_authentication_timeout: Duration,
-    ) -> Result<Output<EventId>, Error>
+    ) -> Result<PublishOutput, Error>
     where
         I: IntoIterator<Item = RelayUrl>,
     {
+        if self.is_shutdown() {
+            return Err(Error::Shutdown);
+        }
+
         // Compose URLs
         let set: HashSet<RelayUrl> = urls.into_iter().collect();
 
@@ -502,7 +730,7 @@ impl RelayPool {
         let relays = self.relays.read().await;
 
         let mut futures: FuturesUnordered<_> = FuturesUnordered::new();
-        let mut output: Output<EventId> = Output::new(event.id);
+        let mut output: PublishOutput = PublishOutput::new(event.id);
 
         let wait_for_ok: bool = match wait_policy {
             InnerAckPolicy::All => true,
@@ -539,7 +767,7 @@ impl RelayPool {
                     output.success.insert(url);
                 }
                 Err(e) => {
-                    output.failed.insert(url, e.to_string());
+                    output.failed.insert(url, e.into());
                 }
             }
         }
@@ -681,6 +909,7 @@ impl RelayPool {
         output
     }
 
+    #[cfg(feature = "negentropy")]
     pub(crate) async fn sync(
         &self,
         targets: HashMap<RelayUrl, (Filter, Vec<(EventId, Timestamp)>)>,
@@ -735,13 +964,13 @@ impl RelayPool {
         Ok(output)
     }
 
-    pub(crate) async fn stream_events(
+    async fn relay_streams(
         &self,
         filters: HashMap<RelayUrl, Vec<Filter>>,
         id: Option<SubscriptionId>,
         timeout: Option<Duration>,
         policy: ReqExitPolicy,
-    ) -> Result<EventStream, Error> {
+    ) -> Result<RelayStreams, Error> {
         // Check if `targets` map is empty
         if filters.is_empty() {
             return Err(Error::NoRelaysSpecified);
@@ -750,10 +979,6 @@ impl RelayPool {
         // Lock with read shared access
         let relays = self.relays.read().await;
 
-        // Create a new channel
-        // NOTE: the events are deduplicated and the send method awaits, so a huge capacity isn't necessary.
-        let (tx, rx) = mpsc::channel(1024);
-
         let mut urls: Vec<RelayUrl> = Vec::with_capacity(filters.len());
         let mut futures = Vec::with_capacity(filters.len());
 
@@ -787,8 +1012,26 @@ impl RelayPool {
         assert_eq!(urls.len(), awaited.len());
 
         // Zip-up urls and futures into a single iterator
-        let streams: Zip<IntoIter<RelayUrl>, IntoIter<Result<_, _>>> =
-            urls.into_iter().zip(awaited);
+        Ok(urls.into_iter().zip(awaited))
+    }
+
+    /// Stream events from the relays
+    pub(crate) async fn stream_events(
+        &self,
+        filters: HashMap<RelayUrl, Vec<Filter>>,
+        id: Option<SubscriptionId>,
+        timeout: Option<Duration>,
+        policy: ReqExitPolicy,
+        opts: StreamOptions,
+    ) -> Result<EventStream, Error> {
+        let streams: RelayStreams = self.relay_streams(filters, id, timeout, policy).await?;
+
+        if let StreamOverflowPolicy::DropOldest = opts.overflow {
+            return Ok(stream_dropping_oldest(streams, opts));
+        }
+
+        // Create a new channel
+        let (tx, rx) = mpsc::channel(opts.buffer.get());
 
         // Single driver task: polls all streams, de-duplicates, forwards
         task::spawn(async move {
@@ -797,8 +1040,14 @@ impl RelayPool {
             #[cfg(target_arch = "wasm32")]
             type OutFuture = Pin<Box<dyn Future<Output = ()>>>;
 
-            // IDs collection, needed to check if an event was already sent to the stream
-            let ids: Arc<Mutex<HashSet<EventId>>> = Arc::new(Mutex::new(HashSet::new()));
+            // Recently seen IDs, needed to check if an event was already sent to the stream.
+            // Bounded, so the memory doesn't grow in a never-ending stream.
+            let ids: Option<Arc<Mutex<SeenIds>>> = opts
+                .dedup
+                .map(|capacity| Arc::new(Mutex::new(SeenIds::new(capacity))));
+
+            // Whether the consumer is behind, to warn once
+            let saturated: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
 
             let mut futures: Vec<OutFuture> = Vec::with_capacity(streams.len());
 
@@ -809,6 +1058,7 @@ impl RelayPool {
                     // Streaming available
                     Ok(mut stream) => {
                         let ids = ids.clone();
+                        let saturated = saturated.clone();
 
                         Box::pin(async move {
                             // Start handling stream items
@@ -820,18 +1070,25 @@ impl RelayPool {
                                     res = stream.next() => {
                                         match res {
                                             Some(Ok(event)) => {
-                                                let mut ids = ids.lock().await;
-
                                                 // Check if ID was already seen or insert into set.
-                                                if ids.insert(event.id) {
-                                                    // Immediately drop the set
-                                                    drop(ids);
-
-                                                    // Send event
-                                                    if tx.send((url.clone(), Ok(event))).await.is_err() {
-                                                        break;
+                                                if let Some(ids) = &ids {
+                                                    if !ids.lock().await.insert(event.id) {
+                                                        continue;
                                                     }
                                                 }
+
+                                                // Back-pressure: wait for the consumer
+                                                let full: bool = tx.capacity() == 0;
+                                                if full && !saturated.swap(true, Ordering::Relaxed) {
+                                                    tracing::warn!(url = %url, "The stream buffer is full: waiting for the consumer.");
+                                                } else if !full {
+                                                    saturated.store(false, Ordering::Relaxed);
+                                                }
+
+                                                // Send event
+                                                if tx.send((url.clone(), Ok(event))).await.is_err() {
+                                                    break;
+                                                }
                                             }
                                             Some(Err(e)) => {
                                                 // Send error
@@ -868,6 +1125,203 @@ impl RelayPool {
         // Return stream
         Ok(Box::pin(ReceiverStream::new(rx)))
     }
+
+    /// Stream events from the relays, aggregating the relays that sent each event
+    ///
+    /// The events are yielded when all the relay streams terminated.
+    /// Up to `capacity` events are held: when exceeded, the oldest one is yielded early.
+    pub(crate) async fn stream_events_with_sources(
+        &self,
+        filters: HashMap<RelayUrl, Vec<Filter>>,
+        id: Option<SubscriptionId>,
+        timeout: Option<Duration>,
+        policy: ReqExitPolicy,
+        capacity: NonZeroUsize,
+    ) -> Result<SourcedEventStream, Error> {
+        let streams = self.relay_streams(filters, id, timeout, policy).await?;
+
+        let (tx, rx) = mpsc::channel(1024);
+
+        task::spawn(async move {
+            let mut merged = merge(streams);
+
+            // The events waiting for the other relays, with the relays that sent them
+            let mut pending: LruCache<EventId, (Event, HashSet<RelayUrl>)> = LruCache::unbounded();
+
+            // The events yielded early, to not yield them again
+            let mut yielded: SeenIds = SeenIds::new(capacity);
+
+            loop {
+                tokio::select! {
+                    // The received dropped, we should terminate the stream
+                    _ = tx.closed() => return,
+                    // Handle stream item
+                    res = merged.next() => {
+                        match res {
+                            Some((url, Ok(event))) => {
+                                // Already pending: add the relay to the sources
+                                if let Some((_, sources)) = pending.peek_mut(&event.id) {
+                                    sources.insert(url);
+                                    continue;
+                                }
+
+                                if yielded.contains(&event.id) {
+                                    continue;
+                                }
+
+                                pending.put(event.id, (event, HashSet::from([url])));
+
+                                // Yield the oldest event if the capacity is exceeded
+                                if pending.len() > capacity.get() {
+                                    if let Some((id, (event, sources))) = pending.pop_lru() {
+                                        yielded.insert(id);
+
+                                        if tx.send((sources, Ok(event))).await.is_err() {
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                            Some((url, Err(e))) => {
+                                // Send error
+                                if tx.send((HashSet::from([url]), Err(e))).await.is_err() {
+                                    return;
+                                }
+                            }
+                            None => break,
+                        }
+                    }
+                }
+            }
+
+            // All the relay streams terminated: yield the events, oldest first
+            while let Some((_, (event, sources))) = pending.pop_lru() {
+                if tx.send((sources, Ok(event))).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        // Return stream
+        Ok(Box::pin(ReceiverStream::new(rx)))
+    }
+}
+
+/// Merge the relay streams, tagging every item with the relay URL
+fn merge(streams: RelayStreams) -> impl Stream<Item = (RelayUrl, Result<Event, relay::Error>)> {
+    stream::select_all(streams.map(|(url, res)| {
+        let stream: RelayEventStream = match res {
+            Ok(stream) => stream,
+            Err(e) => Box::pin(stream::once(future::ready(Err(e)))),
+        };
+        stream.map(move |res| (url.clone(), res))
+    }))
+}
+
+/// Stream the events, discarding the oldest buffered ones when the consumer is behind
+///
+/// The relay streams are never blocked by a slow consumer.
+fn stream_dropping_oldest(streams: RelayStreams, opts: StreamOptions) -> EventStream {
+    // The buffer is kept here: the channel only hands over the items
+    let (tx, rx) = mpsc::channel(1);
+
+    task::spawn(async move {
+        let mut merged = merge(streams);
+        let mut ids: Option<SeenIds> = opts.dedup.map(SeenIds::new);
+        let mut buffer: VecDeque<(RelayUrl, Result<Event, relay::Error>)> = VecDeque::new();
+        let mut dropped: usize = 0;
+        let mut terminated: bool = false;
+
+        while !terminated || !buffer.is_empty() {
+            tokio::select! {
+                // Hand over before buffering more, so only the items the consumer can't take are dropped
+                biased;
+
+                // Hand over the oldest item to the consumer
+                permit = tx.reserve(), if !buffer.is_empty() => {
+                    // The receiver dropped, we should terminate the stream
+                    let Ok(permit) = permit else {
+                        return;
+                    };
+
+                    if let Some(item) = buffer.pop_front() {
+                        permit.send(item);
+                    }
+
+                    // The consumer caught up
+                    if buffer.is_empty() && dropped > 0 {
+                        tracing::debug!(dropped, "The stream consumer caught up.");
+                        dropped = 0;
+                    }
+                }
+                // Handle stream item
+                res = merged.next(), if !terminated => {
+                    let Some((url, res)) = res else {
+                        terminated = true;
+                        continue;
+                    };
+
+                    // Check if ID was already seen
+                    if let (Some(ids), Ok(event)) = (&mut ids, &res) {
+                        if !ids.insert(event.id) {
+                            continue;
+                        }
+                    }
+
+                    // Discard the oldest item if the buffer is full
+                    if buffer.len() >= opts.buffer.get() {
+                        buffer.pop_front();
+
+                        if dropped == 0 {
+                            tracing::warn!(url = %url, "The stream buffer is full: dropping the oldest events.");
+                        }
+
+                        dropped += 1;
+                    }
+
+                    buffer.push_back((url, res));
+                }
+            }
+        }
+    });
+
+    Box::pin(ReceiverStream::new(rx))
+}
+
+/// Bounded set of the recently seen event IDs
+///
+/// When full, the least recently seen ID is forgotten.
+struct SeenIds {
+    ids: LruCache<EventId, ()>,
+    capacity: NonZeroUsize,
+}
+
+impl SeenIds {
+    fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            // Not pre-allocated: the capacity can be big, while most of the streams are short.
+            ids: LruCache::unbounded(),
+            capacity,
+        }
+    }
+
+    #[inline]
+    fn contains(&self, id: &EventId) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Returns `true` if the ID wasn't seen yet
+    fn insert(&mut self, id: EventId) -> bool {
+        if self.ids.put(id, ()).is_some() {
+            return false;
+        }
+
+        if self.ids.len() > self.capacity.get() {
+            self.ids.pop_lru();
+        }
+
+        true
+    }
 }
 
 #[inline]
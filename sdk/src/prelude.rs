@@ -11,6 +11,7 @@
 pub use futures::StreamExt;
 pub use nostr::prelude::*;
 pub use nostr_database::prelude::*;
+#[cfg(feature = "gossip")]
 pub use nostr_gossip::prelude::*;
 
 pub use crate::authenticator::{self, *};
@@ -3,6 +3,7 @@ mod send_event;
 mod send_msg;
 mod stream_events;
 mod subscribe;
+#[cfg(feature = "negentropy")]
 mod sync;
 mod try_connect;
 mod unsubscribe;
@@ -13,6 +14,7 @@ pub use self::send_event::*;
 pub use self::send_msg::*;
 pub use self::stream_events::*;
 pub use self::subscribe::*;
+#[cfg(feature = "negentropy")]
 pub use self::sync::*;
 pub use self::try_connect::*;
 pub use self::unsubscribe::*;
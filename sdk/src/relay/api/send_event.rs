@@ -4,11 +4,11 @@ use std::time::Duration;
 
 use async_utility::time;
 use nostr::message::MachineReadablePrefix;
-use nostr::{ClientMessage, Event, EventId};
+use nostr::{ClientMessage, Event, EventId, Timestamp};
 use tokio::sync::broadcast;
 
 use crate::future::BoxedFuture;
-use crate::relay::{Error, Relay, RelayNotification};
+use crate::relay::{Error, RateLimitClass, Relay, RelayNotification};
 
 /// Send event to relay
 #[must_use = "Does nothing unless you await!"]
@@ -18,6 +18,7 @@ pub struct SendEvent<'relay, 'event> {
     wait_for_ok: bool,
     wait_for_ok_timeout: Duration,
     wait_for_authentication_timeout: Duration,
+    rate_limit_timeout: Duration,
 }
 
 impl<'relay, 'event> SendEvent<'relay, 'event> {
@@ -28,6 +29,7 @@ impl<'relay, 'event> SendEvent<'relay, 'event> {
             wait_for_ok: true,
             wait_for_ok_timeout: Duration::from_secs(10),
             wait_for_authentication_timeout: Duration::from_secs(10),
+            rate_limit_timeout: Duration::from_secs(10),
         }
     }
 
@@ -52,6 +54,34 @@ impl<'relay, 'event> SendEvent<'relay, 'event> {
         self
     }
 
+    /// Max time to wait for a relay rate limit to expire (default: 10 sec)
+    ///
+    /// If the relay rate-limited the publishing, the event is sent when the limit expires.
+    /// If it expires later than this timeout, fails immediately with [`Error::RateLimited`].
+    #[inline]
+    pub fn rate_limit_timeout(mut self, timeout: Duration) -> Self {
+        self.rate_limit_timeout = timeout;
+        self
+    }
+
+    /// Wait for the publishing rate limit, if any, to expire
+    async fn wait_for_rate_limit(&self) -> Result<(), Error> {
+        let stats = self.relay.stats();
+
+        if let Some(until) = stats.rate_limited_until(RateLimitClass::Publish) {
+            let now: Timestamp = Timestamp::now();
+            let wait: Duration = Duration::from_secs(until.as_secs().saturating_sub(now.as_secs()));
+
+            if wait > self.rate_limit_timeout {
+                return Err(Error::RateLimited { until });
+            }
+
+            time::sleep(wait).await;
+        }
+
+        Ok(())
+    }
+
     async fn send(
         &self,
         notifications: &mut broadcast::Receiver<RelayNotification>,
@@ -112,6 +142,9 @@ where
         Box::pin(async move {
             // Health, write permission and number of messages checks are executed in `batch_msg` method.
 
+            // Defer if rate-limited
+            self.wait_for_rate_limit().await?;
+
             // Subscribe to notifications
             let mut notifications = self.relay.inner.internal_notification_sender.subscribe();
 
@@ -127,8 +160,8 @@ where
             if let Some(MachineReadablePrefix::AuthRequired) =
                 MachineReadablePrefix::parse(&message)
             {
-                // Check if NIP42 authenticator is available
-                if self.relay.inner.state.is_authenticator_available() {
+                // Check if the relay can authenticate (NIP42 authenticator available and policy allows it)
+                if self.relay.inner.can_authenticate() {
                     // Wait that relay authenticate
                     wait_for_authentication(
                         &mut notifications,
@@ -162,6 +195,7 @@ mod tests {
 
     use super::*;
     use crate::authenticator::SignerAuthenticator;
+    use crate::relay::{AuthPolicy, RelayOptions};
 
     #[tokio::test]
     async fn test_ok_msg() {
@@ -182,6 +216,86 @@ mod tests {
         relay.send_event(&event).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_rate_limited_send_event() {
+        use crate::transport::mock::MockWebSocketTransport;
+        use crate::transport::websocket::Message;
+
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("Test").sign(&keys).unwrap();
+
+        // The relay rejects the event with a retry-after hint
+        let r = relay.clone();
+        let e = event.clone();
+        let handle = tokio::spawn(async move { r.send_event(&e).await });
+        transport.recv().await.unwrap();
+        transport.push(Message::Text(
+            RelayMessage::ok(event.id, false, "rate-limited: retry after 60s").as_json(),
+        ));
+        assert!(matches!(
+            handle.await.unwrap(),
+            Err(crate::relay::Error::RelayMessage(..))
+        ));
+
+        let until = relay
+            .stats()
+            .rate_limited_until(RateLimitClass::Publish)
+            .unwrap();
+        assert!(until >= Timestamp::now() + 59);
+        assert!(
+            relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Req)
+                .is_none()
+        );
+
+        // Can't wait that long: fail fast, without sending
+        match relay.send_event(&event).await {
+            Err(crate::relay::Error::RateLimited { until: u }) => assert_eq!(u, until),
+            res => panic!("unexpected result: {res:?}"),
+        }
+        assert!(transport.try_recv().is_none());
+
+        // Waits for the limit to expire
+        transport.push(Message::Text(
+            RelayMessage::notice("rate-limited: slow down, try again in 2 seconds").as_json(),
+        ));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(
+            relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Req)
+                .is_some()
+        );
+
+        let r = relay.clone();
+        let e = event.clone();
+        let handle = tokio::spawn(async move { r.send_event(&e).await });
+        transport.recv().await.unwrap();
+        transport.push(Message::Text(
+            RelayMessage::ok(event.id, true, "").as_json(),
+        ));
+        assert_eq!(handle.await.unwrap().unwrap(), event.id);
+
+        // Accepted: the limit is cleared
+        assert!(
+            relay
+                .stats()
+                .rate_limited_until(RateLimitClass::Publish)
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn test_nip42_send_event_without_authenticator() {
         // Mock relay
@@ -235,4 +349,109 @@ mod tests {
         // Send as authenticated
         assert!(relay.send_event(&event).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_nip42_manual_auth_policy() {
+        use crate::transport::mock::MockWebSocketTransport;
+        use crate::transport::websocket::Message;
+
+        let keys = Keys::generate();
+
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .authenticator(SignerAuthenticator::new(keys.clone()))
+            .opts(RelayOptions::default().auth_policy(AuthPolicy::Manual))
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let mut notifications = relay.inner.internal_notification_sender.subscribe();
+
+        // The challenge is notified and not answered automatically
+        transport.push(Message::Text(RelayMessage::auth("challenge").as_json()));
+        let challenge = loop {
+            if let RelayNotification::AuthChallenge { challenge } =
+                notifications.recv().await.unwrap()
+            {
+                break challenge;
+            }
+        };
+        assert_eq!(challenge, "challenge");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(transport.try_recv().is_none());
+
+        // The relay rejects the first EVENT
+        let event = EventBuilder::text_note("Test").sign(&keys).unwrap();
+        let r = relay.clone();
+        let e = event.clone();
+        let handle = tokio::spawn(async move { r.send_event(&e).await });
+        transport.recv().await.unwrap();
+        transport.push(Message::Text(
+            RelayMessage::ok(event.id, false, "auth-required: please authenticate").as_json(),
+        ));
+
+        // The app answers the challenge
+        let r = relay.clone();
+        let auth = tokio::spawn(async move { r.auth(challenge).await });
+        let auth_event = match transport.recv().await.unwrap() {
+            Message::Text(json) => match ClientMessage::from_json(json).unwrap() {
+                ClientMessage::Auth(event) => event.into_owned(),
+                msg => panic!("unexpected message: {msg:?}"),
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        };
+        transport.push(Message::Text(
+            RelayMessage::ok(auth_event.id, true, "").as_json(),
+        ));
+        auth.await.unwrap().unwrap();
+
+        // The EVENT is retried and accepted
+        match transport.recv().await.unwrap() {
+            Message::Text(json) => match ClientMessage::from_json(json).unwrap() {
+                ClientMessage::Event(e) => assert_eq!(e.id, event.id),
+                msg => panic!("unexpected message: {msg:?}"),
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+        transport.push(Message::Text(
+            RelayMessage::ok(event.id, true, "").as_json(),
+        ));
+        assert_eq!(handle.await.unwrap().unwrap(), event.id);
+    }
+
+    #[tokio::test]
+    async fn test_nip42_never_auth_policy() {
+        // Mock relay
+        let opts = LocalRelayBuilderNip42 {
+            mode: LocalRelayBuilderNip42Mode::Write,
+        };
+        let mock = LocalRelay::builder().nip42(opts).build();
+        mock.run().await.unwrap();
+        let url = mock.url().await;
+
+        let keys = Keys::generate();
+        let relay: Relay = Relay::builder(url)
+            .authenticator(SignerAuthenticator::new(keys.clone()))
+            .opts(RelayOptions::default().auth_policy(AuthPolicy::Never))
+            .build();
+
+        relay.connect();
+
+        let event = EventBuilder::text_note("Test").sign(&keys).unwrap();
+
+        // The authenticator is never used
+        match relay.send_event(&event).await.unwrap_err() {
+            crate::relay::Error::RelayMessage(msg) => {
+                assert_eq!(
+                    MachineReadablePrefix::parse(&msg).unwrap(),
+                    MachineReadablePrefix::AuthRequired
+                );
+            }
+            e => panic!("Unexpected error: {e}"),
+        }
+    }
 }
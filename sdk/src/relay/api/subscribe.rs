@@ -14,6 +14,7 @@ pub struct Subscribe<'relay> {
     filters: Vec<Filter>,
     id: Option<SubscriptionId>,
     auto_close: Option<SubscribeAutoCloseOptions>,
+    resume: bool,
 }
 
 impl<'relay> Subscribe<'relay> {
@@ -24,6 +25,7 @@ impl<'relay> Subscribe<'relay> {
             filters,
             id: None,
             auto_close: None,
+            resume: false,
         }
     }
 
@@ -40,6 +42,20 @@ impl<'relay> Subscribe<'relay> {
         self.auto_close = Some(opts);
         self
     }
+
+    /// Resume from the newest received event after a reconnection (default: false)
+    ///
+    /// When re-subscribing, the `since` of the filters is moved to the `created_at` of the newest event
+    /// received by the subscription, to not receive again all the old events.
+    /// The stored events are taken into account only once the EOSE is received,
+    /// and the cursor is never moved after the current time.
+    ///
+    /// Ignored for the auto-closing subscriptions, that aren't re-subscribed.
+    #[inline]
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
 }
 
 pub(super) async fn subscribe_auto_closing(
@@ -92,12 +108,21 @@ async fn subscribe_long_lived(
     relay: &Relay,
     id: SubscriptionId,
     filters: Vec<Filter>,
+    resume: bool,
 ) -> Result<(), Error> {
     // Check if filters are empty
     if filters.is_empty() {
         return Err(Error::EmptyFilters);
     }
 
+    // No auto-close subscription: update subscription filter.
+    // Registered before sending the REQ, to track all the received events.
+    relay
+        .inner
+        .update_subscription(id.clone(), filters.clone(), true)
+        .await;
+    relay.inner.set_subscription_resume(&id, resume).await;
+
     // Compose REQ message
     let msg: ClientMessage = ClientMessage::Req {
         subscription_id: Cow::Borrowed(&id),
@@ -105,10 +130,13 @@ async fn subscribe_long_lived(
     };
 
     // Send REQ message
-    relay.send_msg(msg).await?;
+    if let Err(e) = relay.send_msg(msg).await {
+        // Remove previously added subscription
+        relay.inner.remove_subscription(&id).await;
 
-    // No auto-close subscription: update subscription filter
-    relay.inner.update_subscription(id, filters, true).await;
+        // Propagate error
+        return Err(e);
+    }
 
     // Return
     Ok(())
@@ -129,7 +157,9 @@ impl<'relay> IntoFuture for Subscribe<'relay> {
                     subscribe_auto_closing(self.relay, id.clone(), self.filters, opts, None, None)
                         .await?
                 }
-                None => subscribe_long_lived(self.relay, id.clone(), self.filters).await?,
+                None => {
+                    subscribe_long_lived(self.relay, id.clone(), self.filters, self.resume).await?
+                }
             }
 
             // Return subscription ID
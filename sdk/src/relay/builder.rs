@@ -10,7 +10,9 @@ use super::{Relay, RelayCapabilities};
 use crate::authenticator::Authenticator;
 use crate::events_tracker::MemoryEventsTracker;
 use crate::policy::AdmitPolicy;
-use crate::transport::websocket::{DefaultWebsocketTransport, WebSocketTransport};
+use crate::transport::headers::UpgradeHeadersResolver;
+use crate::transport::middleware::TransportMiddleware;
+use crate::transport::websocket::{DefaultWebsocketTransport, WebSocketLimits, WebSocketTransport};
 
 /// Relay builder
 #[derive(Debug, Clone)]
@@ -25,6 +27,8 @@ pub struct RelayBuilder {
     pub admit_policy: Option<Arc<dyn AdmitPolicy>>,
     /// Authenticator
     pub authenticator: Option<Arc<dyn Authenticator>>,
+    /// WebSocket upgrade headers resolver
+    pub upgrade_headers: Option<Arc<dyn UpgradeHeadersResolver>>,
     /// Capabilities
     pub capabilities: RelayCapabilities,
     /// Relay pool options
@@ -37,10 +41,11 @@ impl RelayBuilder {
     pub fn new(url: RelayUrl) -> Self {
         Self {
             url,
-            websocket_transport: Arc::new(DefaultWebsocketTransport),
+            websocket_transport: Arc::new(DefaultWebsocketTransport::default()),
             database: Arc::new(MemoryEventsTracker::default()),
             admit_policy: None,
             authenticator: None,
+            upgrade_headers: None,
             capabilities: RelayCapabilities::default(),
             opts: RelayOptions::default(),
         }
@@ -56,6 +61,37 @@ impl RelayBuilder {
         self
     }
 
+    /// Wrap the WebSocket transport with a [`TransportMiddleware`]
+    ///
+    /// Each middleware wraps the transport resulting from the previous calls,
+    /// so the middlewares are applied in the order they are added:
+    /// the last one added is the outermost (the closest to the relay logic).
+    ///
+    /// Note that [`RelayBuilder::websocket_transport`] replaces the transport,
+    /// so it must be called before adding the middlewares.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nostr_sdk::prelude::*;
+    /// # use nostr_sdk::transport::middleware::LoggingMiddleware;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let url = RelayUrl::parse("wss://relay.damus.io")?;
+    /// let relay = Relay::builder(url)
+    ///     .add_middleware(LoggingMiddleware)
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn add_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: TransportMiddleware,
+    {
+        self.websocket_transport = middleware.wrap(self.websocket_transport);
+        self
+    }
+
     /// Set a database
     #[inline]
     pub fn database<T>(mut self, database: T) -> Self
@@ -107,6 +143,19 @@ impl RelayBuilder {
         self
     }
 
+    /// Set a WebSocket upgrade headers resolver
+    ///
+    /// The headers are attached to the WebSocket handshake, at every connection attempt.
+    /// Check [`ClientBuilder::upgrade_headers`](crate::client::ClientBuilder::upgrade_headers) to learn more.
+    #[inline]
+    pub fn upgrade_headers<T>(mut self, resolver: T) -> Self
+    where
+        T: UpgradeHeadersResolver + 'static,
+    {
+        self.upgrade_headers = Some(Arc::new(resolver));
+        self
+    }
+
     /// Set capabilities
     #[inline]
     pub fn capabilities(mut self, capabilities: RelayCapabilities) -> Self {
@@ -114,6 +163,42 @@ impl RelayBuilder {
         self
     }
 
+    /// Enable or disable the [`RelayCapabilities::READ`] capability
+    ///
+    /// A relay that can't read refuses the `REQ` and `CLOSE` messages
+    /// and is skipped by the subscriptions and the fetches.
+    ///
+    /// Note that the [`RelayCapabilities::GOSSIP`] and [`RelayCapabilities::DISCOVERY`] capabilities,
+    /// assigned to the relays added by the gossip layer, imply the read capability:
+    /// the relays selected from the NIP-65 lists keep being used for reading.
+    #[inline]
+    pub fn read(mut self, enable: bool) -> Self {
+        self.capabilities.set(RelayCapabilities::READ, enable);
+        self
+    }
+
+    /// Enable or disable the [`RelayCapabilities::WRITE`] capability
+    ///
+    /// A relay that can't write refuses to publish events
+    /// and is skipped when sending events to the write relays.
+    ///
+    /// Note that the [`RelayCapabilities::GOSSIP`] capability,
+    /// assigned to the relays added by the gossip layer, implies the write capability.
+    #[inline]
+    pub fn write(mut self, enable: bool) -> Self {
+        self.capabilities.set(RelayCapabilities::WRITE, enable);
+        self
+    }
+
+    /// WebSocket message and frame size limits
+    ///
+    /// Check [`RelayOptions::websocket_limits`] to learn more.
+    #[inline]
+    pub fn websocket_limits(mut self, limits: WebSocketLimits) -> Self {
+        self.opts = self.opts.websocket_limits(limits);
+        self
+    }
+
     /// Set options
     #[inline]
     pub fn opts(mut self, opts: RelayOptions) -> Self {
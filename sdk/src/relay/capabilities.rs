@@ -96,6 +96,16 @@ impl RelayCapabilities {
         self.0 &= !other.0;
     }
 
+    /// Add or remove capabilities
+    #[inline]
+    pub fn set(&mut self, other: Self, enable: bool) {
+        if enable {
+            self.add(other);
+        } else {
+            self.remove(other);
+        }
+    }
+
     /// Check if relay can read (has READ, GOSSIP, or DISCOVERY)
     #[inline]
     pub fn can_read(self) -> bool {
@@ -309,6 +319,19 @@ mod tests {
         assert!(caps.has_all(RelayCapabilities::GOSSIP));
     }
 
+    #[test]
+    fn test_set() {
+        let mut caps = RelayCapabilities::default();
+        caps.set(RelayCapabilities::WRITE, false);
+        assert!(caps.can_read());
+        assert!(!caps.can_write());
+
+        caps.set(RelayCapabilities::WRITE, true);
+        caps.set(RelayCapabilities::READ, false);
+        assert!(!caps.can_read());
+        assert!(caps.can_write());
+    }
+
     #[test]
     fn test_has_any() {
         let caps = RelayCapabilities::READ | RelayCapabilities::WRITE;
@@ -1,4 +1,3 @@
-use core::ops::RangeInclusive;
 use core::time::Duration;
 
 /// Relay default notification channel size
@@ -11,17 +10,28 @@ pub(super) const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
 // Not increase the max retry interval too much.
 // Keep it small, avoid huge waits before reconnection if internet was gone for much time and then come back.
 pub(super) const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
-pub(super) const JITTER_RANGE: RangeInclusive<i8> = -3..=3;
+pub(super) const DEFAULT_RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+pub(super) const DEFAULT_RECONNECT_JITTER: Duration = Duration::from_secs(3);
+pub(super) const DEFAULT_RECONNECT_RESET_THRESHOLD: Duration = Duration::from_secs(60);
+pub(super) const DEFAULT_OVERSIZED_MESSAGE_PENALTY: Duration = Duration::from_secs(5 * 60);
+/// Lower bound of the reconnection delay, to avoid hammering the relay
+pub(super) const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(100);
 
+#[cfg(feature = "negentropy")]
 pub(super) const NEGENTROPY_FRAME_SIZE_LIMIT: u64 = 60_000; // Default frame limit is 128k. Halve that (hex encoding) and subtract a bit (JSON msg overhead)
+#[cfg(feature = "negentropy")]
 pub(super) const NEGENTROPY_HIGH_WATER_UP: usize = 100;
+#[cfg(feature = "negentropy")]
 pub(super) const NEGENTROPY_LOW_WATER_UP: usize = 50;
+#[cfg(feature = "negentropy")]
 pub(super) const NEGENTROPY_BATCH_SIZE_DOWN: usize = 100;
 
 pub(super) const MIN_ATTEMPTS: usize = 1;
 pub(super) const MIN_SUCCESS_RATE: f64 = 0.90;
 
-pub(super) const PING_INTERVAL: Duration = Duration::from_secs(55); // Used also for latency calculation
+pub(super) const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(55); // Used also for latency calculation
+pub(super) const MIN_PING_INTERVAL: Duration = Duration::from_millis(100);
+pub(super) const DEFAULT_PONG_TIMEOUT: Duration = Duration::from_secs(20);
 
 /// Sleep interval
 #[cfg(not(test))]
@@ -34,3 +44,23 @@ pub(super) const WEBSOCKET_TX_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) const LATENCY_MIN_READS: u64 = 3;
+/// Weight of the previous estimate in the RTT moving average (i.e., `1/8` for the new sample, like the TCP SRTT)
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) const RTT_SMOOTHING: u64 = 8;
+
+/// Number of recent samples used to estimate the clock offset
+pub(super) const CLOCK_OFFSET_WINDOW: usize = 15;
+/// Minimum number of samples agreeing with the median to estimate the clock offset
+pub(super) const CLOCK_OFFSET_MIN_SAMPLES: usize = 5;
+/// Max distance of a sample from the median to agree with it, in seconds
+pub(super) const CLOCK_OFFSET_AGREEMENT: u64 = 10;
+/// Samples farther than this from the local clock are rejected as implausible, in seconds
+pub(super) const CLOCK_OFFSET_MAX: u64 = 60 * 60 * 24;
+/// Below this offset, the local clock is considered in sync with the relay one
+pub(super) const AUTH_CLOCK_SKEW_TOLERANCE: u64 = 30;
+
+/// Rate limit without a retry-after hint: initial backoff, doubled at every consecutive rate limit
+pub(super) const RATE_LIMIT_BACKOFF_BASE: Duration = Duration::from_secs(5);
+pub(super) const RATE_LIMIT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Max retry-after hint accepted from a relay
+pub(super) const RATE_LIMIT_MAX_HINT: Duration = Duration::from_secs(3600);
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::time::Duration;
 
+use nostr::Timestamp;
 use nostr::event::{self, builder};
 use nostr::message::MessageHandleError;
 use nostr_database::DatabaseError;
@@ -29,6 +30,7 @@ pub enum Error {
     /// Hex error
     Hex(faster_hex::Error),
     /// Negentropy error
+    #[cfg(feature = "negentropy")]
     Negentropy(negentropy::Error),
     /// Oneshot recv error
     OneshotRecv(oneshot::error::RecvError),
@@ -63,6 +65,8 @@ pub enum Error {
     Banned,
     /// Relay shutdown
     Shutdown,
+    /// Relay is shutting down
+    ShuttingDown,
     /// Connection rejected
     ConnectionRejected {
         /// Reason
@@ -101,8 +105,15 @@ pub enum Error {
         /// Max tags num
         max_size: usize,
     },
+    /// Rate-limited by the relay
+    RateLimited {
+        /// UNIX timestamp until the relay rate-limits the operation
+        until: Timestamp,
+    },
     /// Event expired
     EventExpired,
+    /// The event ID doesn't match the event content
+    EventIdMismatch,
     /// Max latency exceeded
     MaximumLatencyExceeded {
         /// Max
@@ -140,6 +151,7 @@ impl fmt::Display for Error {
             Self::Event(e) => e.fmt(f),
             Self::EventBuilder(e) => e.fmt(f),
             Self::Hex(e) => e.fmt(f),
+            #[cfg(feature = "negentropy")]
             Self::Negentropy(e) => e.fmt(f),
             Self::OneshotRecv(e) => e.fmt(f),
             Self::Timeout => f.write_str("timeout"),
@@ -160,6 +172,7 @@ impl fmt::Display for Error {
             Self::Sleeping => f.write_str("relay is sleeping"),
             Self::Banned => f.write_str("relay banned"),
             Self::Shutdown => f.write_str("relay shutdown"),
+            Self::ShuttingDown => f.write_str("relay is shutting down"),
             Self::ConnectionRejected { reason } => {
                 let reason: &str = reason.as_deref().unwrap_or("unknown");
                 write!(f, "connection rejected: reason={reason}")
@@ -182,7 +195,9 @@ impl fmt::Display for Error {
                 f,
                 "Received event with too many tags: tags={size}, max_tags={max_size}"
             ),
+            Self::RateLimited { until } => write!(f, "rate-limited until {until}"),
             Self::EventExpired => f.write_str("event expired"),
+            Self::EventIdMismatch => f.write_str("event ID doesn't match the event content"),
             Self::MaximumLatencyExceeded { max, current } => write!(
                 f,
                 "Maximum latency exceeded: max={}ms, current={}ms",
@@ -251,6 +266,7 @@ impl From<faster_hex::Error> for Error {
     }
 }
 
+#[cfg(feature = "negentropy")]
 impl From<negentropy::Error> for Error {
     fn from(e: negentropy::Error) -> Self {
         Self::Negentropy(e)
@@ -1,9 +1,9 @@
 use std::borrow::Cow;
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::time::Duration;
 
 use async_utility::{task, time};
@@ -14,26 +14,36 @@ use nostr::rand::RngCore;
 use nostr::rand::rngs::OsRng;
 use nostr::rand::{Rng, TryRngCore};
 use nostr_database::prelude::*;
+use serde_json::value::RawValue;
 use tokio::sync::mpsc::{self, Receiver, Sender};
-use tokio::sync::{Mutex, MutexGuard, Notify, RwLock, RwLockWriteGuard, broadcast, oneshot};
+use tokio::sync::{Mutex, MutexGuard, Notify, RwLock, RwLockWriteGuard, broadcast, oneshot, watch};
 use universal_time::Instant;
 
 use super::capabilities::{AtomicRelayCapabilities, RelayCapabilities};
 use super::constants::{
-    JITTER_RANGE, MAX_RETRY_INTERVAL, MIN_ATTEMPTS, MIN_SUCCESS_RATE, PING_INTERVAL,
-    SLEEP_INTERVAL, WEBSOCKET_TX_TIMEOUT,
+    AUTH_CLOCK_SKEW_TOLERANCE, MAX_RETRY_INTERVAL, MIN_ATTEMPTS, MIN_PING_INTERVAL,
+    MIN_RECONNECT_DELAY, MIN_SUCCESS_RATE, SLEEP_INTERVAL, WEBSOCKET_TX_TIMEOUT,
 };
-use super::options::{RelayOptions, ReqExitPolicy, SubscribeAutoCloseOptions};
+use super::options::{AuthPolicy, RelayOptions, ReqExitPolicy, SubscribeAutoCloseOptions};
 use super::ping::PingTracker;
-use super::stats::RelayConnectionStats;
+use super::rate_limit::{RateLimitClass, parse_retry_after};
+use super::stats::{RelayConnectionStats, TrafficAccounting};
 use super::{
-    Error, RelayNotification, RelayStatus, SubscriptionActivity, SubscriptionAutoClosedReason,
+    Error, RelayNotification, RelayShutdownSummary, RelayStatus, SubscriptionActivity,
+    SubscriptionAutoClosedReason,
 };
 use crate::client::ClientNotification;
+use crate::interval::Interval;
 use crate::policy::AdmitStatus;
 use crate::relay::status::AtomicRelayStatus;
 use crate::shared::SharedState;
+use crate::throttle::{self, SendLimiter};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::close::WebSocketCloseCode;
 use crate::transport::error::TransportError;
+use crate::transport::headers::UpgradeHeaders;
+#[cfg(target_arch = "wasm32")]
+use crate::transport::websocket::with_limits;
 use crate::transport::websocket::{WebSocketSink, WebSocketStream};
 
 type ClientMessageJson = String;
@@ -55,14 +65,42 @@ struct HandleAutoClosing {
     reason: Option<SubscriptionAutoClosedReason>,
 }
 
+#[derive(Debug)]
 struct JsonMessageItem {
     json: ClientMessageJson,
+    /// ID of the event, if it's an `EVENT` message
+    event_id: Option<EventId>,
     confirmation: Option<oneshot::Sender<()>>,
 }
 
+/// State of the outgoing messages, tracked for the graceful shutdown
+#[derive(Debug, Default)]
+struct FlushState {
+    /// Messages queued but not yet written to the socket
+    queued: usize,
+    /// Messages written to the socket while shutting down
+    sent: usize,
+    /// Events written to the socket and still waiting for the `OK`
+    unacknowledged: HashSet<EventId>,
+}
+
+/// Receivers of the client messages
+#[derive(Debug)]
+struct MessageReceivers {
+    nostr: Receiver<JsonMessageItem>,
+    /// Messages that skip the queue and the rate limits (i.e., `AUTH`)
+    priority: Receiver<JsonMessageItem>,
+    /// Message dequeued from the nostr channel, waiting for the rate limits
+    ///
+    /// Kept here, so it isn't lost if the connection is closed while waiting.
+    pending: Option<JsonMessageItem>,
+}
+
 #[derive(Debug)]
 struct RelayChannels {
-    nostr: (Sender<JsonMessageItem>, Mutex<Receiver<JsonMessageItem>>),
+    nostr: Sender<JsonMessageItem>,
+    priority: Sender<JsonMessageItem>,
+    receivers: Mutex<MessageReceivers>,
     ping: Notify,
     terminate: Notify,
 }
@@ -70,25 +108,36 @@ struct RelayChannels {
 impl RelayChannels {
     pub fn new() -> Self {
         let (tx_nostr, rx_nostr) = mpsc::channel(1024);
+        let (tx_priority, rx_priority) = mpsc::channel(64);
 
         Self {
-            nostr: (tx_nostr, Mutex::new(rx_nostr)),
+            nostr: tx_nostr,
+            priority: tx_priority,
+            receivers: Mutex::new(MessageReceivers {
+                nostr: rx_nostr,
+                priority: rx_priority,
+                pending: None,
+            }),
             ping: Notify::new(),
             terminate: Notify::new(),
         }
     }
 
     #[inline]
-    fn send_client_msg(&self, msg: JsonMessageItem) -> Result<(), Error> {
-        self.nostr
-            .0
-            .try_send(msg)
+    fn send_client_msg(&self, msg: JsonMessageItem, priority: bool) -> Result<(), Error> {
+        let tx: &Sender<JsonMessageItem> = if priority {
+            &self.priority
+        } else {
+            &self.nostr
+        };
+
+        tx.try_send(msg)
             .map_err(|_| Error::CantSendMessageToDispatcher)
     }
 
     #[inline]
-    pub async fn rx_nostr(&self) -> MutexGuard<'_, Receiver<JsonMessageItem>> {
-        self.nostr.1.lock().await
+    pub async fn rx_nostr(&self) -> MutexGuard<'_, MessageReceivers> {
+        self.receivers.lock().await
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -112,6 +161,26 @@ struct SubscriptionData {
     pub received_events: AtomicUsize,
     /// Subscription closed by relay
     pub closed: bool,
+    /// Resume from the newest received event when re-subscribing
+    pub resume: bool,
+    /// `created_at` of the newest received event, used as cursor for the resumption
+    ///
+    /// Moved by the live events and, at the EOSE, by the stored ones.
+    pub newest_event_at: AtomicU64,
+    /// `created_at` of the newest stored event received before the EOSE
+    ///
+    /// The stored events are usually sent newest first,
+    /// so it's a valid cursor only if all of them have been received.
+    pub backfill_newest_at: AtomicU64,
+}
+
+impl SubscriptionData {
+    /// Reset the state of the previous REQ
+    fn requested(&mut self) {
+        self.received_eose = false;
+        self.received_events.store(0, Ordering::SeqCst);
+        self.backfill_newest_at.store(0, Ordering::SeqCst);
+    }
 }
 
 impl Default for SubscriptionData {
@@ -123,6 +192,9 @@ impl Default for SubscriptionData {
             received_eose: false,
             received_events: AtomicUsize::new(0),
             closed: false,
+            resume: false,
+            newest_event_at: AtomicU64::new(0),
+            backfill_newest_at: AtomicU64::new(0),
         }
     }
 }
@@ -135,6 +207,19 @@ pub(super) struct AtomicPrivateData {
     channels: RelayChannels,
     subscriptions: RwLock<HashMap<SubscriptionId, SubscriptionData>>,
     running: AtomicBool,
+    /// Consecutive failed reconnection attempts
+    reconnect_attempts: AtomicU32,
+    /// Last computed reconnection delay (millis)
+    reconnect_delay: AtomicU64,
+    /// The last connection was closed for an oversized message
+    oversized_message: AtomicBool,
+    /// NIP-11 relay information document
+    document: RwLock<Option<RelayInformationDocument>>,
+    /// Outgoing messages rate limiter
+    send_limiter: Option<SendLimiter>,
+    /// Graceful shutdown in progress: new messages are rejected
+    shutting_down: AtomicBool,
+    flush: watch::Sender<FlushState>,
 }
 
 #[derive(Debug, Clone)]
@@ -166,6 +251,13 @@ impl InnerRelay {
                 channels: RelayChannels::new(),
                 subscriptions: RwLock::new(HashMap::new()),
                 running: AtomicBool::new(false),
+                reconnect_attempts: AtomicU32::new(0),
+                reconnect_delay: AtomicU64::new(0),
+                oversized_message: AtomicBool::new(false),
+                document: RwLock::new(None),
+                send_limiter: SendLimiter::new(opts.max_messages_per_sec, opts.max_bytes_per_sec),
+                shutting_down: AtomicBool::new(false),
+                flush: watch::Sender::new(FlushState::default()),
             }),
             capabilities: Arc::new(AtomicRelayCapabilities::new(capabilities)),
             opts,
@@ -194,11 +286,26 @@ impl InnerRelay {
         self.atomic.running.load(Ordering::SeqCst)
     }
 
+    /// Last computed reconnection delay
+    #[inline]
+    pub(super) fn reconnect_delay(&self) -> Duration {
+        Duration::from_millis(self.atomic.reconnect_delay.load(Ordering::SeqCst))
+    }
+
     #[inline]
     pub fn status(&self) -> RelayStatus {
         self.atomic.status.load()
     }
 
+    #[inline]
+    pub(super) fn status_watch(&self) -> watch::Receiver<RelayStatus> {
+        self.atomic.status.watch()
+    }
+
+    /// Set the status
+    ///
+    /// The status is updated **before** emitting the notification:
+    /// when a status notification is received, `status()` already returns that status (or a newer one).
     pub(super) fn set_status(&self, status: RelayStatus, log: bool) {
         // Change status
         self.atomic.status.set(status);
@@ -231,6 +338,15 @@ impl InnerRelay {
 
     /// Perform checks to ensure that the relay is ready for use.
     pub(super) fn ensure_operational(&self) -> Result<(), Error> {
+        // The relay has been shut down or is shutting down: don't wake it up
+        if self.status().is_shutdown() {
+            return Err(Error::Shutdown);
+        }
+
+        if self.atomic.shutting_down.load(Ordering::SeqCst) {
+            return Err(Error::ShuttingDown);
+        }
+
         // Ensures that the relay is awake.
         self.ensure_awake_for_activity();
 
@@ -326,11 +442,57 @@ impl InnerRelay {
         let data: &mut SubscriptionData = subscriptions.entry(id).or_default();
         data.filters = filters;
 
+        // The newest event of the previous filters isn't a valid cursor for the new ones
+        data.newest_event_at.store(0, Ordering::SeqCst);
+
+        // New REQ
+        data.requested();
+
         if update_subscribed_at {
             data.subscribed_at = Timestamp::now();
         }
     }
 
+    /// Enable or disable the resumption of the subscription from the newest received event
+    pub(crate) async fn set_subscription_resume(&self, id: &SubscriptionId, resume: bool) {
+        let mut subscriptions = self.atomic.subscriptions.write().await;
+        if let Some(data) = subscriptions.get_mut(id) {
+            data.resume = resume;
+        }
+    }
+
+    /// Keep track of the newest event received by a resumable subscription
+    async fn track_newest_event(&self, id: &SubscriptionId, created_at: Timestamp) {
+        let subscriptions = self.atomic.subscriptions.read().await;
+        if let Some(data) = subscriptions.get(id) {
+            if data.resume {
+                // The `created_at` is set by the author: a future-dated event can't move the cursor after now
+                let secs: u64 = created_at.min(Timestamp::now()).as_secs();
+
+                if data.received_eose {
+                    data.newest_event_at.fetch_max(secs, Ordering::SeqCst);
+                } else {
+                    data.backfill_newest_at.fetch_max(secs, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    /// Get the `since` to use for re-subscribing, if the subscription is resumable and received some events
+    async fn resume_cursor(&self, id: &SubscriptionId) -> Option<Timestamp> {
+        let subscriptions = self.atomic.subscriptions.read().await;
+        let data: &SubscriptionData = subscriptions.get(id)?;
+
+        if !data.resume {
+            return None;
+        }
+
+        match data.newest_event_at.load(Ordering::SeqCst) {
+            0 => None,
+            secs => Some(Timestamp::from_secs(secs)),
+        }
+    }
+
     /// Mark subscription as closed
     async fn subscription_closed(&self, id: &SubscriptionId) {
         let mut subscriptions = self.atomic.subscriptions.write().await;
@@ -339,11 +501,32 @@ impl InnerRelay {
         }
     }
 
+    /// Check if the EOSE was received for subscription
+    async fn has_received_eose(&self, id: &SubscriptionId) -> bool {
+        let subscriptions = self.atomic.subscriptions.read().await;
+        subscriptions
+            .get(id)
+            .map(|data| data.received_eose)
+            .unwrap_or_default()
+    }
+
     /// Received eose for subscription
     async fn received_eose(&self, id: &SubscriptionId) {
         let mut subscriptions = self.atomic.subscriptions.write().await;
         if let Some(data) = subscriptions.get_mut(id) {
             data.received_eose = true;
+
+            // All the stored events have been received: move the cursor
+            let backfill: u64 = data.backfill_newest_at.swap(0, Ordering::SeqCst);
+            data.newest_event_at.fetch_max(backfill, Ordering::SeqCst);
+        }
+    }
+
+    /// Reset the state of the subscription before sending a new REQ
+    async fn subscription_requested(&self, id: &SubscriptionId) {
+        let mut subscriptions = self.atomic.subscriptions.write().await;
+        if let Some(data) = subscriptions.get_mut(id) {
+            data.requested();
         }
     }
 
@@ -407,6 +590,16 @@ impl InnerRelay {
                     RelayNotification::RelayStatus { .. } => None,
                     RelayNotification::Authenticated => None,
                     RelayNotification::AuthenticationFailed => None,
+                    RelayNotification::AuthChallenge { challenge } => {
+                        Some(ClientNotification::AuthChallenge {
+                            relay_url: self.url.clone(),
+                            challenge,
+                        })
+                    }
+                    RelayNotification::Rtt { rtt } => Some(ClientNotification::Rtt {
+                        relay_url: self.url.clone(),
+                        rtt,
+                    }),
                 };
 
                 // Send external notification
@@ -421,6 +614,12 @@ impl InnerRelay {
         }
     }
 
+    /// Check if the relay can answer the NIP-42 AUTH challenges
+    #[inline]
+    pub(super) fn can_authenticate(&self) -> bool {
+        self.state.is_authenticator_available() && self.opts.auth_policy != AuthPolicy::Never
+    }
+
     pub(super) async fn check_connection_policy(&self) -> Result<AdmitStatus, Error> {
         match &self.state.admit_policy {
             Some(policy) => Ok(policy.admit_connection(&self.url).await?),
@@ -548,9 +747,26 @@ impl InnerRelay {
 
             // Connect and run message handler
             // The termination requests are handled inside this method!
-            self.connect_and_run(stream.take(), &mut rx_nostr, &mut last_ws_error)
+            let uptime: Option<Duration> = self
+                .connect_and_run(stream.take(), &mut rx_nostr, &mut last_ws_error)
                 .await;
 
+            // Penalize the relay if the connection was closed for an oversized message
+            let penalized: bool = self.atomic.oversized_message.swap(false, Ordering::SeqCst);
+
+            // Reset the backoff if the connection has been stable enough,
+            // otherwise count it as a failed attempt.
+            match uptime {
+                Some(uptime) if !penalized && uptime >= self.opts.reconnect_reset_threshold => {
+                    self.atomic.reconnect_attempts.store(0, Ordering::SeqCst);
+                }
+                _ => {
+                    self.atomic
+                        .reconnect_attempts
+                        .fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
             // Get status
             let status: RelayStatus = self.status();
 
@@ -572,7 +788,15 @@ impl InnerRelay {
                 }
 
                 // Sleep before retry to connect
-                let interval: Duration = self.calculate_retry_interval();
+                let mut interval: Duration = self.calculate_retry_interval();
+
+                if penalized {
+                    interval = cmp::max(interval, self.opts.oversized_message_penalty);
+                }
+
+                self.atomic
+                    .reconnect_delay
+                    .store(interval.as_millis() as u64, Ordering::SeqCst);
                 tracing::debug!(
                     "Reconnecting to '{}' relay in {} secs",
                     self.url,
@@ -605,6 +829,11 @@ impl InnerRelay {
 
     /// Depending on attempts and success, use default or incremental retry interval
     fn calculate_retry_interval(&self) -> Duration {
+        // Check if the exponential backoff is enabled
+        if self.opts.reconnect_backoff {
+            return self.calculate_backoff_delay();
+        }
+
         // Check if the incremental interval is enabled
         if self.opts.adjust_retry_interval {
             // Calculate the difference between attempts and success
@@ -618,28 +847,44 @@ impl InnerRelay {
 
             // If the interval is too big, use the min one.
             // If the interval is checked after the jitter, the interval may be the same for all relays!
-            let mut interval: Duration = cmp::min(adaptive_interval, MAX_RETRY_INTERVAL);
+            let interval: Duration = cmp::min(adaptive_interval, MAX_RETRY_INTERVAL);
 
-            // The jitter is added to avoid situations where multiple relays reconnect simultaneously after a failure.
-            // This helps prevent synchronized retry storms.
-            let jitter: i8 = OsRng.unwrap_err().random_range(JITTER_RANGE);
+            return self.add_reconnect_jitter(interval);
+        }
 
-            // Apply jitter
-            if jitter >= 0 {
-                // Positive jitter, add it to the interval.
-                interval = interval.saturating_add(Duration::from_secs(jitter as u64));
-            } else {
-                // Negative jitter, compute `|jitter|` and saturating subtract it from the interval.
-                let jitter: u64 = jitter.unsigned_abs() as u64;
-                interval = interval.saturating_sub(Duration::from_secs(jitter));
-            }
+        // Use default internal
+        self.add_reconnect_jitter(self.opts.retry_interval)
+    }
+
+    /// Exponential backoff, based on the consecutive failed attempts
+    fn calculate_backoff_delay(&self) -> Duration {
+        let attempts: u32 = self.atomic.reconnect_attempts.load(Ordering::SeqCst);
+
+        // The first failure uses the initial delay.
+        // Cap the exponent to avoid overflows.
+        let exp: u32 = cmp::min(attempts.saturating_sub(1), 16);
+        let delay: Duration = self.opts.reconnect_initial_delay.saturating_mul(1 << exp);
 
-            // Return interval
-            return interval;
+        // Never go below the min delay, also if the initial delay is zero.
+        let delay: Duration = delay.clamp(
+            MIN_RECONNECT_DELAY,
+            cmp::max(self.opts.reconnect_max_delay, MIN_RECONNECT_DELAY),
+        );
+
+        self.add_reconnect_jitter(delay)
+    }
+
+    /// Add a random jitter, up to [`RelayOptions::reconnect_jitter`]
+    ///
+    /// The jitter avoids that multiple relays reconnect simultaneously after a failure (synchronized retry storms).
+    fn add_reconnect_jitter(&self, delay: Duration) -> Duration {
+        let max_jitter: u64 = self.opts.reconnect_jitter.as_millis() as u64;
+        if max_jitter > 0 {
+            let jitter: u64 = OsRng.unwrap_err().random_range(0..=max_jitter);
+            return delay.saturating_add(Duration::from_millis(jitter));
         }
 
-        // Use default internal
-        self.opts.retry_interval
+        delay
     }
 
     #[inline]
@@ -659,11 +904,45 @@ impl InnerRelay {
         // Increase the attempts
         self.stats.new_attempt();
 
+        // Get the upgrade headers
+        // NOTE: never log them, they may contain credentials
+        let headers: UpgradeHeaders = match &self.state.upgrade_headers {
+            Some(resolver) => resolver.resolve(&self.url).await,
+            None => UpgradeHeaders::new(),
+        };
+
         // Connect futures
-        let connect_fut = self
-            .state
-            .transport
-            .connect((&self.url).into(), self.proxy());
+        #[cfg(not(target_arch = "wasm32"))]
+        let connect_fut = async {
+            let transport = &self.state.transport;
+            let (ws_tx, ws_rx, metrics) = match self.opts.websocket_limits {
+                Some(limits) => {
+                    transport
+                        .connect_with_limits((&self.url).into(), self.proxy(), &headers, limits)
+                        .await?
+                }
+                None => {
+                    transport
+                        .connect_with_metrics((&self.url).into(), self.proxy(), &headers)
+                        .await?
+                }
+            };
+            self.stats.set_connection_metrics(metrics);
+            Ok((ws_tx, ws_rx))
+        };
+        #[cfg(target_arch = "wasm32")]
+        let connect_fut = async {
+            let (ws_tx, ws_rx) = self
+                .state
+                .transport
+                .connect_with_headers((&self.url).into(), self.proxy(), &headers)
+                .await?;
+            // The browsers don't allow to bound the frames: enforce the limits on the messages
+            match self.opts.websocket_limits {
+                Some(limits) => Ok((ws_tx, with_limits(ws_rx, limits))),
+                None => Ok((ws_tx, ws_rx)),
+            }
+        };
         let fut = time::timeout(Some(timeout), connect_fut);
 
         // Try to connect
@@ -673,6 +952,10 @@ impl InnerRelay {
             // Connect
             res = fut => match res {
                 Some(Ok((ws_tx, ws_rx))) => {
+                    // Account the messages and the bytes of the connection
+                    let (ws_tx, ws_rx) =
+                        TrafficAccounting::new(self.stats.clone()).wrap(ws_tx, ws_rx);
+
                     // Update status
                     self.set_status(RelayStatus::Connected, true);
 
@@ -707,12 +990,12 @@ impl InnerRelay {
     async fn connect_and_run(
         &self,
         stream: Option<(WebSocketSink, WebSocketStream)>,
-        rx_nostr: &mut MutexGuard<'_, Receiver<JsonMessageItem>>,
+        rx_nostr: &mut MutexGuard<'_, MessageReceivers>,
         last_ws_error: &mut Option<String>,
-    ) {
+    ) -> Option<Duration> {
         match stream {
             // Already have a stream, go to post-connection stage
-            Some((ws_tx, ws_rx)) => self.post_connection(ws_tx, ws_rx, rx_nostr).await,
+            Some((ws_tx, ws_rx)) => Some(self.post_connection(ws_tx, ws_rx, rx_nostr).await),
             // No stream is passed, try to connect
             // Set the status to "disconnected" to allow to automatic retries
             None => match self
@@ -720,7 +1003,7 @@ impl InnerRelay {
                 .await
             {
                 // Connection success, go to post-connection stage
-                Ok((ws_tx, ws_rx)) => self.post_connection(ws_tx, ws_rx, rx_nostr).await,
+                Ok((ws_tx, ws_rx)) => Some(self.post_connection(ws_tx, ws_rx, rx_nostr).await),
                 // Error during connection
                 Err(e) => {
                     // TODO: avoid string allocation. The error is converted to string only to perform the `!=` binary operation.
@@ -739,6 +1022,8 @@ impl InnerRelay {
                         tracing::error!(url = %self.url, error= %e, "Connection failed.");
                         *last_ws_error = Some(e);
                     }
+
+                    None
                 }
             },
         }
@@ -746,12 +1031,16 @@ impl InnerRelay {
 
     /// To run after websocket connection.
     /// Run message handlers, pinger and other services
+    ///
+    /// Returns the connection uptime.
     async fn post_connection(
         &self,
         mut ws_tx: WebSocketSink,
         ws_rx: WebSocketStream,
-        rx_nostr: &mut MutexGuard<'_, Receiver<JsonMessageItem>>,
-    ) {
+        rx_nostr: &mut MutexGuard<'_, MessageReceivers>,
+    ) -> Duration {
+        let connected_at: Instant = Instant::now();
+
         // (Re)subscribe to relay
         if self.capabilities.can_read() {
             if let Err(e) = self.resubscribe().await {
@@ -786,7 +1075,10 @@ impl InnerRelay {
             // Termination handler
             _ = self.handle_terminate() => {},
             // Pinger
-            _ = self.pinger() => {}
+            res = self.pinger(&ping) => match res {
+                Ok(()) => tracing::trace!(url = %self.url, "Relay pinger exited."),
+                Err(e) => tracing::error!(url = %self.url, error = %e, "Relay pinger exited with error.")
+            },
         }
 
         // Always try to close the WebSocket connection
@@ -794,52 +1086,111 @@ impl InnerRelay {
             Ok(..) => tracing::debug!("WebSocket connection closed."),
             Err(e) => tracing::error!(error = %e, "Can't close WebSocket connection."),
         }
+
+        connected_at.elapsed()
+    }
+
+    /// The relay and the pool outgoing rate limiters
+    fn send_limiters(&self) -> Vec<&SendLimiter> {
+        [
+            self.atomic.send_limiter.as_ref(),
+            self.state.send_limiter.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Send a client message
+    ///
+    /// The message must already be accounted in the outgoing rate limits.
+    async fn send_json_msg(
+        &self,
+        ws_tx: &mut WebSocketSink,
+        item: JsonMessageItem,
+    ) -> Result<(), Error> {
+        let JsonMessageItem {
+            json,
+            event_id,
+            confirmation,
+        } = item;
+
+        // Get messages size
+        let size: usize = json.len();
+
+        // Log
+        tracing::debug!("Sending '{json}' to '{}' (size: {size} bytes)", self.url);
+
+        // Compose WebSocket text messages
+        let msg: Message = Message::Text(json);
+
+        // Send WebSocket messages
+        let res: Result<(), Error> = send_ws_msg(ws_tx, msg).await;
+
+        // Update the flush state
+        let shutting_down: bool = self.atomic.shutting_down.load(Ordering::SeqCst);
+        self.atomic.flush.send_modify(|state| {
+            state.queued = state.queued.saturating_sub(1);
+
+            if shutting_down && res.is_ok() {
+                state.sent += 1;
+            }
+
+            // Wait for the OK, or report it as unacknowledged if the sending failed
+            if let Some(id) = event_id {
+                state.unacknowledged.insert(id);
+            }
+        });
+
+        res?;
+
+        // Send the confirmation
+        if let Some(confirmation) = confirmation {
+            if confirmation.send(()).is_err() {
+                tracing::error!(url = %self.url, "Can't send msg confirmation.");
+            }
+        }
+
+        Ok(())
     }
 
     async fn sender_message_handler(
         &self,
         ws_tx: &mut WebSocketSink,
-        rx_nostr: &mut MutexGuard<'_, Receiver<JsonMessageItem>>,
+        rx_nostr: &mut MutexGuard<'_, MessageReceivers>,
         ping: &PingTracker,
     ) -> Result<(), Error> {
         #[cfg(target_arch = "wasm32")]
         let _ping = ping;
 
-        loop {
-            tokio::select! {
-                // Nostr channel receiver
-                Some(JsonMessageItem { json, confirmation }) = rx_nostr.recv() => {
-                    // Get messages size
-                    let size: usize = json.len();
-
-                    // Log
-                    tracing::debug!("Sending '{json}' to '{}' (size: {size} bytes)", self.url);
+        let MessageReceivers {
+            nostr,
+            priority,
+            pending,
+        } = &mut **rx_nostr;
 
-                    // Compose WebSocket text messages
-                    let msg: Message = Message::Text(json);
+        loop {
+            // Size of the pending message
+            let pending_size: usize = pending.as_ref().map(|item| item.json.len()).unwrap_or(0);
+            let limiters: Vec<&SendLimiter> = self.send_limiters();
 
-                    // Send WebSocket messages
-                    send_ws_msg(ws_tx, msg).await?;
+            tokio::select! {
+                // Check the priority messages and the pings first
+                biased;
 
-                    // Send the confirmation
-                    if let Some(confirmation) = confirmation {
-                        if confirmation.send(()).is_err() {
-                            tracing::error!(url = %self.url, "Can't send msg confirmation.");
-                        }
+                // Priority channel receiver: skip the queue, without waiting for the rate limits
+                Some(item) = priority.recv() => {
+                    for limiter in limiters.iter() {
+                        limiter.acquire_now(item.json.len());
                     }
 
-                    // Increase sent bytes
-                    self.stats.add_bytes_sent(size);
+                    self.send_json_msg(ws_tx, item).await?;
                 }
                 // Ping channel receiver
                 _ = self.atomic.channels.ping.notified() => {
                     #[cfg(not(target_arch = "wasm32"))]
                     {
-                        // If the last nonce is NOT 0, check if relay replied.
-                        // Return error if relay not replied
-                        if ping.last_nonce() != 0 && !ping.replied() {
-                            return Err(Error::NotRepliedToPing);
-                        }
+                        // The missed pongs are checked by the pinger
 
                         // Generate and save nonce
                         let mut rng = OsRng.unwrap_err();
@@ -860,6 +1211,17 @@ impl InnerRelay {
                         tracing::debug!(url = %self.url, nonce = %nonce, "Ping sent.");
                     }
                 }
+                // Wait for the rate limits of the pending message.
+                // The wait is cancelled (without consuming the limits) if a priority message or a ping comes first.
+                _ = throttle::acquire_all(&limiters, pending_size), if pending.is_some() => {
+                    if let Some(item) = pending.take() {
+                        self.send_json_msg(ws_tx, item).await?;
+                    }
+                }
+                // Nostr channel receiver
+                Some(item) = nostr.recv(), if pending.is_none() => {
+                    *pending = Some(item);
+                }
                 else => break
             }
         }
@@ -877,38 +1239,42 @@ impl InnerRelay {
         let _ping = ping;
 
         while let Some(msg) = ws_rx.next().await {
-            match msg? {
+            let msg: Message = match msg {
+                Ok(msg) => msg,
+                // Not a network issue: the relay is misbehaving
+                Err(e @ TransportError::MessageTooLarge { .. }) => {
+                    self.stats.new_oversized_message();
+                    self.atomic.oversized_message.store(true, Ordering::SeqCst);
+                    tracing::warn!(url = %self.url, error = %e, "Relay sent a message exceeding the size limits.");
+                    return Err(Error::Transport(e));
+                }
+                Err(e) => return Err(Error::Transport(e)),
+            };
+
+            match msg {
                 Message::Text(json) => self.handle_relay_message(&json, &ingester_tx).await,
                 Message::Binary(_) => {
                     tracing::warn!(url = %self.url, "Binary messages aren't supported.");
                 }
                 #[cfg(not(target_arch = "wasm32"))]
                 Message::Pong(bytes) if self.opts.ping && self.state.transport.support_ping() => {
-                    match bytes.try_into() {
-                        Ok(nonce) => {
-                            // Nonce from big-endian bytes
-                            let nonce: u64 = u64::from_be_bytes(nonce);
-
-                            // Get last nonce
-                            let last_nonce: u64 = ping.last_nonce();
-
-                            // Check if last nonce not matches the received one
-                            if last_nonce != nonce {
-                                return Err(Error::PongNotMatch {
-                                    expected: last_nonce,
-                                    received: nonce,
-                                });
-                            }
+                    // Nonce from big-endian bytes
+                    let nonce: Option<u64> = bytes.try_into().ok().map(u64::from_be_bytes);
 
+                    // Ignore the pongs not matching the last ping (i.e., late replies to a missed ping)
+                    match nonce {
+                        Some(nonce) if nonce == ping.last_nonce() => {
                             // Set ping as replied
                             ping.set_replied(true);
 
                             // Save latency
                             let sent_at = ping.sent_at().await;
-                            self.stats.save_latency(sent_at.elapsed());
+                            let rtt: Duration = sent_at.elapsed();
+                            self.stats.save_latency(rtt);
+                            self.send_notification(RelayNotification::Rtt { rtt }, true);
                         }
-                        Err(..) => {
-                            return Err(Error::CantParsePong);
+                        _ => {
+                            tracing::debug!(url = %self.url, "Ignoring unexpected pong.");
                         }
                     }
                 }
@@ -916,7 +1282,8 @@ impl InnerRelay {
                 Message::Close(None) => break,
                 #[cfg(not(target_arch = "wasm32"))]
                 Message::Close(Some(frame)) => {
-                    tracing::info!(code = %frame.code, reason = %frame.reason, "Connection closed by peer.");
+                    let code: WebSocketCloseCode = WebSocketCloseCode::from(&frame);
+                    tracing::info!(code = code.as_u16(), kind = %code, reason = %frame.reason, "Connection closed by peer.");
                     break;
                 }
                 #[cfg(not(target_arch = "wasm32"))]
@@ -935,31 +1302,8 @@ impl InnerRelay {
             match command {
                 // Authenticate to relay
                 IngesterCommand::Authenticate { challenge } => {
-                    match self.auth(challenge).await {
-                        Ok(..) => {
-                            self.send_notification(RelayNotification::Authenticated, false);
-
-                            tracing::info!(url = %self.url, "Authenticated to relay.");
-
-                            // TODO: ?
-                            if let Err(e) = self.resubscribe().await {
-                                tracing::error!(
-                                    url = %self.url,
-                                    error = %e,
-                                    "Impossible to resubscribe."
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            self.send_notification(RelayNotification::AuthenticationFailed, false);
-
-                            tracing::error!(
-                                url = %self.url,
-                                error = %e,
-                                "Can't authenticate to relay."
-                            );
-                        }
-                    }
+                    // Errors are already logged
+                    let _ = self.authenticate(challenge).await;
                 }
             }
         }
@@ -969,9 +1313,13 @@ impl InnerRelay {
 
     /// Monitor if it's time to put the relay in sleep mode.
     async fn sleep_when_idle_monitor(&self) {
+        let mut interval: Interval = Interval::new(SLEEP_INTERVAL);
+
+        // The first tick is immediate
+        interval.tick().await;
+
         loop {
-            // Sleep
-            time::sleep(SLEEP_INTERVAL).await;
+            interval.tick().await;
 
             // Check if should go to sleep
             if self.should_sleep().await {
@@ -984,18 +1332,43 @@ impl InnerRelay {
         }
     }
 
-    /// Send a signal every [`PING_INTERVAL`] to the other tasks, asking to ping the relay.
-    async fn pinger(&self) {
+    /// Send a signal every [`RelayOptions::ping_interval`] to the other tasks, asking to ping the relay.
+    ///
+    /// Returns an error if the relay doesn't reply within the [`RelayOptions::pong_timeout`]
+    /// to [`RelayOptions::max_missed_pongs`] consecutive pings.
+    async fn pinger(&self, ping: &PingTracker) -> Result<(), Error> {
+        #[cfg(target_arch = "wasm32")]
+        let _ping = ping;
+
+        let mut interval: Interval = Interval::new(self.opts.ping_interval.max(MIN_PING_INTERVAL));
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut missed: u32 = 0;
+
         loop {
+            interval.tick().await;
+
             // Check if support ping
             #[cfg(not(target_arch = "wasm32"))]
             if self.opts.ping && self.state.transport.support_ping() {
                 // Ping supported, ping!
                 self.atomic.channels.ping();
-            }
 
-            // Sleep
-            time::sleep(PING_INTERVAL).await;
+                // Wait for the pong
+                time::sleep(self.opts.pong_timeout).await;
+
+                if ping.replied() {
+                    missed = 0;
+                    continue;
+                }
+
+                missed += 1;
+
+                if missed >= self.opts.max_missed_pongs {
+                    return Err(Error::NotRepliedToPing);
+                }
+
+                tracing::warn!(url = %self.url, missed, "Relay not replied to ping.");
+            }
         }
     }
 
@@ -1017,7 +1390,7 @@ impl InnerRelay {
                             Some(MachineReadablePrefix::Pow) => HandleClosedMsg::Remove,
                             Some(MachineReadablePrefix::Blocked) => HandleClosedMsg::Remove,
                             Some(MachineReadablePrefix::RateLimited) => {
-                                // TODO: add something like MarkAsRateLimited?
+                                self.mark_rate_limited(RateLimitClass::Req, message);
                                 // TODO: And retry after some time to re-subscribe
                                 HandleClosedMsg::MarkAsClosed
                             }
@@ -1056,14 +1429,53 @@ impl InnerRelay {
                         }
                     }
                     RelayMessage::EndOfStoredEvents(id) => {
+                        self.stats.clear_rate_limit(RateLimitClass::Req);
                         self.received_eose(id).await;
                     }
-                    RelayMessage::Auth { challenge } if self.state.is_authenticator_available() => {
-                        // Forward action to ingester
-                        let _ = ingester_tx.send(IngesterCommand::Authenticate {
-                            challenge: challenge.to_string(),
-                        });
+                    RelayMessage::Ok {
+                        event_id,
+                        status,
+                        message,
+                    } => {
+                        self.atomic
+                            .flush
+                            .send_if_modified(|state| state.unacknowledged.remove(event_id));
+
+                        if *status {
+                            self.stats.clear_rate_limit(RateLimitClass::Publish);
+                        } else if let Some(MachineReadablePrefix::RateLimited) =
+                            MachineReadablePrefix::parse(message)
+                        {
+                            self.mark_rate_limited(RateLimitClass::Publish, message);
+                        }
+                    }
+                    RelayMessage::Notice(message) => {
+                        // The NOTICE isn't bound to an operation: limit both
+                        if let Some(MachineReadablePrefix::RateLimited) =
+                            MachineReadablePrefix::parse(message)
+                        {
+                            self.mark_rate_limited(RateLimitClass::Publish, message);
+                            self.mark_rate_limited(RateLimitClass::Req, message);
+                        }
                     }
+                    RelayMessage::Auth { challenge } => match self.opts.auth_policy {
+                        AuthPolicy::Automatic if self.state.is_authenticator_available() => {
+                            // Forward action to ingester
+                            let _ = ingester_tx.send(IngesterCommand::Authenticate {
+                                challenge: challenge.to_string(),
+                            });
+                        }
+                        AuthPolicy::Manual => {
+                            // Let the app decide when to authenticate
+                            self.send_notification(
+                                RelayNotification::AuthChallenge {
+                                    challenge: challenge.to_string(),
+                                },
+                                true,
+                            );
+                        }
+                        AuthPolicy::Automatic | AuthPolicy::Never => (),
+                    },
                     _ => (),
                 }
 
@@ -1097,9 +1509,6 @@ impl InnerRelay {
 
         tracing::debug!("Received '{msg}' from '{}' (size: {size} bytes)", self.url);
 
-        // Update bytes received
-        self.stats.add_bytes_received(size);
-
         // Check message size
         if let Some(max_size) = self.opts.limits.messages.max_size {
             let max_size: usize = max_size as usize;
@@ -1114,17 +1523,39 @@ impl InnerRelay {
                 subscription_id,
                 event,
             } => {
-                self.handle_event_msg(subscription_id.into_owned(), event.into_owned())
+                self.handle_event_msg(subscription_id.into_owned(), event.into_owned(), Some(msg))
                     .await
             }
             m => Ok(Some(m)),
         }
     }
 
+    /// Verify the event signature, in the blocking thread pool if enabled
+    async fn verify_event(&self, event: Event) -> Result<Event, Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.opts.verify_in_blocking_pool {
+            let (event, res) = task::spawn_blocking(move || {
+                let res = event.verify();
+                (event, res)
+            })
+            .await
+            .map_err(|e| Error::Any(Box::new(e)))?;
+            res?;
+            return Ok(event);
+        }
+
+        event.verify()?;
+        Ok(event)
+    }
+
+    /// Handle an `EVENT` message
+    ///
+    /// `raw` is the original relay message, used to preserve the original JSON of the event.
     async fn handle_event_msg(
         &self,
         subscription_id: SubscriptionId,
-        event: Event,
+        mut event: Event,
+        raw: Option<&str>,
     ) -> Result<Option<RelayMessage<'static>>, Error> {
         // Check event size
         if let Some(max_size) = self.opts.limits.events.get_max_size(&event.kind) {
@@ -1209,66 +1640,102 @@ impl InnerRelay {
             return Err(Error::EventExpired);
         }
 
-        // Check event admission policy
-        if let Some(policy) = &self.state.admit_policy {
-            if let AdmitStatus::Rejected { .. } = policy
-                .admit_event(&self.url, &subscription_id, &event)
-                .await?
-            {
-                return Ok(None);
-            }
+        // Recompute the ID, instead of trusting the one sent by the relay
+        if self.opts.verify_ids && !event.verify_id() {
+            self.stats.new_id_mismatch();
+            return Err(Error::EventIdMismatch);
         }
 
         // Check the event status
-        match self.state.database().check_id(&event.id).await? {
+        let is_new: bool = match self.state.database().check_id(&event.id).await? {
             // Already saved, continue with code execution
-            DatabaseEventStatus::Saved => {}
+            DatabaseEventStatus::Saved => false,
             // Deleted, immediately return
             DatabaseEventStatus::Deleted => return Ok(None),
             // Not existent, verify the event and try to save it to the database
-            DatabaseEventStatus::NotExistent => {
-                // Check if the event was already verified.
-                //
-                // This is useful if someone continues to send the same invalid event:
-                // since invalid events aren't stored in the database,
-                // skipping this check would result in the re-verification of the event.
-                // This may also be useful to avoid double verification if the event is received at the exact same time by many different Relay instances.
-                //
-                // This is important since event signature verification is a heavy job!
-                if !self.state.verified(&event.id).await {
-                    event.verify()?;
-                }
+            DatabaseEventStatus::NotExistent => true,
+        };
 
-                // Save into the database
-                let send_notification: bool = match self.state.database().save_event(&event).await?
-                {
-                    SaveEventStatus::Success => true,
-                    SaveEventStatus::Rejected(reason) => match reason {
-                        RejectedReason::Ephemeral => true,
-                        RejectedReason::Duplicate => true,
-                        RejectedReason::Deleted => false,
-                        RejectedReason::Expired => false,
-                        RejectedReason::Replaced => false,
-                        RejectedReason::InvalidDelete => false,
-                        RejectedReason::Vanished => false,
-                        RejectedReason::Other => true,
-                    },
-                };
+        // Whether the signature has been verified by this call
+        let mut verified: bool = false;
+
+        // Check if the event was already verified.
+        //
+        // This is useful if someone continues to send the same invalid event:
+        // since invalid events aren't stored in the database,
+        // skipping this check would result in the re-verification of the event.
+        // This may also be useful to avoid double verification if the event is received at the exact same time by many different Relay instances.
+        //
+        // This is important since event signature verification is a heavy job!
+        if is_new && !self.state.verified(&event.id).await {
+            event = self.verify_event(event).await?;
+            verified = true;
+        }
 
-                // If the notification should NOT be sent, immediately return.
-                if !send_notification {
-                    return Ok(None);
+        // Check event admission policy.
+        // Checked after the verification, so the forged events can't affect the state of the policy.
+        if let Some(policy) = &self.state.admit_policy {
+            if let AdmitStatus::Rejected { reason } = policy
+                .admit_event(&self.url, &subscription_id, &event)
+                .await?
+            {
+                if let Some(reason) = reason {
+                    tracing::debug!(url = %self.url, id = %event.id, reason = %reason, "Event rejected by admission policy.");
                 }
+                return Ok(None);
+            }
+        }
 
-                // Send notification
-                self.send_notification(
-                    RelayNotification::Event {
-                        subscription_id: subscription_id.clone(),
-                        event: Box::new(event.clone()),
-                    },
-                    true,
-                );
+        if is_new {
+            // Save into the database
+            let send_notification: bool = match self.state.database().save_event(&event).await? {
+                SaveEventStatus::Success => true,
+                SaveEventStatus::Rejected(reason) => match reason {
+                    RejectedReason::Ephemeral => true,
+                    RejectedReason::Duplicate => true,
+                    RejectedReason::Deleted => false,
+                    RejectedReason::Expired => false,
+                    RejectedReason::Replaced => false,
+                    RejectedReason::InvalidDelete => false,
+                    RejectedReason::Vanished => false,
+                    RejectedReason::Other => true,
+                },
+            };
+
+            // If the notification should NOT be sent, immediately return.
+            if !send_notification {
+                return Ok(None);
+            }
+
+            // Keep the original JSON of the event, to re-broadcast it byte-identical.
+            // Only the copy verified by this call is kept: the other ones may have been forged.
+            if verified && self.state.is_preserving_raw_events() {
+                if let Some(json) = raw.and_then(extract_raw_event) {
+                    self.state.save_raw_event(event.id, json).await;
+                }
             }
+
+            // Send notification
+            self.send_notification(
+                RelayNotification::Event {
+                    subscription_id: subscription_id.clone(),
+                    event: Box::new(event.clone()),
+                },
+                true,
+            );
+        }
+
+        // Move the cursor of the subscription
+        self.track_newest_event(&subscription_id, event.created_at)
+            .await;
+
+        // Estimate the relay clock from the events received in real time.
+        // Gift wraps are skipped, since their `created_at` is randomized.
+        if self.opts.auth_relay_clock
+            && event.kind != Kind::GiftWrap
+            && self.has_received_eose(&subscription_id).await
+        {
+            self.stats.record_relay_time(event.created_at);
         }
 
         Ok(Some(RelayMessage::Event {
@@ -1341,43 +1808,273 @@ impl InnerRelay {
             return Err(Error::ReadDisabled);
         }
 
+        // Serialize the message
+        let json: String = self.client_msg_as_json(&msg).await;
+
+        // The authentication can't be delayed by the queued messages
+        let priority: bool = msg.is_auth();
+
+        let event_id: Option<EventId> = match &msg {
+            ClientMessage::Event(event) => Some(event.id),
+            _ => None,
+        };
+
+        // Account the message before queueing it, to not race with the sender
+        self.atomic.flush.send_modify(|state| state.queued += 1);
+
         match wait_until_sent {
             Some(timeout) => {
                 // Create a channel
                 let (tx, rx) = oneshot::channel();
 
                 // Send the item
-                self.atomic.channels.send_client_msg(JsonMessageItem {
-                    json: msg.as_json(),
-                    confirmation: Some(tx),
-                })?;
+                self.enqueue(
+                    JsonMessageItem {
+                        json,
+                        event_id,
+                        confirmation: Some(tx),
+                    },
+                    priority,
+                )?;
 
                 // Wait for confirmation
                 Ok(time::timeout(Some(timeout), rx)
                     .await
                     .ok_or(Error::Timeout)??)
             }
-            None => self.atomic.channels.send_client_msg(JsonMessageItem {
-                json: msg.as_json(),
-                confirmation: None,
-            }),
+            None => self.enqueue(
+                JsonMessageItem {
+                    json,
+                    event_id,
+                    confirmation: None,
+                },
+                priority,
+            ),
         }
     }
 
-    async fn auth(&self, challenge: String) -> Result<(), Error> {
-        // Check if the relay can authenticate
-        if let Some(policy) = &self.state.admit_policy {
-            if let AdmitStatus::Rejected { reason } = policy.admit_auth(&self.url).await? {
-                return Err(Error::AuthenticationNotAdmitted { reason });
-            }
+    fn enqueue(&self, item: JsonMessageItem, priority: bool) -> Result<(), Error> {
+        let res: Result<(), Error> = self.atomic.channels.send_client_msg(item, priority);
+
+        // Not queued
+        if res.is_err() {
+            self.atomic
+                .flush
+                .send_modify(|state| state.queued = state.queued.saturating_sub(1));
         }
 
-        let Some(authenticator) = &self.state.authenticator else {
-            return Err(Error::AuthenticatorNotConfigured);
-        };
+        res
+    }
 
-        // Create the NIP-42 auth event
-        let event: Event = authenticator.make_auth_event(&self.url, &challenge).await?;
+    /// Flush the queued messages, wait for the pending `OK`s and then shutdown the relay.
+    pub(super) async fn graceful_shutdown(&self, timeout: Duration) -> RelayShutdownSummary {
+        // Stop accepting new messages
+        if !self.atomic.shutting_down.swap(true, Ordering::SeqCst) {
+            let status: RelayStatus = self.status();
+
+            // Flush only if the connection task is (or will be) running
+            if !status.is_initialized()
+                && !status.is_terminated()
+                && !status.is_banned()
+                && !status.is_sleeping()
+                && !status.is_shutdown()
+            {
+                let mut rx = self.atomic.flush.subscribe();
+                let flushed = time::timeout(
+                    Some(timeout),
+                    rx.wait_for(|state| state.queued == 0 && state.unacknowledged.is_empty()),
+                )
+                .await;
+
+                if flushed.is_none() {
+                    tracing::warn!(url = %self.url, "Timeout while flushing the outgoing messages.");
+                }
+            }
+        }
+
+        // Close the connection and stop the tasks
+        self.shutdown();
+
+        let mut summary: RelayShutdownSummary = {
+            let state = self.atomic.flush.borrow();
+            RelayShutdownSummary {
+                sent: state.sent,
+                abandoned: 0,
+                unacknowledged: state.unacknowledged.clone(),
+            }
+        };
+
+        // Wait for the connection task to release the receivers and collect the abandoned messages
+        if let Some(mut receivers) =
+            time::timeout(Some(WEBSOCKET_TX_TIMEOUT), self.atomic.channels.rx_nostr()).await
+        {
+            let MessageReceivers {
+                nostr,
+                priority,
+                pending,
+            } = &mut *receivers;
+            while let Some(item) = pending
+                .take()
+                .or_else(|| priority.try_recv().ok())
+                .or_else(|| nostr.try_recv().ok())
+            {
+                summary.abandoned += 1;
+
+                if let Some(id) = item.event_id {
+                    summary.unacknowledged.insert(id);
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Serialize the client message
+    ///
+    /// If available, the original JSON of the events is used.
+    async fn client_msg_as_json(&self, msg: &ClientMessage<'_>) -> String {
+        match msg {
+            ClientMessage::Event(event) => {
+                if let Some(raw) = self.state.raw_event(&event.id).await {
+                    return format!(r#"["EVENT",{raw}]"#);
+                }
+            }
+            ClientMessage::Req {
+                subscription_id,
+                filters,
+            } => {
+                if let Some(max_limit) = self.max_filter_limit().await {
+                    // Clamp only if needed, to avoid cloning the filters
+                    if filters
+                        .iter()
+                        .any(|f| f.limit.is_some_and(|limit| limit > max_limit))
+                    {
+                        let msg: ClientMessage = ClientMessage::Req {
+                            subscription_id: Cow::Borrowed(subscription_id.as_ref()),
+                            filters: filters
+                                .iter()
+                                .map(|f| Cow::Owned(clamp_limit(f.as_ref().clone(), max_limit)))
+                                .collect(),
+                        };
+                        return msg.as_json();
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        msg.as_json()
+    }
+
+    #[inline]
+    pub(super) async fn information_document(&self) -> Option<RelayInformationDocument> {
+        self.atomic.document.read().await.clone()
+    }
+
+    #[inline]
+    pub(super) async fn set_information_document(&self, document: RelayInformationDocument) {
+        let mut current = self.atomic.document.write().await;
+        *current = Some(document);
+    }
+
+    /// Get the NIP-11 `max_limit`, if known
+    async fn max_filter_limit(&self) -> Option<usize> {
+        let document = self.atomic.document.read().await;
+        let max_limit: i32 = document.as_ref()?.limitation.as_ref()?.max_limit?;
+        usize::try_from(max_limit)
+            .ok()
+            .filter(|max_limit| *max_limit > 0)
+    }
+
+    fn mark_rate_limited(&self, class: RateLimitClass, message: &str) {
+        let hint: Option<Duration> = parse_retry_after(message);
+        let until: Timestamp = self.stats.mark_rate_limited(class, hint);
+
+        tracing::warn!(
+            url = %self.url,
+            class = ?class,
+            until = %until,
+            hinted = hint.is_some(),
+            "Rate-limited by relay."
+        );
+    }
+
+    /// Get the current relay time, if the local clock is skewed and the relay clock must be used
+    fn skewed_clock_now(&self) -> Option<Timestamp> {
+        if !self.opts.auth_relay_clock {
+            return None;
+        }
+
+        let offset: i64 = self.stats.clock_offset()?;
+
+        // Check if the local clock is skewed
+        if offset.unsigned_abs() <= AUTH_CLOCK_SKEW_TOLERANCE {
+            return None;
+        }
+
+        let now: Timestamp = Timestamp::now();
+
+        if offset > 0 {
+            Some(now + offset.unsigned_abs())
+        } else {
+            Some(now - offset.unsigned_abs())
+        }
+    }
+
+    /// Authenticate to the relay, notify the outcome and, on success, re-subscribe.
+    pub(super) async fn authenticate(&self, challenge: String) -> Result<(), Error> {
+        match self.auth(challenge).await {
+            Ok(()) => {
+                self.send_notification(RelayNotification::Authenticated, false);
+
+                tracing::info!(url = %self.url, "Authenticated to relay.");
+
+                // TODO: ?
+                if let Err(e) = self.resubscribe().await {
+                    tracing::error!(
+                        url = %self.url,
+                        error = %e,
+                        "Impossible to resubscribe."
+                    );
+                }
+
+                Ok(())
+            }
+            Err(e) => {
+                self.send_notification(RelayNotification::AuthenticationFailed, false);
+
+                tracing::error!(
+                    url = %self.url,
+                    error = %e,
+                    "Can't authenticate to relay."
+                );
+
+                Err(e)
+            }
+        }
+    }
+
+    async fn auth(&self, challenge: String) -> Result<(), Error> {
+        // Check if the relay can authenticate
+        if let Some(policy) = &self.state.admit_policy {
+            if let AdmitStatus::Rejected { reason } = policy.admit_auth(&self.url).await? {
+                return Err(Error::AuthenticationNotAdmitted { reason });
+            }
+        }
+
+        let Some(authenticator) = &self.state.authenticator else {
+            return Err(Error::AuthenticatorNotConfigured);
+        };
+
+        // Create the NIP-42 auth event
+        let event: Event = match self.skewed_clock_now() {
+            Some(created_at) => {
+                authenticator
+                    .make_auth_event_at(&self.url, &challenge, created_at)
+                    .await?
+            }
+            None => authenticator.make_auth_event(&self.url, &challenge).await?,
+        };
 
         // Ensure event is valid
         if !nip42::is_valid_auth_event(&event, &self.url, &challenge) {
@@ -1445,6 +2142,18 @@ impl InnerRelay {
         let subscriptions = self.subscriptions().await;
         for (id, filters) in subscriptions.into_iter() {
             if !filters.is_empty() && self.should_resubscribe(&id).await {
+                // Skip the events already received.
+                // `since` is inclusive: the events created in the same second of the newest one are received again,
+                // but are deduplicated by the database.
+                let filters: Vec<Filter> = match self.resume_cursor(&id).await {
+                    Some(since) => filters
+                        .into_iter()
+                        .map(|filter| resume_filter(filter, since))
+                        .collect(),
+                    None => filters,
+                };
+
+                self.subscription_requested(&id).await;
                 self.send_msg(ClientMessage::req(id, filters), None).await?;
             } else {
                 tracing::debug!("Skip re-subscription of '{id}'");
@@ -1610,7 +2319,7 @@ impl InnerRelay {
                             match MachineReadablePrefix::parse(&message) {
                                 Some(MachineReadablePrefix::AuthRequired) => {
                                     // Authentication is not enabled, return.
-                                    if !self.state.is_authenticator_available() {
+                                    if !self.can_authenticate() {
                                         return Some(HandleAutoClosing {
                                             to_close: false, // No need to send CLOSE msg
                                             reason: Some(SubscriptionAutoClosedReason::Closed(
@@ -1786,6 +2495,31 @@ async fn close_ws(tx: &mut WebSocketSink) -> Result<(), Error> {
     }
 }
 
+/// Resume the filter from `since`, unless it already has a stricter one.
+fn resume_filter(mut filter: Filter, since: Timestamp) -> Filter {
+    filter.since = Some(match filter.since {
+        Some(current) => current.max(since),
+        None => since,
+    });
+    filter
+}
+
+/// Extract the original JSON of the event from an `EVENT` relay message.
+fn extract_raw_event(msg: &str) -> Option<String> {
+    let values: Vec<&RawValue> = serde_json::from_str(msg).ok()?;
+    let raw: &RawValue = values.get(2)?;
+    Some(raw.get().to_string())
+}
+
+/// Clamp the filter `limit` to the relay `max_limit`
+fn clamp_limit(mut filter: Filter, max_limit: usize) -> Filter {
+    if let Some(limit) = filter.limit {
+        filter.limit = Some(limit.min(max_limit));
+    }
+
+    filter
+}
+
 #[cfg(test)]
 mod tests {
     use nostr::{EventBuilder, Filter, Keys, Kind, RelayUrl, SubscriptionId};
@@ -1793,6 +2527,344 @@ mod tests {
     use super::*;
     use crate::relay::{Relay, RelayOptions};
 
+    #[tokio::test]
+    async fn test_auth_with_relay_clock() {
+        use nostr::{ClientMessage, RelayMessage};
+
+        use crate::authenticator::SignerAuthenticator;
+        use crate::relay::constants::CLOCK_OFFSET_MIN_SAMPLES;
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let keys = Keys::generate();
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .authenticator(SignerAuthenticator::new(keys.clone()))
+            .opts(RelayOptions::default().auth_relay_clock(true))
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let id = relay
+            .subscribe(Filter::new().kind(Kind::TextNote))
+            .await
+            .unwrap();
+
+        // Wait for the REQ
+        transport.recv().await.unwrap();
+
+        // The relay clock is 1 hour ahead
+        let relay_now = Timestamp::now() + 3600;
+
+        // Events received before the EOSE are not used for the estimate
+        let stored = EventBuilder::text_note("stored")
+            .custom_created_at(Timestamp::from_secs(1000))
+            .sign(&keys)
+            .unwrap();
+        transport.push(Message::Text(
+            RelayMessage::event(id.clone(), stored).as_json(),
+        ));
+        transport.push(Message::Text(RelayMessage::eose(id.clone()).as_json()));
+
+        for i in 0..CLOCK_OFFSET_MIN_SAMPLES {
+            let event = EventBuilder::text_note(format!("live {i}"))
+                .custom_created_at(relay_now)
+                .sign(&keys)
+                .unwrap();
+            transport.push(Message::Text(
+                RelayMessage::event(id.clone(), event).as_json(),
+            ));
+        }
+
+        transport.push(Message::Text(RelayMessage::auth("challenge").as_json()));
+
+        let msg = time::timeout(Some(Duration::from_secs(2)), transport.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let event = match msg {
+            Message::Text(json) => match ClientMessage::from_json(json).unwrap() {
+                ClientMessage::Auth(event) => event.into_owned(),
+                msg => panic!("unexpected message: {msg:?}"),
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        };
+
+        // The AUTH event must be dated with the relay time
+        assert!(event.created_at >= relay_now - 5);
+        assert!(event.created_at <= Timestamp::now() + 3605);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_status_churn() {
+        let relay = Relay::new(RelayUrl::parse("ws://127.0.0.1:6666").unwrap());
+
+        // Status notifications: the status is never behind the notification
+        let mut notifications = relay.inner.internal_notification_sender.subscribe();
+        let r = relay.clone();
+        let notified = tokio::spawn(async move {
+            loop {
+                match notifications.recv().await {
+                    Ok(RelayNotification::RelayStatus {
+                        status: RelayStatus::Terminated,
+                    }) => return r.status(),
+                    Ok(..) | Err(broadcast::error::RecvError::Lagged(..)) => {}
+                    Err(broadcast::error::RecvError::Closed) => panic!("channel closed"),
+                }
+            }
+        });
+
+        // Many watchers and lock-free readers during the churn
+        let mut watchers = Vec::new();
+        for _ in 0..100 {
+            let mut watch = relay.status_watch();
+            watchers.push(tokio::spawn(async move {
+                *watch.wait_for(|s| s.is_terminated()).await.unwrap()
+            }));
+        }
+        let mut readers = Vec::new();
+        for _ in 0..100 {
+            let r = relay.clone();
+            readers.push(tokio::spawn(async move {
+                while !r.status().is_terminated() {
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+
+        for _ in 0..1_000 {
+            relay.inner.set_status(RelayStatus::Connecting, false);
+            relay.inner.set_status(RelayStatus::Connected, false);
+            relay.inner.set_status(RelayStatus::Disconnected, false);
+        }
+        relay.inner.set_status(RelayStatus::Terminated, false);
+
+        // No lost transitions
+        assert_eq!(notified.await.unwrap(), RelayStatus::Terminated);
+        for watcher in watchers.into_iter() {
+            assert_eq!(watcher.await.unwrap(), RelayStatus::Terminated);
+        }
+        for reader in readers.into_iter() {
+            reader.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_connection_terminal_status() {
+        let relay = Relay::new(RelayUrl::parse("ws://127.0.0.1:6666").unwrap());
+        relay.inner.set_status(RelayStatus::Connecting, false);
+
+        let r = relay.clone();
+        let handle = tokio::spawn(async move {
+            r.wait_for_connection(Duration::from_secs(10)).await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        relay.inner.set_status(RelayStatus::Banned, false);
+
+        // Returns as soon as the relay can't connect anymore
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clamp_filter_limit() {
+        use nostr::ClientMessage;
+        use nostr::nips::nip11::Limitation;
+
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let document = RelayInformationDocument {
+            limitation: Some(Limitation {
+                max_limit: Some(100),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        relay.set_information_document(document).await;
+
+        let filters = vec![
+            Filter::new().kind(Kind::TextNote).limit(500),
+            Filter::new().kind(Kind::Metadata).limit(10),
+            Filter::new().kind(Kind::Reaction),
+        ];
+        let id = relay.subscribe(filters.clone()).await.unwrap();
+
+        // The REQ is clamped
+        let req = match transport.recv().await.unwrap() {
+            Message::Text(json) => ClientMessage::from_json(json).unwrap(),
+            msg => panic!("unexpected message: {msg:?}"),
+        };
+        match req {
+            ClientMessage::Req { filters, .. } => {
+                let limits: Vec<Option<usize>> = filters.iter().map(|f| f.limit).collect();
+                assert_eq!(limits, vec![Some(100), Some(10), None]);
+            }
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+
+        // The stored subscription keeps the caller filters
+        assert_eq!(relay.subscription(&id).await, Some(filters));
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_waits_for_events_sent_before() {
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let keys = Keys::generate();
+        let acked = EventBuilder::text_note("acked").sign(&keys).unwrap();
+        let pending = EventBuilder::text_note("pending").sign(&keys).unwrap();
+
+        // Both the events are written to the socket before the shutdown
+        for event in [&acked, &pending] {
+            relay.send_event(event).wait_for_ok(false).await.unwrap();
+            transport.recv().await.unwrap();
+        }
+
+        // Only the first one is acknowledged
+        transport.push(Message::Text(
+            RelayMessage::ok(acked.id, true, "").as_json(),
+        ));
+        time::sleep(Duration::from_millis(100)).await;
+
+        let summary = relay.graceful_shutdown(Duration::from_millis(300)).await;
+        assert_eq!(summary.sent, 0);
+        assert_eq!(summary.abandoned, 0);
+        assert_eq!(summary.unacknowledged, HashSet::from([pending.id]));
+    }
+
+    #[tokio::test]
+    async fn test_preserve_raw_events() {
+        use crate::events_tracker::MemoryEventsTracker;
+        use crate::relay::RelayCapabilities;
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("café").sign(&keys).unwrap();
+
+        // Same event, with a different (but equivalent) escaping of the content
+        let raw: String = event.as_json().replace("é", "\\u00e9");
+        assert_ne!(raw, event.as_json());
+
+        let transport = MockWebSocketTransport::new();
+        let state = SharedState::new(
+            Arc::new(MemoryEventsTracker::default()),
+            Arc::new(transport.clone()),
+            None,
+            None,
+            None,
+            None,
+            true,
+        );
+        let url = RelayUrl::parse("ws://mock").unwrap();
+        let relay = Relay::new_shared(
+            url,
+            state,
+            RelayCapabilities::default(),
+            RelayOptions::default(),
+        );
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        // Receive the event
+        let msg: String = format!(r#"["EVENT", "test", {raw}]"#);
+        relay.inner.handle_raw_relay_message(&msg).await.unwrap();
+
+        // Re-broadcast the event: the original JSON must be sent
+        relay.send_event(&event).wait_for_ok(false).await.unwrap();
+        assert_eq!(
+            transport.recv().await,
+            Some(Message::Text(format!(r#"["EVENT",{raw}]"#)))
+        );
+
+        // Locally-constructed events are serialized
+        let local = EventBuilder::text_note("local").sign(&keys).unwrap();
+        relay.send_event(&local).wait_for_ok(false).await.unwrap();
+        assert_eq!(
+            transport.recv().await,
+            Some(Message::Text(ClientMessage::event(local).as_json()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preserve_raw_events_keeps_verified_copy() {
+        use crate::events_tracker::MemoryEventsTracker;
+        use crate::relay::RelayCapabilities;
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("original").sign(&keys).unwrap();
+        let raw: String = event.as_json();
+
+        // Same ID and signature, forged content
+        let forged: String = raw.replace("original", "forged");
+        assert_ne!(raw, forged);
+
+        let transport = MockWebSocketTransport::new();
+        let state = SharedState::new(
+            Arc::new(MemoryEventsTracker::default()),
+            Arc::new(transport.clone()),
+            None,
+            None,
+            None,
+            None,
+            true,
+        );
+        let url = RelayUrl::parse("ws://mock").unwrap();
+        let relay = Relay::new_shared(
+            url,
+            state,
+            RelayCapabilities::default(),
+            RelayOptions::default(),
+        );
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        // Receive the original event, then the forged copy
+        for json in [&raw, &forged] {
+            let msg: String = format!(r#"["EVENT", "test", {json}]"#);
+            let _ = relay.inner.handle_raw_relay_message(&msg).await;
+        }
+
+        // The forged copy must not replace the original JSON
+        relay.send_event(&event).wait_for_ok(false).await.unwrap();
+        assert_eq!(
+            transport.recv().await,
+            Some(Message::Text(format!(r#"["EVENT",{raw}]"#)))
+        );
+    }
+
     #[tokio::test]
     async fn test_subscription_verification_accepts_event_matching_any_filter() {
         let keys = Keys::generate();
@@ -1825,7 +2897,7 @@ mod tests {
         // Handle manually the event message
         let message = relay
             .inner
-            .handle_event_msg(subscription_id.clone(), event.clone())
+            .handle_event_msg(subscription_id.clone(), event.clone(), None)
             .await
             .unwrap();
 
@@ -1840,6 +2912,549 @@ mod tests {
             other => panic!("unexpected message: {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn test_verify_ids_rejects_forged_event() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("test").sign(&keys).unwrap();
+
+        // Same ID, different content
+        let forged = Event::new(
+            event.id,
+            event.pubkey,
+            event.created_at,
+            event.kind,
+            event.tags.clone(),
+            "forged",
+            event.sig,
+        );
+
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let opts = RelayOptions::default().verify_ids(true);
+        let relay = Relay::builder(url).opts(opts).build();
+
+        // The original event is already stored
+        relay
+            .inner
+            .state
+            .database()
+            .save_event(&event)
+            .await
+            .unwrap();
+
+        let subscription_id = SubscriptionId::new("test");
+        let res = relay
+            .inner
+            .handle_event_msg(subscription_id.clone(), forged, None)
+            .await;
+        assert!(matches!(res, Err(Error::EventIdMismatch)));
+        assert_eq!(relay.stats().id_mismatches(), 1);
+
+        // The original event is accepted
+        let res = relay
+            .inner
+            .handle_event_msg(subscription_id, event, None)
+            .await;
+        assert!(matches!(res, Ok(Some(RelayMessage::Event { .. }))));
+        assert_eq!(relay.stats().id_mismatches(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_forged_events_dont_reach_admit_policy() {
+        use std::num::NonZeroU32;
+
+        use crate::policy::RateLimitPolicy;
+
+        let victim = Keys::generate();
+        let event = EventBuilder::text_note("test").sign(&victim).unwrap();
+
+        // Event of the attacker, claiming to be of the victim
+        let attacker = EventBuilder::text_note("spam")
+            .sign(&Keys::generate())
+            .unwrap();
+        let forged = Event::new(
+            attacker.id,
+            victim.public_key(),
+            attacker.created_at,
+            attacker.kind,
+            attacker.tags.clone(),
+            attacker.content.clone(),
+            attacker.sig,
+        );
+
+        let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+        let policy = RateLimitPolicy::new(
+            NonZeroU32::new(1).unwrap(),
+            Duration::from_secs(60),
+            NonZeroU32::new(1).unwrap(),
+        );
+        let relay = Relay::builder(url).admit_policy(policy).build();
+
+        let subscription_id = SubscriptionId::new("test");
+        let res = relay
+            .inner
+            .handle_event_msg(subscription_id.clone(), forged, None)
+            .await;
+        assert!(matches!(res, Err(Error::Event(..))));
+
+        // The bucket of the victim is still full
+        let res = relay
+            .inner
+            .handle_event_msg(subscription_id, event, None)
+            .await;
+        assert!(matches!(res, Ok(Some(RelayMessage::Event { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_in_blocking_pool() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("test").sign(&keys).unwrap();
+
+        // Valid ID, signature of another event
+        let other = EventBuilder::text_note("other").sign(&keys).unwrap();
+        let forged = Event::new(
+            event.id,
+            event.pubkey,
+            event.created_at,
+            event.kind,
+            event.tags.clone(),
+            event.content.clone(),
+            other.sig,
+        );
+
+        for enable in [true, false] {
+            let url = RelayUrl::parse("wss://relay.example.com").unwrap();
+            let opts = RelayOptions::default().verify_in_blocking_pool(enable);
+            let relay = Relay::builder(url).opts(opts).build();
+
+            let subscription_id = SubscriptionId::new("test");
+            let res = relay
+                .inner
+                .handle_event_msg(subscription_id.clone(), forged.clone(), None)
+                .await;
+            assert!(matches!(res, Err(Error::Event(..))));
+
+            let res = relay
+                .inner
+                .handle_event_msg(subscription_id, event.clone(), None)
+                .await;
+            assert!(matches!(res, Ok(Some(RelayMessage::Event { .. }))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message() {
+        use futures::SinkExt;
+        use tokio::net::TcpListener;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        use crate::transport::websocket::{DefaultWebsocketTransport, WebSocketLimits};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _ = ws.send(TungsteniteMessage::text("a".repeat(2048))).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let transport = DefaultWebsocketTransport::new().limits(WebSocketLimits {
+            max_message_size: Some(1024),
+            max_frame_size: Some(1024),
+        });
+        let relay = Relay::builder(url)
+            .websocket_transport(transport)
+            .opts(RelayOptions::default().reconnect(false))
+            .build();
+        relay.try_connect().await.unwrap();
+
+        // The connection is closed and the relay marked as misbehaving
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while relay.stats().oversized_messages() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(relay.stats().oversized_messages(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_penalty() {
+        use tokio::net::TcpListener;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        use crate::transport::websocket::WebSocketLimits;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _ = ws.send(TungsteniteMessage::text("a".repeat(2048))).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let penalty = Duration::from_secs(30);
+        let opts = RelayOptions::default()
+            .retry_interval(Duration::from_millis(100))
+            .adjust_retry_interval(false)
+            .oversized_message_penalty(penalty);
+        let relay = Relay::builder(url)
+            .opts(opts)
+            .websocket_limits(WebSocketLimits {
+                max_message_size: Some(1024),
+                max_frame_size: Some(1024),
+            })
+            .build();
+        relay.connect();
+
+        // The reconnection is delayed by the penalty, instead of the retry interval
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while relay.reconnect_delay().is_zero() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(relay.stats().oversized_messages(), 1);
+        assert_eq!(relay.reconnect_delay(), penalty);
+
+        relay.disconnect();
+    }
+
+    #[tokio::test]
+    async fn test_relay_websocket_limits() {
+        use tokio::net::TcpListener;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        use crate::transport::websocket::WebSocketLimits;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _ = ws.send(TungsteniteMessage::text("a".repeat(2048))).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        // The limits of the relay override the ones of the default transport
+        let relay = Relay::builder(url)
+            .opts(RelayOptions::default().reconnect(false))
+            .websocket_limits(WebSocketLimits {
+                max_message_size: Some(1024),
+                max_frame_size: Some(1024),
+            })
+            .build();
+        relay.try_connect().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while relay.stats().oversized_messages() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(relay.stats().oversized_messages(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pong_timeout() {
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        // Accept the connection but never read from it, so the pings are never answered
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let _ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let opts = RelayOptions::default()
+            .reconnect(false)
+            .ping_interval(Duration::from_millis(100))
+            .pong_timeout(Duration::from_millis(200));
+        let relay = Relay::builder(url).opts(opts).build();
+        relay.try_connect().await.unwrap();
+        assert_eq!(relay.status(), RelayStatus::Connected);
+
+        // The dead connection is closed
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while relay.status() == RelayStatus::Connected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_missed_pongs() {
+        use futures::SinkExt;
+        use tokio::net::TcpListener;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        // Send an unexpected pong, then never read, so the pings are never answered
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            ws.send(TungsteniteMessage::Pong(b"app".to_vec().into()))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let opts = RelayOptions::default()
+            .reconnect(false)
+            .ping_interval(Duration::from_millis(100))
+            .pong_timeout(Duration::from_millis(200))
+            .max_missed_pongs(3);
+        let relay = Relay::builder(url).opts(opts).build();
+        relay.try_connect().await.unwrap();
+
+        // The unexpected pong and the first missed pong don't close the connection
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(relay.status(), RelayStatus::Connected);
+
+        // Closed after 3 missed pongs
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while relay.status() == RelayStatus::Connected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_with_since_cursor() {
+        use futures::SinkExt;
+        use tokio::net::TcpListener;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = RelayUrl::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        let newest = Timestamp::from_secs(1_700_000_000);
+        let event = EventBuilder::text_note("resume")
+            .custom_created_at(newest)
+            .sign(&Keys::generate())
+            .unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            // First connection: reply to the REQs with an event, then drop the connection
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut replied: usize = 0;
+            while replied < 2 {
+                let msg = ws.next().await.unwrap().unwrap();
+                if let Ok(ClientMessage::Req {
+                    subscription_id, ..
+                }) = ClientMessage::from_json(msg.to_text().unwrap_or_default())
+                {
+                    replied += 1;
+                    let id: SubscriptionId = subscription_id.into_owned();
+                    let msg = RelayMessage::event(id.clone(), event.clone());
+                    ws.send(TungsteniteMessage::Text(msg.as_json().into()))
+                        .await
+                        .unwrap();
+                    let msg = RelayMessage::eose(id);
+                    ws.send(TungsteniteMessage::Text(msg.as_json().into()))
+                        .await
+                        .unwrap();
+                }
+            }
+
+            // The connection time has the seconds precision
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            ws.close(None).await.unwrap();
+            drop(ws);
+
+            // Second connection: forward the REQs
+            let (stream, ..) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(msg)) = ws.next().await {
+                if let Ok(ClientMessage::Req {
+                    subscription_id,
+                    filters,
+                }) = ClientMessage::from_json(msg.to_text().unwrap_or_default())
+                {
+                    let filters: Vec<Filter> =
+                        filters.into_iter().map(|f| f.into_owned()).collect();
+                    tx.send((subscription_id.into_owned(), filters)).unwrap();
+                }
+            }
+        });
+
+        let opts = RelayOptions::default()
+            .retry_interval(Duration::from_millis(100))
+            .adjust_retry_interval(false);
+        let relay = Relay::builder(url).opts(opts).build();
+        relay.try_connect().await.unwrap();
+
+        let filter = Filter::new().kind(Kind::TextNote);
+        let resumed = SubscriptionId::new("resumed");
+        let plain = SubscriptionId::new("plain");
+        relay
+            .subscribe(filter.clone())
+            .with_id(resumed.clone())
+            .resume(true)
+            .await
+            .unwrap();
+        relay
+            .subscribe(filter.clone())
+            .with_id(plain.clone())
+            .await
+            .unwrap();
+
+        // Both are re-subscribed, but only one is resumed from the newest event
+        let mut reqs: HashMap<SubscriptionId, Vec<Filter>> = HashMap::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while reqs.len() < 2 {
+                let (id, filters) = rx.recv().await.unwrap();
+                reqs.insert(id, filters);
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reqs[&resumed], vec![filter.clone().since(newest)]);
+        assert_eq!(reqs[&plain], vec![filter.clone()]);
+
+        // The tracked filters are left untouched
+        assert_eq!(relay.subscriptions().await.len(), 2);
+        assert_eq!(relay.subscription(&resumed).await, Some(vec![filter]));
+    }
+
+    #[tokio::test]
+    async fn test_resume_cursor_only_from_complete_backfill() {
+        use crate::transport::mock::MockWebSocketTransport;
+
+        let transport = MockWebSocketTransport::new();
+        let relay = Relay::builder(RelayUrl::parse("ws://mock").unwrap())
+            .websocket_transport(transport.clone())
+            .build();
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let id = SubscriptionId::new("resume");
+        relay
+            .subscribe(Filter::new().kind(Kind::TextNote))
+            .with_id(id.clone())
+            .resume(true)
+            .await
+            .unwrap();
+
+        let keys = Keys::generate();
+        let note = |secs: u64| {
+            EventBuilder::text_note(format!("{secs}"))
+                .custom_created_at(Timestamp::from_secs(secs))
+                .sign(&keys)
+                .unwrap()
+        };
+
+        // Stored events, newest first: the cursor isn't moved until the EOSE
+        relay
+            .inner
+            .handle_event_msg(id.clone(), note(2000), None)
+            .await
+            .unwrap();
+        assert_eq!(relay.inner.resume_cursor(&id).await, None);
+
+        // The connection dropped before the EOSE: the partial backfill is discarded
+        relay.inner.subscription_requested(&id).await;
+        relay
+            .inner
+            .handle_event_msg(id.clone(), note(1500), None)
+            .await
+            .unwrap();
+        relay.inner.received_eose(&id).await;
+        assert_eq!(
+            relay.inner.resume_cursor(&id).await,
+            Some(Timestamp::from_secs(1500))
+        );
+
+        // Live event
+        relay
+            .inner
+            .handle_event_msg(id.clone(), note(1600), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            relay.inner.resume_cursor(&id).await,
+            Some(Timestamp::from_secs(1600))
+        );
+
+        // A future-dated event can't move the cursor after now
+        let future = Timestamp::now() + Duration::from_secs(86_400);
+        relay
+            .inner
+            .handle_event_msg(id.clone(), note(future.as_secs()), None)
+            .await
+            .unwrap();
+        assert!(relay.inner.resume_cursor(&id).await.unwrap() <= Timestamp::now());
+    }
+
+    #[test]
+    fn test_resume_filter_keeps_stricter_since() {
+        let since = Timestamp::from_secs(1_700_000_000);
+
+        let filter = Filter::new().since(since + 100);
+        assert_eq!(resume_filter(filter, since).since, Some(since + 100));
+
+        let filter = Filter::new().since(since - 100);
+        assert_eq!(resume_filter(filter, since).since, Some(since));
+    }
+
+    #[test]
+    fn test_reconnect_jitter_without_backoff() {
+        let url = RelayUrl::parse("ws://localhost:8080").unwrap();
+        let retry_interval = Duration::from_secs(10);
+        let jitter = Duration::from_secs(2);
+
+        // Fixed and adjusted intervals
+        for adjust in [false, true] {
+            let opts = RelayOptions::default()
+                .retry_interval(retry_interval)
+                .adjust_retry_interval(adjust)
+                .reconnect_jitter(jitter);
+            let relay = Relay::builder(url.clone()).opts(opts).build();
+
+            let intervals: HashSet<Duration> = (0..20)
+                .map(|_| relay.inner.calculate_retry_interval())
+                .collect();
+
+            assert!(
+                intervals
+                    .iter()
+                    .all(|i| *i >= retry_interval && *i <= retry_interval + jitter)
+            );
+            assert!(intervals.len() > 1);
+        }
+
+        // Jitter disabled
+        let opts = RelayOptions::default()
+            .retry_interval(retry_interval)
+            .adjust_retry_interval(false)
+            .reconnect_jitter(Duration::ZERO);
+        let relay = Relay::builder(url).opts(opts).build();
+        assert_eq!(relay.inner.calculate_retry_interval(), retry_interval);
+    }
 }
 
 #[cfg(bench)]
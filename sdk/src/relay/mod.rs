@@ -13,7 +13,7 @@ use std::time::Duration;
 use async_utility::time;
 use futures::{Stream, StreamExt};
 use nostr_database::prelude::*;
-use tokio::sync::{broadcast, oneshot};
+use tokio::sync::{broadcast, oneshot, watch};
 
 mod api;
 mod builder;
@@ -25,6 +25,8 @@ mod limits;
 mod notification;
 mod options;
 mod ping;
+mod rate_limit;
+mod shutdown;
 mod stats;
 mod status;
 
@@ -36,9 +38,13 @@ use self::inner::InnerRelay;
 pub use self::limits::*;
 pub use self::notification::*;
 pub use self::options::*;
+pub use self::rate_limit::RateLimitClass;
+pub use self::shutdown::*;
 pub use self::stats::*;
 pub use self::status::*;
 use crate::client::ClientNotification;
+#[cfg(not(feature = "negentropy"))]
+use crate::feature::RequiresNegentropyFeature;
 use crate::shared::SharedState;
 use crate::stream::NotificationStream;
 
@@ -145,7 +151,9 @@ impl Relay {
             builder.websocket_transport,
             builder.admit_policy,
             builder.authenticator,
+            builder.upgrade_headers,
             None,
+            false,
         );
 
         Self::new_shared(builder.url, state, builder.capabilities, builder.opts)
@@ -165,17 +173,48 @@ impl Relay {
     }
 
     /// Get status
+    ///
+    /// Lock-free: the status is read from an atomic.
     #[inline]
     pub fn status(&self) -> RelayStatus {
         self.inner.status()
     }
 
+    /// Watch the status changes
+    ///
+    /// The receiver holds the current status and is notified on every change,
+    /// so it can be used to wait for a status without polling and without missing transitions.
+    /// Intermediate statuses may be skipped if they change faster than they are observed.
+    #[inline]
+    pub fn status_watch(&self) -> watch::Receiver<RelayStatus> {
+        self.inner.status_watch()
+    }
+
     /// Get relay capabilities
     #[inline]
     pub fn capabilities(&self) -> &Arc<AtomicRelayCapabilities> {
         &self.inner.capabilities
     }
 
+    /// Get the NIP-11 relay information document, if set
+    #[inline]
+    pub async fn information_document(&self) -> Option<RelayInformationDocument> {
+        self.inner.information_document().await
+    }
+
+    /// Set the NIP-11 relay information document
+    ///
+    /// The SDK doesn't fetch the document: set it to let the relay apply the advertised limitations.
+    ///
+    /// If the `max_limit` is known, the `limit` of the filters sent to **this** relay is clamped to it,
+    /// so the client doesn't wait for events that the relay will never send.
+    /// The other relays receive the filters unchanged:
+    /// when querying multiple relays, the merged result still honors the caller `limit`.
+    #[inline]
+    pub async fn set_information_document(&self, document: RelayInformationDocument) {
+        self.inner.set_information_document(document).await
+    }
+
     /// Get subscriptions
     #[inline]
     pub async fn subscriptions(&self) -> HashMap<SubscriptionId, Vec<Filter>> {
@@ -194,12 +233,30 @@ impl Relay {
         &self.inner.opts
     }
 
+    /// Get the current reconnection delay
+    ///
+    /// This is the delay computed before the last reconnection attempt.
+    /// Returns [`Duration::ZERO`] if the relay never reconnected.
+    #[inline]
+    pub fn reconnect_delay(&self) -> Duration {
+        self.inner.reconnect_delay()
+    }
+
     /// Get [`RelayConnectionStats`]
     #[inline]
     pub fn stats(&self) -> &RelayConnectionStats {
         &self.inner.stats
     }
 
+    /// Last measured round-trip time
+    ///
+    /// Check [`RelayConnectionStats::rtt`] to learn more.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rtt(&self) -> Option<Duration> {
+        self.inner.stats.rtt()
+    }
+
     #[inline]
     pub(super) fn set_notification_sender(
         &mut self,
@@ -277,7 +334,9 @@ impl Relay {
     /// Wait for relay connection at most for the specified `timeout`.
     /// The code continues when the relay is connected or the `timeout` is reached.
     pub async fn wait_for_connection(&self, timeout: Duration) {
-        let status: RelayStatus = self.status();
+        // Watch the status before checking it, to not miss any transition
+        let mut watch = self.status_watch();
+        let status: RelayStatus = *watch.borrow_and_update();
 
         // Immediately returns if the relay is already connected, if it's terminated or banned.
         if status.is_connected()
@@ -288,27 +347,23 @@ impl Relay {
             return;
         }
 
-        // Subscribe to notifications
-        let mut notifications = self.inner.internal_notification_sender.subscribe();
-
         // Set timeout
         time::timeout(Some(timeout), async {
-            while let Ok(notification) = notifications.recv().await {
+            while watch.changed().await.is_ok() {
                 // Wait for status change. Break loop when connect.
-                if let RelayNotification::RelayStatus { status } = notification {
-                    match status {
-                        // Waiting for connection
-                        RelayStatus::Initialized
-                        | RelayStatus::Pending
-                        | RelayStatus::Connecting
-                        | RelayStatus::Disconnected => {}
-                        // Connected or terminated/banned/sleeping/shutdown
-                        RelayStatus::Connected
-                        | RelayStatus::Terminated
-                        | RelayStatus::Banned
-                        | RelayStatus::Sleeping
-                        | RelayStatus::Shutdown => break,
-                    }
+                let status: RelayStatus = *watch.borrow_and_update();
+                match status {
+                    // Waiting for connection
+                    RelayStatus::Initialized
+                    | RelayStatus::Pending
+                    | RelayStatus::Connecting
+                    | RelayStatus::Disconnected => {}
+                    // Connected or terminated/banned/sleeping/shutdown
+                    RelayStatus::Connected
+                    | RelayStatus::Terminated
+                    | RelayStatus::Banned
+                    | RelayStatus::Sleeping
+                    | RelayStatus::Shutdown => break,
                 }
             }
         })
@@ -357,6 +412,17 @@ impl Relay {
         self.inner.shutdown()
     }
 
+    /// Gracefully shutdown relay
+    ///
+    /// Stop accepting new messages, flush the queued ones and wait for the pending `OK`s, up to the `timeout`.
+    /// Then close the connection and set the status to [`RelayStatus::Shutdown`].
+    ///
+    /// Any message sent in the meanwhile is rejected with [`Error::ShuttingDown`].
+    #[inline]
+    pub async fn graceful_shutdown(&self, timeout: Duration) -> RelayShutdownSummary {
+        self.inner.graceful_shutdown(timeout).await
+    }
+
     /// Send a message to the relay
     #[inline]
     pub fn send_msg<'msg>(&self, msg: ClientMessage<'msg>) -> SendMessage<'_, 'msg> {
@@ -447,23 +513,51 @@ impl Relay {
         Ok(count)
     }
 
+    /// Answer a NIP-42 AUTH challenge
+    ///
+    /// Signs the AUTH event with the configured authenticator, sends it and waits for the `OK`.
+    /// On success, the subscriptions are re-sent and the events waiting for the authentication
+    /// (rejected with `auth-required`) are retried.
+    ///
+    /// Meant to be used with [`AuthPolicy::Manual`], after receiving a [`RelayNotification::AuthChallenge`].
+    #[inline]
+    pub async fn auth<S>(&self, challenge: S) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        self.inner.authenticate(challenge.into()).await
+    }
+
     /// Sync events with relays (negentropy reconciliation)
     #[inline]
+    #[cfg(feature = "negentropy")]
     pub fn sync(&self, filter: Filter) -> SyncEvents<'_> {
         SyncEvents::new(self, filter)
     }
+
+    /// Placeholder of [`Relay::sync`], that requires the `negentropy` feature
+    #[doc(hidden)]
+    #[cfg(not(feature = "negentropy"))]
+    pub fn sync<T>(&self, _filter: T) -> T::Disabled
+    where
+        T: RequiresNegentropyFeature,
+    {
+        unreachable!("the `negentropy` feature is disabled")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
+    use std::time::Instant;
 
     use async_utility::time;
     use nostr_relay_builder::prelude::*;
 
     use super::{Error, *};
     use crate::policy::{AdmitPolicy, AdmitStatus, PolicyError};
+    use crate::transport::headers::UpgradeHeaders;
 
     #[derive(Debug)]
     struct CustomTestPolicy {
@@ -622,6 +716,33 @@ mod tests {
         assert!(!relay.inner.is_running());
     }
 
+    #[tokio::test]
+    async fn test_reconnect_exponential_backoff() {
+        let url = RelayUrl::parse("wss://127.0.0.1:666").unwrap();
+
+        let opts = RelayOptions::default()
+            .reconnect_backoff(true)
+            .reconnect_initial_delay(Duration::from_millis(100))
+            .reconnect_max_delay(Duration::from_millis(500))
+            .reconnect_jitter(Duration::ZERO);
+        let relay: Relay = new_relay(url, opts);
+
+        assert_eq!(relay.reconnect_delay(), Duration::ZERO);
+
+        relay.connect();
+
+        time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(relay.reconnect_delay(), Duration::from_millis(100));
+
+        time::sleep(Duration::from_secs(2)).await;
+
+        // Capped to the max delay
+        assert_eq!(relay.reconnect_delay(), Duration::from_millis(500));
+
+        relay.disconnect();
+    }
+
     #[tokio::test]
     async fn test_connect() {
         // Mock relay
@@ -817,6 +938,73 @@ mod tests {
         assert!(matches!(res.unwrap_err(), Error::Shutdown));
     }
 
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        // Throttle the messages, to keep some in the queue
+        let opts = RelayOptions::default().max_messages_per_sec(5);
+        let relay: Relay = new_relay(url, opts);
+        relay.try_connect().await.unwrap();
+
+        let keys = Keys::generate();
+        for i in 0..8 {
+            let event = EventBuilder::text_note(format!("flush-{i}"))
+                .sign(&keys)
+                .unwrap();
+            relay.send_event(&event).wait_for_ok(false).await.unwrap();
+        }
+
+        let summary = relay.graceful_shutdown(Duration::from_secs(5)).await;
+        assert!(summary.is_clean(), "{summary:?}");
+        assert!(summary.sent > 0);
+        assert_eq!(relay.status(), RelayStatus::Shutdown);
+
+        // Nothing can be sent after the shutdown
+        let res = relay
+            .send_msg(ClientMessage::close(SubscriptionId::generate()))
+            .await;
+        assert!(matches!(res.unwrap_err(), Error::Shutdown));
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_timeout() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let opts = RelayOptions::default().max_messages_per_sec(1);
+        let relay: Relay = new_relay(url, opts);
+        relay.try_connect().await.unwrap();
+
+        let keys = Keys::generate();
+        let mut ids: HashSet<EventId> = HashSet::new();
+        for i in 0..5 {
+            let event = EventBuilder::text_note(format!("abandon-{i}"))
+                .sign(&keys)
+                .unwrap();
+            relay.send_event(&event).wait_for_ok(false).await.unwrap();
+            ids.insert(event.id);
+        }
+
+        let (summary, res) =
+            tokio::join!(relay.graceful_shutdown(Duration::from_millis(500)), async {
+                time::sleep(Duration::from_millis(100)).await;
+                relay
+                    .send_msg(ClientMessage::close(SubscriptionId::generate()))
+                    .await
+            });
+
+        // New messages are rejected while shutting down
+        assert!(matches!(res.unwrap_err(), Error::ShuttingDown));
+
+        assert!(!summary.is_clean());
+        assert!(summary.abandoned > 0);
+        assert!(summary.unacknowledged.len() >= summary.abandoned);
+        assert!(summary.unacknowledged.is_subset(&ids));
+        assert_eq!(relay.status(), RelayStatus::Shutdown);
+    }
+
     #[tokio::test]
     async fn test_shutdown_on_drop() {
         // Mock relay
@@ -939,6 +1127,185 @@ mod tests {
         assert!(!relay.inner.is_running());
     }
 
+    #[tokio::test]
+    async fn test_upgrade_headers() {
+        let (url, rx) = crate::transport::websocket::tests::capture_upgrade_headers().await;
+        let url = RelayUrl::parse(url.as_str()).unwrap();
+
+        let mut headers = HashMap::new();
+        headers.insert(url.clone(), UpgradeHeaders::new().bearer("token"));
+
+        let relay = Relay::builder(url).upgrade_headers(headers).build();
+
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        let captured = rx.await.unwrap();
+        assert!(captured.contains(&("authorization".to_string(), "Bearer token".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_rtt() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let opts = RelayOptions::default().ping_interval(Duration::from_millis(100));
+        let relay = Relay::builder(url).opts(opts).build();
+        assert!(relay.rtt().is_none());
+
+        let mut notifications = relay.notifications();
+        relay.try_connect().await.unwrap();
+
+        let rtt: Duration = tokio::time::timeout(Duration::from_secs(2), async {
+            while let Some(notification) = notifications.next().await {
+                if let RelayNotification::Rtt { rtt } = notification {
+                    return rtt;
+                }
+            }
+            panic!("notifications stream ended");
+        })
+        .await
+        .unwrap();
+
+        assert!(rtt < Duration::from_secs(2));
+        assert!(relay.rtt().is_some());
+        assert!(relay.stats().rtt_ewma().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stats_snapshot() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let relay: Relay = new_relay(url, RelayOptions::default());
+        relay.try_connect().await.unwrap();
+
+        // REQ sent, EOSE received
+        relay
+            .fetch_events(Filter::new().kind(Kind::TextNote))
+            .timeout(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let snapshot = relay.stats().snapshot();
+        assert_eq!(snapshot.attempts, 1);
+        assert_eq!(snapshot.success, 1);
+        assert_eq!(snapshot.reconnects, 0);
+        assert!(snapshot.messages_sent >= 1);
+        assert!(snapshot.messages_received >= 1);
+        assert!(snapshot.bytes_sent > 0);
+        assert!(snapshot.bytes_received > 0);
+
+        // Provided by the default transport
+        let metrics = relay.stats().connection_metrics().unwrap();
+        assert!(metrics.bytes_sent() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_connection_metrics_with_middleware() {
+        use crate::transport::middleware::LoggingMiddleware;
+
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let relay = Relay::builder(url)
+            .add_middleware(LoggingMiddleware)
+            .build();
+        relay.try_connect().await.unwrap();
+
+        relay
+            .fetch_events(Filter::new().kind(Kind::TextNote))
+            .timeout(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        // Still provided by the wrapped transport
+        let metrics = relay.stats().connection_metrics().unwrap();
+        assert!(metrics.bytes_sent() > 0);
+        assert!(metrics.bytes_received() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_max_messages_per_sec() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let opts = RelayOptions::default().max_messages_per_sec(2);
+        let relay = Relay::builder(url).opts(opts).build();
+        relay.try_connect().await.unwrap();
+
+        // The burst is sent immediately, then 2 messages per second
+        let start = Instant::now();
+        for _ in 0..4 {
+            relay
+                .send_msg(ClientMessage::close(SubscriptionId::generate()))
+                .wait_until_sent(Duration::from_secs(5))
+                .await
+                .unwrap();
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+
+        // Queue some messages
+        for _ in 0..4 {
+            relay
+                .send_msg(ClientMessage::close(SubscriptionId::generate()))
+                .await
+                .unwrap();
+        }
+
+        // The AUTH skips the queue
+        let event = EventBuilder::auth("challenge", relay.url().clone())
+            .sign(&Keys::generate())
+            .unwrap();
+        let start = Instant::now();
+        relay
+            .send_msg(ClientMessage::auth(event))
+            .wait_until_sent(Duration::from_secs(5))
+            .await
+            .unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed < Duration::from_millis(1500), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_send_rate_limit_doesnt_delay_auth() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let opts = RelayOptions::default().max_messages_per_sec(1);
+        let relay = Relay::builder(url).opts(opts).build();
+        relay.try_connect().await.unwrap();
+
+        // Consume the burst, so the next queued message has to wait for a whole second
+        relay
+            .send_msg(ClientMessage::close(SubscriptionId::generate()))
+            .wait_until_sent(Duration::from_secs(5))
+            .await
+            .unwrap();
+        relay
+            .send_msg(ClientMessage::close(SubscriptionId::generate()))
+            .await
+            .unwrap();
+        time::sleep(Duration::from_millis(100)).await;
+
+        // The AUTH is sent while the queued message is still waiting
+        let event = EventBuilder::auth("challenge", relay.url().clone())
+            .sign(&Keys::generate())
+            .unwrap();
+        let start = Instant::now();
+        relay
+            .send_msg(ClientMessage::auth(event))
+            .wait_until_sent(Duration::from_secs(5))
+            .await
+            .unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed < Duration::from_millis(500), "{elapsed:?}");
+    }
+
     #[tokio::test]
     async fn test_sleep_when_idle() {
         // Mock relay
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use nostr::{Event, RelayMessage, SubscriptionId};
 
 use super::status::RelayStatus;
@@ -28,4 +30,17 @@ pub enum RelayNotification {
     Authenticated,
     /// Authentication failed
     AuthenticationFailed,
+    /// Received a NIP-42 AUTH challenge that must be answered manually
+    ///
+    /// Sent only with [`AuthPolicy::Manual`](crate::relay::AuthPolicy::Manual).
+    /// Answer it with [`Relay::auth`](crate::relay::Relay::auth).
+    AuthChallenge {
+        /// Challenge
+        challenge: String,
+    },
+    /// Measured the round-trip time of a ping
+    Rtt {
+        /// Round-trip time
+        rtt: Duration,
+    },
 }
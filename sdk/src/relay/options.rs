@@ -1,11 +1,36 @@
 use std::time::Duration;
 
+#[cfg(feature = "negentropy")]
 use tokio::sync::watch::{self, Receiver, Sender};
 
-use super::constants::{DEFAULT_NOTIFICATION_CHANNEL_SIZE, DEFAULT_RETRY_INTERVAL};
+use super::constants::{
+    DEFAULT_NOTIFICATION_CHANNEL_SIZE, DEFAULT_OVERSIZED_MESSAGE_PENALTY, DEFAULT_PING_INTERVAL,
+    DEFAULT_PONG_TIMEOUT, DEFAULT_RECONNECT_INITIAL_DELAY, DEFAULT_RECONNECT_JITTER,
+    DEFAULT_RECONNECT_RESET_THRESHOLD, DEFAULT_RETRY_INTERVAL, MAX_RETRY_INTERVAL,
+};
 use super::limits::RelayLimits;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::proxy::Proxy;
+use crate::transport::websocket::WebSocketLimits;
+
+/// How to answer the NIP-42 AUTH challenges
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/42.md>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AuthPolicy {
+    /// Sign and send the AUTH event as soon as the challenge is received (default)
+    ///
+    /// Requires an authenticator to be configured.
+    #[default]
+    Automatic,
+    /// Don't answer the challenges automatically
+    ///
+    /// An [`AuthChallenge`](crate::relay::RelayNotification::AuthChallenge) notification is sent instead
+    /// and the app decides when to call [`Relay::auth`](crate::relay::Relay::auth).
+    Manual,
+    /// Never authenticate: the challenges are ignored
+    Never,
+}
 
 /// Relay options
 #[derive(Debug, Clone)]
@@ -13,17 +38,33 @@ pub struct RelayOptions {
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) proxy: Option<Proxy>,
     pub(crate) ping: bool,
+    pub(crate) ping_interval: Duration,
+    pub(crate) pong_timeout: Duration,
+    pub(crate) max_missed_pongs: u32,
     pub(crate) reconnect: bool,
     pub(crate) sleep_when_idle: bool,
     pub(crate) connect_timeout: Duration,
     pub(crate) idle_timeout: Duration,
     pub(crate) retry_interval: Duration,
     pub(crate) adjust_retry_interval: bool,
+    pub(crate) reconnect_backoff: bool,
+    pub(crate) reconnect_initial_delay: Duration,
+    pub(crate) reconnect_max_delay: Duration,
+    pub(crate) reconnect_jitter: Duration,
+    pub(crate) reconnect_reset_threshold: Duration,
     pub(crate) verify_subscriptions: bool,
     pub(crate) ban_relay_on_mismatch: bool,
+    pub(crate) verify_ids: bool,
+    pub(crate) verify_in_blocking_pool: bool,
     pub(crate) limits: RelayLimits,
+    pub(crate) websocket_limits: Option<WebSocketLimits>,
+    pub(crate) oversized_message_penalty: Duration,
     pub(crate) max_avg_latency: Option<Duration>,
     pub(crate) notification_channel_size: usize,
+    pub(crate) auth_relay_clock: bool,
+    pub(crate) auth_policy: AuthPolicy,
+    pub(crate) max_messages_per_sec: Option<u32>,
+    pub(crate) max_bytes_per_sec: Option<u64>,
 }
 
 impl Default for RelayOptions {
@@ -32,17 +73,33 @@ impl Default for RelayOptions {
             #[cfg(not(target_arch = "wasm32"))]
             proxy: None,
             ping: true,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            pong_timeout: DEFAULT_PONG_TIMEOUT,
+            max_missed_pongs: 1,
             reconnect: true,
             sleep_when_idle: false,
             connect_timeout: Duration::from_secs(15),
             idle_timeout: Duration::from_secs(300),
             retry_interval: DEFAULT_RETRY_INTERVAL,
             adjust_retry_interval: true,
+            reconnect_backoff: false,
+            reconnect_initial_delay: DEFAULT_RECONNECT_INITIAL_DELAY,
+            reconnect_max_delay: MAX_RETRY_INTERVAL,
+            reconnect_jitter: DEFAULT_RECONNECT_JITTER,
+            reconnect_reset_threshold: DEFAULT_RECONNECT_RESET_THRESHOLD,
             verify_subscriptions: false,
             ban_relay_on_mismatch: false,
+            verify_ids: false,
+            verify_in_blocking_pool: cfg!(not(target_arch = "wasm32")),
             limits: RelayLimits::default(),
+            websocket_limits: None,
+            oversized_message_penalty: DEFAULT_OVERSIZED_MESSAGE_PENALTY,
             max_avg_latency: None,
             notification_channel_size: DEFAULT_NOTIFICATION_CHANNEL_SIZE,
+            auth_relay_clock: false,
+            auth_policy: AuthPolicy::Automatic,
+            max_messages_per_sec: None,
+            max_bytes_per_sec: None,
         }
     }
 }
@@ -77,6 +134,35 @@ impl RelayOptions {
         self
     }
 
+    /// Interval between the keepalive pings (default: 55 sec)
+    ///
+    /// Ignored if the ping is disabled or not supported by the transport.
+    /// Values lower than 100 ms are raised to 100 ms.
+    #[inline]
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Max time to wait for the pong, after sending a ping (default: 20 sec)
+    ///
+    /// If the relay doesn't reply in time to [`RelayOptions::max_missed_pongs`] consecutive pings,
+    /// the connection is considered dead and is closed.
+    #[inline]
+    pub fn pong_timeout(mut self, timeout: Duration) -> Self {
+        self.pong_timeout = timeout;
+        self
+    }
+
+    /// Consecutive unanswered pings after which the connection is closed (default: 1)
+    ///
+    /// Values lower than 1 are raised to 1.
+    #[inline]
+    pub fn max_missed_pongs(mut self, max: u32) -> Self {
+        self.max_missed_pongs = max.max(1);
+        self
+    }
+
     /// Enable/disable auto reconnection (default: true)
     pub fn reconnect(mut self, reconnect: bool) -> Self {
         self.reconnect = reconnect;
@@ -95,6 +181,55 @@ impl RelayOptions {
         self
     }
 
+    /// Use exponential backoff between reconnection attempts (default: false)
+    ///
+    /// The delay starts from [`RelayOptions::reconnect_initial_delay`] and doubles at every failed attempt,
+    /// up to [`RelayOptions::reconnect_max_delay`].
+    /// A random jitter (see [`RelayOptions::reconnect_jitter`]) is added to every delay.
+    ///
+    /// When enabled, [`RelayOptions::retry_interval`] and [`RelayOptions::adjust_retry_interval`] are ignored.
+    #[inline]
+    pub fn reconnect_backoff(mut self, enable: bool) -> Self {
+        self.reconnect_backoff = enable;
+        self
+    }
+
+    /// Initial reconnection delay of the exponential backoff (default: 1 sec)
+    #[inline]
+    pub fn reconnect_initial_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_initial_delay = delay;
+        self
+    }
+
+    /// Max reconnection delay of the exponential backoff (default: 60 sec)
+    #[inline]
+    pub fn reconnect_max_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_max_delay = delay;
+        self
+    }
+
+    /// Max random jitter added to the reconnection delay (default: 3 sec)
+    ///
+    /// Avoids that many relays reconnect simultaneously after a network failure.
+    /// It's added to every delay: the fixed [`RelayOptions::retry_interval`], the adjusted one
+    /// (see [`RelayOptions::adjust_retry_interval`]) and the exponential backoff
+    /// (see [`RelayOptions::reconnect_backoff`]).
+    #[inline]
+    pub fn reconnect_jitter(mut self, jitter: Duration) -> Self {
+        self.reconnect_jitter = jitter;
+        self
+    }
+
+    /// Connection uptime after which the backoff is reset to the initial delay (default: 60 sec)
+    ///
+    /// A connection that drops before this threshold counts as a failed attempt,
+    /// so a relay that accepts and immediately closes the connection is not hammered.
+    #[inline]
+    pub fn reconnect_reset_threshold(mut self, threshold: Duration) -> Self {
+        self.reconnect_reset_threshold = threshold;
+        self
+    }
+
     /// Verify that received events belong to a subscription and match the filter.
     pub fn verify_subscriptions(mut self, enable: bool) -> Self {
         self.verify_subscriptions = enable;
@@ -107,12 +242,52 @@ impl RelayOptions {
         self
     }
 
+    /// Always recompute the ID of the received events (default: false)
+    ///
+    /// By default, the ID of an event already stored in the database is trusted.
+    /// When enabled, the ID is recomputed from the content of every received event
+    /// and the events with a mismatching ID are rejected.
+    pub fn verify_ids(mut self, enable: bool) -> Self {
+        self.verify_ids = enable;
+        self
+    }
+
+    /// Verify the signature of the received events in the blocking thread pool (default: true, false on WASM)
+    ///
+    /// Keeps the heavy signature verification off the async worker threads, so they aren't stalled by a flood of events.
+    /// Ignored on WASM, where there is no blocking thread pool.
+    #[inline]
+    pub fn verify_in_blocking_pool(mut self, enable: bool) -> Self {
+        self.verify_in_blocking_pool = enable;
+        self
+    }
+
     /// Set custom limits
     pub fn limits(mut self, limits: RelayLimits) -> Self {
         self.limits = limits;
         self
     }
 
+    /// WebSocket message and frame size limits (default: the limits of the transport)
+    ///
+    /// Overrides the limits of the transport for this relay.
+    /// Check [`DefaultWebsocketTransport::limits`](crate::transport::websocket::DefaultWebsocketTransport::limits) to learn more.
+    #[inline]
+    pub fn websocket_limits(mut self, limits: WebSocketLimits) -> Self {
+        self.websocket_limits = Some(limits);
+        self
+    }
+
+    /// Min reconnection delay after the relay sent a message exceeding the size limits (default: 5 min)
+    ///
+    /// A relay sending oversized messages is misbehaving, not facing a network issue:
+    /// the connection counts as a failed attempt and the reconnection is delayed by at least this penalty.
+    #[inline]
+    pub fn oversized_message_penalty(mut self, penalty: Duration) -> Self {
+        self.oversized_message_penalty = penalty;
+        self
+    }
+
     /// Set max latency (default: None)
     ///
     /// Relay with an avg. latency greater that this value will be skipped.
@@ -129,6 +304,47 @@ impl RelayOptions {
         self
     }
 
+    /// Use the relay clock for the `created_at` of the NIP-42 AUTH events (default: false)
+    ///
+    /// Relays may reject the AUTH events created by clients with a skewed clock.
+    /// When enabled, the offset of the relay clock is estimated from the events received in real time
+    /// (see [`RelayConnectionStats::clock_offset`](crate::relay::RelayConnectionStats::clock_offset))
+    /// and, if the local clock is found skewed, the AUTH event is dated with the relay time.
+    #[inline]
+    pub fn auth_relay_clock(mut self, enable: bool) -> Self {
+        self.auth_relay_clock = enable;
+        self
+    }
+
+    /// Set the NIP-42 AUTH policy (default: [`AuthPolicy::Automatic`])
+    ///
+    /// Events and subscriptions rejected with `auth-required` are retried after a successful authentication,
+    /// unless the policy is [`AuthPolicy::Never`].
+    #[inline]
+    pub fn auth_policy(mut self, policy: AuthPolicy) -> Self {
+        self.auth_policy = policy;
+        self
+    }
+
+    /// Max number of messages sent per second (default: None)
+    ///
+    /// When the limit is reached, the messages are queued and sent as soon as allowed, instead of failing.
+    /// The `AUTH` messages skip the queue and are never delayed, but count toward the limit.
+    #[inline]
+    pub fn max_messages_per_sec(mut self, max: u32) -> Self {
+        self.max_messages_per_sec = Some(max);
+        self
+    }
+
+    /// Max number of bytes sent per second (default: None)
+    ///
+    /// Check [`RelayOptions::max_messages_per_sec`] to learn more.
+    #[inline]
+    pub fn max_bytes_per_sec(mut self, max: u64) -> Self {
+        self.max_bytes_per_sec = Some(max);
+        self
+    }
+
     /// Sleep when idle (default: false)
     #[inline]
     pub fn sleep_when_idle(mut self, enable: bool) -> Self {
@@ -186,6 +402,7 @@ pub enum ReqExitPolicy {
     WaitDurationAfterEOSE(Duration),
 }
 
+#[cfg(feature = "negentropy")]
 /// Negentropy Sync direction
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum SyncDirection {
@@ -198,6 +415,7 @@ pub enum SyncDirection {
     Both,
 }
 
+#[cfg(feature = "negentropy")]
 /// Sync (negentropy reconciliation) progress
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SyncProgress {
@@ -207,6 +425,7 @@ pub struct SyncProgress {
     pub current: u64,
 }
 
+#[cfg(feature = "negentropy")]
 impl SyncProgress {
     /// Construct new sync progress channel
     #[inline]
@@ -225,6 +444,7 @@ impl SyncProgress {
     }
 }
 
+#[cfg(feature = "negentropy")]
 /// Sync (negentropy reconciliation) options
 #[derive(Debug, Clone)]
 pub struct SyncOptions {
@@ -235,6 +455,7 @@ pub struct SyncOptions {
     pub(super) progress: Option<Sender<SyncProgress>>,
 }
 
+#[cfg(feature = "negentropy")]
 impl Default for SyncOptions {
     fn default() -> Self {
         Self {
@@ -247,6 +468,7 @@ impl Default for SyncOptions {
     }
 }
 
+#[cfg(feature = "negentropy")]
 impl SyncOptions {
     /// New default [`SyncOptions`]
     #[inline]
@@ -326,11 +548,17 @@ mod tests {
         let duration = Some(Duration::from_millis(500));
         let opts = SubscribeAutoCloseOptions::default().idle_timeout(duration);
         assert_eq!(opts.idle_timeout, duration);
+    }
+
+    #[test]
+    #[cfg(feature = "negentropy")]
+    fn test_sync_initial_timeout() {
         let opt = SyncOptions::default().initial_timeout(Duration::from_secs(5));
         assert_eq!(opt.initial_timeout, Duration::from_secs(5));
     }
 
     #[test]
+    #[cfg(feature = "negentropy")]
     fn test_sync_progress_percentage() {
         let sp = SyncProgress {
             total: 5,
@@ -342,6 +570,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "negentropy")]
     fn test_do_up() {
         let opt = SyncOptions::default();
         assert!(!opt.do_up());
@@ -352,6 +581,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "negentropy")]
     fn test_do_down() {
         let opt = SyncOptions::default();
         assert!(opt.do_down());
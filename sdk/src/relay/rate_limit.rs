@@ -0,0 +1,257 @@
+//! Relay rate limits
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use nostr::Timestamp;
+
+use super::constants::{RATE_LIMIT_BACKOFF_BASE, RATE_LIMIT_MAX_BACKOFF, RATE_LIMIT_MAX_HINT};
+
+/// Keywords that precede a retry-after hint
+///
+/// Matched case-insensitively, i.e.: `rate-limited: retry after 30s`, `retry-after: 30`,
+/// `slow down, try again in 2 minutes`, `please wait 500ms`.
+const RETRY_AFTER_KEYWORDS: [&str; 6] = [
+    "retry after",
+    "retry-after",
+    "retry in",
+    "try again in",
+    "try again after",
+    "wait",
+];
+
+/// Class of the operations affected by a rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RateLimitClass {
+    /// Event publishing (`EVENT`)
+    Publish,
+    /// Subscriptions (`REQ`)
+    Req,
+}
+
+/// Parse the retry-after hint of a relay message
+///
+/// A number without unit is considered in seconds.
+pub(super) fn parse_retry_after(message: &str) -> Option<Duration> {
+    let message: String = message.to_lowercase();
+
+    for keyword in RETRY_AFTER_KEYWORDS.iter() {
+        let mut rest: &str = &message;
+
+        while let Some(pos) = rest.find(keyword) {
+            rest = &rest[pos + keyword.len()..];
+
+            if let Some(duration) = parse_duration(rest) {
+                return Some(duration);
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s: &str = s.trim_start_matches(|c: char| c == ':' || c == '=' || c.is_whitespace());
+
+    // Number
+    let end: usize = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let value: f64 = s[..end].parse().ok()?;
+
+    // Unit
+    let unit: &str = s[end..].trim_start();
+    let unit_end: usize = unit
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(unit.len());
+
+    let secs: f64 = match &unit[..unit_end] {
+        "ms" | "msec" | "millis" | "millisecond" | "milliseconds" => value / 1000.0,
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => value,
+        "m" | "min" | "mins" | "minute" | "minutes" => value * 60.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => value * 3600.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+#[derive(Debug, Default)]
+struct RateLimitSlot {
+    /// UNIX timestamp until the operations are limited (0 if not limited)
+    until: AtomicU64,
+    /// Consecutive rate limits without a parsable hint
+    strikes: AtomicU32,
+}
+
+impl RateLimitSlot {
+    fn limited_until(&self, now: Timestamp) -> Option<Timestamp> {
+        let until: Timestamp = Timestamp::from_secs(self.until.load(Ordering::SeqCst));
+        (until > now).then_some(until)
+    }
+
+    fn mark(&self, hint: Option<Duration>, now: Timestamp) -> Timestamp {
+        let delay: Duration = match hint {
+            // Don't let a relay pin the state forever
+            Some(hint) => {
+                self.strikes.store(0, Ordering::SeqCst);
+                hint.min(RATE_LIMIT_MAX_HINT)
+            }
+            // No hint: fall back to an exponential backoff
+            None => {
+                let strikes: u32 = self.strikes.fetch_add(1, Ordering::SeqCst);
+                RATE_LIMIT_BACKOFF_BASE
+                    .saturating_mul(2u32.saturating_pow(strikes))
+                    .min(RATE_LIMIT_MAX_BACKOFF)
+            }
+        };
+
+        // Round up to the next second
+        let secs: u64 = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+        let until: Timestamp = now + secs;
+
+        self.until.store(until.as_secs(), Ordering::SeqCst);
+
+        until
+    }
+
+    fn clear(&self) {
+        self.until.store(0, Ordering::SeqCst);
+        self.strikes.store(0, Ordering::SeqCst);
+    }
+}
+
+/// Rate-limit state of a relay
+#[derive(Debug, Default)]
+pub(super) struct RateLimitState {
+    publish: RateLimitSlot,
+    req: RateLimitSlot,
+}
+
+impl RateLimitState {
+    #[inline]
+    fn slot(&self, class: RateLimitClass) -> &RateLimitSlot {
+        match class {
+            RateLimitClass::Publish => &self.publish,
+            RateLimitClass::Req => &self.req,
+        }
+    }
+
+    #[inline]
+    pub(super) fn limited_until(&self, class: RateLimitClass, now: Timestamp) -> Option<Timestamp> {
+        self.slot(class).limited_until(now)
+    }
+
+    #[inline]
+    pub(super) fn mark(
+        &self,
+        class: RateLimitClass,
+        hint: Option<Duration>,
+        now: Timestamp,
+    ) -> Timestamp {
+        self.slot(class).mark(hint, now)
+    }
+
+    #[inline]
+    pub(super) fn clear(&self, class: RateLimitClass) {
+        self.slot(class).clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let cases: [(&str, Option<Duration>); 10] = [
+            (
+                "rate-limited: retry after 30s",
+                Some(Duration::from_secs(30)),
+            ),
+            (
+                "rate-limited: retry-after: 30",
+                Some(Duration::from_secs(30)),
+            ),
+            (
+                "rate-limited: Retry After 2 minutes",
+                Some(Duration::from_secs(120)),
+            ),
+            (
+                "rate-limited: slow down, try again in 1.5 sec",
+                Some(Duration::from_millis(1500)),
+            ),
+            (
+                "rate-limited: please wait 500ms",
+                Some(Duration::from_millis(500)),
+            ),
+            (
+                "rate-limited: retry-after=1h",
+                Some(Duration::from_secs(3600)),
+            ),
+            (
+                "rate-limited: wait a moment, retry in 10 seconds",
+                Some(Duration::from_secs(10)),
+            ),
+            ("rate-limited: you are noting too much", None),
+            ("rate-limited: retry later", None),
+            ("rate-limited: wait 5 parsecs", None),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(parse_retry_after(message), expected, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_state() {
+        let state = RateLimitState::default();
+        let now = Timestamp::from_secs(1_700_000_000);
+
+        assert!(state.limited_until(RateLimitClass::Publish, now).is_none());
+
+        // With hint
+        let until = state.mark(
+            RateLimitClass::Publish,
+            Some(Duration::from_millis(1500)),
+            now,
+        );
+        assert_eq!(until, now + 2);
+        assert_eq!(
+            state.limited_until(RateLimitClass::Publish, now),
+            Some(until)
+        );
+        assert!(state.limited_until(RateLimitClass::Req, now).is_none());
+
+        // Expired
+        assert!(
+            state
+                .limited_until(RateLimitClass::Publish, until)
+                .is_none()
+        );
+
+        // The hint is capped
+        let until = state.mark(RateLimitClass::Publish, Some(Duration::MAX), now);
+        assert_eq!(until, now + RATE_LIMIT_MAX_HINT.as_secs());
+
+        // Without hint: exponential backoff
+        let base: u64 = RATE_LIMIT_BACKOFF_BASE.as_secs();
+        assert_eq!(state.mark(RateLimitClass::Req, None, now), now + base);
+        assert_eq!(state.mark(RateLimitClass::Req, None, now), now + base * 2);
+        assert_eq!(state.mark(RateLimitClass::Req, None, now), now + base * 4);
+
+        // Capped
+        for _ in 0..32 {
+            state.mark(RateLimitClass::Req, None, now);
+        }
+        assert_eq!(
+            state.limited_until(RateLimitClass::Req, now),
+            Some(now + RATE_LIMIT_MAX_BACKOFF.as_secs())
+        );
+
+        // Cleared
+        state.clear(RateLimitClass::Req);
+        assert!(state.limited_until(RateLimitClass::Req, now).is_none());
+        assert_eq!(state.mark(RateLimitClass::Req, None, now), now + base);
+    }
+}
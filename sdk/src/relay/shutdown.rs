@@ -0,0 +1,24 @@
+//! Relay graceful shutdown
+
+use std::collections::HashSet;
+
+use nostr::EventId;
+
+/// Relay graceful shutdown summary
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayShutdownSummary {
+    /// Messages flushed to the relay while shutting down
+    pub sent: usize,
+    /// Queued messages that couldn't be sent before the timeout
+    pub abandoned: usize,
+    /// Events not acknowledged by the relay (abandoned or without an `OK` before the timeout)
+    pub unacknowledged: HashSet<EventId>,
+}
+
+impl RelayShutdownSummary {
+    /// Check if all the messages have been sent and all the events acknowledged
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.abandoned == 0 && self.unacknowledged.is_empty()
+    }
+}
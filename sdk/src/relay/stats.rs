@@ -1,12 +1,24 @@
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::collections::VecDeque;
 #[cfg(not(target_arch = "wasm32"))]
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use async_wsocket::Message;
+use futures::{SinkExt, StreamExt, future};
 use nostr::Timestamp;
 
+use super::constants::{
+    CLOCK_OFFSET_AGREEMENT, CLOCK_OFFSET_MAX, CLOCK_OFFSET_MIN_SAMPLES, CLOCK_OFFSET_WINDOW,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use super::constants::{LATENCY_MIN_READS, RTT_SMOOTHING};
+use super::rate_limit::{RateLimitClass, RateLimitState};
+use crate::transport::error::TransportError;
 #[cfg(not(target_arch = "wasm32"))]
-use super::constants::LATENCY_MIN_READS;
+use crate::transport::metrics::ConnectionMetrics;
+use crate::transport::websocket::{WebSocketSink, WebSocketStream};
 
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug, Default)]
@@ -15,6 +27,16 @@ struct AverageLatency {
     total: AtomicU64,
     /// Count of latencies
     count: AtomicU64,
+    /// Last round-trip time, in microseconds
+    last_rtt: AtomicU64,
+    /// Exponentially-weighted moving average of the round-trip time, in microseconds
+    ewma_rtt: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct ClockOffset {
+    /// Offsets of the relay clock from the most recent samples, in seconds
+    samples: Mutex<VecDeque<i64>>,
 }
 
 #[derive(Debug, Default)]
@@ -23,6 +45,10 @@ struct InnerRelayConnectionStats {
     success: AtomicUsize,
     bytes_sent: AtomicUsize,
     bytes_received: AtomicUsize,
+    messages_sent: AtomicUsize,
+    messages_received: AtomicUsize,
+    id_mismatches: AtomicUsize,
+    oversized_messages: AtomicUsize,
     // TODO: keep track of msg/event sending attempts and success?
     connected_at: AtomicU64,
     first_connection_at: AtomicU64,
@@ -32,6 +58,30 @@ struct InnerRelayConnectionStats {
     woke_up_at: AtomicU64,
     #[cfg(not(target_arch = "wasm32"))]
     latency: AverageLatency,
+    /// Metrics of the last connection, if provided by the transport
+    #[cfg(not(target_arch = "wasm32"))]
+    connection_metrics: RwLock<Option<Arc<ConnectionMetrics>>>,
+    clock: ClockOffset,
+    rate_limits: RateLimitState,
+}
+
+/// Snapshot of the relay connection counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayConnectionStatsSnapshot {
+    /// Connection attempts
+    pub attempts: usize,
+    /// Successful connections
+    pub success: usize,
+    /// Successful reconnections
+    pub reconnects: usize,
+    /// Messages sent
+    pub messages_sent: usize,
+    /// Messages received
+    pub messages_received: usize,
+    /// Bytes sent
+    pub bytes_sent: usize,
+    /// Bytes received
+    pub bytes_received: usize,
 }
 
 /// Relay connection stats
@@ -63,6 +113,24 @@ impl RelayConnectionStats {
         }
     }
 
+    /// The number of times the connection has been successfully re-established
+    #[inline]
+    pub fn reconnects(&self) -> usize {
+        self.success().saturating_sub(1)
+    }
+
+    /// Messages sent (text and binary frames)
+    #[inline]
+    pub fn messages_sent(&self) -> usize {
+        self.inner.messages_sent.load(Ordering::SeqCst)
+    }
+
+    /// Messages received (text and binary frames)
+    #[inline]
+    pub fn messages_received(&self) -> usize {
+        self.inner.messages_received.load(Ordering::SeqCst)
+    }
+
     /// Bytes sent
     #[inline]
     pub fn bytes_sent(&self) -> usize {
@@ -75,6 +143,47 @@ impl RelayConnectionStats {
         self.inner.bytes_received.load(Ordering::SeqCst)
     }
 
+    /// Metrics of the last connection (handshake durations and bytes)
+    ///
+    /// Available only if the transport provides them (see [`WebSocketTransport::connect_with_metrics`]).
+    ///
+    /// [`WebSocketTransport::connect_with_metrics`]: crate::transport::websocket::WebSocketTransport::connect_with_metrics
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connection_metrics(&self) -> Option<Arc<ConnectionMetrics>> {
+        self.inner
+            .connection_metrics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Take a snapshot of the counters
+    pub fn snapshot(&self) -> RelayConnectionStatsSnapshot {
+        RelayConnectionStatsSnapshot {
+            attempts: self.attempts(),
+            success: self.success(),
+            reconnects: self.reconnects(),
+            messages_sent: self.messages_sent(),
+            messages_received: self.messages_received(),
+            bytes_sent: self.bytes_sent(),
+            bytes_received: self.bytes_received(),
+        }
+    }
+
+    /// Number of received events rejected due to a mismatching ID
+    #[inline]
+    pub fn id_mismatches(&self) -> usize {
+        self.inner.id_mismatches.load(Ordering::SeqCst)
+    }
+
+    /// Number of connections closed because the relay sent a message exceeding the size limits
+    ///
+    /// Every such connection delays the reconnection (see [`RelayOptions::oversized_message_penalty`](crate::relay::RelayOptions::oversized_message_penalty)).
+    #[inline]
+    pub fn oversized_messages(&self) -> usize {
+        self.inner.oversized_messages.load(Ordering::SeqCst)
+    }
+
     /// Get UNIX timestamp of the last connection
     #[inline]
     pub fn connected_at(&self) -> Timestamp {
@@ -128,6 +237,141 @@ impl RelayConnectionStats {
         total.checked_div(count).map(Duration::from_millis)
     }
 
+    /// Last measured round-trip time
+    ///
+    /// Measured from the ping/pong exchanges.
+    /// Returns `None` if no pong has been received yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rtt(&self) -> Option<Duration> {
+        if self.inner.latency.count.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        Some(Duration::from_micros(
+            self.inner.latency.last_rtt.load(Ordering::SeqCst),
+        ))
+    }
+
+    /// Exponentially-weighted moving average of the round-trip time
+    ///
+    /// Unlike the [`RelayConnectionStats::latency`], the recent measurements weigh more than the old ones.
+    /// Returns `None` if no pong has been received yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rtt_ewma(&self) -> Option<Duration> {
+        if self.inner.latency.count.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        Some(Duration::from_micros(
+            self.inner.latency.ewma_rtt.load(Ordering::SeqCst),
+        ))
+    }
+
+    /// Estimated offset of the relay clock from the local one, in seconds
+    ///
+    /// A positive value means that the relay clock is ahead of the local one.
+    ///
+    /// The offset is the median of the most recent samples,
+    /// so a few events with a forged `created_at` can't move it.
+    ///
+    /// Returns `None` if not enough samples agree with the median yet.
+    pub fn clock_offset(&self) -> Option<i64> {
+        let mut samples: Vec<i64> = self
+            .inner
+            .clock
+            .samples
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .copied()
+            .collect();
+
+        // Check number of samples
+        if samples.len() < CLOCK_OFFSET_MIN_SAMPLES {
+            return None;
+        }
+
+        samples.sort_unstable();
+        let median: i64 = samples[samples.len() / 2];
+
+        // Check that the samples agree
+        let agreeing: usize = samples
+            .iter()
+            .filter(|sample| sample.abs_diff(median) <= CLOCK_OFFSET_AGREEMENT)
+            .count();
+
+        if agreeing < CLOCK_OFFSET_MIN_SAMPLES {
+            return None;
+        }
+
+        Some(median)
+    }
+
+    /// Record the current time of the relay
+    ///
+    /// Used to estimate the [`RelayConnectionStats::clock_offset`].
+    /// The samples are taken automatically from the events received in real time,
+    /// but a more reliable time hint (i.e., from the HTTP `Date` header of the NIP-11 document) can be recorded here.
+    ///
+    /// Implausible samples, more than a day away from the local clock, are discarded.
+    pub fn record_relay_time(&self, relay_time: Timestamp) {
+        let now: i64 = Timestamp::now().as_secs() as i64;
+        let sample: i64 = (relay_time.as_secs() as i64).saturating_sub(now);
+
+        if sample.unsigned_abs() > CLOCK_OFFSET_MAX {
+            return;
+        }
+
+        let mut samples = self
+            .inner
+            .clock
+            .samples
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Keep only the most recent samples
+        if samples.len() >= CLOCK_OFFSET_WINDOW {
+            samples.pop_front();
+        }
+
+        samples.push_back(sample);
+    }
+
+    /// Get the UNIX timestamp until the relay rate-limits the operations of the class
+    ///
+    /// Returns `None` if the operations aren't rate-limited.
+    #[inline]
+    pub fn rate_limited_until(&self, class: RateLimitClass) -> Option<Timestamp> {
+        self.inner
+            .rate_limits
+            .limited_until(class, Timestamp::now())
+    }
+
+    /// Mark the operations of the class as rate-limited, for the hinted duration or,
+    /// if the relay didn't provide a hint, with an exponential backoff
+    #[inline]
+    pub(super) fn mark_rate_limited(
+        &self,
+        class: RateLimitClass,
+        hint: Option<Duration>,
+    ) -> Timestamp {
+        self.inner.rate_limits.mark(class, hint, Timestamp::now())
+    }
+
+    #[inline]
+    pub(super) fn clear_rate_limit(&self, class: RateLimitClass) {
+        self.inner.rate_limits.clear(class)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn set_connection_metrics(&self, metrics: Option<Arc<ConnectionMetrics>>) {
+        *self
+            .inner
+            .connection_metrics
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = metrics;
+    }
+
     #[inline]
     pub(super) fn new_attempt(&self) {
         self.inner.attempts.fetch_add(1, Ordering::SeqCst);
@@ -159,19 +403,110 @@ impl RelayConnectionStats {
         }
     }
 
+    #[inline]
+    pub(super) fn new_message_sent(&self, size: usize) {
+        self.inner.messages_sent.fetch_add(1, Ordering::SeqCst);
+        self.add_bytes_sent(size);
+    }
+
+    #[inline]
+    pub(super) fn new_message_received(&self, size: usize) {
+        self.inner.messages_received.fetch_add(1, Ordering::SeqCst);
+        self.add_bytes_received(size);
+    }
+
+    #[inline]
+    pub(super) fn new_id_mismatch(&self) {
+        self.inner.id_mismatches.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub(super) fn new_oversized_message(&self) {
+        self.inner.oversized_messages.fetch_add(1, Ordering::SeqCst);
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub(super) fn save_latency(&self, latency: Duration) {
         let ms: u128 = latency.as_millis();
         if ms <= u64::MAX as u128 {
+            let us: u64 = latency.as_micros().try_into().unwrap_or(u64::MAX);
+
+            self.inner.latency.last_rtt.store(us, Ordering::SeqCst);
             self.inner
                 .latency
                 .total
                 .fetch_add(ms as u64, Ordering::SeqCst);
-            self.inner.latency.count.fetch_add(1, Ordering::SeqCst);
+            let prev: u64 = self.inner.latency.count.fetch_add(1, Ordering::SeqCst);
+
+            // The first sample is taken as is, then the average is smoothed.
+            let _ = self.inner.latency.ewma_rtt.fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |ewma| {
+                    if prev == 0 {
+                        Some(us)
+                    } else {
+                        Some(ewma - ewma / RTT_SMOOTHING + us / RTT_SMOOTHING)
+                    }
+                },
+            );
         }
     }
 }
 
+/// Account the text and binary frames of a connection and their bytes
+///
+/// The frames are counted in place on the atomic counters of the stats,
+/// without going through a middleware chain.
+#[derive(Debug, Clone)]
+pub(super) struct TrafficAccounting {
+    stats: RelayConnectionStats,
+}
+
+impl TrafficAccounting {
+    #[inline]
+    pub(super) fn new(stats: RelayConnectionStats) -> Self {
+        Self { stats }
+    }
+
+    /// Wrap the sink and the stream of a connection
+    pub(super) fn wrap(
+        self,
+        sink: WebSocketSink,
+        stream: WebSocketStream,
+    ) -> (WebSocketSink, WebSocketStream) {
+        let stats: RelayConnectionStats = self.stats.clone();
+        let sink: WebSocketSink = Box::pin(sink.with(move |msg: Message| {
+            if let Some(size) = payload_size(&msg) {
+                stats.new_message_sent(size);
+            }
+            future::ready(Ok::<Message, TransportError>(msg))
+        }));
+
+        let stats: RelayConnectionStats = self.stats;
+        let stream: WebSocketStream = Box::pin(stream.inspect(move |res| {
+            if let Ok(msg) = res {
+                if let Some(size) = payload_size(msg) {
+                    stats.new_message_received(size);
+                }
+            }
+        }));
+
+        (sink, stream)
+    }
+}
+
+/// Size of the text and binary frames, `None` for the control frames
+#[inline]
+fn payload_size(msg: &Message) -> Option<usize> {
+    match msg {
+        Message::Text(text) => Some(text.len()),
+        Message::Binary(data) => Some(data.len()),
+        #[cfg(not(target_arch = "wasm32"))]
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +535,92 @@ mod tests {
         stats.add_bytes_received(30);
         assert_eq!(stats.bytes_received(), 30);
     }
+
+    #[test]
+    fn test_snapshot() {
+        let stats = RelayConnectionStats::default();
+        assert_eq!(stats.snapshot(), RelayConnectionStatsSnapshot::default());
+
+        stats.new_attempt();
+        stats.new_success();
+        stats.new_attempt();
+        stats.new_success();
+        stats.new_message_sent(10);
+        stats.new_message_received(20);
+        stats.new_message_received(5);
+
+        assert_eq!(
+            stats.snapshot(),
+            RelayConnectionStatsSnapshot {
+                attempts: 2,
+                success: 2,
+                reconnects: 1,
+                messages_sent: 1,
+                messages_received: 2,
+                bytes_sent: 10,
+                bytes_received: 25,
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_rtt() {
+        let stats = RelayConnectionStats::default();
+        assert!(stats.rtt().is_none());
+        assert!(stats.rtt_ewma().is_none());
+
+        stats.save_latency(Duration::from_millis(80));
+        assert_eq!(stats.rtt(), Some(Duration::from_millis(80)));
+        assert_eq!(stats.rtt_ewma(), Some(Duration::from_millis(80)));
+
+        // A spike moves the average only by 1/8
+        stats.save_latency(Duration::from_millis(880));
+        assert_eq!(stats.rtt(), Some(Duration::from_millis(880)));
+        assert_eq!(stats.rtt_ewma(), Some(Duration::from_millis(180)));
+    }
+
+    #[test]
+    fn test_clock_offset() {
+        let stats = RelayConnectionStats::default();
+        let ahead = Timestamp::now() + 600;
+
+        // Not enough samples
+        for _ in 0..CLOCK_OFFSET_MIN_SAMPLES - 1 {
+            stats.record_relay_time(ahead);
+        }
+        assert!(stats.clock_offset().is_none());
+
+        stats.record_relay_time(ahead);
+        let offset: i64 = stats.clock_offset().unwrap();
+        assert!((599..=600).contains(&offset));
+    }
+
+    #[test]
+    fn test_clock_offset_rejects_outliers() {
+        let stats = RelayConnectionStats::default();
+        let now = Timestamp::now();
+
+        // Implausible samples are discarded
+        for _ in 0..CLOCK_OFFSET_MIN_SAMPLES {
+            stats.record_relay_time(now + CLOCK_OFFSET_MAX * 2);
+        }
+        assert!(stats.clock_offset().is_none());
+
+        // Samples that don't agree aren't used
+        for i in 0..CLOCK_OFFSET_MIN_SAMPLES as u64 {
+            stats.record_relay_time(now + i * 3600);
+        }
+        assert!(stats.clock_offset().is_none());
+
+        // A forged `created_at` doesn't move the median
+        let stats = RelayConnectionStats::default();
+        stats.record_relay_time(now - 7200);
+        for _ in 0..CLOCK_OFFSET_MIN_SAMPLES {
+            stats.record_relay_time(now);
+        }
+        stats.record_relay_time(now + 7200);
+        let offset: i64 = stats.clock_offset().unwrap();
+        assert!((-1..=0).contains(&offset));
+    }
 }
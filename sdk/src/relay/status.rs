@@ -1,9 +1,18 @@
 use core::fmt;
 use core::sync::atomic::{AtomicU8, Ordering};
 
+use tokio::sync::watch;
+
+/// Relay status storage
+///
+/// The status is stored in an atomic, so it can be read without locks,
+/// and mirrored into a watch channel for change-driven waiting.
+/// The two are updated together, under the watch channel lock,
+/// so the concurrent updates are applied in the same order to both.
 #[derive(Debug)]
 pub(super) struct AtomicRelayStatus {
     value: AtomicU8,
+    watch: watch::Sender<RelayStatus>,
 }
 
 impl Default for AtomicRelayStatus {
@@ -17,12 +26,25 @@ impl AtomicRelayStatus {
     pub(super) fn new(status: RelayStatus) -> Self {
         Self {
             value: AtomicU8::new(status as u8),
+            watch: watch::Sender::new(status),
         }
     }
 
+    /// Set the status
+    ///
+    /// When this returns, both [`AtomicRelayStatus::load`] and the watchers observe the new status (or a newer one).
     #[inline]
     pub fn set(&self, status: RelayStatus) {
-        self.value.store(status as u8, Ordering::SeqCst);
+        self.watch.send_if_modified(|current| {
+            self.value.store(status as u8, Ordering::SeqCst);
+
+            if *current == status {
+                return false;
+            }
+
+            *current = status;
+            true
+        });
     }
 
     pub(super) fn load(&self) -> RelayStatus {
@@ -40,6 +62,11 @@ impl AtomicRelayStatus {
             _ => unreachable!(),
         }
     }
+
+    #[inline]
+    pub(super) fn watch(&self) -> watch::Receiver<RelayStatus> {
+        self.watch.subscribe()
+    }
 }
 
 /// Relay connection status
@@ -140,6 +167,23 @@ mod tests {
         assert_eq!(relay.load(), RelayStatus::Connected);
     }
 
+    #[tokio::test]
+    async fn test_status_watch() {
+        let relay = AtomicRelayStatus::default();
+        let mut watch = relay.watch();
+        assert_eq!(*watch.borrow_and_update(), RelayStatus::Initialized);
+
+        // Same status: no change
+        relay.set(RelayStatus::Initialized);
+        assert!(!watch.has_changed().unwrap());
+
+        relay.set(RelayStatus::Connecting);
+        relay.set(RelayStatus::Connected);
+        assert!(watch.has_changed().unwrap());
+        assert_eq!(*watch.borrow_and_update(), RelayStatus::Connected);
+        assert_eq!(relay.load(), RelayStatus::Connected);
+    }
+
     #[test]
     fn test_status_initialized() {
         let status = RelayStatus::Initialized;
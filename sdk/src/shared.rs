@@ -11,11 +11,15 @@ use tokio::sync::Mutex;
 use crate::authenticator::Authenticator;
 use crate::monitor::Monitor;
 use crate::policy::AdmitPolicy;
+use crate::throttle::SendLimiter;
+use crate::transport::headers::UpgradeHeadersResolver;
 use crate::transport::websocket::WebSocketTransport;
 
 // LruCache pre-allocate, so keep this at a reasonable value.
 // A good value may be <= 128k, considering that stored values are the 64-bit hashes of the event IDs.
 const MAX_VERIFICATION_CACHE_SIZE: usize = 128_000;
+/// Max number of original event JSONs kept in memory
+const MAX_RAW_EVENTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
 
 #[derive(Debug, Clone)]
 pub(crate) struct SharedState {
@@ -24,7 +28,12 @@ pub(crate) struct SharedState {
     verification_cache: Arc<Mutex<LruCache<u64, ()>>>,
     pub(crate) admit_policy: Option<Arc<dyn AdmitPolicy>>,
     pub(crate) authenticator: Option<Arc<dyn Authenticator>>,
+    pub(crate) upgrade_headers: Option<Arc<dyn UpgradeHeadersResolver>>,
     pub(crate) monitor: Option<Monitor>,
+    /// Original JSON of the received events
+    raw_events: Option<Arc<Mutex<LruCache<EventId, String>>>>,
+    /// Outgoing messages rate limiter, shared by all the relays
+    pub(crate) send_limiter: Option<Arc<SendLimiter>>,
 }
 
 impl SharedState {
@@ -33,7 +42,9 @@ impl SharedState {
         transport: Arc<dyn WebSocketTransport>,
         admit_policy: Option<Arc<dyn AdmitPolicy>>,
         authenticator: Option<Arc<dyn Authenticator>>,
+        upgrade_headers: Option<Arc<dyn UpgradeHeadersResolver>>,
         monitor: Option<Monitor>,
+        preserve_raw_events: bool,
     ) -> Self {
         let max_verification_cache_size: NonZeroUsize =
             NonZeroUsize::new(MAX_VERIFICATION_CACHE_SIZE)
@@ -45,10 +56,21 @@ impl SharedState {
             verification_cache: Arc::new(Mutex::new(LruCache::new(max_verification_cache_size))),
             admit_policy,
             authenticator,
+            upgrade_headers,
             monitor,
+            raw_events: preserve_raw_events
+                .then(|| Arc::new(Mutex::new(LruCache::new(MAX_RAW_EVENTS_CACHE_SIZE)))),
+            send_limiter: None,
         }
     }
 
+    /// Limit the outgoing messages of all the relays combined
+    #[inline]
+    pub(crate) fn with_send_limiter(mut self, limiter: Option<SendLimiter>) -> Self {
+        self.send_limiter = limiter.map(Arc::new);
+        self
+    }
+
     #[inline]
     pub(crate) fn database(&self) -> &Arc<dyn NostrDatabase> {
         &self.database
@@ -59,6 +81,28 @@ impl SharedState {
         self.authenticator.is_some()
     }
 
+    #[inline]
+    pub(crate) fn is_preserving_raw_events(&self) -> bool {
+        self.raw_events.is_some()
+    }
+
+    /// Keep the original JSON of a received event
+    ///
+    /// The JSON already kept for the event is never replaced.
+    pub(crate) async fn save_raw_event(&self, id: EventId, json: String) {
+        if let Some(raw_events) = &self.raw_events {
+            let mut raw_events = raw_events.lock().await;
+            raw_events.get_or_insert(id, || json);
+        }
+    }
+
+    /// Get the original JSON of a received event
+    pub(crate) async fn raw_event(&self, id: &EventId) -> Option<String> {
+        let raw_events = self.raw_events.as_ref()?;
+        let mut raw_events = raw_events.lock().await;
+        raw_events.get(id).cloned()
+    }
+
     pub(crate) async fn verified(&self, id: &EventId) -> bool {
         let mut cache = self.verification_cache.lock().await;
 
@@ -0,0 +1,191 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Outgoing messages throttling
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_utility::time;
+use universal_time::Instant;
+
+/// Token bucket
+///
+/// Refilled at `rate` tokens per second, up to a burst of one second worth of tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Available tokens: negative if a request larger than the burst has been granted
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            state: Mutex::new(BucketState {
+                tokens: rate,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Check if the tokens can be taken, returning how long to wait if there aren't enough
+    ///
+    /// The tokens aren't taken.
+    fn ready_in(&self, n: f64) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Refill
+        let now: Instant = Instant::now();
+        let elapsed: f64 = now
+            .checked_duration_since(state.last_refill)
+            .unwrap_or_default()
+            .as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.rate);
+        state.last_refill = now;
+
+        // A request larger than the burst is granted when the bucket is full, going in debt
+        let needed: f64 = n.min(self.rate);
+        if state.tokens >= needed {
+            return None;
+        }
+
+        Some(Duration::from_secs_f64((needed - state.tokens) / self.rate))
+    }
+
+    /// Take the tokens, without waiting
+    fn force_take(&self, n: f64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.tokens -= n;
+    }
+}
+
+/// Rate limiter of the outgoing messages
+///
+/// Limits the number of messages and/or bytes sent per second.
+#[derive(Debug)]
+pub(crate) struct SendLimiter {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl SendLimiter {
+    /// New limiter
+    ///
+    /// Returns `None` if there are no limits.
+    pub(crate) fn new(messages_per_sec: Option<u32>, bytes_per_sec: Option<u64>) -> Option<Self> {
+        let messages = messages_per_sec
+            .filter(|rate| *rate > 0)
+            .map(|rate| TokenBucket::new(rate as f64));
+        let bytes = bytes_per_sec
+            .filter(|rate| *rate > 0)
+            .map(|rate| TokenBucket::new(rate as f64));
+
+        if messages.is_none() && bytes.is_none() {
+            return None;
+        }
+
+        Some(Self { messages, bytes })
+    }
+
+    /// How long to wait before a message of `size` bytes can be sent, or `None` if it can be sent now
+    fn ready_in(&self, size: usize) -> Option<Duration> {
+        let messages = self.messages.as_ref().and_then(|b| b.ready_in(1.0));
+        let bytes = self.bytes.as_ref().and_then(|b| b.ready_in(size as f64));
+        messages.max(bytes)
+    }
+
+    /// Account a message of `size` bytes sent without waiting
+    pub(crate) fn acquire_now(&self, size: usize) {
+        if let Some(messages) = &self.messages {
+            messages.force_take(1.0);
+        }
+
+        if let Some(bytes) = &self.bytes {
+            bytes.force_take(size as f64);
+        }
+    }
+}
+
+/// Wait until a message of `size` bytes can be sent through all the limiters, then account it
+///
+/// Cancel safe: the tokens are taken only once all the limiters are ready,
+/// so dropping the future while waiting doesn't consume them.
+pub(crate) async fn acquire_all(limiters: &[&SendLimiter], size: usize) {
+    while let Some(wait) = limiters.iter().filter_map(|l| l.ready_in(size)).max() {
+        time::sleep(wait).await;
+    }
+
+    for limiter in limiters.iter() {
+        limiter.acquire_now(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_limits() {
+        assert!(SendLimiter::new(None, None).is_none());
+        assert!(SendLimiter::new(Some(0), Some(0)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_messages_per_sec() {
+        let limiter = SendLimiter::new(Some(10), None).unwrap();
+
+        // The burst is sent immediately, then 10 messages per second
+        let start = Instant::now();
+        for _ in 0..15 {
+            acquire_all(&[&limiter], 100).await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(900), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_bytes_per_sec() {
+        let limiter = SendLimiter::new(None, Some(1000)).unwrap();
+
+        let start = Instant::now();
+        acquire_all(&[&limiter], 1000).await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // Larger than the burst: granted when the bucket is full
+        acquire_all(&[&limiter], 2000).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(950), "{elapsed:?}");
+
+        // Wait to repay the debt
+        acquire_all(&[&limiter], 500).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(2400), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(3200), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_acquire_now() {
+        let limiter = SendLimiter::new(Some(2), None).unwrap();
+
+        // Never waits, but the messages are accounted
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire_now(0);
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        acquire_all(&[&limiter], 0).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+    }
+}
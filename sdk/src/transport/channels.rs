@@ -0,0 +1,177 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Nostr protocol channels
+//!
+//! Split a WebSocket connection in typed channels, hiding the [`Message`] framing.
+
+use std::io::{self, ErrorKind};
+
+use async_utility::task;
+use futures::{SinkExt, StreamExt};
+use nostr::JsonUtil;
+use nostr::message::{ClientMessage, RelayMessage};
+use tokio::sync::mpsc;
+
+use super::error::TransportError;
+use super::websocket::{Message, WebSocketSink, WebSocketStream};
+
+const CHANNEL_SIZE: usize = 1024;
+
+/// Outbound channel of the nostr messages
+///
+/// The sender is cheap to clone: when all the clones are dropped, the WebSocket sink is closed.
+#[derive(Debug, Clone)]
+pub struct NostrSender {
+    tx: mpsc::Sender<ClientMessage<'static>>,
+}
+
+impl NostrSender {
+    /// Send a message to the relay
+    ///
+    /// Returns an error if the connection has been closed.
+    pub async fn send(&self, msg: ClientMessage<'static>) -> Result<(), TransportError> {
+        self.tx
+            .send(msg)
+            .await
+            .map_err(|_| TransportError::IO(io::Error::from(ErrorKind::BrokenPipe)))
+    }
+
+    /// Check if the connection has been closed
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// Inbound channel of the nostr messages
+#[derive(Debug)]
+pub struct NostrReceiver {
+    rx: mpsc::Receiver<RelayMessage<'static>>,
+}
+
+impl NostrReceiver {
+    /// Receive the next message from the relay
+    ///
+    /// Returns `None` when the connection has been closed.
+    #[inline]
+    pub async fn recv(&mut self) -> Option<RelayMessage<'static>> {
+        self.rx.recv().await
+    }
+}
+
+/// Split a WebSocket connection in typed nostr channels
+///
+/// Spawns a task that converts the frames to/from the nostr protocol messages:
+/// - the text frames that aren't valid relay messages are skipped;
+/// - the pings are answered with a pong;
+/// - the task exits when the connection is closed or when the [`NostrReceiver`] is dropped.
+pub fn into_nostr_channels(
+    sink: WebSocketSink,
+    stream: WebSocketStream,
+) -> (NostrSender, NostrReceiver) {
+    let (outbound_tx, outbound_rx) = mpsc::channel(CHANNEL_SIZE);
+    let (inbound_tx, inbound_rx) = mpsc::channel(CHANNEL_SIZE);
+
+    task::spawn(pump(sink, stream, outbound_rx, inbound_tx));
+
+    (
+        NostrSender { tx: outbound_tx },
+        NostrReceiver { rx: inbound_rx },
+    )
+}
+
+async fn pump(
+    mut sink: WebSocketSink,
+    mut stream: WebSocketStream,
+    mut outbound: mpsc::Receiver<ClientMessage<'static>>,
+    inbound: mpsc::Sender<RelayMessage<'static>>,
+) {
+    // Set to false when all the senders have been dropped
+    let mut sending: bool = true;
+
+    loop {
+        tokio::select! {
+            msg = outbound.recv(), if sending => match msg {
+                Some(msg) => {
+                    if let Err(e) = sink.send(Message::Text(msg.as_json())).await {
+                        tracing::error!(error = %e, "Impossible to send message.");
+                        break;
+                    }
+                }
+                None => {
+                    sending = false;
+                    let _ = sink.close().await;
+                }
+            },
+            msg = stream.next() => match msg {
+                Some(Ok(Message::Text(json))) => match RelayMessage::from_json(&json) {
+                    Ok(msg) => {
+                        if inbound.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Skipping invalid relay message."),
+                },
+                Some(Ok(Message::Ping(data))) => {
+                    if let Err(e) = sink.send(Message::Pong(data)).await {
+                        tracing::error!(error = %e, "Impossible to send pong.");
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(..))) | None => break,
+                Some(Ok(..)) => {}
+                Some(Err(e)) => {
+                    tracing::error!(error = %e, "WebSocket stream error.");
+                    break;
+                }
+            },
+            _ = inbound.closed() => break,
+        }
+    }
+
+    if sending {
+        let _ = sink.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::{SubscriptionId, Url};
+
+    use super::*;
+    use crate::transport::mock::MockWebSocketTransport;
+    use crate::transport::websocket::WebSocketTransport;
+
+    #[tokio::test]
+    async fn test_nostr_channels() {
+        let transport = MockWebSocketTransport::new();
+        let url = Url::parse("ws://mock").unwrap();
+        let (sink, stream) = transport.connect(&url, None).await.unwrap();
+
+        let (sender, mut receiver) = into_nostr_channels(sink, stream);
+
+        // Inbound: invalid messages are skipped, pings are answered
+        let id = SubscriptionId::new("test");
+        transport.push(Message::Text(String::from("invalid")));
+        transport.push(Message::Ping(vec![1, 2, 3]));
+        transport.push(Message::Text(RelayMessage::eose(id.clone()).as_json()));
+
+        assert_eq!(receiver.recv().await, Some(RelayMessage::eose(id.clone())));
+        assert_eq!(transport.recv().await, Some(Message::Pong(vec![1, 2, 3])));
+
+        // Outbound
+        sender.send(ClientMessage::close(id.clone())).await.unwrap();
+        assert_eq!(
+            transport.recv().await,
+            Some(Message::Text(ClientMessage::close(id.clone()).as_json()))
+        );
+
+        // Closing the connection closes both channels
+        transport.disconnect();
+        assert_eq!(receiver.recv().await, None);
+        assert!(sender.is_closed());
+        assert!(sender.send(ClientMessage::close(id)).await.is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! WebSocket close codes
+
+use std::fmt;
+
+pub use async_wsocket::message::CloseFrame;
+
+/// WebSocket close code
+///
+/// <https://datatracker.ietf.org/doc/html/rfc6455#section-7.4.1>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebSocketCloseCode {
+    /// Normal closure (1000)
+    NormalClosure,
+    /// The endpoint is going away, i.e. server shutdown or browser navigation (1001)
+    GoingAway,
+    /// Protocol error (1002)
+    ProtocolError,
+    /// Received a type of data that can't be accepted (1003)
+    UnsupportedData,
+    /// Received data inconsistent with the type of the message, i.e. non-UTF-8 text (1007)
+    InvalidFramePayloadData,
+    /// Received a message that violates the endpoint policy (1008)
+    PolicyViolation,
+    /// Received a message too big to process (1009)
+    MessageTooBig,
+    /// Unexpected condition on the server (1011)
+    InternalServerError,
+    /// Other code
+    Unknown(u16),
+}
+
+impl WebSocketCloseCode {
+    /// Get the numeric code
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            Self::NormalClosure => 1000,
+            Self::GoingAway => 1001,
+            Self::ProtocolError => 1002,
+            Self::UnsupportedData => 1003,
+            Self::InvalidFramePayloadData => 1007,
+            Self::PolicyViolation => 1008,
+            Self::MessageTooBig => 1009,
+            Self::InternalServerError => 1011,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl fmt::Display for WebSocketCloseCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NormalClosure => f.write_str("normal closure"),
+            Self::GoingAway => f.write_str("going away"),
+            Self::ProtocolError => f.write_str("protocol error"),
+            Self::UnsupportedData => f.write_str("unsupported data"),
+            Self::InvalidFramePayloadData => f.write_str("invalid frame payload data"),
+            Self::PolicyViolation => f.write_str("policy violation"),
+            Self::MessageTooBig => f.write_str("message too big"),
+            Self::InternalServerError => f.write_str("internal server error"),
+            Self::Unknown(code) => write!(f, "unknown ({code})"),
+        }
+    }
+}
+
+impl From<u16> for WebSocketCloseCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1000 => Self::NormalClosure,
+            1001 => Self::GoingAway,
+            1002 => Self::ProtocolError,
+            1003 => Self::UnsupportedData,
+            1007 => Self::InvalidFramePayloadData,
+            1008 => Self::PolicyViolation,
+            1009 => Self::MessageTooBig,
+            1011 => Self::InternalServerError,
+            code => Self::Unknown(code),
+        }
+    }
+}
+
+impl From<WebSocketCloseCode> for u16 {
+    #[inline]
+    fn from(code: WebSocketCloseCode) -> Self {
+        code.as_u16()
+    }
+}
+
+impl From<&CloseFrame> for WebSocketCloseCode {
+    #[inline]
+    fn from(frame: &CloseFrame) -> Self {
+        Self::from(frame.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_code_roundtrip() {
+        for code in [1000, 1001, 1002, 1003, 1007, 1008, 1009, 1011, 1006, 4000] {
+            assert_eq!(u16::from(WebSocketCloseCode::from(code)), code);
+        }
+
+        assert_eq!(
+            WebSocketCloseCode::from(1009),
+            WebSocketCloseCode::MessageTooBig
+        );
+        assert_eq!(
+            WebSocketCloseCode::from(4000),
+            WebSocketCloseCode::Unknown(4000)
+        );
+    }
+
+    #[test]
+    fn test_close_code_from_frame() {
+        let frame = CloseFrame {
+            code: 1008,
+            reason: String::from("blocked"),
+        };
+        assert_eq!(
+            WebSocketCloseCode::from(&frame),
+            WebSocketCloseCode::PolicyViolation
+        );
+    }
+}
@@ -0,0 +1,365 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! DNS resolution
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, io};
+
+use async_utility::time;
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use tokio::net::{self, TcpStream};
+
+use super::error::TransportError;
+use crate::future::BoxedFuture;
+
+/// DNS resolver
+///
+/// Resolve the host of the relays, i.e., to use DNS-over-HTTPS or a split-horizon setup.
+pub trait DnsResolver: fmt::Debug + Send + Sync {
+    /// Resolve the host to the socket addresses, in order of preference
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxedFuture<'a, Result<Vec<SocketAddr>, io::Error>>;
+}
+
+/// System DNS resolver
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemDnsResolver;
+
+impl DnsResolver for SystemDnsResolver {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxedFuture<'a, Result<Vec<SocketAddr>, io::Error>> {
+        Box::pin(async move { Ok(net::lookup_host((host, port)).await?.collect()) })
+    }
+}
+
+/// Default delay between the connection attempts
+///
+/// <https://www.rfc-editor.org/rfc/rfc8305#section-8>
+const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolver of a transport
+///
+/// The DNS resolver is compared by pointer, to allow the transport deriving the comparison traits.
+#[derive(Debug, Clone)]
+pub(super) struct Resolver {
+    resolver: Arc<dyn DnsResolver>,
+    attempt_delay: Duration,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self {
+            resolver: Arc::new(SystemDnsResolver),
+            attempt_delay: DEFAULT_CONNECTION_ATTEMPT_DELAY,
+        }
+    }
+}
+
+impl Resolver {
+    /// Set the DNS resolver
+    #[inline]
+    pub(super) fn resolver(mut self, resolver: Arc<dyn DnsResolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Set the delay between the connection attempts
+    #[inline]
+    pub(super) fn attempt_delay(mut self, delay: Duration) -> Self {
+        self.attempt_delay = delay;
+        self
+    }
+
+    #[inline]
+    fn key(&self) -> (usize, Duration) {
+        (
+            Arc::as_ptr(&self.resolver) as *const () as usize,
+            self.attempt_delay,
+        )
+    }
+
+    /// Resolve the host and connect to the first reachable address
+    ///
+    /// The IP addresses aren't resolved.
+    /// The connection attempts are raced as described in [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305#section-5):
+    /// check [`race`] to learn more.
+    pub(super) async fn connect(&self, host: &str, port: u16) -> Result<TcpStream, TransportError> {
+        // The IPv6 addresses are enclosed in brackets
+        let ip: &str = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = ip.parse::<IpAddr>() {
+            return Ok(TcpStream::connect(SocketAddr::new(ip, port)).await?);
+        }
+
+        let addrs: Vec<SocketAddr> = self
+            .resolver
+            .resolve(host, port)
+            .await
+            .map_err(|e| TransportError::DnsResolutionFailed(format!("{host}: {e}")))?;
+
+        if addrs.is_empty() {
+            return Err(TransportError::DnsResolutionFailed(format!(
+                "no addresses found for {host}"
+            )));
+        }
+
+        Ok(race(interleave(addrs), self.attempt_delay, TcpStream::connect).await?)
+    }
+}
+
+/// Sort the addresses alternating the families, starting from the family of the first address
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6: bool = match addrs.first() {
+        Some(addr) => addr.is_ipv6(),
+        None => return addrs,
+    };
+
+    let (mut preferred, mut other): (VecDeque<SocketAddr>, VecDeque<SocketAddr>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_ipv6);
+
+    let mut sorted: Vec<SocketAddr> = Vec::with_capacity(preferred.len() + other.len());
+
+    loop {
+        match (preferred.pop_front(), other.pop_front()) {
+            (None, None) => break,
+            (preferred, other) => sorted.extend(preferred.into_iter().chain(other)),
+        }
+    }
+
+    sorted
+}
+
+/// Race the connection attempts
+///
+/// An attempt is started every `attempt_delay`, or as soon as the previous one fails,
+/// while the previous attempts keep running.
+/// The first established connection is returned, and the other attempts are dropped.
+async fn race<F, Fut, T>(
+    addrs: Vec<SocketAddr>,
+    attempt_delay: Duration,
+    connect: F,
+) -> Result<T, io::Error>
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = Result<T, io::Error>>,
+{
+    let mut addrs = addrs.into_iter().peekable();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error: Option<io::Error> = None;
+
+    loop {
+        // Start the next attempt
+        if let Some(addr) = addrs.next() {
+            attempts.push(connect(addr));
+        }
+
+        // All the attempts failed
+        if attempts.is_empty() {
+            break;
+        }
+
+        // Wait for an attempt to complete, or for the delay to start the next one
+        tokio::select! {
+            res = attempts.next() => match res {
+                Some(Ok(conn)) => return Ok(conn),
+                Some(Err(e)) => last_error = Some(e),
+                None => {}
+            },
+            _ = time::sleep(attempt_delay), if addrs.peek().is_some() => {}
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to")))
+}
+
+impl PartialEq for Resolver {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Resolver {}
+
+impl PartialOrd for Resolver {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Resolver {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for Resolver {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future;
+    use std::sync::atomic::{self, AtomicBool};
+    use std::time::Instant;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(100);
+
+    /// Set the flag when dropped
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, atomic::Ordering::SeqCst);
+        }
+    }
+
+    /// Resolve any host to the given addresses
+    #[derive(Debug)]
+    struct StaticResolver(Vec<SocketAddr>);
+
+    impl DnsResolver for StaticResolver {
+        fn resolve<'a>(
+            &'a self,
+            _host: &'a str,
+            _port: u16,
+        ) -> BoxedFuture<'a, Result<Vec<SocketAddr>, io::Error>> {
+            Box::pin(async move { Ok(self.0.clone()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_in_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Port closed
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+
+        // The first address is unreachable, the second one is used
+        let resolver =
+            Resolver::default().resolver(Arc::new(StaticResolver(vec![closed_addr, addr])));
+        let stream = resolver
+            .connect("relay.example", addr.port())
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+
+        // No addresses
+        let resolver = Resolver::default().resolver(Arc::new(StaticResolver(Vec::new())));
+        let err = resolver.connect("relay.example", 443).await.unwrap_err();
+        assert!(matches!(err, TransportError::DnsResolutionFailed(..)));
+
+        // All the addresses refused the connection
+        let resolver = Resolver::default().resolver(Arc::new(StaticResolver(vec![closed_addr])));
+        let err = resolver.connect("relay.example", 443).await.unwrap_err();
+        assert!(matches!(err, TransportError::ConnectionRefused));
+    }
+
+    #[test]
+    fn test_interleave() {
+        let v4_1: SocketAddr = "127.0.0.1:443".parse().unwrap();
+        let v4_2: SocketAddr = "127.0.0.2:443".parse().unwrap();
+        let v6_1: SocketAddr = "[::1]:443".parse().unwrap();
+        let v6_2: SocketAddr = "[::2]:443".parse().unwrap();
+        let v6_3: SocketAddr = "[::3]:443".parse().unwrap();
+
+        assert_eq!(
+            interleave(vec![v6_1, v6_2, v6_3, v4_1, v4_2]),
+            vec![v6_1, v4_1, v6_2, v4_2, v6_3]
+        );
+        assert_eq!(interleave(vec![v4_1, v4_2, v6_1]), vec![v4_1, v6_1, v4_2]);
+        assert_eq!(interleave(vec![v4_1, v4_2]), vec![v4_1, v4_2]);
+        assert!(interleave(Vec::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_race_head_start() {
+        let stalled: SocketAddr = "[::1]:443".parse().unwrap();
+        let reachable: SocketAddr = "127.0.0.1:443".parse().unwrap();
+        let dropped = Arc::new(AtomicBool::new(false));
+
+        // The first attempt never completes: the second one starts after the delay and wins
+        let started_at = Instant::now();
+        let addr = race(vec![stalled, reachable], DELAY, |addr| {
+            let guard = DropFlag(dropped.clone());
+            async move {
+                let _guard = guard;
+                if addr == stalled {
+                    future::pending::<()>().await;
+                }
+                Ok(addr)
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(addr, reachable);
+        assert!(started_at.elapsed() >= DELAY);
+
+        // The stalled attempt has been dropped
+        assert!(dropped.load(atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_race_next_attempt_on_failure() {
+        let failing: SocketAddr = "[::1]:443".parse().unwrap();
+        let reachable: SocketAddr = "127.0.0.1:443".parse().unwrap();
+
+        // The first attempt fails: the second one starts without waiting the delay
+        let started_at = Instant::now();
+        let addr = race(
+            vec![failing, reachable],
+            Duration::from_secs(60),
+            |addr| async move {
+                if addr == failing {
+                    return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+                }
+                Ok(addr)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(addr, reachable);
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+
+        // All the attempts fail: the last error is returned
+        let err = race(vec![failing], DELAY, |_| async {
+            Err::<(), _>(io::Error::from(io::ErrorKind::ConnectionRefused))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn test_compare_by_pointer() {
+        let resolver: Arc<dyn DnsResolver> = Arc::new(SystemDnsResolver);
+        let a = Resolver::default().resolver(resolver.clone());
+        assert_eq!(a, Resolver::default().resolver(resolver));
+        assert_ne!(Resolver::default(), Resolver::default());
+        assert_ne!(a.clone(), a.attempt_delay(Duration::from_millis(100)));
+    }
+}
@@ -3,11 +3,55 @@
 use std::fmt;
 use std::io::{self, ErrorKind};
 
+/// Phase of the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConnectionPhase {
+    /// TCP connection, to the relay or to the proxy
+    Tcp,
+    /// Proxy negotiation (SOCKS5 or HTTP `CONNECT`)
+    Proxy,
+    /// TLS handshake
+    Tls,
+    /// WebSocket handshake
+    Handshake,
+}
+
+impl fmt::Display for ConnectionPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => f.write_str("TCP connection"),
+            Self::Proxy => f.write_str("proxy negotiation"),
+            Self::Tls => f.write_str("TLS handshake"),
+            Self::Handshake => f.write_str("WebSocket handshake"),
+        }
+    }
+}
+
 /// Transport Error
 #[derive(Debug)]
 pub enum TransportError {
     /// I/O error
     IO(io::Error),
+    /// The host couldn't be resolved
+    DnsResolutionFailed(String),
+    /// The connection was refused
+    ConnectionRefused,
+    /// The server rejected the WebSocket upgrade
+    HttpUpgradeFailed {
+        /// HTTP status code
+        status_code: u16,
+        /// Response body
+        body: String,
+    },
+    /// A phase of the connection timed out
+    ConnectionTimeout(ConnectionPhase),
+    /// The received message or frame exceeds the size limits
+    MessageTooLarge {
+        /// Size of the message or frame
+        size: usize,
+        /// Maximum allowed size
+        max_size: usize,
+    },
     /// An error happened in the underlying backend.
     Backend(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -18,6 +62,21 @@ impl fmt::Display for TransportError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::IO(e) => e.fmt(f),
+            Self::DnsResolutionFailed(e) => write!(f, "DNS resolution failed: {e}"),
+            Self::ConnectionRefused => f.write_str("connection refused"),
+            Self::HttpUpgradeFailed { status_code, body } => {
+                write!(f, "HTTP upgrade failed: status={status_code}")?;
+
+                if !body.is_empty() {
+                    write!(f, ", body={body}")?;
+                }
+
+                Ok(())
+            }
+            Self::ConnectionTimeout(phase) => write!(f, "{phase} timed out"),
+            Self::MessageTooLarge { size, max_size } => {
+                write!(f, "message too large: size={size}, max_size={max_size}")
+            }
             Self::Backend(e) => e.fmt(f),
         }
     }
@@ -25,7 +84,10 @@ impl fmt::Display for TransportError {
 
 impl From<io::Error> for TransportError {
     fn from(e: io::Error) -> Self {
-        Self::IO(e)
+        match e.kind() {
+            ErrorKind::ConnectionRefused => Self::ConnectionRefused,
+            _ => Self::IO(e),
+        }
     }
 }
 
@@ -36,6 +98,25 @@ impl TransportError {
         Self::IO(io::Error::new(ErrorKind::TimedOut, "timeout"))
     }
 
+    /// Check if the error is transient, so retrying the connection may succeed
+    ///
+    /// The network errors, the timeouts and the server errors (HTTP `408`, `429` and `5xx`) are retriable,
+    /// while the rejected upgrades (i.e., HTTP `401` or `403`) and the exceeded size limits aren't.
+    /// The backend errors are opaque, so are considered retriable.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Self::IO(..)
+            | Self::DnsResolutionFailed(..)
+            | Self::ConnectionRefused
+            | Self::ConnectionTimeout(..)
+            | Self::Backend(..) => true,
+            Self::HttpUpgradeFailed { status_code, .. } => {
+                matches!(status_code, 408 | 429 | 500..=599)
+            }
+            Self::MessageTooLarge { .. } => false,
+        }
+    }
+
     /// Create a new backend error.
     #[inline]
     pub fn backend<E>(error: E) -> Self
@@ -45,3 +126,40 @@ impl TransportError {
         Self::Backend(error.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retriable() {
+        assert!(TransportError::timeout().is_retriable());
+        assert!(TransportError::ConnectionRefused.is_retriable());
+        assert!(TransportError::DnsResolutionFailed(String::from("relay.example")).is_retriable());
+        assert!(TransportError::ConnectionTimeout(ConnectionPhase::Tls).is_retriable());
+
+        let upgrade = |status_code: u16| TransportError::HttpUpgradeFailed {
+            status_code,
+            body: String::new(),
+        };
+        assert!(upgrade(503).is_retriable());
+        assert!(upgrade(429).is_retriable());
+        assert!(!upgrade(401).is_retriable());
+        assert!(!upgrade(403).is_retriable());
+
+        let too_large = TransportError::MessageTooLarge {
+            size: 2,
+            max_size: 1,
+        };
+        assert!(!too_large.is_retriable());
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let e = TransportError::from(io::Error::from(ErrorKind::ConnectionRefused));
+        assert!(matches!(e, TransportError::ConnectionRefused));
+
+        let e = TransportError::from(io::Error::from(ErrorKind::BrokenPipe));
+        assert!(matches!(e, TransportError::IO(..)));
+    }
+}
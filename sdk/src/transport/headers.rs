@@ -0,0 +1,136 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! WebSocket upgrade headers
+
+use std::collections::HashMap;
+use std::fmt;
+
+use nostr::RelayUrl;
+
+use crate::future::BoxedFuture;
+
+/// WebSocket upgrade request headers
+///
+/// Custom HTTP headers attached to the WebSocket handshake,
+/// i.e., to access relays behind reverse proxies that check a bearer token or a cookie.
+///
+/// The header values are considered sensitive: they are redacted in the [`fmt::Debug`] output.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct UpgradeHeaders {
+    headers: Vec<(String, String)>,
+}
+
+impl fmt::Debug for UpgradeHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.headers.iter().map(|(name, ..)| (name, "<redacted>")))
+            .finish()
+    }
+}
+
+impl UpgradeHeaders {
+    /// New empty headers
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header
+    pub fn header<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add the `Authorization: Bearer <token>` header
+    #[inline]
+    pub fn bearer<T>(self, token: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.header("Authorization", format!("Bearer {}", token.as_ref()))
+    }
+
+    /// Add the `Cookie` header
+    #[inline]
+    pub fn cookie<T>(self, cookie: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.header("Cookie", cookie)
+    }
+
+    /// Check if there are no headers
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Iterate the headers as `(name, value)` pairs
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+/// Resolve the WebSocket upgrade headers of a relay
+///
+/// Called at every connection attempt, so the tokens can be refreshed.
+pub trait UpgradeHeadersResolver: fmt::Debug + Send + Sync {
+    /// Get the upgrade headers for the relay
+    ///
+    /// Return empty headers to connect without custom headers.
+    fn resolve<'a>(&'a self, url: &'a RelayUrl) -> BoxedFuture<'a, UpgradeHeaders>;
+}
+
+/// Static headers, per relay
+impl UpgradeHeadersResolver for HashMap<RelayUrl, UpgradeHeaders> {
+    fn resolve<'a>(&'a self, url: &'a RelayUrl) -> BoxedFuture<'a, UpgradeHeaders> {
+        Box::pin(async move { self.get(url).cloned().unwrap_or_default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_values() {
+        let headers = UpgradeHeaders::new()
+            .bearer("secret-token")
+            .cookie("session=secret-cookie");
+
+        let debug: String = format!("{headers:?}");
+        assert!(debug.contains("Authorization"));
+        assert!(debug.contains("Cookie"));
+        assert!(!debug.contains("secret"));
+
+        let values: Vec<(&str, &str)> = headers.iter().collect();
+        assert_eq!(
+            values,
+            vec![
+                ("Authorization", "Bearer secret-token"),
+                ("Cookie", "session=secret-cookie")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_resolver() {
+        let url = RelayUrl::parse("wss://private.example.com").unwrap();
+        let other = RelayUrl::parse("wss://public.example.com").unwrap();
+
+        let mut resolver: HashMap<RelayUrl, UpgradeHeaders> = HashMap::new();
+        resolver.insert(url.clone(), UpgradeHeaders::new().bearer("token"));
+
+        assert!(!resolver.resolve(&url).await.is_empty());
+        assert!(resolver.resolve(&other).await.is_empty());
+    }
+}
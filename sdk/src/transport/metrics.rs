@@ -0,0 +1,91 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Connection metrics
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Metrics of a WebSocket connection
+///
+/// The handshake durations are measured while connecting,
+/// the bytes are accounted until the connection is closed.
+#[derive(Debug)]
+pub struct ConnectionMetrics {
+    tcp_connect_duration: Duration,
+    tls_handshake_duration: Option<Duration>,
+    ws_handshake_duration: Duration,
+    connected_at: Instant,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl ConnectionMetrics {
+    /// New connection metrics
+    ///
+    /// Useful for the custom transports.
+    pub fn new(
+        tcp_connect_duration: Duration,
+        tls_handshake_duration: Option<Duration>,
+        ws_handshake_duration: Duration,
+    ) -> Self {
+        Self {
+            tcp_connect_duration,
+            tls_handshake_duration,
+            ws_handshake_duration,
+            connected_at: Instant::now(),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    /// Duration of the TCP connection, including the proxy negotiation
+    #[inline]
+    pub fn tcp_connect_duration(&self) -> Duration {
+        self.tcp_connect_duration
+    }
+
+    /// Duration of the TLS handshake, if the connection is encrypted
+    #[inline]
+    pub fn tls_handshake_duration(&self) -> Option<Duration> {
+        self.tls_handshake_duration
+    }
+
+    /// Duration of the WebSocket handshake
+    #[inline]
+    pub fn ws_handshake_duration(&self) -> Duration {
+        self.ws_handshake_duration
+    }
+
+    /// When the connection has been established
+    #[inline]
+    pub fn connected_at(&self) -> Instant {
+        self.connected_at
+    }
+
+    /// Payload bytes sent
+    #[inline]
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Payload bytes received
+    #[inline]
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Account sent bytes
+    #[inline]
+    pub fn add_bytes_sent(&self, size: usize) {
+        self.bytes_sent.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Account received bytes
+    #[inline]
+    pub fn add_bytes_received(&self, size: usize) {
+        self.bytes_received
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,614 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! WebSocket transport middlewares
+//!
+//! Middlewares sit between the relay logic and the transport,
+//! and can inspect, modify, drop or inject WebSocket frames.
+//!
+//! # Ordering
+//!
+//! Given a chain `[A, B, C]`:
+//! - outgoing frames traverse the chain **forward**: `relay -> A -> B -> C -> transport`;
+//! - incoming frames traverse the chain **backward**: `transport -> C -> B -> A -> relay`.
+//!
+//! The frames injected by a middleware continue through the rest of the chain, in the same direction.
+//!
+//! # State
+//!
+//! A new chain is instantiated for every connection, using the [`WebSocketMiddlewareFactory`]s,
+//! so a middleware can keep per-connection state.
+//! The same middleware instance handles both the outgoing and the incoming frames of a connection.
+//!
+//! # Backpressure
+//!
+//! Frames are processed one at a time:
+//! the next frame isn't accepted until the previous one has traversed the whole chain.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, ready};
+
+use futures::{Sink, StreamExt, stream};
+use nostr::Url;
+use tokio::sync::Mutex;
+
+use super::error::TransportError;
+use super::headers::UpgradeHeaders;
+use super::websocket::{
+    IntoWebSocketTransport, Message, WebSocketSink, WebSocketStream, WebSocketTransport,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use super::websocket::{MeteredConnection, WebSocketLimits};
+use crate::future::BoxedFuture;
+
+/// Middleware action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiddlewareAction {
+    /// Forward the (possibly modified) frame
+    Forward(Message),
+    /// Drop the frame
+    Drop,
+    /// Replace the frame with zero or more frames
+    ///
+    /// Use it to inject additional frames, together with the original one.
+    Inject(Vec<Message>),
+}
+
+/// WebSocket middleware
+///
+/// Check the [module-level documentation](self) for the ordering semantics.
+pub trait WebSocketMiddleware: fmt::Debug + Send {
+    /// Handle an outgoing frame
+    fn on_send(
+        &mut self,
+        msg: Message,
+    ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+        Box::pin(async move { Ok(MiddlewareAction::Forward(msg)) })
+    }
+
+    /// Handle an incoming frame
+    fn on_receive(
+        &mut self,
+        msg: Message,
+    ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+        Box::pin(async move { Ok(MiddlewareAction::Forward(msg)) })
+    }
+}
+
+/// WebSocket middleware factory
+///
+/// Called at every connection to instantiate a fresh middleware.
+pub trait WebSocketMiddlewareFactory: fmt::Debug + Send + Sync {
+    /// Create a new middleware for the connection to `url`
+    fn create(&self, url: &Url) -> Box<dyn WebSocketMiddleware>;
+}
+
+/// WebSocket transport with a middleware chain
+///
+/// Wraps any [`WebSocketTransport`] and implements [`WebSocketTransport`] itself,
+/// so it can be used everywhere a transport is expected.
+#[derive(Debug, Clone)]
+pub struct MiddlewareTransport {
+    inner: Arc<dyn WebSocketTransport>,
+    factories: Vec<Arc<dyn WebSocketMiddlewareFactory>>,
+}
+
+impl MiddlewareTransport {
+    /// Wrap a transport with a middleware chain
+    ///
+    /// The first factory creates the outermost middleware (the closest to the relay logic).
+    pub fn new<T>(inner: T, factories: Vec<Arc<dyn WebSocketMiddlewareFactory>>) -> Self
+    where
+        T: IntoWebSocketTransport,
+    {
+        Self {
+            inner: inner.into_transport(),
+            factories,
+        }
+    }
+
+    /// Instantiate the chain for a new connection
+    fn wrap(
+        &self,
+        url: &Url,
+        sink: WebSocketSink,
+        stream: WebSocketStream,
+    ) -> (WebSocketSink, WebSocketStream) {
+        let chain: Vec<Box<dyn WebSocketMiddleware>> =
+            self.factories.iter().map(|f| f.create(url)).collect();
+        wrap(sink, stream, chain)
+    }
+}
+
+impl WebSocketTransport for MiddlewareTransport {
+    fn support_ping(&self) -> bool {
+        self.inner.support_ping()
+    }
+
+    fn connect<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+    ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+        Box::pin(async move {
+            let (sink, stream) = self.inner.connect(url, proxy).await?;
+            Ok(self.wrap(url, sink, stream))
+        })
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+        Box::pin(async move {
+            let (sink, stream) = self.inner.connect_with_headers(url, proxy, headers).await?;
+            Ok(self.wrap(url, sink, stream))
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_metrics<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        Box::pin(async move {
+            // The metrics account the frames actually written to and read from the socket
+            let (sink, stream, metrics) =
+                self.inner.connect_with_metrics(url, proxy, headers).await?;
+            let (sink, stream) = self.wrap(url, sink, stream);
+            Ok((sink, stream, metrics))
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_limits<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+        limits: WebSocketLimits,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        Box::pin(async move {
+            let (sink, stream, metrics) = self
+                .inner
+                .connect_with_limits(url, proxy, headers, limits)
+                .await?;
+            let (sink, stream) = self.wrap(url, sink, stream);
+            Ok((sink, stream, metrics))
+        })
+    }
+}
+
+/// Transport middleware
+///
+/// Unlike [`WebSocketMiddleware`], that handles the single frames,
+/// a transport middleware wraps the whole [`WebSocketTransport`].
+///
+/// Check [`RelayBuilder::add_middleware`](crate::relay::RelayBuilder::add_middleware) to learn how to apply it.
+pub trait TransportMiddleware {
+    /// Wrap the `inner` transport
+    fn wrap(self, inner: Arc<dyn WebSocketTransport>) -> Arc<dyn WebSocketTransport>;
+}
+
+/// Logging middleware
+///
+/// Emits a `TRACE` level [`tracing`] event for every WebSocket frame sent or received.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+impl TransportMiddleware for LoggingMiddleware {
+    fn wrap(self, inner: Arc<dyn WebSocketTransport>) -> Arc<dyn WebSocketTransport> {
+        Arc::new(MiddlewareTransport::new(
+            inner,
+            vec![Arc::new(FrameLoggerFactory)],
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct FrameLoggerFactory;
+
+impl WebSocketMiddlewareFactory for FrameLoggerFactory {
+    fn create(&self, url: &Url) -> Box<dyn WebSocketMiddleware> {
+        Box::new(FrameLogger { url: url.clone() })
+    }
+}
+
+#[derive(Debug)]
+struct FrameLogger {
+    url: Url,
+}
+
+impl FrameLogger {
+    fn log(&self, direction: &str, msg: &Message) {
+        match msg {
+            Message::Text(text) => tracing::trace!(
+                url = %self.url,
+                direction,
+                len = text.len(),
+                "Text frame: {text}"
+            ),
+            Message::Binary(data) => {
+                tracing::trace!(url = %self.url, direction, len = data.len(), "Binary frame.")
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Message::Ping(data) => {
+                tracing::trace!(url = %self.url, direction, len = data.len(), "Ping frame.")
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Message::Pong(data) => {
+                tracing::trace!(url = %self.url, direction, len = data.len(), "Pong frame.")
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Message::Close(frame) => {
+                tracing::trace!(url = %self.url, direction, ?frame, "Close frame.")
+            }
+        }
+    }
+}
+
+impl WebSocketMiddleware for FrameLogger {
+    fn on_send(
+        &mut self,
+        msg: Message,
+    ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+        self.log("send", &msg);
+        Box::pin(async move { Ok(MiddlewareAction::Forward(msg)) })
+    }
+
+    fn on_receive(
+        &mut self,
+        msg: Message,
+    ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+        self.log("receive", &msg);
+        Box::pin(async move { Ok(MiddlewareAction::Forward(msg)) })
+    }
+}
+
+type Chain = Arc<Mutex<Vec<Box<dyn WebSocketMiddleware>>>>;
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Send,
+    Receive,
+}
+
+/// Wrap a connection with a middleware chain
+fn wrap(
+    sink: WebSocketSink,
+    stream: WebSocketStream,
+    chain: Vec<Box<dyn WebSocketMiddleware>>,
+) -> (WebSocketSink, WebSocketStream) {
+    if chain.is_empty() {
+        return (sink, stream);
+    }
+
+    let chain: Chain = Arc::new(Mutex::new(chain));
+
+    let sink: WebSocketSink = Box::pin(MiddlewareSink {
+        inner: sink,
+        chain: chain.clone(),
+        processing: None,
+        pending: VecDeque::new(),
+    });
+
+    let stream: WebSocketStream = Box::pin(
+        stream
+            .then(move |res| {
+                let chain: Chain = chain.clone();
+                async move {
+                    match res {
+                        Ok(msg) => match process(chain, Direction::Receive, msg).await {
+                            Ok(msgs) => msgs.into_iter().map(Ok).collect(),
+                            Err(e) => vec![Err(e)],
+                        },
+                        Err(e) => vec![Err(e)],
+                    }
+                }
+            })
+            .flat_map(stream::iter),
+    );
+
+    (sink, stream)
+}
+
+/// Pass a frame through the chain
+async fn process(
+    chain: Chain,
+    direction: Direction,
+    msg: Message,
+) -> Result<Vec<Message>, TransportError> {
+    let mut chain = chain.lock().await;
+    let len: usize = chain.len();
+
+    let mut msgs: Vec<Message> = vec![msg];
+
+    for i in 0..len {
+        // Send traverses the chain forward, receive backward
+        let index: usize = match direction {
+            Direction::Send => i,
+            Direction::Receive => len - 1 - i,
+        };
+        let middleware = &mut chain[index];
+
+        let mut next: Vec<Message> = Vec::with_capacity(msgs.len());
+
+        for msg in msgs.into_iter() {
+            let action: MiddlewareAction = match direction {
+                Direction::Send => middleware.on_send(msg).await?,
+                Direction::Receive => middleware.on_receive(msg).await?,
+            };
+
+            match action {
+                MiddlewareAction::Forward(msg) => next.push(msg),
+                MiddlewareAction::Drop => {}
+                MiddlewareAction::Inject(msgs) => next.extend(msgs),
+            }
+        }
+
+        // Nothing left to process
+        if next.is_empty() {
+            return Ok(next);
+        }
+
+        msgs = next;
+    }
+
+    Ok(msgs)
+}
+
+struct MiddlewareSink {
+    inner: WebSocketSink,
+    chain: Chain,
+    /// Frame being processed by the chain
+    processing: Option<BoxedFuture<'static, Result<Vec<Message>, TransportError>>>,
+    /// Processed frames, waiting to be sent to the inner sink
+    pending: VecDeque<Message>,
+}
+
+impl MiddlewareSink {
+    /// Complete the processing of the current frame and send the results to the inner sink
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), TransportError>> {
+        loop {
+            if let Some(fut) = self.processing.as_mut() {
+                let res = ready!(fut.as_mut().poll(cx));
+                self.processing = None;
+                self.pending.extend(res?);
+            }
+
+            if self.pending.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+
+            ready!(self.inner.as_mut().poll_ready(cx))?;
+
+            if let Some(msg) = self.pending.pop_front() {
+                self.inner.as_mut().start_send(msg)?;
+            }
+        }
+    }
+}
+
+impl Sink<Message> for MiddlewareSink {
+    type Error = TransportError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_drain(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        this.processing = Some(Box::pin(process(this.chain.clone(), Direction::Send, item)));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        this.inner.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        this.inner.as_mut().poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::SinkExt;
+    use nostr::RelayUrl;
+
+    use super::*;
+    use crate::relay::RelayBuilder;
+    use crate::transport::mock::MockWebSocketTransport;
+
+    /// Append its name to the text frames
+    #[derive(Debug)]
+    struct Tagger {
+        name: &'static str,
+    }
+
+    impl WebSocketMiddleware for Tagger {
+        fn on_send(
+            &mut self,
+            msg: Message,
+        ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+            Box::pin(async move {
+                match msg {
+                    Message::Text(text) => Ok(MiddlewareAction::Forward(Message::Text(format!(
+                        "{text}|{}",
+                        self.name
+                    )))),
+                    msg => Ok(MiddlewareAction::Forward(msg)),
+                }
+            })
+        }
+
+        fn on_receive(
+            &mut self,
+            msg: Message,
+        ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+            self.on_send(msg)
+        }
+    }
+
+    #[derive(Debug)]
+    struct TaggerFactory(&'static str);
+
+    impl WebSocketMiddlewareFactory for TaggerFactory {
+        fn create(&self, _url: &Url) -> Box<dyn WebSocketMiddleware> {
+            Box::new(Tagger { name: self.0 })
+        }
+    }
+
+    /// Drop the "drop" frames, duplicate the "dup" frames and count the outgoing frames of the connection
+    #[derive(Debug, Default)]
+    struct Filter {
+        sent: usize,
+    }
+
+    impl WebSocketMiddleware for Filter {
+        fn on_send(
+            &mut self,
+            msg: Message,
+        ) -> BoxedFuture<'_, Result<MiddlewareAction, TransportError>> {
+            Box::pin(async move {
+                self.sent += 1;
+
+                match msg {
+                    Message::Text(text) if text.starts_with("drop") => Ok(MiddlewareAction::Drop),
+                    Message::Text(text) if text.starts_with("dup") => {
+                        Ok(MiddlewareAction::Inject(vec![
+                            Message::Text(text.clone()),
+                            Message::Text(text),
+                        ]))
+                    }
+                    Message::Text(text) if text.starts_with("count") => Ok(
+                        MiddlewareAction::Forward(Message::Text(format!("{text}:{}", self.sent))),
+                    ),
+                    msg => Ok(MiddlewareAction::Forward(msg)),
+                }
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    struct FilterFactory;
+
+    impl WebSocketMiddlewareFactory for FilterFactory {
+        fn create(&self, _url: &Url) -> Box<dyn WebSocketMiddleware> {
+            Box::new(Filter::default())
+        }
+    }
+
+    fn text(s: &str) -> Message {
+        Message::Text(s.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_chain_ordering() {
+        let mock = MockWebSocketTransport::new();
+        let transport = MiddlewareTransport::new(
+            mock.clone(),
+            vec![Arc::new(TaggerFactory("A")), Arc::new(TaggerFactory("B"))],
+        );
+        let url = Url::parse("ws://mock").unwrap();
+
+        let (mut sink, mut stream) = transport.connect(&url, None).await.unwrap();
+
+        // Send: forward
+        sink.send(text("out")).await.unwrap();
+        assert_eq!(mock.recv().await, Some(text("out|A|B")));
+
+        // Receive: backward
+        mock.push(text("in"));
+        assert_eq!(stream.next().await.unwrap().unwrap(), text("in|B|A"));
+    }
+
+    #[derive(Debug)]
+    struct Tag(&'static str);
+
+    impl TransportMiddleware for Tag {
+        fn wrap(self, inner: Arc<dyn WebSocketTransport>) -> Arc<dyn WebSocketTransport> {
+            Arc::new(MiddlewareTransport::new(
+                inner,
+                vec![Arc::new(TaggerFactory(self.0))],
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport_middleware_ordering() {
+        let mock = MockWebSocketTransport::new();
+        let url = RelayUrl::parse("ws://mock").unwrap();
+        let builder = RelayBuilder::new(url.clone())
+            .websocket_transport(mock.clone())
+            .add_middleware(Tag("A"))
+            .add_middleware(LoggingMiddleware)
+            .add_middleware(Tag("B"));
+
+        let (mut sink, mut stream) = builder
+            .websocket_transport
+            .connect((&url).into(), None)
+            .await
+            .unwrap();
+
+        // The last added middleware is the outermost
+        sink.send(text("out")).await.unwrap();
+        assert_eq!(mock.recv().await, Some(text("out|B|A")));
+
+        mock.push(text("in"));
+        assert_eq!(stream.next().await.unwrap().unwrap(), text("in|A|B"));
+    }
+
+    #[tokio::test]
+    async fn test_drop_and_inject() {
+        let mock = MockWebSocketTransport::new();
+        let transport = MiddlewareTransport::new(
+            mock.clone(),
+            vec![Arc::new(FilterFactory), Arc::new(TaggerFactory("A"))],
+        );
+        let url = Url::parse("ws://mock").unwrap();
+
+        let (mut sink, ..) = transport.connect(&url, None).await.unwrap();
+
+        sink.send(text("drop")).await.unwrap();
+        sink.send(text("dup")).await.unwrap();
+        sink.send(text("pass")).await.unwrap();
+
+        // The injected frames traverse the rest of the chain
+        assert_eq!(mock.recv().await, Some(text("dup|A")));
+        assert_eq!(mock.recv().await, Some(text("dup|A")));
+        assert_eq!(mock.recv().await, Some(text("pass|A")));
+        assert!(mock.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_per_connection_state() {
+        let mock = MockWebSocketTransport::new();
+        let transport = MiddlewareTransport::new(mock.clone(), vec![Arc::new(FilterFactory)]);
+        let url = Url::parse("ws://mock").unwrap();
+
+        let (mut sink, ..) = transport.connect(&url, None).await.unwrap();
+        sink.send(text("pass")).await.unwrap();
+        sink.send(text("count")).await.unwrap();
+        assert_eq!(mock.recv().await, Some(text("pass")));
+        assert_eq!(mock.recv().await, Some(text("count:2")));
+
+        // New connection, new state
+        let (mut sink, ..) = transport.connect(&url, None).await.unwrap();
+        sink.send(text("count")).await.unwrap();
+        assert_eq!(mock.recv().await, Some(text("count:1")));
+    }
+}
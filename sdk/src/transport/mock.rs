@@ -0,0 +1,315 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! Mock WebSocket transport for (unit) tests
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::{Sink, StreamExt};
+use nostr::Url;
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::error::TransportError;
+use super::websocket::{Message, WebSocketSink, WebSocketStream, WebSocketTransport};
+use crate::future::BoxedFuture;
+
+#[derive(Debug, Default)]
+struct MockState {
+    /// Messages queued before the connection
+    queue: Vec<Message>,
+    /// Read side of the current connection
+    incoming: Option<UnboundedSender<Message>>,
+    /// Number of established connections
+    connections: usize,
+}
+
+#[derive(Debug)]
+struct InnerMockWebSocketTransport {
+    state: Mutex<MockState>,
+    outgoing_tx: UnboundedSender<Message>,
+    outgoing_rx: AsyncMutex<UnboundedReceiver<Message>>,
+}
+
+/// Mock WebSocket transport
+///
+/// Every connection is wired to in-memory channels:
+/// the messages pushed with [`MockWebSocketTransport::push`] are delivered on the read side,
+/// while the messages sent to the sink can be received with [`MockWebSocketTransport::recv`].
+///
+/// The transport is cheap to clone: all the clones share the same state.
+#[derive(Debug, Clone)]
+pub struct MockWebSocketTransport {
+    inner: Arc<InnerMockWebSocketTransport>,
+}
+
+impl Default for MockWebSocketTransport {
+    fn default() -> Self {
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded();
+        Self {
+            inner: Arc::new(InnerMockWebSocketTransport {
+                state: Mutex::new(MockState::default()),
+                outgoing_tx,
+                outgoing_rx: AsyncMutex::new(outgoing_rx),
+            }),
+        }
+    }
+}
+
+impl MockWebSocketTransport {
+    /// New mock transport
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a message to be delivered on the read side
+    ///
+    /// If not connected, the message is queued and delivered on the next connection.
+    pub fn push(&self, msg: Message) {
+        let mut state = self.inner.state.lock().expect("mock state poisoned");
+
+        let msg: Message = match &state.incoming {
+            Some(incoming) => match incoming.unbounded_send(msg) {
+                Ok(()) => return,
+                // The connection has been closed: queue the message
+                Err(e) => e.into_inner(),
+            },
+            None => msg,
+        };
+
+        state.queue.push(msg);
+    }
+
+    /// Receive the next message sent to the sink
+    ///
+    /// Returns `None` if the transport has been dropped.
+    pub async fn recv(&self) -> Option<Message> {
+        let mut rx = self.inner.outgoing_rx.lock().await;
+        rx.next().await
+    }
+
+    /// Receive a message sent to the sink, if any, without waiting
+    pub fn try_recv(&self) -> Option<Message> {
+        let mut rx = self.inner.outgoing_rx.try_lock().ok()?;
+        rx.try_recv().ok()
+    }
+
+    /// Close the current connection
+    ///
+    /// The read side of the connection is terminated.
+    pub fn disconnect(&self) {
+        let mut state = self.inner.state.lock().expect("mock state poisoned");
+        state.incoming = None;
+    }
+
+    /// Number of established connections
+    pub fn connections(&self) -> usize {
+        let state = self.inner.state.lock().expect("mock state poisoned");
+        state.connections
+    }
+}
+
+impl WebSocketTransport for MockWebSocketTransport {
+    fn support_ping(&self) -> bool {
+        false
+    }
+
+    fn connect<'a>(
+        &'a self,
+        _url: &'a Url,
+        _proxy: Option<SocketAddr>,
+    ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+        Box::pin(async move {
+            let (tx, rx) = mpsc::unbounded();
+
+            let mut state = self.inner.state.lock().expect("mock state poisoned");
+
+            // Deliver the queued messages
+            for msg in state.queue.drain(..) {
+                // Can't fail: the receiver is still alive
+                let _ = tx.unbounded_send(msg);
+            }
+
+            state.incoming = Some(tx);
+            state.connections += 1;
+
+            let sink: WebSocketSink = Box::pin(MockSink {
+                outgoing: self.inner.outgoing_tx.clone(),
+            }) as WebSocketSink;
+            let stream: WebSocketStream = Box::pin(rx.map(Ok)) as WebSocketStream;
+
+            Ok((sink, stream))
+        })
+    }
+}
+
+struct MockSink {
+    outgoing: UnboundedSender<Message>,
+}
+
+impl Sink<Message> for MockSink {
+    type Error = TransportError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.outgoing
+            .unbounded_send(item)
+            .map_err(|e| TransportError::backend(e.into_send_error()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::IntoFuture;
+    use std::time::Duration;
+
+    use nostr::prelude::*;
+
+    use super::*;
+    use crate::relay::{Relay, ReqExitPolicy};
+
+    fn into_client_message(msg: Message) -> ClientMessage<'static> {
+        match msg {
+            Message::Text(json) => ClientMessage::from_json(json).unwrap(),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_and_sink() {
+        let transport = MockWebSocketTransport::new();
+        let url = Url::parse("ws://mock").unwrap();
+
+        transport.push(Message::Text(String::from("first")));
+
+        let (mut sink, mut stream) = transport.connect(&url, None).await.unwrap();
+        assert_eq!(transport.connections(), 1);
+
+        transport.push(Message::Text(String::from("second")));
+
+        // Read side
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            Message::Text(String::from("first"))
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            Message::Text(String::from("second"))
+        );
+
+        // Sink side
+        futures::SinkExt::send(&mut sink, Message::Text(String::from("out")))
+            .await
+            .unwrap();
+        assert_eq!(
+            transport.recv().await,
+            Some(Message::Text(String::from("out")))
+        );
+        assert!(transport.try_recv().is_none());
+
+        // Disconnect terminates the read side
+        transport.disconnect();
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_relay_subscription_flow() {
+        let transport = MockWebSocketTransport::new();
+        let url = RelayUrl::parse("ws://mock").unwrap();
+        let relay = Relay::builder(url)
+            .websocket_transport(transport.clone())
+            .build();
+
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("test").sign(&keys).unwrap();
+
+        let filter = Filter::new().kind(Kind::TextNote);
+        let fetch = relay
+            .fetch_events(filter.clone())
+            .timeout(Duration::from_secs(5))
+            .policy(ReqExitPolicy::ExitOnEOSE);
+
+        let mock = async {
+            // Wait for the REQ
+            let subscription_id = match into_client_message(transport.recv().await.unwrap()) {
+                ClientMessage::Req {
+                    subscription_id,
+                    filters,
+                } => {
+                    assert_eq!(filters.len(), 1);
+                    assert_eq!(filters[0].as_ref(), &filter);
+                    subscription_id.into_owned()
+                }
+                msg => panic!("unexpected message: {msg:?}"),
+            };
+
+            // Reply with the event and EOSE
+            let msg = RelayMessage::event(subscription_id.clone(), event.clone());
+            transport.push(Message::Text(msg.as_json()));
+            let msg = RelayMessage::eose(subscription_id);
+            transport.push(Message::Text(msg.as_json()));
+        };
+
+        let (events, ..) = tokio::join!(fetch.into_future(), mock);
+        let events = events.unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.first().unwrap().id, event.id);
+    }
+
+    #[tokio::test]
+    async fn test_relay_bytes_accounting() {
+        let transport = MockWebSocketTransport::new();
+        let url = RelayUrl::parse("ws://mock").unwrap();
+        let relay = Relay::builder(url)
+            .websocket_transport(transport.clone())
+            .build();
+
+        relay
+            .try_connect()
+            .timeout(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let stats = relay.stats();
+        assert_eq!(stats.bytes_sent(), 0);
+        assert_eq!(stats.bytes_received(), 0);
+
+        // Outgoing
+        relay
+            .subscribe(Filter::new().kind(Kind::TextNote))
+            .await
+            .unwrap();
+        let msg: Message = transport.recv().await.unwrap();
+        assert_eq!(stats.bytes_sent(), msg.len());
+
+        // Incoming
+        let msg = Message::Text(RelayMessage::notice("test").as_json());
+        let size: usize = msg.len();
+        transport.push(msg);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(stats.bytes_received(), size);
+    }
+}
@@ -4,5 +4,17 @@
 
 //! Nostr transports
 
+pub mod channels;
+pub mod close;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dns;
 pub mod error;
+pub mod headers;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+pub mod middleware;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
+#[cfg(not(target_arch = "wasm32"))]
+mod tls;
 pub mod websocket;
@@ -0,0 +1,278 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Copyright (c) 2023-2025 Rust Nostr Developers
+// Distributed under the MIT software license
+
+//! TLS configuration of the native transport
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+use super::error::TransportError;
+
+/// TLS configuration
+///
+/// The rustls client config is built at the first connection and then shared by all the connections of the transport.
+#[derive(Default, Clone)]
+pub(super) struct TlsConfig {
+    root_certificates: Vec<Vec<u8>>,
+    pinned_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    client_config: Arc<OnceLock<Arc<ClientConfig>>>,
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("root_certificates", &self.root_certificates.len())
+            .field("pinned_certificates", &self.pinned_certificates.len())
+            .field("accept_invalid_certs", &self.accept_invalid_certs)
+            .finish()
+    }
+}
+
+// The cached client config is derived from the other fields, so it's ignored by the comparisons
+
+impl PartialEq for TlsConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.root_certificates == other.root_certificates
+            && self.pinned_certificates == other.pinned_certificates
+            && self.accept_invalid_certs == other.accept_invalid_certs
+    }
+}
+
+impl Eq for TlsConfig {}
+
+impl PartialOrd for TlsConfig {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TlsConfig {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.root_certificates
+            .cmp(&other.root_certificates)
+            .then(self.pinned_certificates.cmp(&other.pinned_certificates))
+            .then(self.accept_invalid_certs.cmp(&other.accept_invalid_certs))
+    }
+}
+
+impl Hash for TlsConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.root_certificates.hash(state);
+        self.pinned_certificates.hash(state);
+        self.accept_invalid_certs.hash(state);
+    }
+}
+
+impl TlsConfig {
+    pub(super) fn add_root_certificate(mut self, der: Vec<u8>) -> Self {
+        self.root_certificates.push(der);
+        self.client_config = Arc::default();
+        self
+    }
+
+    pub(super) fn pin_certificate(mut self, der: Vec<u8>) -> Self {
+        self.pinned_certificates.push(der);
+        self.client_config = Arc::default();
+        self
+    }
+
+    pub(super) fn accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self.client_config = Arc::default();
+        self
+    }
+
+    /// Get the rustls client config, building it at the first call
+    pub(super) fn client_config(&self) -> Result<Arc<ClientConfig>, TransportError> {
+        if let Some(config) = self.client_config.get() {
+            return Ok(config.clone());
+        }
+
+        let config: Arc<ClientConfig> = Arc::new(self.build()?);
+        Ok(self.client_config.get_or_init(|| config).clone())
+    }
+
+    fn build(&self) -> Result<ClientConfig, TransportError> {
+        let provider: Arc<CryptoProvider> = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(TransportError::backend)?;
+
+        // The pinned certificates replace the chain validation
+        if !self.pinned_certificates.is_empty() {
+            return Ok(builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerification {
+                    pinned: self.pinned_certificates.clone(),
+                    provider,
+                }))
+                .with_no_client_auth());
+        }
+
+        if self.accept_invalid_certs {
+            return Ok(builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+                .with_no_client_auth());
+        }
+
+        let mut root_store: RootCertStore = RootCertStore::empty();
+        root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+        for der in self.root_certificates.iter() {
+            root_store
+                .add(CertificateDer::from(der.as_slice()))
+                .map_err(TransportError::backend)?;
+        }
+
+        Ok(builder
+            .with_root_certificates(root_store)
+            .with_no_client_auth())
+    }
+}
+
+/// Accept only the pinned server certificates, checking the handshake signatures
+#[derive(Debug)]
+struct PinnedCertificateVerification {
+    pinned: Vec<Vec<u8>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self
+            .pinned
+            .iter()
+            .any(|der| der.as_slice() == end_entity.as_ref())
+        {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(String::from(
+                "certificate pinning failure",
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Accept any server certificate, still checking the handshake signatures
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_config_cached() {
+        let tls = TlsConfig::default();
+        let a = tls.client_config().unwrap();
+        let b = tls.clone().client_config().unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // A new certificate invalidates the cache
+        let tls = tls.add_root_certificate(include_bytes!("testdata/ca.der").to_vec());
+        let c = tls.client_config().unwrap();
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn test_invalid_root_certificate() {
+        let tls = TlsConfig::default().add_root_certificate(vec![0, 1, 2]);
+        assert!(tls.client_config().is_err());
+    }
+}
@@ -9,15 +9,32 @@ use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use async_wsocket::{ConnectionMode, Message, WebSocket};
+#[cfg(target_arch = "wasm32")]
+use async_utility::time;
+#[cfg(target_arch = "wasm32")]
+use async_wsocket::ConnectionMode;
+pub use async_wsocket::Message;
+use async_wsocket::WebSocket;
+use base64::engine::{Engine, general_purpose};
 use futures::stream::SplitSink;
 use futures::{Sink, SinkExt, Stream, StreamExt, TryStreamExt};
 use nostr::Url;
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::dns::{DnsResolver, Resolver};
 use super::error::TransportError;
+use super::headers::UpgradeHeaders;
+#[cfg(not(target_arch = "wasm32"))]
+use super::metrics::ConnectionMetrics;
+#[cfg(not(target_arch = "wasm32"))]
+use super::tls::TlsConfig;
 use crate::future::BoxedFuture;
 
+const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+const MAX_FRAME_SIZE: usize = 10 * 1024 * 1024;
+
 /// WebSocket transport sink
 #[cfg(not(target_arch = "wasm32"))]
 pub type WebSocketSink = Pin<Box<dyn Sink<Message, Error = TransportError> + Send>>;
@@ -30,6 +47,13 @@ pub type WebSocketStream = Pin<Box<dyn Stream<Item = Result<Message, TransportEr
 /// WebSocket transport stream
 #[cfg(target_arch = "wasm32")]
 pub type WebSocketStream = Pin<Box<dyn Stream<Item = Result<Message, TransportError>>>>;
+/// WebSocket transport sink and stream, with the metrics of the connection
+#[cfg(not(target_arch = "wasm32"))]
+pub type MeteredConnection = (
+    WebSocketSink,
+    WebSocketStream,
+    Option<Arc<ConnectionMetrics>>,
+);
 
 #[doc(hidden)]
 pub trait IntoWebSocketTransport {
@@ -71,11 +95,257 @@ pub trait WebSocketTransport: fmt::Debug + Send + Sync {
         url: &'a Url,
         proxy: Option<SocketAddr>,
     ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>>;
+
+    /// Connect, attaching custom headers to the WebSocket upgrade request
+    ///
+    /// By default, returns an error if any header is passed,
+    /// to avoid connecting without the expected credentials.
+    fn connect_with_headers<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+        if headers.is_empty() {
+            return self.connect(url, proxy);
+        }
+
+        Box::pin(async move {
+            Err(TransportError::backend(
+                "custom upgrade headers not supported by the transport",
+            ))
+        })
+    }
+
+    /// Connect, also returning the metrics of the connection
+    ///
+    /// By default, connects with [`WebSocketTransport::connect_with_headers`], without metrics.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_metrics<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        Box::pin(async move {
+            let (tx, rx) = self.connect_with_headers(url, proxy, headers).await?;
+            Ok((tx, rx, None))
+        })
+    }
+
+    /// Connect with the [`WebSocketLimits`] of the relay, instead of the ones of the transport
+    ///
+    /// By default, connects with [`WebSocketTransport::connect_with_metrics`]
+    /// and enforces the [`WebSocketLimits::max_message_size`] on the stream (see [`with_limits`]).
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_limits<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+        limits: WebSocketLimits,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        Box::pin(async move {
+            let (tx, rx, metrics) = self.connect_with_metrics(url, proxy, headers).await?;
+            Ok((tx, with_limits(rx, limits), metrics))
+        })
+    }
+}
+
+/// Proxy protocol
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProxyKind {
+    /// SOCKS5 proxy
+    #[default]
+    Socks5,
+    /// SOCKS5 proxy, with username/password authentication (RFC 1929)
+    ///
+    /// The proxy can still select no authentication.
+    /// The username and the password must be 1 to 255 bytes long.
+    Socks5WithPassword {
+        /// Username
+        username: String,
+        /// Password
+        password: String,
+    },
+    /// HTTP proxy, tunneling the connection with the `CONNECT` method
+    HttpConnect {
+        /// Value of the `Proxy-Authorization` header (i.e., `Basic <base64 of user:password>`)
+        authorization: Option<String>,
+    },
+}
+
+impl ProxyKind {
+    /// HTTP proxy, tunneling the connection with the `CONNECT` method and the `Basic` authentication
+    pub fn http_connect_basic<U, P>(username: U, password: P) -> Self
+    where
+        U: AsRef<str>,
+        P: AsRef<str>,
+    {
+        let credentials: String = format!("{}:{}", username.as_ref(), password.as_ref());
+        Self::HttpConnect {
+            authorization: Some(format!(
+                "Basic {}",
+                general_purpose::STANDARD.encode(credentials)
+            )),
+        }
+    }
+}
+
+/// WebSocket limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WebSocketLimits {
+    /// Maximum size of a message, in bytes (default: 10 MiB)
+    pub max_message_size: Option<usize>,
+    /// Maximum size of a single frame, in bytes (default: 10 MiB)
+    pub max_frame_size: Option<usize>,
+}
+
+impl Default for WebSocketLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: Some(MAX_MESSAGE_SIZE),
+            max_frame_size: Some(MAX_FRAME_SIZE),
+        }
+    }
+}
+
+impl WebSocketLimits {
+    /// Disable all limits
+    #[inline]
+    pub fn disable() -> Self {
+        Self {
+            max_message_size: None,
+            max_frame_size: None,
+        }
+    }
 }
 
 /// Default websocket transport
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct DefaultWebsocketTransport;
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DefaultWebsocketTransport {
+    connect_timeout: Option<Duration>,
+    proxy_kind: ProxyKind,
+    limits: WebSocketLimits,
+    #[cfg(not(target_arch = "wasm32"))]
+    tls: TlsConfig,
+    #[cfg(not(target_arch = "wasm32"))]
+    resolver: Resolver,
+}
+
+impl DefaultWebsocketTransport {
+    /// New default websocket transport
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connection timeout (default: None)
+    ///
+    /// Bound the whole TCP, TLS and WebSocket handshake.
+    /// By default, no timeout is applied at the transport level.
+    ///
+    /// On native targets, the error reports the phase that timed out
+    /// (see [`TransportError::ConnectionTimeout`]).
+    #[inline]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Proxy protocol (default: [`ProxyKind::Socks5`])
+    ///
+    /// Applies to the proxy address of the relay options.
+    /// Not supported in the browser, where the proxy is ignored.
+    #[inline]
+    pub fn proxy_kind(mut self, kind: ProxyKind) -> Self {
+        self.proxy_kind = kind;
+        self
+    }
+
+    /// Message and frame size limits (default: 10 MiB)
+    ///
+    /// The frames are bounded while reading them, so a relay can't exhaust the memory
+    /// by sending a huge message. Exceeding a limit closes the connection with an error.
+    #[inline]
+    pub fn limits(mut self, limits: WebSocketLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Trust an additional root certificate, in DER format
+    ///
+    /// The certificate is added to the webpki root certificates,
+    /// i.e., to connect to relays using a certificate issued by a private CA.
+    /// An invalid certificate makes the connections fail.
+    ///
+    /// Not available in the browser, which uses the system trust store.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, der: Vec<u8>) -> Self {
+        self.tls = self.tls.add_root_certificate(der);
+        self
+    }
+
+    /// Pin a server certificate, in DER format
+    ///
+    /// When at least one certificate is pinned, the connections are accepted only if the relay presents
+    /// one of the pinned certificates, which can be self-signed. The root certificates are ignored.
+    /// Otherwise, the connections fail with a `certificate pinning failure` error.
+    ///
+    /// Not available in the browser.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pin_certificate(mut self, der: &[u8]) -> Self {
+        self.tls = self.tls.pin_certificate(der.to_vec());
+        self
+    }
+
+    /// Accept any server certificate (default: false)
+    ///
+    /// **Dangerous**: the identity of the relays is no longer verified,
+    /// allowing anyone in the middle to read and alter the traffic. Use only for development.
+    ///
+    /// Not available in the browser.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.tls = self.tls.accept_invalid_certs(accept);
+        self
+    }
+
+    /// DNS resolver (default: [`SystemDnsResolver`](super::dns::SystemDnsResolver))
+    ///
+    /// The connection attempts to the resolved addresses are raced
+    /// (see [`DefaultWebsocketTransport::connection_attempt_delay`]).
+    /// When connecting through a proxy, the host is resolved by the proxy.
+    ///
+    /// Not available in the browser.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn dns_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: DnsResolver + 'static,
+    {
+        self.resolver = self.resolver.resolver(Arc::new(resolver));
+        self
+    }
+
+    /// Delay between the connection attempts (default: 250 ms)
+    ///
+    /// When the host resolves to multiple addresses, the attempts are raced
+    /// alternating IPv6 and IPv4 ("Happy Eyeballs", [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305)):
+    /// a new attempt starts after this delay, or as soon as the previous one fails,
+    /// and the first established connection is used.
+    ///
+    /// Not available in the browser.
+    #[inline]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connection_attempt_delay(mut self, delay: Duration) -> Self {
+        self.resolver = self.resolver.attempt_delay(delay);
+        self
+    }
+}
 
 impl WebSocketTransport for DefaultWebsocketTransport {
     fn support_ping(&self) -> bool {
@@ -88,30 +358,482 @@ impl WebSocketTransport for DefaultWebsocketTransport {
         proxy: Option<SocketAddr>,
     ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
         Box::pin(async move {
-            let mode: ConnectionMode = match proxy {
-                #[cfg(not(target_arch = "wasm32"))]
-                Some(proxy) => ConnectionMode::Proxy(proxy),
-                #[cfg(target_arch = "wasm32")]
-                Some(_) => ConnectionMode::Direct,
-                None => ConnectionMode::Direct,
+            // Connect
+            // The native connection applies the proxy kind and the limits to the handshake
+            #[cfg(not(target_arch = "wasm32"))]
+            let (socket, ..) = native::connect(
+                url,
+                proxy,
+                &self.proxy_kind,
+                &UpgradeHeaders::new(),
+                &self.limits,
+                &self.tls,
+                &self.resolver,
+                self.connect_timeout,
+            )
+            .await?;
+
+            // The browsers don't support proxies
+            #[cfg(target_arch = "wasm32")]
+            let socket: WebSocket = {
+                let _ = proxy;
+                time::timeout(
+                    self.connect_timeout,
+                    WebSocket::connect(url, &ConnectionMode::Direct),
+                )
+                .await
+                .ok_or_else(TransportError::timeout)?
+                .map_err(TransportError::backend)?
             };
 
+            Ok(split(socket, self.limits))
+        })
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+        if headers.is_empty() {
+            return self.connect(url, proxy);
+        }
+
+        Box::pin(async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                // Connect
+                let (socket, ..) = native::connect(
+                    url,
+                    proxy,
+                    &self.proxy_kind,
+                    headers,
+                    &self.limits,
+                    &self.tls,
+                    &self.resolver,
+                    self.connect_timeout,
+                )
+                .await?;
+
+                Ok(split(socket, self.limits))
+            }
+
+            // The browsers don't allow to set the headers of the WebSocket handshake
+            #[cfg(target_arch = "wasm32")]
+            {
+                let _ = (url, proxy);
+                Err(TransportError::backend(
+                    "custom upgrade headers not supported in the browser",
+                ))
+            }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_metrics<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        self.connect_with_limits(url, proxy, headers, self.limits)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_with_limits<'a>(
+        &'a self,
+        url: &'a Url,
+        proxy: Option<SocketAddr>,
+        headers: &'a UpgradeHeaders,
+        limits: WebSocketLimits,
+    ) -> BoxedFuture<'a, Result<MeteredConnection, TransportError>> {
+        Box::pin(async move {
             // Connect
-            let socket: WebSocket = WebSocket::connect(url, &mode)
-                .await
+            let (socket, metrics) = native::connect(
+                url,
+                proxy,
+                &self.proxy_kind,
+                headers,
+                &limits,
+                &self.tls,
+                &self.resolver,
+                self.connect_timeout,
+            )
+            .await?;
+
+            // Account the bytes
+            let metrics: Arc<ConnectionMetrics> = Arc::new(metrics);
+            let (tx, rx) = split(socket, limits);
+            let (tx, rx) = native::meter(tx, rx, metrics.clone());
+
+            Ok((tx, rx, Some(metrics)))
+        })
+    }
+}
+
+/// Split sink and stream
+fn split(socket: WebSocket, limits: WebSocketLimits) -> (WebSocketSink, WebSocketStream) {
+    let (tx, rx) = socket.split();
+
+    // NOTE: don't use sink_map_err here, as it may cause panics!
+    // Issue: https://github.com/rust-nostr/nostr/issues/984
+    let sink: WebSocketSink = Box::pin(TransportSink(tx)) as WebSocketSink;
+    #[cfg(not(target_arch = "wasm32"))]
+    let rx = rx.map_err(native::map_error);
+    #[cfg(target_arch = "wasm32")]
+    let rx = rx.map_err(TransportError::backend);
+
+    let stream: WebSocketStream = with_limits(Box::pin(rx) as WebSocketStream, limits);
+
+    (sink, stream)
+}
+
+/// Enforce the [`WebSocketLimits::max_message_size`] on a stream
+///
+/// Useful for the custom transports: the messages that exceed the limit are turned into errors.
+/// This doesn't prevent the allocation of the received message,
+/// so the transport should also bound the size of the frames while reading them.
+pub fn with_limits(stream: WebSocketStream, limits: WebSocketLimits) -> WebSocketStream {
+    let Some(max_size) = limits.max_message_size else {
+        return stream;
+    };
+
+    Box::pin(stream.and_then(move |msg| async move {
+        let size: usize = msg.len();
+
+        if size > max_size {
+            return Err(TransportError::MessageTooLarge { size, max_size });
+        }
+
+        Ok(msg)
+    }))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::future::Future;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use std::time::{Duration, Instant};
+
+    use async_utility::time;
+    use async_wsocket::WebSocket;
+    use futures::{Sink, TryStreamExt};
+    use nostr::Url;
+    use rustls::pki_types::ServerName;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+    use tokio_socks::tcp::Socks5Stream;
+    use tokio_tungstenite::MaybeTlsStream;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::error::{CapacityError, Error as WsError};
+    use tokio_tungstenite::tungstenite::handshake::client::Request;
+    use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+    use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+
+    use super::{
+        Message, ProxyKind, TransportError, WebSocketLimits, WebSocketSink, WebSocketStream,
+    };
+    use crate::transport::dns::Resolver;
+    use crate::transport::error::ConnectionPhase;
+    use crate::transport::headers::UpgradeHeaders;
+    use crate::transport::metrics::ConnectionMetrics;
+    use crate::transport::tls::TlsConfig;
+
+    /// Max size of the HTTP CONNECT response head
+    const MAX_CONNECT_RESPONSE_SIZE: usize = 8 * 1024;
+
+    pub(super) async fn connect(
+        url: &Url,
+        proxy: Option<SocketAddr>,
+        proxy_kind: &ProxyKind,
+        headers: &UpgradeHeaders,
+        limits: &WebSocketLimits,
+        tls: &TlsConfig,
+        resolver: &Resolver,
+        timeout: Option<Duration>,
+    ) -> Result<(WebSocket, ConnectionMetrics), TransportError> {
+        let mut request: Request = url
+            .as_str()
+            .into_client_request()
+            .map_err(TransportError::backend)?;
+
+        for (name, value) in headers.iter() {
+            let name: HeaderName =
+                HeaderName::from_bytes(name.as_bytes()).map_err(TransportError::backend)?;
+            // The error doesn't include the value
+            let mut value: HeaderValue =
+                HeaderValue::from_str(value).map_err(TransportError::backend)?;
+            value.set_sensitive(true);
+            request.headers_mut().append(name, value);
+        }
+
+        // Bound the frames while reading them
+        let config: WebSocketConfig = WebSocketConfig::default()
+            .max_message_size(limits.max_message_size)
+            .max_frame_size(limits.max_frame_size);
+
+        let host: &str = url
+            .host_str()
+            .ok_or_else(|| TransportError::backend("empty host"))?;
+        let port: u16 = url
+            .port_or_known_default()
+            .ok_or_else(|| TransportError::backend("invalid port"))?;
+
+        // The timeout bounds the whole connection: every phase gets the remaining time
+        let started_at: Instant = Instant::now();
+        let deadline: Option<Instant> = timeout.map(|timeout| started_at + timeout);
+
+        // Reject the invalid credentials before connecting
+        if let (Some(..), ProxyKind::Socks5WithPassword { username, password }) =
+            (proxy, proxy_kind)
+        {
+            check_socks5_credential("username", username)?;
+            check_socks5_credential("password", password)?;
+        }
+
+        let conn: TcpStream = match proxy {
+            Some(proxy) => {
+                let conn: TcpStream =
+                    phase(deadline, ConnectionPhase::Tcp, TcpStream::connect(proxy)).await??;
+
+                match proxy_kind {
+                    ProxyKind::Socks5 => phase(
+                        deadline,
+                        ConnectionPhase::Proxy,
+                        Socks5Stream::connect_with_socket(conn, (host, port)),
+                    )
+                    .await?
+                    .map_err(TransportError::backend)?
+                    .into_inner(),
+                    ProxyKind::Socks5WithPassword { username, password } => phase(
+                        deadline,
+                        ConnectionPhase::Proxy,
+                        Socks5Stream::connect_with_password_and_socket(
+                            conn,
+                            (host, port),
+                            username,
+                            password,
+                        ),
+                    )
+                    .await?
+                    .map_err(TransportError::backend)?
+                    .into_inner(),
+                    ProxyKind::HttpConnect { authorization } => {
+                        phase(
+                            deadline,
+                            ConnectionPhase::Proxy,
+                            connect_via_http_connect(conn, host, port, authorization.as_deref()),
+                        )
+                        .await??
+                    }
+                }
+            }
+            None => phase(deadline, ConnectionPhase::Tcp, resolver.connect(host, port)).await??,
+        };
+
+        let tcp_connect_duration: Duration = started_at.elapsed();
+
+        // TLS handshake
+        let tls_started_at: Instant = Instant::now();
+        let (conn, tls_handshake_duration) = match url.scheme() {
+            "wss" => {
+                // The IPv6 addresses are enclosed in brackets
+                let domain: &str = host.trim_start_matches('[').trim_end_matches(']');
+                let server_name: ServerName<'static> =
+                    ServerName::try_from(domain.to_string()).map_err(TransportError::backend)?;
+                let connector: TlsConnector = TlsConnector::from(tls.client_config()?);
+                let conn = phase(
+                    deadline,
+                    ConnectionPhase::Tls,
+                    connector.connect(server_name, conn),
+                )
+                .await?
                 .map_err(TransportError::backend)?;
+                (MaybeTlsStream::Rustls(conn), Some(tls_started_at.elapsed()))
+            }
+            _ => (MaybeTlsStream::Plain(conn), None),
+        };
 
-            // Split sink and stream
-            let (tx, rx) = socket.split();
+        // NOT REMOVE `Box::pin`!
+        // Use `Box::pin` to fix stack overflow on windows targets due to large `Future`
+        let ws_started_at: Instant = Instant::now();
+        let (stream, ..) = phase(
+            deadline,
+            ConnectionPhase::Handshake,
+            Box::pin(tokio_tungstenite::client_async_with_config(
+                request,
+                conn,
+                Some(config),
+            )),
+        )
+        .await?
+        .map_err(map_handshake_error)?;
 
-            // NOTE: don't use sink_map_err here, as it may cause panics!
-            // Issue: https://github.com/rust-nostr/nostr/issues/984
-            let sink: WebSocketSink = Box::pin(TransportSink(tx)) as WebSocketSink;
-            let stream: WebSocketStream =
-                Box::pin(rx.map_err(TransportError::backend)) as WebSocketStream;
+        let metrics: ConnectionMetrics = ConnectionMetrics::new(
+            tcp_connect_duration,
+            tls_handshake_duration,
+            ws_started_at.elapsed(),
+        );
 
-            Ok((sink, stream))
-        })
+        Ok((WebSocket::Tokio(stream), metrics))
+    }
+
+    /// Account the payload bytes of the connection
+    pub(super) fn meter(
+        sink: WebSocketSink,
+        stream: WebSocketStream,
+        metrics: Arc<ConnectionMetrics>,
+    ) -> (WebSocketSink, WebSocketStream) {
+        let sink: WebSocketSink = Box::pin(MeteredSink {
+            sink,
+            metrics: metrics.clone(),
+        });
+        let stream: WebSocketStream = Box::pin(stream.inspect_ok(move |msg| {
+            metrics.add_bytes_received(msg.len());
+        }));
+        (sink, stream)
+    }
+
+    struct MeteredSink {
+        sink: WebSocketSink,
+        metrics: Arc<ConnectionMetrics>,
+    }
+
+    impl Sink<Message> for MeteredSink {
+        type Error = TransportError;
+
+        fn poll_ready(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            self.sink.as_mut().poll_ready(cx)
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+            let size: usize = item.len();
+            self.sink.as_mut().start_send(item)?;
+            self.metrics.add_bytes_sent(size);
+            Ok(())
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            self.sink.as_mut().poll_flush(cx)
+        }
+
+        fn poll_close(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            self.sink.as_mut().poll_close(cx)
+        }
+    }
+
+    fn check_socks5_credential(name: &str, value: &str) -> Result<(), TransportError> {
+        if value.is_empty() || value.len() > 255 {
+            return Err(TransportError::IO(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("SOCKS5 {name} must be 1 to 255 bytes long"),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Run a connection phase, until the deadline
+    async fn phase<F>(
+        deadline: Option<Instant>,
+        phase: ConnectionPhase,
+        future: F,
+    ) -> Result<F::Output, TransportError>
+    where
+        F: Future,
+    {
+        let timeout: Option<Duration> =
+            deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        time::timeout(timeout, future)
+            .await
+            .ok_or(TransportError::ConnectionTimeout(phase))
+    }
+
+    /// Map the stream errors, keeping the exceeded limits distinguishable
+    pub(super) fn map_error(error: async_wsocket::Error) -> TransportError {
+        match error {
+            async_wsocket::Error::Ws(WsError::Capacity(CapacityError::MessageTooLong {
+                size,
+                max_size,
+            })) => TransportError::MessageTooLarge { size, max_size },
+            e => TransportError::backend(e),
+        }
+    }
+
+    /// Map the WebSocket handshake errors, keeping the rejected upgrades distinguishable
+    fn map_handshake_error(error: WsError) -> TransportError {
+        match error {
+            WsError::Http(response) => TransportError::HttpUpgradeFailed {
+                status_code: response.status().as_u16(),
+                body: response
+                    .body()
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default()
+                    .into_owned(),
+            },
+            WsError::Io(e) => TransportError::from(e),
+            e => TransportError::backend(e),
+        }
+    }
+
+    /// Open a tunnel to `host:port` through an HTTP proxy
+    pub(super) async fn connect_via_http_connect(
+        mut stream: TcpStream,
+        host: &str,
+        port: u16,
+        authorization: Option<&str>,
+    ) -> Result<TcpStream, TransportError> {
+        let mut request: String =
+            format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if let Some(authorization) = authorization {
+            request.push_str(&format!("Proxy-Authorization: {authorization}\r\n"));
+        }
+        request.push_str("\r\n");
+
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read the response head byte by byte, to not consume the tunneled data
+        let mut head: Vec<u8> = Vec::with_capacity(128);
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() >= MAX_CONNECT_RESPONSE_SIZE {
+                return Err(TransportError::backend(
+                    "HTTP CONNECT proxy response head too large",
+                ));
+            }
+
+            head.push(stream.read_u8().await?);
+        }
+
+        // Check the status line (i.e., `HTTP/1.1 200 Connection established`)
+        let head: String = String::from_utf8_lossy(&head).into_owned();
+        let status_line: &str = head.lines().next().unwrap_or_default();
+        let status: Option<&str> = status_line
+            .strip_prefix("HTTP/1.")
+            .and_then(|rest| rest.split_whitespace().nth(1));
+
+        // Any 2xx status means that the tunnel is established
+        match status {
+            Some(status) if status.len() == 3 && status.starts_with('2') => Ok(stream),
+            _ => Err(TransportError::backend(format!(
+                "HTTP CONNECT proxy refused the tunnel to {host}:{port}: {status_line}"
+            ))),
+        }
     }
 }
 
@@ -144,3 +866,601 @@ impl Sink<Message> for TransportSink {
             .map_err(TransportError::backend)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io;
+
+    use nostr_relay_builder::prelude::*;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http::StatusCode;
+
+    use super::*;
+    use crate::future::BoxedFuture;
+    use crate::transport::error::ConnectionPhase;
+
+    /// Accept a single WebSocket connection and return the handshake headers
+    pub(crate) async fn capture_upgrade_headers() -> (Url, oneshot::Receiver<Vec<(String, String)>>)
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = Url::parse(&format!("ws://{addr}")).unwrap();
+
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let callback = |req: &Request, res: Response| {
+                let headers = req
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.as_str().to_string(),
+                            value.to_str().unwrap().to_string(),
+                        )
+                    })
+                    .collect();
+                let _ = tx.send(headers);
+                Ok(res)
+            };
+            let _ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        });
+
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_headers() {
+        let (url, rx) = capture_upgrade_headers().await;
+
+        let headers = UpgradeHeaders::new().bearer("token").cookie("session=abc");
+
+        let transport = DefaultWebsocketTransport::new();
+        let (_sink, _stream) = transport
+            .connect_with_headers(&url, None, &headers)
+            .await
+            .unwrap();
+
+        let captured = rx.await.unwrap();
+        assert!(captured.contains(&("authorization".to_string(), "Bearer token".to_string())));
+        assert!(captured.contains(&("cookie".to_string(), "session=abc".to_string())));
+    }
+
+    #[derive(Debug)]
+    struct NoHeadersTransport;
+
+    impl WebSocketTransport for NoHeadersTransport {
+        fn support_ping(&self) -> bool {
+            false
+        }
+
+        fn connect<'a>(
+            &'a self,
+            _url: &'a Url,
+            _proxy: Option<SocketAddr>,
+        ) -> BoxedFuture<'a, Result<(WebSocketSink, WebSocketStream), TransportError>> {
+            Box::pin(async move { Err(TransportError::timeout()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_headers_not_supported() {
+        let url = Url::parse("ws://127.0.0.1:1").unwrap();
+        let headers = UpgradeHeaders::new().bearer("token");
+
+        let res = NoHeadersTransport
+            .connect_with_headers(&url, None, &headers)
+            .await;
+        assert!(matches!(res, Err(TransportError::Backend(..))));
+    }
+
+    /// Run an HTTP CONNECT proxy that replies with `status` and, if `200`, tunnels the connection
+    async fn http_connect_proxy(status: &'static str) -> (SocketAddr, oneshot::Receiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpStream;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut client, ..) = listener.accept().await.unwrap();
+
+            let mut head: Vec<u8> = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(client.read_u8().await.unwrap());
+            }
+            let head = String::from_utf8(head).unwrap();
+
+            client
+                .write_all(format!("HTTP/1.1 {status}\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+
+            if status.starts_with('2') {
+                let target: &str = head
+                    .strip_prefix("CONNECT ")
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .unwrap();
+                let mut target = TcpStream::connect(target).await.unwrap();
+                let _ = tx.send(head);
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut target).await;
+            } else {
+                let _ = tx.send(head);
+            }
+        });
+
+        (addr, rx)
+    }
+
+    #[tokio::test]
+    async fn test_connect_via_http_connect() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+        let (proxy, rx) = http_connect_proxy("200 Connection established").await;
+
+        let transport = DefaultWebsocketTransport::new()
+            .proxy_kind(ProxyKind::http_connect_basic("user", "pass"));
+        let (_sink, _stream) = transport.connect((&url).into(), Some(proxy)).await.unwrap();
+
+        let head = rx.await.unwrap();
+        let url: &Url = (&url).into();
+        let target = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+        assert!(head.starts_with(&format!("CONNECT {target} HTTP/1.1\r\n")));
+        assert!(head.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_via_http_connect_refused() {
+        let url = Url::parse("ws://127.0.0.1:1").unwrap();
+        let (proxy, ..) = http_connect_proxy("407 Proxy Authentication Required").await;
+
+        let transport = DefaultWebsocketTransport::new().proxy_kind(ProxyKind::HttpConnect {
+            authorization: None,
+        });
+        match transport.connect(&url, Some(proxy)).await {
+            Err(TransportError::Backend(e)) => assert!(e.to_string().contains("407")),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(..) => panic!("expected error"),
+        }
+    }
+
+    /// Run a SOCKS5 proxy that, if `credentials` are set, requires the username/password authentication
+    async fn socks5_proxy(credentials: Option<(&'static str, &'static str)>) -> SocketAddr {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpStream;
+
+        async fn read_string(client: &mut TcpStream) -> String {
+            let len: u8 = client.read_u8().await.unwrap();
+            let mut buf = vec![0; len as usize];
+            client.read_exact(&mut buf).await.unwrap();
+            String::from_utf8(buf).unwrap()
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut client, ..) = listener.accept().await.unwrap();
+
+            // Greeting
+            assert_eq!(client.read_u8().await.unwrap(), 0x05);
+            let num: u8 = client.read_u8().await.unwrap();
+            let mut methods = vec![0; num as usize];
+            client.read_exact(&mut methods).await.unwrap();
+
+            match credentials {
+                Some((username, password)) => {
+                    assert!(methods.contains(&0x02));
+                    client.write_all(&[0x05, 0x02]).await.unwrap();
+
+                    // Sub-negotiation
+                    assert_eq!(client.read_u8().await.unwrap(), 0x01);
+                    let valid: bool = read_string(&mut client).await == username
+                        && read_string(&mut client).await == password;
+                    client
+                        .write_all(&[0x01, if valid { 0x00 } else { 0x01 }])
+                        .await
+                        .unwrap();
+
+                    if !valid {
+                        return;
+                    }
+                }
+                None => client.write_all(&[0x05, 0x00]).await.unwrap(),
+            }
+
+            // Request
+            let mut head = [0; 4];
+            client.read_exact(&mut head).await.unwrap();
+            let host: String = match head[3] {
+                0x01 => {
+                    let mut ip = [0; 4];
+                    client.read_exact(&mut ip).await.unwrap();
+                    std::net::Ipv4Addr::from(ip).to_string()
+                }
+                0x03 => read_string(&mut client).await,
+                atyp => panic!("unexpected address type: {atyp}"),
+            };
+            let port: u16 = client.read_u16().await.unwrap();
+
+            let mut target = TcpStream::connect((host.as_str(), port)).await.unwrap();
+            client
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut target).await;
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_connect_via_socks5() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        // No authentication
+        let proxy = socks5_proxy(None).await;
+        let transport = DefaultWebsocketTransport::new();
+        let (_sink, _stream) = transport.connect((&url).into(), Some(proxy)).await.unwrap();
+
+        // The proxy selects no authentication, even if credentials are offered
+        let proxy = socks5_proxy(None).await;
+        let transport =
+            DefaultWebsocketTransport::new().proxy_kind(ProxyKind::Socks5WithPassword {
+                username: String::from("user"),
+                password: String::from("pass"),
+            });
+        let (_sink, _stream) = transport.connect((&url).into(), Some(proxy)).await.unwrap();
+
+        // Username/password authentication
+        let proxy = socks5_proxy(Some(("user", "pass"))).await;
+        let (_sink, _stream) = transport.connect((&url).into(), Some(proxy)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_via_socks5_auth_failure() {
+        let url = Url::parse("ws://127.0.0.1:1").unwrap();
+
+        // Wrong password
+        let proxy = socks5_proxy(Some(("user", "pass"))).await;
+        let transport =
+            DefaultWebsocketTransport::new().proxy_kind(ProxyKind::Socks5WithPassword {
+                username: String::from("user"),
+                password: String::from("wrong"),
+            });
+        assert!(matches!(
+            transport.connect(&url, Some(proxy)).await,
+            Err(TransportError::Backend(..))
+        ));
+
+        // Credentials too long: rejected before connecting
+        let transport =
+            DefaultWebsocketTransport::new().proxy_kind(ProxyKind::Socks5WithPassword {
+                username: "a".repeat(256),
+                password: String::from("pass"),
+            });
+        match transport.connect(&url, Some(proxy)).await {
+            Err(TransportError::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(..) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_size_limit() {
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = Url::parse(&format!("ws://{addr}")).unwrap();
+
+        tokio::spawn(async move {
+            for size in [512, 2048] {
+                let (stream, ..) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let _ = ws.send(TungsteniteMessage::text("a".repeat(size))).await;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+
+        let transport = DefaultWebsocketTransport::new().limits(WebSocketLimits {
+            max_message_size: Some(1024),
+            max_frame_size: Some(1024),
+        });
+
+        // Within the limits
+        let (_sink, mut stream) = transport.connect(&url, None).await.unwrap();
+        let msg = stream.next().await.unwrap().unwrap();
+        assert_eq!(msg.len(), 512);
+
+        // Exceeds the limits
+        let (_sink, mut stream) = transport.connect(&url, None).await.unwrap();
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(TransportError::MessageTooLarge { max_size: 1024, .. })
+        ));
+    }
+
+    #[test]
+    fn test_with_limits() {
+        let messages = vec![
+            Ok(Message::Text("a".repeat(10))),
+            Ok(Message::Text("a".repeat(100))),
+        ];
+        let stream: WebSocketStream = Box::pin(futures::stream::iter(messages));
+        let limits = WebSocketLimits {
+            max_message_size: Some(50),
+            max_frame_size: None,
+        };
+
+        let results: Vec<_> = futures::executor::block_on(with_limits(stream, limits).collect());
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(TransportError::MessageTooLarge {
+                size: 100,
+                max_size: 50
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        // Mock relay
+        let opts = LocalRelayTestOptions {
+            unresponsive_connection: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let mock = MockRelay::run_with_opts(opts).await.unwrap();
+        let url = mock.url().await;
+
+        let transport = DefaultWebsocketTransport::new().connect_timeout(Duration::from_secs(1));
+
+        let res = transport.connect((&url).into(), None).await;
+        match res {
+            Err(TransportError::ConnectionTimeout(phase)) => {
+                assert_eq!(phase, ConnectionPhase::Handshake)
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(..) => panic!("expected timeout"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_proxy() {
+        // Proxy that accepts the connection but never replies
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_conn, ..) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let url = Url::parse("ws://127.0.0.1:1").unwrap();
+        let transport = DefaultWebsocketTransport::new().connect_timeout(Duration::from_secs(1));
+
+        let res = transport.connect(&url, Some(proxy)).await;
+        assert!(matches!(
+            res,
+            Err(TransportError::ConnectionTimeout(ConnectionPhase::Proxy))
+        ));
+    }
+
+    /// Run a TLS WebSocket server, with a certificate for `localhost` issued by a test CA
+    async fn tls_server() -> Url {
+        use rustls::ServerConfig;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+        use tokio_rustls::TlsAcceptor;
+
+        let cert = CertificateDer::from(include_bytes!("testdata/localhost.der").to_vec());
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            include_bytes!("testdata/localhost.key.der").to_vec(),
+        ));
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(vec![cert], key)
+                .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((stream, ..)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        let _ws = tokio_tungstenite::accept_async(stream).await;
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                });
+            }
+        });
+
+        Url::parse(&format!("wss://localhost:{port}")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connect_tls_root_certificate() {
+        let url = tls_server().await;
+
+        // Unknown CA
+        let transport = DefaultWebsocketTransport::new();
+        assert!(transport.connect(&url, None).await.is_err());
+
+        // Trusted CA
+        let transport = DefaultWebsocketTransport::new()
+            .add_root_certificate(include_bytes!("testdata/ca.der").to_vec());
+        let (_sink, _stream) = transport.connect(&url, None).await.unwrap();
+
+        // Invalid root certificate
+        let transport = DefaultWebsocketTransport::new().add_root_certificate(vec![0, 1, 2]);
+        assert!(transport.connect(&url, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_tls_accept_invalid_certs() {
+        let url = tls_server().await;
+
+        let transport = DefaultWebsocketTransport::new().danger_accept_invalid_certs(true);
+        let (_sink, _stream) = transport.connect(&url, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_tls_pinned_certificate() {
+        let url = tls_server().await;
+
+        // Pinned certificate, even if issued by an unknown CA
+        let transport = DefaultWebsocketTransport::new()
+            .pin_certificate(include_bytes!("testdata/localhost.der"));
+        let (_sink, _stream) = transport.connect(&url, None).await.unwrap();
+
+        // Other certificate pinned
+        let transport =
+            DefaultWebsocketTransport::new().pin_certificate(include_bytes!("testdata/ca.der"));
+        match transport.connect(&url, None).await {
+            Err(TransportError::Backend(e)) => {
+                assert!(e.to_string().contains("certificate pinning failure"))
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(..) => panic!("expected error"),
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestResolver(SocketAddr);
+
+    impl DnsResolver for TestResolver {
+        fn resolve<'a>(
+            &'a self,
+            host: &'a str,
+            _port: u16,
+        ) -> BoxedFuture<'a, Result<Vec<SocketAddr>, io::Error>> {
+            Box::pin(async move {
+                match host {
+                    "relay.test" => Ok(vec![self.0]),
+                    _ => Err(io::Error::new(io::ErrorKind::NotFound, "unknown host")),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_dns_resolver() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+        let url: &Url = (&url).into();
+        let addr: SocketAddr = format!("127.0.0.1:{}", url.port().unwrap())
+            .parse()
+            .unwrap();
+
+        let transport = DefaultWebsocketTransport::new().dns_resolver(TestResolver(addr));
+
+        // Resolved by the custom resolver
+        let url = Url::parse(&format!("ws://relay.test:{}", addr.port())).unwrap();
+        let (_sink, _stream) = transport.connect(&url, None).await.unwrap();
+
+        // Unknown host
+        let url = Url::parse(&format!("ws://unknown.test:{}", addr.port())).unwrap();
+        assert!(transport.connect(&url, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_http_upgrade_failed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = Url::parse(&format!("ws://{addr}")).unwrap();
+
+        tokio::spawn(async move {
+            let (stream, ..) = listener.accept().await.unwrap();
+            let callback = |_req: &Request, _res: Response| {
+                let res = ErrorResponse::new(Some(String::from("auth required")));
+                let (mut parts, body) = res.into_parts();
+                parts.status = StatusCode::UNAUTHORIZED;
+                Err(ErrorResponse::from_parts(parts, body))
+            };
+            let _ = tokio_tungstenite::accept_hdr_async(stream, callback).await;
+        });
+
+        let transport = DefaultWebsocketTransport::new();
+        let Err(e) = transport.connect(&url, None).await else {
+            panic!("expected error");
+        };
+        assert!(!e.is_retriable());
+        match e {
+            TransportError::HttpUpgradeFailed { status_code, body } => {
+                assert_eq!(status_code, 401);
+                assert_eq!(body, "auth required");
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let url = Url::parse(&format!("ws://{addr}")).unwrap();
+        let transport = DefaultWebsocketTransport::new();
+        match transport.connect(&url, None).await {
+            Err(e) => {
+                assert!(matches!(e, TransportError::ConnectionRefused));
+                assert!(e.is_retriable());
+            }
+            Ok(..) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_metrics() {
+        let mock = MockRelay::run().await.unwrap();
+        let url = mock.url().await;
+
+        let transport = DefaultWebsocketTransport::new();
+        let (mut tx, mut rx, metrics) = transport
+            .connect_with_metrics((&url).into(), None, &UpgradeHeaders::new())
+            .await
+            .unwrap();
+        let metrics = metrics.unwrap();
+        assert!(metrics.tls_handshake_duration().is_none());
+        assert_eq!(metrics.bytes_sent(), 0);
+
+        let req: String =
+            ClientMessage::req(SubscriptionId::new("metrics"), vec![Filter::new()]).as_json();
+        tx.send(Message::Text(req.clone())).await.unwrap();
+        assert_eq!(metrics.bytes_sent(), req.len() as u64);
+
+        // EOSE
+        let msg = rx.next().await.unwrap().unwrap();
+        assert_eq!(metrics.bytes_received(), msg.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_connect_tls_with_metrics() {
+        let url = tls_server().await;
+
+        let transport = DefaultWebsocketTransport::new()
+            .add_root_certificate(include_bytes!("testdata/ca.der").to_vec());
+        let (.., metrics) = transport
+            .connect_with_metrics(&url, None, &UpgradeHeaders::new())
+            .await
+            .unwrap();
+        let metrics = metrics.unwrap();
+        assert!(metrics.tls_handshake_duration().is_some());
+        assert!(metrics.connected_at().elapsed() < Duration::from_secs(5));
+    }
+}